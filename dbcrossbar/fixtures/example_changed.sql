@@ -0,0 +1 @@
+create table example (id bigint not null, first_name text, email text);
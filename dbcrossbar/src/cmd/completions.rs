@@ -0,0 +1,155 @@
+//! The `completions` subcommand.
+
+use common_failures::Result;
+use dbcrossbarlib::{config::Configuration, drivers::all_drivers, Context};
+use failure::format_err;
+use std::io::{self, Write};
+use structopt::{clap::Shell, StructOpt};
+
+/// Shell completion arguments.
+#[derive(Debug, StructOpt)]
+pub(crate) struct Opt {
+    /// The shell to generate completions for (bash, zsh, fish, powershell,
+    /// elvish).
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    shell: String,
+}
+
+/// Values we can complete for `--if-exists`. `upsert-on:` must be followed by
+/// column names, which we can't complete.
+const IF_EXISTS_VALUES: &str = "error overwrite append upsert-on:";
+
+/// Print a shell completion script to standard output.
+pub(crate) async fn run(
+    _ctx: Context,
+    _config: Configuration,
+    enable_unstable: bool,
+    opt: Opt,
+) -> Result<()> {
+    let shell = opt
+        .shell
+        .to_ascii_lowercase()
+        .parse::<Shell>()
+        .map_err(|err| format_err!("{}", err))?;
+
+    // `clap` doesn't know how to complete locators or `--if-exists`, so we
+    // patch or extend the scripts it generates for the shells we support best.
+    let mut script = vec![];
+    super::Opt::clap().gen_completions_to("dbcrossbar", shell, &mut script);
+    let script = String::from_utf8(script).expect("script should be UTF-8");
+    let schemes = all_drivers()
+        .iter()
+        .filter(|driver| !driver.is_unstable() || enable_unstable)
+        .map(|driver| driver.scheme())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = match shell {
+        Shell::Bash => bash_script(&script, &schemes),
+        Shell::Zsh => zsh_script(&script, &schemes),
+        Shell::Fish => fish_script(&script, &schemes),
+        _ => script,
+    };
+    io::stdout().write_all(script.as_bytes())?;
+    Ok(())
+}
+
+/// Wrap `clap`'s bash completion function with one that also completes
+/// `--if-exists` values and locator schemes.
+fn bash_script(script: &str, schemes: &str) -> String {
+    let mut script = script.replace(
+        "complete -F _dbcrossbar -o bashdefault -o default dbcrossbar\n",
+        "",
+    );
+    script.push_str(&format!(
+        r#"_dbcrossbar_with_values() {{
+    local cur prev
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    if [[ "${{prev}}" == "=" ]]; then
+        prev="${{COMP_WORDS[COMP_CWORD-2]}}"
+    fi
+    if [[ "${{prev}}" == "--if-exists" ]]; then
+        COMPREPLY=( $(compgen -W "{if_exists}" -- "${{cur}}") )
+        compopt -o nospace
+        return 0
+    fi
+
+    _dbcrossbar "$@"
+
+    # Subcommands which take locators as arguments.
+    local min_word
+    case "${{COMP_WORDS[1]}}" in
+        cp|count) min_word=2 ;;
+        schema) min_word=3 ;;
+        *) return 0 ;;
+    esac
+    if [[ ${{COMP_CWORD}} -ge ${{min_word}} && "${{cur}}" != -* ]]; then
+        local schemes
+        schemes=( $(compgen -W "{schemes}" -- "${{cur}}") )
+        if [[ ${{#schemes[@]}} -gt 0 ]]; then
+            COMPREPLY+=( "${{schemes[@]}}" )
+            compopt -o nospace
+        fi
+    fi
+    return 0
+}}
+
+complete -F _dbcrossbar_with_values -o bashdefault -o default dbcrossbar
+"#,
+        if_exists = IF_EXISTS_VALUES,
+        schemes = schemes,
+    ));
+    script
+}
+
+/// Patch `clap`'s zsh completion script to complete `--if-exists` values and
+/// locator schemes.
+fn zsh_script(script: &str, schemes: &str) -> String {
+    let mut out = String::with_capacity(script.len());
+    for line in script.lines() {
+        if line.starts_with("'--if-exists=[") && line.ends_with("]' \\") {
+            let (spec, rest) = line.split_at(line.len() - "' \\".len());
+            out.push_str(spec);
+            out.push_str(":if-exists:_dbcrossbar_if_exists");
+            out.push_str(rest);
+        } else if line.starts_with("':") && line.contains("locator -- ") {
+            out.push_str(&line.replace(":_files'", ":_dbcrossbar_locators'"));
+        } else if line == "_dbcrossbar \"$@\"" {
+            out.push_str(&format!(
+                r#"(( $+functions[_dbcrossbar_if_exists] )) ||
+_dbcrossbar_if_exists() {{
+    local values; values=({if_exists})
+    compadd -S '' -a values
+}}
+(( $+functions[_dbcrossbar_locators] )) ||
+_dbcrossbar_locators() {{
+    local schemes; schemes=({schemes})
+    _alternative 'schemes:locator scheme:compadd -S "" -a schemes' 'files:file:_files'
+}}
+
+"#,
+                if_exists = IF_EXISTS_VALUES,
+                schemes = schemes,
+            ));
+            out.push_str(line);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Extend `clap`'s fish completion script to complete `--if-exists` values and
+/// locator schemes.
+fn fish_script(script: &str, schemes: &str) -> String {
+    let mut script = script.to_owned();
+    script.push_str(&format!(
+        r#"complete -c dbcrossbar -n "__fish_seen_subcommand_from cp conv apply" -l if-exists -x -a "{if_exists}"
+complete -c dbcrossbar -n "__fish_seen_subcommand_from cp count conv dump apply lint diff" -a "{schemes}"
+"#,
+        if_exists = IF_EXISTS_VALUES,
+        schemes = schemes,
+    ));
+    script
+}
@@ -2,10 +2,11 @@
 
 use common_failures::Result;
 use dbcrossbarlib::{
-    config::Configuration, Context, DriverArguments, SharedArguments, SourceArguments,
+    config::Configuration, Context, SharedArguments, SourceArguments,
     TemporaryStorage, UnparsedLocator,
 };
 use failure::{format_err, ResultExt};
+use std::{fs, path::PathBuf};
 use structopt::{self, StructOpt};
 
 /// Count arguments.
@@ -28,6 +29,10 @@ pub(crate) struct Opt {
     #[structopt(long = "where")]
     where_clause: Option<String>,
 
+    /// Read data using the SQL query in this file, instead of a table.
+    #[structopt(long = "from-query")]
+    from_query: Option<PathBuf>,
+
     /// The locator specifying the records to count.
     locator: UnparsedLocator,
 }
@@ -39,11 +44,40 @@ pub(crate) async fn run(
     enable_unstable: bool,
     opt: Opt,
 ) -> Result<()> {
-    let schema_opt = opt.schema.map(|s| s.parse(enable_unstable)).transpose()?;
-    let locator = opt.locator.parse(enable_unstable)?;
+    let schema_opt = opt
+        .schema
+        .map(|s| config.resolve_locator(&s)?.locator().parse(enable_unstable))
+        .transpose()?;
+    let resolved = config.resolve_locator(&opt.locator)?;
+    let locator = resolved.locator().parse(enable_unstable)?;
+    let query = opt
+        .from_query
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .with_context(|_| format!("could not read {}", path.display()))
+        })
+        .transpose()?;
+
+    // Parse our source arguments, and use any credentials they specify.
+    let from_args = resolved.from_args(&opt.from_args)?;
+    let (ctx, from_args) = ctx
+        .with_credentials_from_driver_args(from_args)
+        .context("error parsing --from-args")?;
 
     // Figure out what table schema to use.
-    let schema = {
+    let schema = if let (None, Some(query)) = (&schema_opt, &query) {
+        locator
+            .query_schema(ctx.clone(), query.to_owned())
+            .await
+            .context("error reading schema from --from-query")?
+            .ok_or_else(|| {
+                format_err!(
+                    "don't know how to read schema from --from-query using {}",
+                    locator,
+                )
+            })
+    } else {
         let schema_locator = schema_opt.as_ref().unwrap_or(&locator);
         schema_locator
             .schema(ctx.clone())
@@ -61,8 +95,8 @@ pub(crate) async fn run(
     let shared_args = SharedArguments::new(schema, temporary_storage, 1);
 
     // Build our source arguments.
-    let from_args = DriverArguments::from_cli_args(&opt.from_args)?;
-    let source_args = SourceArguments::new(from_args, opt.where_clause.clone());
+    let source_args =
+        SourceArguments::new(from_args, opt.where_clause.clone()).with_query(query);
 
     let count = locator.count(ctx.clone(), shared_args, source_args).await?;
     println!("{}", count);
@@ -2,14 +2,21 @@
 
 use common_failures::Result;
 use dbcrossbarlib::{
-    config::Configuration, rechunk::rechunk_csvs, tokio_glue::try_forward, Context,
-    DestinationArguments, DisplayOutputLocators, DriverArguments, IfExists,
-    SharedArguments, SourceArguments, TemporaryStorage, UnparsedLocator,
+    config::{Configuration, ResolvedLocator},
+    summary::{RunSummary, SummaryCollector},
+    tokio_glue::try_forward,
+    BoxLocator, ColumnMapping, Context, CopyOperation, DateFormats, Deduplication,
+    DisplayOutputLocators, IfExists, Locator, Masking, Sampling, TemporaryStorage,
+    UnparsedLocator,
 };
 use failure::{format_err, ResultExt};
 use futures::{pin_mut, stream, FutureExt, StreamExt, TryStreamExt};
 use humanize_rs::bytes::Bytes as HumanizedBytes;
 use slog::{debug, o};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use structopt::{self, StructOpt};
 use tokio::io;
 use tokio_util::codec::{FramedWrite, LinesCodec};
@@ -21,6 +28,16 @@ pub(crate) struct Opt {
     #[structopt(long = "if-exists", default_value = "error")]
     if_exists: IfExists,
 
+    /// With `--if-exists=append` or `upsert-on`, add any missing columns to
+    /// the destination table before loading.
+    #[structopt(long = "evolve-schema")]
+    evolve_schema: bool,
+
+    /// Create the destination's PostgreSQL schema or BigQuery dataset if it
+    /// doesn't exist.
+    #[structopt(long = "create-missing")]
+    create_missing: bool,
+
     /// The schema to use (defaults to input table schema).
     #[structopt(long = "schema")]
     schema: Option<UnparsedLocator>,
@@ -30,6 +47,18 @@ pub(crate) struct Opt {
     #[structopt(long = "temporary")]
     temporaries: Vec<String>,
 
+    /// Read data using the SQL query in this file, instead of a table.
+    #[structopt(long = "from-query")]
+    from_query: Option<PathBuf>,
+
+    /// Copy a random sample of rows, using a percentage like `1%`.
+    #[structopt(long = "sample")]
+    sample: Option<String>,
+
+    /// Copy at most this many rows.
+    #[structopt(long = "limit")]
+    limit: Option<u64>,
+
     /// Specify the approximate size of the CSV streams manipulated by
     /// `dbcrossbar`. This can be used to split a large input into multiple
     /// smaller outputs. Actual data streams may be bigger or smaller depending
@@ -50,14 +79,95 @@ pub(crate) struct Opt {
     #[structopt(long = "where")]
     where_clause: Option<String>,
 
+    /// Parse input dates using a format like `%m/%d/%Y`, or `epoch`. Use
+    /// `COLUMN=FORMAT` to apply a format to a single column (can be repeated).
+    #[structopt(long = "date-format")]
+    date_formats: Vec<String>,
+
+    /// Parse input timestamps using a format like `%m/%d/%Y %H:%M:%S`,
+    /// `epoch` or `epoch_millis`. Use `COLUMN=FORMAT` to apply a format to a
+    /// single column (can be repeated).
+    #[structopt(long = "timestamp-format")]
+    timestamp_formats: Vec<String>,
+
+    /// Only copy these columns, in this order (comma-separated, can be
+    /// repeated).
+    #[structopt(long = "select")]
+    selects: Vec<String>,
+
+    /// Don't copy these columns (comma-separated, can be repeated).
+    #[structopt(long = "exclude")]
+    excludes: Vec<String>,
+
+    /// Rename a column, using `OLD:NEW` (can be repeated).
+    #[structopt(long = "rename")]
+    renames: Vec<String>,
+
+    /// Read column renames from a file containing one `OLD:NEW` pair per
+    /// line.
+    #[structopt(long = "rename-file")]
+    rename_file: Option<PathBuf>,
+
+    /// Move renamed columns to the front, in the order they were listed.
+    #[structopt(long = "reorder-columns")]
+    reorder_columns: bool,
+
+    /// Override the type of a column, using `COLUMN:TYPE` (can be repeated).
+    #[structopt(long = "cast")]
+    casts: Vec<String>,
+
+    /// Remove rows which are exact duplicates of an earlier row.
+    #[structopt(long = "dedup")]
+    dedup: bool,
+
+    /// Keep only one row for each distinct value of these columns
+    /// (comma-separated).
+    #[structopt(long = "dedup-on")]
+    dedup_on: Option<String>,
+
+    /// With `--dedup-on`, keep the row with the largest value of this column,
+    /// instead of the last row.
+    #[structopt(long = "dedup-order-by")]
+    dedup_order_by: Option<String>,
+
+    /// Mask sensitive columns using a JSON spec file.
+    #[structopt(long = "mask-file")]
+    mask_file: Option<PathBuf>,
+
     /// How many data streams should we attempt to copy in parallel?
     #[structopt(long = "max-streams", short = "J", default_value = "4")]
     max_streams: usize,
 
+    /// Copy at most this much data per second, across all streams. Examples:
+    /// "500Kb", "10Mb".
+    #[structopt(long = "max-bytes-per-second")]
+    max_bytes_per_second: Option<HumanizedBytes>, // usize
+
     /// Display where we wrote our output data.
     #[structopt(long = "display-output-locators")]
     display_output_locators: bool,
 
+    /// Print the SQL and jobs we would run, without copying any data. This
+    /// still reads the source schema (unless `--schema` is passed) and lists
+    /// tables matching a wildcard source, but never connects to the
+    /// destination.
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// Write a JSON summary of this copy to this file when finished, or to
+    /// standard output if `-`.
+    #[structopt(long = "summary")]
+    summary: Option<PathBuf>,
+
+    /// Copy each table listed in this file (one per line), replacing
+    /// `{table}` in both locators with the table name.
+    #[structopt(long = "manifest")]
+    manifest: Option<PathBuf>,
+
+    /// When copying multiple tables, how many should we copy in parallel?
+    #[structopt(long = "max-parallel-tables", default_value = "4")]
+    max_parallel_tables: usize,
+
     /// The input table.
     from_locator: UnparsedLocator,
 
@@ -72,111 +182,402 @@ pub(crate) async fn run(
     enable_unstable: bool,
     opt: Opt,
 ) -> Result<()> {
-    let schema_opt = opt.schema.map(|s| s.parse(enable_unstable)).transpose()?;
-    let from_locator = opt.from_locator.parse(enable_unstable)?;
-    let to_locator = opt.to_locator.parse(enable_unstable)?;
-
-    // Figure out what table schema to use.
-    let schema = {
-        let schema_locator = schema_opt.as_ref().unwrap_or(&from_locator);
-        schema_locator
-            .schema(ctx.clone())
-            .await
-            .with_context(|_| format!("error reading schema from {}", schema_locator))?
-            .ok_or_else(|| {
-                format_err!("don't know how to read schema from {}", schema_locator)
-            })
-    }?;
-
-    // Build our shared arguments.
-    let temporaries = opt.temporaries.clone();
-    let temporary_storage = TemporaryStorage::with_config(temporaries, &config)?;
-    let shared_args = SharedArguments::new(schema, temporary_storage, opt.max_streams);
-
-    // Build our source arguments.
-    let from_args = DriverArguments::from_cli_args(&opt.from_args)?;
-    let source_args = SourceArguments::new(from_args, opt.where_clause.clone());
-
-    // Build our destination arguments.
-    let to_args = DriverArguments::from_cli_args(&opt.to_args)?;
-    let dest_args = DestinationArguments::new(to_args, opt.if_exists);
-
-    // Can we short-circuit this particular copy using special features of the
-    // the source and destination, or do we need to pull the data down to the
-    // local machine?
-    let should_use_remote = opt.stream_size.is_none()
-        && to_locator.supports_write_remote_data(from_locator.as_ref());
-    let dests = if should_use_remote {
-        // Build a logging context.
-        let ctx = ctx.child(o!(
-            "from_locator" => from_locator.to_string(),
-            "to_locator" => to_locator.to_string(),
-        ));
+    // Start recording our summary as early as possible, so that it includes
+    // all the time we spend.
+    let summary_collector = opt.summary.as_ref().map(|_| SummaryCollector::new());
 
-        // Perform a remote transfer.
-        debug!(ctx.log(), "performing remote data transfer");
-        let dests = to_locator
-            .write_remote_data(ctx, from_locator, shared_args, source_args, dest_args)
-            .await?;
+    let from = config.resolve_locator(&opt.from_locator)?;
+    let to = config.resolve_locator(&opt.to_locator)?;
 
-        // Convert our list of output locators into a stream.
-        stream::iter(dests).map(Ok).boxed()
-    } else {
-        // We have to transfer the data via the local machine, so read data from
-        // input.
-        debug!(ctx.log(), "performing local data transfer");
-
-        let input_ctx = ctx.child(o!("from_locator" => from_locator.to_string()));
-        let mut data = from_locator
-            .local_data(input_ctx, shared_args.clone(), source_args)
-            .await?
-            .ok_or_else(|| {
-                format_err!("don't know how to read data from {}", from_locator)
-            })?;
-
-        // Honor --stream-size if passed.
-        if let Some(stream_size) = opt.stream_size {
-            let stream_size = stream_size.size();
-            data = rechunk_csvs(ctx.clone(), stream_size, data)?;
+    // If we were given a manifest, copy each table it lists.
+    if let Some(manifest) = &opt.manifest {
+        let mut tables = vec![];
+        for name in read_manifest(manifest)? {
+            let from_locator =
+                replace_table_name(from.locator(), &name)?.parse(enable_unstable)?;
+            tables.push(TableToCopy { name, from_locator });
+        }
+        return copy_tables(ctx, &config, enable_unstable, &opt, &from, &to, tables)
+            .await;
+    }
+
+    // If our source contains a wildcard, copy each matching table.
+    let from_locator = from.locator().parse(enable_unstable)?;
+    if let Some(matches) = from_locator.matching_tables(ctx.clone()).await? {
+        if matches.is_empty() {
+            return Err(format_err!("no tables match {}", from_locator));
         }
+        let tables = matches
+            .into_iter()
+            .map(|(name, from_locator)| TableToCopy { name, from_locator })
+            .collect();
+        return copy_tables(ctx, &config, enable_unstable, &opt, &from, &to, tables)
+            .await;
+    }
+
+    let to_locator = to.locator().parse(enable_unstable)?;
+    let to_display_output_locators = to_locator.display_output_locators();
+    let from_locator_str = from_locator.to_string();
+    let to_locator_str = to_locator.to_string();
+    let display_output_locators =
+        should_display_output_locators(&opt, to_locator.as_ref());
+    let copy = build_copy_operation(
+        &config,
+        enable_unstable,
+        &opt,
+        &from,
+        &to,
+        from_locator,
+        to_locator,
+    )?;
+
+    // If this is a dry run, describe what we would do and stop.
+    if opt.dry_run {
+        print!("{}", copy.dry_run(ctx).await?);
+        return Ok(());
+    }
+
+    // If we're not allowed to display our output locators, it's probably
+    // because we're writing data to standard output.
+    let data_to_stdout =
+        matches!(to_display_output_locators, DisplayOutputLocators::Never);
+    let display_output_locators = display_output_locators?;
+
+    // Make sure we can write our summary.
+    let summary_to_stdout = opt
+        .summary
+        .as_ref()
+        .is_some_and(|path| path.as_os_str() == "-");
+    if summary_to_stdout && (display_output_locators || data_to_stdout) {
+        return Err(format_err!(
+            "cannot use --summary=- when writing other output to standard output"
+        ));
+    }
 
-        // Write data to output.
-        let output_ctx = ctx.child(o!("to_locator" => to_locator.to_string()));
-        let result_stream = to_locator
-            .write_local_data(output_ctx, data, shared_args.clone(), dest_args)
-            .await?;
-
-        // Consume the stream of futures produced by `write_local_data`, allowing a
-        // certain degree of parallelism. This is where all the actual work happens,
-        // and this what controls how many "input driver" -> "output driver"
-        // connections are running at any given time.
-        result_stream
-            // Run up to `parallelism` futures in parallel.
-            .try_buffer_unordered(shared_args.max_streams())
-            .boxed()
+    // Copy our data, and summarize what happened, even if we failed.
+    let ctx = match &summary_collector {
+        Some(collector) => ctx.with_summary_collector(collector.clone()),
+        None => ctx,
     };
+    let result = copy_and_display(&ctx, copy, display_output_locators).await;
+    if let (Some(collector), Some(path)) = (summary_collector, &opt.summary) {
+        let summary = collector.summarize(&from_locator_str, &to_locator_str, &result);
+        write_summary(&summary, path)?;
+    }
+    result
+}
+
+/// A table to copy when copying multiple tables.
+struct TableToCopy {
+    /// The name of this table, which replaces `{table}` in our destination.
+    name: String,
+    /// Where to read this table from.
+    from_locator: BoxLocator,
+}
+
+/// Copy each of `tables`, running up to `--max-parallel-tables` copies at
+/// once, and report which tables succeeded and failed.
+async fn copy_tables(
+    ctx: Context,
+    config: &Configuration,
+    enable_unstable: bool,
+    opt: &Opt,
+    from: &ResolvedLocator,
+    to: &ResolvedLocator,
+    tables: Vec<TableToCopy>,
+) -> Result<()> {
+    if !to.locator().as_str().contains("{table}") {
+        return Err(format_err!(
+            "when copying multiple tables, the destination locator must contain {{table}}"
+        ));
+    }
+    if opt.schema.is_some() {
+        return Err(format_err!(
+            "cannot use --schema when copying multiple tables"
+        ));
+    }
+    if opt.from_query.is_some() {
+        return Err(format_err!(
+            "cannot use --from-query when copying multiple tables"
+        ));
+    }
+    if opt.summary.is_some() {
+        return Err(format_err!(
+            "cannot use --summary when copying multiple tables"
+        ));
+    }
+    if opt.max_parallel_tables == 0 {
+        return Err(format_err!("--max-parallel-tables must be at least 1"));
+    }
+
+    // Build all our copy operations before we start, so that we catch any
+    // errors in our arguments right away.
+    let mut copies = Vec::with_capacity(tables.len());
+    for table in tables {
+        let name = table.name;
+        let to_locator =
+            replace_table_name(to.locator(), &name)?.parse(enable_unstable)?;
+        let display_output_locators =
+            should_display_output_locators(opt, to_locator.as_ref())?;
+        let copy = build_copy_operation(
+            config,
+            enable_unstable,
+            opt,
+            from,
+            to,
+            table.from_locator,
+            to_locator,
+        )
+        .with_context(|_| format!("could not set up copy of table {}", name))?;
+        copies.push((name, copy, display_output_locators));
+    }
+
+    // If this is a dry run, describe each copy in order, and stop.
+    if opt.dry_run {
+        for (i, (_, copy, _)) in copies.into_iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print!("{}", copy.dry_run(ctx.clone()).await?);
+        }
+        return Ok(());
+    }
+
+    // Copy our tables, keeping our results in the same order as our tables.
+    let results = stream::iter(copies.into_iter().map(
+        |(name, copy, display_output_locators)| {
+            let ctx = ctx.child(o!("table" => name.clone()));
+            async move {
+                let result =
+                    copy_and_display(&ctx, copy, display_output_locators).await;
+                (name, result)
+            }
+        },
+    ))
+    .buffered(opt.max_parallel_tables)
+    .collect::<Vec<_>>()
+    .await;
 
-    // Optionally display `dests`, depending on a combination of
-    // `--display-output-locators` and the defaults for `to_locator`.
-    let display_output_locators = match (
+    // Report what happened to each table.
+    let mut failures = 0;
+    for (name, result) in &results {
+        match result {
+            Ok(()) => eprintln!("copied {}", name),
+            Err(err) => {
+                failures += 1;
+                eprintln!("FAILED {}: {}", name, display_causes(err));
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format_err!(
+            "could not copy {} of {} tables",
+            failures,
+            results.len(),
+        ));
+    }
+    Ok(())
+}
+
+/// Build a `CopyOperation` from `from_locator` to `to_locator`, using the
+/// options in `opt`.
+fn build_copy_operation(
+    config: &Configuration,
+    enable_unstable: bool,
+    opt: &Opt,
+    from: &ResolvedLocator,
+    to: &ResolvedLocator,
+    from_locator: BoxLocator,
+    to_locator: BoxLocator,
+) -> Result<CopyOperation> {
+    let schema_opt = opt
+        .schema
+        .as_ref()
+        .map(|s| config.resolve_locator(s)?.locator().parse(enable_unstable))
+        .transpose()?;
+    let temporary_storage =
+        TemporaryStorage::with_config(opt.temporaries.clone(), config)?;
+    let mut copy = CopyOperation::new(from_locator, to_locator)
+        .temporary_storage(temporary_storage)
+        .from_args(from.from_args(&opt.from_args)?)
+        .to_args(to.to_args(&opt.to_args)?)
+        .if_exists(opt.if_exists.clone())
+        .evolve_schema(opt.evolve_schema)
+        .create_missing(opt.create_missing)
+        .max_streams(opt.max_streams);
+    if let Some(schema_locator) = schema_opt {
+        copy = copy.schema(schema_locator);
+    }
+    if let Some(stream_size) = &opt.stream_size {
+        copy = copy.stream_size(stream_size.size());
+    }
+    if let Some(max_bytes_per_second) = &opt.max_bytes_per_second {
+        copy = copy.max_bytes_per_second(max_bytes_per_second.size());
+    }
+    if let Some(where_clause) = &opt.where_clause {
+        copy = copy.where_clause(where_clause.to_owned());
+    }
+    if let Some(from_query) = &opt.from_query {
+        let query = fs::read_to_string(from_query)
+            .with_context(|_| format!("could not read {}", from_query.display()))?;
+        copy = copy.query(query);
+    }
+    if opt.sample.is_some() || opt.limit.is_some() {
+        let mut sampling = Sampling::default();
+        if let Some(sample) = &opt.sample {
+            sampling.set_sample(sample)?;
+        }
+        if let Some(limit) = opt.limit {
+            sampling.set_limit(limit);
+        }
+        copy = copy.sampling(sampling);
+    }
+    if !opt.date_formats.is_empty() || !opt.timestamp_formats.is_empty() {
+        let mut date_formats = DateFormats::default();
+        for spec in &opt.date_formats {
+            date_formats.add_date_format(spec)?;
+        }
+        for spec in &opt.timestamp_formats {
+            date_formats.add_timestamp_format(spec)?;
+        }
+        copy = copy.date_formats(date_formats);
+    }
+    let mut column_mapping = ColumnMapping::default();
+    for spec in &opt.selects {
+        column_mapping.add_select(spec)?;
+    }
+    for spec in &opt.excludes {
+        column_mapping.add_exclude(spec)?;
+    }
+    if let Some(rename_file) = &opt.rename_file {
+        let contents = fs::read_to_string(rename_file)
+            .with_context(|_| format!("could not read {}", rename_file.display()))?;
+        column_mapping
+            .add_mapping_file(&contents)
+            .with_context(|_| format!("error in {}", rename_file.display()))?;
+    }
+    for spec in &opt.renames {
+        column_mapping.add_rename(spec)?;
+    }
+    if opt.reorder_columns {
+        if !column_mapping.has_renames() {
+            return Err(format_err!(
+                "--reorder-columns requires --rename or --rename-file"
+            ));
+        }
+        column_mapping.set_reorder(true);
+    }
+    for spec in &opt.casts {
+        column_mapping.add_cast(spec)?;
+    }
+    if !column_mapping.is_empty() {
+        copy = copy.column_mapping(column_mapping);
+    }
+    let mut dedup = Deduplication::default();
+    dedup.set_exact(opt.dedup);
+    if let Some(dedup_on) = &opt.dedup_on {
+        dedup.set_keys(dedup_on)?;
+    }
+    if let Some(dedup_order_by) = &opt.dedup_order_by {
+        if opt.dedup_on.is_none() {
+            return Err(format_err!("--dedup-order-by requires --dedup-on"));
+        }
+        dedup.set_order_by(dedup_order_by);
+    }
+    if opt.dedup && opt.dedup_on.is_some() {
+        return Err(format_err!("cannot use both --dedup and --dedup-on"));
+    }
+    if !dedup.is_empty() {
+        copy = copy.dedup(dedup);
+    }
+    if let Some(mask_file) = &opt.mask_file {
+        let contents = fs::read_to_string(mask_file)
+            .with_context(|_| format!("could not read {}", mask_file.display()))?;
+        let masking = Masking::from_json(&contents)
+            .with_context(|_| format!("error in {}", mask_file.display()))?;
+        copy = copy.masking(masking);
+    }
+    Ok(copy)
+}
+
+/// Should we display the output locators for `to_locator`? This depends on
+/// both `--display-output-locators` and the defaults for `to_locator`.
+fn should_display_output_locators(
+    opt: &Opt,
+    to_locator: &dyn Locator,
+) -> Result<bool> {
+    match (
         opt.display_output_locators,
         to_locator.display_output_locators(),
     ) {
         // The user passed `--display-output-locators`, but displaying them is
         // forbidden (probably because we wrote actual data to standard output).
-        (true, DisplayOutputLocators::Never) => {
-            return Err(format_err!(
-                "cannot use --display-output-locators with {}",
-                to_locator
-            ))
-        }
+        (true, DisplayOutputLocators::Never) => Err(format_err!(
+            "cannot use --display-output-locators with {}",
+            to_locator
+        )),
 
         // We want to display our actual output locators.
-        (true, _) | (false, DisplayOutputLocators::ByDefault) => true,
+        (true, _) | (false, DisplayOutputLocators::ByDefault) => Ok(true),
 
         // We don't want to display our output locators.
-        (false, _) => false,
-    };
+        (false, _) => Ok(false),
+    }
+}
+
+/// Read a list of table names from `path`, skipping blank lines and lines
+/// starting with `#`.
+fn read_manifest(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|_| format!("could not read {}", path.display()))?;
+    let names = parse_manifest(&contents);
+    if names.is_empty() {
+        return Err(format_err!("no tables listed in {}", path.display()));
+    }
+    Ok(names)
+}
+
+/// Parse the contents of a manifest file.
+fn parse_manifest(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_owned())
+        .collect()
+}
+
+/// Replace `{table}` in `locator` with `name`.
+fn replace_table_name(
+    locator: &UnparsedLocator,
+    name: &str,
+) -> Result<UnparsedLocator> {
+    if !locator.as_str().contains("{table}") {
+        return Err(format_err!(
+            "expected {} to contain {{table}}",
+            locator.as_str()
+        ));
+    }
+    locator.as_str().replace("{table}", name).parse()
+}
+
+/// Format `err` and all its causes on a single line.
+fn display_causes(err: &failure::Error) -> String {
+    err.iter_chain()
+        .map(|cause| cause.to_string())
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// Run `copy`, and display the output locators if `display_output_locators`
+/// is true.
+async fn copy_and_display(
+    ctx: &Context,
+    copy: CopyOperation,
+    display_output_locators: bool,
+) -> Result<()> {
+    // Start copying.
+    let dests = copy.run(ctx.clone()).await?;
 
     // Print our destination
     if display_output_locators {
@@ -199,7 +600,7 @@ pub(crate) async fn run(
             }
         });
         pin_mut!(dest_strings);
-        try_forward(&ctx, dest_strings, stdout_sink).await?;
+        try_forward(ctx, dest_strings, stdout_sink).await?;
     } else {
         // Just collect our results and ignore
         let dests = dests.try_collect::<Vec<_>>().boxed().await?;
@@ -207,3 +608,15 @@ pub(crate) async fn run(
     }
     Ok(())
 }
+
+/// Write `summary` as JSON to `path`, or to standard output if `path` is `-`.
+fn write_summary(summary: &RunSummary, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(summary)?;
+    if path.as_os_str() == "-" {
+        println!("{}", json);
+    } else {
+        fs::write(path, format!("{}\n", json))
+            .with_context(|_| format!("could not write {}", path.display()))?;
+    }
+    Ok(())
+}
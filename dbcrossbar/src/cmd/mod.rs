@@ -2,11 +2,14 @@
 
 use dbcrossbarlib::{config::Configuration, tokio_glue::BoxFuture, Context};
 use futures::FutureExt;
+use std::net::SocketAddr;
 //use structopt::StructOpt;
 use structopt_derive::StructOpt;
+use url::Url;
 
 use crate::logging::LogFormat;
 
+pub(crate) mod completions;
 pub(crate) mod config;
 pub(crate) mod count;
 pub(crate) mod cp;
@@ -29,10 +32,25 @@ pub(crate) struct Opt {
     #[structopt(long = "log-extra")]
     pub(crate) log_extra: Vec<String>,
 
+    /// Log more details. Pass `-v` to show each step of an operation, `-vv` to
+    /// show debugging information and `-vvv` to show everything. Ignored if
+    /// `RUST_LOG` is set.
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    pub(crate) verbose: u8,
+
     /// Enable unstable, experimental features.
     #[structopt(long = "enable-unstable")]
     pub(crate) enable_unstable: bool,
 
+    /// Serve Prometheus metrics at `http://ADDR/metrics` while running (for
+    /// example, `127.0.0.1:9184`).
+    #[structopt(long = "metrics-listen", value_name = "ADDR")]
+    pub(crate) metrics_listen: Option<SocketAddr>,
+
+    /// Push Prometheus metrics to the Pushgateway at URL when finished.
+    #[structopt(long = "metrics-push-url", value_name = "URL")]
+    pub(crate) metrics_push_url: Option<Url>,
+
     /// The command to run.
     #[structopt(subcommand)]
     pub(crate) cmd: Command,
@@ -42,6 +60,17 @@ pub(crate) struct Opt {
 #[derive(Debug, StructOpt)]
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum Command {
+    /// Generate shell completion scripts.
+    #[structopt(name = "completions")]
+    #[structopt(after_help = r#"EXAMPLES:
+    dbcrossbar completions bash > /etc/bash_completion.d/dbcrossbar
+    dbcrossbar completions fish > ~/.config/fish/completions/dbcrossbar.fish
+"#)]
+    Completions {
+        #[structopt(flatten)]
+        command: completions::Opt,
+    },
+
     /// Update configuration.
     #[structopt(name = "config")]
     Config {
@@ -94,6 +123,9 @@ pub(crate) enum Command {
 
 pub(crate) fn run(ctx: Context, config: Configuration, opt: Opt) -> BoxFuture<()> {
     match opt.cmd {
+        Command::Completions { command } => {
+            completions::run(ctx, config, opt.enable_unstable, command).boxed()
+        }
         Command::Config { command } => config::run(ctx, config, command).boxed(),
 
         Command::Count { command } => {
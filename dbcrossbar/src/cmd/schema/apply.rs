@@ -0,0 +1,77 @@
+//! The `apply` subcommand.
+
+use common_failures::Result;
+use dbcrossbarlib::{
+    config::Configuration, Context, DestinationArguments, IfExists, UnparsedLocator,
+};
+use failure::{format_err, ResultExt};
+use structopt::{self, StructOpt};
+
+/// Schema apply arguments.
+#[derive(Debug, StructOpt)]
+pub(crate) struct Opt {
+    /// One of `error`, `overwrite` or `append`. With `append`, an existing
+    /// table will be left alone.
+    #[structopt(long = "if-exists", default_value = "error")]
+    if_exists: IfExists,
+
+    /// Pass an extra argument of the form `key=value` to the destination
+    /// driver.
+    #[structopt(long = "to-arg")]
+    to_args: Vec<String>,
+
+    /// Create the destination's BigQuery dataset if it doesn't exist.
+    #[structopt(long = "create-missing")]
+    create_missing: bool,
+
+    /// Print the SQL we would run, without creating any tables.
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// The schema to apply.
+    schema_locator: UnparsedLocator,
+
+    /// The table to create.
+    to_locator: UnparsedLocator,
+}
+
+/// Create a table using a schema.
+pub(crate) async fn run(
+    ctx: Context,
+    config: Configuration,
+    enable_unstable: bool,
+    opt: Opt,
+) -> Result<()> {
+    let schema_locator = config
+        .resolve_locator(&opt.schema_locator)?
+        .locator()
+        .parse(enable_unstable)?;
+    let to = config.resolve_locator(&opt.to_locator)?;
+    let to_locator = to.locator().parse(enable_unstable)?;
+    let schema = schema_locator.schema(ctx.clone()).await?.ok_or_else(|| {
+        format_err!("don't know how to read schema from {}", schema_locator)
+    })?;
+    let (to_ctx, to_args) = ctx
+        .with_credentials_from_driver_args(to.to_args(&opt.to_args)?)
+        .context("error parsing --to-args")?;
+    let dest_args = DestinationArguments::new(to_args, opt.if_exists.clone())
+        .with_create_missing(opt.create_missing);
+
+    // If this is a dry run, describe what we would do and stop.
+    if opt.dry_run {
+        println!("-- Apply schema from: {}", schema_locator);
+        println!("-- Apply to: {}", to_locator);
+        println!("-- If exists: {}", opt.if_exists);
+        match to_locator.write_schema_dry_run(&to_ctx, &schema, dest_args)? {
+            Some(description) => print!("{}", description),
+            None => println!(
+                "-- (Cannot describe how the schema would be applied to {})",
+                to_locator,
+            ),
+        }
+        return Ok(());
+    }
+
+    to_locator.write_schema(to_ctx, schema, dest_args).await?;
+    Ok(())
+}
@@ -1,8 +1,10 @@
 //! The `conv` subcommand.
 
 use common_failures::Result;
-use dbcrossbarlib::{config::Configuration, Context, IfExists, UnparsedLocator};
-use failure::format_err;
+use dbcrossbarlib::{
+    config::Configuration, Context, DestinationArguments, IfExists, UnparsedLocator,
+};
+use failure::{format_err, ResultExt};
 use structopt::{self, StructOpt};
 
 /// Schema conversion arguments.
@@ -12,6 +14,11 @@ pub(crate) struct Opt {
     #[structopt(long = "if-exists", default_value = "error")]
     if_exists: IfExists,
 
+    /// Pass an extra argument of the form `key=value` to the destination
+    /// driver.
+    #[structopt(long = "to-arg")]
+    to_args: Vec<String>,
+
     /// The input schema.
     from_locator: UnparsedLocator,
 
@@ -22,15 +29,21 @@ pub(crate) struct Opt {
 /// Perform our schema conversion.
 pub(crate) async fn run(
     ctx: Context,
-    _config: Configuration,
+    config: Configuration,
     enable_unstable: bool,
     opt: Opt,
 ) -> Result<()> {
-    let from_locator = opt.from_locator.parse(enable_unstable)?;
-    let to_locator = opt.to_locator.parse(enable_unstable)?;
+    let from = config.resolve_locator(&opt.from_locator)?;
+    let to = config.resolve_locator(&opt.to_locator)?;
+    let from_locator = from.locator().parse(enable_unstable)?;
+    let to_locator = to.locator().parse(enable_unstable)?;
     let schema = from_locator.schema(ctx.clone()).await?.ok_or_else(|| {
         format_err!("don't know how to read schema from {}", from_locator)
     })?;
-    to_locator.write_schema(ctx, schema, opt.if_exists).await?;
+    let (to_ctx, to_args) = ctx
+        .with_credentials_from_driver_args(to.to_args(&opt.to_args)?)
+        .context("error parsing --to-args")?;
+    let dest_args = DestinationArguments::new(to_args, opt.if_exists);
+    to_locator.write_schema(to_ctx, schema, dest_args).await?;
     Ok(())
 }
@@ -0,0 +1,61 @@
+//! The `diff` subcommand.
+
+use common_failures::Result;
+use dbcrossbarlib::{
+    config::Configuration, schema_diff::SchemaDiff, Context, UnparsedLocator,
+};
+use failure::format_err;
+use structopt::{self, StructOpt};
+
+/// Schema comparison arguments.
+#[derive(Debug, StructOpt)]
+pub(crate) struct Opt {
+    /// Instead of reporting differences, print `ALTER TABLE` statements which
+    /// would update the old table to match the new schema. Only supported when
+    /// the old locator is a PostgreSQL or BigQuery table.
+    #[structopt(long = "alter-sql")]
+    alter_sql: bool,
+
+    /// The schema to compare against.
+    old_locator: UnparsedLocator,
+
+    /// The schema to compare.
+    new_locator: UnparsedLocator,
+}
+
+/// Compare two schemas, print out any differences, and fail if there were any.
+pub(crate) async fn run(
+    ctx: Context,
+    config: Configuration,
+    enable_unstable: bool,
+    opt: Opt,
+) -> Result<()> {
+    let old_locator = config
+        .resolve_locator(&opt.old_locator)?
+        .locator()
+        .parse(enable_unstable)?;
+    let new_locator = config
+        .resolve_locator(&opt.new_locator)?
+        .locator()
+        .parse(enable_unstable)?;
+    let old_schema = old_locator.schema(ctx.clone()).await?.ok_or_else(|| {
+        format_err!("don't know how to read schema from {}", old_locator)
+    })?;
+    let new_schema = new_locator.schema(ctx.clone()).await?.ok_or_else(|| {
+        format_err!("don't know how to read schema from {}", new_locator)
+    })?;
+
+    let diff = SchemaDiff::compare(&old_schema, &new_schema);
+    if opt.alter_sql {
+        print!("{}", diff.alter_table_sql(old_locator.as_ref())?);
+        Ok(())
+    } else if diff.is_empty() {
+        Ok(())
+    } else {
+        print!("{}", diff);
+        Err(format_err!(
+            "schemas differ: {} column(s) changed",
+            diff.changes().len(),
+        ))
+    }
+}
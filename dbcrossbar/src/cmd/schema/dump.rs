@@ -0,0 +1,39 @@
+//! The `dump` subcommand.
+
+use common_failures::Result;
+use dbcrossbarlib::{
+    config::Configuration, Context, DestinationArguments, DriverArguments, IfExists,
+    UnparsedLocator,
+};
+use failure::format_err;
+use structopt::{self, StructOpt};
+
+/// Schema dump arguments.
+#[derive(Debug, StructOpt)]
+pub(crate) struct Opt {
+    /// The table whose schema we should dump.
+    locator: UnparsedLocator,
+}
+
+/// Print the schema of a table in `dbcrossbar-schema` format.
+pub(crate) async fn run(
+    ctx: Context,
+    config: Configuration,
+    enable_unstable: bool,
+    opt: Opt,
+) -> Result<()> {
+    let locator = config
+        .resolve_locator(&opt.locator)?
+        .locator()
+        .parse(enable_unstable)?;
+    let schema = locator.schema(ctx.clone()).await?.ok_or_else(|| {
+        format_err!("don't know how to read schema from {}", locator)
+    })?;
+    let stdout = "dbcrossbar-schema:-"
+        .parse::<UnparsedLocator>()?
+        .parse(enable_unstable)?;
+    let dest_args =
+        DestinationArguments::new(DriverArguments::default(), IfExists::default());
+    stdout.write_schema(ctx, schema, dest_args).await?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! The `lint` subcommand.
+
+use common_failures::Result;
+use dbcrossbarlib::{
+    config::Configuration, schema_lint::SchemaLint, Context, UnparsedLocator,
+};
+use failure::format_err;
+use structopt::{self, StructOpt};
+
+/// Schema lint arguments.
+#[derive(Debug, StructOpt)]
+pub(crate) struct Opt {
+    /// The schema to check.
+    schema_locator: UnparsedLocator,
+
+    /// The destination to check against. This may be a driver scheme like
+    /// `postgres:`, or a full locator.
+    target: String,
+}
+
+/// Check a schema for problems, print them out, and fail if there were any
+/// errors.
+pub(crate) async fn run(
+    ctx: Context,
+    config: Configuration,
+    enable_unstable: bool,
+    opt: Opt,
+) -> Result<()> {
+    let schema_locator = config
+        .resolve_locator(&opt.schema_locator)?
+        .locator()
+        .parse(enable_unstable)?;
+    let schema = schema_locator.schema(ctx).await?.ok_or_else(|| {
+        format_err!("don't know how to read schema from {}", schema_locator)
+    })?;
+
+    // Allow locator aliases here, too.
+    let target = opt.target.parse::<UnparsedLocator>()?;
+    let target = config
+        .resolve_locator(&target)?
+        .locator()
+        .as_str()
+        .to_owned();
+    let scheme = match target.find(':') {
+        Some(idx) => &target[..=idx],
+        None => return Err(format_err!("expected a scheme like \"postgres:\"")),
+    };
+
+    let lint = SchemaLint::check(&schema, scheme)?;
+    print!("{}", lint);
+    if lint.has_errors() {
+        Err(format_err!(
+            "schema has problems: {} issue(s) found",
+            lint.problems().len(),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -2,7 +2,11 @@ use dbcrossbarlib::{config::Configuration, tokio_glue::BoxFuture, Context};
 use futures::FutureExt;
 use structopt_derive::StructOpt;
 
+pub(crate) mod apply;
 pub(crate) mod conv;
+pub(crate) mod diff;
+pub(crate) mod dump;
+pub(crate) mod lint;
 
 /// Schema-related commands.
 #[derive(Debug, StructOpt)]
@@ -19,6 +23,51 @@ pub(crate) enum Opt {
         #[structopt(flatten)]
         command: conv::Opt,
     },
+
+    /// Print the schema of a table in `dbcrossbar-schema` JSON format.
+    #[structopt(name = "dump")]
+    #[structopt(after_help = r#"EXAMPLE LOCATORS:
+    postgres://localhost:5432/db#table
+    bigquery:project:dataset.table
+"#)]
+    Dump {
+        #[structopt(flatten)]
+        command: dump::Opt,
+    },
+
+    /// Create a table using a schema, without copying any data.
+    #[structopt(name = "apply")]
+    #[structopt(after_help = r#"EXAMPLE LOCATORS:
+    dbcrossbar-schema:table.json
+    postgres://localhost:5432/db#table
+"#)]
+    Apply {
+        #[structopt(flatten)]
+        command: apply::Opt,
+    },
+
+    /// Check a table schema for problems with a specific database.
+    #[structopt(name = "lint")]
+    #[structopt(after_help = r#"EXAMPLES:
+    dbcrossbar schema lint postgres-sql:table.sql bigquery:
+    dbcrossbar schema lint bigquery-schema:table.json postgres://localhost:5432/db#table
+"#)]
+    Lint {
+        #[structopt(flatten)]
+        command: lint::Opt,
+    },
+
+    /// Compare two table schemas, and exit with an error if they differ.
+    #[structopt(name = "diff")]
+    #[structopt(after_help = r#"EXAMPLE LOCATORS:
+    postgres-sql:table.sql
+    postgres://localhost:5432/db#table
+    bigquery:project:dataset.table
+"#)]
+    Diff {
+        #[structopt(flatten)]
+        command: diff::Opt,
+    },
 }
 
 pub(crate) fn run(
@@ -31,5 +80,17 @@ pub(crate) fn run(
         Opt::Conv { command } => {
             conv::run(ctx, config, enable_unstable, command).boxed()
         }
+        Opt::Dump { command } => {
+            dump::run(ctx, config, enable_unstable, command).boxed()
+        }
+        Opt::Apply { command } => {
+            apply::run(ctx, config, enable_unstable, command).boxed()
+        }
+        Opt::Lint { command } => {
+            lint::run(ctx, config, enable_unstable, command).boxed()
+        }
+        Opt::Diff { command } => {
+            diff::run(ctx, config, enable_unstable, command).boxed()
+        }
     }
 }
@@ -2,8 +2,9 @@
 
 use dbcrossbarlib::{Error, Result};
 use failure::format_err;
-use slog::{error, slog_o as o, Drain, Logger, Never};
-use std::{io::stderr, result, str::FromStr};
+use slog::{error, slog_o as o, Drain, FilterLevel, Logger, Never};
+use slog_envlogger::{EnvLogger, LogBuilder};
+use std::{env, io::stderr, result, str::FromStr};
 
 /// A polymorphic log drain (which means we need to use `Box<dyn ...>`,
 /// because that's how Rust does runtime polymorphism).
@@ -54,6 +55,28 @@ impl FromStr for LogFormat {
     }
 }
 
+/// Filter `drain` using `RUST_LOG` if it's set, or using the number of `-v`
+/// flags passed on the command line if it isn't.
+pub(crate) fn filter_drain(drain: BoxDrain, verbose: u8) -> EnvLogger<BoxDrain> {
+    let builder = LogBuilder::new(drain);
+    let builder = match env::var("RUST_LOG") {
+        Ok(filters) => builder.parse(&filters),
+        Err(_) => builder.filter(None, verbosity_level(verbose)),
+    };
+    builder.build()
+}
+
+/// Convert a count of `-v` flags to a log level.
+fn verbosity_level(verbose: u8) -> FilterLevel {
+    match verbose {
+        // This matches the default `RUST_LOG` behavior.
+        0 => FilterLevel::Error,
+        1 => FilterLevel::Info,
+        2 => FilterLevel::Debug,
+        _ => FilterLevel::Trace,
+    }
+}
+
 /// Given a log `drain`, and a list of `extra` values in the format
 /// `"key=value"`, create a global logger.
 pub(crate) fn global_logger_with_extra_values(
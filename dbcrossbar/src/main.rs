@@ -26,13 +26,21 @@ extern crate openssl;
 extern crate tokio;
 
 use common_failures::{quick_main, Result};
-use dbcrossbarlib::{config::Configuration, run_futures_with_runtime, Context};
-use slog::{debug, Drain};
+use dbcrossbarlib::{
+    config::Configuration, metrics, run_futures_with_runtime,
+    telemetry::SpanCollector, Context, ErrorKind,
+};
+use futures::FutureExt;
+use slog::{debug, warn, Drain};
 use slog_async::{self, OverflowStrategy};
+use std::process;
 use structopt::{self, StructOpt};
 
+use crate::signals::ShutdownSignal;
+
 mod cmd;
 mod logging;
+mod signals;
 
 quick_main!(run);
 
@@ -50,7 +58,7 @@ fn run() -> Result<()> {
     // need to be able to untangle very complicated logs from many parallel
     // async tasks.
     let base_drain = opt.log_format.create_drain();
-    let filtered = slog_envlogger::new(base_drain);
+    let filtered = logging::filter_drain(base_drain, opt.verbose);
     let drain = slog_async::Async::new(filtered)
         .chan_size(64)
         // This may slow down application performance, even when `RUST_LOG` is
@@ -74,9 +82,50 @@ fn run() -> Result<()> {
     let config = Configuration::try_default()?;
     debug!(ctx.log(), "{:?}", config);
 
+    // Record tracing spans, if we've been asked to export them.
+    let span_collector = SpanCollector::from_env()?;
+    let ctx = match &span_collector {
+        Some(collector) => ctx.with_span_collector(collector.clone()),
+        None => ctx,
+    };
+
     // Create a future to run our command.
-    let cmd_fut = cmd::run(ctx, config, opt);
+    let metrics_listen = opt.metrics_listen;
+    let metrics_push_url = opt.metrics_push_url.clone();
+    let cmd_fut = async move {
+        if let Some(addr) = metrics_listen {
+            metrics::serve(&ctx, addr).await?;
+        }
+        let (cmd_ctx, mut span) = ctx.span("dbcrossbar");
+        let result =
+            signals::run_until_shutdown_signal(&ctx, cmd::run(cmd_ctx, config, opt))
+                .await;
+        if let Err(err) = &result {
+            span.record_error(err);
+        }
+        drop(span);
+        if let Some(collector) = span_collector {
+            if let Err(err) = collector.export(&ctx).await {
+                warn!(ctx.log(), "could not export tracing spans: {}", err);
+            }
+        }
+        if let Some(url) = metrics_push_url {
+            if let Err(err) = metrics::push(&ctx, &url).await {
+                warn!(ctx.log(), "could not push metrics: {}", err);
+            }
+        }
+        result
+    }
+    .boxed();
 
-    // Run our futures.
-    run_futures_with_runtime(cmd_fut, worker_fut)
+    // Run our futures. If we were cancelled by a signal, exit with a status
+    // that says so.
+    let result = run_futures_with_runtime(cmd_fut, worker_fut);
+    if let Err(err) = &result {
+        if let Some(ErrorKind::Cancelled { signal }) = ErrorKind::of(err) {
+            eprintln!("Error: {}", err);
+            process::exit(ShutdownSignal::exit_code_for_name(signal));
+        }
+    }
+    result
 }
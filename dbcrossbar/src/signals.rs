@@ -0,0 +1,89 @@
+//! Handling signals which ask us to stop, like `SIGINT` and `SIGTERM`.
+
+use common_failures::Result;
+use dbcrossbarlib::{tokio_glue::BoxFuture, Context, ErrorKind};
+use futures::future::{self, Either, FutureExt};
+use slog::{error, warn};
+
+/// A signal asking us to stop.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ShutdownSignal {
+    /// `SIGINT`, usually sent by pressing Control-C.
+    Interrupt,
+    /// `SIGTERM`, usually sent by a process supervisor.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Terminate,
+}
+
+impl ShutdownSignal {
+    /// The name of this signal.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ShutdownSignal::Interrupt => "SIGINT",
+            ShutdownSignal::Terminate => "SIGTERM",
+        }
+    }
+
+    /// The exit code to use for a run cancelled by the signal named `name`.
+    /// We follow the shell convention of 128 plus the signal number.
+    pub(crate) fn exit_code_for_name(name: &str) -> i32 {
+        if name == ShutdownSignal::Terminate.name() {
+            128 + 15
+        } else {
+            128 + 2
+        }
+    }
+}
+
+/// Wait until we receive a signal asking us to stop.
+#[cfg(unix)]
+pub(crate) async fn shutdown_signal() -> Result<ShutdownSignal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let received =
+        future::select(interrupt.recv().boxed(), terminate.recv().boxed()).await;
+    match received {
+        Either::Left(_) => Ok(ShutdownSignal::Interrupt),
+        Either::Right(_) => Ok(ShutdownSignal::Terminate),
+    }
+}
+
+/// Wait until we receive a signal asking us to stop.
+#[cfg(not(unix))]
+pub(crate) async fn shutdown_signal() -> Result<ShutdownSignal> {
+    tokio::signal::ctrl_c().await?;
+    Ok(ShutdownSignal::Interrupt)
+}
+
+/// Run `fut`, but if we receive a shutdown signal first, cancel it, clean up
+/// after it, and return an `ErrorKind::Cancelled` error.
+pub(crate) async fn run_until_shutdown_signal(
+    ctx: &Context,
+    fut: BoxFuture<()>,
+) -> Result<()> {
+    let (signal, fut) = match future::select(fut, shutdown_signal().boxed()).await {
+        Either::Left((result, _)) => return result,
+        Either::Right((signal, fut)) => (signal?, fut),
+    };
+    warn!(ctx.log(), "received {}, cancelling", signal.name());
+
+    // Take our cleanup actions _before_ dropping `fut`, which would otherwise
+    // forget them. Then drop `fut` to stop any work in progress.
+    let cleanup = ctx.clean_up_after_cancel();
+    drop(fut);
+
+    // Clean up, unless we're interrupted again.
+    match future::select(cleanup, shutdown_signal().boxed()).await {
+        Either::Left((Ok(()), _)) => {}
+        Either::Left((Err(err), _)) => error!(ctx.log(), "{}", err),
+        Either::Right((_, _)) => {
+            warn!(ctx.log(), "received another signal, skipping cleanup");
+        }
+    }
+    Err(ErrorKind::Cancelled {
+        signal: signal.name().to_owned(),
+    }
+    .into())
+}
@@ -15,3 +15,19 @@ fn version_flag() {
     let output = testdir.cmd().arg("--version").expect_success();
     assert!(output.stdout_str().contains(env!("CARGO_PKG_VERSION")));
 }
+
+#[test]
+fn completions_bash() {
+    let testdir = TestDir::new("dbcrossbar", "completions_bash");
+    let output = testdir
+        .cmd()
+        .args(&["completions", "bash"])
+        .expect_success();
+    assert!(output.stdout_str().contains("_dbcrossbar()"));
+    assert!(output.stdout_str().contains("--if-exists"));
+    assert!(output
+        .stdout_str()
+        .contains("error overwrite append upsert-on:"));
+    assert!(output.stdout_str().contains("postgres:"));
+    assert!(output.stdout_str().contains("bigquery:"));
+}
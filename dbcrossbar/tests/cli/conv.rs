@@ -11,7 +11,7 @@ const EXAMPLE_SQL: &str = include_str!("../../fixtures/example.sql");
 /// means we don't fully test certain modes of the CLI (though we have unit
 /// tests for much of the related code).
 const INPUT_SQL: &str = include_str!(
-    "../../../dbcrossbarlib/src/drivers/postgres_shared/schema/schema_sql_example.sql"
+    "../../../dbcrossbarschema/src/postgres/schema/schema_sql_example.sql"
 );
 
 #[test]
@@ -79,6 +79,75 @@ fn conv_csv_to_pg_sql() {
     assert!(output.stdout_str().contains("last_name"));
 }
 
+#[test]
+fn conv_pg_sql_to_elasticsearch_mapping() {
+    let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_elasticsearch_mapping");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "postgres-sql:-",
+            "elasticsearch-mapping:-",
+        ])
+        .output_with_stdin(EXAMPLE_SQL)
+        .expect_success();
+    let mapping: serde_json::Value =
+        serde_json::from_str(output.stdout_str()).unwrap();
+    let properties = &mapping["mappings"]["properties"];
+    assert_eq!(properties["id"]["type"], "integer");
+    assert_eq!(properties["first_name"]["type"], "text");
+}
+
+#[test]
+fn conv_pg_sql_to_databricks_sql() {
+    let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_databricks_sql");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "--to-arg=table_name=main.db.example",
+            "--to-arg=partition_by[]=last_name",
+            "postgres-sql:-",
+            "databricks-sql:-",
+        ])
+        .output_with_stdin(EXAMPLE_SQL)
+        .expect_success();
+    let sql = output.stdout_str();
+    assert!(sql.contains("CREATE TABLE `main`.`db`.`example` ("));
+    assert!(sql.contains("`id` INT"));
+    assert!(sql.contains("USING delta"));
+    assert!(sql.contains("PARTITIONED BY (`last_name`)"));
+}
+
+#[test]
+fn conv_pg_sql_to_druid_spec() {
+    let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_druid_spec");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "--to-arg=input[]=s3://example/example/",
+            "postgres-sql:-",
+            "druid-spec:-",
+        ])
+        .output_with_stdin(INPUT_SQL)
+        .expect_success();
+    let spec: serde_json::Value = serde_json::from_str(output.stdout_str()).unwrap();
+    let data_schema = &spec["spec"]["dataSchema"];
+    assert_eq!(spec["type"], "index_parallel");
+    assert!(data_schema["timestampSpec"]["column"].is_string());
+    assert_eq!(
+        spec["spec"]["ioConfig"]["inputSource"]["prefixes"][0],
+        "s3://example/example/",
+    );
+}
+
 #[test]
 fn conv_pg_sql_to_bq_schema() {
     let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_bq_schema");
@@ -182,3 +251,89 @@ fn conv_old_dbcrossbar_schema_to_new() {
         serde_json::from_str::<serde_json::Value>(EXPECTED).unwrap(),
     );
 }
+
+#[test]
+fn conv_pg_sql_to_pg_sql_idempotent() {
+    let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_pg_sql_idempotent");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "conv",
+            "--to-arg=if_not_exists=true",
+            "--to-arg=drop_if_exists=true",
+            "postgres-sql:-",
+            "postgres-sql:-",
+        ])
+        .output_with_stdin(EXAMPLE_SQL)
+        .expect_success();
+    let sql = output.stdout_str();
+    assert!(sql.starts_with("DROP TABLE IF EXISTS \"example\";\n"));
+    assert!(sql.contains("CREATE TABLE IF NOT EXISTS \"example\""));
+}
+
+#[test]
+fn conv_with_locator_alias() {
+    let testdir = TestDir::new("dbcrossbar", "conv_with_locator_alias");
+    let src = testdir.src_path("fixtures/example.sql");
+    fs::write(
+        testdir.path("dbcrossbar.toml"),
+        format!(
+            "[locators]\nexample-schema = 'postgres-sql:{}'\n",
+            src.display()
+        ),
+    )
+    .unwrap();
+    let output = testdir
+        .cmd()
+        .env("DBCROSSBAR_CONFIG_DIR", testdir.path("."))
+        .args(&["schema", "conv", "example-schema", "postgres-sql:-"])
+        .tee_output()
+        .expect_success();
+    assert!(output.stdout_str().contains("CREATE TABLE"));
+}
+
+#[test]
+fn conv_pg_sql_to_bigquery_sql_or_replace() {
+    let testdir = TestDir::new("dbcrossbar", "conv_pg_sql_to_bigquery_sql_or_replace");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "--to-arg=table_name=project:dataset.example",
+            "--to-arg=or_replace=true",
+            "postgres-sql:-",
+            "bigquery-sql:-",
+        ])
+        .output_with_stdin(EXAMPLE_SQL)
+        .expect_success();
+    let sql = output.stdout_str();
+    assert!(
+        sql.starts_with("CREATE OR REPLACE TABLE `project`.`dataset`.`example` (\n")
+    );
+    assert!(sql.contains("`id` INT64,"));
+}
+
+#[test]
+fn conv_pg_sql_to_bigquery_sql_rejects_conflicting_args() {
+    let testdir = TestDir::new(
+        "dbcrossbar",
+        "conv_pg_sql_to_bigquery_sql_rejects_conflicting_args",
+    );
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "--to-arg=table_name=project:dataset.example",
+            "--to-arg=if_not_exists=true",
+            "--to-arg=or_replace=true",
+            "postgres-sql:-",
+            "bigquery-sql:-",
+        ])
+        .output_with_stdin(EXAMPLE_SQL)
+        .expect_failure();
+}
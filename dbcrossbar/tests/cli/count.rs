@@ -69,3 +69,17 @@ fn count_postgres() {
 
     assert_eq!(output.stdout_str().trim(), "2");
 }
+
+#[test]
+fn count_csv() {
+    let testdir = TestDir::new("dbcrossbar", "count_csv");
+    let src = testdir.src_path("fixtures/posts.csv");
+
+    let output = testdir
+        .cmd()
+        .args(&["count", &format!("csv:{}", src.display())])
+        .tee_output()
+        .expect_success();
+
+    assert_eq!(output.stdout_str().trim(), "2");
+}
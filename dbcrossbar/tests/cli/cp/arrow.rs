@@ -0,0 +1,30 @@
+//! Tests for the `arrow:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_arrow_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_arrow_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "arrow:out/",
+        ])
+        .expect_success();
+
+    assert!(testdir.path("out/example.arrows").exists());
+
+    testdir
+        .cmd()
+        .args(&["--enable-unstable", "cp", "arrow:out/", "csv:out.csv"])
+        .expect_success();
+    let expected = fs::read_to_string(&src).unwrap();
+    testdir.expect_file_contents("out.csv", &expected);
+}
@@ -0,0 +1,33 @@
+//! Tests for the `avro:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_avro_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_avro_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            "--to-arg=codec=deflate",
+            &format!("csv:{}", src.display()),
+            "avro:out/",
+        ])
+        .expect_success();
+
+    // Avro object container files start with a magic number.
+    let avro = fs::read(testdir.path("out/example.avro")).unwrap();
+    assert!(avro.starts_with(b"Obj\x01"));
+
+    testdir
+        .cmd()
+        .args(&["--enable-unstable", "cp", "avro:out/", "csv:out.csv"])
+        .expect_success();
+    let expected = fs::read_to_string(&src).unwrap();
+    testdir.expect_file_contents("out.csv", &expected);
+}
@@ -56,3 +56,483 @@ fn cp_csv_to_csv_piped() {
         .expect_success();
     assert_eq!(output.stdout_str(), EXAMPLE_CSV);
 }
+
+#[test]
+fn cp_csv_to_tsv_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_tsv_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--to-arg=delimiter=tab",
+            "--to-arg=header=false",
+            &format!("csv:{}", src.display()),
+            "csv:out.tsv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.tsv", "1\tJohn\tDoe\n");
+
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            "--from-arg=delimiter=tab",
+            "--from-arg=header=false",
+            "csv:out.tsv",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", EXAMPLE_CSV);
+}
+
+#[test]
+fn cp_csv_to_csv_with_same_dialect_copies_bytes() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_csv_to_csv_with_same_dialect_copies_bytes");
+    // Re-encoding this would remove the unnecessary quotes.
+    let input = "id\tname\n\"1\"\t\"John\"\n";
+    testdir.create_file("in/a.tsv", input);
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--from-arg=delimiter=tab",
+            "--to-arg=delimiter=tab",
+            "csv:in/a.tsv",
+            "csv:out.tsv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.tsv", input);
+
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=csv:in/a.tsv",
+            "--from-arg=delimiter=tab",
+            "--to-arg=delimiter=tab",
+            "csv:in/",
+            "csv:out/",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out/a.csv", input);
+}
+
+#[test]
+fn cp_csv_with_date_formats() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_date_formats");
+    testdir.create_file(
+        "schema.sql",
+        "CREATE TABLE example (id INT, born DATE, seen_at TIMESTAMP);\n",
+    );
+    testdir.create_file("in.csv", "id,born,seen_at\n1,07/20/1969,-14182940\n2,,\n");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=postgres-sql:schema.sql",
+            "--date-format=%m/%d/%Y",
+            "--timestamp-format=seen_at=epoch",
+            "csv:in.csv",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents(
+        "out.csv",
+        "id,born,seen_at\n1,1969-07-20,1969-07-20T20:17:40\n2,,\n",
+    );
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=postgres-sql:schema.sql",
+            "--date-format=%d.%m.%Y",
+            "csv:in.csv",
+            "csv:bad.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("07/20/1969"));
+}
+
+#[test]
+fn cp_csv_with_renames() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_renames");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir.create_file("renames.txt", "# Our new names.\nlast_name:surname\n");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--rename=first_name:given_name",
+            "--rename-file=renames.txt",
+            "--reorder-columns",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "surname,given_name,id\nDoe,John,1\n");
+}
+
+#[test]
+fn cp_csv_with_rename_file_and_reorder_columns() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_csv_with_rename_file_and_reorder_columns");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir.create_file("renames.txt", "last_name:surname\n");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--rename-file=renames.txt",
+            "--reorder-columns",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "surname,id,first_name\nDoe,1,John\n");
+}
+
+#[test]
+fn cp_csv_reorder_columns_requires_renames() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_csv_reorder_columns_requires_renames");
+    let src = testdir.src_path("fixtures/example.csv");
+
+    // Other column mappings aren't enough to make `--reorder-columns` useful.
+    for mapping in &["--select=last_name,id", "--exclude=id", "--cast=id:text"] {
+        let output = testdir
+            .cmd()
+            .args(&[
+                "cp",
+                mapping,
+                "--reorder-columns",
+                &format!("csv:{}", src.display()),
+                "csv:out.csv",
+            ])
+            .expect_failure();
+        assert!(output
+            .stderr_str()
+            .contains("--reorder-columns requires --rename"));
+    }
+}
+
+#[test]
+fn cp_csv_with_select_and_exclude() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_select_and_exclude");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--select=last_name,id",
+            &format!("csv:{}", src.display()),
+            "csv:selected.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("selected.csv", "last_name,id\nDoe,1\n");
+
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--exclude=first_name",
+            &format!("csv:{}", src.display()),
+            "csv:excluded.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("excluded.csv", "id,last_name\n1,Doe\n");
+}
+
+#[test]
+fn cp_csv_with_casts() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_casts");
+    testdir.create_file(
+        "schema.sql",
+        "CREATE TABLE example (id TEXT, active TEXT, price TEXT);\n",
+    );
+    testdir.create_file("in.csv", "id,active,price\n1.0,yes,12.50\n2,,\n");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=postgres-sql:schema.sql",
+            "--cast=id:bigint",
+            "--cast=active:bool",
+            "--cast=price:numeric(12,2)",
+            "csv:in.csv",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "id,active,price\n1,t,12.50\n2,,\n");
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=postgres-sql:schema.sql",
+            "--cast=active:int16",
+            "csv:in.csv",
+            "csv:bad.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("yes"));
+}
+
+#[test]
+fn cp_csv_with_mask_file() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_mask_file");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir.create_file(
+        "mask.json",
+        r#"{"columns": {"first_name": {"replace": "Jane"}, "last_name": {"truncate": 1}}}"#,
+    );
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--mask-file=mask.json",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "id,first_name,last_name\n1,Jane,D\n");
+
+    testdir.create_file("bad_mask.json", r#"{"columns": {"id": "hash"}}"#);
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--cast=id:int64",
+            "--mask-file=bad_mask.json",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("not a text column"));
+}
+
+#[test]
+fn cp_csv_with_from_query_fails() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_from_query_fails");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir.create_file("query.sql", "SELECT 1");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--from-query=query.sql",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("--from-query"));
+}
+
+#[test]
+fn cp_csv_with_evolve_schema_fails() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_evolve_schema_fails");
+    let src = testdir.src_path("fixtures/example.csv");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--if-exists=append",
+            "--evolve-schema",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("--evolve-schema"));
+}
+
+#[test]
+fn cp_csv_with_create_missing_fails() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_create_missing_fails");
+    let src = testdir.src_path("fixtures/example.csv");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--create-missing",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("--create-missing"));
+}
+
+#[test]
+fn cp_csv_tables_from_manifest() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_tables_from_manifest");
+    testdir.create_file("in/a.csv", "id\n1\n");
+    testdir.create_file("in/b.csv", "id\n2\n");
+    testdir.create_file("tables.txt", "# Tables to copy.\na\n\nb\nmissing\n");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--manifest=tables.txt",
+            "--max-parallel-tables=2",
+            "csv:in/{table}.csv",
+            "csv:out/{table}.csv",
+        ])
+        .expect_failure();
+    testdir.expect_file_contents("out/a.csv", "id\n1\n");
+    testdir.expect_file_contents("out/b.csv", "id\n2\n");
+    let stderr = output.stderr_str();
+    assert!(stderr.contains("copied a\n"));
+    assert!(stderr.contains("copied b\n"));
+    assert!(stderr.contains("FAILED missing"));
+    assert!(stderr.contains("could not copy 1 of 3 tables"));
+}
+
+#[test]
+fn cp_csv_with_limit() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_limit");
+    testdir.create_file("in.csv", "id\n1\n2\n3\n");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--limit=2",
+            "--sample=100%",
+            "csv:in.csv",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "id\n1\n2\n");
+}
+
+#[test]
+fn cp_csv_with_dedup() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_dedup");
+    testdir.create_file(
+        "schema.sql",
+        "CREATE TABLE example (id INT, version INT, name TEXT);\n",
+    );
+    testdir.create_file("in.csv", "id,version,name\n1,9,a\n1,10,b\n1,9,a\n2,1,c\n");
+    testdir
+        .cmd()
+        .args(&["cp", "--dedup", "csv:in.csv", "csv:exact.csv"])
+        .expect_success();
+    testdir
+        .expect_file_contents("exact.csv", "id,version,name\n1,9,a\n1,10,b\n2,1,c\n");
+
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--schema=postgres-sql:schema.sql",
+            "--dedup-on=id",
+            "--dedup-order-by=version",
+            "csv:in.csv",
+            "csv:latest.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("latest.csv", "id,version,name\n1,10,b\n2,1,c\n");
+}
+
+#[test]
+fn cp_csv_with_max_bytes_per_second() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_max_bytes_per_second");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--max-bytes-per-second=1Mb",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.csv", EXAMPLE_CSV);
+}
+
+#[test]
+fn cp_csv_with_zero_max_streams_fails() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_zero_max_streams_fails");
+    let src = testdir.src_path("fixtures/example.csv");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--max-streams=0",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("--max-streams"));
+}
+
+#[test]
+fn cp_csv_dry_run_with_zero_max_streams_fails() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_csv_dry_run_with_zero_max_streams_fails");
+    let src = testdir.src_path("fixtures/example.csv");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--max-streams=0",
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    assert!(output.stderr_str().contains("--max-streams"));
+}
+
+#[test]
+fn cp_csv_with_summary() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_summary");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--summary=summary.json",
+            // Force a local copy, so that we count our streams.
+            "--dedup",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "csv:out.csv",
+        ])
+        .expect_success();
+    let summary: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(testdir.path("summary.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(summary["succeeded"], true);
+    assert_eq!(summary["to_locator"], "csv:out.csv");
+    assert_eq!(summary["dest_locators"][0], "csv:out.csv");
+    let rows = EXAMPLE_CSV.lines().count() - 1;
+    assert_eq!(summary["streams"][0]["rows"], rows);
+}
+
+#[test]
+fn cp_csv_with_summary_on_failure() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_with_summary_on_failure");
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--summary=summary.json",
+            "csv:does_not_exist.csv",
+            "csv:out.csv",
+        ])
+        .expect_failure();
+    let summary: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(testdir.path("summary.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(summary["succeeded"], false);
+    assert!(summary["error"]
+        .as_str()
+        .unwrap()
+        .contains("does_not_exist"));
+}
@@ -0,0 +1,59 @@
+//! Tests for the `delta:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_delta_and_read_schema() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_delta_and_read_schema");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "delta:example/",
+        ])
+        .expect_success();
+
+    let commit = fs::read_to_string(
+        testdir.path("example/_delta_log/00000000000000000000.json"),
+    )
+    .unwrap();
+    assert!(commit.contains("\"add\""));
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "delta:example/",
+            "postgres-sql:-",
+        ])
+        .expect_success();
+    assert!(output.stdout_str().contains("CREATE TABLE"));
+    assert!(output.stdout_str().contains("first_name"));
+}
+
+#[test]
+fn cp_csv_to_delta_refuses_to_overwrite() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_delta_refuses_to_overwrite");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    fs::create_dir_all(testdir.path("example/_delta_log")).unwrap();
+    testdir.create_file("example/_delta_log/00000000000000000000.json", "");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "delta:example/",
+        ])
+        .expect_failure();
+}
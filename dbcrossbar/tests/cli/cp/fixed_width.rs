@@ -0,0 +1,38 @@
+//! Tests for the `fixed-width:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_fixed_width_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_fixed_width_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    let layout = testdir.src_path("fixtures/example_layout.json");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("--to-arg=layout={}", layout.display()),
+            &format!("csv:{}", src.display()),
+            "fixed-width:out.txt",
+        ])
+        .expect_success();
+    testdir.expect_file_contents("out.txt", "   1 John      Doe       \n");
+
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("--from-arg=layout={}", layout.display()),
+            "fixed-width:out.txt",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    let expected = fs::read_to_string(&src).unwrap();
+    testdir.expect_file_contents("out.csv", &expected);
+}
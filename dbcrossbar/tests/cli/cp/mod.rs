@@ -4,15 +4,22 @@ use cli_test_dir::*;
 use difference::assert_diff;
 use std::{env, fs};
 
+mod arrow;
+mod avro;
 mod bigml;
 mod bigquery;
 mod combined;
 mod csv;
+mod delta;
+mod fixed_width;
 mod gs;
+mod ndjson;
+mod parquet;
 mod postgres;
 mod redshift;
 mod s3;
 mod shopify;
+mod xlsx;
 
 /// The URL of our test database.
 pub(crate) fn postgres_test_url() -> String {
@@ -0,0 +1,57 @@
+//! Tests for the `ndjson:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_ndjson_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_ndjson_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "ndjson:out.ndjson",
+        ])
+        .expect_success();
+    testdir.expect_file_contents(
+        "out.ndjson",
+        "{\"id\":1,\"first_name\":\"John\",\"last_name\":\"Doe\"}\n",
+    );
+
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            "ndjson:out.ndjson",
+            "csv:out.csv",
+        ])
+        .expect_success();
+    let expected = fs::read_to_string(&src).unwrap();
+    testdir.expect_file_contents("out.csv", &expected);
+}
+
+#[test]
+fn cp_ndjson_to_ndjson_piped() {
+    let testdir = TestDir::new("dbcrossbar", "cp_ndjson_to_ndjson_piped");
+    let schema = testdir.src_path("fixtures/example.sql");
+    let ndjson = "{\"id\":1,\"first_name\":\"John\",\"last_name\":\"Doe\"}\n";
+    let output = testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            "ndjson:-",
+            "ndjson:-",
+        ])
+        .output_with_stdin(ndjson)
+        .expect_success();
+    assert_eq!(output.stdout_str(), ndjson);
+}
@@ -0,0 +1,33 @@
+//! Tests for the `parquet:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_parquet_and_back() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_parquet_and_back");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            "--to-arg=compression=gzip",
+            &format!("csv:{}", src.display()),
+            "parquet:out/",
+        ])
+        .expect_success();
+
+    // Parquet files start with a magic number.
+    let parquet = fs::read(testdir.path("out/example.parquet")).unwrap();
+    assert!(parquet.starts_with(b"PAR1"));
+
+    testdir
+        .cmd()
+        .args(&["--enable-unstable", "cp", "parquet:out/", "csv:out.csv"])
+        .expect_success();
+    let expected = fs::read_to_string(&src).unwrap();
+    testdir.expect_file_contents("out.csv", &expected);
+}
@@ -48,6 +48,102 @@ fn cp_csv_to_postgres_append() {
         .expect_success();
 }
 
+#[test]
+#[ignore]
+fn cp_csv_to_postgres_with_evolve_schema() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_postgres_with_evolve_schema");
+    let pg_table = post_test_table_url("cp_csv_to_postgres_with_evolve_schema");
+    testdir.create_file("old.csv", "id,name\n1,Alice\n");
+    testdir.create_file("new.csv", "id,name,email\n2,Bob,bob@example.com\n");
+
+    // CSV to Postgres.
+    testdir
+        .cmd()
+        .args(&["cp", "--if-exists=overwrite", "csv:old.csv", &pg_table])
+        .tee_output()
+        .expect_success();
+
+    // Appending data with an extra column fails by default.
+    testdir
+        .cmd()
+        .args(&["cp", "--if-exists=append", "csv:new.csv", &pg_table])
+        .tee_output()
+        .expect_failure();
+
+    // But it works if we allow the destination schema to evolve.
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--if-exists=append",
+            "--evolve-schema",
+            "csv:new.csv",
+            &pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+
+    // PostgreSQL back to CSV.
+    testdir
+        .cmd()
+        .args(&["cp", &pg_table, "csv:out.csv"])
+        .tee_output()
+        .expect_success();
+    let actual = fs::read_to_string(testdir.path("out.csv")).unwrap();
+    assert_diff!(
+        "id,name,email\n1,Alice,\n2,Bob,bob@example.com\n",
+        &actual,
+        ",",
+        0
+    );
+}
+
+#[test]
+#[ignore]
+fn cp_csv_to_postgres_append_checks_schema() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_csv_to_postgres_append_checks_schema");
+    let pg_table = post_test_table_url("cp_csv_to_postgres_append_checks_schema");
+    testdir.create_file(
+        "old.sql",
+        "CREATE TABLE example (id INT NOT NULL, name TEXT NOT NULL);\n",
+    );
+    testdir.create_file("new.sql", "CREATE TABLE example (id BIGINT, email TEXT);\n");
+    testdir.create_file("old.csv", "id,name\n1,Alice\n");
+    testdir.create_file("new.csv", "id,email\n2,bob@example.com\n");
+
+    // CSV to Postgres.
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--if-exists=overwrite",
+            "--schema=postgres-sql:old.sql",
+            "csv:old.csv",
+            &pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+
+    // Appending incompatible data reports every problem before copying.
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--if-exists=append",
+            "--schema=postgres-sql:new.sql",
+            "csv:new.csv",
+            &pg_table,
+        ])
+        .tee_output()
+        .expect_failure();
+    let stderr = output.stderr_str();
+    assert!(stderr.contains(r#"column "id" has type int64"#));
+    assert!(stderr.contains(r#"column "id" may contain NULL"#));
+    assert!(stderr.contains(r#"destination column "name" is NOT NULL"#));
+    assert!(stderr.contains(r#"column "email" is missing"#));
+}
+
 #[test]
 #[ignore]
 fn cp_from_postgres_with_where() {
@@ -91,6 +187,51 @@ fn cp_from_postgres_with_where() {
     assert_diff!(&expected, &actual, ",", 0);
 }
 
+#[test]
+#[ignore]
+fn cp_from_postgres_with_query() {
+    let testdir = TestDir::new("dbcrossbar", "cp_from_postgres_with_query");
+    let src = testdir.src_path("fixtures/posts.csv");
+    let filtered = testdir.src_path("fixtures/posts_where_author_id_1.csv");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    let pg_table = post_test_table_url("cp_from_postgres_with_query");
+
+    // CSV to Postgres.
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--if-exists=overwrite",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            &pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+
+    // PostgreSQL back to CSV using --from-query, inferring the schema from
+    // the query.
+    testdir.create_file(
+        "query.sql",
+        "SELECT author_id, title FROM cp_from_postgres_with_query WHERE author_id = 1",
+    );
+    testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--from-query=query.sql",
+            &post_test_table_url("posts_by_author_1"),
+            "csv:out/",
+        ])
+        .tee_output()
+        .expect_success();
+
+    let expected = fs::read_to_string(&filtered).unwrap();
+    let actual =
+        fs::read_to_string(testdir.path("out/posts_by_author_1.csv")).unwrap();
+    assert_diff!(&expected, &actual, ",", 0);
+}
+
 #[test]
 #[ignore]
 fn postgres_upsert() {
@@ -275,3 +416,182 @@ fn cp_pg_tricky_column_types() {
     let actual = fs::read_to_string(testdir.path("out.csv")).unwrap();
     assert_diff!(&expected, &actual, ",", 0);
 }
+
+#[test]
+fn cp_csv_to_postgres_dry_run() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_postgres_dry_run");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    // Use a database that doesn't exist, to make sure we don't connect.
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#example";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--if-exists=overwrite",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("DROP TABLE IF EXISTS \"example\";"));
+    assert!(stdout.contains("CREATE TABLE \"example\" ("));
+    assert!(stdout.contains("COPY \"example\" ("));
+}
+
+#[test]
+fn cp_csv_to_postgres_dry_run_with_append() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_postgres_dry_run_with_append");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    // We don't connect to check the existing table, so this should work.
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#example";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--if-exists=append",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(
+        stdout.contains("(Not checking whether this data fits any existing table)")
+    );
+    assert!(stdout.contains("CREATE TABLE IF NOT EXISTS \"example\" ("));
+}
+
+#[test]
+fn cp_csv_to_postgres_dry_run_with_table_options() {
+    let testdir = TestDir::new(
+        "dbcrossbar",
+        "cp_csv_to_postgres_dry_run_with_table_options",
+    );
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    // Use a database that doesn't exist, to make sure we don't connect.
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#example";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--if-exists=overwrite",
+            "--to-arg=unlogged=true",
+            "--to-arg=tablespace=fast_disk",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("CREATE UNLOGGED TABLE \"example\" ("));
+    assert!(stdout.contains(") TABLESPACE \"fast_disk\";"));
+}
+
+#[test]
+fn cp_csv_to_postgres_dry_run_with_create_missing() {
+    let testdir = TestDir::new(
+        "dbcrossbar",
+        "cp_csv_to_postgres_dry_run_with_create_missing",
+    );
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    // Use a database that doesn't exist, to make sure we don't connect.
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#staging.example";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--if-exists=overwrite",
+            "--create-missing",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("CREATE SCHEMA IF NOT EXISTS \"staging\";"));
+    assert!(stdout.contains("CREATE TABLE \"staging\".\"example\" ("));
+}
+
+#[test]
+#[ignore]
+fn cp_postgres_wildcard_tables_to_csv() {
+    let testdir = TestDir::new("dbcrossbar", "cp_postgres_wildcard_tables_to_csv");
+    testdir.create_file("a.csv", "id\n1\n");
+    testdir.create_file("b.csv", "id\n2\n");
+
+    // Create two tables with a common prefix.
+    for name in &["a", "b"] {
+        testdir
+            .cmd()
+            .args(&[
+                "cp",
+                "--if-exists=overwrite",
+                &format!("csv:{}.csv", name),
+                &post_test_table_url(&format!("cp_wildcard_{}", name)),
+            ])
+            .tee_output()
+            .expect_success();
+    }
+
+    // Copy both tables at once.
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            &post_test_table_url("cp_wildcard_*"),
+            "csv:out/{table}.csv",
+        ])
+        .tee_output()
+        .expect_success();
+    testdir.expect_file_contents("out/cp_wildcard_a.csv", "id\n1\n");
+    testdir.expect_file_contents("out/cp_wildcard_b.csv", "id\n2\n");
+    assert!(output.stderr_str().contains("copied cp_wildcard_a\n"));
+}
+
+#[test]
+#[ignore]
+fn cp_postgres_to_postgres_in_same_database() {
+    let testdir =
+        TestDir::new("dbcrossbar", "cp_postgres_to_postgres_in_same_database");
+    let src_table = post_test_table_url("cp_pg_same_database_src");
+    let dest_table = post_test_table_url("cp_pg_same_database_dest");
+    testdir.create_file("in.csv", "id,name\n1,Alice\n2,Bob\n");
+
+    testdir
+        .cmd()
+        .args(&["cp", "--if-exists=overwrite", "csv:in.csv", &src_table])
+        .tee_output()
+        .expect_success();
+
+    // This reuses connections to the same database, so make sure that our
+    // `COPY TO` and our `COPY FROM` don't end up waiting for each other.
+    testdir
+        .cmd()
+        .args(&["cp", "--if-exists=overwrite", &src_table, &dest_table])
+        .tee_output()
+        .expect_success();
+
+    testdir
+        .cmd()
+        .args(&["cp", &dest_table, "csv:out.csv"])
+        .tee_output()
+        .expect_success();
+    testdir.expect_file_contents("out.csv", "id,name\n1,Alice\n2,Bob\n");
+}
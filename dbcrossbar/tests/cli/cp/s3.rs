@@ -52,3 +52,26 @@ fn cp_csv_to_s3_to_csv() {
     let actual = fs::read_to_string(testdir.path("out/many_types.csv")).unwrap();
     assert_diff!(&expected, &actual, ",", 0);
 }
+
+#[test]
+fn cp_csv_to_s3_dry_run() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_s3_dry_run");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "cp",
+            "--dry-run",
+            "--if-exists=overwrite",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "s3://example-bucket/dry_run/",
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("-- Delete all files under s3://example-bucket/dry_run/"));
+    assert!(stdout.contains("-- Upload CSV data to s3://example-bucket/dry_run/"));
+}
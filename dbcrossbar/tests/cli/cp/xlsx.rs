@@ -0,0 +1,41 @@
+//! Tests for the `xlsx:` driver.
+
+use cli_test_dir::*;
+use std::fs;
+
+#[test]
+fn cp_csv_to_xlsx() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_xlsx");
+    let src = testdir.src_path("fixtures/example.csv");
+    let schema = testdir.src_path("fixtures/example.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("--schema=postgres-sql:{}", schema.display()),
+            &format!("csv:{}", src.display()),
+            "xlsx:out.xlsx",
+        ])
+        .expect_success();
+
+    // An `.xlsx` file is a zip archive.
+    let workbook = fs::read(testdir.path("out.xlsx")).unwrap();
+    assert!(workbook.starts_with(b"PK"));
+}
+
+#[test]
+fn cp_csv_to_xlsx_refuses_to_overwrite() {
+    let testdir = TestDir::new("dbcrossbar", "cp_csv_to_xlsx_refuses_to_overwrite");
+    let src = testdir.src_path("fixtures/example.csv");
+    testdir.create_file("out.xlsx", "");
+    testdir
+        .cmd()
+        .args(&[
+            "--enable-unstable",
+            "cp",
+            &format!("csv:{}", src.display()),
+            "xlsx:out.xlsx",
+        ])
+        .expect_failure();
+}
@@ -0,0 +1,67 @@
+//! Tests for the `schema diff` subcommand.
+
+use cli_test_dir::*;
+
+#[test]
+fn diff_identical_schemas() {
+    let testdir = TestDir::new("dbcrossbar", "diff_identical_schemas");
+    let schema = testdir.src_path("fixtures/example.sql");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "diff",
+            &format!("postgres-sql:{}", schema.display()),
+            &format!("postgres-sql:{}", schema.display()),
+        ])
+        .expect_success();
+    assert_eq!(output.stdout_str(), "");
+}
+
+#[test]
+fn diff_changed_schemas() {
+    let testdir = TestDir::new("dbcrossbar", "diff_changed_schemas");
+    let old = testdir.src_path("fixtures/example.sql");
+    let new = testdir.src_path("fixtures/example_changed.sql");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "diff",
+            &format!("postgres-sql:{}", old.display()),
+            &format!("postgres-sql:{}", new.display()),
+        ])
+        .expect_failure();
+    assert_eq!(
+        output.stdout_str(),
+        r#"- "last_name" text
+~ "id" int32 -> int64 NOT NULL
++ "email" text
+"#,
+    );
+}
+
+#[test]
+fn diff_alter_sql() {
+    let testdir = TestDir::new("dbcrossbar", "diff_alter_sql");
+    let old = testdir.src_path("fixtures/example.sql");
+    let new = testdir.src_path("fixtures/example_changed.sql");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "diff",
+            "--alter-sql",
+            &format!("postgres-sql:{}", old.display()),
+            &format!("postgres-sql:{}", new.display()),
+        ])
+        .expect_success();
+    assert_eq!(
+        output.stdout_str(),
+        r#"ALTER TABLE "example" DROP COLUMN "last_name";
+ALTER TABLE "example" ALTER COLUMN "id" TYPE bigint USING "id"::bigint;
+ALTER TABLE "example" ALTER COLUMN "id" SET NOT NULL;
+ALTER TABLE "example" ADD COLUMN "email" text;
+"#,
+    );
+}
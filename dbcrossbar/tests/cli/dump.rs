@@ -0,0 +1,213 @@
+//! Tests for the `schema dump` and `schema apply` subcommands.
+
+use cli_test_dir::*;
+
+use super::cp::*;
+
+#[test]
+fn schema_dump_pg_sql() {
+    let testdir = TestDir::new("dbcrossbar", "schema_dump_pg_sql");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "dump",
+            &format!("postgres-sql:{}", schema.display()),
+        ])
+        .tee_output()
+        .expect_success();
+    assert!(output.stdout_str().contains(r#""name": "author_id""#));
+}
+
+#[test]
+#[ignore]
+fn schema_apply_postgres() {
+    let testdir = TestDir::new("dbcrossbar", "schema_apply_postgres");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    let pg_table = post_test_table_url("schema_apply_postgres");
+
+    // Create an empty table, then replace it, then leave it alone.
+    for &if_exists in &["--if-exists=overwrite", "--if-exists=append"] {
+        testdir
+            .cmd()
+            .args(&[
+                "schema",
+                "apply",
+                if_exists,
+                &format!("postgres-sql:{}", schema.display()),
+                &pg_table,
+            ])
+            .tee_output()
+            .expect_success();
+    }
+
+    // Without `--if-exists`, we should refuse to touch an existing table.
+    testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            &format!("postgres-sql:{}", schema.display()),
+            &pg_table,
+        ])
+        .tee_output()
+        .expect_failure();
+
+    // Make sure our new table has the right columns, and no rows.
+    let output = testdir
+        .cmd()
+        .args(&["schema", "dump", &pg_table])
+        .tee_output()
+        .expect_success();
+    assert!(output.stdout_str().contains(r#""name": "title""#));
+    let output = testdir
+        .cmd()
+        .args(&["count", &pg_table])
+        .tee_output()
+        .expect_success();
+    assert_eq!(output.stdout_str().trim(), "0");
+}
+
+#[test]
+fn schema_apply_postgres_dry_run() {
+    let testdir = TestDir::new("dbcrossbar", "schema_apply_postgres_dry_run");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    // Use a database that doesn't exist, to make sure we don't connect.
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#posts";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            "--dry-run",
+            "--if-exists=overwrite",
+            &format!("postgres-sql:{}", schema.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("-- If exists: overwrite"));
+    assert!(stdout.contains("DROP TABLE IF EXISTS \"posts\";"));
+    assert!(stdout.contains("CREATE TABLE \"posts\" ("));
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            "--dry-run",
+            "--if-exists=append",
+            &format!("postgres-sql:{}", schema.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(!stdout.contains("DROP TABLE"));
+    assert!(stdout.contains("CREATE TABLE IF NOT EXISTS \"posts\" ("));
+}
+
+#[test]
+#[ignore]
+fn schema_apply_bigquery() {
+    let testdir = TestDir::new("dbcrossbar", "schema_apply_bigquery");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    let bq_table = bq_test_table("schema_apply_bigquery");
+
+    // Create an empty table, then replace it, then leave it alone.
+    for &if_exists in &["--if-exists=overwrite", "--if-exists=append"] {
+        testdir
+            .cmd()
+            .args(&[
+                "schema",
+                "apply",
+                if_exists,
+                &format!("postgres-sql:{}", schema.display()),
+                &bq_table,
+            ])
+            .tee_output()
+            .expect_success();
+    }
+
+    // Make sure our new table has the right columns, and no rows.
+    let output = testdir
+        .cmd()
+        .args(&["schema", "dump", &bq_table])
+        .tee_output()
+        .expect_success();
+    assert!(output.stdout_str().contains(r#""name": "title""#));
+    let output = testdir
+        .cmd()
+        .args(&["count", &bq_table])
+        .tee_output()
+        .expect_success();
+    assert_eq!(output.stdout_str().trim(), "0");
+}
+
+#[test]
+fn schema_apply_bigquery_dry_run() {
+    let testdir = TestDir::new("dbcrossbar", "schema_apply_bigquery_dry_run");
+    let schema = testdir.src_path("fixtures/posts.sql");
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            "--dry-run",
+            "--if-exists=overwrite",
+            "--create-missing",
+            "--to-arg=table_description=Blog posts",
+            &format!("postgres-sql:{}", schema.display()),
+            "bigquery:example:dataset.posts",
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains("CREATE SCHEMA IF NOT EXISTS `example`.`dataset`"));
+    assert!(stdout.contains("CREATE OR REPLACE TABLE `example`.`dataset`.`posts` ("));
+    assert!(stdout.contains(r#"description="Blog posts""#));
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            "--dry-run",
+            "--if-exists=append",
+            &format!("postgres-sql:{}", schema.display()),
+            "bigquery:example:dataset.posts",
+        ])
+        .tee_output()
+        .expect_success();
+    let stdout = output.stdout_str();
+    assert!(!stdout.contains("CREATE SCHEMA"));
+    assert!(
+        stdout.contains("CREATE TABLE IF NOT EXISTS `example`.`dataset`.`posts` (")
+    );
+}
+
+#[test]
+fn schema_apply_postgres_rejects_create_missing() {
+    let testdir =
+        TestDir::new("dbcrossbar", "schema_apply_postgres_rejects_create_missing");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    let pg_table = "postgres://postgres:@localhost:1/nonexistent#posts";
+
+    let output = testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "apply",
+            "--dry-run",
+            "--create-missing",
+            &format!("postgres-sql:{}", schema.display()),
+            pg_table,
+        ])
+        .tee_output()
+        .expect_failure();
+    assert!(output.stderr_str().contains("--create-missing"));
+}
@@ -0,0 +1,54 @@
+//! Tests for external driver plugins.
+
+use cli_test_dir::*;
+use std::{env, ffi::OsString};
+
+/// Build a `PATH` which includes our example external driver.
+fn path_with_example_driver(testdir: &TestDir) -> OsString {
+    let mut paths = vec![testdir.src_path("fixtures/external_driver")];
+    if let Some(path) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&path));
+    }
+    env::join_paths(paths).expect("could not build PATH")
+}
+
+#[test]
+#[cfg(unix)]
+fn external_driver_schema() {
+    let testdir = TestDir::new("dbcrossbar", "external_driver_schema");
+    let output = testdir
+        .cmd()
+        .env("PATH", path_with_example_driver(&testdir))
+        .args(&[
+            "--enable-unstable",
+            "schema",
+            "conv",
+            "example:anything",
+            "postgres-sql:-",
+        ])
+        .expect_success();
+    assert!(output.stdout_str().contains("\"id\" bigint NOT NULL"));
+}
+
+#[test]
+#[cfg(unix)]
+fn external_driver_local_data() {
+    let testdir = TestDir::new("dbcrossbar", "external_driver_local_data");
+    let output = testdir
+        .cmd()
+        .env("PATH", path_with_example_driver(&testdir))
+        .args(&["--enable-unstable", "cp", "example:anything", "csv:-"])
+        .expect_success();
+    assert_eq!(output.stdout_str(), "id\n1\n2\n");
+}
+
+#[test]
+fn external_driver_requires_enable_unstable() {
+    let testdir =
+        TestDir::new("dbcrossbar", "external_driver_requires_enable_unstable");
+    testdir
+        .cmd()
+        .env("PATH", path_with_example_driver(&testdir))
+        .args(&["schema", "conv", "example:anything", "postgres-sql:-"])
+        .expect_failure();
+}
@@ -0,0 +1,40 @@
+//! Tests for the `schema lint` subcommand.
+
+use cli_test_dir::*;
+
+#[test]
+fn schema_lint_valid_schema() {
+    let testdir = TestDir::new("dbcrossbar", "schema_lint_valid_schema");
+    let schema = testdir.src_path("fixtures/posts.sql");
+    testdir
+        .cmd()
+        .args(&[
+            "schema",
+            "lint",
+            &format!("postgres-sql:{}", schema.display()),
+            "bigquery:",
+        ])
+        .tee_output()
+        .expect_success();
+}
+
+#[test]
+fn schema_lint_reports_problems() {
+    let testdir = TestDir::new("dbcrossbar", "schema_lint_reports_problems");
+    let output = testdir
+        .cmd()
+        .args(&["schema", "lint", "postgres-sql:-", "bigquery:"])
+        .output_with_stdin(
+            r#"CREATE TABLE example (
+    "id" bigint,
+    "ID" bigint,
+    "select" text,
+    "bad-name" text
+);"#,
+        )
+        .expect_failure();
+    let stdout = output.stdout_str();
+    assert!(stdout.contains(r#"error: column "ID": name conflicts with column "id""#));
+    assert!(stdout.contains(r#"warning: column "select""#));
+    assert!(stdout.contains(r#"error: column "bad-name""#));
+}
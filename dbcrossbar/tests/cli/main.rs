@@ -2,7 +2,17 @@
 //!
 //! This is the top-level file for a single CLI integration test binary.
 
+#![allow(
+    clippy::empty_docs,
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args
+)]
+
 pub(crate) mod about;
 pub(crate) mod conv;
 pub(crate) mod count;
 pub(crate) mod cp;
+pub(crate) mod diff;
+pub(crate) mod dump;
+pub(crate) mod external;
+pub(crate) mod lint;
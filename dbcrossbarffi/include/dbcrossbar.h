@@ -0,0 +1,29 @@
+/* C API for dbcrossbar schema conversion. See dbcrossbarffi/src/lib.rs. */
+
+#ifndef DBCROSSBAR_H
+#define DBCROSSBAR_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Look up the schema of `locator`, and return it as dbcrossbar-schema JSON.
+ * Returns NULL on error. Free the result with `dbcrossbar_string_free`. */
+char *dbcrossbar_schema_json(const char *locator);
+
+/* Convert `schema_json` into the format used by the schema driver
+ * `dest_scheme`, such as "postgres-sql:" or "bigquery-schema:". Returns NULL
+ * on error. Free the result with `dbcrossbar_string_free`. */
+char *dbcrossbar_schema_conv(const char *schema_json, const char *dest_scheme);
+
+/* The last error which occurred on this thread, or NULL. Do not free this. */
+const char *dbcrossbar_last_error(void);
+
+/* Free a string returned by this library. */
+void dbcrossbar_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DBCROSSBAR_H */
@@ -0,0 +1,387 @@
+//! A small C API for `dbcrossbar` schema conversion.
+//!
+//! This allows non-Rust programs to look up table schemas and to convert them
+//! to other dialects, using the same drivers as `dbcrossbar schema conv`. See
+//! `include/dbcrossbar.h` for the C declarations.
+//!
+//! All strings passed to and from these functions are NUL-terminated UTF-8.
+//! Strings returned by this library must be freed using
+//! `dbcrossbar_string_free`. When a function fails, it returns `NULL`, and the
+//! error message can be retrieved using `dbcrossbar_last_error`. Panics are
+//! never allowed to unwind into the caller; they're reported as errors, too.
+//!
+//! We also export a few safe Rust functions, which are shared with our other
+//! language bindings.
+
+#![warn(missing_docs, unused_extern_crates, clippy::all)]
+
+use dbcrossbarlib::{
+    run_futures_with_runtime, tokio_glue::BoxFuture, Context, DestinationArguments,
+    DriverArguments, Error, IfExists, Result, TemporaryStorage, UnparsedLocator,
+};
+use failure::{format_err, ResultExt};
+use futures::FutureExt;
+use slog::{o, Discard, Logger};
+use std::{
+    cell::RefCell,
+    env,
+    ffi::{CStr, CString},
+    fs,
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    ptr,
+    sync::{Arc, Mutex},
+};
+
+thread_local! {
+    /// The last error which occurred on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Look up the schema of `locator`, and return it in `dbcrossbar-schema`
+/// JSON format.
+///
+/// Returns `NULL` on error.
+///
+/// # Safety
+///
+/// `locator` must be a valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dbcrossbar_schema_json(
+    locator: *const c_char,
+) -> *mut c_char {
+    string_result(catch_panic(|| schema_json(locator)))
+}
+
+/// Implementation of `dbcrossbar_schema_json`, returning a `Result`.
+unsafe fn schema_json(locator: *const c_char) -> Result<String> {
+    schema_json_string(str_arg(locator, "locator")?)
+}
+
+/// Convert `schema_json` (in `dbcrossbar-schema` JSON format) into the format
+/// used by the schema driver `dest_scheme`, for example `"postgres-sql:"` or
+/// `"bigquery-schema:"`.
+///
+/// Returns `NULL` on error.
+///
+/// # Safety
+///
+/// `schema_json` and `dest_scheme` must be valid pointers to NUL-terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn dbcrossbar_schema_conv(
+    schema_json: *const c_char,
+    dest_scheme: *const c_char,
+) -> *mut c_char {
+    string_result(catch_panic(|| schema_conv(schema_json, dest_scheme)))
+}
+
+/// Implementation of `dbcrossbar_schema_conv`, returning a `Result`.
+unsafe fn schema_conv(
+    schema_json: *const c_char,
+    dest_scheme: *const c_char,
+) -> Result<String> {
+    schema_conv_string(
+        str_arg(schema_json, "schema_json")?,
+        str_arg(dest_scheme, "dest_scheme")?,
+    )
+}
+
+/// Return the message for the last error that occurred on the current thread,
+/// or `NULL` if no error has occurred.
+///
+/// The returned string belongs to this library, and it remains valid until the
+/// next call to a `dbcrossbar_*` function on the same thread. Do not free it.
+#[no_mangle]
+pub extern "C" fn dbcrossbar_last_error() -> *const c_char {
+    panic::catch_unwind(|| {
+        LAST_ERROR.with(|last_error| match &*last_error.borrow() {
+            Some(msg) => msg.as_ptr(),
+            None => ptr::null(),
+        })
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// Free a string returned by this library. Passing `NULL` is allowed.
+///
+/// # Safety
+///
+/// `s` must be `NULL`, or a string returned by this library which has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn dbcrossbar_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // There's nothing useful we can do if this panics, but we must not
+        // unwind into C.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(CString::from_raw(s))));
+    }
+}
+
+/// Look up the schema of `locator`, and return it in `dbcrossbar-schema`
+/// JSON format.
+///
+/// This is the safe Rust version of `dbcrossbar_schema_json`, for use by other
+/// language bindings.
+pub fn schema_json_string(locator: &str) -> Result<String> {
+    let locator = locator.to_owned();
+    let dest_scheme = "dbcrossbar-schema:".to_owned();
+    run_with_context(move |ctx| convert_schema(ctx, locator, dest_scheme))
+}
+
+/// Convert `schema_json` into the format used by the schema driver
+/// `dest_scheme`.
+///
+/// This is the safe Rust version of `dbcrossbar_schema_conv`, for use by other
+/// language bindings.
+pub fn schema_conv_string(schema_json: &str, dest_scheme: &str) -> Result<String> {
+    // Our drivers only know how to read schemas from files, so write
+    // `schema_json` to a temporary file.
+    let input = TempFile::new("json");
+    fs::write(&input.path, schema_json)
+        .with_context(|_| format!("could not write {}", input.path.display()))?;
+    let locator = format!("dbcrossbar-schema:{}", input.path.display());
+    let dest_scheme = dest_scheme.to_owned();
+    run_with_context(move |ctx| convert_schema(ctx, locator, dest_scheme))
+}
+
+/// Read the schema at `locator`, and write it using the schema driver
+/// `dest_scheme`, returning the output as a string.
+async fn convert_schema(
+    ctx: Context,
+    locator: String,
+    dest_scheme: String,
+) -> Result<String> {
+    if !dest_scheme.ends_with(':') {
+        return Err(format_err!(
+            "expected a scheme like \"postgres-sql:\", found {:?}",
+            dest_scheme,
+        ));
+    }
+
+    // Read our schema.
+    let locator = locator.parse::<UnparsedLocator>()?.parse(false)?;
+    let schema = locator
+        .schema(ctx.clone())
+        .await
+        .with_context(|_| format!("error reading schema from {}", locator))?
+        .ok_or_else(|| {
+            format_err!("don't know how to read schema from {}", locator)
+        })?;
+
+    // Write it to a temporary file and read it back.
+    let output = TempFile::new("out");
+    let dest = format!("{}{}", dest_scheme, output.path.display())
+        .parse::<UnparsedLocator>()?
+        .parse(false)?;
+    let dest_args =
+        DestinationArguments::new(DriverArguments::default(), IfExists::Overwrite);
+    dest.write_schema(ctx, schema, dest_args)
+        .await
+        .with_context(|_| format!("error writing schema to {}", dest))?;
+    Ok(fs::read_to_string(&output.path)
+        .with_context(|_| format!("could not read {}", output.path.display()))?)
+}
+
+/// Run `f` using a new `Context` and `tokio` runtime, and return its output.
+///
+/// This must be called from synchronous code.
+pub fn run_with_context<T, F, Fut>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Context) -> Fut,
+    Fut: std::future::Future<Output = Result<T>> + Send + 'static,
+{
+    // We're embedded in somebody else's program, so don't log anything.
+    run_with_logger(Logger::root(Discard, o!()), f)
+}
+
+/// Like `run_with_context`, but send log messages to `log`. Language bindings
+/// can use this to report progress.
+pub fn run_with_logger<T, F, Fut>(log: Logger, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Context) -> Fut,
+    Fut: std::future::Future<Output = Result<T>> + Send + 'static,
+{
+    let (ctx, worker_fut) = Context::create(log);
+
+    let output = Arc::new(Mutex::new(None));
+    let cmd_output = output.clone();
+    let cmd_fut = f(ctx);
+    let cmd_fut: BoxFuture<()> = async move {
+        let result = cmd_fut.await?;
+        *cmd_output.lock().expect("lock poisoned") = Some(result);
+        Ok(())
+    }
+    .boxed();
+    run_futures_with_runtime(cmd_fut, worker_fut)?;
+
+    let result = output.lock().expect("lock poisoned").take();
+    result.ok_or_else(|| format_err!("operation produced no output"))
+}
+
+/// Convert a C string argument to a `&str`.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(format_err!("{} must not be NULL", name));
+    }
+    Ok(CStr::from_ptr(s)
+        .to_str()
+        .with_context(|_| format!("{} is not valid UTF-8", name))?)
+}
+
+/// Call `f`, converting any panic into an error, so that we never unwind
+/// across the C API boundary.
+fn catch_panic<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|msg| (*msg).to_owned())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_owned());
+        Err(format_err!("panic in dbcrossbar: {}", msg))
+    })
+}
+
+/// Convert `result` into a string that we can return to C, recording any
+/// error.
+fn string_result(result: Result<String>) -> *mut c_char {
+    let result = result.and_then(|s| Ok(CString::new(s)?));
+    match result {
+        Ok(s) => {
+            set_last_error(None);
+            s.into_raw()
+        }
+        Err(err) => {
+            set_last_error(Some(&err));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Record `err` as the last error on this thread.
+fn set_last_error(err: Option<&Error>) {
+    let msg = err.map(|err| {
+        // Error messages should never contain NUL, but don't panic if they do.
+        let msg = error_message(err).replace('\0', "\\0");
+        CString::new(msg).expect("NUL bytes were removed")
+    });
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = msg);
+}
+
+/// Format `err` and all its causes as a single line.
+pub fn error_message(err: &Error) -> String {
+    err.iter_chain()
+        .map(|cause| cause.to_string())
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// A temporary file, which will be deleted when dropped.
+struct TempFile {
+    /// The path to our file.
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Choose a new temporary file name ending in `ext`.
+    fn new(ext: &str) -> TempFile {
+        let name = format!("dbcrossbarffi-{}.{}", TemporaryStorage::random_tag(), ext);
+        TempFile {
+            path: env::temp_dir().join(name),
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[test]
+fn schema_conv_to_postgres_sql() {
+    let schema_json = CString::new(
+        r#"{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [{ "name": "id", "is_nullable": false, "data_type": "int64" }]
+  }]
+}"#,
+    )
+    .unwrap();
+    let dest_scheme = CString::new("postgres-sql:").unwrap();
+    unsafe {
+        let sql = dbcrossbar_schema_conv(schema_json.as_ptr(), dest_scheme.as_ptr());
+        assert!(!sql.is_null());
+        let sql_str = CStr::from_ptr(sql).to_str().unwrap().to_owned();
+        dbcrossbar_string_free(sql);
+        assert!(sql_str.contains("CREATE TABLE \"example\""));
+        assert!(sql_str.contains("\"id\" bigint NOT NULL"));
+    }
+}
+
+#[test]
+fn schema_json_from_postgres_sql() {
+    let input = TempFile::new("sql");
+    fs::write(&input.path, "CREATE TABLE example (id BIGINT NOT NULL);\n").unwrap();
+    let locator =
+        CString::new(format!("postgres-sql:{}", input.path.display())).unwrap();
+    unsafe {
+        let json = dbcrossbar_schema_json(locator.as_ptr());
+        assert!(!json.is_null());
+        let json_str = CStr::from_ptr(json).to_str().unwrap().to_owned();
+        dbcrossbar_string_free(json);
+        assert!(json_str.contains("\"name\": \"example\""));
+        assert!(json_str.contains("\"int64\""));
+        assert!(dbcrossbar_last_error().is_null());
+    }
+}
+
+#[test]
+fn schema_json_reports_bad_locators() {
+    let locator = CString::new("nosuchdriver:example").unwrap();
+    unsafe {
+        let json = dbcrossbar_schema_json(locator.as_ptr());
+        assert!(json.is_null());
+        let err = CStr::from_ptr(dbcrossbar_last_error()).to_str().unwrap();
+        assert!(err.contains("nosuchdriver:"));
+        dbcrossbar_string_free(json);
+    }
+}
+
+#[test]
+fn schema_conv_reports_bad_json() {
+    let schema_json = CString::new("{ not json").unwrap();
+    let dest_scheme = CString::new("postgres-sql:").unwrap();
+    unsafe {
+        let sql = dbcrossbar_schema_conv(schema_json.as_ptr(), dest_scheme.as_ptr());
+        assert!(sql.is_null());
+        assert!(!dbcrossbar_last_error().is_null());
+        dbcrossbar_string_free(sql);
+    }
+}
+
+#[test]
+fn panics_are_reported_as_errors() {
+    let result = catch_panic(|| -> Result<()> { panic!("oops") });
+    let err = result.unwrap_err();
+    assert_eq!(error_message(&err), "panic in dbcrossbar: oops");
+}
+
+#[test]
+fn errors_are_reported() {
+    let dest_scheme = CString::new("postgres-sql:").unwrap();
+    unsafe {
+        let sql = dbcrossbar_schema_conv(ptr::null(), dest_scheme.as_ptr());
+        assert!(sql.is_null());
+        let err = CStr::from_ptr(dbcrossbar_last_error()).to_str().unwrap();
+        assert_eq!(err, "schema_json must not be NULL");
+    }
+}
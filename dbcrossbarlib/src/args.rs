@@ -3,6 +3,7 @@
 use std::{fmt, marker::PhantomData};
 
 use crate::common::*;
+use crate::sampling::Sampling;
 use crate::separator::Separator;
 
 /// Trait used to add new methods to `EnumSet`.
@@ -128,6 +129,7 @@ impl SharedArguments<Verified> {
 pub enum SourceArgumentsFeatures {
     DriverArgs,
     WhereClause,
+    Query,
 }
 
 impl fmt::Display for DisplayEnumSet<SourceArgumentsFeatures> {
@@ -139,6 +141,9 @@ impl fmt::Display for DisplayEnumSet<SourceArgumentsFeatures> {
         if self.0.contains(SourceArgumentsFeatures::WhereClause) {
             write!(f, "{}--where=$SQL_EXPR", sep.display())?;
         }
+        if self.0.contains(SourceArgumentsFeatures::Query) {
+            write!(f, "{}--from-query=$SQL_FILE", sep.display())?;
+        }
         Ok(())
     }
 }
@@ -152,6 +157,13 @@ pub struct SourceArguments<ArgumentState> {
     /// A `WHERE` clause for this query.
     where_clause: Option<String>,
 
+    /// An SQL query to read data from, instead of reading a table.
+    query: Option<String>,
+
+    /// Which rows to read, if we don't want all of them. This is only set for
+    /// drivers where `Locator::supports_sampling` returns true.
+    sampling: Sampling,
+
     /// We need to include a reference to `ArgumentState` somewhere, so use a
     /// 0-byte phantom value.
     _phantom: PhantomData<ArgumentState>,
@@ -164,10 +176,24 @@ impl SourceArguments<Unverified> {
         Self {
             driver_args,
             where_clause,
+            query: None,
+            sampling: Sampling::default(),
             _phantom: PhantomData,
         }
     }
 
+    /// Read data using the SQL `query`, instead of reading the source table.
+    pub fn with_query(mut self, query: Option<String>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Only read the rows specified by `sampling`.
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
     /// Construct a new `SourceArguments` with typical values for a temporary
     /// storage location.
     pub fn for_temporary() -> Self {
@@ -185,7 +211,10 @@ impl SourceArguments<Unverified> {
             .contains(SourceArgumentsFeatures::DriverArgs)
             && !self.driver_args.is_empty()
         {
-            return Err(format_err!("this data source does not support --from-args"));
+            return Err(ErrorKind::DriverArguments {
+                message: "this data source does not support --from-args".to_owned(),
+            }
+            .into());
         }
         if !features
             .source_args
@@ -194,9 +223,20 @@ impl SourceArguments<Unverified> {
         {
             return Err(format_err!("this data source does not support --where"));
         }
+        if !features
+            .source_args
+            .contains(SourceArgumentsFeatures::Query)
+            && self.query.is_some()
+        {
+            return Err(format_err!(
+                "this data source does not support --from-query"
+            ));
+        }
         Ok(SourceArguments {
             driver_args: self.driver_args,
             where_clause: self.where_clause,
+            query: self.query,
+            sampling: self.sampling,
             _phantom: PhantomData,
         })
     }
@@ -213,12 +253,24 @@ impl SourceArguments<Verified> {
     pub fn where_clause(&self) -> Option<&str> {
         self.where_clause.as_ref().map(|s| &s[..])
     }
+
+    /// An SQL query to read data from, instead of reading the source table.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_ref().map(|s| &s[..])
+    }
+
+    /// Which rows to read, if we don't want all of them.
+    pub fn sampling(&self) -> &Sampling {
+        &self.sampling
+    }
 }
 
 /// What `DestinationArguments` features are supported by a given driver?
 #[derive(Debug, EnumSetType)]
 pub enum DestinationArgumentsFeatures {
     DriverArgs,
+    EvolveSchema,
+    CreateMissing,
 }
 
 impl fmt::Display for DisplayEnumSet<DestinationArgumentsFeatures> {
@@ -227,9 +279,16 @@ impl fmt::Display for DisplayEnumSet<DestinationArgumentsFeatures> {
         if self.0.contains(DestinationArgumentsFeatures::DriverArgs) {
             write!(f, "{}--to-arg=$NAME=$VALUE", sep.display())?;
         }
+        if self.0.contains(DestinationArgumentsFeatures::EvolveSchema) {
+            write!(f, "{}--evolve-schema", sep.display())?;
+        }
+        if self.0.contains(DestinationArgumentsFeatures::CreateMissing) {
+            write!(f, "{}--create-missing", sep.display())?;
+        }
         Ok(())
     }
 }
+
 /// Data destination arguments.
 #[derive(Clone, Debug, Default)]
 pub struct DestinationArguments<ArgumentState> {
@@ -239,6 +298,14 @@ pub struct DestinationArguments<ArgumentState> {
     /// What to do it the destination already exists.
     if_exists: IfExists,
 
+    /// Should we add columns which are missing from an existing destination
+    /// table?
+    evolve_schema: bool,
+
+    /// Should we create a missing schema, dataset or other container for the
+    /// destination table?
+    create_missing: bool,
+
     /// We need to include a reference to `ArgumentState` somewhere, so use a
     /// 0-byte phantom value.
     _phantom: PhantomData<ArgumentState>,
@@ -251,10 +318,26 @@ impl DestinationArguments<Unverified> {
         DestinationArguments {
             driver_args,
             if_exists,
+            evolve_schema: false,
+            create_missing: false,
             _phantom: PhantomData,
         }
     }
 
+    /// When appending to an existing table, add any columns that the table is
+    /// missing instead of failing.
+    pub fn with_evolve_schema(mut self, evolve_schema: bool) -> Self {
+        self.evolve_schema = evolve_schema;
+        self
+    }
+
+    /// Create the schema, dataset or other container for the destination table
+    /// if it doesn't exist yet, instead of failing.
+    pub fn with_create_missing(mut self, create_missing: bool) -> Self {
+        self.create_missing = create_missing;
+        self
+    }
+
     /// Construct a new `DestinationArguments` with typical values for a
     /// temporary storage location.
     pub fn for_temporary() -> Self {
@@ -271,18 +354,97 @@ impl DestinationArguments<Unverified> {
             .dest_args
             .contains(DestinationArgumentsFeatures::DriverArgs)
             && !self.driver_args.is_empty()
+        {
+            return Err(ErrorKind::DriverArguments {
+                message: "this data destination does not support --to-args".to_owned(),
+            }
+            .into());
+        }
+        if !features
+            .dest_args
+            .contains(DestinationArgumentsFeatures::EvolveSchema)
+            && self.evolve_schema
         {
             return Err(format_err!(
-                "this data destination does not support --to-args"
+                "this data destination does not support --evolve-schema"
+            ));
+        }
+        if !features
+            .dest_args
+            .contains(DestinationArgumentsFeatures::CreateMissing)
+            && self.create_missing
+        {
+            return Err(format_err!(
+                "this data destination does not support --create-missing"
             ));
         }
         self.if_exists.verify(features.dest_if_exists)?;
+        self.verify_evolve_schema()?;
+        Ok(DestinationArguments {
+            driver_args: self.driver_args,
+            if_exists: self.if_exists,
+            evolve_schema: self.evolve_schema,
+            create_missing: self.create_missing,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Like `verify`, but check against the features supported by
+    /// `write_schema` instead of the features supported by `write_local_data`.
+    pub fn verify_for_write_schema(
+        self,
+        features: Features,
+    ) -> Result<DestinationArguments<Verified>> {
+        if !features
+            .write_schema_args
+            .contains(DestinationArgumentsFeatures::DriverArgs)
+            && !self.driver_args.is_empty()
+        {
+            return Err(ErrorKind::DriverArguments {
+                message: "this schema destination does not support --to-args"
+                    .to_owned(),
+            }
+            .into());
+        }
+        if !features
+            .write_schema_args
+            .contains(DestinationArgumentsFeatures::EvolveSchema)
+            && self.evolve_schema
+        {
+            return Err(format_err!(
+                "this schema destination does not support --evolve-schema"
+            ));
+        }
+        if !features
+            .write_schema_args
+            .contains(DestinationArgumentsFeatures::CreateMissing)
+            && self.create_missing
+        {
+            return Err(format_err!(
+                "this schema destination does not support --create-missing"
+            ));
+        }
+        self.if_exists.verify(features.write_schema_if_exists)?;
+        self.verify_evolve_schema()?;
         Ok(DestinationArguments {
             driver_args: self.driver_args,
             if_exists: self.if_exists,
+            evolve_schema: self.evolve_schema,
+            create_missing: self.create_missing,
             _phantom: PhantomData,
         })
     }
+
+    /// We can only evolve the schema of a table we're adding rows to.
+    fn verify_evolve_schema(&self) -> Result<()> {
+        match &self.if_exists {
+            IfExists::Append | IfExists::Upsert(_) => Ok(()),
+            _ if self.evolve_schema => Err(format_err!(
+                "--evolve-schema requires --if-exists=append or --if-exists=upsert-on:..."
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 // These methods are only available in the `Verified` state.
@@ -296,4 +458,16 @@ impl DestinationArguments<Verified> {
     pub fn if_exists(&self) -> &IfExists {
         &self.if_exists
     }
+
+    /// Should we add columns which are missing from an existing destination
+    /// table?
+    pub fn evolve_schema(&self) -> bool {
+        self.evolve_schema
+    }
+
+    /// Should we create a missing schema, dataset or other container for the
+    /// destination table?
+    pub fn create_missing(&self) -> bool {
+        self.create_missing
+    }
 }
@@ -0,0 +1,183 @@
+//! Cleaning up after a cancelled run.
+//!
+//! When we're interrupted, we want to abort any half-finished uploads and
+//! jobs, and delete any temporary objects we created. Code which creates
+//! something that would need to be cleaned up registers a cleanup action using
+//! `Context::on_cancel`, and keeps the returned `CleanupGuard` until the thing
+//! is no longer in use. When a run is cancelled, call
+//! `Context::clean_up_after_cancel` before dropping the futures doing the
+//! work, and then wait for the cleanup to finish.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use crate::common::*;
+
+/// A cleanup action, which will be called with a `Context` if we're cancelled.
+pub(crate) type CleanupFn = Box<dyn FnOnce(Context) -> BoxFuture<()> + Send>;
+
+/// Cleanup actions which should be performed if we're cancelled.
+#[derive(Default)]
+pub(crate) struct CleanupRegistry {
+    /// Our registered actions, and descriptions for our logs, indexed by ID.
+    actions: Mutex<BTreeMap<u64, (String, CleanupFn)>>,
+    /// The next ID to assign.
+    next_id: AtomicU64,
+}
+
+impl CleanupRegistry {
+    /// Register `action` to be run if we're cancelled. The action will be
+    /// forgotten when the returned `CleanupGuard` is dropped.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        description: String,
+        action: CleanupFn,
+    ) -> CleanupGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.actions
+            .lock()
+            .expect("lock poisoned")
+            .insert(id, (description, action));
+        CleanupGuard {
+            registry: Arc::downgrade(self),
+            id,
+        }
+    }
+
+    /// Forget all our registered actions, and return a future which runs
+    /// them in parallel.
+    ///
+    /// We take the actions immediately, so that they won't be forgotten if
+    /// the code which registered them is then dropped.
+    pub(crate) fn run_all(&self, ctx: &Context) -> BoxFuture<()> {
+        let actions =
+            std::mem::take(&mut *self.actions.lock().expect("lock poisoned"));
+        let ctx = ctx.clone();
+        async move {
+            if actions.is_empty() {
+                return Ok(());
+            }
+            info!(
+                ctx.log(),
+                "cleaning up {} incomplete operations",
+                actions.len()
+            );
+            let mut cleanups = Vec::with_capacity(actions.len());
+            for (_, (description, action)) in actions {
+                let ctx = ctx.clone();
+                cleanups.push(
+                    async move {
+                        debug!(ctx.log(), "cleanup: {}", description);
+                        match action(ctx.clone()).await {
+                            Ok(()) => 0,
+                            Err(err) => {
+                                error!(
+                                    ctx.log(),
+                                    "could not {}: {}", description, err
+                                );
+                                1
+                            }
+                        }
+                    }
+                    .boxed(),
+                );
+            }
+            let failures = stream::iter(cleanups)
+                .buffer_unordered(10)
+                .collect::<Vec<usize>>()
+                .await
+                .into_iter()
+                .sum::<usize>();
+            if failures > 0 {
+                return Err(format_err!(
+                    "could not clean up {} incomplete operations",
+                    failures,
+                ));
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+impl fmt::Debug for CleanupRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actions = self.actions.lock().expect("lock poisoned");
+        f.debug_list()
+            .entries(actions.values().map(|(description, _)| description))
+            .finish()
+    }
+}
+
+/// Keeps a cleanup action registered until dropped.
+#[must_use = "the cleanup action is forgotten when the guard is dropped"]
+pub(crate) struct CleanupGuard {
+    /// The registry containing our action.
+    registry: Weak<CleanupRegistry>,
+    /// The ID of our action.
+    id: u64,
+}
+
+impl CleanupGuard {
+    /// Keep our action registered for the rest of the run. This is useful for
+    /// temporary files, which we may still be reading after the code that
+    /// created them has returned.
+    pub(crate) fn keep_until_exit(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            registry
+                .actions
+                .lock()
+                .expect("lock poisoned")
+                .remove(&self.id);
+        }
+    }
+}
+
+#[test]
+fn runs_only_registered_actions() {
+    use std::sync::atomic::AtomicUsize;
+
+    let (ctx, worker_fut) = Context::create_for_test("runs_only_registered_actions");
+    let registry = Arc::new(CleanupRegistry::default());
+    let count = Arc::new(AtomicUsize::new(0));
+    let counting_action = |count: Arc<AtomicUsize>| -> CleanupFn {
+        Box::new(move |_ctx| {
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            .boxed()
+        })
+    };
+
+    let kept = registry.register("kept".to_owned(), counting_action(count.clone()));
+    let dropped =
+        registry.register("dropped".to_owned(), counting_action(count.clone()));
+    drop(dropped);
+
+    let cmd_fut = async move {
+        // Dropping `kept` after we start cleaning up doesn't forget it.
+        let cleanup = registry.run_all(&ctx);
+        drop(kept);
+        cleanup.await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Actions only run once.
+        registry.run_all(&ctx).await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        Ok(())
+    };
+    run_futures_with_runtime(cmd_fut.boxed(), worker_fut).unwrap();
+}
@@ -14,9 +14,10 @@ pub(crate) struct AwsCredentials {
 }
 
 impl AwsCredentials {
-    /// Try to look up a default value for our AWS credentials.
-    pub(crate) async fn try_default() -> Result<AwsCredentials> {
-        let creds = CredentialsManager::singleton().get("aws").await?;
+    /// Look up the AWS credentials to use in `ctx`. These are normally our
+    /// default credentials, unless a locator chose others.
+    pub(crate) async fn for_context(ctx: &Context) -> Result<AwsCredentials> {
+        let creds = CredentialsManager::singleton().get_for(ctx, "aws").await?;
         let access_key_id = creds.get_required("access_key_id")?.to_owned();
         let secret_access_key = creds.get_required("secret_access_key")?.to_owned();
         let session_token = creds.get_optional("session_token").map(|t| t.to_owned());
@@ -3,6 +3,8 @@
 mod auth;
 pub(crate) mod s3;
 mod signing;
+mod sts;
 
 pub(crate) use auth::*;
 pub(crate) use signing::*;
+pub(crate) use sts::*;
@@ -0,0 +1,213 @@
+//! A minimal native REST client for S3.
+//!
+//! Most of our S3 support still uses the `aws` CLI, but we need to talk to S3
+//! directly to control how we upload files.
+//!
+//! Docs: https://docs.aws.amazon.com/AmazonS3/latest/API/Welcome.html
+
+use bytes::Bytes;
+use chrono::Utc;
+use reqwest::{Method, Response};
+
+use crate::clouds::aws::{
+    aws_canonical_query, aws_payload_hash, aws_percent_encode, sign_s3_request_v4,
+    AwsCredentials,
+};
+use crate::clouds::retry::{
+    is_temporary_reqwest_error, is_temporary_status, retry, Attempt, RetryPolicy,
+};
+use crate::common::*;
+use crate::credentials::CredentialsManager;
+
+/// Split an `s3://bucket/key` URL into a bucket and key.
+pub(crate) fn parse_s3_url(url: &Url) -> Result<(String, String)> {
+    if url.scheme() != "s3" {
+        return Err(format_err!("expected an s3:// URL, found {}", url));
+    }
+    let bucket = url
+        .host_str()
+        .ok_or_else(|| format_err!("could not get bucket from {}", url))?
+        .to_owned();
+    let key = percent_encoding::percent_decode_str(url.path())
+        .decode_utf8()
+        .with_context(|_| format!("object key is not UTF-8 in {}", url))?;
+    Ok((bucket, key.trim_start_matches('/').to_owned()))
+}
+
+#[test]
+fn parse_s3_urls() {
+    let url = "s3://bucket/dir/file%201.csv".parse::<Url>().unwrap();
+    let (bucket, key) = parse_s3_url(&url).unwrap();
+    assert_eq!(bucket, "bucket");
+    assert_eq!(key, "dir/file 1.csv");
+    let url = "gs://bucket/dir/".parse::<Url>().unwrap();
+    assert!(parse_s3_url(&url).is_err());
+}
+
+/// A client for S3, using the `aws` credentials for a `Context`.
+pub(crate) struct Client {
+    /// Our HTTP client.
+    client: reqwest::Client,
+    /// Our credentials.
+    credentials: AwsCredentials,
+    /// The region containing our buckets.
+    region: String,
+}
+
+impl Client {
+    /// Create a new client using the `aws` credentials for `ctx`.
+    pub(crate) async fn new(ctx: &Context) -> Result<Client> {
+        let credentials = AwsCredentials::for_context(ctx).await?;
+        let creds = CredentialsManager::singleton().get_for(ctx, "aws").await?;
+        let region = creds.get_required("default_region")?.to_owned();
+        Ok(Client {
+            client: reqwest::Client::new(),
+            credentials,
+            region,
+        })
+    }
+
+    /// Build the HTTPS URL for `key` in `bucket`, with the specified query
+    /// parameters.
+    pub(crate) fn url(
+        &self,
+        bucket: &str,
+        key: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Url> {
+        let path = key
+            .split('/')
+            .map(aws_percent_encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        // Virtual-hosted URLs don't work over HTTPS for bucket names containing
+        // dots, so fall back to path-style URLs for those.
+        let mut url = if bucket.contains('.') {
+            Url::parse(&format!(
+                "https://s3.{}.amazonaws.com/{}/{}",
+                self.region, bucket, path,
+            ))?
+        } else {
+            Url::parse(&format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                bucket, self.region, path,
+            ))?
+        };
+        if !query.is_empty() {
+            url.set_query(Some(&aws_canonical_query(query)));
+        }
+        Ok(url)
+    }
+
+    /// Sign and send a request, retrying temporary failures, and return an
+    /// error if it fails.
+    ///
+    /// Only use this for requests which are safe to repeat.
+    pub(crate) async fn send(
+        &self,
+        ctx: &Context,
+        method: Method,
+        url: Url,
+        headers: &[(&str, &str)],
+        body: Bytes,
+    ) -> Result<Response> {
+        let description = format!("{} {}", method, url);
+        retry(ctx, &RetryPolicy::default(), &description, || {
+            self.send_attempt(ctx, method.clone(), url.clone(), headers, body.clone())
+        })
+        .await
+    }
+
+    /// Sign and send a request exactly once, and return an error if it fails.
+    pub(crate) async fn send_once(
+        &self,
+        ctx: &Context,
+        method: Method,
+        url: Url,
+        headers: &[(&str, &str)],
+        body: Bytes,
+    ) -> Result<Response> {
+        match self.send_attempt(ctx, method, url, headers, body).await {
+            Attempt::Finished(resp) => Ok(resp),
+            Attempt::FailedTemporarily(err) | Attempt::FailedPermanently(err) => {
+                Err(err)
+            }
+        }
+    }
+
+    /// Sign and send a request, and classify any errors.
+    async fn send_attempt(
+        &self,
+        ctx: &Context,
+        method: Method,
+        url: Url,
+        headers: &[(&str, &str)],
+        body: Bytes,
+    ) -> Attempt<Response> {
+        trace!(ctx.log(), "{} {}", method, url);
+        let signed_headers = match sign_s3_request_v4(
+            &self.credentials,
+            &self.region,
+            method.as_str(),
+            &url,
+            headers,
+            &aws_payload_hash(&body),
+            Utc::now(),
+        ) {
+            Ok(signed_headers) => signed_headers,
+            Err(err) => return Attempt::FailedPermanently(err),
+        };
+        let mut req = self.client.request(method, url.clone());
+        for (k, v) in headers {
+            req = req.header(*k, *v);
+        }
+        for (k, v) in &signed_headers {
+            req = req.header(&k[..], &v[..]);
+        }
+        let resp = match req.body(body).send().await {
+            Ok(resp) => resp,
+            Err(err) => {
+                let temporary = is_temporary_reqwest_error(&err);
+                let err: Error = Error::from(err)
+                    .context(ErrorKind::Connection {
+                        target: url.to_string(),
+                    })
+                    .into();
+                return if temporary {
+                    Attempt::FailedTemporarily(err)
+                } else {
+                    Attempt::FailedPermanently(err)
+                };
+            }
+        };
+        let status = resp.status();
+        if status.is_success() {
+            Attempt::Finished(resp)
+        } else {
+            let body = resp.text().await.unwrap_or_default();
+            let err = format_err!("error accessing {}: {} {}", url, status, body);
+            if is_temporary_status(status) {
+                Attempt::FailedTemporarily(err)
+            } else {
+                Attempt::FailedPermanently(err)
+            }
+        }
+    }
+}
+
+/// Extract the text of the first `<tag>` element in `xml`. S3 responses are
+/// simple enough that we don't need a real XML parser for the values we use.
+pub(crate) fn xml_element_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let len = xml[start..].find(&close)?;
+    Some(&xml[start..start + len])
+}
+
+#[test]
+fn extracts_xml_element_text() {
+    let xml = r#"<?xml version="1.0"?><InitiateMultipartUploadResult><Bucket>b</Bucket><UploadId>abc.123</UploadId></InitiateMultipartUploadResult>"#;
+    assert_eq!(xml_element_text(xml, "UploadId"), Some("abc.123"));
+    assert_eq!(xml_element_text(xml, "Key"), None);
+}
@@ -13,9 +13,9 @@ pub(crate) async fn download_file(
     file_url: &Url,
 ) -> Result<BoxStream<BytesMut>> {
     debug!(ctx.log(), "streaming from {} using `aws s3 cp`", file_url);
-    let mut child = aws_s3_command()
+    let mut child = aws_s3_command(ctx)
         .await?
-        .args(&["cp", file_url.as_str(), "-"])
+        .args(["cp", file_url.as_str(), "-"])
         .stdout(Stdio::piped())
         .spawn()
         .context("error running `aws s3 cp`")?;
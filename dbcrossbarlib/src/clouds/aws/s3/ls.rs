@@ -16,9 +16,9 @@ pub(crate) async fn ls(
 ) -> Result<impl Stream<Item = Result<Url>> + Send + Unpin + 'static> {
     // Start a child process to list files at that URL.
     debug!(ctx.log(), "listing {}", url);
-    let mut child = aws_s3_command()
+    let mut child = aws_s3_command(ctx)
         .await?
-        .args(&["ls", "--recursive", url.as_str()])
+        .args(["ls", "--recursive", url.as_str()])
         .stdout(Stdio::piped())
         .spawn()
         .context("error running `aws s3 ls`")?;
@@ -1,31 +1,33 @@
-//! Wrappers for `aws` CLI commands.
+//! Wrappers for `aws` CLI commands, plus a native implementation of uploads.
 //!
-//! We'll probably replace these with a native implementation at some point.
+//! We'll probably replace the rest of these with a native implementation at
+//! some point.
 
 use tokio::process::Command;
 
 use crate::common::*;
 use crate::credentials::CredentialsManager;
 
+mod client;
 mod download_file;
 mod ls;
 mod rmdir;
 mod upload_file;
 
+pub(crate) use client::xml_element_text;
 pub(crate) use download_file::download_file;
 pub(crate) use ls::ls;
 pub(crate) use rmdir::rmdir;
-pub(crate) use upload_file::upload_file;
+pub(crate) use upload_file::{upload_file, UploadOptions};
 
 /// Create a new `tokio::process::Command` that invokes `aws s3` with the
-/// necessary `AWS` variables set.
+/// necessary `AWS` variables set for `ctx`.
 ///
-/// The plan is for this to someday take a `bucket` argument that looks up
-/// bucket-specific credentials, once [`CredentialsManager`] supports per-host
-/// credentials. For now, this basically exists to (try to) ensure that we're
-/// not relying on `aws`'s built-in authentication.
-pub(self) async fn aws_s3_command() -> Result<Command> {
-    let creds = CredentialsManager::singleton().get("aws").await?;
+/// This exists to (try to) ensure that we're not relying on `aws`'s built-in
+/// authentication, and so that we can use any credentials chosen by a
+/// locator's driver arguments.
+async fn aws_s3_command(ctx: &Context) -> Result<Command> {
+    let creds = CredentialsManager::singleton().get_for(ctx, "aws").await?;
 
     let mut command = Command::new("aws");
     command.env("AWS_ACCESS_KEY_ID", creds.get_required("access_key_id")?);
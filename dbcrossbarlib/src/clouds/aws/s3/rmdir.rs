@@ -15,9 +15,9 @@ pub(crate) async fn rmdir(ctx: &Context, url: &Url) -> Result<()> {
             url,
         ));
     }
-    let status = aws_s3_command()
+    let status = aws_s3_command(ctx)
         .await?
-        .args(&["rm", "--recursive", url.as_str()])
+        .args(["rm", "--recursive", url.as_str()])
         // Throw away stdout so it doesn't corrupt our output.
         .stdout(Stdio::null())
         .status()
@@ -1,42 +1,354 @@
 //! Upload files to S3.
 
-use std::process::Stdio;
+use bytes::{BufMut, Bytes};
+use reqwest::Method;
 
-use super::aws_s3_command;
+use super::client::{parse_s3_url, xml_element_text, Client};
 use crate::common::*;
-use crate::tokio_glue::copy_stream_to_writer;
 
-/// Upload `data` as a file at `url`.
-pub(crate) async fn upload_file<'a>(
-    ctx: &'a Context,
-    data: BoxStream<BytesMut>,
-    file_url: &'a Url,
+/// The smallest part size that S3 allows, except for the last part.
+pub(crate) const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// The largest part size that S3 allows.
+const MAX_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+
+/// The maximum number of parts that S3 allows in a single upload.
+const MAX_PARTS: usize = 10_000;
+
+/// How should we upload files to S3?
+#[derive(Clone, Debug)]
+pub(crate) struct UploadOptions {
+    /// The size of each part of a multipart upload, in bytes.
+    pub(crate) part_size: usize,
+    /// How many parts of each file should we upload at once?
+    pub(crate) concurrency: usize,
+}
+
+impl UploadOptions {
+    /// Make sure that S3 will accept these options.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.part_size < MIN_PART_SIZE || self.part_size > MAX_PART_SIZE {
+            return Err(format_err!(
+                "S3 part size must be between 5 MiB and 5 GiB, found {} bytes",
+                self.part_size,
+            ));
+        }
+        if self.concurrency == 0 {
+            return Err(format_err!("S3 upload concurrency must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        UploadOptions {
+            part_size: 16 * 1024 * 1024,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Upload `data` as a file at `url`, replacing any existing file.
+///
+/// If `data` fits in a single part, we upload it using a single `PUT`.
+/// Otherwise, we use a multipart upload, sending up to `options.concurrency`
+/// parts at once. If anything goes wrong, we abort the multipart upload, so
+/// that S3 doesn't keep (and charge us for) the parts we've already uploaded.
+pub(crate) async fn upload_file(
+    ctx: &Context,
+    mut data: BoxStream<BytesMut>,
+    file_url: &Url,
+    options: &UploadOptions,
 ) -> Result<()> {
-    // Run `aws cp - $URL` as a background process.
-    debug!(ctx.log(), "uploading stream to `aws s3`");
-    let mut child = aws_s3_command()
-        .await?
-        .args(&["cp", "-", file_url.as_str()])
-        .stdin(Stdio::piped())
-        // Throw away stdout so it doesn't corrupt our output.
-        .stdout(Stdio::null())
-        .spawn()
-        .context("error running `aws s3`")?;
-    let child_stdin = child.stdin.take().expect("child should have stdin");
-
-    // Copy data to our child process.
-    copy_stream_to_writer(ctx.clone(), data, child_stdin)
+    debug!(ctx.log(), "streaming to {}", file_url);
+    let (ctx, mut span) = ctx.span("upload_file");
+    span.set_attribute("url", file_url);
+    let ctx = &ctx;
+    let (bucket, key) = parse_s3_url(file_url)?;
+    let client = Client::new(ctx).await?;
+
+    // If all our data fits in one part, upload it directly.
+    let mut buffer = BytesMut::with_capacity(options.part_size);
+    let (first_part, at_end) = read_part(&mut data, &mut buffer, options.part_size)
         .await
-        .context("error copying data to `aws s3`")?;
+        .with_context(|_| format!("error reading data for {}", file_url))?;
+    if at_end {
+        let size = first_part.len();
+        let url = client.url(&bucket, &key, &[])?;
+        client
+            .send(
+                ctx,
+                Method::PUT,
+                url,
+                &[("content-type", "text/csv")],
+                first_part,
+            )
+            .await
+            .with_context(|_| format!("error uploading to {}", file_url))?;
+        info!(ctx.log(), "uploaded {} bytes to {}", size, file_url);
+        return Ok(());
+    }
 
-    // Wait for `aws s3` to finish.
-    let status = child
-        .wait()
+    // Otherwise, start a multipart upload.
+    let url = client.url(&bucket, &key, &[("uploads", "")])?;
+    // This isn't safe to retry, because each request starts a new upload.
+    let resp = client
+        .send_once(
+            ctx,
+            Method::POST,
+            url,
+            &[("content-type", "text/csv")],
+            Bytes::new(),
+        )
         .await
-        .with_context(|_| format!("error finishing upload to {}", file_url))?;
-    if status.success() {
+        .with_context(|_| format!("error starting upload to {}", file_url))?;
+    let resp_body = resp.text().await?;
+    let upload_id = xml_element_text(&resp_body, "UploadId")
+        .ok_or_else(|| format_err!("no UploadId in S3 response: {}", resp_body))?
+        .to_owned();
+    trace!(ctx.log(), "started multipart upload {}", upload_id);
+
+    // If we're cancelled, abort our upload, too.
+    let _abort_guard = {
+        let bucket = bucket.clone();
+        let key = key.clone();
+        let upload_id = upload_id.clone();
+        ctx.on_cancel(
+            format!("abort multipart upload to {}", file_url),
+            move |ctx| async move {
+                let client = Client::new(&ctx).await?;
+                let uploader = MultipartUpload {
+                    client: &client,
+                    bucket: &bucket,
+                    key: &key,
+                    upload_id: &upload_id,
+                };
+                uploader.abort(&ctx).await
+            },
+        )
+    };
+
+    let uploader = MultipartUpload {
+        client: &client,
+        bucket: &bucket,
+        key: &key,
+        upload_id: &upload_id,
+    };
+    let result = uploader
+        .upload(ctx, first_part, data, buffer, options)
+        .await;
+    match result {
+        Ok(size) => {
+            info!(ctx.log(), "uploaded {} bytes to {}", size, file_url);
+            Ok(())
+        }
+        Err(err) => {
+            warn!(
+                ctx.log(),
+                "aborting multipart upload to {}: {}", file_url, err,
+            );
+            if let Err(abort_err) = uploader.abort(ctx).await {
+                error!(
+                    ctx.log(),
+                    "could not abort multipart upload {} to {}: {}",
+                    upload_id,
+                    file_url,
+                    abort_err,
+                );
+            }
+            Err(err
+                .context(format!("error uploading to {}", file_url))
+                .into())
+        }
+    }
+}
+
+/// Read the next part from `data`, using `buffer` to hold any leftover data.
+/// Returns the part, and whether we've reached the end of `data`.
+async fn read_part(
+    data: &mut BoxStream<BytesMut>,
+    buffer: &mut BytesMut,
+    part_size: usize,
+) -> Result<(Bytes, bool)> {
+    while buffer.len() < part_size {
+        match data.next().await.transpose()? {
+            Some(chunk) => buffer.put(chunk),
+            None => return Ok((buffer.split().freeze(), true)),
+        }
+    }
+    Ok((buffer.split_to(part_size).freeze(), false))
+}
+
+/// The state we need to split our input into numbered parts.
+struct PartState {
+    data: BoxStream<BytesMut>,
+    buffer: BytesMut,
+    /// A part we've already read, but not yet returned.
+    pending: Option<Bytes>,
+    at_end: bool,
+    part_number: usize,
+    part_size: usize,
+}
+
+/// Read the next numbered part, for use with `stream::try_unfold`.
+///
+/// This is a named function instead of a closure, because closures returning
+/// `async` blocks which borrow their arguments confuse the `Send` checks on
+/// our boxed futures.
+async fn next_part(
+    mut state: PartState,
+) -> Result<Option<((usize, Bytes), PartState)>> {
+    let part = match state.pending.take() {
+        Some(part) => part,
+        None if state.at_end => return Ok(None),
+        None => {
+            let (part, at_end) =
+                read_part(&mut state.data, &mut state.buffer, state.part_size).await?;
+            state.at_end = at_end;
+            if part.is_empty() {
+                return Ok(None);
+            }
+            part
+        }
+    };
+    state.part_number += 1;
+    if state.part_number > MAX_PARTS {
+        return Err(format_err!(
+            "file needs more than {} parts of {} bytes",
+            MAX_PARTS,
+            state.part_size,
+        ));
+    }
+    Ok(Some(((state.part_number, part), state)))
+}
+
+/// A multipart upload in progress.
+struct MultipartUpload<'a> {
+    client: &'a Client,
+    bucket: &'a str,
+    key: &'a str,
+    upload_id: &'a str,
+}
+
+impl<'a> MultipartUpload<'a> {
+    /// Upload `first_part`, followed by the rest of `data`, and complete the
+    /// upload. Returns the number of bytes uploaded.
+    async fn upload(
+        &self,
+        ctx: &Context,
+        first_part: Bytes,
+        data: BoxStream<BytesMut>,
+        buffer: BytesMut,
+        options: &UploadOptions,
+    ) -> Result<usize> {
+        let state = PartState {
+            data,
+            buffer,
+            pending: Some(first_part),
+            at_end: false,
+            part_number: 0,
+            part_size: options.part_size,
+        };
+        let parts = stream::try_unfold(state, next_part);
+
+        // Upload our parts in parallel.
+        let mut etags = parts
+            .map_ok(|(part_number, part)| self.upload_part(ctx, part_number, part))
+            .try_buffer_unordered(options.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        etags.sort_by_key(|(part_number, _, _)| *part_number);
+        let size = etags.iter().map(|(_, _, len)| len).sum();
+
+        // Complete our upload.
+        let mut xml = "<CompleteMultipartUpload>".to_owned();
+        for (part_number, etag, _) in &etags {
+            xml.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                part_number, etag,
+            ));
+        }
+        xml.push_str("</CompleteMultipartUpload>");
+        let url =
+            self.client
+                .url(self.bucket, self.key, &[("uploadId", self.upload_id)])?;
+        let resp = self
+            .client
+            .send(
+                ctx,
+                Method::POST,
+                url,
+                &[("content-type", "application/xml")],
+                Bytes::from(xml),
+            )
+            .await?;
+        // S3 may report an error in the body of a successful response.
+        let resp_body = resp.text().await?;
+        if resp_body.contains("<Error>") {
+            return Err(format_err!(
+                "could not complete multipart upload: {}",
+                resp_body,
+            ));
+        }
+        Ok(size)
+    }
+
+    /// Upload a single part. Returns the part number, its ETag and its size.
+    async fn upload_part(
+        &self,
+        ctx: &Context,
+        part_number: usize,
+        part: Bytes,
+    ) -> Result<(usize, String, usize)> {
+        let len = part.len();
+        let part_number_str = part_number.to_string();
+        let url = self.client.url(
+            self.bucket,
+            self.key,
+            &[
+                ("partNumber", &part_number_str),
+                ("uploadId", self.upload_id),
+            ],
+        )?;
+        let resp = self
+            .client
+            .send(ctx, Method::PUT, url, &[], part)
+            .await
+            .with_context(|_| format!("error uploading part {}", part_number))?;
+        let etag = resp
+            .headers()
+            .get("etag")
+            .and_then(|etag| etag.to_str().ok())
+            .ok_or_else(|| format_err!("no ETag for part {}", part_number))?
+            .to_owned();
+        trace!(ctx.log(), "uploaded part {} ({} bytes)", part_number, len);
+        Ok((part_number, etag, len))
+    }
+
+    /// Abort this upload, and delete any parts we've uploaded.
+    async fn abort(&self, ctx: &Context) -> Result<()> {
+        let url =
+            self.client
+                .url(self.bucket, self.key, &[("uploadId", self.upload_id)])?;
+        self.client
+            .send(ctx, Method::DELETE, url, &[], Bytes::new())
+            .await?;
         Ok(())
-    } else {
-        Err(format_err!("`aws s3` returned error: {}", status))
     }
 }
+
+#[test]
+fn validates_upload_options() {
+    assert!(UploadOptions::default().validate().is_ok());
+    let too_small = UploadOptions {
+        part_size: 1024,
+        ..UploadOptions::default()
+    };
+    assert!(too_small.validate().is_err());
+    let no_concurrency = UploadOptions {
+        concurrency: 0,
+        ..UploadOptions::default()
+    };
+    assert!(no_concurrency.validate().is_err());
+}
@@ -0,0 +1,109 @@
+//! Assuming IAM roles using AWS STS.
+//!
+//! Docs: https://docs.aws.amazon.com/STS/latest/APIReference/API_AssumeRole.html
+
+use chrono::{DateTime, Utc};
+
+use super::s3::xml_element_text;
+use super::{
+    aws_canonical_query, aws_payload_hash, sign_request_v4, AwsCredentials,
+    SigningScope,
+};
+use crate::common::*;
+
+/// Temporary credentials for an assumed role.
+pub(crate) struct AssumedRole {
+    /// Our temporary credentials.
+    pub(crate) credentials: AwsCredentials,
+    /// When our credentials expire.
+    pub(crate) expiration: DateTime<Utc>,
+}
+
+/// Use `base` credentials to assume the IAM role `role_arn`.
+pub(crate) async fn assume_role(
+    base: &AwsCredentials,
+    role_arn: &str,
+) -> Result<AssumedRole> {
+    // The global STS endpoint is signed as `us-east-1`.
+    let query = aws_canonical_query(&[
+        ("Action", "AssumeRole"),
+        ("DurationSeconds", "3600"),
+        ("RoleArn", role_arn),
+        ("RoleSessionName", "dbcrossbar"),
+        ("Version", "2011-06-15"),
+    ]);
+    let url = Url::parse(&format!("https://sts.amazonaws.com/?{}", query))?;
+    let scope = SigningScope {
+        region: "us-east-1",
+        service: "sts",
+    };
+    let signed_headers = sign_request_v4(
+        base,
+        &scope,
+        "GET",
+        &url,
+        &[],
+        &aws_payload_hash(b""),
+        Utc::now(),
+    )?;
+    let mut req = reqwest::Client::new().get(url);
+    for (k, v) in &signed_headers {
+        req = req.header(&k[..], &v[..]);
+    }
+    let resp = req.send().await.context(ErrorKind::Connection {
+        target: "https://sts.amazonaws.com/".to_owned(),
+    })?;
+    let status = resp.status();
+    let body = resp.text().await?;
+    if !status.is_success() {
+        let message = xml_element_text(&body, "Message").unwrap_or(&body);
+        return Err(format_err!(
+            "could not assume role {}: {} {}",
+            role_arn,
+            status,
+            message,
+        ));
+    }
+    Ok(parse_assume_role_response(&body)
+        .with_context(|_| format!("could not assume role {}", role_arn))?)
+}
+
+/// Parse the XML returned by `AssumeRole`.
+fn parse_assume_role_response(xml: &str) -> Result<AssumedRole> {
+    let element = |tag: &str| -> Result<String> {
+        xml_element_text(xml, tag)
+            .map(|text| text.to_owned())
+            .ok_or_else(|| format_err!("no {} in STS response", tag))
+    };
+    let expiration = DateTime::parse_from_rfc3339(&element("Expiration")?)
+        .context("could not parse STS expiration time")?
+        .with_timezone(&Utc);
+    Ok(AssumedRole {
+        credentials: AwsCredentials {
+            access_key_id: element("AccessKeyId")?,
+            secret_access_key: element("SecretAccessKey")?,
+            session_token: Some(element("SessionToken")?),
+        },
+        expiration,
+    })
+}
+
+#[test]
+fn parses_assume_role_responses() {
+    let xml = r#"<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+  <AssumeRoleResult>
+    <Credentials>
+      <AccessKeyId>ASIAEXAMPLE</AccessKeyId>
+      <SecretAccessKey>secret</SecretAccessKey>
+      <SessionToken>token</SessionToken>
+      <Expiration>2021-03-01T12:00:00Z</Expiration>
+    </Credentials>
+  </AssumeRoleResult>
+</AssumeRoleResponse>"#;
+    let role = parse_assume_role_response(xml).unwrap();
+    assert_eq!(role.credentials.access_key_id, "ASIAEXAMPLE");
+    assert_eq!(role.credentials.secret_access_key, "secret");
+    assert_eq!(role.credentials.session_token.as_deref(), Some("token"));
+    assert_eq!(role.expiration.to_rfc3339(), "2021-03-01T12:00:00+00:00");
+    assert!(parse_assume_role_response("<Error/>").is_err());
+}
@@ -0,0 +1,23 @@
+//! Downloading blobs.
+
+use reqwest::Method;
+
+use super::{parse_azure_url, Client};
+use crate::common::*;
+use crate::tokio_glue::http_response_stream;
+
+/// Download the blob at the specified `azure://` URL as a stream.
+pub(crate) async fn download_file(
+    ctx: &Context,
+    file_url: &Url,
+) -> Result<BoxStream<BytesMut>> {
+    debug!(ctx.log(), "streaming from {}", file_url);
+    let (container, blob) = parse_azure_url(file_url)?;
+    let client = Client::new().await?;
+    let url = client.url(&container, &blob, "")?;
+    let resp = client
+        .send(ctx, client.request(Method::GET, url))
+        .await
+        .with_context(|_| format!("error downloading {}", file_url))?;
+    Ok(http_response_stream(resp))
+}
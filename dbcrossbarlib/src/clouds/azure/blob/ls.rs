@@ -0,0 +1,129 @@
+//! Listing blobs.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Method;
+
+use super::{parse_azure_url, Client};
+use crate::common::*;
+
+/// A page of listing results.
+struct ListPage {
+    /// The names of the blobs on this page.
+    names: Vec<String>,
+    /// A marker for fetching the next page, if there is one.
+    next_marker: Option<String>,
+}
+
+/// Parse the XML returned by the "List Blobs" API.
+///
+/// The response format is simple and stable, so we extract the fields we need
+/// with regular expressions instead of depending on a full XML parser.
+fn parse_list_page(xml: &str) -> Result<ListPage> {
+    lazy_static! {
+        static ref NAME_RE: Regex = Regex::new(r"(?s)<Blob>.*?<Name>(.*?)</Name>")
+            .expect("invalid `NAME_RE` in source");
+        static ref NEXT_MARKER_RE: Regex =
+            Regex::new(r"(?s)<NextMarker>(.*?)</NextMarker>")
+                .expect("invalid `NEXT_MARKER_RE` in source");
+    }
+    if !xml.contains("<EnumerationResults") {
+        return Err(format_err!("unexpected response from Azure: {}", xml));
+    }
+    let names = NAME_RE
+        .captures_iter(xml)
+        .map(|cap| xml_unescape(&cap[1]))
+        .collect();
+    let next_marker = NEXT_MARKER_RE
+        .captures(xml)
+        .map(|cap| xml_unescape(&cap[1]))
+        .filter(|marker| !marker.is_empty());
+    Ok(ListPage { names, next_marker })
+}
+
+/// Replace the standard XML entities in `s`.
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[test]
+fn parse_list_pages() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://acct.blob.core.windows.net/" ContainerName="c">
+  <Prefix>dir/</Prefix>
+  <Blobs>
+    <Blob><Name>dir/a.csv</Name><Properties><Content-Length>10</Content-Length></Properties></Blob>
+    <Blob><Name>dir/b&amp;c.csv</Name><Properties><Content-Length>0</Content-Length></Properties></Blob>
+  </Blobs>
+  <NextMarker>abc</NextMarker>
+</EnumerationResults>"#;
+    let page = parse_list_page(xml).unwrap();
+    assert_eq!(page.names, vec!["dir/a.csv", "dir/b&c.csv"]);
+    assert_eq!(page.next_marker.as_deref(), Some("abc"));
+
+    let last = parse_list_page(
+        "<EnumerationResults><Blobs /><NextMarker /></EnumerationResults>",
+    )
+    .unwrap();
+    assert!(last.names.is_empty());
+    assert!(last.next_marker.is_none());
+}
+
+/// List all the blobs at the specified `azure://` URL, recursively.
+///
+/// If `url` names a single blob, we return just that blob. Otherwise, we
+/// treat "/" as a directory separator, as with `s3://` and `gs://` URLs.
+pub(crate) async fn ls(
+    ctx: &Context,
+    url: &Url,
+) -> Result<impl Stream<Item = Result<Url>> + Send + Unpin + 'static> {
+    debug!(ctx.log(), "listing {}", url);
+    let (container, path) = parse_azure_url(url)?;
+    let client = Client::new().await?;
+
+    let mut marker: Option<String> = None;
+    let mut urls = vec![];
+    loop {
+        // Build our query in its own scope, because `Serializer` isn't `Send`.
+        let query = {
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            query
+                .append_pair("restype", "container")
+                .append_pair("comp", "list")
+                .append_pair("prefix", &path);
+            if let Some(marker) = &marker {
+                query.append_pair("marker", marker);
+            }
+            query.finish()
+        };
+        let list_url = client.url(&container, "", &query)?;
+        let xml = client
+            .send(ctx, client.request(Method::GET, list_url))
+            .await
+            .with_context(|_| format!("error listing {}", url))?
+            .text()
+            .await?;
+        let page = parse_list_page(&xml)?;
+        for name in page.names {
+            // Only include exact matches and files in subdirectories.
+            if path.is_empty()
+                || path.ends_with('/')
+                || name == path
+                || name.starts_with(&format!("{}/", path))
+            {
+                let mut file_url = url.clone();
+                file_url.set_path(&format!("/{}", name));
+                urls.push(Ok(file_url));
+            }
+        }
+        marker = page.next_marker;
+        if marker.is_none() {
+            break;
+        }
+    }
+    Ok(stream::iter(urls))
+}
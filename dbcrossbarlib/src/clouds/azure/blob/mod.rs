@@ -0,0 +1,149 @@
+//! Native REST client for Azure Blob Storage.
+//!
+//! We authenticate using a shared access signature (SAS) token, which is
+//! appended to the query string of each request.
+//!
+//! Docs: https://docs.microsoft.com/en-us/rest/api/storageservices/blob-service-rest-api
+
+use reqwest::{RequestBuilder, Response};
+
+use crate::common::*;
+use crate::credentials::CredentialsManager;
+
+mod download_file;
+mod ls;
+mod rmdir;
+mod upload_file;
+
+pub(crate) use download_file::download_file;
+pub(crate) use ls::ls;
+pub(crate) use rmdir::rmdir;
+pub(crate) use upload_file::upload_file;
+
+/// The version of the REST API that we use.
+const API_VERSION: &str = "2020-04-08";
+
+/// Split an `azure://container/path` URL into a container and blob path.
+pub(crate) fn parse_azure_url(url: &Url) -> Result<(String, String)> {
+    if url.scheme() != "azure" {
+        return Err(format_err!("expected an azure:// URL, found {}", url));
+    }
+    let container = url
+        .host_str()
+        .ok_or_else(|| format_err!("could not get container from {}", url))?
+        .to_owned();
+    let path = percent_encoding::percent_decode_str(url.path())
+        .decode_utf8()
+        .with_context(|_| format!("blob path is not UTF-8 in {}", url))?;
+    Ok((container, path.trim_start_matches('/').to_owned()))
+}
+
+#[test]
+fn parse_azure_urls() {
+    let url = "azure://container/dir/file%201.csv".parse::<Url>().unwrap();
+    let (container, path) = parse_azure_url(&url).unwrap();
+    assert_eq!(container, "container");
+    assert_eq!(path, "dir/file 1.csv");
+    let url = "s3://bucket/dir/".parse::<Url>().unwrap();
+    assert!(parse_azure_url(&url).is_err());
+}
+
+/// A client for a single Azure storage account.
+pub(crate) struct Client {
+    /// Our HTTP client.
+    client: reqwest::Client,
+    /// The name of our storage account.
+    account: String,
+    /// Our SAS token, without a leading `?`.
+    sas_token: String,
+}
+
+impl Client {
+    /// Create a new client using the `azure_storage` credentials.
+    pub(crate) async fn new() -> Result<Client> {
+        let creds = CredentialsManager::singleton().get("azure_storage").await?;
+        let sas_token = creds
+            .get_required("sas_token")?
+            .trim_start_matches('?')
+            .to_owned();
+        let account = creds.get_required("account")?.to_owned();
+        Ok(Client {
+            client: reqwest::Client::new(),
+            account,
+            sas_token,
+        })
+    }
+
+    /// Our SAS token, for use by services which need to access our blobs.
+    pub(crate) fn sas_token(&self) -> &str {
+        &self.sas_token
+    }
+
+    /// Build the HTTPS URL for `container`, or for a blob in `container` if
+    /// `blob` is not empty, without any credentials.
+    pub(crate) fn https_url(&self, container: &str, blob: &str) -> Result<Url> {
+        let mut url =
+            Url::parse(&format!("https://{}.blob.core.windows.net/", self.account,))?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| format_err!("cannot build Azure URL"))?;
+            segments.clear().push(container);
+            if !blob.is_empty() {
+                segments.extend(blob.split('/'));
+            }
+        }
+        Ok(url)
+    }
+
+    /// Build the HTTPS URL for `container`, or for a blob in `container` if
+    /// `blob` is not empty. `query` should not include our SAS token.
+    pub(crate) fn url(&self, container: &str, blob: &str, query: &str) -> Result<Url> {
+        let mut url = self.https_url(container, blob)?;
+        if query.is_empty() {
+            url.set_query(Some(&self.sas_token));
+        } else {
+            url.set_query(Some(&format!("{}&{}", query, self.sas_token)));
+        }
+        Ok(url)
+    }
+
+    /// Start a request using `method`.
+    pub(crate) fn request(&self, method: reqwest::Method, url: Url) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .header("x-ms-version", API_VERSION)
+    }
+
+    /// Send `req`, and return an error if it fails.
+    pub(crate) async fn send(
+        &self,
+        ctx: &Context,
+        req: RequestBuilder,
+    ) -> Result<Response> {
+        let req = req.build()?;
+        // Don't log the query string, because it contains our SAS token.
+        let mut log_url = req.url().clone();
+        log_url.set_query(None);
+        trace!(ctx.log(), "{} {}", req.method(), log_url);
+        let resp = self
+            .client
+            .execute(req)
+            .await
+            .context(ErrorKind::Connection {
+                target: log_url.to_string(),
+            })?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            Err(format_err!(
+                "error accessing {}: {} {}",
+                log_url,
+                status,
+                body,
+            ))
+        }
+    }
+}
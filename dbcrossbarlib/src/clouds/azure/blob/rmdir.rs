@@ -0,0 +1,37 @@
+//! Deleting blobs.
+
+use reqwest::Method;
+
+use super::{ls, parse_azure_url, Client};
+use crate::common::*;
+
+/// How many blobs should we delete at once?
+const PARALLEL_DELETES: usize = 10;
+
+/// Recursively delete an `azure://` directory without deleting the container.
+pub(crate) async fn rmdir(ctx: &Context, url: &Url) -> Result<()> {
+    debug!(ctx.log(), "deleting existing {}", url);
+    if !url.path().ends_with('/') {
+        return Err(format_err!(
+            "can only write to azure:// URL ending in '/', got {}",
+            url,
+        ));
+    }
+    let client = Client::new().await?;
+    let client = &client;
+    ls(ctx, url)
+        .await?
+        .map_ok(|file_url| async move {
+            let (container, blob) = parse_azure_url(&file_url)?;
+            let delete_url = client.url(&container, &blob, "")?;
+            client
+                .send(ctx, client.request(Method::DELETE, delete_url))
+                .await
+                .with_context(|_| format!("error deleting {}", file_url))?;
+            Ok(())
+        })
+        .try_buffer_unordered(PARALLEL_DELETES)
+        .try_collect::<Vec<()>>()
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Uploading blobs.
+
+use bytes::BufMut;
+use reqwest::Method;
+
+use super::{parse_azure_url, Client};
+use crate::common::*;
+
+/// How much data should we upload in each block? Azure allows up to 50,000
+/// blocks per blob, which gives us a limit of about 195 GiB.
+#[cfg(not(debug_assertions))]
+const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Use a tiny block size in debug mode, so that we exercise our block logic.
+#[cfg(debug_assertions)]
+const BLOCK_SIZE: usize = 128;
+
+/// Upload `data` to the specified `azure://` URL, replacing any existing blob.
+///
+/// We upload our data as a series of blocks, and then commit the list of
+/// blocks, which allows us to upload streams of unknown size.
+pub(crate) async fn upload_file(
+    ctx: &Context,
+    mut data: BoxStream<BytesMut>,
+    file_url: &Url,
+) -> Result<()> {
+    debug!(ctx.log(), "streaming to {}", file_url);
+    let (ctx, mut span) = ctx.span("upload_file");
+    span.set_attribute("url", file_url);
+    let ctx = &ctx;
+    let (container, blob) = parse_azure_url(file_url)?;
+    let client = Client::new().await?;
+
+    let mut block_ids = vec![];
+    let mut buffer = BytesMut::with_capacity(BLOCK_SIZE);
+    let mut total_size = 0;
+    loop {
+        let chunk = data.next().await.transpose()?;
+        let at_end = chunk.is_none();
+        if let Some(chunk) = chunk {
+            buffer.put(chunk);
+        }
+        while buffer.len() >= BLOCK_SIZE || (at_end && !buffer.is_empty()) {
+            let len = buffer.len().min(BLOCK_SIZE);
+            let block = buffer.split_to(len).freeze();
+            total_size += block.len();
+            let block_id = block_id(block_ids.len());
+            let query = format!(
+                "comp=block&blockid={}",
+                percent_encoding::utf8_percent_encode(
+                    &block_id,
+                    percent_encoding::NON_ALPHANUMERIC,
+                ),
+            );
+            let url = client.url(&container, &blob, &query)?;
+            client
+                .send(ctx, client.request(Method::PUT, url).body(block))
+                .await
+                .with_context(|_| format!("error uploading to {}", file_url))?;
+            block_ids.push(block_id);
+        }
+        if at_end {
+            break;
+        }
+    }
+
+    // Commit our blocks. This also works for empty blobs.
+    let mut xml = r#"<?xml version="1.0" encoding="utf-8"?><BlockList>"#.to_owned();
+    for block_id in &block_ids {
+        xml.push_str(&format!("<Latest>{}</Latest>", block_id));
+    }
+    xml.push_str("</BlockList>");
+    let url = client.url(&container, &blob, "comp=blocklist")?;
+    client
+        .send(
+            ctx,
+            client
+                .request(Method::PUT, url)
+                .header("Content-Type", "application/xml")
+                .header("x-ms-blob-content-type", "text/csv")
+                .body(xml),
+        )
+        .await
+        .with_context(|_| format!("error committing {}", file_url))?;
+    info!(ctx.log(), "uploaded {} bytes to {}", total_size, file_url);
+    Ok(())
+}
+
+/// Generate the ID of block number `idx`. All the block IDs in a blob must
+/// have the same length, so we pad them.
+fn block_id(idx: usize) -> String {
+    base64::encode(format!("block-{:08}", idx))
+}
+
+#[test]
+fn block_ids_have_same_length() {
+    assert_eq!(block_id(0).len(), block_id(49_999).len());
+    assert_ne!(block_id(0), block_id(1));
+}
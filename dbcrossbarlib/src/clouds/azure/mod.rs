@@ -0,0 +1,3 @@
+//! Interfaces to Microsoft Azure.
+
+pub(crate) mod blob;
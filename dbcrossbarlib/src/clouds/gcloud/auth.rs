@@ -70,17 +70,29 @@ async fn ensure_parent_directory(path: &Path) -> Result<()> {
 }
 
 /// Get the service account key needed to connect a server app to BigQuery.
-async fn service_account_key() -> Result<ServiceAccountKey> {
+async fn service_account_key(ctx: &Context) -> Result<ServiceAccountKey> {
     let creds = CredentialsManager::singleton()
-        .get("gcloud_service_account_key")
+        .get_for(ctx, "gcloud_service_account_key")
         .await?;
-    Ok(serde_json::from_str(creds.get_required("value")?)
-        .context("could not parse service account key")?)
+    let value = creds.get_required("value")?;
+
+    // Application default credentials may contain user credentials instead of
+    // a service account key, which `serde` would report confusingly.
+    let key_type = serde_json::from_str::<serde_json::Value>(value)
+        .ok()
+        .and_then(|json| json.get("type").and_then(|t| t.as_str()).map(str::to_owned));
+    if key_type.as_deref() == Some("authorized_user") {
+        return Err(format_err!(
+            "found user credentials instead of a service account key (we do not \
+             yet support `gcloud auth application-default login`)"
+        ));
+    }
+    Ok(serde_json::from_str(value).context("could not parse service account key")?)
 }
 
 /// Build an authenticator using service account credentials.
-async fn service_account_authenticator() -> Result<Authenticator> {
-    let service_account_key = service_account_key().await?;
+async fn service_account_authenticator(ctx: &Context) -> Result<Authenticator> {
+    let service_account_key = service_account_key(ctx).await?;
     // We're going to use the private key ID to indentify our stored token. As far
     // as I can tell, this is not especially sensitive information.
     let key_id = service_account_key.private_key_id.as_ref().ok_or_else(|| {
@@ -164,7 +176,15 @@ async fn installed_flow_authenticator() -> Result<Authenticator> {
 /// Create an authenticator using service account credentials if available, and
 /// interactive credentials otherwise.
 pub(crate) async fn authenticator(ctx: &Context) -> Result<Authenticator> {
-    match service_account_authenticator().await {
+    // If a locator chose a specific service account, don't fall back to
+    // anything else.
+    if ctx
+        .credentials_arguments()
+        .overrides("gcloud_service_account_key")
+    {
+        return service_account_authenticator(ctx).await;
+    }
+    match service_account_authenticator(ctx).await {
         // We have a service account configured, so use it.
         Ok(auth) => Ok(auth),
         Err(err) => {
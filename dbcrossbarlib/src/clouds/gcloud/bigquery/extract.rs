@@ -8,18 +8,32 @@ use super::{
 use crate::common::*;
 use crate::drivers::bigquery_shared::TableName;
 
+/// The maximum number of wildcard URIs that BigQuery allows in an extract job.
+pub(crate) const MAX_EXTRACT_FILES: usize = 500;
+
 /// Extract a table from BigQuery to Google Cloud Storage.
+///
+/// If `file_count` is greater than 1, we pass BigQuery one wildcard URI for
+/// each file. BigQuery will use a separate worker for each URI, which speeds up
+/// the extract and gives us more files to download in parallel.
 pub(crate) async fn extract(
     ctx: &Context,
     source_table: &TableName,
     dest_gs_url: &Url,
+    file_count: usize,
     labels: &Labels,
 ) -> Result<()> {
-    trace!(ctx.log(), "extract {} into {}", source_table, dest_gs_url);
+    trace!(
+        ctx.log(),
+        "extract {} into {} using {} file(s)",
+        source_table,
+        dest_gs_url,
+        file_count,
+    );
 
     // Configure our job.
     let config = JobConfigurationExtract {
-        destination_uris: vec![format!("{}/*.csv", dest_gs_url)],
+        destination_uris: extract_destination_uris(dest_gs_url, file_count)?,
         source_table: TableReference::from(source_table),
     };
 
@@ -34,3 +48,41 @@ pub(crate) async fn extract(
     .await?;
     Ok(())
 }
+
+/// The wildcard URIs to pass to an extract job which writes `file_count` files
+/// to `dest_gs_url`.
+fn extract_destination_uris(
+    dest_gs_url: &Url,
+    file_count: usize,
+) -> Result<Vec<String>> {
+    match file_count {
+        0 => Err(format_err!("cannot extract data into 0 files")),
+        1 => Ok(vec![format!("{}/*.csv", dest_gs_url)]),
+        n if n <= MAX_EXTRACT_FILES => Ok((0..n)
+            .map(|i| format!("{}/part{:03}-*.csv", dest_gs_url, i))
+            .collect()),
+        n => Err(format_err!(
+            "BigQuery can extract data into at most {} files, not {}",
+            MAX_EXTRACT_FILES,
+            n,
+        )),
+    }
+}
+
+#[test]
+fn builds_extract_destination_uris() {
+    let url = "gs://example/temp".parse::<Url>().unwrap();
+    assert_eq!(
+        extract_destination_uris(&url, 1).unwrap(),
+        vec!["gs://example/temp/*.csv"],
+    );
+    assert_eq!(
+        extract_destination_uris(&url, 2).unwrap(),
+        vec![
+            "gs://example/temp/part000-*.csv",
+            "gs://example/temp/part001-*.csv",
+        ],
+    );
+    assert!(extract_destination_uris(&url, 0).is_err());
+    assert!(extract_destination_uris(&url, MAX_EXTRACT_FILES + 1).is_err());
+}
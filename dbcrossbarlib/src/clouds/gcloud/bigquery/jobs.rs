@@ -3,8 +3,10 @@
 //! These use a number of closely-related types.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::{collections::HashMap, convert::TryFrom};
 use tokio::time::{sleep, Duration};
+use uuid::Uuid;
 
 use super::{
     super::{Client, NoQuery},
@@ -85,10 +87,9 @@ impl Job {
 
     /// Get the job ID, with the project and region prefixes stripped.
     pub(crate) fn reference(&self) -> Result<&JobReference> {
-        Ok(self
-            .job_reference
+        self.job_reference
             .as_ref()
-            .ok_or_else(|| format_err!("newly created job has no jobReference"))?)
+            .ok_or_else(|| format_err!("newly created job has no jobReference"))
     }
 
     /// Get a URL which can be used for this job.
@@ -112,7 +113,9 @@ pub(crate) struct JobReference {
     /// The bare ID, suitable for use in URL.
     pub(crate) job_id: String,
 
-    /// The location of this job.
+    /// The location of this job. We leave this empty when creating jobs,
+    /// and let BigQuery choose based on the datasets involved.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub(crate) location: String,
 }
 
@@ -181,11 +184,13 @@ impl JobConfigurationQuery {
 pub(crate) struct JobConfigurationLoad {
     pub(crate) source_uris: Vec<String>,
     pub(crate) schema: Option<TableSchema>,
+    pub(crate) source_format: Option<SourceFormat>,
     pub(crate) destination_table: TableReference,
     pub(crate) create_disposition: Option<CreateDisposition>,
     pub(crate) write_disposition: Option<WriteDisposition>,
     pub(crate) skip_leading_rows: Option<i32>,
     pub(crate) allow_quoted_newlines: Option<bool>,
+    pub(crate) schema_update_options: Option<Vec<SchemaUpdateOption>>,
 }
 
 /// Configuration for data extraction jobs.
@@ -257,6 +262,14 @@ impl From<&TableName> for TableReference {
     }
 }
 
+/// The format of the data files we're loading.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Serialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum SourceFormat {
+    Csv,
+    NewlineDelimitedJson,
+}
+
 /// Should this job create new tables?
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Serialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -292,6 +305,14 @@ impl TryFrom<&IfExists> for WriteDisposition {
     }
 }
 
+/// How may this job change the schema of an existing table?
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Serialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum SchemaUpdateOption {
+    /// Add new nullable columns.
+    AllowFieldAddition,
+}
+
 /// Run a BigQuery job.
 pub(crate) async fn run_job(
     ctx: &Context,
@@ -299,6 +320,8 @@ pub(crate) async fn run_job(
     project_id: &str,
     mut job: Job,
 ) -> Result<Job> {
+    let (ctx, mut span) = ctx.span("bigquery_job");
+    let ctx = &ctx;
     trace!(
         ctx.log(),
         "starting BigQuery job on {} {:?}",
@@ -306,18 +329,55 @@ pub(crate) async fn run_job(
         job,
     );
 
+    // Choose our own job ID, so that we can safely retry creating the job.
+    if job.job_reference.is_none() {
+        job.job_reference = Some(JobReference {
+            project_id: project_id.to_owned(),
+            job_id: format!("dbcrossbar_{}", Uuid::new_v4().to_simple()),
+            location: String::new(),
+        });
+    }
+    let job_id = job.reference()?.job_id.clone();
+    span.set_attribute("job_id", &job_id);
+
     // Create our job.
     let insert_url = format!(
         "https://bigquery.googleapis.com/bigquery/v2/projects/{}/jobs",
         project_id,
     );
-    job = client
-        .post::<Job, _, _, _>(ctx, &insert_url, NoQuery, job)
-        .await?;
+    job = match client
+        .post_idempotent::<Job, _, _, _>(ctx, &insert_url, NoQuery, job)
+        .await?
+    {
+        Some(job) => job,
+        None => {
+            // An earlier attempt created our job, so look it up.
+            let job_url = format!("{}/{}", insert_url, job_id);
+            client.get::<Job, _, _>(ctx, &job_url, NoQuery).await?
+        }
+    };
+    info!(ctx.log(), "started BigQuery job {}", job_id);
+    if let Some(summary) = ctx.summary() {
+        summary.record_job_id(&job_id);
+    }
 
     // Get the URL for polling the job.
     let job_url = job.url()?;
 
+    // If we're cancelled, cancel our job, too.
+    let mut cancel_url = job_url.clone();
+    cancel_url.set_path(&format!("{}/cancel", job_url.path()));
+    let _cancel_guard = ctx.on_cancel(
+        format!("cancel BigQuery job {}", job_id),
+        move |ctx| async move {
+            let client = Client::new(&ctx).await?;
+            client
+                .post::<Value, _, _, _>(&ctx, cancel_url.as_str(), NoQuery, json!({}))
+                .await?;
+            Ok(())
+        },
+    );
+
     // Check our current job status.
     let mut sleep_duration = Duration::from_secs(2);
     loop {
@@ -340,9 +400,16 @@ pub(crate) async fn run_job(
     }
 
     // Return either an error or a finished job.
-    job.status
+    info!(ctx.log(), "finished BigQuery job {}", job_id);
+    if let Err(err) = job
+        .status
         .as_ref()
         .expect("should have already checked for status")
-        .check_for_error()?;
+        .check_for_error()
+    {
+        let err: Error = err.into();
+        span.record_error(&err);
+        return Err(err);
+    }
     Ok(job)
 }
@@ -3,8 +3,8 @@
 use super::{
     super::Client,
     jobs::{
-        run_job, CreateDisposition, Job, JobConfigurationLoad, Labels, TableReference,
-        WriteDisposition,
+        run_job, CreateDisposition, Job, JobConfigurationLoad, Labels,
+        SchemaUpdateOption, SourceFormat, TableReference, WriteDisposition,
     },
     TableSchema,
 };
@@ -12,28 +12,23 @@ use crate::common::*;
 use crate::drivers::bigquery_shared::BqTable;
 use std::convert::TryFrom;
 
-/// Load data from `gs_url` into `dest_table`.
+/// Load data from `gs_url` into `dest_table`. The files at `gs_url` should be
+/// in `source_format`. If `evolve_schema` is true, add any columns which are
+/// missing from an existing `dest_table`.
 pub(crate) async fn load(
     ctx: &Context,
     gs_url: &Url,
+    source_format: SourceFormat,
     dest_table: &BqTable,
     if_exists: &IfExists,
+    evolve_schema: bool,
     labels: &Labels,
 ) -> Result<()> {
     trace!(ctx.log(), "loading {} into {}", gs_url, dest_table.name);
 
     // Configure our job.
-    let config = JobConfigurationLoad {
-        source_uris: vec![gs_url.to_string()],
-        schema: Some(TableSchema {
-            fields: dest_table.columns.clone(),
-        }),
-        destination_table: TableReference::from(&dest_table.name),
-        create_disposition: Some(CreateDisposition::CreateIfNeeded),
-        write_disposition: Some(WriteDisposition::try_from(if_exists)?),
-        skip_leading_rows: Some(1),
-        allow_quoted_newlines: Some(true),
-    };
+    let config =
+        load_job_config(gs_url, source_format, dest_table, if_exists, evolve_schema)?;
 
     // Run our job.
     let client = Client::new(ctx).await?;
@@ -46,3 +41,33 @@ pub(crate) async fn load(
     .await?;
     Ok(())
 }
+
+/// Build the configuration for a job which loads `gs_url` into `dest_table`.
+pub(crate) fn load_job_config(
+    gs_url: &Url,
+    source_format: SourceFormat,
+    dest_table: &BqTable,
+    if_exists: &IfExists,
+    evolve_schema: bool,
+) -> Result<JobConfigurationLoad> {
+    // These options only make sense for CSV files.
+    let is_csv = source_format == SourceFormat::Csv;
+    Ok(JobConfigurationLoad {
+        source_uris: vec![gs_url.to_string()],
+        source_format: Some(source_format),
+        schema: Some(TableSchema {
+            fields: dest_table.columns.clone(),
+        }),
+        destination_table: TableReference::from(&dest_table.name),
+        create_disposition: Some(CreateDisposition::CreateIfNeeded),
+        write_disposition: Some(WriteDisposition::try_from(if_exists)?),
+        skip_leading_rows: if is_csv { Some(1) } else { None },
+        allow_quoted_newlines: if is_csv { Some(true) } else { None },
+        // BigQuery will add new columns to the table as it loads our data.
+        schema_update_options: if evolve_schema {
+            Some(vec![SchemaUpdateOption::AllowFieldAddition])
+        } else {
+            None
+        },
+    })
+}
@@ -3,8 +3,9 @@
 use serde::{Deserialize, Serialize};
 use std::{error, fmt};
 
+use crate::cancellation::CleanupGuard;
 use crate::common::*;
-use crate::drivers::bigquery_shared::{BqColumn, TableName};
+use crate::drivers::bigquery_shared::{bq_string_literal, BqColumn, Ident, TableName};
 
 mod extract;
 pub(crate) mod jobs;
@@ -66,3 +67,68 @@ pub(crate) async fn drop_table(
     let sql = format!("DROP TABLE {};\n", table_name.dotted_and_quoted());
     execute_sql(ctx, table_name.project(), &sql, labels).await
 }
+
+/// Generate SQL to create the dataset containing `table_name`, if it doesn't
+/// already exist. If `location` is `None`, BigQuery uses its default location.
+pub(crate) fn create_dataset_sql(
+    table_name: &TableName,
+    location: Option<&str>,
+) -> Result<String> {
+    let mut sql = format!(
+        "CREATE SCHEMA IF NOT EXISTS {}.{}",
+        Ident(table_name.project()),
+        Ident(table_name.dataset()),
+    );
+    if let Some(location) = location {
+        sql.push_str(&format!(
+            " OPTIONS (location={})",
+            bq_string_literal(location)?
+        ));
+    }
+    Ok(sql)
+}
+
+#[test]
+fn create_dataset_sql_sets_location() {
+    let table_name = "project:dataset.table".parse::<TableName>().unwrap();
+    assert_eq!(
+        create_dataset_sql(&table_name, None).unwrap(),
+        "CREATE SCHEMA IF NOT EXISTS `project`.`dataset`",
+    );
+    assert_eq!(
+        create_dataset_sql(&table_name, Some("EU")).unwrap(),
+        r#"CREATE SCHEMA IF NOT EXISTS `project`.`dataset` OPTIONS (location="EU")"#,
+    );
+}
+
+/// Create the dataset containing `table_name`, if it doesn't already exist.
+pub(crate) async fn create_dataset_if_missing(
+    ctx: &Context,
+    table_name: &TableName,
+    location: Option<&str>,
+    labels: &Labels,
+) -> Result<()> {
+    debug!(
+        ctx.log(),
+        "creating dataset {}:{} if missing",
+        table_name.project(),
+        table_name.dataset(),
+    );
+    let sql = create_dataset_sql(table_name, location)?;
+    execute_sql(ctx, table_name.project(), &sql, labels).await
+}
+
+/// Drop the temporary table `table_name` if we're cancelled before the returned
+/// guard is dropped.
+pub(crate) fn drop_table_on_cancel(
+    ctx: &Context,
+    table_name: &TableName,
+    labels: &Labels,
+) -> CleanupGuard {
+    let table_name = table_name.to_owned();
+    let labels = labels.to_owned();
+    ctx.on_cancel(
+        format!("drop temporary table {}", table_name),
+        move |ctx| async move { drop_table(&ctx, &table_name, &labels).await },
+    )
+}
@@ -1,18 +1,20 @@
 //! A Google Cloud REST client.
 
-use bigml::wait::{wait, BackoffType, WaitOptions, WaitStatus};
 use failure::ResultExt;
 use mime::{self, Mime};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::{
     self,
     header::{HeaderMap, CONTENT_TYPE},
-    IntoUrl,
+    IntoUrl, StatusCode,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{error, fmt, time::Duration};
+use std::{error, fmt};
 
 use super::auth::{authenticator, AccessToken, Authenticator};
+use crate::clouds::retry::{
+    is_temporary_reqwest_error, is_temporary_status, retry, Attempt, RetryPolicy,
+};
 use crate::common::*;
 use crate::tokio_glue::IdiomaticBytesStream;
 
@@ -22,6 +24,7 @@ use crate::tokio_glue::IdiomaticBytesStream;
 static SCOPES: &[&str] = &[
     "https://www.googleapis.com/auth/devstorage.read_write",
     "https://www.googleapis.com/auth/bigquery",
+    "https://www.googleapis.com/auth/spreadsheets",
 ];
 
 /// An empty `GET` query.
@@ -70,13 +73,8 @@ impl Client {
     ) -> Result<reqwest::Response> {
         trace!(ctx.log(), "GET {}", url);
         let token = self.token().await?;
-        let wait_options = WaitOptions::default()
-            .backoff_type(BackoffType::Exponential)
-            .retry_interval(Duration::from_secs(10))
-            // Don't retry too much because we're probably classifying some
-            // permanent errors as temporary.
-            .allowed_errors(3);
-        wait(&wait_options, move || {
+        let description = format!("GET {}", url);
+        retry(ctx, &RetryPolicy::default(), &description, move || {
             let token = token.clone();
             let headers = headers.clone();
             async move {
@@ -91,30 +89,26 @@ impl Client {
                     // The HTTP request failed outright, because of something
                     // like a DNS error or whatever.
                     Err(err) => {
-                        // Request and timeout errors look like the kind of
-                        // things we should probably retry. But this is based on
-                        // guesswork not experience.
-                        let temporary = err.is_request() || err.is_timeout();
+                        let temporary = is_temporary_reqwest_error(&err);
                         let err: Error = err.into();
                         let err = err.context(format!("could not GET {}", url));
                         if temporary {
-                            WaitStatus::FailedTemporarily(err.into())
+                            Attempt::FailedTemporarily(err.into())
                         } else {
-                            WaitStatus::FailedPermanently(err.into())
+                            Attempt::FailedPermanently(err.into())
                         }
                     }
                     // We talked to the server and it returned a server-side
-                    // error (50-599). There's a chance that things might work
-                    // next time, we hope.
-                    Ok(resp) if resp.status().is_server_error() => {
-                        WaitStatus::FailedTemporarily(
+                    // error (50-599), or asked us to slow down. There's a
+                    // chance that things might work next time, we hope.
+                    Ok(resp) if is_temporary_status(resp.status()) => {
+                        Attempt::FailedTemporarily(
                             self.handle_error(ctx, "GET", url, resp).await,
                         )
                     }
-                    Ok(resp) => WaitStatus::Finished(resp),
+                    Ok(resp) => Attempt::Finished(resp),
                 }
             }
-            .boxed()
         })
         .await
     }
@@ -189,6 +183,75 @@ impl Client {
         self.handle_response(ctx, "POST", &url, http_resp).await
     }
 
+    /// Make an HTTP POST request which is safe to repeat, retrying any
+    /// temporary failures.
+    ///
+    /// If a retried request fails with `409 Conflict`, we assume that an
+    /// earlier attempt succeeded even though we didn't see the response, and
+    /// return `None`.
+    pub(crate) async fn post_idempotent<Output, U, Query, Body>(
+        &self,
+        ctx: &Context,
+        url: U,
+        query: Query,
+        body: Body,
+    ) -> Result<Option<Output>>
+    where
+        Output: fmt::Debug + DeserializeOwned,
+        U: IntoUrl,
+        Query: fmt::Debug + Serialize,
+        Body: fmt::Debug + Serialize,
+    {
+        let url = build_url(url, query)?;
+        trace!(ctx.log(), "POST {} {:?}", url, body);
+        let token = self.token().await?;
+        let description = format!("POST {}", url);
+        let (url, body) = (&url, &body);
+        let mut attempts = 0;
+        let http_resp = retry(ctx, &RetryPolicy::default(), &description, move || {
+            let token = token.clone();
+            attempts += 1;
+            let is_retry = attempts > 1;
+            async move {
+                let resp_result = self
+                    .client
+                    .post(url.as_str())
+                    .bearer_auth(token.as_str())
+                    .json(body)
+                    .send()
+                    .await;
+                match resp_result {
+                    Err(err) => {
+                        let temporary = is_temporary_reqwest_error(&err);
+                        let err: Error = err.into();
+                        let err = err.context(format!("could not POST {}", url));
+                        if temporary {
+                            Attempt::FailedTemporarily(err.into())
+                        } else {
+                            Attempt::FailedPermanently(err.into())
+                        }
+                    }
+                    Ok(resp) if is_retry && resp.status() == StatusCode::CONFLICT => {
+                        Attempt::Finished(None)
+                    }
+                    Ok(resp) if is_temporary_status(resp.status()) => {
+                        Attempt::FailedTemporarily(
+                            self.handle_error(ctx, "POST", url, resp).await,
+                        )
+                    }
+                    Ok(resp) => Attempt::Finished(Some(resp)),
+                }
+            }
+        })
+        .await?;
+        match http_resp {
+            Some(http_resp) => Ok(Some(
+                self.handle_response(ctx, "POST", url, http_resp).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
     /// Post a stream of data to the specified URL.
     pub(crate) async fn post_stream<U, Query>(
         &self,
@@ -235,22 +298,50 @@ impl Client {
         let url = build_url(url, query)?;
         trace!(ctx.log(), "DELETE {}", url);
         let token = self.token().await?;
-        let http_resp = self
-            .client
-            .delete(url.as_str())
-            .bearer_auth(token.as_str())
-            .send()
-            .await
-            .with_context(|_| format!("error deleting {}", url))?;
-        if http_resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(format_err!(
-                "error deleting {}: {}",
-                url,
-                http_resp.status(),
-            ))
-        }
+        let description = format!("DELETE {}", url);
+        let url = &url;
+        let mut attempts = 0;
+        retry(ctx, &RetryPolicy::default(), &description, move || {
+            let token = token.clone();
+            attempts += 1;
+            let is_retry = attempts > 1;
+            async move {
+                let resp_result = self
+                    .client
+                    .delete(url.as_str())
+                    .bearer_auth(token.as_str())
+                    .send()
+                    .await;
+                match resp_result {
+                    Err(err) => {
+                        let temporary = is_temporary_reqwest_error(&err);
+                        let err: Error = err.into();
+                        let err = err.context(format!("error deleting {}", url));
+                        if temporary {
+                            Attempt::FailedTemporarily(err.into())
+                        } else {
+                            Attempt::FailedPermanently(err.into())
+                        }
+                    }
+                    Ok(resp) if resp.status().is_success() => Attempt::Finished(()),
+                    // If we're retrying, an earlier attempt may have already
+                    // deleted this.
+                    Ok(resp) if is_retry && resp.status() == StatusCode::NOT_FOUND => {
+                        Attempt::Finished(())
+                    }
+                    Ok(resp) => {
+                        let status = resp.status();
+                        let err = format_err!("error deleting {}: {}", url, status);
+                        if is_temporary_status(status) {
+                            Attempt::FailedTemporarily(err)
+                        } else {
+                            Attempt::FailedPermanently(err)
+                        }
+                    }
+                }
+            }
+        })
+        .await
     }
 
     /// Get an access token.
@@ -355,6 +446,7 @@ struct ErrorResponse {
 
 /// Information about a GCloud error.
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub(crate) struct GCloudError {
     pub(crate) code: i32,
     pub(crate) message: String,
@@ -372,6 +464,7 @@ impl error::Error for GCloudError {}
 /// Details about an individial GCloud error.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
 pub(crate) struct ErrorDetail {
     pub(crate) domain: String,
     pub(crate) reason: String,
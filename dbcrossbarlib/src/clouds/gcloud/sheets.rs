@@ -0,0 +1,168 @@
+//! Interfaces to Google Sheets.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{percent_encode, Client, NoQuery};
+use crate::common::*;
+
+/// A rectangular range of cells.
+///
+/// Docs: https://developers.google.com/sheets/api/reference/rest/v4/spreadsheets.values#ValueRange
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ValueRange {
+    /// The cells in this range, by row. Trailing empty rows and cells are
+    /// omitted.
+    #[serde(default)]
+    pub(crate) values: Vec<Vec<Value>>,
+}
+
+/// Metadata about a spreadsheet.
+#[derive(Debug, Deserialize)]
+struct Spreadsheet {
+    /// The individual sheets in this spreadsheet.
+    #[serde(default)]
+    sheets: Vec<Sheet>,
+}
+
+/// Metadata about an individual sheet.
+#[derive(Debug, Deserialize)]
+struct Sheet {
+    /// Properties of this sheet.
+    properties: SheetProperties,
+}
+
+/// Properties of an individual sheet.
+#[derive(Debug, Deserialize)]
+struct SheetProperties {
+    /// The name of this sheet.
+    title: String,
+}
+
+/// Query parameters for reading values.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetValuesQuery {
+    /// Return numbers and booleans as JSON values, not as formatted strings.
+    value_render_option: &'static str,
+    /// Return dates and times as formatted strings.
+    date_time_render_option: &'static str,
+}
+
+/// Query parameters for appending values.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppendValuesQuery {
+    /// How to interpret input values. We always use `"RAW"`, so that strings
+    /// like `=A1` are never treated as formulas.
+    value_input_option: &'static str,
+    /// Whether to insert new rows, or to overwrite existing rows.
+    insert_data_option: &'static str,
+}
+
+/// An empty request body.
+#[derive(Debug, Serialize)]
+struct Empty {}
+
+/// The URL for a range of values in a spreadsheet.
+fn values_url(spreadsheet_id: &str, sheet: &str) -> String {
+    // Sheet names are quoted using single quotes in A1 notation.
+    let range = format!("'{}'", sheet.replace('\'', "''"));
+    format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        percent_encode(spreadsheet_id),
+        percent_encode(&range),
+    )
+}
+
+/// Look up the name of the first sheet in a spreadsheet.
+pub(crate) async fn first_sheet_title(
+    ctx: &Context,
+    spreadsheet_id: &str,
+) -> Result<String> {
+    let client = Client::new(ctx).await?;
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=sheets.properties.title",
+        percent_encode(spreadsheet_id),
+    );
+    let spreadsheet = client.get::<Spreadsheet, _, _>(ctx, &url, NoQuery).await?;
+    spreadsheet
+        .sheets
+        .into_iter()
+        .next()
+        .map(|sheet| sheet.properties.title)
+        .ok_or_else(|| format_err!("spreadsheet {} has no sheets", spreadsheet_id))
+}
+
+/// Read all the values in `sheet`.
+pub(crate) async fn get_values(
+    ctx: &Context,
+    spreadsheet_id: &str,
+    sheet: &str,
+) -> Result<Vec<Vec<Value>>> {
+    debug!(
+        ctx.log(),
+        "reading sheet {:?} from {}", sheet, spreadsheet_id
+    );
+    let client = Client::new(ctx).await?;
+    let query = GetValuesQuery {
+        value_render_option: "UNFORMATTED_VALUE",
+        date_time_render_option: "FORMATTED_STRING",
+    };
+    let url = values_url(spreadsheet_id, sheet);
+    let range = client.get::<ValueRange, _, _>(ctx, &url, query).await?;
+    Ok(range.values)
+}
+
+/// Clear all the values in `sheet`.
+pub(crate) async fn clear_values(
+    ctx: &Context,
+    spreadsheet_id: &str,
+    sheet: &str,
+) -> Result<()> {
+    debug!(
+        ctx.log(),
+        "clearing sheet {:?} in {}", sheet, spreadsheet_id
+    );
+    let client = Client::new(ctx).await?;
+    let url = format!("{}:clear", values_url(spreadsheet_id, sheet));
+    client
+        .post::<Value, _, _, _>(ctx, &url, NoQuery, Empty {})
+        .await?;
+    Ok(())
+}
+
+/// Append `values` to `sheet`, after any existing data.
+pub(crate) async fn append_values(
+    ctx: &Context,
+    spreadsheet_id: &str,
+    sheet: &str,
+    values: Vec<Vec<Value>>,
+) -> Result<()> {
+    debug!(
+        ctx.log(),
+        "appending {} rows to sheet {:?} in {}",
+        values.len(),
+        sheet,
+        spreadsheet_id,
+    );
+    let client = Client::new(ctx).await?;
+    let query = AppendValuesQuery {
+        value_input_option: "RAW",
+        insert_data_option: "INSERT_ROWS",
+    };
+    let url = format!("{}:append", values_url(spreadsheet_id, sheet));
+    client
+        .post::<Value, _, _, _>(ctx, &url, query, ValueRange { values })
+        .await?;
+    Ok(())
+}
+
+#[test]
+fn values_url_quotes_sheet_names() {
+    assert_eq!(
+        values_url("abc", "Bob's data"),
+        "https://sheets.googleapis.com/v4/spreadsheets/abc/values/%27Bob%27%27s%20data%27",
+    );
+}
@@ -48,7 +48,7 @@ pub(crate) fn parse_gs_url(url: &Url) -> Result<(String, String)> {
             .host_str()
             .ok_or_else(|| format_err!("could not get bucket from {}", url))?
             .to_owned();
-        let object = (&url.path()[1..]).to_owned();
+        let object = url.path()[1..].to_owned();
         Ok((bucket, object))
     }
 }
@@ -72,6 +72,7 @@ pub(crate) struct StorageObject {
     #[serde(deserialize_with = "deserialize_int::<'_, i64, _>")]
     pub(crate) generation: i64,
     /// The generation number for this object's metadata.
+    #[allow(dead_code)]
     #[serde(deserialize_with = "deserialize_int::<'_, i64, _>")]
     pub(crate) metageneration: i64,
 }
@@ -35,6 +35,9 @@ pub(crate) async fn upload_file<'a>(
     file_url: &'a Url,
 ) -> Result<StorageObject> {
     debug!(ctx.log(), "streaming to {}", file_url);
+    let (ctx, mut span) = ctx.span("upload_file");
+    span.set_attribute("url", file_url);
+    let ctx = &ctx;
     let (bucket, object) = parse_gs_url(file_url)?;
 
     // Compute a running CRC32 sum.
@@ -50,13 +53,13 @@ pub(crate) async fn upload_file<'a>(
         if_generation_match: 0,
         name: object.clone(),
     };
-    let client = Client::new(&ctx).await?;
+    let client = Client::new(ctx).await?;
     client
         .post_stream(
             ctx.clone(),
             &url,
             query,
-            idiomatic_bytes_stream(&ctx, stream.boxed()),
+            idiomatic_bytes_stream(ctx, stream.boxed()),
         )
         .await?;
 
@@ -74,6 +77,7 @@ pub(crate) async fn upload_file<'a>(
     );
     let obj: StorageObject = client.get(ctx, &obj_url, NoQuery).await?;
     if obj.crc32c == crc32c {
+        info!(ctx.log(), "uploaded {} bytes to {}", obj.size, file_url);
         Ok(obj)
     } else {
         Err(format_err!(
@@ -1,4 +1,6 @@
 //! Interfaces to various clouds.
 
 pub(crate) mod aws;
+pub(crate) mod azure;
 pub(crate) mod gcloud;
+pub(crate) mod retry;
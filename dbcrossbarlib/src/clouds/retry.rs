@@ -0,0 +1,136 @@
+//! Retrying cloud API calls which fail temporarily.
+
+use rand::{thread_rng, Rng};
+use reqwest::StatusCode;
+use std::{cmp::min, convert::TryFrom, time::Duration};
+use tokio::time::sleep;
+
+use crate::common::*;
+use crate::metrics::METRICS;
+
+/// The result of a single attempt to do something.
+pub(crate) enum Attempt<T> {
+    /// We succeeded.
+    Finished(T),
+    /// We failed, but we might succeed if we try again.
+    FailedTemporarily(Error),
+    /// We failed, and there's no point in trying again.
+    FailedPermanently(Error),
+}
+
+/// How often should we retry, and how long should we wait between attempts?
+#[derive(Clone, Debug)]
+pub(crate) struct RetryPolicy {
+    /// The maximum number of attempts, including the first.
+    max_attempts: u32,
+    /// How long should we wait before the first retry?
+    initial_delay: Duration,
+    /// The longest we should ever wait between attempts.
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// How long should we wait after `failures` failed attempts?
+    ///
+    /// We double the delay after each failure, up to `max_delay`, and then
+    /// pick a random value between half the delay and the full delay. The
+    /// randomness prevents parallel streams from all retrying at once.
+    fn delay(&self, failures: u32) -> Duration {
+        let exponent = min(failures.saturating_sub(1), 16);
+        let delay = min(self.initial_delay * 2u32.pow(exponent), self.max_delay);
+        let millis = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+        let jittered = thread_rng().gen_range(millis / 2..=millis);
+        Duration::from_millis(jittered)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 6,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Call `f` until it finishes or fails permanently, waiting between attempts
+/// according to `policy`. `description` is used in log messages and errors.
+///
+/// Only use this for operations which are safe to repeat.
+pub(crate) async fn retry<T, F, Fut>(
+    ctx: &Context,
+    policy: &RetryPolicy,
+    description: &str,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Attempt<T>>,
+{
+    let mut failures = 0;
+    loop {
+        match f().await {
+            Attempt::Finished(value) => return Ok(value),
+            Attempt::FailedPermanently(err) => return Err(err),
+            Attempt::FailedTemporarily(err) => {
+                failures += 1;
+                if failures >= policy.max_attempts {
+                    return Err(err
+                        .context(format!(
+                            "{} failed {} times, giving up",
+                            description, failures,
+                        ))
+                        .into());
+                }
+                let delay = policy.delay(failures);
+                METRICS.retries.add(1);
+                warn!(
+                    ctx.log(),
+                    "{} failed (attempt {} of {}), retrying in {:?}: {}",
+                    description,
+                    failures,
+                    policy.max_attempts,
+                    delay,
+                    err,
+                );
+                sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Is `status` an HTTP status which might go away if we try again?
+pub(crate) fn is_temporary_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// Is `err` the kind of HTTP client error that might go away if we try again?
+pub(crate) fn is_temporary_reqwest_error(err: &reqwest::Error) -> bool {
+    // These are based on guesswork, not experience.
+    err.is_connect() || err.is_request() || err.is_timeout()
+}
+
+#[test]
+fn retry_delays_grow_exponentially_with_jitter() {
+    let policy = RetryPolicy::default();
+    for _ in 0..100 {
+        let first = policy.delay(1);
+        assert!(first >= Duration::from_millis(500));
+        assert!(first <= Duration::from_secs(1));
+        let third = policy.delay(3);
+        assert!(third >= Duration::from_secs(2));
+        assert!(third <= Duration::from_secs(4));
+        assert!(policy.delay(100) <= Duration::from_secs(60));
+    }
+}
+
+#[test]
+fn classifies_temporary_statuses() {
+    assert!(is_temporary_status(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(is_temporary_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(!is_temporary_status(StatusCode::NOT_FOUND));
+    assert!(!is_temporary_status(StatusCode::FORBIDDEN));
+}
@@ -0,0 +1,591 @@
+//! Selecting, renaming, reordering and casting columns as we copy them.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    convert::TryFrom,
+    io::{BufReader, BufWriter},
+    str,
+};
+use uuid::Uuid;
+
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::{Column, DataType};
+use crate::transform::spawn_sync_transform;
+
+/// How to select, rename, reorder and cast columns between the source and the
+/// destination.
+///
+/// The source is always read using its original column names and types, and
+/// the destination schema and CSV data use the new names and types.
+#[derive(Clone, Debug, Default)]
+pub struct ColumnMapping {
+    /// The columns to copy, in order, if we don't want all of them.
+    select: Option<Vec<String>>,
+    /// Columns which should not be copied.
+    exclude: Vec<String>,
+    /// Pairs of `(old, new)` column names, in the order they were specified.
+    renames: Vec<(String, String)>,
+    /// Should the columns in `renames` be moved to the front, in the order
+    /// they were specified?
+    reorder: bool,
+    /// Pairs of `(column, data_type)`, overriding the destination type of a
+    /// column.
+    casts: Vec<(String, DataType)>,
+}
+
+/// A column in our output, and where it comes from.
+struct OutputColumn<'a> {
+    /// The name of the column in the source data.
+    source: &'a str,
+    /// The destination column.
+    column: Column,
+    /// Was the type of this column overridden using a cast?
+    is_cast: bool,
+}
+
+impl ColumnMapping {
+    /// Does this mapping leave all columns alone?
+    pub fn is_empty(&self) -> bool {
+        self.select.is_none()
+            && self.exclude.is_empty()
+            && self.renames.is_empty()
+            && self.casts.is_empty()
+    }
+
+    /// Does this mapping rename any columns?
+    pub fn has_renames(&self) -> bool {
+        !self.renames.is_empty()
+    }
+
+    /// Only copy the columns in `spec`, which is a comma-separated list of
+    /// column names. Columns will be output in the order listed.
+    pub fn add_select(&mut self, spec: &str) -> Result<()> {
+        let select = self.select.get_or_insert_with(Vec::new);
+        for name in parse_column_list(spec)? {
+            if select.contains(&name) {
+                return Err(format_err!("column {:?} was selected twice", name));
+            }
+            select.push(name);
+        }
+        Ok(())
+    }
+
+    /// Don't copy the columns in `spec`, which is a comma-separated list of
+    /// column names.
+    pub fn add_exclude(&mut self, spec: &str) -> Result<()> {
+        self.exclude.extend(parse_column_list(spec)?);
+        Ok(())
+    }
+
+    /// Rename a column, using a specification of the form `OLD:NEW`.
+    pub fn add_rename(&mut self, spec: &str) -> Result<()> {
+        let idx = spec
+            .find(':')
+            .ok_or_else(|| format_err!("expected OLD:NEW, found {:?}", spec))?;
+        let (old, new) = (&spec[..idx], &spec[idx + 1..]);
+        if old.is_empty() || new.is_empty() {
+            return Err(format_err!("expected OLD:NEW, found {:?}", spec));
+        }
+        if self.renames.iter().any(|(o, _)| o == old) {
+            return Err(format_err!("column {:?} was renamed more than once", old));
+        }
+        self.renames.push((old.to_owned(), new.to_owned()));
+        Ok(())
+    }
+
+    /// Add renames from the contents of a mapping file, which contains one
+    /// `OLD:NEW` pair per line. Blank lines and lines starting with `#` are
+    /// ignored.
+    pub fn add_mapping_file(&mut self, contents: &str) -> Result<()> {
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.add_rename(line)
+                .with_context(|_| format!("error on line {}", line_idx + 1))?;
+        }
+        Ok(())
+    }
+
+    /// Override the destination type of a column, using a specification of
+    /// the form `COLUMN:TYPE`. `COLUMN` is the original name of the column.
+    pub fn add_cast(&mut self, spec: &str) -> Result<()> {
+        let idx = spec
+            .find(':')
+            .ok_or_else(|| format_err!("expected COLUMN:TYPE, found {:?}", spec))?;
+        let (name, ty) = (&spec[..idx], &spec[idx + 1..]);
+        if name.is_empty() {
+            return Err(format_err!("expected COLUMN:TYPE, found {:?}", spec));
+        }
+        if self.casts.iter().any(|(n, _)| n == name) {
+            return Err(format_err!("column {:?} was cast more than once", name));
+        }
+        let data_type = parse_cast_type(ty)
+            .with_context(|_| format!("cannot cast {:?} to {:?}", name, ty))?;
+        self.casts.push((name.to_owned(), data_type));
+        Ok(())
+    }
+
+    /// Move renamed columns to the front, in the order they were listed. Any
+    /// other columns follow in their original order.
+    pub fn set_reorder(&mut self, reorder: bool) {
+        self.reorder = reorder;
+    }
+
+    /// Find the columns in `schema` which we want to copy.
+    fn selected_columns<'a>(&self, schema: &'a Schema) -> Result<Vec<&'a Column>> {
+        let find_column = |name: &str| {
+            schema
+                .table
+                .columns
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| format_err!("cannot find column {:?}", name))
+        };
+        let mut columns = match &self.select {
+            Some(select) => select
+                .iter()
+                .map(|name| find_column(name))
+                .collect::<Result<Vec<_>>>()?,
+            None => schema.table.columns.iter().collect(),
+        };
+        for name in &self.exclude {
+            find_column(name)?;
+        }
+        columns.retain(|c| !self.exclude.contains(&c.name));
+        if columns.is_empty() {
+            return Err(format_err!("no columns were selected"));
+        }
+        Ok(columns)
+    }
+
+    /// Compute the destination columns for `schema`.
+    fn output_columns<'a>(&self, schema: &'a Schema) -> Result<Vec<OutputColumn<'a>>> {
+        let columns = self.selected_columns(schema)?;
+        for (old, _) in &self.renames {
+            if !columns.iter().any(|c| &c.name == old) {
+                return Err(format_err!(
+                    "cannot rename {:?}, because it is not being copied",
+                    old
+                ));
+            }
+        }
+        for (name, _) in &self.casts {
+            if !columns.iter().any(|c| &c.name == name) {
+                return Err(format_err!(
+                    "cannot cast {:?}, because it is not being copied",
+                    name
+                ));
+            }
+        }
+
+        // Put our columns in the right order.
+        let mut ordered: Vec<&Column> = vec![];
+        if self.reorder {
+            for (old, _) in &self.renames {
+                ordered.extend(columns.iter().filter(|c| &c.name == old));
+            }
+            ordered.extend(
+                columns
+                    .iter()
+                    .filter(|c| !self.renames.iter().any(|(old, _)| old == &c.name)),
+            );
+        } else {
+            ordered.extend(columns.iter());
+        }
+
+        // Rename and cast them, and check for duplicates.
+        let mut seen = HashSet::new();
+        let mut output = Vec::with_capacity(ordered.len());
+        for col in ordered {
+            let mut new_col = col.to_owned();
+            if let Some((_, new)) =
+                self.renames.iter().find(|(old, _)| old == &col.name)
+            {
+                new_col.name = new.to_owned();
+            }
+            let cast = self.casts.iter().find(|(name, _)| name == &col.name);
+            if let Some((_, data_type)) = cast {
+                new_col.data_type = data_type.to_owned();
+            }
+            if !seen.insert(new_col.name.clone()) {
+                return Err(format_err!(
+                    "more than one column would be named {:?}",
+                    new_col.name
+                ));
+            }
+            output.push(OutputColumn {
+                source: &col.name,
+                column: new_col,
+                is_cast: cast.is_some(),
+            });
+        }
+        Ok(output)
+    }
+
+    /// Remove any columns we don't want to copy from the source schema,
+    /// without renaming or reordering anything. This is the schema we pass to
+    /// the source driver, so that it doesn't read unnecessary columns.
+    pub(crate) fn select_from_schema(&self, schema: &Schema) -> Result<Schema> {
+        let columns = self.selected_columns(schema)?;
+        let mut selected = schema.to_owned();
+        selected
+            .table
+            .columns
+            .retain(|col| columns.iter().any(|c| c.name == col.name));
+        Ok(selected)
+    }
+
+    /// Apply this mapping to the source schema, producing the destination
+    /// schema.
+    pub(crate) fn apply_to_schema(&self, schema: &Schema) -> Result<Schema> {
+        let mut mapped = schema.to_owned();
+        mapped.table.columns = self
+            .output_columns(schema)?
+            .into_iter()
+            .map(|out| out.column)
+            .collect();
+        Ok(mapped)
+    }
+}
+
+/// Select, rename, reorder and cast the columns in `streams`, which use the
+/// column names in `schema`.
+pub(crate) fn map_columns(
+    ctx: Context,
+    schema: &Schema,
+    mapping: &ColumnMapping,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    let columns = mapping
+        .output_columns(schema)?
+        .into_iter()
+        .map(|out| {
+            let cast = if out.is_cast {
+                Some(out.column.data_type)
+            } else {
+                None
+            };
+            (out.source.to_owned(), out.column.name, cast)
+        })
+        .collect::<Vec<_>>();
+    let mapped = streams.and_then(move |stream| {
+        let ctx = ctx.clone();
+        let columns = columns.clone();
+        async move {
+            let data = spawn_sync_transform(
+                ctx,
+                "map_columns".to_owned(),
+                stream.data,
+                move |_ctx, rdr, wtr| copy_mapping_columns(&columns, rdr, wtr),
+            )?;
+            Ok(CsvStream {
+                name: stream.name,
+                data,
+            })
+        }
+        .boxed()
+    });
+    Ok(mapped.boxed())
+}
+
+/// Parse a comma-separated list of column names.
+fn parse_column_list(spec: &str) -> Result<Vec<String>> {
+    spec.split(',')
+        .map(|name| {
+            let name = name.trim();
+            if name.is_empty() {
+                Err(format_err!(
+                    "expected a list of column names, found {:?}",
+                    spec
+                ))
+            } else {
+                Ok(name.to_owned())
+            }
+        })
+        .collect()
+}
+
+/// Parse a type name passed to `--cast`. We accept our portable type names,
+/// and common SQL names for the same types.
+fn parse_cast_type(ty: &str) -> Result<DataType> {
+    let ty = ty.trim().to_ascii_lowercase();
+    // Ignore any precision, such as `numeric(12,2)` or `varchar(255)`, because
+    // our portable types don't have one.
+    let base = match ty.find('(') {
+        Some(idx) if ty.ends_with(')') => ty[..idx].trim_end(),
+        _ => &ty[..],
+    };
+    match base {
+        "bool" | "boolean" => Ok(DataType::Bool),
+        "date" => Ok(DataType::Date),
+        "decimal" | "numeric" => Ok(DataType::Decimal),
+        "float32" | "real" | "float4" => Ok(DataType::Float32),
+        "float64" | "double precision" | "float8" | "float" => Ok(DataType::Float64),
+        "int16" | "smallint" | "int2" => Ok(DataType::Int16),
+        "int32" | "integer" | "int" | "int4" => Ok(DataType::Int32),
+        "int64" | "bigint" | "int8" => Ok(DataType::Int64),
+        "json" | "jsonb" => Ok(DataType::Json),
+        "text" | "string" | "varchar" | "character varying" => Ok(DataType::Text),
+        "timestamp_without_time_zone" | "timestamp" => {
+            Ok(DataType::TimestampWithoutTimeZone)
+        }
+        "timestamp_with_time_zone" | "timestamptz" => {
+            Ok(DataType::TimestampWithTimeZone)
+        }
+        "uuid" => Ok(DataType::Uuid),
+        _ => Err(format_err!("unknown type {:?}", ty)),
+    }
+}
+
+/// Convert `cell` to our CSV interchange format for `data_type`, or return an
+/// error if it isn't a valid value of that type.
+fn cast_cell<'a>(data_type: &DataType, cell: &'a str) -> Result<Cow<'a, str>> {
+    match data_type {
+        DataType::Bool => {
+            let value = bool::from_csv_cell(cell.trim())?;
+            Ok(Cow::Borrowed(if value { "t" } else { "f" }))
+        }
+        DataType::Date => {
+            NaiveDate::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        DataType::Decimal | DataType::Float32 | DataType::Float64 => {
+            let cell = cell.trim();
+            f64::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            // Allow values like `12.0`, which are common when integers have
+            // been stored as floating point numbers.
+            let cell = cell.trim();
+            let digits = match cell.find('.') {
+                Some(idx) if cell[idx + 1..].bytes().all(|b| b == b'0') => {
+                    &cell[..idx]
+                }
+                _ => cell,
+            };
+            let value = i64::from_csv_cell(digits)?;
+            let fits = match data_type {
+                DataType::Int16 => i16::try_from(value).is_ok(),
+                DataType::Int32 => i32::try_from(value).is_ok(),
+                _ => true,
+            };
+            if !fits {
+                return Err(format_err!("{} is out of range", value));
+            }
+            Ok(Cow::Owned(value.to_string()))
+        }
+        DataType::Json => {
+            serde_json::Value::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        DataType::TimestampWithoutTimeZone => {
+            NaiveDateTime::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        DataType::TimestampWithTimeZone => {
+            DateTime::<Utc>::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        DataType::Uuid => {
+            Uuid::from_csv_cell(cell)?;
+            Ok(Cow::Borrowed(cell))
+        }
+        _ => Ok(Cow::Borrowed(cell)),
+    }
+}
+
+/// Copy CSV data from `rdr` to `wtr`. For each `(source, dest, cast)` value in
+/// `columns`, we output the source column using the name `dest`, converting it
+/// to `cast` if present.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+fn copy_mapping_columns(
+    columns: &[(String, String, Option<DataType>)],
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    let headers = rdr.byte_headers()?.to_owned();
+    let indices = columns
+        .iter()
+        .map(|(source, _, _)| {
+            headers
+                .iter()
+                .position(|h| h == source.as_bytes())
+                .ok_or_else(|| {
+                    format_err!("cannot find column {:?} in CSV data", source)
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    wtr.write_record(columns.iter().map(|(_, dest, _)| dest))?;
+    let mut record = csv::ByteRecord::new();
+    let mut row_idx = 0;
+    while rdr.read_byte_record(&mut record)? {
+        row_idx += 1;
+        for (&idx, (source, _, cast)) in indices.iter().zip(columns) {
+            let cell = &record[idx];
+            match cast {
+                Some(data_type) if !cell.is_empty() => {
+                    let cast_value = str::from_utf8(cell)
+                        .map_err(Error::from)
+                        .and_then(|cell| cast_cell(data_type, cell))
+                        .with_context(|_| ErrorKind::InvalidData {
+                            row: row_idx,
+                            column: source.to_owned(),
+                            value: String::from_utf8_lossy(cell).into_owned(),
+                        })?;
+                    wtr.write_field(cast_value.as_bytes())?;
+                }
+                _ => wtr.write_field(cell)?,
+            }
+        }
+        wtr.write_record(None::<&[u8]>)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[test]
+fn maps_schemas() {
+    let column = |name: &str| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type: DataType::Text,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![column("a"), column("b"), column("c")],
+    })
+    .unwrap();
+    let names = |mapping: &ColumnMapping| {
+        mapping
+            .apply_to_schema(&schema)
+            .unwrap()
+            .table
+            .columns
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<_>>()
+    };
+
+    let mut mapping = ColumnMapping::default();
+    assert!(mapping.is_empty());
+    mapping
+        .add_mapping_file("# Comment\nc:see\n\n  a:eh  \n")
+        .unwrap();
+    assert_eq!(names(&mapping), &["eh", "b", "see"]);
+    mapping.set_reorder(true);
+    assert_eq!(names(&mapping), &["see", "eh", "b"]);
+
+    assert!(mapping.add_rename("c:again").is_err());
+    assert!(mapping.add_rename("nope").is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_rename("a:b").unwrap();
+    assert!(mapping.apply_to_schema(&schema).is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_rename("z:y").unwrap();
+    assert!(mapping.apply_to_schema(&schema).is_err());
+}
+
+#[test]
+fn selects_columns() {
+    let column = |name: &str| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type: DataType::Text,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![column("id"), column("email"), column("ssn"), column("zip")],
+    })
+    .unwrap();
+    let names = |schema: Schema| {
+        schema
+            .table
+            .columns
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<_>>()
+    };
+
+    let mut mapping = ColumnMapping::default();
+    mapping.add_select("zip, id").unwrap();
+    mapping.add_select("ssn").unwrap();
+    mapping.add_exclude("ssn").unwrap();
+    mapping.add_rename("zip:postal_code").unwrap();
+    assert!(!mapping.is_empty());
+    assert_eq!(
+        names(mapping.select_from_schema(&schema).unwrap()),
+        &["id", "zip"],
+    );
+    assert_eq!(
+        names(mapping.apply_to_schema(&schema).unwrap()),
+        &["postal_code", "id"],
+    );
+
+    assert!(mapping.add_select("id").is_err());
+    assert!(mapping.add_exclude("a,,b").is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_exclude("email,ssn").unwrap();
+    mapping.add_rename("ssn:tax_id").unwrap();
+    assert!(mapping.apply_to_schema(&schema).is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_exclude("missing").unwrap();
+    assert!(mapping.select_from_schema(&schema).is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_select("id").unwrap();
+    mapping.add_exclude("id").unwrap();
+    assert!(mapping.select_from_schema(&schema).is_err());
+}
+
+#[test]
+fn casts_columns() {
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "price".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Text,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let mut mapping = ColumnMapping::default();
+    mapping.add_cast("price:NUMERIC(12, 2)").unwrap();
+    mapping.add_rename("price:cost").unwrap();
+    let mapped = mapping.apply_to_schema(&schema).unwrap();
+    assert_eq!(mapped.table.columns[0].name, "cost");
+    assert_eq!(mapped.table.columns[0].data_type, DataType::Decimal);
+    assert!(mapping.add_cast("price:text").is_err());
+    assert!(mapping.add_cast("other:money").is_err());
+    let mut mapping = ColumnMapping::default();
+    mapping.add_cast("missing:text").unwrap();
+    assert!(mapping.apply_to_schema(&schema).is_err());
+
+    let cases = &[
+        (DataType::Bool, "Yes", Some("t")),
+        (DataType::Bool, "maybe", None),
+        (DataType::Decimal, " 12.50 ", Some("12.50")),
+        (DataType::Int16, "12.000", Some("12")),
+        (DataType::Int16, "12.5", None),
+        (DataType::Int16, "40000", None),
+        (DataType::Int64, "40000", Some("40000")),
+        (DataType::Json, "hello", None),
+        (DataType::Text, "[1,2]", Some("[1,2]")),
+        (DataType::Date, "2001-01-01", Some("2001-01-01")),
+        (DataType::Date, "01/01/2001", None),
+    ];
+    for (data_type, input, expected) in cases {
+        let output = cast_cell(data_type, input).ok();
+        assert_eq!(output.as_deref(), *expected, "{:?} {:?}", data_type, input);
+    }
+}
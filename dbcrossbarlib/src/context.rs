@@ -1,10 +1,16 @@
 //! Logging and error-handling context.
 
-use slog::{OwnedKV, SendSyncRefUnwindSafeKV};
+use slog::{Drain, Duplicate, OwnedKV, SendSyncRefUnwindSafeKV};
+use std::sync::Arc;
 use tokio::process::Child;
 use tokio_stream::wrappers::ReceiverStream;
 
+use crate::cancellation::{CleanupGuard, CleanupRegistry};
 use crate::common::*;
+use crate::credentials::CredentialsArguments;
+use crate::drivers::postgres_shared::ConnectionPool;
+use crate::summary::{SummaryCollector, WarningRecorder};
+use crate::telemetry::{Span, SpanCollector, TraceContext};
 
 /// Context shared by our various asynchronous operations.
 #[derive(Debug, Clone)]
@@ -14,6 +20,16 @@ pub struct Context {
     /// To report asynchronous errors anywhere in the application, send them to
     /// this channel.
     error_sender: mpsc::Sender<Error>,
+    /// Where to record tracing spans, if anywhere.
+    trace: Option<TraceContext>,
+    /// Where to record information for our run summary, if anywhere.
+    summary: Option<Arc<SummaryCollector>>,
+    /// What we need to clean up if we're cancelled.
+    cleanup: Arc<CleanupRegistry>,
+    /// Which cloud credentials to use, if not the defaults.
+    credentials: CredentialsArguments,
+    /// Idle PostgreSQL connections, which we can reuse for later operations.
+    postgres_connections: Arc<ConnectionPool>,
 }
 
 impl Context {
@@ -23,7 +39,15 @@ impl Context {
     pub fn create(log: Logger) -> (Self, BoxFuture<()>) {
         let (error_sender, receiver) = mpsc::channel(1);
         let mut receiver = ReceiverStream::new(receiver);
-        let context = Context { log, error_sender };
+        let context = Context {
+            log,
+            error_sender,
+            trace: None,
+            summary: None,
+            cleanup: Arc::new(CleanupRegistry::default()),
+            credentials: CredentialsArguments::default(),
+            postgres_connections: Arc::new(ConnectionPool::default()),
+        };
         let worker_future = async move {
             match receiver.next().await {
                 // All senders have shut down correctly.
@@ -39,7 +63,6 @@ impl Context {
     /// Create a new context which can be used from a test case.
     #[cfg(test)]
     pub fn create_for_test(test_name: &str) -> (Self, BoxFuture<()>) {
-        use slog::Drain;
         use slog_async::OverflowStrategy;
 
         let decorator = slog_term::PlainDecorator::new(std::io::stderr());
@@ -69,9 +92,125 @@ impl Context {
         Context {
             log: self.log.new(log_kv),
             error_sender: self.error_sender.clone(),
+            trace: self.trace.clone(),
+            summary: self.summary.clone(),
+            cleanup: self.cleanup.clone(),
+            credentials: self.credentials.clone(),
+            postgres_connections: self.postgres_connections.clone(),
+        }
+    }
+
+    /// Record tracing spans for this context and its children using
+    /// `collector`.
+    pub fn with_span_collector(self, collector: Arc<SpanCollector>) -> Self {
+        Context {
+            trace: Some(TraceContext::root(collector)),
+            ..self
+        }
+    }
+
+    /// Record information about this run in `collector`, including any
+    /// warnings logged by this context and its children.
+    pub fn with_summary_collector(self, collector: Arc<SummaryCollector>) -> Self {
+        let recorder = WarningRecorder::new(collector.clone());
+        let log = Logger::root(
+            Duplicate::new(self.log.clone(), recorder).ignore_res(),
+            o!(),
+        );
+        Context {
+            log,
+            summary: Some(collector),
+            ..self
+        }
+    }
+
+    /// Get the collector for our run summary, if we have one.
+    pub(crate) fn summary(&self) -> Option<&Arc<SummaryCollector>> {
+        self.summary.as_ref()
+    }
+
+    /// Remove any driver arguments which choose cloud credentials, like
+    /// `aws_profile`, from `args`. Returns a context which uses those
+    /// credentials, and the remaining arguments.
+    pub fn with_credentials_from_driver_args(
+        &self,
+        args: DriverArguments,
+    ) -> Result<(Context, DriverArguments)> {
+        let (credentials, rest) = CredentialsArguments::split_from(args)?;
+        Ok((self.with_credentials_arguments(credentials), rest))
+    }
+
+    /// Use `credentials` for any cloud where it specifies credentials, and our
+    /// existing credentials otherwise.
+    pub(crate) fn with_credentials_arguments(
+        &self,
+        credentials: CredentialsArguments,
+    ) -> Context {
+        Context {
+            credentials: credentials.or(&self.credentials),
+            ..self.clone()
         }
     }
 
+    /// Which cloud credentials should we use?
+    pub(crate) fn credentials_arguments(&self) -> &CredentialsArguments {
+        &self.credentials
+    }
+
+    /// Idle PostgreSQL connections shared by this context and its children.
+    pub(crate) fn postgres_connections(&self) -> &Arc<ConnectionPool> {
+        &self.postgres_connections
+    }
+
+    /// Start a tracing span named `name`. Returns a child context to use for
+    /// work done inside the span, and a `Span` which will be logged and
+    /// recorded when it's dropped.
+    pub fn span(&self, name: &str) -> (Context, Span) {
+        let (span, trace) = Span::start(self.log.clone(), self.trace.as_ref(), name);
+        let ctx = Context {
+            log: self.log.clone(),
+            error_sender: self.error_sender.clone(),
+            trace,
+            summary: self.summary.clone(),
+            cleanup: self.cleanup.clone(),
+            credentials: self.credentials.clone(),
+            postgres_connections: self.postgres_connections.clone(),
+        };
+        (ctx, span)
+    }
+
+    /// Call `action` to clean up if this run is cancelled while the returned
+    /// guard is still alive. `description` should describe what `action`
+    /// does, like "delete gs://bucket/temp/".
+    pub(crate) fn on_cancel<F, Fut>(
+        &self,
+        description: String,
+        action: F,
+    ) -> CleanupGuard
+    where
+        F: FnOnce(Context) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        // Clean up using the same credentials as the code which registered
+        // this action.
+        let credentials = self.credentials.clone();
+        self.cleanup.register(
+            description,
+            Box::new(move |ctx| {
+                action(ctx.with_credentials_arguments(credentials)).boxed()
+            }),
+        )
+    }
+
+    /// Clean up after a cancelled run, by calling all the actions registered
+    /// using `on_cancel` whose guards are still alive.
+    ///
+    /// Call this _before_ dropping the futures which were doing the work, or
+    /// else they'll drop their guards, and then await the returned future.
+    pub fn clean_up_after_cancel(&self) -> BoxFuture<()> {
+        self.cleanup.run_all(self)
+    }
+
     /// Spawn an async worker in this context, and report any errors to the
     /// future returned by `create`.
     pub fn spawn_worker<W>(&self, worker: W)
@@ -0,0 +1,593 @@
+//! A builder-style API for copying data between locators.
+//!
+//! This does the same work as `dbcrossbar cp`, but it can be called directly
+//! by programs which embed `dbcrossbarlib`.
+
+use crate::column_mapping::{map_columns, ColumnMapping};
+use crate::common::*;
+use crate::date_formats::{normalize_dates, DateFormats};
+use crate::dedup::{dedup_rows, Deduplication};
+use crate::masking::{mask_columns, Masking};
+use crate::metrics::{count_csvs, ActiveStream, Direction};
+use crate::rechunk::rechunk_csvs;
+use crate::sampling::{sample_rows, Sampling};
+use crate::schema_diff::SchemaDiff;
+use crate::throttle::throttle_csvs;
+
+/// A copy operation from one locator to another.
+///
+/// ```no_compile
+/// let dests = CopyOperation::new(src, dest)
+///     .if_exists(IfExists::Overwrite)
+///     .temporary("gs://example-bucket/temp/")
+///     .max_streams(8)
+///     .run(ctx)
+///     .await?
+///     .try_collect::<Vec<_>>()
+///     .await?;
+/// ```
+pub struct CopyOperation {
+    /// The locator to copy data from.
+    from_locator: BoxLocator,
+    /// The locator to copy data to.
+    to_locator: BoxLocator,
+    /// The locator to read our schema from, if not `from_locator`.
+    schema_locator: Option<BoxLocator>,
+    /// Temporary storage to use during the copy.
+    temporary_storage: TemporaryStorage,
+    /// The approximate size of the CSV streams to use, if we want to rechunk.
+    stream_size: Option<usize>,
+    /// Driver-specific arguments for `from_locator`.
+    from_args: DriverArguments,
+    /// Driver-specific arguments for `to_locator`.
+    to_args: DriverArguments,
+    /// An SQL `WHERE` clause specifying rows to copy.
+    where_clause: Option<String>,
+    /// An SQL query to read data from, instead of reading the source table.
+    query: Option<String>,
+    /// What to do if the destination already exists.
+    if_exists: IfExists,
+    /// Should we add missing columns to an existing destination table?
+    evolve_schema: bool,
+    /// Should we create a missing schema or dataset for the destination?
+    create_missing: bool,
+    /// How many data streams should we attempt to copy in parallel?
+    max_streams: usize,
+    /// The maximum number of bytes to copy per second, across all streams.
+    max_bytes_per_second: Option<usize>,
+    /// Non-standard date and timestamp formats used by the source data.
+    date_formats: DateFormats,
+    /// How to select, rename and reorder columns.
+    column_mapping: ColumnMapping,
+    /// How to remove duplicate rows.
+    dedup: Deduplication,
+    /// How to mask sensitive columns.
+    masking: Masking,
+    /// Which rows to copy, if we don't want all of them.
+    sampling: Sampling,
+}
+
+impl CopyOperation {
+    /// Create a new copy operation, which will copy data from `from_locator`
+    /// to `to_locator`. This uses the same defaults as `dbcrossbar cp`.
+    pub fn new(from_locator: BoxLocator, to_locator: BoxLocator) -> Self {
+        CopyOperation {
+            from_locator,
+            to_locator,
+            schema_locator: None,
+            temporary_storage: TemporaryStorage::new(vec![]),
+            stream_size: None,
+            from_args: DriverArguments::default(),
+            to_args: DriverArguments::default(),
+            where_clause: None,
+            query: None,
+            if_exists: IfExists::default(),
+            evolve_schema: false,
+            create_missing: false,
+            max_streams: 4,
+            max_bytes_per_second: None,
+            date_formats: DateFormats::default(),
+            column_mapping: ColumnMapping::default(),
+            dedup: Deduplication::default(),
+            masking: Masking::default(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Read the table schema from `schema_locator` instead of from the source.
+    pub fn schema(mut self, schema_locator: BoxLocator) -> Self {
+        self.schema_locator = Some(schema_locator);
+        self
+    }
+
+    /// Add a temporary location, such as `gs://bucket/tempdir` or
+    /// `bigquery:project:dataset`. These will be checked in the order they
+    /// were added.
+    pub fn temporary<S: Into<String>>(mut self, location: S) -> Self {
+        self.temporary_storage.push(location.into());
+        self
+    }
+
+    /// Replace all our temporary locations with `temporary_storage`.
+    pub fn temporary_storage(mut self, temporary_storage: TemporaryStorage) -> Self {
+        self.temporary_storage = temporary_storage;
+        self
+    }
+
+    /// Split the data into CSV streams of approximately `stream_size` bytes.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn stream_size(mut self, stream_size: usize) -> Self {
+        self.stream_size = Some(stream_size);
+        self
+    }
+
+    /// Driver-specific arguments for the source.
+    pub fn from_args(mut self, from_args: DriverArguments) -> Self {
+        self.from_args = from_args;
+        self
+    }
+
+    /// Driver-specific arguments for the destination.
+    pub fn to_args(mut self, to_args: DriverArguments) -> Self {
+        self.to_args = to_args;
+        self
+    }
+
+    /// Only copy rows matching the SQL `WHERE` clause `where_clause`.
+    pub fn where_clause<S: Into<String>>(mut self, where_clause: S) -> Self {
+        self.where_clause = Some(where_clause.into());
+        self
+    }
+
+    /// Read data using the SQL `query`, instead of reading the source table.
+    /// Unless `schema` is also specified, the schema will be based on the
+    /// columns returned by `query`.
+    pub fn query<S: Into<String>>(mut self, query: S) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// What should we do if the destination already exists?
+    pub fn if_exists(mut self, if_exists: IfExists) -> Self {
+        self.if_exists = if_exists;
+        self
+    }
+
+    /// When appending or upserting to an existing table, add any columns
+    /// which the table is missing, instead of failing.
+    pub fn evolve_schema(mut self, evolve_schema: bool) -> Self {
+        self.evolve_schema = evolve_schema;
+        self
+    }
+
+    /// Create the destination's schema, dataset or other container if it
+    /// doesn't exist yet, instead of failing.
+    pub fn create_missing(mut self, create_missing: bool) -> Self {
+        self.create_missing = create_missing;
+        self
+    }
+
+    /// How many data streams should we attempt to copy in parallel?
+    pub fn max_streams(mut self, max_streams: usize) -> Self {
+        self.max_streams = max_streams;
+        self
+    }
+
+    /// Copy at most `max_bytes_per_second` bytes of CSV data per second,
+    /// across all streams. This can be used to limit the load on a production
+    /// database.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn max_bytes_per_second(mut self, max_bytes_per_second: usize) -> Self {
+        self.max_bytes_per_second = Some(max_bytes_per_second);
+        self
+    }
+
+    /// Parse dates and timestamps in the source data using `date_formats`,
+    /// and convert them to the destination's format.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn date_formats(mut self, date_formats: DateFormats) -> Self {
+        self.date_formats = date_formats;
+        self
+    }
+
+    /// Select, rename and reorder columns using `column_mapping`. The source
+    /// will only be asked for the selected columns, and the destination schema
+    /// will use the new column names.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn column_mapping(mut self, column_mapping: ColumnMapping) -> Self {
+        self.column_mapping = column_mapping;
+        self
+    }
+
+    /// Remove duplicate rows using `dedup`. Columns are identified using their
+    /// destination names.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn dedup(mut self, dedup: Deduplication) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Mask sensitive columns using `masking`. Columns are identified using
+    /// their destination names.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn masking(mut self, masking: Masking) -> Self {
+        self.masking = masking;
+        self
+    }
+
+    /// Only copy the rows specified by `sampling`. Sources which support it
+    /// will only read those rows, and we'll sample other sources as we copy.
+    ///
+    /// This forces the data to be copied via the local machine.
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Run this copy operation, returning a stream of locators describing where
+    /// we wrote our data.
+    ///
+    /// The copy will not finish until the returned stream has been consumed.
+    pub async fn run(mut self, ctx: Context) -> Result<BoxStream<BoxLocator>> {
+        self.validate()?;
+        let (ctx, mut copy_span) = ctx.span("copy");
+        copy_span.set_attribute("from_locator", &self.from_locator);
+        copy_span.set_attribute("to_locator", &self.to_locator);
+        let (from_ctx, to_ctx) = self.take_credentials(&ctx)?;
+        let schema = {
+            let (ctx, _span) = from_ctx.span("read_schema");
+            self.read_schema(&ctx).await?
+        };
+        let source_schema = self.column_mapping.select_from_schema(&schema)?;
+        let dest_schema = self.column_mapping.apply_to_schema(&schema)?;
+        self.dedup.validate(&dest_schema)?;
+        self.masking.validate(&dest_schema)?;
+        {
+            let (ctx, _span) = to_ctx.span("check_destination_schema");
+            self.check_destination_schema(&ctx, &dest_schema).await?;
+        }
+        let should_use_remote = self.should_use_remote();
+        let from_locator = self.from_locator;
+        let to_locator = self.to_locator;
+
+        // Build our arguments. The source uses the original column names, and
+        // the destination uses any new names from `column_mapping`.
+        let shared_args = SharedArguments::new(
+            source_schema.clone(),
+            self.temporary_storage.clone(),
+            self.max_streams,
+        );
+        let dest_shared_args = SharedArguments::new(
+            dest_schema.clone(),
+            self.temporary_storage,
+            self.max_streams,
+        );
+        // If the source can sample rows itself, let it, so that we don't need
+        // to read the whole table.
+        let sample_locally =
+            !self.sampling.is_empty() && !from_locator.supports_sampling();
+        let source_sampling = if sample_locally {
+            Sampling::default()
+        } else {
+            self.sampling.clone()
+        };
+        let source_args = SourceArguments::new(self.from_args, self.where_clause)
+            .with_query(self.query)
+            .with_sampling(source_sampling);
+        let dest_args = DestinationArguments::new(self.to_args, self.if_exists)
+            .with_evolve_schema(self.evolve_schema)
+            .with_create_missing(self.create_missing);
+
+        if should_use_remote {
+            // Build a logging context. A remote transfer is performed by the
+            // destination, so it uses the destination's credentials wherever
+            // they were specified.
+            let ctx = from_ctx
+                .with_credentials_arguments(to_ctx.credentials_arguments().clone());
+            let ctx = ctx.child(o!(
+                "from_locator" => from_locator.to_string(),
+                "to_locator" => to_locator.to_string(),
+            ));
+
+            // Perform a remote transfer.
+            info!(ctx.log(), "performing remote data transfer");
+            let summary = ctx.summary().cloned();
+            let (ctx, mut span) = ctx.span("write_remote_data");
+            let result = to_locator
+                .write_remote_data(
+                    ctx,
+                    from_locator,
+                    shared_args,
+                    source_args,
+                    dest_args,
+                )
+                .await;
+            if let Err(err) = &result {
+                span.record_error(err);
+                copy_span.record_error(err);
+            }
+            let dests = result?;
+            if let Some(summary) = summary {
+                for dest in &dests {
+                    summary.record_dest_locator(&dest.to_string());
+                }
+            }
+
+            // Convert our list of output locators into a stream.
+            Ok(stream::iter(dests).map(Ok).boxed())
+        } else {
+            // We have to transfer the data via the local machine, so read data
+            // from input.
+            info!(ctx.log(), "performing local data transfer");
+
+            let input_ctx =
+                from_ctx.child(o!("from_locator" => from_locator.to_string()));
+            let mut data = from_locator
+                .local_data(input_ctx, shared_args.clone(), source_args)
+                .await?
+                .ok_or_else(|| {
+                    format_err!("don't know how to read data from {}", from_locator)
+                })?;
+            data = count_csvs(&ctx, Direction::Read, data);
+
+            // Sample our rows first, so that we don't do any unnecessary work.
+            if sample_locally {
+                data = sample_rows(ctx.clone(), &self.sampling, data)?;
+            }
+
+            // Convert any non-standard dates next, so that we report bad data
+            // early.
+            if !self.date_formats.is_empty() {
+                data = normalize_dates(
+                    ctx.clone(),
+                    &source_schema,
+                    &self.date_formats,
+                    data,
+                )?;
+            }
+
+            // Select, rename and reorder columns. Some sources will have
+            // already dropped unselected columns, but others always return
+            // every column.
+            if !self.column_mapping.is_empty() {
+                data = map_columns(ctx.clone(), &schema, &self.column_mapping, data)?;
+            }
+
+            // Remove duplicate rows, using the destination column names.
+            if !self.dedup.is_empty() {
+                data = dedup_rows(ctx.clone(), &dest_schema, &self.dedup, data)?;
+            }
+
+            // Mask sensitive columns, using their destination names.
+            if !self.masking.is_empty() {
+                data = mask_columns(ctx.clone(), &dest_schema, &self.masking, data)?;
+            }
+
+            // Honor `stream_size` if passed.
+            if let Some(stream_size) = self.stream_size {
+                data = rechunk_csvs(ctx.clone(), stream_size, data)?;
+            }
+
+            // Limit how fast we copy data, if requested.
+            if let Some(max_bytes_per_second) = self.max_bytes_per_second {
+                data = throttle_csvs(ctx.clone(), max_bytes_per_second, data)?;
+            }
+
+            // Write data to output.
+            data = count_csvs(&ctx, Direction::Written, data);
+            let output_ctx = to_ctx.child(o!("to_locator" => to_locator.to_string()));
+            let result_stream = to_locator
+                .write_local_data(output_ctx, data, dest_shared_args, dest_args)
+                .await?;
+
+            // Consume the stream of futures produced by `write_local_data`,
+            // allowing a certain degree of parallelism. This is where all the
+            // actual work happens, and this what controls how many "input
+            // driver" -> "output driver" connections are running at any given
+            // time.
+            let stream_ctx = ctx.clone();
+            Ok(result_stream
+                // Record a span for each stream we write.
+                .map_ok(move |fut| {
+                    let (_ctx, mut span) = stream_ctx.span("write_stream");
+                    let summary = stream_ctx.summary().cloned();
+                    async move {
+                        let mut active = ActiveStream::start();
+                        let result = fut.await;
+                        match &result {
+                            Ok(dest) => {
+                                active.succeeded();
+                                span.set_attribute("dest_locator", dest);
+                                if let Some(summary) = summary {
+                                    summary.record_dest_locator(&dest.to_string());
+                                }
+                            }
+                            Err(err) => span.record_error(err),
+                        }
+                        result
+                    }
+                })
+                // Run up to `parallelism` futures in parallel.
+                .try_buffer_unordered(shared_args.max_streams())
+                // Keep our `copy` span open until our caller is done with us.
+                .inspect(move |_| {
+                    let _copy_span = &copy_span;
+                })
+                .boxed())
+        }
+    }
+
+    /// Describe what this copy operation would do, without copying any data or
+    /// creating any tables.
+    ///
+    /// This still needs to read the table schema, which may require connecting
+    /// to the source. To avoid this, pass a schema file to `schema`. We never
+    /// connect to the destination, so we don't check that our data will fit
+    /// into an existing table.
+    pub async fn dry_run(mut self, ctx: Context) -> Result<String> {
+        self.validate()?;
+        let (from_ctx, to_ctx) = self.take_credentials(&ctx)?;
+        let schema = self.read_schema(&from_ctx).await?;
+        let dest_schema = self.column_mapping.apply_to_schema(&schema)?;
+        self.dedup.validate(&dest_schema)?;
+        self.masking.validate(&dest_schema)?;
+        let should_use_remote = self.should_use_remote();
+
+        let mut out = vec![];
+        writeln!(out, "-- Copy from: {}", self.from_locator)?;
+        writeln!(out, "-- Copy to: {}", self.to_locator)?;
+        writeln!(out, "-- If exists: {}", self.if_exists)?;
+        if let IfExists::Append | IfExists::Upsert(_) = self.if_exists {
+            writeln!(
+                out,
+                "-- (Not checking whether this data fits any existing table)",
+            )?;
+        }
+        if should_use_remote {
+            writeln!(out, "-- Transfer: directly, without downloading data")?;
+        } else {
+            writeln!(
+                out,
+                "-- Transfer: via this machine, {} stream(s) at a time",
+                self.max_streams,
+            )?;
+        }
+
+        let shared_args = SharedArguments::new(
+            dest_schema,
+            self.temporary_storage,
+            self.max_streams,
+        );
+        let dest_args = DestinationArguments::new(self.to_args, self.if_exists)
+            .with_evolve_schema(self.evolve_schema)
+            .with_create_missing(self.create_missing);
+        let description = self.to_locator.write_dry_run(
+            &to_ctx,
+            self.from_locator.as_ref(),
+            shared_args,
+            dest_args,
+        )?;
+        match description {
+            Some(description) => write!(out, "{}", description)?,
+            None => writeln!(
+                out,
+                "-- (Cannot describe how data would be written to {})",
+                self.to_locator,
+            )?,
+        }
+        Ok(String::from_utf8(out).expect("dry run output should always be UTF-8"))
+    }
+
+    /// Check any options which we can validate without looking at the schema.
+    /// This is shared by `run` and `dry_run`.
+    fn validate(&self) -> Result<()> {
+        if self.max_streams == 0 {
+            return Err(format_err!("--max-streams must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// Remove any driver arguments which choose cloud credentials, and return
+    /// contexts for accessing our source and our destination.
+    fn take_credentials(&mut self, ctx: &Context) -> Result<(Context, Context)> {
+        let (from_ctx, from_args) = ctx
+            .with_credentials_from_driver_args(std::mem::take(&mut self.from_args))
+            .context("error parsing --from-args")?;
+        self.from_args = from_args;
+        let (to_ctx, to_args) = ctx
+            .with_credentials_from_driver_args(std::mem::take(&mut self.to_args))
+            .context("error parsing --to-args")?;
+        self.to_args = to_args;
+        Ok((from_ctx, to_ctx))
+    }
+
+    /// Read the schema we'll use for this copy.
+    async fn read_schema(&self, ctx: &Context) -> Result<Schema> {
+        if let (None, Some(query)) = (&self.schema_locator, &self.query) {
+            info!(ctx.log(), "reading schema from query");
+            return self
+                .from_locator
+                .query_schema(ctx.clone(), query.to_owned())
+                .await
+                .context("error reading schema from --from-query")?
+                .ok_or_else(|| {
+                    format_err!(
+                        "don't know how to read schema from --from-query using {}",
+                        self.from_locator,
+                    )
+                });
+        }
+        let schema_locator =
+            self.schema_locator.as_ref().unwrap_or(&self.from_locator);
+        info!(ctx.log(), "reading schema from {}", schema_locator);
+        schema_locator
+            .schema(ctx.clone())
+            .await
+            .with_context(|_| format!("error reading schema from {}", schema_locator))?
+            .ok_or_else(|| {
+                format_err!("don't know how to read schema from {}", schema_locator)
+            })
+    }
+
+    /// If we're adding data to an existing table, make sure that our data will
+    /// fit before we start copying it, and report every problem we find.
+    async fn check_destination_schema(
+        &self,
+        ctx: &Context,
+        dest_schema: &Schema,
+    ) -> Result<()> {
+        match self.if_exists {
+            IfExists::Append | IfExists::Upsert(_) => {}
+            IfExists::Error | IfExists::Overwrite => return Ok(()),
+        }
+        let existing = self
+            .to_locator
+            .existing_schema(ctx.clone())
+            .await
+            .with_context(|_| {
+                format!("error reading schema from {}", self.to_locator)
+            })?;
+        let existing = match existing {
+            Some(existing) => existing,
+            None => return Ok(()),
+        };
+        debug!(ctx.log(), "checking schema of existing {}", self.to_locator);
+        let problems = SchemaDiff::compare(&existing, dest_schema)
+            .load_incompatibilities(self.evolve_schema);
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let mut report = String::new();
+        for problem in &problems {
+            report.push_str("\n- ");
+            report.push_str(problem);
+        }
+        Err(format_err!(
+            "cannot copy data into {}:{}",
+            self.to_locator,
+            report,
+        ))
+    }
+
+    /// Can we short-circuit this particular copy using special features of the
+    /// the source and destination, or do we need to pull the data down to the
+    /// local machine?
+    fn should_use_remote(&self) -> bool {
+        self.stream_size.is_none()
+            && self.max_bytes_per_second.is_none()
+            && self.date_formats.is_empty()
+            && self.column_mapping.is_empty()
+            && self.dedup.is_empty()
+            && self.masking.is_empty()
+            && self.sampling.is_empty()
+            && self
+                .to_locator
+                .supports_write_remote_data(self.from_locator.as_ref())
+    }
+}
@@ -1,18 +1,22 @@
 //! Support for looking up credentials.
 
 use async_trait::async_trait;
+use chrono::Utc;
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     env, fmt,
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 use tokio::{fs, sync::Mutex};
 
+use crate::clouds::aws::{assume_role, AwsCredentials};
 use crate::common::*;
 use crate::config::config_dir;
+use crate::driver_args::deserialize_bool_arg;
 
 /// A set of credentials that we can use to access a service.
 ///
@@ -70,6 +74,11 @@ pub(crate) struct CredentialsManager {
     ///
     /// Before locking this, you must have locked exactly one key in `sources`.
     cache: Mutex<HashMap<String, Credentials>>,
+
+    /// Credentials that we've looked up using `CredentialsArguments`, indexed
+    /// by a key describing where they came from. This is never locked at the
+    /// same time as `sources` or `cache`.
+    overridden_cache: Mutex<HashMap<String, Credentials>>,
 }
 
 impl CredentialsManager {
@@ -84,13 +93,17 @@ impl CredentialsManager {
         let config_dir = config_dir()?;
 
         // Specify how to connect to AWS.
-        let aws = EnvCredentialsSource::new(vec![
-            EnvMapping::required("access_key_id", "AWS_ACCESS_KEY_ID"),
-            EnvMapping::required("secret_access_key", "AWS_SECRET_ACCESS_KEY"),
-            EnvMapping::optional("session_token", "AWS_SESSION_TOKEN"),
-            EnvMapping::required("default_region", "AWS_DEFAULT_REGION"),
+        sources.insert("aws".to_owned(), Mutex::new(aws_env_source().boxed()));
+
+        // Specify how to connect to Azure Blob Storage.
+        let azure_storage = EnvCredentialsSource::new(vec![
+            EnvMapping::required("sas_token", "AZURE_STORAGE_SAS_TOKEN"),
+            EnvMapping::required("account", "AZURE_STORAGE_ACCOUNT"),
         ]);
-        sources.insert("aws".to_owned(), Mutex::new(aws.boxed()));
+        sources.insert(
+            "azure_storage".to_owned(),
+            Mutex::new(azure_storage.boxed()),
+        );
 
         // Specify how to find Google Cloud service account keys.
         let gcloud_service_account_key = CredentialsSources::new(vec![
@@ -135,8 +148,27 @@ impl CredentialsManager {
         )]);
         sources.insert("shopify".to_owned(), Mutex::new(shopify_secret.boxed()));
 
+        // Specify how to connect to Salesforce.
+        let salesforce = EnvCredentialsSource::new(vec![
+            EnvMapping::required("instance_url", "SALESFORCE_INSTANCE_URL"),
+            EnvMapping::required("access_token", "SALESFORCE_ACCESS_TOKEN"),
+        ]);
+        sources.insert("salesforce".to_owned(), Mutex::new(salesforce.boxed()));
+
+        // Specify how to authenticate with an Iceberg REST catalog.
+        let iceberg = EnvCredentialsSource::new(vec![EnvMapping::required(
+            "token",
+            "ICEBERG_TOKEN",
+        )]);
+        sources.insert("iceberg".to_owned(), Mutex::new(iceberg.boxed()));
+
         let cache = Mutex::new(HashMap::new());
-        Ok(CredentialsManager { sources, cache })
+        let overridden_cache = Mutex::new(HashMap::new());
+        Ok(CredentialsManager {
+            sources,
+            cache,
+            overridden_cache,
+        })
     }
 
     /// Look up the credential `name` and return it.
@@ -174,6 +206,247 @@ impl CredentialsManager {
             ))
         }
     }
+
+    /// Look up the credential `name` for use in `ctx`. This works like `get`,
+    /// but respects any `CredentialsArguments` attached to `ctx`.
+    pub(crate) async fn get_for(
+        &self,
+        ctx: &Context,
+        name: &str,
+    ) -> Result<Credentials> {
+        let (key, source) = match ctx.credentials_arguments().source_for(name)? {
+            Some(found) => found,
+            None => return self.get(name).await,
+        };
+
+        // Hold this lock while we look up credentials, so that we don't
+        // assume the same role many times in parallel.
+        let mut cache = self.overridden_cache.lock().await;
+        match cache.get(&key) {
+            Some(c) if !c.needs_refresh() => return Ok(c.to_owned()),
+            _ => {}
+        }
+        if let Some(c) = source.get_credentials().await? {
+            cache.insert(key, c.clone());
+            Ok(c)
+        } else {
+            Err(format_err!(
+                "could not find credentials for {} in any of:\n{}",
+                name,
+                source,
+            ))
+        }
+    }
+}
+
+/// Get the standard AWS credentials from environment variables.
+fn aws_env_source() -> EnvCredentialsSource {
+    EnvCredentialsSource::new(vec![
+        EnvMapping::required("access_key_id", "AWS_ACCESS_KEY_ID"),
+        EnvMapping::required("secret_access_key", "AWS_SECRET_ACCESS_KEY"),
+        EnvMapping::optional("session_token", "AWS_SESSION_TOKEN"),
+        EnvMapping::required("default_region", "AWS_DEFAULT_REGION"),
+    ])
+}
+
+/// The names of the driver arguments in `CredentialsArguments`.
+const CREDENTIALS_ARGUMENT_NAMES: &[&str] = &[
+    "aws_profile",
+    "aws_role_arn",
+    "gcloud_service_account_key_file",
+    "gcloud_application_default_credentials",
+];
+
+/// Driver arguments which choose the credentials used for a single locator,
+/// like `--from-arg=aws_profile=prod`. These allow the source and destination
+/// of a copy to use different identities.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CredentialsArguments {
+    /// A profile in `~/.aws/credentials` or `~/.aws/config`.
+    aws_profile: Option<String>,
+
+    /// The ARN of an IAM role to assume.
+    aws_role_arn: Option<String>,
+
+    /// The path to a Google Cloud service account key file.
+    gcloud_service_account_key_file: Option<PathBuf>,
+
+    /// Should we use Google Cloud "application default credentials"?
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    gcloud_application_default_credentials: bool,
+}
+
+impl CredentialsArguments {
+    /// Split any credentials arguments out of `args`, and return them along
+    /// with the remaining arguments.
+    pub(crate) fn split_from(
+        args: DriverArguments,
+    ) -> Result<(CredentialsArguments, DriverArguments)> {
+        let (creds_args, rest) = args.partition(CREDENTIALS_ARGUMENT_NAMES);
+        let creds = creds_args.deserialize::<CredentialsArguments>()?;
+        if creds.gcloud_service_account_key_file.is_some()
+            && creds.gcloud_application_default_credentials
+        {
+            return Err(ErrorKind::DriverArguments {
+                message: "cannot use both gcloud_service_account_key_file and \
+                          gcloud_application_default_credentials"
+                    .to_owned(),
+            }
+            .into());
+        }
+        Ok((creds, rest))
+    }
+
+    /// Do we choose our own AWS credentials?
+    fn overrides_aws(&self) -> bool {
+        self.aws_profile.is_some() || self.aws_role_arn.is_some()
+    }
+
+    /// Do we choose our own Google Cloud credentials?
+    fn overrides_gcloud(&self) -> bool {
+        self.gcloud_service_account_key_file.is_some()
+            || self.gcloud_application_default_credentials
+    }
+
+    /// Use our credentials for each cloud where we specify them, and the ones
+    /// in `fallback` otherwise.
+    pub(crate) fn or(self, fallback: &CredentialsArguments) -> CredentialsArguments {
+        let overrides_aws = self.overrides_aws();
+        let overrides_gcloud = self.overrides_gcloud();
+        let (aws_profile, aws_role_arn) = if overrides_aws {
+            (self.aws_profile, self.aws_role_arn)
+        } else {
+            (fallback.aws_profile.clone(), fallback.aws_role_arn.clone())
+        };
+        let (gcloud_service_account_key_file, gcloud_application_default_credentials) =
+            if overrides_gcloud {
+                (
+                    self.gcloud_service_account_key_file,
+                    self.gcloud_application_default_credentials,
+                )
+            } else {
+                (
+                    fallback.gcloud_service_account_key_file.clone(),
+                    fallback.gcloud_application_default_credentials,
+                )
+            };
+        CredentialsArguments {
+            aws_profile,
+            aws_role_arn,
+            gcloud_service_account_key_file,
+            gcloud_application_default_credentials,
+        }
+    }
+
+    /// Do we replace the default source for the credential `name`?
+    pub(crate) fn overrides(&self, name: &str) -> bool {
+        match name {
+            "aws" => self.overrides_aws(),
+            "gcloud_service_account_key" => self.overrides_gcloud(),
+            _ => false,
+        }
+    }
+
+    /// If we replace the default source for the credential `name`, return a
+    /// cache key and our source.
+    fn source_for(
+        &self,
+        name: &str,
+    ) -> Result<Option<(String, Box<dyn CredentialsSource>)>> {
+        if !self.overrides(name) {
+            return Ok(None);
+        }
+        let source: Box<dyn CredentialsSource> = match name {
+            "aws" => AwsProfileCredentialsSource {
+                profile: self.aws_profile.clone(),
+                role_arn: self.aws_role_arn.clone(),
+            }
+            .boxed(),
+            "gcloud_service_account_key" => {
+                let path = match &self.gcloud_service_account_key_file {
+                    Some(path) => path.to_owned(),
+                    None => application_default_credentials_path()?,
+                };
+                FileCredentialsSource::new("value", path).boxed()
+            }
+            _ => unreachable!("no override for credential {:?}", name),
+        };
+        Ok(Some((format!("{}: {:?}", name, source), source)))
+    }
+}
+
+#[test]
+fn credentials_arguments_are_split_from_driver_args() {
+    let args = DriverArguments::from_cli_args(&[
+        "aws_profile=prod",
+        "part_size_mib=16",
+        "gcloud_application_default_credentials=true",
+    ])
+    .unwrap();
+    let (creds, rest) = CredentialsArguments::split_from(args).unwrap();
+    assert_eq!(creds.aws_profile.as_deref(), Some("prod"));
+    assert!(creds.gcloud_application_default_credentials);
+    assert!(creds.overrides("aws"));
+    assert!(creds.overrides("gcloud_service_account_key"));
+    assert!(!creds.overrides("azure_storage"));
+    assert_eq!(
+        rest.to_json().unwrap(),
+        serde_json::json!({ "part_size_mib": "16" }),
+    );
+
+    let both_gcloud = DriverArguments::from_cli_args(&[
+        "gcloud_service_account_key_file=key.json",
+        "gcloud_application_default_credentials=true",
+    ])
+    .unwrap();
+    assert!(CredentialsArguments::split_from(both_gcloud).is_err());
+}
+
+#[test]
+fn credentials_arguments_fall_back_per_cloud() {
+    let source = CredentialsArguments {
+        aws_profile: Some("source".to_owned()),
+        gcloud_application_default_credentials: true,
+        ..CredentialsArguments::default()
+    };
+    let dest = CredentialsArguments {
+        aws_role_arn: Some("arn:aws:iam::123456789012:role/dest".to_owned()),
+        ..CredentialsArguments::default()
+    };
+    let merged = dest.or(&source);
+    assert_eq!(merged.aws_profile, None);
+    assert_eq!(
+        merged.aws_role_arn.as_deref(),
+        Some("arn:aws:iam::123456789012:role/dest"),
+    );
+    assert!(merged.gcloud_application_default_credentials);
+}
+
+/// The path to Google Cloud's "application default credentials".
+///
+/// We check `GOOGLE_APPLICATION_CREDENTIALS` first, and then look for the file
+/// written by `gcloud auth application-default login`.
+fn application_default_credentials_path() -> Result<PathBuf> {
+    if let Some(path) = try_var("GOOGLE_APPLICATION_CREDENTIALS")? {
+        return Ok(PathBuf::from(path));
+    }
+    let gcloud_config_dir = match try_var("CLOUDSDK_CONFIG")? {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            // `gcloud` uses `%APPDATA%` on Windows and `~/.config` elsewhere,
+            // even on the Mac.
+            #[cfg(windows)]
+            let base = dirs::config_dir();
+            #[cfg(not(windows))]
+            let base = dirs::home_dir().map(|home| home.join(".config"));
+            base.ok_or_else(|| {
+                format_err!("cannot find directory containing gcloud configuration")
+            })?
+            .join("gcloud")
+        }
+    };
+    Ok(gcloud_config_dir.join("application_default_credentials.json"))
 }
 
 /// An interface for looking up credentials.
@@ -280,11 +553,11 @@ impl CredentialsSource for EnvCredentialsSource {
             data.insert(self.mapping[0].key.to_owned(), value);
             for m in &self.mapping[1..] {
                 if m.optional {
-                    if let Some(value) = try_var(&m.var)? {
+                    if let Some(value) = try_var(m.var)? {
                         data.insert(m.key.to_owned(), value);
                     }
                 } else {
-                    data.insert(m.key.to_owned(), var(&m.var)?);
+                    data.insert(m.key.to_owned(), var(m.var)?);
                 }
             }
             Ok(Some(Credentials {
@@ -398,3 +671,230 @@ impl CredentialsSource for CredentialsSources {
         Ok(None)
     }
 }
+
+/// Look up AWS credentials using a named profile, an assumed role, or both.
+#[derive(Debug)]
+struct AwsProfileCredentialsSource {
+    /// A profile in `~/.aws/credentials` or `~/.aws/config`. If this is
+    /// missing, we start from our usual environment variables.
+    profile: Option<String>,
+    /// An IAM role to assume.
+    role_arn: Option<String>,
+}
+
+impl fmt::Display for AwsProfileCredentialsSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.profile {
+            Some(profile) => write!(f, "- The AWS profile {:?}", profile)?,
+            None => write!(f, "{}", aws_env_source().to_string().trim_end())?,
+        }
+        if let Some(role_arn) = &self.role_arn {
+            write!(f, ", used to assume the role {}", role_arn)?;
+        }
+        writeln!(f)
+    }
+}
+
+#[async_trait]
+impl CredentialsSource for AwsProfileCredentialsSource {
+    async fn get_credentials(&self) -> Result<Option<Credentials>> {
+        let mut creds = match &self.profile {
+            Some(profile) => {
+                AwsConfigFiles::load().await?.credentials(profile).await?
+            }
+            None => match aws_env_source().get_credentials().await? {
+                Some(creds) => creds,
+                None => return Ok(None),
+            },
+        };
+        if let Some(role_arn) = &self.role_arn {
+            creds = assume_role_credentials(&creds, role_arn).await?;
+        }
+        Ok(Some(creds))
+    }
+}
+
+/// Use `base` to assume `role_arn`, and return the role's credentials. These
+/// will be in the same region as `base`.
+async fn assume_role_credentials(
+    base: &Credentials,
+    role_arn: &str,
+) -> Result<Credentials> {
+    let base_aws = AwsCredentials {
+        access_key_id: base.get_required("access_key_id")?.to_owned(),
+        secret_access_key: base.get_required("secret_access_key")?.to_owned(),
+        session_token: base.get_optional("session_token").map(|t| t.to_owned()),
+    };
+    let role = assume_role(&base_aws, role_arn).await?;
+    let mut data = HashMap::new();
+    data.insert(
+        "access_key_id".to_owned(),
+        role.credentials.access_key_id.clone(),
+    );
+    data.insert(
+        "secret_access_key".to_owned(),
+        role.credentials.secret_access_key.clone(),
+    );
+    if let Some(session_token) = &role.credentials.session_token {
+        data.insert("session_token".to_owned(), session_token.to_owned());
+    }
+    if let Some(region) = base.get_optional("default_region") {
+        data.insert("default_region".to_owned(), region.to_owned());
+    }
+    let lifetime = (role.expiration - Utc::now())
+        .to_std()
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    Ok(Credentials {
+        data,
+        expires: Some(Instant::now() + lifetime),
+    })
+}
+
+/// Sections of an INI file, indexed by name.
+type IniSections = HashMap<String, HashMap<String, String>>;
+
+/// Parse the simple INI format used by the AWS CLI's configuration files.
+fn parse_ini(text: &str) -> IniSections {
+    let mut sections = IniSections::new();
+    let mut current = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        } else if let Some(name) =
+            line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+        {
+            let name = name.trim().to_owned();
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+        } else if let (Some(section), Some(eq)) = (&current, line.find('=')) {
+            sections
+                .get_mut(section)
+                .expect("section should exist")
+                .insert(
+                    line[..eq].trim().to_owned(),
+                    line[eq + 1..].trim().to_owned(),
+                );
+        }
+    }
+    sections
+}
+
+#[test]
+fn parses_aws_ini_files() {
+    let ini = parse_ini(
+        "# comment\n[default]\naws_access_key_id = AKIA1\n\n[profile prod]\nregion=us-west-2\nrole_arn = arn:aws:iam::1:role/r\n",
+    );
+    assert_eq!(ini["default"]["aws_access_key_id"], "AKIA1");
+    assert_eq!(ini["profile prod"]["region"], "us-west-2");
+    assert_eq!(ini["profile prod"]["role_arn"], "arn:aws:iam::1:role/r");
+}
+
+/// The AWS CLI's `credentials` and `config` files.
+struct AwsConfigFiles {
+    /// The contents of `~/.aws/credentials`.
+    credentials: IniSections,
+    /// The contents of `~/.aws/config`.
+    config: IniSections,
+}
+
+impl AwsConfigFiles {
+    /// Load our AWS configuration files, respecting the same environment
+    /// variables as the AWS CLI. Missing files are treated as empty.
+    async fn load() -> Result<AwsConfigFiles> {
+        let aws_dir = dirs::home_dir()
+            .ok_or_else(|| format_err!("cannot find home directory"))?
+            .join(".aws");
+        let credentials_path = try_var("AWS_SHARED_CREDENTIALS_FILE")?
+            .map(PathBuf::from)
+            .unwrap_or_else(|| aws_dir.join("credentials"));
+        let config_path = try_var("AWS_CONFIG_FILE")?
+            .map(PathBuf::from)
+            .unwrap_or_else(|| aws_dir.join("config"));
+        Ok(AwsConfigFiles {
+            credentials: read_ini_if_exists(&credentials_path).await?,
+            config: read_ini_if_exists(&config_path).await?,
+        })
+    }
+
+    /// Look up `key` for `profile`, checking `credentials` before `config`.
+    fn get(&self, profile: &str, key: &str) -> Option<&str> {
+        let config_section = if profile == "default" {
+            profile.to_owned()
+        } else {
+            format!("profile {}", profile)
+        };
+        self.credentials
+            .get(profile)
+            .and_then(|section| section.get(key))
+            .or_else(|| {
+                self.config
+                    .get(&config_section)
+                    .and_then(|section| section.get(key))
+            })
+            .map(|value| &value[..])
+    }
+
+    /// Get the credentials for `profile`. If the profile specifies a
+    /// `role_arn`, we assume that role using the keys in its `source_profile`.
+    async fn credentials(&self, profile: &str) -> Result<Credentials> {
+        let role_arn = self.get(profile, "role_arn");
+        let keys_profile = match role_arn {
+            Some(_) => self.get(profile, "source_profile").ok_or_else(|| {
+                format_err!(
+                    "AWS profile {:?} has a role_arn, but it has no source_profile",
+                    profile,
+                )
+            })?,
+            None => profile,
+        };
+
+        let mut data = HashMap::new();
+        let access_key_id =
+            self.get(keys_profile, "aws_access_key_id").ok_or_else(|| {
+                format_err!("could not find keys for AWS profile {:?}", keys_profile)
+            })?;
+        data.insert("access_key_id".to_owned(), access_key_id.to_owned());
+        let secret_access_key = self
+            .get(keys_profile, "aws_secret_access_key")
+            .ok_or_else(|| {
+                format_err!(
+                    "no aws_secret_access_key for AWS profile {:?}",
+                    keys_profile,
+                )
+            })?;
+        data.insert("secret_access_key".to_owned(), secret_access_key.to_owned());
+        if let Some(session_token) = self.get(keys_profile, "aws_session_token") {
+            data.insert("session_token".to_owned(), session_token.to_owned());
+        }
+        let region = match self
+            .get(profile, "region")
+            .or_else(|| self.get(keys_profile, "region"))
+        {
+            Some(region) => Some(region.to_owned()),
+            None => try_var("AWS_DEFAULT_REGION")?,
+        };
+        if let Some(region) = region {
+            data.insert("default_region".to_owned(), region);
+        }
+        let creds = Credentials {
+            data,
+            expires: None,
+        };
+
+        match role_arn {
+            Some(role_arn) => assume_role_credentials(&creds, role_arn).await,
+            None => Ok(creds),
+        }
+    }
+}
+
+/// Read and parse the INI file at `path`, or return no sections if it doesn't
+/// exist.
+async fn read_ini_if_exists(path: &Path) -> Result<IniSections> {
+    match fs::read_to_string(path).await {
+        Ok(text) => Ok(parse_ini(&text)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(IniSections::new()),
+        Err(err) => Err(format_err!("error reading {}: {}", path.display(), err)),
+    }
+}
@@ -95,7 +95,7 @@ pub(crate) fn csv_stream_name<'a>(
         // only a single input, and we therefore want to extract the "basename",
         // or filename without any directories.
         file_path
-            .rsplitn(2, '/')
+            .rsplit('/')
             .next()
             .expect("should have '/' in URL")
     } else if file_path.starts_with(base_path) {
@@ -125,7 +125,7 @@ pub(crate) fn csv_stream_name<'a>(
 
     // Now strip any extension.
     let name = basename_or_relative
-        .splitn(2, '.')
+        .split('.')
         .next()
         .ok_or_else(|| format_err!("can't get basename of {}", file_path))?;
     Ok(name)
@@ -0,0 +1,356 @@
+//! Parsing non-standard date and timestamp formats in our input data.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::{
+    collections::HashMap,
+    io::{BufReader, BufWriter},
+    str::FromStr,
+};
+
+use crate::common::*;
+use crate::schema::DataType;
+use crate::transform::spawn_sync_transform;
+
+/// How a date or timestamp is formatted in our input data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum DateFormat {
+    /// A `strftime`-style format string, such as `%m/%d/%Y`.
+    Strftime(String),
+    /// Seconds since the Unix epoch.
+    EpochSeconds,
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+}
+
+impl FromStr for DateFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "epoch" | "epoch_seconds" => Ok(DateFormat::EpochSeconds),
+            "epoch_millis" => Ok(DateFormat::EpochMillis),
+            _ if s.contains('%') => Ok(DateFormat::Strftime(s.to_owned())),
+            _ => Err(format_err!(
+                "expected a format like \"%m/%d/%Y\", \"epoch\" or \"epoch_millis\", found {:?}",
+                s,
+            )),
+        }
+    }
+}
+
+impl DateFormat {
+    /// Parse `cell` as a timestamp in this format.
+    fn parse_naive_date_time(&self, cell: &str) -> Result<NaiveDateTime> {
+        match self {
+            DateFormat::Strftime(fmt) => Ok(NaiveDateTime::parse_from_str(cell, fmt)
+                .or_else(|_| {
+                    // If `fmt` doesn't include a time, treat it as midnight.
+                    NaiveDate::parse_from_str(cell, fmt).map(|d| d.and_hms(0, 0, 0))
+                })
+                .with_context(|_| {
+                    format!("cannot parse {:?} using {:?}", cell, fmt)
+                })?),
+            DateFormat::EpochSeconds => {
+                let secs = cell
+                    .parse::<i64>()
+                    .with_context(|_| format!("cannot parse {:?} as seconds", cell))?;
+                NaiveDateTime::from_timestamp_opt(secs, 0)
+                    .ok_or_else(|| format_err!("timestamp {} is out of range", secs))
+            }
+            DateFormat::EpochMillis => {
+                let millis = cell.parse::<i64>().with_context(|_| {
+                    format!("cannot parse {:?} as milliseconds", cell)
+                })?;
+                let nanos = u32::try_from(millis.rem_euclid(1000) * 1_000_000)
+                    .expect("remainder should always fit in u32");
+                NaiveDateTime::from_timestamp_opt(millis.div_euclid(1000), nanos)
+                    .ok_or_else(|| format_err!("timestamp {} is out of range", millis))
+            }
+        }
+    }
+
+    /// Parse `cell` as a timestamp with a time zone in this format. If the
+    /// format doesn't include a time zone, we assume UTC.
+    fn parse_utc_date_time(&self, cell: &str) -> Result<DateTime<Utc>> {
+        if let DateFormat::Strftime(fmt) = self {
+            if let Ok(timestamp) = DateTime::parse_from_str(cell, fmt) {
+                return Ok(timestamp.with_timezone(&Utc));
+            }
+        }
+        Ok(DateTime::from_utc(self.parse_naive_date_time(cell)?, Utc))
+    }
+
+    /// Convert `cell` from this format to our CSV interchange format for
+    /// `data_type`.
+    fn normalize(&self, data_type: &DataType, cell: &str) -> Result<String> {
+        match data_type {
+            DataType::Date => match self {
+                DateFormat::Strftime(fmt) => Ok(NaiveDate::parse_from_str(cell, fmt)
+                    .with_context(|_| {
+                        format!("cannot parse {:?} using {:?}", cell, fmt)
+                    })?
+                    .format("%Y-%m-%d")
+                    .to_string()),
+                _ => Ok(self
+                    .parse_naive_date_time(cell)?
+                    .date()
+                    .format("%Y-%m-%d")
+                    .to_string()),
+            },
+            DataType::TimestampWithoutTimeZone => Ok(self
+                .parse_naive_date_time(cell)?
+                .format("%Y-%m-%dT%H:%M:%S%.f")
+                .to_string()),
+            DataType::TimestampWithTimeZone => Ok(self
+                .parse_utc_date_time(cell)?
+                .format("%Y-%m-%dT%H:%M:%S%.fZ")
+                .to_string()),
+            _ => Err(format_err!("cannot apply a date format to {:?}", data_type)),
+        }
+    }
+}
+
+/// Date and timestamp formats to use when parsing input data.
+///
+/// By default, we expect input data to use our CSV interchange format. This
+/// allows converting other formats as we copy, so that mismatched formats are
+/// caught before we start loading data.
+#[derive(Clone, Debug, Default)]
+pub struct DateFormats {
+    /// The format to use for `DataType::Date` columns.
+    date: Option<DateFormat>,
+    /// The format to use for timestamp columns.
+    timestamp: Option<DateFormat>,
+    /// Formats for specific columns.
+    columns: HashMap<String, DateFormat>,
+}
+
+impl DateFormats {
+    /// Are we using our standard interchange formats for everything?
+    pub fn is_empty(&self) -> bool {
+        self.date.is_none() && self.timestamp.is_none() && self.columns.is_empty()
+    }
+
+    /// Add a date format, either of the form `FORMAT` for all date columns,
+    /// or `COLUMN=FORMAT` for a single column.
+    pub fn add_date_format(&mut self, spec: &str) -> Result<()> {
+        if let Some(date) = self.add_column_format(spec)? {
+            self.date = Some(date);
+        }
+        Ok(())
+    }
+
+    /// Add a timestamp format, either of the form `FORMAT` for all timestamp
+    /// columns, or `COLUMN=FORMAT` for a single column.
+    pub fn add_timestamp_format(&mut self, spec: &str) -> Result<()> {
+        if let Some(timestamp) = self.add_column_format(spec)? {
+            self.timestamp = Some(timestamp);
+        }
+        Ok(())
+    }
+
+    /// If `spec` has the form `COLUMN=FORMAT`, record the format for `COLUMN`
+    /// and return `None`. Otherwise, return the parsed format.
+    fn add_column_format(&mut self, spec: &str) -> Result<Option<DateFormat>> {
+        match spec.find('=') {
+            Some(idx) if !spec[..idx].contains('%') => {
+                let format = spec[idx + 1..].parse::<DateFormat>()?;
+                self.columns.insert(spec[..idx].to_owned(), format);
+                Ok(None)
+            }
+            _ => Ok(Some(spec.parse::<DateFormat>()?)),
+        }
+    }
+
+    /// Look up the format for each column in `schema`, or `None` if the column
+    /// should be left alone.
+    fn formats_for_schema(&self, schema: &Schema) -> Result<Vec<Option<DateFormat>>> {
+        for name in self.columns.keys() {
+            if !schema.table.columns.iter().any(|c| &c.name == name) {
+                return Err(format_err!("cannot find date column {:?}", name));
+            }
+        }
+        schema
+            .table
+            .columns
+            .iter()
+            .map(|col| {
+                let data_type = resolve_data_type(schema, &col.data_type);
+                let default = match data_type {
+                    DataType::Date => self.date.as_ref(),
+                    DataType::TimestampWithoutTimeZone
+                    | DataType::TimestampWithTimeZone => self.timestamp.as_ref(),
+                    _ => None,
+                };
+                match self.columns.get(&col.name) {
+                    Some(_) if !is_date_type(data_type) => Err(format_err!(
+                        "column {:?} is not a date or timestamp column",
+                        col.name,
+                    )),
+                    Some(format) => Ok(Some(format.to_owned())),
+                    None => Ok(default.cloned()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Look up named data types.
+pub(crate) fn resolve_data_type<'a>(
+    schema: &'a Schema,
+    data_type: &'a DataType,
+) -> &'a DataType {
+    match data_type {
+        DataType::Named(name) => {
+            resolve_data_type(schema, schema.data_type_for_name(name))
+        }
+        _ => data_type,
+    }
+}
+
+/// Can we apply a `DateFormat` to `data_type`?
+fn is_date_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Date
+            | DataType::TimestampWithoutTimeZone
+            | DataType::TimestampWithTimeZone
+    )
+}
+
+/// Convert dates and timestamps in `streams` from the formats specified by
+/// `date_formats` to our CSV interchange format.
+pub(crate) fn normalize_dates(
+    ctx: Context,
+    schema: &Schema,
+    date_formats: &DateFormats,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    // Figure out what we need to convert, and report any errors now.
+    let formats = date_formats.formats_for_schema(schema)?;
+    let mut columns = HashMap::new();
+    for (col, format) in schema.table.columns.iter().zip(formats) {
+        if let Some(format) = format {
+            let data_type = resolve_data_type(schema, &col.data_type).to_owned();
+            columns.insert(col.name.clone(), (data_type, format));
+        }
+    }
+
+    let normalized = streams.and_then(move |stream| {
+        let ctx = ctx.clone();
+        let columns = columns.clone();
+        async move {
+            let data = spawn_sync_transform(
+                ctx,
+                "normalize_dates".to_owned(),
+                stream.data,
+                move |_ctx, rdr, wtr| copy_normalizing_dates(&columns, rdr, wtr),
+            )?;
+            Ok(CsvStream {
+                name: stream.name,
+                data,
+            })
+        }
+        .boxed()
+    });
+    Ok(normalized.boxed())
+}
+
+/// Copy CSV data from `rdr` to `wtr`, converting the formats of the columns
+/// in `columns`.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+fn copy_normalizing_dates(
+    columns: &HashMap<String, (DataType, DateFormat)>,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    let headers = rdr.headers()?.to_owned();
+    wtr.write_record(&headers)?;
+    let conversions = headers
+        .iter()
+        .map(|name| columns.get(name))
+        .collect::<Vec<_>>();
+
+    let mut record = csv::StringRecord::new();
+    let mut row_idx = 0;
+    while rdr.read_record(&mut record)? {
+        row_idx += 1;
+        for (idx, cell) in record.iter().enumerate() {
+            match conversions.get(idx).and_then(|c| *c) {
+                Some((data_type, format)) if !cell.is_empty() => {
+                    let normalized =
+                        format.normalize(data_type, cell).with_context(|_| {
+                            ErrorKind::InvalidData {
+                                row: row_idx,
+                                column: headers[idx].to_owned(),
+                                value: cell.to_owned(),
+                            }
+                        })?;
+                    wtr.write_field(normalized)?;
+                }
+                _ => wtr.write_field(cell)?,
+            }
+        }
+        wtr.write_record(None::<&[u8]>)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[test]
+fn parses_date_formats() {
+    let mut formats = DateFormats::default();
+    assert!(formats.is_empty());
+    formats.add_date_format("%m/%d/%Y").unwrap();
+    formats.add_timestamp_format("created_at=epoch").unwrap();
+    formats.add_timestamp_format("%Y%m%d %H:%M=%S").unwrap();
+    assert_eq!(
+        formats.date,
+        Some(DateFormat::Strftime("%m/%d/%Y".to_owned()))
+    );
+    assert_eq!(
+        formats.timestamp,
+        Some(DateFormat::Strftime("%Y%m%d %H:%M=%S".to_owned())),
+    );
+    assert_eq!(formats.columns["created_at"], DateFormat::EpochSeconds);
+    assert!(formats.add_date_format("MM/DD/YYYY").is_err());
+}
+
+#[test]
+fn normalizes_dates() {
+    let cases = &[
+        (DataType::Date, "%m/%d/%Y", "07/20/1969", "1969-07-20"),
+        (DataType::Date, "epoch", "-14182940", "1969-07-20"),
+        (
+            DataType::TimestampWithoutTimeZone,
+            "%m/%d/%Y %H:%M",
+            "07/20/1969 20:17",
+            "1969-07-20T20:17:00",
+        ),
+        (
+            DataType::TimestampWithoutTimeZone,
+            "%m/%d/%Y",
+            "07/20/1969",
+            "1969-07-20T00:00:00",
+        ),
+        (
+            DataType::TimestampWithTimeZone,
+            "%d/%m/%Y %H:%M:%S %z",
+            "20/07/1969 21:17:39 +0100",
+            "1969-07-20T20:17:39Z",
+        ),
+        (
+            DataType::TimestampWithTimeZone,
+            "epoch_millis",
+            "-14182940500",
+            "1969-07-20T20:17:39.500Z",
+        ),
+    ];
+    for &(ref data_type, format, input, expected) in cases {
+        let format = format.parse::<DateFormat>().unwrap();
+        assert_eq!(format.normalize(data_type, input).unwrap(), expected);
+    }
+}
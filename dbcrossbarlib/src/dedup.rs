@@ -0,0 +1,327 @@
+//! Removing duplicate rows as we copy them.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io::{BufReader, BufWriter},
+    str,
+};
+
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::date_formats::resolve_data_type;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::DataType;
+use crate::tokio_glue::box_stream_once;
+use crate::transform::spawn_sync_transform;
+
+/// How to remove duplicate rows.
+///
+/// Columns are named using their destination names.
+#[derive(Clone, Debug, Default)]
+pub struct Deduplication {
+    /// Remove rows which are exact duplicates of an earlier row.
+    exact: bool,
+    /// Keep only one row for each distinct value of these columns.
+    keys: Vec<String>,
+    /// When deduplicating using `keys`, keep the row with the largest value of
+    /// this column, instead of the last row.
+    order_by: Option<String>,
+}
+
+impl Deduplication {
+    /// Are we removing any duplicates?
+    pub fn is_empty(&self) -> bool {
+        !self.exact && self.keys.is_empty()
+    }
+
+    /// Remove rows which are exact duplicates of an earlier row.
+    pub fn set_exact(&mut self, exact: bool) {
+        self.exact = exact;
+    }
+
+    /// Keep only one row for each distinct value of the comma-separated
+    /// columns in `spec`.
+    pub fn set_keys(&mut self, spec: &str) -> Result<()> {
+        let keys = spec
+            .split(',')
+            .map(|k| k.trim().to_owned())
+            .collect::<Vec<_>>();
+        if keys.iter().any(|k| k.is_empty()) {
+            return Err(format_err!("expected COL1,COL2,..., found {:?}", spec));
+        }
+        self.keys = keys;
+        Ok(())
+    }
+
+    /// When deduplicating by key, keep the row with the largest value of
+    /// `column`.
+    pub fn set_order_by(&mut self, column: &str) {
+        self.order_by = Some(column.to_owned());
+    }
+
+    /// Make sure that we can use these options with `schema`.
+    pub(crate) fn validate(&self, schema: &Schema) -> Result<()> {
+        if self.exact && !self.keys.is_empty() {
+            return Err(format_err!(
+                "cannot remove exact duplicates and duplicate keys at the same time"
+            ));
+        }
+        if self.order_by.is_some() && self.keys.is_empty() {
+            return Err(format_err!(
+                "must specify key columns when choosing rows by order"
+            ));
+        }
+        for name in self.keys.iter().chain(self.order_by.iter()) {
+            if !schema.table.columns.iter().any(|c| &c.name == name) {
+                return Err(format_err!(
+                    "cannot deduplicate using {:?}, because it is not being copied",
+                    name,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remove duplicate rows from `streams`, which use the column names in
+/// `schema`.
+///
+/// Duplicates may appear in different streams, so we combine all our streams
+/// into one and process it in a single thread. To remove exact duplicates, we
+/// need to remember a hash of every distinct row. To keep one row per key, we
+/// need to hold the chosen rows in memory until we've read all our input.
+pub(crate) fn dedup_rows(
+    ctx: Context,
+    schema: &Schema,
+    dedup: &Deduplication,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    dedup.validate(schema)?;
+    let order_type = dedup.order_by.as_ref().map(|name| {
+        let column = schema
+            .table
+            .columns
+            .iter()
+            .find(|c| &c.name == name)
+            .expect("validate should have checked order_by column");
+        resolve_data_type(schema, &column.data_type).to_owned()
+    });
+    let dedup = dedup.to_owned();
+    let stream = concatenate_csv_streams(ctx.clone(), streams)?;
+    let data = spawn_sync_transform(
+        ctx,
+        "dedup_rows".to_owned(),
+        stream.data,
+        move |ctx, rdr, wtr| {
+            let dropped = copy_dedup_rows(&dedup, order_type.as_ref(), rdr, wtr)?;
+            info!(ctx.log(), "dropped {} duplicate rows", dropped);
+            Ok(())
+        },
+    )?;
+    Ok(box_stream_once(Ok(CsvStream {
+        name: schema.table.name.clone(),
+        data,
+    })))
+}
+
+/// Copy CSV data from `rdr` to `wtr`, removing duplicates. Returns the number
+/// of rows dropped.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+fn copy_dedup_rows(
+    dedup: &Deduplication,
+    order_type: Option<&DataType>,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<usize> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    let headers = rdr.byte_headers()?.to_owned();
+    wtr.write_byte_record(&headers)?;
+    let column_index = |name: &str| -> Result<usize> {
+        headers
+            .iter()
+            .position(|h| h == name.as_bytes())
+            .ok_or_else(|| format_err!("could not find column {:?} in data", name))
+    };
+
+    let mut dropped = 0;
+    let mut record = csv::ByteRecord::new();
+    if dedup.exact {
+        // Remember a hash of each row we've already written.
+        let mut seen = HashSet::new();
+        while rdr.read_byte_record(&mut record)? {
+            if seen.insert(record_digest(&record, 0..record.len())) {
+                wtr.write_byte_record(&record)?;
+            } else {
+                dropped += 1;
+            }
+        }
+    } else {
+        let key_indices = dedup
+            .keys
+            .iter()
+            .map(|k| column_index(k))
+            .collect::<Result<Vec<_>>>()?;
+        let order_index = dedup
+            .order_by
+            .as_ref()
+            .map(|o| column_index(o))
+            .transpose()?;
+
+        // Keep the best row for each key, in the order each key first appears.
+        let mut best_rows: Vec<csv::ByteRecord> = vec![];
+        let mut row_for_key = HashMap::new();
+        let mut row_idx = 0;
+        while rdr.read_byte_record(&mut record)? {
+            row_idx += 1;
+            let key = record_digest(&record, key_indices.iter().copied());
+            match row_for_key.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(best_rows.len());
+                    best_rows.push(record.clone());
+                }
+                Entry::Occupied(entry) => {
+                    dropped += 1;
+                    let best = &mut best_rows[*entry.get()];
+                    let replace = match (order_index, order_type) {
+                        (Some(idx), Some(data_type)) => {
+                            compare_cells(data_type, &record[idx], &best[idx])
+                                .with_context(|_| ErrorKind::InvalidData {
+                                    row: row_idx,
+                                    column: dedup.order_by.clone().unwrap_or_default(),
+                                    value: String::from_utf8_lossy(&record[idx])
+                                        .into_owned(),
+                                })?
+                                != Ordering::Less
+                        }
+                        // With no ordering column, the last row wins.
+                        _ => true,
+                    };
+                    if replace {
+                        best.clone_from(&record);
+                    }
+                }
+            }
+        }
+        for row in &best_rows {
+            wtr.write_byte_record(row)?;
+        }
+    }
+    wtr.flush()?;
+    Ok(dropped)
+}
+
+/// Hash the cells of `record` at `indices`. We hash the length of each cell,
+/// too, so that `("ab", "c")` and `("a", "bc")` are different.
+fn record_digest(
+    record: &csv::ByteRecord,
+    indices: impl Iterator<Item = usize>,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for idx in indices {
+        let cell = &record[idx];
+        hasher.update(cell.len().to_le_bytes());
+        hasher.update(cell);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Compare two CSV cells containing values of `data_type`. Empty cells are
+/// `NULL`, which sorts before everything else.
+fn compare_cells(data_type: &DataType, a: &[u8], b: &[u8]) -> Result<Ordering> {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return Ok(Ordering::Equal),
+        (true, false) => return Ok(Ordering::Less),
+        (false, true) => return Ok(Ordering::Greater),
+        (false, false) => {}
+    }
+    let (a, b) = (str::from_utf8(a)?, str::from_utf8(b)?);
+    Ok(match data_type {
+        DataType::Date => {
+            NaiveDate::from_csv_cell(a)?.cmp(&NaiveDate::from_csv_cell(b)?)
+        }
+        DataType::Decimal | DataType::Float32 | DataType::Float64 => {
+            let (a, b) = (f64::from_csv_cell(a)?, f64::from_csv_cell(b)?);
+            a.partial_cmp(&b)
+                .ok_or_else(|| format_err!("cannot compare {} and {}", a, b))?
+        }
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            i64::from_csv_cell(a)?.cmp(&i64::from_csv_cell(b)?)
+        }
+        DataType::TimestampWithoutTimeZone => {
+            NaiveDateTime::from_csv_cell(a)?.cmp(&NaiveDateTime::from_csv_cell(b)?)
+        }
+        DataType::TimestampWithTimeZone => {
+            DateTime::<Utc>::from_csv_cell(a)?.cmp(&DateTime::<Utc>::from_csv_cell(b)?)
+        }
+        _ => a.cmp(b),
+    })
+}
+
+#[test]
+fn removes_duplicates() {
+    use crate::test_util::SharedBuffer;
+
+    let dedup_csv = |dedup: &Deduplication, order_type: Option<&DataType>| {
+        let input = "id,version,name\n1,2,a\n2,1,b\n1,10,c\n2,1,b\n1,9,d\n";
+        let output = SharedBuffer::default();
+        copy_dedup_rows(
+            dedup,
+            order_type,
+            Box::new(input.as_bytes()),
+            Box::new(output.clone()),
+        )
+        .unwrap();
+        let bytes = output.contents();
+        String::from_utf8(bytes).unwrap()
+    };
+
+    let mut exact = Deduplication::default();
+    exact.set_exact(true);
+    assert_eq!(
+        dedup_csv(&exact, None),
+        "id,version,name\n1,2,a\n2,1,b\n1,10,c\n1,9,d\n",
+    );
+
+    let mut last = Deduplication::default();
+    last.set_keys("id").unwrap();
+    assert_eq!(dedup_csv(&last, None), "id,version,name\n1,9,d\n2,1,b\n");
+
+    // Compare `version` as an integer, not as text.
+    let mut latest = last.clone();
+    latest.set_order_by("version");
+    assert_eq!(
+        dedup_csv(&latest, Some(&DataType::Int32)),
+        "id,version,name\n1,10,c\n2,1,b\n",
+    );
+}
+
+#[test]
+fn compares_cells() {
+    assert_eq!(
+        compare_cells(&DataType::Int64, b"9", b"10").unwrap(),
+        Ordering::Less,
+    );
+    assert_eq!(
+        compare_cells(&DataType::Text, b"9", b"10").unwrap(),
+        Ordering::Greater,
+    );
+    assert_eq!(
+        compare_cells(
+            &DataType::TimestampWithTimeZone,
+            b"2020-01-01T10:00:00+05:00",
+            b"2020-01-01T06:00:00Z",
+        )
+        .unwrap(),
+        Ordering::Less,
+    );
+    assert_eq!(
+        compare_cells(&DataType::Int64, b"", b"1").unwrap(),
+        Ordering::Less,
+    );
+    assert!(compare_cells(&DataType::Int64, b"x", b"1").is_err());
+}
@@ -1,4 +1,7 @@
-use serde::de::DeserializeOwned;
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer,
+};
 use serde_json::{Map, Value};
 use std::{ops::Range, str::FromStr, sync::Arc};
 
@@ -34,7 +37,7 @@ impl DriverArguments {
 
     /// Convert these arguments to a JSON object. We treat keys of the form
     /// "parent.nested" as `{ "parent": { "nested": ... } }`.
-    fn to_json(&self) -> Result<Value> {
+    pub(crate) fn to_json(&self) -> Result<Value> {
         let mut json = Value::Object(Map::new());
         for arg in &self.args {
             insert_into_json(
@@ -47,10 +50,25 @@ impl DriverArguments {
         Ok(json)
     }
 
+    /// Split these arguments into two collections: those whose top-level name
+    /// is one of `names`, and everything else. Both keep their original order.
+    pub(crate) fn partition(self, names: &[&str]) -> (Self, Self) {
+        let (matching, rest) = self
+            .args
+            .into_iter()
+            .partition(|arg| names.contains(&arg.name.top_level()));
+        (Self { args: matching }, Self { args: rest })
+    }
+
     /// Deserialize our driver arguments into a struct of type `T` using
     /// `serde`. This obeys the same nesting rules as `[DriverArguments::to_json]`.
     pub(crate) fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
-        Ok(serde_json::from_value(self.to_json()?)?)
+        serde_json::from_value(self.to_json()?).map_err(|err| {
+            ErrorKind::DriverArguments {
+                message: err.to_string(),
+            }
+            .into()
+        })
     }
 }
 
@@ -74,9 +92,97 @@ fn to_json_detects_conflicts() {
     }
 }
 
+#[test]
+fn partition_splits_by_top_level_name() {
+    use serde_json::json;
+    let raw_args = &["a=b", "c.d=x", "e=y", "a2=z"];
+    let args = DriverArguments::from_cli_args(raw_args).unwrap();
+    let (matching, rest) = args.partition(&["a", "c"]);
+    assert_eq!(
+        matching.to_json().unwrap(),
+        json!({"a": "b", "c": { "d": "x" } }),
+    );
+    assert_eq!(rest.to_json().unwrap(), json!({"e": "y", "a2": "z"}));
+}
+
+/// Deserialize a boolean driver argument. All driver arguments are passed to us
+/// as strings, so we accept `"true"` and `"false"` here.
+///
+/// Use this as `#[serde(default, deserialize_with = "deserialize_bool_arg")]`.
+pub(crate) fn deserialize_bool_arg<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match &s[..] {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(de::Error::custom(format!(
+            "expected \"true\" or \"false\", found {:?}",
+            s,
+        ))),
+    }
+}
+
+#[test]
+fn deserialize_bool_arg_accepts_true_and_false() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Example {
+        #[serde(default, deserialize_with = "deserialize_bool_arg")]
+        flag: bool,
+    }
+
+    let parse = |raw_args: &[&str]| {
+        DriverArguments::from_cli_args(raw_args)
+            .unwrap()
+            .deserialize::<Example>()
+    };
+    assert!(!parse(&[]).unwrap().flag);
+    assert!(parse(&["flag=true"]).unwrap().flag);
+    assert!(!parse(&["flag=false"]).unwrap().flag);
+    assert!(parse(&["flag=yes"]).is_err());
+}
+
+/// Deserialize an optional integer driver argument. All driver arguments are
+/// passed to us as strings, so we parse them here.
+///
+/// Use this as `#[serde(default, deserialize_with = "deserialize_optional_usize_arg")]`.
+pub(crate) fn deserialize_optional_usize_arg<'de, D>(
+    deserializer: D,
+) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse::<usize>().map(Some).map_err(|_| {
+        de::Error::custom(format!("expected a non-negative integer, found {:?}", s))
+    })
+}
+
+#[test]
+fn deserialize_optional_usize_arg_accepts_integers() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Example {
+        #[serde(default, deserialize_with = "deserialize_optional_usize_arg")]
+        count: Option<usize>,
+    }
+
+    let parse = |raw_args: &[&str]| {
+        DriverArguments::from_cli_args(raw_args)
+            .unwrap()
+            .deserialize::<Example>()
+    };
+    assert_eq!(parse(&[]).unwrap().count, None);
+    assert_eq!(parse(&["count=16"]).unwrap().count, Some(16));
+    assert!(parse(&["count=-1"]).is_err());
+    assert!(parse(&["count=many"]).is_err());
+}
+
 /// The name of a driver argument.
 #[derive(Clone, Debug)]
-pub(self) struct Arg {
+struct Arg {
     file_info: Arc<FileInfo>,
     name: ArgName,
     value: Value,
@@ -105,11 +211,23 @@ impl FromStr for Arg {
 /// non-`Component::FinalArray` value, and finish with a optional
 /// `Component::FinalArray`. These rules are enforced by the parser's grammar.
 #[derive(Clone, Debug)]
-pub(self) struct ArgName(Vec<Component>);
+struct ArgName(Vec<Component>);
+
+impl ArgName {
+    /// The first component of this name, which is always a member name.
+    fn top_level(&self) -> &str {
+        match &self.0[0] {
+            Component::Member(_, name) => name,
+            Component::FinalArray(_) => {
+                unreachable!("driver argument names start with a member")
+            }
+        }
+    }
+}
 
 /// A component of the name of a driver argument.
 #[derive(Clone, Debug)]
-pub(self) enum Component {
+enum Component {
     /// A ".name" or `[name]` expression.
     Member(Range<usize>, String),
     /// A "[]" expression, which can only appear at the end.
@@ -0,0 +1,512 @@
+//! Converting between CSV data and Arrow IPC streams.
+
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Date32Array, Date64Array, Float32Array, Float64Array,
+        Int16Array, Int32Array, Int64Array, Int8Array, LargeStringArray, StringArray,
+        TimestampMicrosecondArray, TimestampMillisecondArray,
+        TimestampNanosecondArray, TimestampSecondArray, UInt16Array, UInt32Array,
+        UInt64Array, UInt8Array,
+    },
+    datatypes::{
+        DataType as ArrowType, DateUnit, Field, Schema as ArrowSchema, TimeUnit,
+    },
+    ipc::{reader::StreamReader, writer::StreamWriter},
+    record_batch::RecordBatch,
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use std::{
+    io::{BufReader, BufWriter},
+    sync::Arc,
+};
+
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::{Column, DataType};
+
+/// How many rows to put in each Arrow record batch.
+const BATCH_SIZE: usize = 64 * 1024;
+
+/// The time zone we use for `timestamp_with_time_zone` columns.
+const UTC: &str = "UTC";
+
+/// Follow `DataType::Named` until we reach a concrete type.
+fn resolve<'a>(schema: &'a Schema, data_type: &'a DataType) -> &'a DataType {
+    match data_type {
+        DataType::Named(name) => resolve(schema, schema.data_type_for_name(name)),
+        other => other,
+    }
+}
+
+/// Build an Arrow schema for our table.
+fn arrow_schema_for(schema: &Schema) -> ArrowSchema {
+    let fields = schema
+        .table
+        .columns
+        .iter()
+        .map(|col| {
+            Field::new(
+                &col.name,
+                arrow_type_for(schema, &col.data_type),
+                col.is_nullable,
+            )
+        })
+        .collect();
+    ArrowSchema::new(fields)
+}
+
+/// Choose an Arrow type for a portable type.
+///
+/// Arrow has types for arrays and structs, but we store them as JSON strings,
+/// like our other types without an obvious Arrow equivalent.
+fn arrow_type_for(schema: &Schema, data_type: &DataType) -> ArrowType {
+    match resolve(schema, data_type) {
+        DataType::Bool => ArrowType::Boolean,
+        DataType::Date => ArrowType::Date32(DateUnit::Day),
+        DataType::Float32 => ArrowType::Float32,
+        DataType::Float64 => ArrowType::Float64,
+        DataType::Int16 => ArrowType::Int16,
+        DataType::Int32 => ArrowType::Int32,
+        DataType::Int64 => ArrowType::Int64,
+        DataType::TimestampWithoutTimeZone => {
+            ArrowType::Timestamp(TimeUnit::Microsecond, None)
+        }
+        DataType::TimestampWithTimeZone => {
+            ArrowType::Timestamp(TimeUnit::Microsecond, Some(UTC.to_owned()))
+        }
+        DataType::Array(_)
+        | DataType::Decimal
+        | DataType::GeoJson(_)
+        | DataType::Json
+        | DataType::Named(_)
+        | DataType::OneOf(_)
+        | DataType::Struct(_)
+        | DataType::Text
+        | DataType::Uuid => ArrowType::Utf8,
+    }
+}
+
+/// Convert an Arrow schema into a portable table named `table_name`.
+fn table_from_arrow(arrow_schema: &ArrowSchema, table_name: &str) -> Result<Table> {
+    let columns = arrow_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let data_type = data_type_from_arrow(field.data_type()).map_err(|_| {
+                ErrorKind::UnsupportedType {
+                    column: field.name().to_owned(),
+                    type_name: format!("{:?}", field.data_type()),
+                }
+            })?;
+            Ok(Column {
+                name: field.name().to_owned(),
+                is_nullable: field.is_nullable(),
+                data_type,
+                comment: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Table {
+        name: table_name.to_owned(),
+        columns,
+    })
+}
+
+/// Convert an Arrow type into a portable type.
+fn data_type_from_arrow(arrow_type: &ArrowType) -> Result<DataType> {
+    match arrow_type {
+        ArrowType::Boolean => Ok(DataType::Bool),
+        ArrowType::Int8 | ArrowType::Int16 | ArrowType::UInt8 => Ok(DataType::Int16),
+        ArrowType::Int32 | ArrowType::UInt16 => Ok(DataType::Int32),
+        ArrowType::Int64 | ArrowType::UInt32 => Ok(DataType::Int64),
+        ArrowType::UInt64 => Ok(DataType::Decimal),
+        ArrowType::Float32 => Ok(DataType::Float32),
+        ArrowType::Float64 => Ok(DataType::Float64),
+        ArrowType::Utf8 | ArrowType::LargeUtf8 => Ok(DataType::Text),
+        ArrowType::Date32(_) | ArrowType::Date64(_) => Ok(DataType::Date),
+        ArrowType::Timestamp(_, None) => Ok(DataType::TimestampWithoutTimeZone),
+        ArrowType::Timestamp(_, Some(_)) => Ok(DataType::TimestampWithTimeZone),
+        other => Err(format_err!("unsupported Arrow type {:?}", other)),
+    }
+}
+
+/// Read the schema at the start of an Arrow IPC stream, and convert it to a
+/// portable schema.
+pub(super) fn read_arrow_stream_schema(
+    rdr: Box<dyn Read>,
+    table_name: &str,
+) -> Result<Schema> {
+    let reader = StreamReader::try_new(BufReader::with_capacity(BUFFER_SIZE, rdr))?;
+    let table = table_from_arrow(&reader.schema(), table_name)?;
+    Schema::from_table(table)
+}
+
+/// Read CSV data from `rdr`, and write it to `wtr` as an Arrow IPC stream.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+pub(super) fn copy_csv_to_arrow(
+    schema: &Schema,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let arrow_schema = Arc::new(arrow_schema_for(schema));
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let wtr = BufWriter::with_capacity(BUFFER_SIZE, wtr);
+    let mut wtr = StreamWriter::try_new(wtr, &arrow_schema)?;
+
+    // Check to make sure our CSV headers and table column names match.
+    let columns = &schema.table.columns;
+    let headers = rdr.headers()?;
+    if headers.len() != columns.len()
+        || headers.iter().zip(columns).any(|(h, c)| h != c.name)
+    {
+        return Err(format_err!(
+            "CSV headers {:?} do not match schema columns {:?}",
+            headers.iter().collect::<Vec<_>>(),
+            columns.iter().map(|c| &c.name).collect::<Vec<_>>(),
+        ));
+    }
+
+    // Read our rows, and write them out one batch at a time.
+    let mut rows = Vec::with_capacity(BATCH_SIZE);
+    let mut first_row = 1;
+    for row in rdr.records() {
+        rows.push(row?);
+        if rows.len() >= BATCH_SIZE {
+            let batch = build_batch(schema, &arrow_schema, &rows, first_row)?;
+            wtr.write(&batch)?;
+            first_row += rows.len();
+            rows.clear();
+        }
+    }
+    if !rows.is_empty() {
+        let batch = build_batch(schema, &arrow_schema, &rows, first_row)?;
+        wtr.write(&batch)?;
+    }
+    wtr.finish()?;
+    Ok(())
+}
+
+/// Convert `rows` into an Arrow record batch. `first_row` is the row number of
+/// `rows[0]`, which we use for error messages.
+fn build_batch(
+    schema: &Schema,
+    arrow_schema: &Arc<ArrowSchema>,
+    rows: &[csv::StringRecord],
+    first_row: usize,
+) -> Result<RecordBatch> {
+    let arrays = schema
+        .table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, col)| build_array(schema, col, idx, rows, first_row))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(arrow_schema.clone(), arrays)?)
+}
+
+/// Build an Arrow array containing column `idx` of `rows`.
+fn build_array(
+    schema: &Schema,
+    col: &Column,
+    idx: usize,
+    rows: &[csv::StringRecord],
+    first_row: usize,
+) -> Result<ArrayRef> {
+    let cells = ColumnCells {
+        col,
+        cells: rows.iter().map(|row| &row[idx]).collect(),
+        first_row,
+    };
+    let array: ArrayRef = match resolve(schema, &col.data_type) {
+        DataType::Bool => {
+            Arc::new(BooleanArray::from(cells.parse(bool::from_csv_cell)?))
+        }
+        DataType::Date => {
+            Arc::new(Date32Array::from(cells.parse(|cell| {
+                NaiveDate::from_csv_cell(cell).map(days_since_epoch)
+            })?))
+        }
+        DataType::Float32 => {
+            Arc::new(Float32Array::from(cells.parse(f32::from_csv_cell)?))
+        }
+        DataType::Float64 => {
+            Arc::new(Float64Array::from(cells.parse(f64::from_csv_cell)?))
+        }
+        DataType::Int16 => {
+            Arc::new(Int16Array::from(cells.parse(i16::from_csv_cell)?))
+        }
+        DataType::Int32 => {
+            Arc::new(Int32Array::from(cells.parse(i32::from_csv_cell)?))
+        }
+        DataType::Int64 => {
+            Arc::new(Int64Array::from(cells.parse(i64::from_csv_cell)?))
+        }
+        DataType::TimestampWithoutTimeZone => {
+            Arc::new(TimestampMicrosecondArray::from_opt_vec(
+                cells.parse(|cell| {
+                    NaiveDateTime::from_csv_cell(cell).map(|ts| timestamp_micros(&ts))
+                })?,
+                None,
+            ))
+        }
+        DataType::TimestampWithTimeZone => {
+            Arc::new(TimestampMicrosecondArray::from_opt_vec(
+                cells.parse(|cell| {
+                    DateTime::<Utc>::from_csv_cell(cell)
+                        .map(|ts| timestamp_micros(&ts.naive_utc()))
+                })?,
+                Some(UTC.to_owned()),
+            ))
+        }
+        // Everything else is already a string in our CSV interchange format.
+        _ => Arc::new(StringArray::from(cells.parse(Ok)?)),
+    };
+    Ok(array)
+}
+
+/// The cells of a single CSV column.
+struct ColumnCells<'a> {
+    /// The column these cells belong to.
+    col: &'a Column,
+    /// Our raw CSV cells.
+    cells: Vec<&'a str>,
+    /// The row number of the first cell, for error messages.
+    first_row: usize,
+}
+
+impl<'a> ColumnCells<'a> {
+    /// Parse our cells using `parse`. Empty cells in nullable columns are
+    /// treated as `NULL`.
+    fn parse<T, F>(&self, parse: F) -> Result<Vec<Option<T>>>
+    where
+        F: Fn(&'a str) -> Result<T>,
+    {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(offset, &cell)| {
+                if self.col.is_nullable && cell.is_empty() {
+                    return Ok(None);
+                }
+                let value = parse(cell).with_context(|_| ErrorKind::InvalidData {
+                    row: self.first_row + offset,
+                    column: self.col.name.clone(),
+                    value: cell.to_owned(),
+                })?;
+                Ok(Some(value))
+            })
+            .collect()
+    }
+}
+
+/// Convert a date to days since the Unix epoch.
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    i32::try_from((date - NaiveDate::from_ymd(1970, 1, 1)).num_days())
+        .expect("every NaiveDate should fit in i32 days")
+}
+
+/// Convert a timestamp to microseconds since the Unix epoch.
+fn timestamp_micros(timestamp: &NaiveDateTime) -> i64 {
+    timestamp.timestamp() * 1_000_000 + i64::from(timestamp.timestamp_subsec_micros())
+}
+
+/// Read an Arrow IPC stream from `rdr`, and write it to `wtr` as CSV, using
+/// the columns in `schema`.
+///
+/// Columns are matched with Arrow fields by name. This is synchronous, so it
+/// should be run using `spawn_sync_transform`.
+pub(super) fn copy_arrow_to_csv(
+    schema: &Schema,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let reader = StreamReader::try_new(BufReader::with_capacity(BUFFER_SIZE, rdr))?;
+
+    // Figure out where to find each of our columns.
+    let arrow_schema = reader.schema();
+    let columns = &schema.table.columns;
+    let indices = columns
+        .iter()
+        .map(|col| {
+            arrow_schema
+                .fields()
+                .iter()
+                .position(|f| f.name() == &col.name)
+                .ok_or_else(|| {
+                    format_err!("cannot find column {:?} in Arrow data", col.name)
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    wtr.write_record(columns.iter().map(|c| &c.name))?;
+    let mut record = Vec::with_capacity(columns.len());
+    for batch in reader {
+        let batch = batch?;
+        for row in 0..batch.num_rows() {
+            record.clear();
+            for (col, &idx) in columns.iter().zip(&indices) {
+                let cell = array_value_to_csv_cell(batch.column(idx), row)
+                    .with_context(|_| {
+                        format!("cannot convert Arrow column {:?}", col.name)
+                    })?;
+                record.push(cell);
+            }
+            wtr.write_record(&record)?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Downcast `array` to a concrete array type.
+fn downcast<T: 'static>(array: &ArrayRef) -> Result<&T> {
+    array.as_any().downcast_ref::<T>().ok_or_else(|| {
+        format_err!("unexpected array type for {:?}", array.data_type())
+    })
+}
+
+/// Convert the value at `row` in `array` to a cell in our CSV interchange
+/// format.
+fn array_value_to_csv_cell(array: &ArrayRef, row: usize) -> Result<String> {
+    if array.is_null(row) {
+        return Ok(String::new());
+    }
+    Ok(match array.data_type() {
+        ArrowType::Boolean => {
+            let value = downcast::<BooleanArray>(array)?.value(row);
+            if value { "t" } else { "f" }.to_owned()
+        }
+        ArrowType::Int8 => downcast::<Int8Array>(array)?.value(row).to_string(),
+        ArrowType::Int16 => downcast::<Int16Array>(array)?.value(row).to_string(),
+        ArrowType::Int32 => downcast::<Int32Array>(array)?.value(row).to_string(),
+        ArrowType::Int64 => downcast::<Int64Array>(array)?.value(row).to_string(),
+        ArrowType::UInt8 => downcast::<UInt8Array>(array)?.value(row).to_string(),
+        ArrowType::UInt16 => downcast::<UInt16Array>(array)?.value(row).to_string(),
+        ArrowType::UInt32 => downcast::<UInt32Array>(array)?.value(row).to_string(),
+        ArrowType::UInt64 => downcast::<UInt64Array>(array)?.value(row).to_string(),
+        ArrowType::Float32 => downcast::<Float32Array>(array)?.value(row).to_string(),
+        ArrowType::Float64 => downcast::<Float64Array>(array)?.value(row).to_string(),
+        ArrowType::Utf8 => downcast::<StringArray>(array)?.value(row).to_owned(),
+        ArrowType::LargeUtf8 => {
+            downcast::<LargeStringArray>(array)?.value(row).to_owned()
+        }
+        ArrowType::Date32(_) => {
+            let days = downcast::<Date32Array>(array)?.value(row);
+            format_date(i64::from(days))
+        }
+        ArrowType::Date64(_) => {
+            let millis = downcast::<Date64Array>(array)?.value(row);
+            format_date(millis.div_euclid(24 * 60 * 60 * 1_000))
+        }
+        ArrowType::Timestamp(unit, tz) => {
+            let micros = match unit {
+                TimeUnit::Second => {
+                    downcast::<TimestampSecondArray>(array)?.value(row) * 1_000_000
+                }
+                TimeUnit::Millisecond => {
+                    downcast::<TimestampMillisecondArray>(array)?.value(row) * 1_000
+                }
+                TimeUnit::Microsecond => {
+                    downcast::<TimestampMicrosecondArray>(array)?.value(row)
+                }
+                TimeUnit::Nanosecond => downcast::<TimestampNanosecondArray>(array)?
+                    .value(row)
+                    .div_euclid(1_000),
+            };
+            format_timestamp(micros, tz.is_some())
+        }
+        other => return Err(format_err!("unsupported Arrow type {:?}", other)),
+    })
+}
+
+/// Format a date, measured in days since the Unix epoch.
+fn format_date(days: i64) -> String {
+    let date = NaiveDate::from_ymd(1970, 1, 1) + Duration::days(days);
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Format a timestamp, measured in microseconds since the Unix epoch.
+fn format_timestamp(micros: i64, with_zone: bool) -> String {
+    let timestamp = NaiveDateTime::from_timestamp(
+        micros.div_euclid(1_000_000),
+        u32::try_from(micros.rem_euclid(1_000_000) * 1_000)
+            .expect("remainder should always fit in u32"),
+    );
+    if with_zone {
+        timestamp.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+    } else {
+        timestamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+    }
+}
+
+#[test]
+fn arrow_types_to_portable_types() {
+    assert_eq!(
+        data_type_from_arrow(&ArrowType::Int8).unwrap(),
+        DataType::Int16
+    );
+    assert_eq!(
+        data_type_from_arrow(&ArrowType::Timestamp(TimeUnit::Millisecond, None))
+            .unwrap(),
+        DataType::TimestampWithoutTimeZone,
+    );
+    assert_eq!(
+        data_type_from_arrow(&ArrowType::Timestamp(
+            TimeUnit::Nanosecond,
+            Some("America/New_York".to_owned())
+        ))
+        .unwrap(),
+        DataType::TimestampWithTimeZone,
+    );
+    assert!(data_type_from_arrow(&ArrowType::Binary).is_err());
+}
+
+#[test]
+fn csv_to_arrow_round_trip() {
+    use crate::test_util::SharedBuffer;
+
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64" },
+      { "name": "name", "is_nullable": true, "data_type": "text" },
+      { "name": "active", "is_nullable": true, "data_type": "bool" },
+      { "name": "score", "is_nullable": true, "data_type": "float64" },
+      { "name": "born", "is_nullable": true, "data_type": "date" },
+      { "name": "seen", "is_nullable": true, "data_type": "timestamp_with_time_zone" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let csv = "id,name,active,score,born,seen
+1,Ann,t,1.5,2000-01-31,2020-02-03T04:05:06.500Z
+2,,,,,
+";
+    let arrow = SharedBuffer::default();
+    copy_csv_to_arrow(&schema, Box::new(csv.as_bytes()), Box::new(arrow.clone()))
+        .unwrap();
+    let arrow = arrow.contents();
+
+    let parsed = read_arrow_stream_schema(
+        Box::new(std::io::Cursor::new(arrow.clone())),
+        "example",
+    )
+    .unwrap();
+    assert_eq!(parsed.table, schema.table);
+
+    let out = SharedBuffer::default();
+    copy_arrow_to_csv(
+        &schema,
+        Box::new(std::io::Cursor::new(arrow)),
+        Box::new(out.clone()),
+    )
+    .unwrap();
+    let out = out.contents_str();
+    assert_eq!(out, csv);
+}
@@ -0,0 +1,384 @@
+//! Driver for working with Arrow IPC streams.
+
+use std::{
+    ffi::OsStr,
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tokio::{
+    fs,
+    io::{self, BufReader},
+};
+use walkdir::WalkDir;
+
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::csv_stream::csv_stream_name;
+use crate::tokio_glue::{copy_reader_to_stream, copy_stream_to_writer};
+use crate::transform::spawn_sync_transform;
+
+mod convert;
+
+use self::convert::{copy_arrow_to_csv, copy_csv_to_arrow, read_arrow_stream_schema};
+
+/// A file containing an Arrow IPC stream, or a directory containing such
+/// files.
+///
+/// We use the Arrow streaming format, and not the random-access file format,
+/// so that we can read and write standard input and output.
+#[derive(Clone, Debug)]
+pub(crate) struct ArrowLocator {
+    path: PathOrStdio,
+}
+
+impl ArrowLocator {
+    /// Construct an `ArrowLocator` from a path.
+    fn from_path<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: PathOrStdio::Path(path.into()),
+        }
+    }
+}
+
+impl fmt::Display for ArrowLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for ArrowLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(ArrowLocator { path })
+    }
+}
+
+impl Locator for ArrowLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.path.clone()).boxed()
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.path.clone(), shared_args, source_args).boxed()
+    }
+
+    fn display_output_locators(&self) -> DisplayOutputLocators {
+        match &self.path {
+            // If we write our data to standard output, we don't also want to
+            // print out "arrow:-" to the same standard output.
+            PathOrStdio::Stdio => DisplayOutputLocators::Never,
+            _ => DisplayOutputLocators::IfRequested,
+        }
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.path.clone(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for ArrowLocator {
+    fn scheme() -> &'static str {
+        "arrow:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema
+                | LocatorFeatures::LocalData
+                | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::no_append(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Implementation of `schema`, but as a real `async` function.
+///
+/// If our locator is a directory, we use the schema of the first Arrow file we
+/// find. Arrow schemas don't include a table name, so we use the name of our
+/// file or directory.
+async fn schema_helper(ctx: Context, path: PathOrStdio) -> Result<Option<Schema>> {
+    match path {
+        PathOrStdio::Stdio => {
+            // This is hard to fix without seekable input, because we would
+            // need to read the same schema message again when reading our data.
+            Err(format_err!("cannot yet read Arrow schema from stdin"))
+        }
+        PathOrStdio::Path(base_path) => {
+            spawn_blocking(move || {
+                let paths = arrow_paths(&ctx, &base_path)?;
+                let path = paths.first().ok_or_else(|| {
+                    format_err!("no Arrow files found in {}", base_path.display())
+                })?;
+                let table_name = base_path
+                    .file_stem()
+                    .ok_or_else(|| {
+                        format_err!(
+                            "cannot get table name from {}",
+                            base_path.display()
+                        )
+                    })?
+                    .to_string_lossy()
+                    .into_owned();
+                let file = File::open(path)
+                    .with_context(|_| format!("cannot open {}", path.display()))?;
+                let schema = read_arrow_stream_schema(Box::new(file), &table_name)
+                    .with_context(|_| {
+                        format!("error reading schema from {}", path.display())
+                    })?;
+                Ok(Some(schema))
+            })
+            .await
+        }
+    }
+}
+
+/// Convert a stream of Arrow data into a `CsvStream`.
+fn arrow_to_csv_stream(
+    ctx: &Context,
+    schema: &Schema,
+    name: String,
+    data: BoxStream<BytesMut>,
+) -> Result<CsvStream> {
+    let schema = schema.to_owned();
+    let data = spawn_sync_transform(
+        ctx.clone(),
+        "copy_arrow_to_csv".to_owned(),
+        data,
+        move |_ctx, rdr, wtr| copy_arrow_to_csv(&schema, rdr, wtr),
+    )?;
+    Ok(CsvStream { name, data })
+}
+
+/// Convert a `CsvStream` into a stream of Arrow data.
+fn csv_stream_to_arrow(
+    ctx: &Context,
+    schema: &Schema,
+    stream: CsvStream,
+) -> Result<BoxStream<BytesMut>> {
+    let schema = schema.to_owned();
+    spawn_sync_transform(
+        ctx.clone(),
+        "copy_csv_to_arrow".to_owned(),
+        stream.data,
+        move |_ctx, rdr, wtr| copy_csv_to_arrow(&schema, rdr, wtr),
+    )
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+async fn local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(ArrowLocator::features())?;
+    let _source_args = source_args.verify(ArrowLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    match path {
+        PathOrStdio::Stdio => {
+            let data = BufReader::with_capacity(BUFFER_SIZE, io::stdin());
+            let stream = copy_reader_to_stream(ctx.clone(), data)?
+                .map_err(move |e| format_err!("cannot read stdin: {}", e))
+                .boxed();
+            let csv_stream =
+                arrow_to_csv_stream(&ctx, &schema, "data".to_owned(), stream)?;
+            Ok(Some(box_stream_once(Ok(csv_stream))))
+        }
+        PathOrStdio::Path(base_path) => {
+            let paths = arrow_paths(&ctx, &base_path)?;
+            let csv_streams = stream::iter(paths).map(Ok).and_then(move |file_path| {
+                let ctx = ctx.clone();
+                let base_path = base_path.clone();
+                let schema = schema.clone();
+                async move {
+                    // Get the name of our stream.
+                    let name = csv_stream_name(
+                        &base_path.to_string_lossy(),
+                        &file_path.to_string_lossy(),
+                    )?
+                    .to_owned();
+                    let ctx = ctx.child(o!(
+                        "stream" => name.clone(),
+                        "path" => format!("{}", file_path.display())
+                    ));
+
+                    // Open our file and convert it to CSV.
+                    let data = fs::File::open(file_path.clone()).await.with_context(
+                        |_| format!("cannot open {}", file_path.display()),
+                    )?;
+                    let data = BufReader::with_capacity(BUFFER_SIZE, data);
+                    let stream = copy_reader_to_stream(ctx.clone(), data)?
+                        .map_err(move |e| {
+                            format_err!("cannot read {}: {}", file_path.display(), e)
+                        })
+                        .boxed();
+                    arrow_to_csv_stream(&ctx, &schema, name, stream)
+                }
+                .boxed()
+            });
+            Ok(Some(csv_streams.boxed()))
+        }
+    }
+}
+
+/// Recursively look at `base_path`, picking out the Arrow files. If `base_path`
+/// is a file, return it.
+fn arrow_paths(ctx: &Context, base_path: &Path) -> Result<Vec<PathBuf>> {
+    if base_path.is_file() {
+        return Ok(vec![base_path.to_owned()]);
+    }
+
+    let mut paths = vec![];
+    debug!(ctx.log(), "walking {}", base_path.display());
+    let walker = WalkDir::new(base_path)
+        .follow_links(true)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+    for dirent in walker.into_iter() {
+        let dirent = dirent.with_context(|_| {
+            format!("error listing files in {}", base_path.display())
+        })?;
+        let p = dirent.path();
+        trace!(ctx.log(), "found dirent {}", p.display());
+        if dirent.file_type().is_dir() {
+            continue;
+        } else if !dirent.file_type().is_file() {
+            return Err(format_err!("not a file: {}", p.display()));
+        } else if p.extension() == Some(OsStr::new("arrows")) {
+            paths.push(p.to_owned());
+        } else {
+            return Err(format_err!("{} must end in *.arrows", p.display()));
+        }
+    }
+    Ok(paths)
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+async fn write_local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(ArrowLocator::features())?;
+    let dest_args = dest_args.verify(ArrowLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+    match path {
+        PathOrStdio::Stdio => {
+            if_exists.warn_if_not_default_for_stdout(&ctx);
+            let stream = concatenate_csv_streams(ctx.clone(), data)?;
+            let arrow = csv_stream_to_arrow(&ctx, &schema, stream)?;
+            let fut = async move {
+                copy_stream_to_writer(ctx.clone(), arrow, io::stdout())
+                    .await
+                    .context("error writing to stdout")?;
+                Ok(ArrowLocator {
+                    path: PathOrStdio::Stdio,
+                }
+                .boxed())
+            };
+            Ok(box_stream_once(Ok(fut.boxed())))
+        }
+        PathOrStdio::Path(path) => {
+            if path.to_string_lossy().ends_with('/') {
+                // Write streams to our directory as multiple files.
+                let result_stream = data.map_ok(move |stream| {
+                    let path = path.clone();
+                    let ctx = ctx.clone();
+                    let schema = schema.clone();
+                    let if_exists = if_exists.clone();
+
+                    async move {
+                        let arrow_path = path.join(format!("{}.arrows", stream.name));
+                        let ctx = ctx.child(o!(
+                            "stream" => stream.name.clone(),
+                            "path" => format!("{}", arrow_path.display()),
+                        ));
+                        let arrow = csv_stream_to_arrow(&ctx, &schema, stream)?;
+                        write_stream_to_file(
+                            ctx,
+                            arrow,
+                            arrow_path.clone(),
+                            if_exists,
+                        )
+                        .await?;
+                        Ok(ArrowLocator::from_path(arrow_path).boxed())
+                    }
+                    .boxed()
+                });
+                Ok(result_stream.boxed())
+            } else {
+                // Write all our streams as a single file.
+                let stream = concatenate_csv_streams(ctx.clone(), data)?;
+                let fut = async move {
+                    let ctx = ctx.child(o!(
+                        "stream" => stream.name.clone(),
+                        "path" => format!("{}", path.display()),
+                    ));
+                    let arrow = csv_stream_to_arrow(&ctx, &schema, stream)?;
+                    write_stream_to_file(ctx, arrow, path.clone(), if_exists).await?;
+                    Ok(ArrowLocator::from_path(path).boxed())
+                };
+                Ok(box_stream_once(Ok(fut.boxed())))
+            }
+        }
+    }
+}
+
+/// Write `data` to `dest`, honoring `if_exists`.
+async fn write_stream_to_file(
+    ctx: Context,
+    data: BoxStream<BytesMut>,
+    dest: PathBuf,
+    if_exists: IfExists,
+) -> Result<()> {
+    // Make sure our destination directory exists.
+    let dir = dest
+        .parent()
+        .ok_or_else(|| format_err!("cannot find parent dir for {}", dest.display()))?;
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|_| format!("unable to create directory {}", dir.display()))?;
+
+    debug!(ctx.log(), "writing stream to file {}", dest.display());
+    let wtr = if_exists
+        .to_async_open_options_no_append()?
+        .open(dest.clone())
+        .await
+        .with_context(|_| format!("cannot open {}", dest.display()))?;
+    copy_stream_to_writer(ctx.clone(), data, wtr)
+        .await
+        .with_context(|_| format!("error writing {}", dest.display()))?;
+    Ok(())
+}
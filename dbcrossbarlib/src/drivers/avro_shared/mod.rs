@@ -0,0 +1,361 @@
+//! Converting between portable schemas and Apache Avro schemas.
+//!
+//! Avro schemas are JSON documents, so we build and parse them as
+//! `serde_json::Value` instead of defining a full set of types.
+//!
+//! Spec: https://avro.apache.org/docs/1.10.2/spec.html
+
+use serde_json::{json, Map, Value};
+
+use crate::common::*;
+use crate::schema::{Column, DataType, StructField};
+
+mod ocf;
+
+pub(crate) use self::ocf::{
+    copy_avro_to_csv, copy_csv_to_avro, read_avro_file_schema, AvroCodec,
+};
+
+/// Is `name` a valid Avro record, enum or field name?
+fn is_valid_avro_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Convert `name` into a valid Avro name by replacing invalid characters. We
+/// use this for the names of records and enums, which don't appear in the
+/// data.
+fn sanitize_avro_name(name: &str) -> String {
+    let mut sanitized = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if !sanitized.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+#[test]
+fn sanitize_avro_names() {
+    assert_eq!(sanitize_avro_name("public.users"), "public_users");
+    assert_eq!(sanitize_avro_name("2021"), "_2021");
+    assert!(is_valid_avro_name(&sanitize_avro_name("my table")));
+    assert!(!is_valid_avro_name("my table"));
+}
+
+/// Build an Avro `record` schema for our table.
+pub(crate) fn avro_schema_for(schema: &Schema) -> Result<Value> {
+    let fields = schema
+        .table
+        .columns
+        .iter()
+        .map(|col| {
+            let mut field = avro_field(
+                schema,
+                &schema.table.name,
+                &col.name,
+                col.is_nullable,
+                &col.data_type,
+            )?;
+            if let Some(comment) = &col.comment {
+                field.insert("doc".to_owned(), Value::String(comment.to_owned()));
+            }
+            Ok(Value::Object(field))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({
+        "type": "record",
+        "name": sanitize_avro_name(&schema.table.name),
+        "fields": fields,
+    }))
+}
+
+/// Build an Avro field. `parent_name` is used to generate unique names for
+/// nested records and enums.
+fn avro_field(
+    schema: &Schema,
+    parent_name: &str,
+    name: &str,
+    is_nullable: bool,
+    data_type: &DataType,
+) -> Result<Map<String, Value>> {
+    if !is_valid_avro_name(name) {
+        return Err(format_err!(
+            "cannot use {:?} as an Avro field name (must match [A-Za-z_][A-Za-z0-9_]*)",
+            name,
+        ));
+    }
+    let type_name = format!("{}_{}", parent_name, name);
+    let ty = avro_type(schema, &type_name, data_type)?;
+    let mut field = Map::new();
+    field.insert("name".to_owned(), Value::String(name.to_owned()));
+    if is_nullable {
+        field.insert("type".to_owned(), json!(["null", ty]));
+        field.insert("default".to_owned(), Value::Null);
+    } else {
+        field.insert("type".to_owned(), ty);
+    }
+    Ok(field)
+}
+
+/// Build an Avro type for `data_type`. `type_name` is used to name any records
+/// or enums that we need to create.
+fn avro_type(schema: &Schema, type_name: &str, data_type: &DataType) -> Result<Value> {
+    Ok(match data_type {
+        DataType::Array(elem_type) => json!({
+            "type": "array",
+            "items": avro_type(schema, type_name, elem_type)?,
+        }),
+        DataType::Bool => json!("boolean"),
+        DataType::Date => json!({ "type": "int", "logicalType": "date" }),
+        // Avro decimals need a fixed precision and scale, which we don't know,
+        // so use strings to avoid losing data.
+        DataType::Decimal => json!("string"),
+        DataType::Float32 => json!("float"),
+        DataType::Float64 => json!("double"),
+        DataType::GeoJson(_) | DataType::Json | DataType::Text => json!("string"),
+        DataType::Int16 | DataType::Int32 => json!("int"),
+        DataType::Int64 => json!("long"),
+        DataType::Named(name) => {
+            let ty = schema.data_type_for_name(name);
+            avro_type(schema, &sanitize_avro_name(name), ty)?
+        }
+        DataType::OneOf(values) => {
+            // Avro enum symbols have the same restrictions as names, so fall
+            // back to strings if necessary.
+            if values.iter().all(|v| is_valid_avro_name(v)) {
+                json!({
+                    "type": "enum",
+                    "name": sanitize_avro_name(type_name),
+                    "symbols": values,
+                })
+            } else {
+                json!("string")
+            }
+        }
+        DataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    Ok(Value::Object(avro_field(
+                        schema,
+                        type_name,
+                        &field.name,
+                        field.is_nullable,
+                        &field.data_type,
+                    )?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            json!({
+                "type": "record",
+                "name": sanitize_avro_name(type_name),
+                "fields": fields,
+            })
+        }
+        DataType::TimestampWithoutTimeZone => {
+            json!({ "type": "long", "logicalType": "local-timestamp-micros" })
+        }
+        DataType::TimestampWithTimeZone => {
+            json!({ "type": "long", "logicalType": "timestamp-micros" })
+        }
+        DataType::Uuid => json!({ "type": "string", "logicalType": "uuid" }),
+    })
+}
+
+/// Parse an Avro `record` schema into a portable schema.
+pub(crate) fn schema_from_avro(avro: &Value) -> Result<Schema> {
+    let (name, fields) = record_fields(avro)?;
+    let columns = fields
+        .iter()
+        .map(|field| {
+            let (name, is_nullable, data_type) = parse_field(field)?;
+            let comment = field.get("doc").and_then(|doc| doc.as_str());
+            Ok(Column {
+                name,
+                is_nullable,
+                data_type,
+                comment: comment.map(|c| c.to_owned()),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Schema::from_table(Table {
+        name: name.to_owned(),
+        columns,
+    })
+}
+
+/// Get the name and fields of an Avro record.
+fn record_fields(avro: &Value) -> Result<(&str, &[Value])> {
+    if avro.get("type").and_then(|ty| ty.as_str()) != Some("record") {
+        return Err(format_err!("expected an Avro record, found {}", avro));
+    }
+    let name = avro
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| format_err!("Avro record has no name: {}", avro))?;
+    let fields = avro
+        .get("fields")
+        .and_then(|fields| fields.as_array())
+        .ok_or_else(|| format_err!("Avro record has no fields: {}", avro))?;
+    Ok((name, fields))
+}
+
+/// Parse an Avro field, returning its name, nullability and type.
+fn parse_field(field: &Value) -> Result<(String, bool, DataType)> {
+    let name = field
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| format_err!("Avro field has no name: {}", field))?;
+    let ty = field
+        .get("type")
+        .ok_or_else(|| format_err!("Avro field {:?} has no type", name))?;
+    let (is_nullable, ty) = strip_null(ty);
+    let data_type = parse_type(ty).map_err(|_| ErrorKind::UnsupportedType {
+        column: name.to_owned(),
+        type_name: ty.to_string(),
+    })?;
+    Ok((name.to_owned(), is_nullable, data_type))
+}
+
+/// If `ty` is a union of `"null"` and another type, return `true` and the other
+/// type.
+fn strip_null(ty: &Value) -> (bool, &Value) {
+    if let Some(variants) = ty.as_array() {
+        let non_null = variants
+            .iter()
+            .filter(|v| v.as_str() != Some("null"))
+            .collect::<Vec<_>>();
+        if non_null.len() == 1 && variants.len() == 2 {
+            return (true, non_null[0]);
+        }
+    }
+    (false, ty)
+}
+
+/// Parse an Avro type.
+fn parse_type(ty: &Value) -> Result<DataType> {
+    let logical_type = ty.get("logicalType").and_then(|lt| lt.as_str());
+    let base_type = match ty {
+        Value::String(name) => &name[..],
+        Value::Object(obj) => obj
+            .get("type")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| format_err!("unknown Avro type {}", ty))?,
+        _ => return Err(format_err!("unsupported Avro type {}", ty)),
+    };
+    Ok(match (base_type, logical_type) {
+        ("int", Some("date")) => DataType::Date,
+        ("long", Some("timestamp-millis")) | ("long", Some("timestamp-micros")) => {
+            DataType::TimestampWithTimeZone
+        }
+        ("long", Some("local-timestamp-millis"))
+        | ("long", Some("local-timestamp-micros")) => {
+            DataType::TimestampWithoutTimeZone
+        }
+        ("string", Some("uuid")) => DataType::Uuid,
+        ("bytes", Some("decimal")) | ("fixed", Some("decimal")) => DataType::Decimal,
+        ("boolean", _) => DataType::Bool,
+        ("int", _) => DataType::Int32,
+        ("long", _) => DataType::Int64,
+        ("float", _) => DataType::Float32,
+        ("double", _) => DataType::Float64,
+        ("string", _) => DataType::Text,
+        ("array", _) => {
+            let items = ty
+                .get("items")
+                .ok_or_else(|| format_err!("Avro array has no items: {}", ty))?;
+            DataType::Array(Box::new(parse_type(items)?))
+        }
+        ("enum", _) => {
+            let symbols = ty
+                .get("symbols")
+                .and_then(|s| s.as_array())
+                .ok_or_else(|| format_err!("Avro enum has no symbols: {}", ty))?;
+            DataType::OneOf(
+                symbols
+                    .iter()
+                    .map(|s| {
+                        s.as_str().map(|s| s.to_owned()).ok_or_else(|| {
+                            format_err!("invalid Avro enum symbol {}", s)
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        }
+        ("map", _) => DataType::Json,
+        ("record", _) => {
+            let (_, fields) = record_fields(ty)?;
+            DataType::Struct(
+                fields
+                    .iter()
+                    .map(|field| {
+                        let (name, is_nullable, data_type) = parse_field(field)?;
+                        Ok(StructField {
+                            name,
+                            is_nullable,
+                            data_type,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        }
+        _ => return Err(format_err!("unsupported Avro type {}", ty)),
+    })
+}
+
+#[test]
+fn avro_schema_round_trip() {
+    let json = r#"
+{
+  "named_data_types": [{
+    "name": "color",
+    "data_type": { "one_of": ["red", "green", "blue"] }
+  }],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "a", "is_nullable": true,  "data_type": "text", "comment": "A" },
+      { "name": "b", "is_nullable": true,  "data_type": "int32" },
+      { "name": "c", "is_nullable": false, "data_type": "uuid" },
+      { "name": "d", "is_nullable": true,  "data_type": "date" },
+      { "name": "e", "is_nullable": true,  "data_type": "float64" },
+      { "name": "f", "is_nullable": true,  "data_type": { "array": "text" } },
+      { "name": "h", "is_nullable": true,  "data_type": { "struct": [
+        { "name": "x", "data_type": "float64", "is_nullable": false },
+        { "name": "y", "data_type": "float64", "is_nullable": false }
+      ] } },
+      { "name": "i", "is_nullable": false, "data_type": "timestamp_with_time_zone" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let avro = avro_schema_for(&schema).unwrap();
+    assert_eq!(avro["name"], "example");
+    assert_eq!(avro["fields"][0]["type"], json!(["null", "string"]));
+    assert_eq!(avro["fields"][0]["default"], Value::Null);
+    assert_eq!(avro["fields"][0]["doc"], "A");
+    assert_eq!(avro["fields"][6]["type"][1]["name"], "example_h");
+    let parsed = schema_from_avro(&avro).unwrap();
+    assert_eq!(parsed.table, schema.table);
+}
+
+#[test]
+fn avro_schema_rejects_invalid_field_names() {
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "first name".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Text,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    assert!(avro_schema_for(&schema).is_err());
+}
@@ -0,0 +1,843 @@
+//! Reading and writing Avro object container files.
+//!
+//! We encode CSV rows directly using our portable schema, which always matches
+//! the Avro schema generated by `avro_schema_for`. When decoding, we follow the
+//! writer's Avro schema stored in the file header.
+//!
+//! Spec: https://avro.apache.org/docs/1.10.2/spec.html#Object+Container+Files
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io::{self, BufReader, BufWriter},
+    str,
+};
+
+use super::{avro_schema_for, is_valid_avro_name, record_fields, schema_from_avro};
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::DataType;
+
+/// The magic bytes at the start of every Avro object container file.
+const MAGIC: &[u8] = b"Obj\x01";
+
+/// How many bytes of encoded records to put in a block before we write it.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Compression codecs for Avro blocks.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub(crate) enum AvroCodec {
+    /// No compression.
+    #[default]
+    Null,
+    /// Raw deflate compression, without any zlib headers.
+    Deflate,
+}
+
+impl AvroCodec {
+    /// The name of this codec in `avro.codec`.
+    fn name(self) -> &'static str {
+        match self {
+            AvroCodec::Null => "null",
+            AvroCodec::Deflate => "deflate",
+        }
+    }
+
+    /// Look up a codec using its name in `avro.codec`.
+    fn from_name(name: &str) -> Result<AvroCodec> {
+        match name {
+            "null" => Ok(AvroCodec::Null),
+            "deflate" => Ok(AvroCodec::Deflate),
+            _ => Err(format_err!("unsupported Avro codec {:?}", name)),
+        }
+    }
+
+    /// Compress a block of data.
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            AvroCodec::Null => Ok(data.to_owned()),
+            AvroCodec::Deflate => {
+                let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Decompress a block of data.
+    fn decompress(self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            AvroCodec::Null => Ok(data),
+            AvroCodec::Deflate => {
+                let mut out = vec![];
+                DeflateDecoder::new(&data[..]).read_to_end(&mut out)?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// Read CSV data from `rdr`, and write it to `wtr` as an Avro object container
+/// file, using the Avro schema generated from `schema`.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+pub(crate) fn copy_csv_to_avro(
+    schema: &Schema,
+    codec: AvroCodec,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let avro_schema = avro_schema_for(schema)?;
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = BufWriter::with_capacity(BUFFER_SIZE, wtr);
+
+    // Check to make sure our CSV headers and table column names match.
+    let columns = &schema.table.columns;
+    let headers = rdr.headers()?;
+    if headers.len() != columns.len()
+        || headers.iter().zip(columns).any(|(h, c)| h != c.name)
+    {
+        return Err(format_err!(
+            "CSV headers {:?} do not match schema columns {:?}",
+            headers.iter().collect::<Vec<_>>(),
+            columns.iter().map(|c| &c.name).collect::<Vec<_>>(),
+        ));
+    }
+
+    // Write our header.
+    let sync = rand::random::<[u8; 16]>();
+    let mut header = MAGIC.to_vec();
+    write_long(&mut header, 2);
+    write_bytes(&mut header, b"avro.schema");
+    write_bytes(&mut header, avro_schema.to_string().as_bytes());
+    write_bytes(&mut header, b"avro.codec");
+    write_bytes(&mut header, codec.name().as_bytes());
+    write_long(&mut header, 0);
+    header.extend_from_slice(&sync);
+    wtr.write_all(&header)?;
+
+    // Write our rows, a block at a time.
+    let mut block = vec![];
+    let mut count = 0;
+    for (row_idx, row) in rdr.records().enumerate() {
+        let row = row?;
+        for (col, cell) in columns.iter().zip(row.iter()) {
+            encode_cell(schema, col.is_nullable, &col.data_type, cell, &mut block)
+                .with_context(|_| ErrorKind::InvalidData {
+                    row: row_idx + 1,
+                    column: col.name.clone(),
+                    value: cell.to_owned(),
+                })?;
+        }
+        count += 1;
+        if block.len() >= BLOCK_SIZE {
+            write_block(&mut wtr, codec, count, &block, &sync)?;
+            block.clear();
+            count = 0;
+        }
+    }
+    if count > 0 {
+        write_block(&mut wtr, codec, count, &block, &sync)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write a block containing `count` encoded records.
+fn write_block(
+    wtr: &mut dyn Write,
+    codec: AvroCodec,
+    count: i64,
+    block: &[u8],
+    sync: &[u8; 16],
+) -> Result<()> {
+    let data = codec.compress(block)?;
+    let mut prefix = vec![];
+    write_long(&mut prefix, count);
+    write_len(&mut prefix, data.len());
+    wtr.write_all(&prefix)?;
+    wtr.write_all(&data)?;
+    wtr.write_all(sync)?;
+    Ok(())
+}
+
+/// Encode a CSV cell as Avro.
+fn encode_cell(
+    schema: &Schema,
+    is_nullable: bool,
+    data_type: &DataType,
+    cell: &str,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    // Nullable columns are unions of `"null"` and the actual type.
+    if is_nullable {
+        if cell.is_empty() {
+            write_long(buf, 0);
+            return Ok(());
+        }
+        write_long(buf, 1);
+    }
+    let value = if data_type.serializes_as_json_for_csv(schema) {
+        serde_json::from_str(cell)?
+    } else {
+        Value::String(cell.to_owned())
+    };
+    encode_value(schema, data_type, &value, buf)
+}
+
+/// Encode a JSON value as Avro. Scalar values may be represented as JSON
+/// strings in our CSV interchange format.
+fn encode_value(
+    schema: &Schema,
+    data_type: &DataType,
+    value: &Value,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    match data_type {
+        DataType::Array(elem_type) => {
+            let items = value.as_array().ok_or_else(|| {
+                format_err!("expected a JSON array, found {}", value)
+            })?;
+            if !items.is_empty() {
+                write_len(buf, items.len());
+                for item in items {
+                    if item.is_null() {
+                        return Err(format_err!("Avro arrays cannot contain null"));
+                    }
+                    encode_value(schema, elem_type, item, buf)?;
+                }
+            }
+            write_long(buf, 0);
+        }
+        DataType::Bool => {
+            let b = match value {
+                Value::Bool(b) => *b,
+                other => bool::from_csv_cell(str_value(other)?)?,
+            };
+            buf.push(u8::from(b));
+        }
+        DataType::Date => {
+            let date = NaiveDate::from_csv_cell(str_value(value)?)?;
+            write_long(buf, (date - NaiveDate::from_ymd(1970, 1, 1)).num_days());
+        }
+        DataType::Decimal => write_bytes(buf, scalar_string(value)?.as_bytes()),
+        DataType::Float32 => {
+            // Avro `float` is single precision, so we need to round.
+            #[allow(clippy::cast_possible_truncation)]
+            let f = f64_value(value)? as f32;
+            buf.extend_from_slice(&f.to_le_bytes())
+        }
+        DataType::Float64 => buf.extend_from_slice(&f64_value(value)?.to_le_bytes()),
+        DataType::GeoJson(_) | DataType::Json => {
+            write_bytes(buf, value.to_string().as_bytes())
+        }
+        DataType::Int16 | DataType::Int32 => {
+            let n = i64_value(value)?;
+            if i32::try_from(n).is_err() {
+                return Err(format_err!("{} is too large for an Avro int", n));
+            }
+            write_long(buf, n);
+        }
+        DataType::Int64 => write_long(buf, i64_value(value)?),
+        DataType::Named(name) => {
+            encode_value(schema, schema.data_type_for_name(name), value, buf)?
+        }
+        DataType::OneOf(values) => {
+            let s = str_value(value)?;
+            if values.iter().all(|v| is_valid_avro_name(v)) {
+                let idx = values.iter().position(|v| v == s).ok_or_else(|| {
+                    format_err!("{:?} is not one of {:?}", s, values)
+                })?;
+                write_len(buf, idx);
+            } else {
+                write_bytes(buf, s.as_bytes());
+            }
+        }
+        DataType::Struct(fields) => {
+            let obj = value.as_object().ok_or_else(|| {
+                format_err!("expected a JSON object, found {}", value)
+            })?;
+            for field in fields {
+                let field_value = obj.get(&field.name).unwrap_or(&Value::Null);
+                if field.is_nullable {
+                    if field_value.is_null() {
+                        write_long(buf, 0);
+                        continue;
+                    }
+                    write_long(buf, 1);
+                }
+                encode_value(schema, &field.data_type, field_value, buf)
+                    .with_context(|_| format!("error in field {:?}", field.name))?;
+            }
+        }
+        DataType::Text | DataType::Uuid => {
+            write_bytes(buf, scalar_string(value)?.as_bytes())
+        }
+        DataType::TimestampWithoutTimeZone => {
+            let timestamp = NaiveDateTime::from_csv_cell(str_value(value)?)?;
+            write_long(buf, timestamp_micros(&timestamp));
+        }
+        DataType::TimestampWithTimeZone => {
+            let timestamp = DateTime::<Utc>::from_csv_cell(str_value(value)?)?;
+            write_long(buf, timestamp_micros(&timestamp.naive_utc()));
+        }
+    }
+    Ok(())
+}
+
+/// Get a string from a JSON value.
+fn str_value(value: &Value) -> Result<&str> {
+    value
+        .as_str()
+        .ok_or_else(|| format_err!("expected a JSON string, found {}", value))
+}
+
+/// Convert a scalar JSON value to a string.
+fn scalar_string(value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.to_owned()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(format_err!("expected a JSON scalar, found {}", value)),
+    }
+}
+
+/// Get an integer from a JSON number or string.
+fn i64_value(value: &Value) -> Result<i64> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| format_err!("expected an integer, found {}", n)),
+        other => i64::from_csv_cell(str_value(other)?),
+    }
+}
+
+/// Get a floating point number from a JSON number or string.
+fn f64_value(value: &Value) -> Result<f64> {
+    match value {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| format_err!("expected a number, found {}", n)),
+        other => f64::from_csv_cell(str_value(other)?),
+    }
+}
+
+/// Convert a timestamp to microseconds since the Unix epoch.
+fn timestamp_micros(timestamp: &NaiveDateTime) -> i64 {
+    timestamp.timestamp() * 1_000_000 + i64::from(timestamp.timestamp_subsec_micros())
+}
+
+/// Write a zig-zag encoded variable-length integer.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn write_long(buf: &mut Vec<u8>, n: i64) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z & !0x7f != 0 {
+        buf.push((z & 0x7f) as u8 | 0x80);
+        z >>= 7;
+    }
+    buf.push(z as u8);
+}
+
+/// Write a length or count as a variable-length integer.
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    write_long(buf, i64::try_from(len).expect("length should fit in i64"));
+}
+
+/// Write a length-prefixed byte string.
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+/// Read a zig-zag encoded variable-length integer, or return `None` if we're
+/// at the end of our input.
+#[allow(clippy::cast_possible_wrap)]
+fn read_long_or_eof(rdr: &mut dyn Read) -> Result<Option<i64>> {
+    let mut z = 0u64;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8];
+        match rdr.read_exact(&mut byte) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && shift == 0 => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        }
+        if shift >= 64 {
+            return Err(format_err!("invalid Avro integer"));
+        }
+        z |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(Some((z >> 1) as i64 ^ -((z & 1) as i64)))
+}
+
+/// Read a zig-zag encoded variable-length integer.
+fn read_long(rdr: &mut dyn Read) -> Result<i64> {
+    read_long_or_eof(rdr)?.ok_or_else(|| format_err!("unexpected end of Avro data"))
+}
+
+/// Read `len` bytes.
+fn read_fixed(rdr: &mut dyn Read, len: i64) -> Result<Vec<u8>> {
+    let len = usize::try_from(len)
+        .map_err(|_| format_err!("invalid Avro length {}", len))?;
+    let mut bytes = vec![0; len];
+    Read::read_exact(rdr, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read a length-prefixed byte string.
+fn read_bytes(rdr: &mut dyn Read) -> Result<Vec<u8>> {
+    let len = read_long(rdr)?;
+    read_fixed(rdr, len)
+}
+
+/// Read a length-prefixed UTF-8 string.
+fn read_string(rdr: &mut dyn Read) -> Result<String> {
+    Ok(String::from_utf8(read_bytes(rdr)?)?)
+}
+
+/// The header of an Avro object container file.
+struct AvroHeader {
+    /// The Avro schema used to write this file.
+    schema: Value,
+    /// The codec used to compress each block.
+    codec: AvroCodec,
+    /// The marker which follows each block.
+    sync: [u8; 16],
+}
+
+/// Read the header of an Avro object container file.
+fn read_header(rdr: &mut dyn Read) -> Result<AvroHeader> {
+    let mut magic = [0u8; 4];
+    rdr.read_exact(&mut magic)
+        .context("cannot read Avro header")?;
+    if magic != MAGIC {
+        return Err(format_err!("not an Avro object container file"));
+    }
+
+    // Metadata is stored as an Avro `map` of `bytes`.
+    let mut metadata = HashMap::new();
+    loop {
+        let mut count = read_long(rdr)?;
+        if count == 0 {
+            break;
+        } else if count < 0 {
+            // Negative counts are followed by the size of the block in bytes,
+            // which we don't need.
+            count = -count;
+            read_long(rdr)?;
+        }
+        for _ in 0..count {
+            let key = read_string(rdr)?;
+            let value = read_bytes(rdr)?;
+            metadata.insert(key, value);
+        }
+    }
+    let mut sync = [0u8; 16];
+    rdr.read_exact(&mut sync)?;
+
+    let schema = metadata
+        .get("avro.schema")
+        .ok_or_else(|| format_err!("Avro file has no schema"))?;
+    let schema = serde_json::from_slice(schema).context("cannot parse Avro schema")?;
+    let codec = match metadata.get("avro.codec") {
+        Some(name) => AvroCodec::from_name(str::from_utf8(name)?)?,
+        None => AvroCodec::Null,
+    };
+    Ok(AvroHeader {
+        schema,
+        codec,
+        sync,
+    })
+}
+
+/// Read the header of an Avro object container file, and convert its schema
+/// to a portable schema.
+pub(crate) fn read_avro_file_schema(rdr: Box<dyn Read>) -> Result<Schema> {
+    let mut rdr = BufReader::with_capacity(BUFFER_SIZE, rdr);
+    let header = read_header(&mut rdr)?;
+    schema_from_avro(&header.schema)
+}
+
+/// Read an Avro object container file from `rdr`, and write it to `wtr` as
+/// CSV, using the columns in `schema`.
+///
+/// Columns are matched with Avro fields by name. This is synchronous, so it
+/// should be run using `spawn_sync_transform`.
+pub(crate) fn copy_avro_to_csv(
+    schema: &Schema,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = BufReader::with_capacity(BUFFER_SIZE, rdr);
+    let header = read_header(&mut rdr)?;
+
+    // Figure out where to find each of our columns.
+    let (_, fields) = record_fields(&header.schema)?;
+    let mut field_names = vec![];
+    let mut field_types = vec![];
+    for field in fields {
+        field_names.push(
+            field
+                .get("name")
+                .and_then(|name| name.as_str())
+                .ok_or_else(|| format_err!("Avro field has no name: {}", field))?,
+        );
+        field_types.push(
+            field
+                .get("type")
+                .ok_or_else(|| format_err!("Avro field has no type: {}", field))?,
+        );
+    }
+    let columns = &schema.table.columns;
+    let indices = columns
+        .iter()
+        .map(|col| {
+            field_names
+                .iter()
+                .position(|&name| name == col.name)
+                .ok_or_else(|| {
+                    format_err!("cannot find column {:?} in Avro file", col.name)
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    wtr.write_record(columns.iter().map(|c| &c.name))?;
+    let mut row_idx = 0;
+    let mut values = Vec::with_capacity(field_types.len());
+    let mut record = Vec::with_capacity(columns.len());
+    while let Some(count) = read_long_or_eof(&mut rdr)? {
+        let size = read_long(&mut rdr)?;
+        let data = read_fixed(&mut rdr, size)?;
+        let mut sync = [0u8; 16];
+        rdr.read_exact(&mut sync)?;
+        if sync != header.sync {
+            return Err(format_err!("Avro block has an invalid sync marker"));
+        }
+        let data = header.codec.decompress(data)?;
+        let mut block = &data[..];
+        for _ in 0..count {
+            row_idx += 1;
+            values.clear();
+            for ty in &field_types {
+                let value = decode_value(ty, &mut block).with_context(|_| {
+                    format!("cannot read Avro record {}", row_idx)
+                })?;
+                values.push(value);
+            }
+            record.clear();
+            record.extend(indices.iter().map(|&idx| value_to_csv_cell(&values[idx])));
+            wtr.write_record(&record)?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Convert a decoded Avro value into a cell in our CSV interchange format.
+fn value_to_csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(true) => "t".to_owned(),
+        Value::Bool(false) => "f".to_owned(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    }
+}
+
+/// Decode a value of Avro type `ty`. Dates, timestamps and decimals are
+/// returned as strings in our CSV interchange format.
+fn decode_value(ty: &Value, rdr: &mut &[u8]) -> Result<Value> {
+    let (base_type, logical_type) = match ty {
+        // Unions are encoded as the index of the variant, followed by the value.
+        Value::Array(variants) => {
+            let idx = read_long(rdr)?;
+            let variant = usize::try_from(idx)
+                .ok()
+                .and_then(|idx| variants.get(idx))
+                .ok_or_else(|| format_err!("invalid Avro union index {}", idx))?;
+            return decode_value(variant, rdr);
+        }
+        Value::String(name) => (&name[..], None),
+        Value::Object(obj) => (
+            obj.get("type")
+                .and_then(|t| t.as_str())
+                .ok_or_else(|| format_err!("unknown Avro type {}", ty))?,
+            obj.get("logicalType").and_then(|lt| lt.as_str()),
+        ),
+        _ => return Err(format_err!("unsupported Avro type {}", ty)),
+    };
+    Ok(match (base_type, logical_type) {
+        ("null", _) => Value::Null,
+        ("boolean", _) => {
+            let mut byte = [0u8];
+            Read::read_exact(rdr, &mut byte)?;
+            Value::Bool(byte[0] != 0)
+        }
+        ("int", Some("date")) => {
+            let days = read_long(rdr)?;
+            let date = NaiveDate::from_ymd(1970, 1, 1)
+                .checked_add_signed(Duration::days(days))
+                .ok_or_else(|| format_err!("Avro date {} is out of range", days))?;
+            Value::String(date.format("%Y-%m-%d").to_string())
+        }
+        ("long", Some("timestamp-millis")) => {
+            Value::String(format_timestamp(read_long(rdr)? * 1_000, true)?)
+        }
+        ("long", Some("timestamp-micros")) => {
+            Value::String(format_timestamp(read_long(rdr)?, true)?)
+        }
+        ("long", Some("local-timestamp-millis")) => {
+            Value::String(format_timestamp(read_long(rdr)? * 1_000, false)?)
+        }
+        ("long", Some("local-timestamp-micros")) => {
+            Value::String(format_timestamp(read_long(rdr)?, false)?)
+        }
+        ("int", _) | ("long", _) => Value::from(read_long(rdr)?),
+        ("float", _) => {
+            let mut bytes = [0u8; 4];
+            Read::read_exact(rdr, &mut bytes)?;
+            let f = f32::from_le_bytes(bytes);
+            // Format as `f32` to avoid printing extra digits.
+            if f.is_finite() {
+                Value::Number(f.to_string().parse::<Number>()?)
+            } else {
+                Value::String(f.to_string())
+            }
+        }
+        ("double", _) => {
+            let mut bytes = [0u8; 8];
+            Read::read_exact(rdr, &mut bytes)?;
+            let f = f64::from_le_bytes(bytes);
+            Number::from_f64(f)
+                .map(Value::Number)
+                .unwrap_or_else(|| Value::String(f.to_string()))
+        }
+        ("bytes", Some("decimal")) => {
+            let bytes = read_bytes(rdr)?;
+            Value::String(format_decimal(&bytes, decimal_scale(ty))?)
+        }
+        ("fixed", Some("decimal")) => {
+            let size = ty
+                .get("size")
+                .and_then(|s| s.as_i64())
+                .ok_or_else(|| format_err!("Avro fixed type has no size: {}", ty))?;
+            let bytes = read_fixed(rdr, size)?;
+            Value::String(format_decimal(&bytes, decimal_scale(ty))?)
+        }
+        ("string", _) => Value::String(read_string(rdr)?),
+        ("enum", _) => {
+            let idx = read_long(rdr)?;
+            let symbol = ty
+                .get("symbols")
+                .and_then(|s| s.as_array())
+                .and_then(|symbols| symbols.get(usize::try_from(idx).ok()?))
+                .and_then(|s| s.as_str())
+                .ok_or_else(|| format_err!("invalid Avro enum index {}", idx))?;
+            Value::String(symbol.to_owned())
+        }
+        ("array", _) => {
+            let items = ty
+                .get("items")
+                .ok_or_else(|| format_err!("Avro array has no items: {}", ty))?;
+            let mut out = vec![];
+            decode_blocks(rdr, |rdr| {
+                out.push(decode_value(items, rdr)?);
+                Ok(())
+            })?;
+            Value::Array(out)
+        }
+        ("map", _) => {
+            let values = ty
+                .get("values")
+                .ok_or_else(|| format_err!("Avro map has no values: {}", ty))?;
+            let mut out = Map::new();
+            decode_blocks(rdr, |rdr| {
+                let key = read_string(rdr)?;
+                out.insert(key, decode_value(values, rdr)?);
+                Ok(())
+            })?;
+            Value::Object(out)
+        }
+        ("record", _) => {
+            let (_, fields) = record_fields(ty)?;
+            let mut out = Map::new();
+            for field in fields {
+                let name = field
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .ok_or_else(|| format_err!("Avro field has no name: {}", field))?;
+                let field_ty = field
+                    .get("type")
+                    .ok_or_else(|| format_err!("Avro field has no type: {}", field))?;
+                out.insert(name.to_owned(), decode_value(field_ty, rdr)?);
+            }
+            Value::Object(out)
+        }
+        _ => return Err(format_err!("unsupported Avro type {}", ty)),
+    })
+}
+
+/// Decode the blocks of an Avro `array` or `map`, calling `decode_item` for
+/// each item.
+fn decode_blocks<F>(rdr: &mut &[u8], mut decode_item: F) -> Result<()>
+where
+    F: FnMut(&mut &[u8]) -> Result<()>,
+{
+    loop {
+        let mut count = read_long(rdr)?;
+        if count == 0 {
+            return Ok(());
+        } else if count < 0 {
+            // Negative counts are followed by the size of the block in bytes.
+            count = -count;
+            read_long(rdr)?;
+        }
+        for _ in 0..count {
+            decode_item(rdr)?;
+        }
+    }
+}
+
+/// Get the scale of an Avro decimal type.
+fn decimal_scale(ty: &Value) -> u64 {
+    ty.get("scale").and_then(|s| s.as_u64()).unwrap_or(0)
+}
+
+/// Format a timestamp, measured in microseconds since the Unix epoch.
+fn format_timestamp(micros: i64, with_zone: bool) -> Result<String> {
+    let timestamp = NaiveDateTime::from_timestamp_opt(
+        micros.div_euclid(1_000_000),
+        u32::try_from(micros.rem_euclid(1_000_000) * 1_000)
+            .expect("remainder should always fit in u32"),
+    )
+    .ok_or_else(|| format_err!("Avro timestamp {} is out of range", micros))?;
+    if with_zone {
+        Ok(timestamp.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string())
+    } else {
+        Ok(timestamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+    }
+}
+
+/// Format an Avro decimal, which is stored as a big-endian two's complement
+/// integer with an implicit `scale`.
+fn format_decimal(bytes: &[u8], scale: u64) -> Result<String> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return Err(format_err!(
+            "cannot convert {}-byte Avro decimal",
+            bytes.len()
+        ));
+    }
+    // Sign-extend our bytes to 128 bits.
+    let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    let value = i128::from_be_bytes(buf);
+
+    let scale = usize::try_from(scale)?;
+    let mut digits = value.unsigned_abs().to_string();
+    if digits.len() <= scale {
+        digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+    }
+    if scale > 0 {
+        digits.insert(digits.len() - scale, '.');
+    }
+    if value < 0 {
+        digits.insert(0, '-');
+    }
+    Ok(digits)
+}
+
+#[test]
+fn zig_zag_round_trip() {
+    for &n in &[0, -1, 1, -64, 64, i64::from(i32::MAX), i64::MIN, i64::MAX] {
+        let mut buf = vec![];
+        write_long(&mut buf, n);
+        assert_eq!(read_long(&mut &buf[..]).unwrap(), n);
+    }
+    let mut buf = vec![];
+    write_long(&mut buf, -64);
+    assert_eq!(buf, vec![0x7f]);
+    buf.clear();
+    write_long(&mut buf, 64);
+    assert_eq!(buf, vec![0x80, 0x01]);
+}
+
+#[test]
+fn formats_decimals() {
+    assert_eq!(format_decimal(&[0x30, 0x39], 2).unwrap(), "123.45");
+    assert_eq!(format_decimal(&[0xfb], 2).unwrap(), "-0.05");
+    assert_eq!(format_decimal(&[0x2a], 0).unwrap(), "42");
+}
+
+#[test]
+fn csv_to_avro_round_trip() {
+    use crate::test_util::SharedBuffer;
+
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64" },
+      { "name": "name", "is_nullable": true, "data_type": "text" },
+      { "name": "active", "is_nullable": true, "data_type": "bool" },
+      { "name": "score", "is_nullable": true, "data_type": "float32" },
+      { "name": "born", "is_nullable": true, "data_type": "date" },
+      { "name": "seen", "is_nullable": true, "data_type": "timestamp_with_time_zone" },
+      { "name": "color", "is_nullable": true, "data_type": { "one_of": ["red", "green"] } },
+      { "name": "tags", "is_nullable": true, "data_type": { "array": "text" } },
+      { "name": "point", "is_nullable": true, "data_type": { "struct": [
+        { "name": "x", "data_type": "float64", "is_nullable": false },
+        { "name": "y", "data_type": "float64", "is_nullable": true }
+      ] } }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let csv = "id,name,active,score,born,seen,color,tags,point
+1,Ann,t,1.5,2000-01-31,2020-02-03T04:05:06.500Z,green,\"[\"\"a\"\",\"\"b\"\"]\",\"{\"\"x\"\":1.5,\"\"y\"\":null}\"
+2,,,,,,,,
+";
+    for &codec in &[AvroCodec::Null, AvroCodec::Deflate] {
+        let avro = SharedBuffer::default();
+        copy_csv_to_avro(
+            &schema,
+            codec,
+            Box::new(csv.as_bytes()),
+            Box::new(avro.clone()),
+        )
+        .unwrap();
+        let avro = avro.contents();
+        assert!(avro.starts_with(MAGIC));
+
+        let parsed =
+            read_avro_file_schema(Box::new(io::Cursor::new(avro.clone()))).unwrap();
+        assert_eq!(parsed.table, schema.table);
+
+        let out = SharedBuffer::default();
+        copy_avro_to_csv(
+            &schema,
+            Box::new(io::Cursor::new(avro)),
+            Box::new(out.clone()),
+        )
+        .unwrap();
+        let out = out.contents_str();
+        assert_eq!(out, csv);
+    }
+}
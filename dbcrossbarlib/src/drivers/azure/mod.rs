@@ -0,0 +1,142 @@
+//! Support for Azure Blob Storage.
+
+use std::{fmt, str::FromStr};
+
+use crate::clouds::azure::blob;
+use crate::common::*;
+
+mod local_data;
+mod prepare_as_destination;
+mod write_local_data;
+
+use local_data::local_data_helper;
+pub(crate) use prepare_as_destination::prepare_as_destination_helper;
+use write_local_data::write_local_data_helper;
+
+/// A blob or directory in Azure Blob Storage, like
+/// `azure://container/dir/`.
+#[derive(Clone, Debug)]
+pub(crate) struct AzureLocator {
+    url: Url,
+}
+
+impl AzureLocator {
+    /// Access the `azure://` URL in this locator.
+    pub(crate) fn as_url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl fmt::Display for AzureLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.url.fmt(f)
+    }
+}
+
+impl FromStr for AzureLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with(Self::scheme()) {
+            let url = s
+                .parse::<Url>()
+                .with_context(|_| format!("cannot parse {}", s))?;
+            if url.host_str().map(|h| h.is_empty()).unwrap_or(true) {
+                Err(format_err!("{} must start with azure://$CONTAINER/", url))
+            } else if !url.path().starts_with('/') {
+                Err(format_err!("{} must start with azure://", url))
+            } else {
+                Ok(AzureLocator { url })
+            }
+        } else {
+            Err(format_err!("expected {} to begin with azure://", s))
+        }
+    }
+}
+
+impl Locator for AzureLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.url.clone(), shared_args, source_args).boxed()
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.url.clone(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for AzureLocator {
+    fn scheme() -> &'static str {
+        "azure:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::LocalData | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::Overwrite.into(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Given a `TemporaryStorage`, extract a unique `azure://` temporary
+/// directory, including a random component.
+pub(crate) fn find_azure_temp_dir(
+    temporary_storage: &TemporaryStorage,
+) -> Result<AzureLocator> {
+    let mut temp = temporary_storage
+        .find_scheme(AzureLocator::scheme())
+        .ok_or_else(|| format_err!("need `--temporary=azure://...` argument"))?
+        .to_owned();
+    if !temp.ends_with('/') {
+        temp.push('/');
+    }
+    temp.push_str(&TemporaryStorage::random_tag());
+    temp.push('/');
+    AzureLocator::from_str(&temp)
+}
+
+/// Delete `temp`, which was returned by `find_azure_temp_dir`, if this run is
+/// cancelled.
+pub(crate) fn delete_azure_temp_dir_on_cancel(ctx: &Context, temp: &AzureLocator) {
+    let url = temp.as_url().to_owned();
+    ctx.on_cancel(format!("delete {}", url), move |ctx| async move {
+        blob::rmdir(&ctx, &url).await
+    })
+    .keep_until_exit();
+}
+
+#[test]
+fn find_azure_temp_dirs() {
+    let storage = TemporaryStorage::new(vec!["azure://container/temp".to_owned()]);
+    let temp = find_azure_temp_dir(&storage).unwrap();
+    assert!(temp
+        .as_url()
+        .as_str()
+        .starts_with("azure://container/temp/"));
+    assert!(temp.as_url().path().ends_with('/'));
+}
@@ -0,0 +1,22 @@
+//! Preparing container directories as output destinations.
+
+use crate::clouds::azure::blob;
+use crate::common::*;
+
+/// Prepare the target of this locator for use as a destination.
+pub(crate) async fn prepare_as_destination_helper(
+    ctx: Context,
+    azure_url: Url,
+    if_exists: IfExists,
+) -> Result<()> {
+    // Delete the existing output, if it exists.
+    if if_exists == IfExists::Overwrite {
+        // Delete all the blobs under `azure_url`.
+        blob::rmdir(&ctx, &azure_url).await
+    } else {
+        Err(format_err!(
+            "must specify `overwrite` for {} destination",
+            azure_url,
+        ))
+    }
+}
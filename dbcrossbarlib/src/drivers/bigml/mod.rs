@@ -34,7 +34,7 @@ enum BigMlAction {
 }
 
 /// (Internal.) Options for resource creation.
-pub(self) struct CreateOptions {
+struct CreateOptions {
     /// Should we concatenate our input CSVs into a single stream?
     pub(self) concat_csv_streams: bool,
     /// Should we convert our initial source into a dataset?
@@ -209,6 +209,7 @@ impl LocatorStatic for BigMlLocator {
                 | LocatorFeatures::LocalData
                 | LocatorFeatures::WriteLocalData,
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
             // We allow all `--if-exists` features because we always generate a
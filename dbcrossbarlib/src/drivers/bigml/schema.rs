@@ -23,7 +23,7 @@ pub(crate) async fn schema_helper(
         // use the human-readable name.
         let mut columns = vec![];
         let mut fields = fields.iter().collect::<Vec<_>>();
-        fields.sort_by(|&(id1, _), &(id2, _)| id1.cmp(id2));
+        fields.sort_by_key(|&(id1, _)| id1);
         for (_field_id, field) in fields {
             columns.push(Column {
                 name: field.name.clone(),
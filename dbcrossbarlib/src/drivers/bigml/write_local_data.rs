@@ -11,7 +11,7 @@ use super::{source::SourceExt, BigMlLocator, CreateOptions};
 use crate::clouds::aws::{sign_s3_url, AwsCredentials};
 use crate::common::*;
 use crate::concat::concatenate_csv_streams;
-use crate::drivers::s3::find_s3_temp_dir;
+use crate::drivers::s3::{delete_s3_temp_dir_on_cancel, find_s3_temp_dir};
 
 /// Parsed version of `--to-arg` values.
 #[derive(Clone, Debug, Deserialize)]
@@ -67,6 +67,7 @@ pub(crate) async fn write_local_data_helper(
     let sources: BoxStream<BoxFuture<(Context, Source)>> =
         if let Some(s3_temp) = s3_temp {
             // We have S3 temporary storage, so let's copy everything there.
+            delete_s3_temp_dir_on_cancel(&ctx, &s3_temp);
 
             // Pass our `data` streams to `S3Locator::write_local_data`, which will
             // write them to S3 and return a `BoxStream<BoxFuture<BoxLocator>>>`,
@@ -97,7 +98,7 @@ pub(crate) async fn write_local_data_helper(
                     debug!(ctx.log(), "creating BigML source from S3 object");
 
                     // Sign the S3 URL.
-                    let aws_creds = AwsCredentials::try_default().await?;
+                    let aws_creds = AwsCredentials::for_context(&ctx).await?;
                     let url = locator
                         .parse::<Url>()
                         .context("could not parse S3 temporary URL")?;
@@ -177,7 +178,7 @@ pub(crate) async fn write_local_data_helper(
                 bigml_dest_args.optype_for_text.unwrap_or(Optype::Text);
             let update = source.calculate_column_type_fix(&schema, optype_for_text)?;
             trace!(ctx.log(), "updating source with {:?}", update);
-            client.update(&source.id(), &update).await?;
+            client.update(source.id(), &update).await?;
             trace!(ctx.log(), "waiting for source to be ready (again)");
             source = client.wait(source.id()).await?;
 
@@ -6,7 +6,7 @@ use crate::clouds::gcloud::bigquery;
 use crate::common::*;
 use crate::drivers::{
     bigquery::BigQueryLocator,
-    bigquery_shared::{BqTable, GCloudDriverArguments, Usage},
+    bigquery_shared::{BqTable, BqTableExt, GCloudDriverArguments, Usage},
 };
 
 /// Implementation of `count`, but as a real `async` function.
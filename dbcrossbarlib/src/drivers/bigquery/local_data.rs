@@ -1,7 +1,10 @@
 //! Helper for reading data from BigQuery.
 
 use crate::common::*;
-use crate::drivers::{bigquery::BigQueryLocator, gs::find_gs_temp_dir};
+use crate::drivers::{
+    bigquery::BigQueryLocator,
+    gs::{delete_gs_temp_dir_on_cancel, find_gs_temp_dir},
+};
 
 /// Implementation of `local_data`, but as a real `async` function.
 pub(crate) async fn local_data_helper(
@@ -13,6 +16,7 @@ pub(crate) async fn local_data_helper(
     // Build a temporary location.
     let shared_args_v = shared_args.clone().verify(BigQueryLocator::features())?;
     let gs_temp = find_gs_temp_dir(shared_args_v.temporary_storage())?;
+    delete_gs_temp_dir_on_cancel(&ctx, &gs_temp);
     let gs_dest_args = DestinationArguments::for_temporary();
     let gs_source_args = SourceArguments::for_temporary();
 
@@ -10,12 +10,14 @@ mod local_data;
 mod schema;
 mod write_local_data;
 mod write_remote_data;
+mod write_schema;
 
 use self::count::count_helper;
 use self::local_data::local_data_helper;
 use self::schema::schema_helper;
 use self::write_local_data::write_local_data_helper;
-use self::write_remote_data::write_remote_data_helper;
+use self::write_remote_data::{write_dry_run_helper, write_remote_data_helper};
+use self::write_schema::{write_schema_dry_run_helper, write_schema_helper};
 
 /// A locator for a BigQuery table.
 #[derive(Debug, Clone)]
@@ -63,6 +65,24 @@ impl Locator for BigQueryLocator {
         schema_helper(ctx, self.to_owned()).boxed()
     }
 
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+
+    fn write_schema_dry_run(
+        &self,
+        _ctx: &Context,
+        schema: &Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_schema_dry_run_helper(self, schema, dest_args).map(Some)
+    }
+
     fn count(
         &self,
         ctx: Context,
@@ -116,6 +136,16 @@ impl Locator for BigQueryLocator {
         )
         .boxed()
     }
+
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        source: &dyn Locator,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_dry_run_helper(source, self, shared_args, dest_args).map(Some)
+    }
 }
 
 impl LocatorStatic for BigQueryLocator {
@@ -126,13 +156,20 @@ impl LocatorStatic for BigQueryLocator {
     fn features() -> Features {
         Features {
             locator: LocatorFeatures::Schema
+                | LocatorFeatures::WriteSchema
                 | LocatorFeatures::LocalData
                 | LocatorFeatures::WriteLocalData
                 | LocatorFeatures::Count,
-            write_schema_if_exists: EnumSet::empty(),
+            write_schema_if_exists: IfExistsFeatures::Error
+                | IfExistsFeatures::Append
+                | IfExistsFeatures::Overwrite,
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs
+                | DestinationArgumentsFeatures::CreateMissing,
             source_args: SourceArgumentsFeatures::DriverArgs
                 | SourceArgumentsFeatures::WhereClause,
-            dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            dest_args: DestinationArgumentsFeatures::DriverArgs
+                | DestinationArgumentsFeatures::EvolveSchema
+                | DestinationArgumentsFeatures::CreateMissing,
             dest_if_exists: IfExistsFeatures::Error
                 | IfExistsFeatures::Overwrite
                 | IfExistsFeatures::Append
@@ -1,7 +1,12 @@
 //! Implementation of `write_local_data` for BigQuery.
 
 use crate::common::*;
-use crate::drivers::{bigquery::BigQueryLocator, gs::find_gs_temp_dir};
+use crate::drivers::{
+    bigquery::BigQueryLocator,
+    bigquery_shared::{GCloudDriverArguments, LoadFormat},
+    gs::{delete_gs_temp_dir_on_cancel, find_gs_temp_dir},
+    ndjson::{csv_stream_to_ndjson, JsonColumnStyle},
+};
 use crate::tokio_glue::ConsumeWithParallelism;
 
 /// Implementation of `write_local_data`, but as a real `async` function.
@@ -15,9 +20,38 @@ pub(crate) async fn write_local_data_helper(
     // Build a temporary location.
     let shared_args_v = shared_args.clone().verify(BigQueryLocator::features())?;
     let gs_temp = find_gs_temp_dir(shared_args_v.temporary_storage())?;
+    delete_gs_temp_dir_on_cancel(&ctx, &gs_temp);
     let gs_dest_args = DestinationArguments::for_temporary();
     let gs_source_args = SourceArguments::for_temporary();
 
+    // If we're loading NDJSON, convert our data before uploading it.
+    let load_format = dest_args
+        .clone()
+        .verify(BigQueryLocator::features())?
+        .driver_args()
+        .deserialize::<GCloudDriverArguments>()
+        .context("error parsing --to-args")?
+        .load_format;
+    let data = match load_format {
+        LoadFormat::Csv => data,
+        LoadFormat::Ndjson => {
+            let ctx = ctx.clone();
+            let schema = shared_args_v.schema().to_owned();
+            data.map(move |stream| {
+                let stream = stream?;
+                let name = stream.name.clone();
+                let data = csv_stream_to_ndjson(
+                    &ctx,
+                    &schema,
+                    JsonColumnStyle::String,
+                    stream,
+                )?;
+                Ok(CsvStream { name, data })
+            })
+            .boxed()
+        }
+    };
+
     // Copy to a temporary gs:// location.
     let to_temp_ctx = ctx.child(o!("to_temp" => gs_temp.to_string()));
     let result_stream = gs_temp
@@ -1,11 +1,14 @@
 //! Implementation of `BigQueryLocator::write_remote_data`.
 
 use super::BigQueryLocator;
-use crate::clouds::gcloud::bigquery;
+use crate::clouds::gcloud::bigquery::{self, jobs::SourceFormat};
 use crate::common::*;
 use crate::drivers::{
-    bigquery_shared::{BqTable, GCloudDriverArguments, SchemaBigQueryExt, Usage},
-    gs::GsLocator,
+    bigquery_shared::{
+        BqTable, BqTableExt, GCloudDriverArguments, LoadFormat, SchemaBigQueryExt,
+        TableNameExt, Usage,
+    },
+    gs::{find_gs_temp_dir, GsLocator},
 };
 
 /// Copy `source` to `dest` using `schema`.
@@ -40,14 +43,26 @@ pub(crate) async fn write_remote_data_helper(
     let schema = shared_args.schema();
     let temporary_storage = shared_args.temporary_storage();
     let if_exists = dest_args.if_exists();
+    let evolve_schema = dest_args.evolve_schema();
 
-    // Get our billing labels.
-    let job_labels = dest_args
+    // Get our billing labels and load format.
+    let driver_args = dest_args
         .driver_args()
         .deserialize::<GCloudDriverArguments>()
-        .context("error parsing --to-args")?
-        .job_labels
-        .to_owned();
+        .context("error parsing --to-args")?;
+    let job_labels = driver_args.job_labels.to_owned();
+    let load_format = driver_args.load_format;
+
+    // If we were asked to, create our destination dataset.
+    if dest_args.create_missing() {
+        bigquery::create_dataset_if_missing(
+            &ctx,
+            &dest.table_name,
+            driver_args.dataset_location.as_deref(),
+            &job_labels,
+        )
+        .await?;
+    }
 
     // If our URL looks like a directory, add a glob.
     //
@@ -56,68 +71,42 @@ pub(crate) async fn write_remote_data_helper(
     // `dbcrossbar` property. Elsewhere, we're trying to default to adding
     // `**/*.csv`, but that's not supported by BigQuery.
     if source_url.as_str().ends_with('/') {
-        source_url = source_url.join("*.csv")?;
+        source_url = source_url.join(load_glob(load_format))?;
     }
     let ctx = ctx.child(o!("source_url" => source_url.as_str().to_owned()));
 
-    // Decide if we need to use a temp table.
-    let use_temp = !schema.bigquery_can_import_from_csv()? || if_exists.is_upsert();
-    let initial_table_name = if use_temp {
-        let initial_table_name =
-            dest.table_name.temporary_table_name(temporary_storage)?;
-        debug!(
-            ctx.log(),
-            "loading into temporary table {}", initial_table_name
-        );
-        initial_table_name
-    } else {
-        let initial_table_name = dest.table_name.clone();
-        debug!(
-            ctx.log(),
-            "loading directly into final table {}", initial_table_name,
-        );
-        initial_table_name
-    };
-
-    // Build the information we'll need about our initial table.
-    let initial_table = BqTable::for_table_name_and_columns(
+    // Decide how we want to load our data.
+    let plan = LoadPlan::new(
         schema,
-        initial_table_name,
-        &schema.table.columns,
-        if use_temp {
-            Usage::CsvLoad
-        } else {
-            Usage::FinalTable
-        },
+        temporary_storage,
+        &dest,
+        if_exists,
+        evolve_schema,
+        load_format,
     )?;
+    let initial_table = &plan.initial_table;
+    debug!(ctx.log(), "loading into table {}", initial_table.name());
 
-    // Decide how to handle overwrites of the initial table.
-    let if_initial_table_exists = if use_temp {
-        &IfExists::Overwrite
-    } else {
-        if_exists
-    };
+    // If we're loading into a temporary table, drop it if we're cancelled.
+    let _drop_temp_guard = plan.final_table.as_ref().map(|_| {
+        bigquery::drop_table_on_cancel(&ctx, initial_table.name(), &job_labels)
+    });
 
     // Load our data.
     bigquery::load(
         &ctx,
         &source_url,
-        &initial_table,
-        if_initial_table_exists,
+        source_format(load_format),
+        initial_table,
+        &plan.if_initial_table_exists,
+        evolve_schema,
         &job_labels,
     )
     .await?;
 
-    // If `use_temp` is false, then we're done. Otherwise, run the update SQL to
-    // build the final table (if needed).
-    if use_temp {
-        // Build a `BqTable` for our final table.
-        let dest_table = BqTable::for_table_name_and_columns(
-            schema,
-            dest.table_name.clone(),
-            &schema.table.columns,
-            Usage::FinalTable,
-        )?;
+    // If we loaded directly into the final table, then we're done. Otherwise,
+    // run the update SQL to build the final table.
+    if let Some(dest_table) = &plan.final_table {
         debug!(
             ctx.log(),
             "transforming data into final table {}",
@@ -125,10 +114,7 @@ pub(crate) async fn write_remote_data_helper(
         );
 
         // Generate and run our import SQL.
-        let mut query = Vec::new();
-        dest_table.write_import_sql(initial_table.name(), if_exists, &mut query)?;
-        let query =
-            String::from_utf8(query).expect("generated SQL should always be UTF-8");
+        let query = plan.import_sql(if_exists)?.expect("should have import SQL");
         debug!(ctx.log(), "import sql: {}", query);
         bigquery::execute_sql(&ctx, dest.project(), &query, &job_labels).await?;
 
@@ -136,5 +122,201 @@ pub(crate) async fn write_remote_data_helper(
         bigquery::drop_table(&ctx, initial_table.name(), &job_labels).await?;
     }
 
+    // Apply any table labels or description we were given.
+    if let Some(options_sql) = driver_args.table_options_sql(&dest.table_name)? {
+        debug!(ctx.log(), "table options sql: {}", options_sql);
+        bigquery::execute_sql(&ctx, dest.project(), &options_sql, &job_labels).await?;
+    }
+
     Ok(vec![dest.boxed()])
 }
+
+/// The glob we use to find data files in a `gs://` directory.
+///
+/// When we load NDJSON, we load every file in the directory, because
+/// `write_local_data` uploads NDJSON data using our usual `gs://` file names.
+fn load_glob(load_format: LoadFormat) -> &'static str {
+    match load_format {
+        LoadFormat::Csv => "*.csv",
+        LoadFormat::Ndjson => "*",
+    }
+}
+
+/// The BigQuery source format corresponding to `load_format`.
+fn source_format(load_format: LoadFormat) -> SourceFormat {
+    match load_format {
+        LoadFormat::Csv => SourceFormat::Csv,
+        LoadFormat::Ndjson => SourceFormat::NewlineDelimitedJson,
+    }
+}
+
+/// How we plan to load data into BigQuery.
+struct LoadPlan {
+    /// The table we'll load our data into. This may be a temporary table.
+    initial_table: BqTable,
+    /// What to do if `initial_table` exists.
+    if_initial_table_exists: IfExists,
+    /// If `initial_table` is a temporary table, the final table we'll import
+    /// data into using SQL.
+    final_table: Option<BqTable>,
+}
+
+impl LoadPlan {
+    /// Decide how to load data with `schema` into `dest`.
+    fn new(
+        schema: &Schema,
+        temporary_storage: &TemporaryStorage,
+        dest: &BigQueryLocator,
+        if_exists: &IfExists,
+        evolve_schema: bool,
+        load_format: LoadFormat,
+    ) -> Result<LoadPlan> {
+        // Decide if we need to use a temp table. BigQuery can load all our
+        // types directly from NDJSON, but our import SQL only knows how to
+        // read CSV-style temp tables.
+        let use_temp = match load_format {
+            LoadFormat::Csv => {
+                !schema.bigquery_can_import_from_csv()? || if_exists.is_upsert()
+            }
+            LoadFormat::Ndjson if if_exists.is_upsert() => {
+                return Err(format_err!(
+                    "cannot use --if-exists=upsert-on:... with --to-arg=load_format=ndjson"
+                ));
+            }
+            LoadFormat::Ndjson => false,
+        };
+        if use_temp && evolve_schema {
+            // BigQuery can only add columns during a load job, not when
+            // running our import SQL.
+            return Err(format_err!(
+                "BigQuery can only use --evolve-schema when loading directly into the destination table, which is not possible with this schema or --if-exists value"
+            ));
+        }
+        if use_temp {
+            let initial_table_name =
+                dest.table_name.temporary_table_name(temporary_storage)?;
+            Ok(LoadPlan {
+                initial_table: BqTable::for_table_name_and_columns(
+                    schema,
+                    initial_table_name,
+                    &schema.table.columns,
+                    Usage::CsvLoad,
+                )?,
+                // We always overwrite our temporary table.
+                if_initial_table_exists: IfExists::Overwrite,
+                final_table: Some(BqTable::for_table_name_and_columns(
+                    schema,
+                    dest.table_name.clone(),
+                    &schema.table.columns,
+                    Usage::FinalTable,
+                )?),
+            })
+        } else {
+            Ok(LoadPlan {
+                initial_table: BqTable::for_table_name_and_columns(
+                    schema,
+                    dest.table_name.clone(),
+                    &schema.table.columns,
+                    Usage::FinalTable,
+                )?,
+                if_initial_table_exists: if_exists.to_owned(),
+                final_table: None,
+            })
+        }
+    }
+
+    /// The SQL we'll use to import data from `initial_table` into
+    /// `final_table`, if we have one.
+    fn import_sql(&self, if_exists: &IfExists) -> Result<Option<String>> {
+        match &self.final_table {
+            Some(final_table) => {
+                let mut query = Vec::new();
+                final_table.write_import_sql(
+                    self.initial_table.name(),
+                    if_exists,
+                    &mut query,
+                )?;
+                Ok(Some(
+                    String::from_utf8(query)
+                        .expect("generated SQL should always be UTF-8"),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Describe the jobs that `write_remote_data_helper` would run to load
+/// `source`, without running them.
+///
+/// If `source` is not a `gs://` locator, we describe how `write_local_data`
+/// would upload the data to temporary storage and load it from there.
+pub(crate) fn write_dry_run_helper(
+    source: &dyn Locator,
+    dest: &BigQueryLocator,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let shared_args = shared_args.verify(BigQueryLocator::features())?;
+    let dest_args = dest_args.verify(BigQueryLocator::features())?;
+    let schema = shared_args.schema();
+    let temporary_storage = shared_args.temporary_storage();
+    let if_exists = dest_args.if_exists();
+    let evolve_schema = dest_args.evolve_schema();
+    let driver_args = dest_args
+        .driver_args()
+        .deserialize::<GCloudDriverArguments>()
+        .context("error parsing --to-args")?;
+    let load_format = driver_args.load_format;
+
+    let mut out = vec![];
+    if dest_args.create_missing() {
+        let create_sql = bigquery::create_dataset_sql(
+            &dest.table_name,
+            driver_args.dataset_location.as_deref(),
+        )?;
+        writeln!(out, "-- Run query:")?;
+        writeln!(out, "{}", create_sql)?;
+    }
+    let mut source_url = match source.as_any().downcast_ref::<GsLocator>() {
+        Some(gs_source) => gs_source.as_url().to_owned(),
+        None => {
+            let gs_temp = find_gs_temp_dir(temporary_storage)?;
+            writeln!(out, "-- Upload data streams to {}", gs_temp)?;
+            gs_temp.as_url().to_owned()
+        }
+    };
+    if source_url.as_str().ends_with('/') {
+        source_url = source_url.join(load_glob(load_format))?;
+    }
+
+    let plan = LoadPlan::new(
+        schema,
+        temporary_storage,
+        dest,
+        if_exists,
+        evolve_schema,
+        load_format,
+    )?;
+    let config = bigquery::load_job_config(
+        &source_url,
+        source_format(load_format),
+        &plan.initial_table,
+        &plan.if_initial_table_exists,
+        evolve_schema,
+    )?;
+    writeln!(out, "-- Run load job:")?;
+    serde_json::to_writer_pretty(&mut out, &config)?;
+    writeln!(out)?;
+    if let Some(import_sql) = plan.import_sql(if_exists)? {
+        writeln!(out, "-- Run query:")?;
+        writeln!(out, "{}", import_sql.trim())?;
+        writeln!(out, "-- Drop table {}", plan.initial_table.name())?;
+    }
+    if let Some(options_sql) = driver_args.table_options_sql(&dest.table_name)? {
+        writeln!(out, "-- Run query:")?;
+        writeln!(out, "{}", options_sql)?;
+    }
+
+    Ok(String::from_utf8(out).expect("generated output should always be UTF-8"))
+}
@@ -0,0 +1,88 @@
+//! Support for creating BigQuery tables from a schema.
+
+use super::BigQueryLocator;
+use crate::clouds::gcloud::bigquery;
+use crate::common::*;
+use crate::drivers::bigquery_shared::{
+    BqTable, BqTableExt, GCloudDriverArguments, Usage,
+};
+
+/// Implementation of `write_schema`, but as a real `async` function.
+pub(crate) async fn write_schema_helper(
+    ctx: Context,
+    dest: BigQueryLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args = dest_args.verify_for_write_schema(BigQueryLocator::features())?;
+    let driver_args = dest_args
+        .driver_args()
+        .deserialize::<GCloudDriverArguments>()
+        .context("error parsing --to-args")?;
+    let job_labels = driver_args.job_labels.to_owned();
+
+    let ctx = ctx.child(o!("table" => dest.table_name.to_string()));
+    for sql in write_schema_sql(&dest, &schema, &dest_args, &driver_args)? {
+        debug!(ctx.log(), "write schema sql: {}", sql);
+        bigquery::execute_sql(&ctx, dest.project(), &sql, &job_labels).await?;
+    }
+    Ok(())
+}
+
+/// Describe the SQL that `write_schema_helper` would run, without connecting
+/// to BigQuery.
+pub(crate) fn write_schema_dry_run_helper(
+    dest: &BigQueryLocator,
+    schema: &Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let dest_args = dest_args.verify_for_write_schema(BigQueryLocator::features())?;
+    let driver_args = dest_args
+        .driver_args()
+        .deserialize::<GCloudDriverArguments>()
+        .context("error parsing --to-args")?;
+
+    let mut out = String::new();
+    for sql in write_schema_sql(dest, schema, &dest_args, &driver_args)? {
+        out.push_str("-- Run query:\n");
+        out.push_str(sql.trim_end());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The SQL statements needed to create `dest` using `schema`, in the order we
+/// need to run them.
+fn write_schema_sql(
+    dest: &BigQueryLocator,
+    schema: &Schema,
+    dest_args: &DestinationArguments<Verified>,
+    driver_args: &GCloudDriverArguments,
+) -> Result<Vec<String>> {
+    let mut statements = vec![];
+
+    // If we were asked to, create our destination dataset.
+    if dest_args.create_missing() {
+        statements.push(bigquery::create_dataset_sql(
+            &dest.table_name,
+            driver_args.dataset_location.as_deref(),
+        )?);
+    }
+
+    // Create our table.
+    let table = BqTable::for_table_name_and_columns(
+        schema,
+        dest.table_name.clone(),
+        &schema.table.columns,
+        Usage::FinalTable,
+    )?;
+    let mut create_sql = vec![];
+    table.write_create_sql(dest_args.if_exists(), &mut create_sql)?;
+    statements.push(String::from_utf8(create_sql).expect("should always be UTF-8"));
+
+    // Apply any table labels or description we were given.
+    if let Some(options_sql) = driver_args.table_options_sql(&dest.table_name)? {
+        statements.push(options_sql);
+    }
+    Ok(statements)
+}
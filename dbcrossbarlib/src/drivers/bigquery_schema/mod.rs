@@ -39,9 +39,9 @@ impl Locator for BigQuerySchemaLocator {
         &self,
         ctx: Context,
         schema: Schema,
-        if_exists: IfExists,
+        dest_args: DestinationArguments<Unverified>,
     ) -> BoxFuture<()> {
-        write_schema_helper(ctx, self.to_owned(), schema, if_exists).boxed()
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
     }
 }
 
@@ -54,6 +54,7 @@ impl LocatorStatic for BigQuerySchemaLocator {
         Features {
             locator: LocatorFeatures::Schema | LocatorFeatures::WriteSchema,
             write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
@@ -78,7 +79,7 @@ async fn schema_helper(
         .with_context(|_| format!("error parsing {}", source.path))?;
 
     // Build a `BqTable`, convert it, and set a placeholder name.
-    let arbitrary_name = TableName::from_str(&"unused:unused.unused")?;
+    let arbitrary_name = TableName::from_str("unused:unused.unused")?;
     let bq_table = BqTable {
         name: arbitrary_name,
         columns,
@@ -93,13 +94,15 @@ async fn write_schema_helper(
     ctx: Context,
     dest: BigQuerySchemaLocator,
     schema: Schema,
-    if_exists: IfExists,
+    dest_args: DestinationArguments<Unverified>,
 ) -> Result<()> {
+    let dest_args =
+        dest_args.verify_for_write_schema(BigQuerySchemaLocator::features())?;
     // The BigQuery table name doesn't matter here, because our BigQuery schema
     // won't use it. We could convert `table.name` into a valid BigQuery table
     // name, but because BigQuery table names obey fairly strict restrictions,
     // it's not worth doing the work if we're just going throw it away.
-    let arbitrary_name = TableName::from_str(&"unused:unused.unused")?;
+    let arbitrary_name = TableName::from_str("unused:unused.unused")?;
 
     // Convert our schema to a BigQuery table.
     let bq_table = BqTable::for_table_name_and_columns(
@@ -110,7 +113,10 @@ async fn write_schema_helper(
     )?;
 
     // Output our schema to our destination.
-    let mut f = dest.path.create_async(ctx, if_exists).await?;
+    let mut f = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
     buffer_sync_write_and_copy_to_async(&mut f, |buff| {
         bq_table.write_json_schema(buff)
     })
@@ -0,0 +1,132 @@
+//! Generating BigQuery `ALTER TABLE` statements from a schema diff.
+
+use std::convert::TryFrom;
+
+use super::{BqColumn, ColumnName, TableName, Usage};
+use crate::common::*;
+use crate::schema_diff::{ColumnChange, SchemaDiff};
+
+/// Write out the `ALTER TABLE` statements needed to apply `diff` to
+/// `table_name`.
+///
+/// BigQuery only supports a limited set of schema changes. In particular, we
+/// can't add `NOT NULL` columns or make existing columns `NOT NULL`, and only
+/// certain type changes are allowed. We report an error for the first two
+/// cases, and let BigQuery decide whether it can make the requested type
+/// change.
+pub(crate) fn write_bq_alter_table_sql(
+    table_name: &TableName,
+    diff: &SchemaDiff,
+    f: &mut dyn Write,
+) -> Result<()> {
+    let table_name = table_name.dotted_and_quoted();
+    for change in diff.changes() {
+        match change {
+            ColumnChange::Removed(col) => {
+                let name = ColumnName::try_from(&col.name)?;
+                writeln!(
+                    f,
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    table_name,
+                    name.quoted(),
+                )?;
+            }
+            ColumnChange::Added(col) => {
+                let name = ColumnName::try_from(&col.name)?;
+                let bq_col =
+                    BqColumn::for_column(&diff.new, name, col, Usage::FinalTable)?;
+                if bq_col.is_not_null() {
+                    return Err(format_err!(
+                        "BigQuery cannot add NOT NULL column {:?} to {}",
+                        col.name,
+                        table_name,
+                    ));
+                }
+                writeln!(
+                    f,
+                    "ALTER TABLE {} ADD COLUMN {} {};",
+                    table_name,
+                    bq_col.name.quoted(),
+                    bq_col.bq_data_type()?,
+                )?;
+            }
+            ColumnChange::Changed { old, new } => {
+                let name = ColumnName::try_from(&new.name)?;
+                let old_bq_col = BqColumn::for_column(
+                    &diff.old,
+                    name.clone(),
+                    old,
+                    Usage::FinalTable,
+                )?;
+                let bq_col =
+                    BqColumn::for_column(&diff.new, name, new, Usage::FinalTable)?;
+                // Several portable types may map to the same BigQuery type, so
+                // compare the native types.
+                if old_bq_col.bq_data_type()? != bq_col.bq_data_type()? {
+                    writeln!(
+                        f,
+                        "ALTER TABLE {} ALTER COLUMN {} SET DATA TYPE {};",
+                        table_name,
+                        bq_col.name.quoted(),
+                        bq_col.bq_data_type()?,
+                    )?;
+                }
+                if old.is_nullable && !new.is_nullable {
+                    return Err(format_err!(
+                        "BigQuery cannot make existing column {:?} in {} NOT NULL",
+                        new.name,
+                        table_name,
+                    ));
+                } else if !old.is_nullable && new.is_nullable {
+                    writeln!(
+                        f,
+                        "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL;",
+                        table_name,
+                        bq_col.name.quoted(),
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn generates_alter_table_sql() {
+    use crate::schema::{Column, DataType};
+
+    let col = |name: &str, data_type: DataType, is_nullable: bool| Column {
+        name: name.to_owned(),
+        is_nullable,
+        data_type,
+        comment: None,
+    };
+    let old = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            col("id", DataType::Int32, false),
+            col("legacy", DataType::Bool, true),
+        ],
+    })
+    .unwrap();
+    let new = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            col("id", DataType::Float64, true),
+            col("email", DataType::Text, true),
+        ],
+    })
+    .unwrap();
+    let diff = SchemaDiff::compare(&old, &new);
+    let table_name = "project:dataset.example".parse::<TableName>().unwrap();
+    let mut sql = vec![];
+    write_bq_alter_table_sql(&table_name, &diff, &mut sql).unwrap();
+    assert_eq!(
+        String::from_utf8(sql).unwrap(),
+        "ALTER TABLE `project`.`dataset`.`example` DROP COLUMN `legacy`;
+ALTER TABLE `project`.`dataset`.`example` ALTER COLUMN `id` SET DATA TYPE FLOAT64;
+ALTER TABLE `project`.`dataset`.`example` ALTER COLUMN `id` DROP NOT NULL;
+ALTER TABLE `project`.`dataset`.`example` ADD COLUMN `email` STRING;
+",
+    );
+}
@@ -1,8 +1,12 @@
 //! Arguments which can be passed to various Google Cloud drivers.
 
+use itertools::Itertools;
 use serde::Deserialize;
 
+use super::TableName;
 use crate::clouds::gcloud::bigquery::Labels;
+use crate::common::*;
+use crate::driver_args::deserialize_optional_usize_arg;
 
 /// Parse version of `--to-arg` and `--from-arg` labels.
 #[derive(Clone, Debug, Deserialize)]
@@ -11,4 +15,106 @@ pub(crate) struct GCloudDriverArguments {
     /// Billing labels to apply to objects and jobs.
     #[serde(default)]
     pub(crate) job_labels: Labels,
+
+    /// The file format to use when loading data into BigQuery.
+    #[serde(default)]
+    pub(crate) load_format: LoadFormat,
+
+    /// The number of files to split BigQuery extracts into. If this isn't
+    /// specified, BigQuery decides for itself.
+    #[serde(default, deserialize_with = "deserialize_optional_usize_arg")]
+    pub(crate) extract_files: Option<usize>,
+
+    /// Labels to apply to the destination table.
+    #[serde(default)]
+    pub(crate) table_labels: Labels,
+
+    /// A description to set on the destination table.
+    pub(crate) table_description: Option<String>,
+
+    /// The location to use when creating a missing dataset with
+    /// `--create-missing`.
+    pub(crate) dataset_location: Option<String>,
+}
+
+impl GCloudDriverArguments {
+    /// Generate an `ALTER TABLE ... SET OPTIONS` statement which applies our
+    /// table labels and description to `table_name`, or `None` if we weren't
+    /// asked to set any table options.
+    pub(crate) fn table_options_sql(
+        &self,
+        table_name: &TableName,
+    ) -> Result<Option<String>> {
+        let mut options = vec![];
+        if let Some(description) = &self.table_description {
+            options.push(format!("description={}", bq_string_literal(description)?));
+        }
+        if !self.table_labels.is_empty() {
+            // Sort our labels so that we generate the same SQL every time.
+            let labels = self
+                .table_labels
+                .iter()
+                .sorted()
+                .map(|(k, v)| {
+                    Ok(format!(
+                        "({}, {})",
+                        bq_string_literal(k)?,
+                        bq_string_literal(v)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            options.push(format!("labels=[{}]", labels.join(", ")));
+        }
+        if options.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(format!(
+                "ALTER TABLE {} SET OPTIONS ({})",
+                table_name.dotted_and_quoted(),
+                options.join(", "),
+            )))
+        }
+    }
+}
+
+/// Quote `s` as a BigQuery string literal. JSON string escapes are also valid
+/// in BigQuery.
+pub(crate) fn bq_string_literal(s: &str) -> Result<String> {
+    Ok(serde_json::to_string(s)?)
+}
+
+#[test]
+fn table_options_sql_sets_labels_and_description() {
+    let table_name = "project:dataset.table".parse::<TableName>().unwrap();
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<GCloudDriverArguments>()
+            .unwrap()
+    };
+    assert_eq!(parse(&[]).table_options_sql(&table_name).unwrap(), None);
+    assert_eq!(
+        parse(&[
+            "table_labels[team]=data",
+            "table_labels[env]=prod",
+            "table_description=Daily \"events\"",
+        ])
+        .table_options_sql(&table_name)
+        .unwrap()
+        .unwrap(),
+        r#"ALTER TABLE `project`.`dataset`.`table` SET OPTIONS (description="Daily \"events\"", labels=[("env", "prod"), ("team", "data")])"#,
+    );
+}
+
+/// File formats which we can use to load data into BigQuery.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub(crate) enum LoadFormat {
+    /// Load data from CSV files. This is our normal interchange format.
+    #[default]
+    Csv,
+    /// Load data from newline-delimited JSON files. This allows loading
+    /// `STRUCT` and `ARRAY` columns directly, without a temporary table.
+    Ndjson,
 }
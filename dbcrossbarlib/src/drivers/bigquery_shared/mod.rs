@@ -1,30 +1,16 @@
 //! Code shared between various BigQuery-related drivers.
 //!
-//! Much of this code falls into a few major categories:
-//!
-//! - Extension traits which extend "portable" types with BigQuery-specific
-//!   APIs. These wrappers include [`TableBigQueryExt`], [`ColumnBigQueryExt`]
-//!   and [`DataTypeBigQueryExt`].
-//! - Native BigQuery equivalents of our portable types, including [`BqTable`],
-//!   [`BqColumn`] and [`BqDataType`].
-//!
-//! The best starting points are probably [`TableBigQueryExt`] and [`BqTable`].
+//! The BigQuery schema types, such as [`BqTable`], live in
+//! [`dbcrossbarschema::bigquery`], so that they can be used without any I/O.
+//! This module adds the parts which depend on our command-line arguments.
 
-mod column;
-mod column_name;
-mod data_type;
+mod alter_table;
 mod driver_args;
-mod export_udf;
-mod import_udf;
-mod indent_level;
-mod schema;
 mod table;
 mod table_name;
 
-pub(crate) use self::column::*;
-pub(crate) use self::column_name::*;
-pub(crate) use self::data_type::*;
+pub(crate) use self::alter_table::*;
 pub(crate) use self::driver_args::*;
-pub(crate) use self::schema::*;
 pub(crate) use self::table::*;
 pub(crate) use self::table_name::*;
+pub(crate) use dbcrossbarschema::bigquery::*;
@@ -0,0 +1,140 @@
+//! Schema-only driver for writing BigQuery `CREATE TABLE` statements.
+
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::driver_args::deserialize_bool_arg;
+use crate::drivers::bigquery_shared::{BqTable, CreateTableType, TableName, Usage};
+
+/// An SQL file containing a BigQuery `CREATE TABLE` statement.
+#[derive(Clone, Debug)]
+pub(crate) struct BigQuerySqlLocator {
+    path: PathOrStdio,
+}
+
+impl fmt::Display for BigQuerySqlLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for BigQuerySqlLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(BigQuerySqlLocator { path })
+    }
+}
+
+impl Locator for BigQuerySqlLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+}
+
+impl LocatorStatic for BigQuerySqlLocator {
+    fn scheme() -> &'static str {
+        "bigquery-sql:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::WriteSchema.into(),
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BigQuerySqlDestinationArguments {
+    /// The table name to use, in the form `project:dataset.table`.
+    table_name: Option<String>,
+
+    /// Output `CREATE TABLE IF NOT EXISTS` instead of `CREATE TABLE`.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    if_not_exists: bool,
+
+    /// Output `CREATE OR REPLACE TABLE` instead of `CREATE TABLE`.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    or_replace: bool,
+}
+
+impl BigQuerySqlDestinationArguments {
+    /// Which kind of `CREATE TABLE` statement should we output?
+    fn create_table_type(&self) -> Result<CreateTableType> {
+        match (self.if_not_exists, self.or_replace) {
+            (false, false) => Ok(CreateTableType::Plain),
+            (true, false) => Ok(CreateTableType::IfNotExists),
+            (false, true) => Ok(CreateTableType::OrReplace),
+            (true, true) => Err(format_err!(
+                "cannot use both --to-arg=if_not_exists=true and --to-arg=or_replace=true"
+            )),
+        }
+    }
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: BigQuerySqlLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args =
+        dest_args.verify_for_write_schema(BigQuerySqlLocator::features())?;
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<BigQuerySqlDestinationArguments>()
+        .context("could not parse --to-arg")?;
+    let create_table_type = to_args.create_table_type()?;
+
+    // Source table names rarely look like BigQuery table names, so we only
+    // use them if nobody gave us a `table_name`, and only if they parse.
+    let table_name = to_args
+        .table_name
+        .as_deref()
+        .unwrap_or(&schema.table.name)
+        .parse::<TableName>()
+        .context("try passing --to-arg=table_name=project:dataset.table")?;
+    let table = BqTable::for_table_name_and_columns(
+        &schema,
+        table_name,
+        &schema.table.columns,
+        Usage::FinalTable,
+    )?;
+
+    let mut out = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
+    buffer_sync_write_and_copy_to_async(&mut out, |buff| {
+        table.write_create_table_sql(create_table_type, buff)
+    })
+    .await
+    .with_context(|_| format!("error writing {}", dest.path))?;
+    out.flush().await?;
+    Ok(())
+}
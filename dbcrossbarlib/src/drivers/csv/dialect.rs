@@ -0,0 +1,787 @@
+//! Support for CSV dialects other than our CSV interchange format.
+
+use encoding_rs::Encoding;
+use serde::Deserialize;
+use std::{
+    borrow::Cow,
+    io::{BufRead, BufReader, BufWriter},
+    mem,
+};
+
+use crate::common::*;
+use crate::driver_args::deserialize_bool_arg;
+use crate::transform::spawn_sync_transform;
+
+/// CSV dialect options, passed using `--from-arg` or `--to-arg`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CsvDialect {
+    /// The field delimiter. Defaults to `,`.
+    delimiter: Option<String>,
+
+    /// The quote character. Defaults to `"`.
+    quote: Option<String>,
+
+    /// An escape character for quotes inside quoted fields, such as `\`. By
+    /// default, embedded quotes are doubled.
+    escape: Option<String>,
+
+    /// When to quote fields on output.
+    quote_style: Option<QuoteStyle>,
+
+    /// Does our data have a header row?
+    #[serde(default = "default_header", deserialize_with = "deserialize_bool_arg")]
+    header: bool,
+
+    /// The line terminator to use on output. We always accept both on input.
+    terminator: Option<Terminator>,
+
+    /// How `NULL` is represented in unquoted fields, such as `\N`. Defaults
+    /// to the empty string.
+    null: Option<String>,
+
+    /// How `NULL` is represented in quoted fields. By default, quoted fields
+    /// are never `NULL`.
+    quoted_null: Option<String>,
+
+    /// The character encoding of our input, such as `latin1` or
+    /// `windows-1252`. We always write UTF-8.
+    encoding: Option<String>,
+
+    /// What to do with invalid data in the input encoding.
+    #[serde(default)]
+    invalid_encoding: InvalidEncoding,
+}
+
+/// The default value of `header`.
+fn default_header() -> bool {
+    true
+}
+
+/// When to quote fields on output.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum QuoteStyle {
+    /// Only quote fields when necessary.
+    Necessary,
+    /// Always quote fields.
+    Always,
+    /// Quote all fields which aren't numbers.
+    NonNumeric,
+    /// Never quote fields, even if this produces invalid CSV.
+    Never,
+}
+
+/// What to do when our input contains byte sequences which aren't valid in
+/// the declared encoding.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+enum InvalidEncoding {
+    /// Report an error.
+    #[default]
+    Error,
+    /// Replace invalid sequences with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Skip the entire row, and print a warning.
+    SkipRow,
+}
+
+/// Line terminators.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Terminator {
+    /// Unix-style `\n`.
+    Lf,
+    /// Windows-style `\r\n`.
+    Crlf,
+}
+
+impl CsvDialect {
+    /// Parse and validate our dialect from `driver_args`.
+    pub(crate) fn from_driver_args(driver_args: &DriverArguments) -> Result<Self> {
+        let dialect = driver_args.deserialize::<CsvDialect>()?;
+        // Check our arguments now, so that we report errors early.
+        dialect.reader_builder()?;
+        dialect.core_reader()?;
+        dialect.output_quoting()?;
+        dialect.encoding()?;
+        Ok(dialect)
+    }
+
+    /// Our input encoding, if one was specified.
+    fn encoding(&self) -> Result<Option<&'static Encoding>> {
+        match &self.encoding {
+            None => Ok(None),
+            Some(label) => {
+                let encoding = Encoding::for_label(label.as_bytes())
+                    .ok_or_else(|| format_err!("unknown encoding {:?}", label))?;
+                // We transcode each field separately, so we need to be able
+                // to find the field boundaries without decoding.
+                if !encoding.is_ascii_compatible() {
+                    return Err(format_err!(
+                        "the {} encoding is not supported",
+                        encoding.name()
+                    ));
+                }
+                Ok(Some(encoding))
+            }
+        }
+    }
+
+    /// Make sure this dialect doesn't contain options which only make sense
+    /// for input.
+    pub(crate) fn check_valid_for_output(&self) -> Result<()> {
+        if self.encoding.is_some() || self.invalid_encoding != InvalidEncoding::Error {
+            return Err(format_err!(
+                "encoding and invalid_encoding may only be used with --from-arg"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Is this our standard CSV interchange format?
+    fn is_interchange_format(&self) -> bool {
+        self.delimiter.is_none()
+            && self.quote.is_none()
+            && self.escape.is_none()
+            && self.quote_style.is_none()
+            && self.header
+            && self.terminator.is_none()
+            && self.encoding.is_none()
+            && self.null.is_none()
+            && self.quoted_null.is_none()
+    }
+
+    /// Build a CSV reader for this dialect.
+    pub(crate) fn reader_builder(&self) -> Result<csv::ReaderBuilder> {
+        let mut builder = csv::ReaderBuilder::new();
+        builder.has_headers(self.header);
+        if let Some(delimiter) = &self.delimiter {
+            builder.delimiter(byte_arg("delimiter", delimiter)?);
+        }
+        if let Some(quote) = &self.quote {
+            builder.quote(byte_arg("quote", quote)?);
+        }
+        if let Some(escape) = &self.escape {
+            builder
+                .escape(Some(byte_arg("escape", escape)?))
+                .double_quote(false);
+        }
+        Ok(builder)
+    }
+
+    /// Build a low-level CSV reader for this dialect. Unlike the `csv` crate,
+    /// this allows us to find out which fields were quoted.
+    fn core_reader(&self) -> Result<csv_core::Reader> {
+        let mut builder = csv_core::ReaderBuilder::new();
+        if let Some(delimiter) = &self.delimiter {
+            builder.delimiter(byte_arg("delimiter", delimiter)?);
+        }
+        if let Some(quote) = &self.quote {
+            builder.quote(byte_arg("quote", quote)?);
+        }
+        if let Some(escape) = &self.escape {
+            builder
+                .escape(Some(byte_arg("escape", escape)?))
+                .double_quote(false);
+        }
+        Ok(builder.build())
+    }
+
+    /// Build a CSV writer for this dialect. We quote fields ourselves using
+    /// `output_quoting`, because we sometimes need to force a field to be
+    /// quoted.
+    fn writer_builder(&self) -> Result<csv::WriterBuilder> {
+        let mut builder = csv::WriterBuilder::new();
+        builder.quote_style(csv::QuoteStyle::Never);
+        if let Some(delimiter) = &self.delimiter {
+            builder.delimiter(byte_arg("delimiter", delimiter)?);
+        }
+        if self.terminator == Some(Terminator::Crlf) {
+            builder.terminator(csv::Terminator::CRLF);
+        }
+        Ok(builder)
+    }
+
+    /// How to quote fields on output.
+    fn output_quoting(&self) -> Result<OutputQuoting> {
+        Ok(OutputQuoting {
+            delimiter: match &self.delimiter {
+                Some(delimiter) => byte_arg("delimiter", delimiter)?,
+                None => b',',
+            },
+            quote: self.quote_byte()?,
+            escape: match &self.escape {
+                Some(escape) => Some(byte_arg("escape", escape)?),
+                None => None,
+            },
+            style: self.quote_style.unwrap_or(QuoteStyle::Necessary),
+        })
+    }
+
+    /// Our quote character.
+    fn quote_byte(&self) -> Result<u8> {
+        match &self.quote {
+            Some(quote) => byte_arg("quote", quote),
+            None => Ok(b'"'),
+        }
+    }
+
+    /// Convert a field to our interchange format, where `NULL` is represented
+    /// as an empty field.
+    fn null_to_interchange<'a>(&self, field: &'a Field) -> &'a [u8] {
+        let null = if field.quoted {
+            self.quoted_null.as_deref()
+        } else {
+            Some(self.null.as_deref().unwrap_or(""))
+        };
+        if null.map(str::as_bytes) == Some(&field.value[..]) {
+            &b""[..]
+        } else {
+            &field.value[..]
+        }
+    }
+
+    /// Convert CSV data in this dialect to our CSV interchange format. If our
+    /// data has no header row, we use the column names from `schema`.
+    ///
+    /// This is synchronous, so it should be run using `spawn_sync_transform`.
+    fn copy_to_interchange_format(
+        &self,
+        ctx: &Context,
+        schema: &Schema,
+        rdr: Box<dyn Read>,
+        wtr: Box<dyn Write>,
+    ) -> Result<()> {
+        let encoding = self.encoding()?;
+        let mut rdr = QuoteTrackingReader {
+            core: self.core_reader()?,
+            quote: self.quote_byte()?,
+            rdr: BufReader::with_capacity(BUFFER_SIZE, rdr),
+        };
+        let mut wtr =
+            csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+        let mut record = vec![];
+        if self.header && rdr.read_record(&mut record)? {
+            let headers = record.iter().map(|f| &f.value[..]).collect::<Vec<_>>();
+            match encoding {
+                Some(encoding) => {
+                    let headers =
+                        transcode_fields(encoding, &headers).ok_or_else(|| {
+                            format_err!(
+                                "CSV headers are not valid {}",
+                                encoding.name()
+                            )
+                        })?;
+                    wtr.write_record(headers.iter().map(|f| f.as_bytes()))?;
+                }
+                None => wtr.write_record(&headers)?,
+            }
+        } else {
+            wtr.write_record(schema.table.columns.iter().map(|c| &c.name))?;
+        }
+        let mut row_idx = 0;
+        let mut skipped = 0;
+        while rdr.read_record(&mut record)? {
+            row_idx += 1;
+            let fields = record
+                .iter()
+                .map(|f| self.null_to_interchange(f))
+                .collect::<Vec<_>>();
+            let encoding = match encoding {
+                Some(encoding) => encoding,
+                None => {
+                    wtr.write_record(&fields)?;
+                    continue;
+                }
+            };
+            match (transcode_fields(encoding, &fields), self.invalid_encoding) {
+                (Some(fields), _) => {
+                    wtr.write_record(fields.iter().map(|f| f.as_bytes()))?
+                }
+                (None, InvalidEncoding::Error) => {
+                    return Err(format_err!(
+                        "row {} contains invalid {} data",
+                        row_idx,
+                        encoding.name(),
+                    ));
+                }
+                (None, InvalidEncoding::Replace) => {
+                    wtr.write_record(fields.iter().map(|field| {
+                        encoding.decode_without_bom_handling(field).0.into_owned()
+                    }))?;
+                }
+                (None, InvalidEncoding::SkipRow) => skipped += 1,
+            }
+        }
+        if skipped > 0 {
+            if let Some(encoding) = encoding {
+                warn!(
+                    ctx.log(),
+                    "skipped {} rows containing invalid {} data",
+                    skipped,
+                    encoding.name(),
+                );
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Convert CSV data in our CSV interchange format to this dialect.
+    ///
+    /// This is synchronous, so it should be run using `spawn_sync_transform`.
+    fn copy_from_interchange_format(
+        &self,
+        rdr: Box<dyn Read>,
+        wtr: Box<dyn Write>,
+    ) -> Result<()> {
+        let quoting = self.output_quoting()?;
+        let mut rdr =
+            csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+        let mut wtr = self
+            .writer_builder()?
+            .from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+        let headers = rdr.byte_headers()?;
+        if self.header {
+            wtr.write_record(headers.iter().map(|h| quoting.quote(h, false)))?;
+        }
+
+        // Figure out how to write `NULL`, and which values need to be quoted
+        // so that they won't be read back as `NULL`.
+        let (null, null_is_quoted) = match (&self.null, &self.quoted_null) {
+            (Some(null), _) => (null.as_bytes(), false),
+            (None, Some(quoted_null)) => (quoted_null.as_bytes(), true),
+            (None, None) => (&b""[..], false),
+        };
+        let unquoted_null = self.null.as_deref().unwrap_or("").as_bytes();
+        let single_column = headers.len() == 1;
+
+        let mut record = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut record)? {
+            wtr.write_record(record.iter().map(|value| {
+                if value.is_empty() {
+                    // Never write a blank line, which would be skipped when
+                    // reading.
+                    let force = null_is_quoted || (null.is_empty() && single_column);
+                    if force {
+                        quoting.quote(null, true)
+                    } else {
+                        Cow::Borrowed(null)
+                    }
+                } else {
+                    quoting.quote(value, value == unquoted_null)
+                }
+            }))?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Convert a stream of CSV data in this dialect to our CSV interchange
+    /// format.
+    pub(crate) fn decode_stream(
+        &self,
+        ctx: &Context,
+        schema: &Schema,
+        stream: CsvStream,
+    ) -> Result<CsvStream> {
+        if self.is_interchange_format() {
+            return Ok(stream);
+        }
+        let dialect = self.to_owned();
+        let schema = schema.to_owned();
+        let data = spawn_sync_transform(
+            ctx.clone(),
+            "copy_csv_dialect_to_csv".to_owned(),
+            stream.data,
+            move |ctx, rdr, wtr| {
+                dialect.copy_to_interchange_format(&ctx, &schema, rdr, wtr)
+            },
+        )?;
+        Ok(CsvStream {
+            name: stream.name,
+            data,
+        })
+    }
+
+    /// Convert a stream of CSV data in our CSV interchange format to this
+    /// dialect.
+    pub(crate) fn encode_stream(
+        &self,
+        ctx: &Context,
+        stream: CsvStream,
+    ) -> Result<CsvStream> {
+        if self.is_interchange_format() {
+            return Ok(stream);
+        }
+        let dialect = self.to_owned();
+        let data = spawn_sync_transform(
+            ctx.clone(),
+            "copy_csv_to_csv_dialect".to_owned(),
+            stream.data,
+            move |_ctx, rdr, wtr| dialect.copy_from_interchange_format(rdr, wtr),
+        )?;
+        Ok(CsvStream {
+            name: stream.name,
+            data,
+        })
+    }
+}
+
+/// A CSV field, and whether it was quoted.
+struct Field {
+    value: Vec<u8>,
+    quoted: bool,
+}
+
+/// Reads CSV records, keeping track of which fields were quoted. The `csv`
+/// crate doesn't expose this, so we drive `csv_core` directly.
+struct QuoteTrackingReader<R> {
+    core: csv_core::Reader,
+    quote: u8,
+    rdr: R,
+}
+
+impl<R: BufRead> QuoteTrackingReader<R> {
+    /// Read the next record into `record`, returning false if there are no
+    /// more records.
+    fn read_record(&mut self, record: &mut Vec<Field>) -> Result<bool> {
+        record.clear();
+        let mut value = vec![];
+        let mut quoted = None;
+        let mut output = [0; 4096];
+        let quote = self.quote;
+        loop {
+            let input = self.rdr.fill_buf()?;
+            let (result, nin, nout) = self.core.read_field(input, &mut output);
+            if quoted.is_none() {
+                // A field is quoted if its first byte is a quote. Blank lines
+                // before a record are skipped, so ignore any line endings.
+                let mut consumed = &input[..nin];
+                if record.is_empty() {
+                    let start = consumed
+                        .iter()
+                        .position(|&b| b != b'\r' && b != b'\n')
+                        .unwrap_or(consumed.len());
+                    consumed = &consumed[start..];
+                }
+                quoted = consumed.first().map(|&b| b == quote);
+            }
+            self.rdr.consume(nin);
+            value.extend_from_slice(&output[..nout]);
+            match result {
+                csv_core::ReadFieldResult::InputEmpty
+                | csv_core::ReadFieldResult::OutputFull => {}
+                csv_core::ReadFieldResult::Field { record_end } => {
+                    record.push(Field {
+                        value: mem::take(&mut value),
+                        quoted: quoted.take().unwrap_or(false),
+                    });
+                    if record_end {
+                        return Ok(true);
+                    }
+                }
+                csv_core::ReadFieldResult::End => return Ok(false),
+            }
+        }
+    }
+}
+
+/// How to quote fields on output.
+struct OutputQuoting {
+    delimiter: u8,
+    quote: u8,
+    escape: Option<u8>,
+    style: QuoteStyle,
+}
+
+impl OutputQuoting {
+    /// Quote `field` if our quote style requires it, or if `force` is true.
+    /// We never quote anything when using `QuoteStyle::Never`.
+    fn quote<'a>(&self, field: &'a [u8], force: bool) -> Cow<'a, [u8]> {
+        let needs_quotes = match self.style {
+            QuoteStyle::Never => false,
+            QuoteStyle::Always => true,
+            QuoteStyle::NonNumeric => {
+                force
+                    || std::str::from_utf8(field)
+                        .ok()
+                        .and_then(|s| s.parse::<f64>().ok())
+                        .is_none()
+            }
+            QuoteStyle::Necessary => {
+                force
+                    || field.iter().any(|&b| {
+                        b == self.delimiter
+                            || b == self.quote
+                            || Some(b) == self.escape
+                            || b == b'\r'
+                            || b == b'\n'
+                    })
+            }
+        };
+        if !needs_quotes {
+            return Cow::Borrowed(field);
+        }
+        let mut quoted = Vec::with_capacity(field.len() + 2);
+        quoted.push(self.quote);
+        for &b in field {
+            // Escape both quotes and any literal escape characters, so that
+            // readers can tell them apart.
+            if b == self.quote || Some(b) == self.escape {
+                quoted.push(self.escape.unwrap_or(self.quote));
+            }
+            quoted.push(b);
+        }
+        quoted.push(self.quote);
+        Cow::Owned(quoted)
+    }
+}
+
+/// Transcode each field to UTF-8, or return `None` if any field contains
+/// invalid data.
+fn transcode_fields<'a>(
+    encoding: &'static Encoding,
+    fields: &[&'a [u8]],
+) -> Option<Vec<Cow<'a, str>>> {
+    fields
+        .iter()
+        .map(|&field| {
+            encoding.decode_without_bom_handling_and_without_replacement(field)
+        })
+        .collect()
+}
+
+/// Parse a single-byte argument. We accept `tab` or `\t` as a tab character,
+/// because tabs are awkward to type on the command line.
+fn byte_arg(name: &str, value: &str) -> Result<u8> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format_err!(
+            "{} must be a single ASCII character, found {:?}",
+            name,
+            value
+        )),
+    }
+}
+
+#[test]
+fn parses_dialects() {
+    let parse = |args: &[&str]| {
+        CsvDialect::from_driver_args(&DriverArguments::from_cli_args(args).unwrap())
+    };
+    assert!(parse(&[]).unwrap().is_interchange_format());
+    let tsv = parse(&["delimiter=tab", "header=false"]).unwrap();
+    assert!(!tsv.is_interchange_format());
+    assert!(!tsv.header);
+    assert!(parse(&["delimiter=;;"]).is_err());
+    assert!(parse(&["terminator=cr"]).is_err());
+    assert!(parse(&["header=no"]).is_err());
+}
+
+#[test]
+fn converts_dialects() {
+    use crate::schema::{Column, DataType};
+    use crate::test_util::SharedBuffer;
+    use std::io;
+
+    let column = |name: &str| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type: DataType::Text,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![column("a"), column("b")],
+    })
+    .unwrap();
+    let dialect = CsvDialect::from_driver_args(
+        &DriverArguments::from_cli_args(&[
+            "delimiter=|",
+            "escape=\\",
+            "header=false",
+            "terminator=crlf",
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+
+    let out = SharedBuffer::default();
+    dialect
+        .copy_from_interchange_format(
+            Box::new(&b"a,b\n1,\"say \"\"hi\"\"\"\n"[..]),
+            Box::new(out.clone()),
+        )
+        .unwrap();
+    let converted = out.contents();
+    assert_eq!(converted, b"1|\"say \\\"hi\\\"\"\r\n");
+
+    let (ctx, _worker_fut) = Context::create_for_test("converts_dialects");
+    let out = SharedBuffer::default();
+    dialect
+        .copy_to_interchange_format(
+            &ctx,
+            &schema,
+            Box::new(io::Cursor::new(converted)),
+            Box::new(out.clone()),
+        )
+        .unwrap();
+    let out = out.contents();
+    assert_eq!(out, b"a,b\n1,\"say \"\"hi\"\"\"\n");
+}
+
+#[test]
+fn round_trips_escape_characters() {
+    use crate::schema::{Column, DataType};
+    use crate::test_util::SharedBuffer;
+    use std::io;
+
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "a".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Text,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let dialect = CsvDialect::from_driver_args(
+        &DriverArguments::from_cli_args(&["escape=\\"]).unwrap(),
+    )
+    .unwrap();
+    let input = &b"a\n\"C:\\dir\\ \"\"quoted\"\"\"\n"[..];
+
+    let out = SharedBuffer::default();
+    dialect
+        .copy_from_interchange_format(Box::new(input), Box::new(out.clone()))
+        .unwrap();
+    let converted = out.contents();
+    assert_eq!(converted, &b"a\n\"C:\\\\dir\\\\ \\\"quoted\\\"\"\n"[..]);
+
+    let (ctx, _worker_fut) = Context::create_for_test("round_trips_escape_characters");
+    let out = SharedBuffer::default();
+    dialect
+        .copy_to_interchange_format(
+            &ctx,
+            &schema,
+            Box::new(io::Cursor::new(converted)),
+            Box::new(out.clone()),
+        )
+        .unwrap();
+    assert_eq!(out.contents(), input);
+}
+
+#[test]
+fn transcodes_input() {
+    use crate::schema::{Column, DataType};
+    use crate::test_util::SharedBuffer;
+
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "a".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Text,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let (ctx, _worker_fut) = Context::create_for_test("transcodes_input");
+    let convert = |args: &[&str], input: &'static [u8]| -> Result<String> {
+        let dialect =
+            CsvDialect::from_driver_args(&DriverArguments::from_cli_args(args)?)?;
+        let out = SharedBuffer::default();
+        dialect.copy_to_interchange_format(
+            &ctx,
+            &schema,
+            Box::new(input),
+            Box::new(out.clone()),
+        )?;
+        let out = out.contents();
+        Ok(String::from_utf8(out)?)
+    };
+
+    assert_eq!(
+        convert(&["encoding=latin1"], b"a\ncaf\xe9\n").unwrap(),
+        "a\ncaf\u{e9}\n",
+    );
+    let invalid = b"a\nok\nbad\xff\n";
+    assert!(convert(&["encoding=utf-8"], invalid).is_err());
+    assert_eq!(
+        convert(&["encoding=utf-8", "invalid_encoding=replace"], invalid).unwrap(),
+        "a\nok\nbad\u{fffd}\n",
+    );
+    assert_eq!(
+        convert(&["encoding=utf-8", "invalid_encoding=skip_row"], invalid).unwrap(),
+        "a\nok\n",
+    );
+    assert!(convert(&["encoding=utf-16le"], b"").is_err());
+    assert!(convert(&["encoding=klingon"], b"").is_err());
+}
+
+#[test]
+fn converts_nulls() {
+    use crate::schema::{Column, DataType};
+    use crate::test_util::SharedBuffer;
+
+    let column = |name: &str| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type: DataType::Text,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![column("a"), column("b")],
+    })
+    .unwrap();
+    let (ctx, _worker_fut) = Context::create_for_test("converts_nulls");
+    let dialect = |args: &[&str]| {
+        CsvDialect::from_driver_args(&DriverArguments::from_cli_args(args).unwrap())
+            .unwrap()
+    };
+    let decode = |args: &[&str], input: &'static [u8]| {
+        let out = SharedBuffer::default();
+        dialect(args)
+            .copy_to_interchange_format(
+                &ctx,
+                &schema,
+                Box::new(input),
+                Box::new(out.clone()),
+            )
+            .unwrap();
+        let out = out.contents();
+        String::from_utf8(out).unwrap()
+    };
+    let encode = |args: &[&str], input: &'static [u8]| {
+        let out = SharedBuffer::default();
+        dialect(args)
+            .copy_from_interchange_format(Box::new(input), Box::new(out.clone()))
+            .unwrap();
+        let out = out.contents();
+        String::from_utf8(out).unwrap()
+    };
+
+    // mysqldump and PostgreSQL `TEXT` style.
+    assert_eq!(decode(&["null=\\N"], b"a,b\n\\N,\"\\N\"\n"), "a,b\n,\\N\n");
+    assert_eq!(encode(&["null=\\N"], b"a,b\n,\\N\n"), "a,b\n\\N,\"\\N\"\n");
+
+    // Literal `NULL`, only when quoted.
+    let args = &["quoted_null=NULL"];
+    assert_eq!(decode(args, b"a,b\n\"NULL\",NULL\n"), "a,b\n,NULL\n");
+    assert_eq!(encode(args, b"a,b\n,NULL\n"), "a,b\n\"NULL\",NULL\n");
+
+    // Quoted fields which span lines still work.
+    assert_eq!(
+        decode(&["null=NULL"], b"a,b\n\n\"x\ny\",NULL\n"),
+        "a,b\n\"x\ny\",\n"
+    );
+
+    // Single-column `NULL` values must not be written as blank lines.
+    assert_eq!(encode(&["delimiter=tab"], b"a\n\"\"\nx\n"), "a\n\"\"\nx\n");
+}
@@ -1,6 +1,11 @@
 //! Driver for working with CSV files.
 
-use std::{ffi::OsStr, fmt, path::PathBuf, str::FromStr};
+use std::{
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use tokio::{
     fs,
     io::{self, BufReader},
@@ -13,6 +18,12 @@ use crate::csv_stream::csv_stream_name;
 use crate::schema::{Column, DataType, Table};
 use crate::tokio_glue::{copy_reader_to_stream, copy_stream_to_writer};
 
+mod dialect;
+mod write_remote_data;
+
+use self::dialect::CsvDialect;
+use self::write_remote_data::write_remote_data_helper;
+
 /// (Incomplete.) A CSV file containing data, or a directory containing CSV
 /// files.
 ///
@@ -95,6 +106,15 @@ impl Locator for CsvLocator {
         .boxed()
     }
 
+    fn count(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<usize> {
+        count_helper(ctx, self.path.clone(), shared_args, source_args).boxed()
+    }
+
     fn local_data(
         &self,
         ctx: Context,
@@ -123,6 +143,36 @@ impl Locator for CsvLocator {
         write_local_data_helper(ctx, self.path.clone(), data, shared_args, dest_args)
             .boxed()
     }
+
+    fn supports_write_remote_data(&self, source: &dyn Locator) -> bool {
+        // We can copy CSV files directly, without going through the CSV
+        // interchange format, as long as we're not reading or writing stdio.
+        match source.as_any().downcast_ref::<CsvLocator>() {
+            Some(source) => {
+                source.path != PathOrStdio::Stdio && self.path != PathOrStdio::Stdio
+            }
+            None => false,
+        }
+    }
+
+    fn write_remote_data(
+        &self,
+        ctx: Context,
+        source: BoxLocator,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<Vec<BoxLocator>> {
+        write_remote_data_helper(
+            ctx,
+            source,
+            self.to_owned(),
+            shared_args,
+            source_args,
+            dest_args,
+        )
+        .boxed()
+    }
 }
 
 async fn local_data_helper(
@@ -131,53 +181,31 @@ async fn local_data_helper(
     shared_args: SharedArguments<Unverified>,
     source_args: SourceArguments<Unverified>,
 ) -> Result<Option<BoxStream<CsvStream>>> {
-    let _shared_args = shared_args.verify(CsvLocator::features())?;
-    let _source_args = source_args.verify(CsvLocator::features())?;
+    let shared_args = shared_args.verify(CsvLocator::features())?;
+    let source_args = source_args.verify(CsvLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let dialect = CsvDialect::from_driver_args(source_args.driver_args())
+        .context("could not parse --from-arg")?;
     match path {
         PathOrStdio::Stdio => {
             let data = BufReader::with_capacity(BUFFER_SIZE, io::stdin());
-            let stream = copy_reader_to_stream(ctx, data)?;
+            let stream = copy_reader_to_stream(ctx.clone(), data)?;
             let csv_stream = CsvStream {
                 name: "data".to_owned(),
                 data: stream
                     .map_err(move |e| format_err!("cannot read stdin: {}", e))
                     .boxed(),
             };
+            let csv_stream = dialect.decode_stream(&ctx, &schema, csv_stream)?;
             Ok(Some(box_stream_once(Ok(csv_stream))))
         }
         PathOrStdio::Path(base_path) => {
-            // Recursively look at our paths, picking out the ones that look
-            // like CSVs. We do this synchronously because it's reasonably
-            // fast and we'd like to catch errors up front.
-            let mut paths = vec![];
-            debug!(ctx.log(), "walking {}", base_path.display());
-            let walker = WalkDir::new(&base_path).follow_links(true);
-            for dirent in walker.into_iter() {
-                let dirent = dirent.with_context(|_| {
-                    format!("error listing files in {}", base_path.display())
-                })?;
-                let p = dirent.path();
-                trace!(ctx.log(), "found dirent {}", p.display());
-                if dirent.file_type().is_dir() {
-                    continue;
-                } else if !dirent.file_type().is_file() {
-                    return Err(format_err!("not a file: {}", p.display()));
-                }
-
-                let ext = p.extension();
-                if ext == Some(OsStr::new("csv")) || ext == Some(OsStr::new("CSV")) {
-                    paths.push(p.to_owned());
-                } else {
-                    return Err(format_err!(
-                        "{} must end in *.csv or *.CSV",
-                        p.display()
-                    ));
-                }
-            }
-
+            let paths = csv_paths(&ctx, &base_path)?;
             let csv_streams = stream::iter(paths).map(Ok).and_then(move |file_path| {
                 let ctx = ctx.clone();
                 let base_path = base_path.clone();
+                let schema = schema.clone();
+                let dialect = dialect.clone();
                 async move {
                     // Get the name of our stream.
                     let name = csv_stream_name(
@@ -195,9 +223,9 @@ async fn local_data_helper(
                         |_| format!("cannot open {}", file_path.display()),
                     )?;
                     let data = BufReader::with_capacity(BUFFER_SIZE, data);
-                    let stream = copy_reader_to_stream(ctx, data)?;
+                    let stream = copy_reader_to_stream(ctx.clone(), data)?;
 
-                    Ok(CsvStream {
+                    let csv_stream = CsvStream {
                         name,
                         data: stream
                             .map_err(move |e| {
@@ -208,7 +236,8 @@ async fn local_data_helper(
                                 )
                             })
                             .boxed(),
-                    })
+                    };
+                    dialect.decode_stream(&ctx, &schema, csv_stream)
                 }
                 .boxed()
             });
@@ -218,6 +247,87 @@ async fn local_data_helper(
     }
 }
 
+/// Count the records in the CSV file or files at `path`, not including
+/// headers.
+async fn count_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<usize> {
+    let _shared_args = shared_args.verify(CsvLocator::features())?;
+    let source_args = source_args.verify(CsvLocator::features())?;
+    let dialect = CsvDialect::from_driver_args(source_args.driver_args())
+        .context("could not parse --from-arg")?;
+
+    // `csv` is synchronous, so do this on a helper thread.
+    spawn_blocking(move || match path {
+        PathOrStdio::Stdio => {
+            let stdin = std::io::stdin();
+            let rdr = dialect.reader_builder()?.from_reader(stdin.lock());
+            Ok(count_records(rdr).context("error reading stdin")?)
+        }
+        PathOrStdio::Path(base_path) => {
+            let mut count = 0;
+            for csv_path in csv_paths(&ctx, &base_path)? {
+                let rdr = dialect
+                    .reader_builder()?
+                    .from_path(&csv_path)
+                    .with_context(|_| {
+                        format!("error opening {}", csv_path.display())
+                    })?;
+                count += count_records(rdr).with_context(|_| {
+                    format!("error reading {}", csv_path.display())
+                })?;
+            }
+            Ok(count)
+        }
+    })
+    .await
+}
+
+/// Count the records in `rdr`.
+fn count_records<R: Read>(mut rdr: csv::Reader<R>) -> Result<usize, csv::Error> {
+    let mut record = csv::ByteRecord::new();
+    let mut count = 0;
+    while rdr.read_byte_record(&mut record)? {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Recursively look at `base_path`, picking out the files that look like CSVs.
+/// We do this synchronously because it's reasonably fast and we'd like to
+/// catch errors up front.
+fn csv_paths(ctx: &Context, base_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    debug!(ctx.log(), "walking {}", base_path.display());
+    let walker = WalkDir::new(base_path).follow_links(true);
+    for dirent in walker.into_iter() {
+        let dirent = dirent.with_context(|_| {
+            format!("error listing files in {}", base_path.display())
+        })?;
+        let p = dirent.path();
+        trace!(ctx.log(), "found dirent {}", p.display());
+        if dirent.file_type().is_dir() {
+            continue;
+        } else if !dirent.file_type().is_file() {
+            return Err(format_err!("not a file: {}", p.display()));
+        }
+
+        let ext = p.extension().and_then(|ext| ext.to_str());
+        if let Some("csv") | Some("CSV") | Some("tsv") | Some("TSV") = ext {
+            paths.push(p.to_owned());
+        } else {
+            return Err(format_err!(
+                "{} must end in *.csv, *.CSV, *.tsv or *.TSV",
+                p.display()
+            ));
+        }
+    }
+    Ok(paths)
+}
+
 async fn write_local_data_helper(
     ctx: Context,
     path: PathOrStdio,
@@ -228,10 +338,14 @@ async fn write_local_data_helper(
     let _shared_args = shared_args.verify(CsvLocator::features())?;
     let dest_args = dest_args.verify(CsvLocator::features())?;
     let if_exists = dest_args.if_exists().to_owned();
+    let dialect = CsvDialect::from_driver_args(dest_args.driver_args())
+        .context("could not parse --to-arg")?;
+    dialect.check_valid_for_output()?;
     match path {
         PathOrStdio::Stdio => {
             if_exists.warn_if_not_default_for_stdout(&ctx);
             let stream = concatenate_csv_streams(ctx.clone(), data)?;
+            let stream = dialect.encode_stream(&ctx, stream)?;
             let fut = async move {
                 copy_stream_to_writer(ctx.clone(), stream.data, io::stdout())
                     .await
@@ -250,15 +364,17 @@ async fn write_local_data_helper(
                     let path = path.clone();
                     let ctx = ctx.clone();
                     let if_exists = if_exists.clone();
+                    let dialect = dialect.clone();
 
                     async move {
                         // TODO: This join does not handle `..` or nested `/` in
                         // a particularly safe fashion.
-                        let csv_path = path.join(&format!("{}.csv", stream.name));
+                        let csv_path = path.join(format!("{}.csv", stream.name));
                         let ctx = ctx.child(o!(
                             "stream" => stream.name.clone(),
                             "path" => format!("{}", csv_path.display()),
                         ));
+                        let stream = dialect.encode_stream(&ctx, stream)?;
                         write_stream_to_file(
                             ctx,
                             stream.data,
@@ -279,6 +395,7 @@ async fn write_local_data_helper(
                         "stream" => stream.name.clone(),
                         "path" => format!("{}", path.display()),
                     ));
+                    let stream = dialect.encode_stream(&ctx, stream)?;
                     write_stream_to_file(ctx, stream.data, path.clone(), if_exists)
                         .await?;
                     Ok(CsvLocator::from_path(path).boxed())
@@ -325,11 +442,13 @@ impl LocatorStatic for CsvLocator {
     fn features() -> Features {
         Features {
             locator: LocatorFeatures::Schema
+                | LocatorFeatures::Count
                 | LocatorFeatures::LocalData
                 | LocatorFeatures::WriteLocalData,
             write_schema_if_exists: EnumSet::empty(),
-            source_args: EnumSet::empty(),
-            dest_args: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: SourceArgumentsFeatures::DriverArgs.into(),
+            dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
             dest_if_exists: IfExistsFeatures::no_append(),
             _placeholder: (),
         }
@@ -0,0 +1,131 @@
+//! Implementation of `CsvLocator::write_remote_data`.
+
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::BufReader};
+
+use super::{csv_paths, dialect::CsvDialect, write_stream_to_file, CsvLocator};
+use crate::common::*;
+use crate::csv_stream::csv_stream_name;
+use crate::tokio_glue::copy_reader_to_stream;
+
+/// Copy CSV files from `source` to `dest`.
+///
+/// If both sides use the same CSV dialect, we copy the bytes of each file
+/// without parsing them. Otherwise, we fall back to `local_data` and
+/// `write_local_data`, which convert between dialects.
+pub(crate) async fn write_remote_data_helper(
+    ctx: Context,
+    source: BoxLocator,
+    dest: CsvLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<Vec<BoxLocator>> {
+    let source_path = source
+        .as_any()
+        .downcast_ref::<CsvLocator>()
+        .ok_or_else(|| format_err!("not a csv locator: {}", source))?
+        .path
+        .clone();
+
+    // Verify our arguments.
+    let shared_args_v = shared_args.clone().verify(CsvLocator::features())?;
+    let source_args_v = source_args.clone().verify(CsvLocator::features())?;
+    let dest_args_v = dest_args.clone().verify(CsvLocator::features())?;
+    let max_streams = shared_args_v.max_streams();
+    let if_exists = dest_args_v.if_exists().to_owned();
+    let source_dialect = CsvDialect::from_driver_args(source_args_v.driver_args())
+        .context("could not parse --from-arg")?;
+    let dest_dialect = CsvDialect::from_driver_args(dest_args_v.driver_args())
+        .context("could not parse --to-arg")?;
+    dest_dialect.check_valid_for_output()?;
+
+    // Figure out which files we can copy directly.
+    let copies = match (&source_path, &dest.path) {
+        (PathOrStdio::Path(source_path), PathOrStdio::Path(dest_path))
+            if source_dialect == dest_dialect =>
+        {
+            passthrough_copies(&ctx, source_path, dest_path)?
+        }
+        _ => None,
+    };
+
+    match copies {
+        Some(copies) => {
+            info!(ctx.log(), "copying CSV files without parsing them");
+            stream::iter(copies)
+                .map(|(from, to)| {
+                    let ctx = ctx.clone();
+                    let if_exists = if_exists.clone();
+                    async move {
+                        copy_file(&ctx, &from, to.clone(), if_exists).await?;
+                        Ok(CsvLocator::from_path(to).boxed())
+                    }
+                })
+                .buffer_unordered(max_streams)
+                .try_collect::<Vec<_>>()
+                .await
+        }
+        None => {
+            let data = source
+                .local_data(ctx.clone(), shared_args.clone(), source_args)
+                .await?
+                .ok_or_else(|| format_err!("don't know how to read {}", source))?;
+            dest.write_local_data(ctx, data, shared_args, dest_args)
+                .await?
+                .try_buffer_unordered(max_streams)
+                .try_collect::<Vec<_>>()
+                .await
+        }
+    }
+}
+
+/// Build a list of `(from, to)` paths for copying `source_path` to
+/// `dest_path` byte-for-byte, or return `None` if the files would need to be
+/// concatenated.
+fn passthrough_copies(
+    ctx: &Context,
+    source_path: &Path,
+    dest_path: &Path,
+) -> Result<Option<Vec<(PathBuf, PathBuf)>>> {
+    let paths = csv_paths(ctx, source_path)?;
+    if dest_path.to_string_lossy().ends_with('/') {
+        // Use the same file names as `write_local_data`.
+        let base_path = source_path.to_string_lossy();
+        let mut copies = Vec::with_capacity(paths.len());
+        for path in paths {
+            let name =
+                csv_stream_name(&base_path, &path.to_string_lossy())?.to_owned();
+            let to = dest_path.join(format!("{}.csv", name));
+            copies.push((path, to));
+        }
+        Ok(Some(copies))
+    } else if paths.len() == 1 {
+        Ok(Some(vec![(paths[0].clone(), dest_path.to_owned())]))
+    } else {
+        // Concatenating CSV files means removing their headers.
+        Ok(None)
+    }
+}
+
+/// Copy the file at `from` to `to`, honoring `if_exists`.
+async fn copy_file(
+    ctx: &Context,
+    from: &Path,
+    to: PathBuf,
+    if_exists: IfExists,
+) -> Result<()> {
+    let ctx = ctx.child(o!(
+        "from_path" => format!("{}", from.display()),
+        "path" => format!("{}", to.display()),
+    ));
+    let data = fs::File::open(from)
+        .await
+        .with_context(|_| format!("cannot open {}", from.display()))?;
+    let data = BufReader::with_capacity(BUFFER_SIZE, data);
+    let from = from.to_owned();
+    let stream = copy_reader_to_stream(ctx.clone(), data)?
+        .map_err(move |e| format_err!("cannot read {}: {}", from.display(), e))
+        .boxed();
+    write_stream_to_file(ctx, stream, to, if_exists).await
+}
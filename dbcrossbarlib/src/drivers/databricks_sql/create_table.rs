@@ -0,0 +1,242 @@
+//! Generating Spark SQL `CREATE TABLE` statements.
+
+use itertools::Itertools;
+use std::fmt;
+
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+/// A Spark SQL identifier, quoted with backticks.
+pub(crate) struct Ident<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.0.replace('`', "``"))
+    }
+}
+
+/// Quote a Spark SQL string literal. Unlike standard SQL, Spark uses
+/// backslash escapes inside strings.
+pub(crate) fn spark_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[test]
+fn quoting() {
+    assert_eq!(Ident("a`b").to_string(), "`a``b`");
+    assert_eq!(spark_quote(r"it's a \ test"), r"'it\'s a \\ test'");
+}
+
+/// A Databricks `CREATE TABLE` statement for a Delta table.
+#[derive(Debug)]
+pub(crate) struct SparkCreateTable<'a> {
+    /// The schema we're creating a table for, which we need to look up named
+    /// types.
+    schema: &'a Schema,
+    /// The parts of our table name, for example `["catalog", "db", "table"]`.
+    pub(crate) name: Vec<String>,
+    /// Columns to partition by.
+    pub(crate) partition_by: Vec<String>,
+    /// Where to store the table's data, for external tables.
+    pub(crate) location: Option<String>,
+    /// Should we generate `CREATE TABLE IF NOT EXISTS`?
+    pub(crate) if_not_exists: bool,
+}
+
+impl<'a> SparkCreateTable<'a> {
+    /// Create a new `CREATE TABLE` statement for `schema`, using `name`, which
+    /// may contain `.` characters separating catalog and database names.
+    pub(crate) fn from_schema(schema: &'a Schema, name: &str) -> Result<Self> {
+        let name = name.split('.').map(|s| s.to_owned()).collect::<Vec<_>>();
+        if name.iter().any(|part| part.is_empty()) || name.len() > 3 {
+            return Err(format_err!(
+                "expected Databricks table name like catalog.schema.table, found {:?}",
+                name.join("."),
+            ));
+        }
+        Ok(SparkCreateTable {
+            schema,
+            name,
+            partition_by: vec![],
+            location: None,
+            if_not_exists: false,
+        })
+    }
+
+    /// Make sure our partition columns exist and have types which Delta allows
+    /// as partition columns.
+    pub(crate) fn validate(&self) -> Result<()> {
+        for col_name in &self.partition_by {
+            let col = self
+                .schema
+                .table
+                .columns
+                .iter()
+                .find(|c| &c.name == col_name)
+                .ok_or_else(|| {
+                    format_err!("cannot partition by unknown column {:?}", col_name)
+                })?;
+            if let DataType::Array(_) | DataType::Struct(_) | DataType::Json =
+                self.resolve(&col.data_type)
+            {
+                return Err(format_err!(
+                    "cannot partition by column {:?} because of its type",
+                    col_name,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up the real type of any named types.
+    fn resolve<'b>(&'b self, data_type: &'b DataType) -> &'b DataType {
+        match data_type {
+            DataType::Named(name) => {
+                self.resolve(self.schema.data_type_for_name(name))
+            }
+            other => other,
+        }
+    }
+
+    /// Write the Spark SQL version of `data_type`.
+    fn write_data_type(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        data_type: &DataType,
+    ) -> fmt::Result {
+        match data_type {
+            DataType::Array(elem_type) => {
+                write!(f, "ARRAY<")?;
+                self.write_data_type(f, elem_type)?;
+                write!(f, ">")
+            }
+            DataType::Bool => write!(f, "BOOLEAN"),
+            DataType::Date => write!(f, "DATE"),
+            // Spark decimals are limited to 38 digits, so we need to choose a
+            // fixed precision and scale.
+            DataType::Decimal => write!(f, "DECIMAL(38, 9)"),
+            DataType::Float32 => write!(f, "FLOAT"),
+            DataType::Float64 => write!(f, "DOUBLE"),
+            DataType::GeoJson(_)
+            | DataType::Json
+            | DataType::OneOf(_)
+            | DataType::Text
+            | DataType::Uuid => write!(f, "STRING"),
+            DataType::Int16 => write!(f, "SMALLINT"),
+            DataType::Int32 => write!(f, "INT"),
+            DataType::Int64 => write!(f, "BIGINT"),
+            DataType::Named(name) => {
+                self.write_data_type(f, self.schema.data_type_for_name(name))
+            }
+            DataType::Struct(fields) => {
+                write!(f, "STRUCT<")?;
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", Ident(&field.name))?;
+                    self.write_data_type(f, &field.data_type)?;
+                    if !field.is_nullable {
+                        write!(f, " NOT NULL")?;
+                    }
+                }
+                write!(f, ">")
+            }
+            DataType::TimestampWithoutTimeZone => write!(f, "TIMESTAMP_NTZ"),
+            DataType::TimestampWithTimeZone => write!(f, "TIMESTAMP"),
+        }
+    }
+
+    /// Write a single column definition.
+    fn write_column(&self, f: &mut fmt::Formatter<'_>, col: &Column) -> fmt::Result {
+        write!(f, "    {} ", Ident(&col.name))?;
+        self.write_data_type(f, &col.data_type)?;
+        if !col.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        if let Some(comment) = &col.comment {
+            write!(f, " COMMENT {}", spark_quote(comment))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SparkCreateTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CREATE TABLE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        writeln!(
+            f,
+            "{} (",
+            self.name.iter().map(|part| Ident(part)).join(".")
+        )?;
+        for (idx, col) in self.schema.table.columns.iter().enumerate() {
+            self.write_column(f, col)?;
+            if idx + 1 < self.schema.table.columns.len() {
+                write!(f, ",")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, ")")?;
+        write!(f, "USING delta")?;
+        if !self.partition_by.is_empty() {
+            write!(
+                f,
+                "\nPARTITIONED BY ({})",
+                self.partition_by.iter().map(|c| Ident(c)).join(", "),
+            )?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, "\nLOCATION {}", spark_quote(location))?;
+        }
+        writeln!(f, ";")
+    }
+}
+
+#[test]
+fn create_table_sql() {
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64", "comment": "Primary key" },
+      { "name": "created_on", "is_nullable": true, "data_type": "date" },
+      { "name": "seen_at", "is_nullable": true, "data_type": "timestamp_without_time_zone" },
+      { "name": "tags", "is_nullable": true, "data_type": { "array": "text" } },
+      { "name": "point", "is_nullable": true, "data_type": { "struct": [
+        { "name": "x", "data_type": "float64", "is_nullable": false },
+        { "name": "y", "data_type": "float64", "is_nullable": true }
+      ] } }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let mut table = SparkCreateTable::from_schema(&schema, "main.db.example").unwrap();
+    table.partition_by = vec!["created_on".to_owned()];
+    table.if_not_exists = true;
+    table.validate().unwrap();
+    assert_eq!(
+        table.to_string(),
+        r#"CREATE TABLE IF NOT EXISTS `main`.`db`.`example` (
+    `id` BIGINT NOT NULL COMMENT 'Primary key',
+    `created_on` DATE,
+    `seen_at` TIMESTAMP_NTZ,
+    `tags` ARRAY<STRING>,
+    `point` STRUCT<`x`: DOUBLE NOT NULL, `y`: DOUBLE>
+)
+USING delta
+PARTITIONED BY (`created_on`);
+"#,
+    );
+
+    table.partition_by = vec!["tags".to_owned()];
+    assert!(table.validate().is_err());
+    table.partition_by = vec!["missing".to_owned()];
+    assert!(table.validate().is_err());
+    assert!(SparkCreateTable::from_schema(&schema, "a..b").is_err());
+}
@@ -0,0 +1,134 @@
+//! Schema-only driver for writing Databricks (Spark SQL) `CREATE TABLE`
+//! statements.
+
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::driver_args::deserialize_bool_arg;
+
+mod create_table;
+
+use create_table::SparkCreateTable;
+
+/// An SQL file containing a Databricks `CREATE TABLE ... USING delta`
+/// statement.
+#[derive(Clone, Debug)]
+pub(crate) struct DatabricksSqlLocator {
+    path: PathOrStdio,
+}
+
+impl fmt::Display for DatabricksSqlLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for DatabricksSqlLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(DatabricksSqlLocator { path })
+    }
+}
+
+impl Locator for DatabricksSqlLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+}
+
+impl LocatorStatic for DatabricksSqlLocator {
+    fn scheme() -> &'static str {
+        "databricks-sql:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::WriteSchema.into(),
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatabricksSqlDestinationArguments {
+    /// The table name to use, optionally including a catalog and schema.
+    table_name: Option<String>,
+
+    /// Columns to use for `PARTITIONED BY`.
+    #[serde(default)]
+    partition_by: Vec<String>,
+
+    /// A storage location for an external table.
+    location: Option<String>,
+
+    /// Output `CREATE TABLE IF NOT EXISTS` instead of `CREATE TABLE`.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    if_not_exists: bool,
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: DatabricksSqlLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args =
+        dest_args.verify_for_write_schema(DatabricksSqlLocator::features())?;
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<DatabricksSqlDestinationArguments>()
+        .context("could not parse --to-arg")?;
+
+    // By default, use the last part of the source table name, because the
+    // source database's schema names probably don't make sense for Databricks.
+    let table_name = match &to_args.table_name {
+        Some(table_name) => table_name.to_owned(),
+        None => schema
+            .table
+            .name
+            .rsplit('.')
+            .next()
+            .unwrap_or(&schema.table.name)
+            .to_owned(),
+    };
+    let mut table = SparkCreateTable::from_schema(&schema, &table_name)?;
+    table.partition_by = to_args.partition_by;
+    table.location = to_args.location;
+    table.if_not_exists = to_args.if_not_exists;
+    table.validate()?;
+
+    let mut out = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
+    buffer_sync_write_and_copy_to_async(&mut out, |buff| write!(buff, "{}", table))
+        .await
+        .with_context(|_| format!("error writing {}", dest.path))?;
+    out.flush().await?;
+    Ok(())
+}
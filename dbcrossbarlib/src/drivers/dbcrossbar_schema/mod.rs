@@ -2,10 +2,9 @@
 
 use std::{fmt, str::FromStr};
 
-use self::external_schema::ExternalSchema;
-use crate::common::*;
+use dbcrossbarschema::external_schema::ExternalSchema;
 
-pub(crate) mod external_schema;
+use crate::common::*;
 
 /// A JSON file containing a `dbcrossbar` native schema.
 #[derive(Clone, Debug)]
@@ -41,9 +40,9 @@ impl Locator for DbcrossbarSchemaLocator {
         &self,
         ctx: Context,
         schema: Schema,
-        if_exists: IfExists,
+        dest_args: DestinationArguments<Unverified>,
     ) -> BoxFuture<()> {
-        write_schema_helper(ctx, self.to_owned(), schema, if_exists).boxed()
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
     }
 }
 
@@ -56,6 +55,7 @@ impl LocatorStatic for DbcrossbarSchemaLocator {
         Features {
             locator: LocatorFeatures::Schema | LocatorFeatures::WriteSchema,
             write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
@@ -87,10 +87,15 @@ async fn write_schema_helper(
     ctx: Context,
     dest: DbcrossbarSchemaLocator,
     schema: Schema,
-    if_exists: IfExists,
+    dest_args: DestinationArguments<Unverified>,
 ) -> Result<()> {
+    let dest_args =
+        dest_args.verify_for_write_schema(DbcrossbarSchemaLocator::features())?;
     // Generate our JSON.
-    let mut f = dest.path.create_async(ctx, if_exists).await?;
+    let mut f = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
     buffer_sync_write_and_copy_to_async(&mut f, |buff| {
         serde_json::to_writer_pretty(buff, &schema)
     })
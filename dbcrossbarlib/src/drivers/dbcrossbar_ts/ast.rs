@@ -457,7 +457,7 @@ impl ToDataType for Type {
 
             TypeDetails::Number => Ok(DataType::Float64),
 
-            TypeDetails::Ref(id) => source_file.identifier_to_data_type(&id),
+            TypeDetails::Ref(id) => source_file.identifier_to_data_type(id),
 
             TypeDetails::String => Ok(DataType::Text),
 
@@ -761,6 +761,7 @@ interface Point {
 
 // Use `main_error` for pretty test output.
 #[test]
+#[allow(clippy::single_element_loop)]
 fn parses_shopify_schema() -> Result<(), main_error::MainError> {
     let file_string = include_str!("../../../../dbcrossbar/fixtures/shopify.ts");
     let source_file =
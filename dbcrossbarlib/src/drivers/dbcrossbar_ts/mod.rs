@@ -79,6 +79,7 @@ impl LocatorStatic for DbcrossbarTsLocator {
         Features {
             locator: LocatorFeatures::Schema.into(),
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
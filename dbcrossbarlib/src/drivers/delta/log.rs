@@ -0,0 +1,210 @@
+//! Reading the Delta transaction log.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::{spark_schema::schema_from_spark, DeltaLocator, DeltaTablePath};
+use crate::clouds::{aws::s3, gcloud::storage};
+use crate::common::*;
+
+/// The directory containing the transaction log, relative to the table root.
+pub(crate) const LOG_DIR: &str = "_delta_log";
+
+/// The parts of a `metaData` action that we care about.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metadata {
+    /// The user-visible name of the table, if any.
+    #[serde(default)]
+    name: Option<String>,
+    /// The table's Spark schema, serialized as a JSON string.
+    schema_string: String,
+}
+
+/// A single line of a commit file. Each line holds exactly one action, and we
+/// only care about `metaData`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Action {
+    #[serde(default)]
+    meta_data: Option<Metadata>,
+}
+
+/// A file in the transaction log.
+enum LogFile {
+    Local(PathBuf),
+    S3(Url),
+    Gs(storage::StorageObject),
+}
+
+impl LogFile {
+    /// The name of this file, without any directories.
+    fn file_name(&self) -> String {
+        let path = match self {
+            LogFile::Local(path) => path.to_string_lossy().into_owned(),
+            LogFile::S3(url) => url.path().to_owned(),
+            LogFile::Gs(obj) => obj.name.clone(),
+        };
+        path.rsplit('/').next().unwrap_or_default().to_owned()
+    }
+
+    /// If this is a JSON commit file, return its version number.
+    fn commit_version(&self) -> Option<u64> {
+        self.file_name()
+            .strip_suffix(".json")
+            .and_then(|version| version.parse::<u64>().ok())
+    }
+
+    /// Is this a Parquet checkpoint file?
+    fn is_checkpoint(&self) -> bool {
+        let name = self.file_name();
+        name.contains(".checkpoint") && name.ends_with(".parquet")
+    }
+
+    /// Read the contents of this file.
+    async fn read(&self, ctx: &Context) -> Result<Vec<u8>> {
+        let data = match self {
+            LogFile::Local(path) => {
+                return Ok(tokio::fs::read(path)
+                    .await
+                    .with_context(|_| format!("error reading {}", path.display()))?)
+            }
+            LogFile::S3(url) => s3::download_file(ctx, url).await?,
+            LogFile::Gs(obj) => storage::download_file(ctx, obj).await?,
+        };
+        data.try_fold(vec![], |mut buf, bytes| async move {
+            buf.extend_from_slice(&bytes);
+            Ok(buf)
+        })
+        .await
+    }
+}
+
+/// List the files in the transaction log of `table`.
+async fn list_log_files(ctx: &Context, table: &DeltaLocator) -> Result<Vec<LogFile>> {
+    match &table.path {
+        DeltaTablePath::Local(path) => {
+            let log_dir = path.join(LOG_DIR);
+            let mut entries = tokio::fs::read_dir(&log_dir)
+                .await
+                .with_context(|_| format!("cannot read {}", log_dir.display()))?;
+            let mut files = vec![];
+            while let Some(entry) = entries.next_entry().await? {
+                files.push(LogFile::Local(entry.path()));
+            }
+            Ok(files)
+        }
+        DeltaTablePath::S3(url) => {
+            let log_url = url.join(&format!("{}/", LOG_DIR))?;
+            s3::ls(ctx, &log_url)
+                .await?
+                .map_ok(LogFile::S3)
+                .try_collect()
+                .await
+        }
+        DeltaTablePath::Gs(url) => {
+            let log_url = url.join(&format!("{}/", LOG_DIR))?;
+            storage::ls(ctx, &log_url)
+                .await?
+                .map_ok(LogFile::Gs)
+                .try_collect()
+                .await
+        }
+    }
+}
+
+/// Guess a table name from the last component of the table's path.
+fn default_table_name(table: &DeltaLocator) -> String {
+    let path = match &table.path {
+        DeltaTablePath::Local(path) => path.to_string_lossy().into_owned(),
+        DeltaTablePath::S3(url) | DeltaTablePath::Gs(url) => url.path().to_owned(),
+    };
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("delta_table")
+        .to_owned()
+}
+
+#[test]
+fn default_table_names() {
+    let name = |s: &str| default_table_name(&s.parse::<DeltaLocator>().unwrap());
+    assert_eq!(name("delta:/data/orders/"), "orders");
+    assert_eq!(name("delta:s3://bucket/warehouse/orders"), "orders");
+    assert_eq!(name("delta:gs://bucket/"), "delta_table");
+}
+
+/// Find the most recent `metaData` action in a commit file.
+fn find_metadata(data: &[u8]) -> Result<Option<Metadata>> {
+    let mut found = None;
+    for line in data.split(|&b| b == b'\n') {
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+        }
+        let action = serde_json::from_slice::<Action>(line)?;
+        if let Some(metadata) = action.meta_data {
+            found = Some(metadata);
+        }
+    }
+    Ok(found)
+}
+
+#[test]
+fn finds_metadata_in_commit() {
+    let commit = br#"{"commitInfo":{"operation":"WRITE"}}
+{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}
+{"metaData":{"id":"x","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[]}","partitionColumns":[],"configuration":{}}}
+{"add":{"path":"part-00000.parquet","partitionValues":{},"size":1,"modificationTime":0,"dataChange":true}}
+"#;
+    let metadata = find_metadata(commit).unwrap().unwrap();
+    assert!(metadata.schema_string.contains("struct"));
+    assert!(find_metadata(b"{\"add\":{}}\n").unwrap().is_none());
+}
+
+/// Implementation of `schema`, but as a real `async` function.
+pub(super) async fn schema_helper(
+    ctx: Context,
+    source: DeltaLocator,
+) -> Result<Option<Schema>> {
+    let files = list_log_files(&ctx, &source).await?;
+    let has_checkpoint = files.iter().any(|f| f.is_checkpoint());
+    let mut commits = files
+        .into_iter()
+        .filter_map(|f| f.commit_version().map(|v| (v, f)))
+        .collect::<Vec<_>>();
+    commits.sort_by(|(v1, _), (v2, _)| v2.cmp(v1));
+
+    // Walk backwards through the log until we find the latest table metadata.
+    for (version, file) in commits {
+        debug!(
+            ctx.log(),
+            "checking version {} of {} for metadata", version, source
+        );
+        let data = file.read(&ctx).await?;
+        let metadata = find_metadata(&data).with_context(|_| {
+            format!("error parsing {} in {}", file.file_name(), source)
+        })?;
+        if let Some(metadata) = metadata {
+            let spark = serde_json::from_str::<Value>(&metadata.schema_string)
+                .with_context(|_| format!("error parsing schema of {}", source))?;
+            let name = metadata.name.unwrap_or_else(|| default_table_name(&source));
+            let schema = schema_from_spark(&name, &spark)
+                .with_context(|_| format!("error reading schema of {}", source))?;
+            return Ok(Some(schema));
+        }
+    }
+
+    if has_checkpoint {
+        Err(format_err!(
+            "the metadata for {} is only stored in a Parquet checkpoint, which we can't read yet",
+            source,
+        ))
+    } else {
+        Err(format_err!(
+            "could not find any table metadata in {}",
+            source
+        ))
+    }
+}
@@ -0,0 +1,127 @@
+//! Driver for working with Delta Lake tables.
+//!
+//! Spec: https://github.com/delta-io/delta/blob/master/PROTOCOL.md
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use crate::common::*;
+
+mod log;
+mod spark_schema;
+mod write_local_data;
+
+use self::log::schema_helper;
+use self::write_local_data::write_local_data_helper;
+
+/// Where a Delta table is stored.
+#[derive(Clone, Debug)]
+pub(crate) enum DeltaTablePath {
+    /// A directory on the local file system.
+    Local(PathBuf),
+    /// An `s3://` URL.
+    S3(Url),
+    /// A `gs://` URL.
+    Gs(Url),
+}
+
+/// A Delta Lake table, like `delta:/data/orders/` or
+/// `delta:s3://bucket/orders/`.
+#[derive(Clone, Debug)]
+pub(crate) struct DeltaLocator {
+    path: DeltaTablePath,
+}
+
+impl fmt::Display for DeltaLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            DeltaTablePath::Local(path) => {
+                write!(f, "{}{}", Self::scheme(), path.display())
+            }
+            DeltaTablePath::S3(url) | DeltaTablePath::Gs(url) => {
+                write!(f, "{}{}", Self::scheme(), url)
+            }
+        }
+    }
+}
+
+impl FromStr for DeltaLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path_str = s.strip_prefix(Self::scheme()).ok_or_else(|| {
+            format_err!("expected {:?} to begin with {}", s, Self::scheme())
+        })?;
+        let path = if path_str.starts_with("s3://") || path_str.starts_with("gs://") {
+            let mut url = path_str
+                .parse::<Url>()
+                .with_context(|_| format!("cannot parse {}", s))?;
+            // We always treat the table as a directory.
+            if !url.path().ends_with('/') {
+                url.set_path(&format!("{}/", url.path()));
+            }
+            if url.scheme() == "s3" {
+                DeltaTablePath::S3(url)
+            } else {
+                DeltaTablePath::Gs(url)
+            }
+        } else if path_str.is_empty() || path_str == "-" {
+            return Err(format_err!("expected a table directory in {:?}", s));
+        } else {
+            DeltaTablePath::Local(PathBuf::from(path_str))
+        };
+        Ok(DeltaLocator { path })
+    }
+}
+
+#[test]
+fn parses_cloud_urls_as_directories() {
+    let loc = "delta:s3://bucket/orders".parse::<DeltaLocator>().unwrap();
+    assert_eq!(loc.to_string(), "delta:s3://bucket/orders/");
+    let loc = "delta:gs://bucket/orders/".parse::<DeltaLocator>().unwrap();
+    assert!(matches!(loc.path, DeltaTablePath::Gs(_)));
+    assert!("delta:-".parse::<DeltaLocator>().is_err());
+}
+
+impl Locator for DeltaLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.to_owned(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for DeltaLocator {
+    fn scheme() -> &'static str {
+        "delta:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::Error.into(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
@@ -0,0 +1,263 @@
+//! Converting between portable schemas and the Spark schemas stored in Delta
+//! table metadata.
+//!
+//! Spark schemas are JSON documents, so we build and parse them as
+//! `serde_json::Value` instead of defining a full set of types.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::common::*;
+use crate::drivers::parquet_shared::ParquetType;
+use crate::schema::{Column, DataType, StructField};
+
+/// Parse a Spark `struct` schema into a portable schema named `table_name`.
+pub(crate) fn schema_from_spark(table_name: &str, spark: &Value) -> Result<Schema> {
+    let columns = struct_fields(spark)?
+        .iter()
+        .map(|field| {
+            let (name, is_nullable, data_type) = parse_field(field)?;
+            let comment = field
+                .get("metadata")
+                .and_then(|metadata| metadata.get("comment"))
+                .and_then(|comment| comment.as_str());
+            Ok(Column {
+                name,
+                is_nullable,
+                data_type,
+                comment: comment.map(|c| c.to_owned()),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Schema::from_table(Table {
+        name: table_name.to_owned(),
+        columns,
+    })
+}
+
+/// Get the fields of a Spark `struct`.
+fn struct_fields(spark: &Value) -> Result<&[Value]> {
+    if spark.get("type").and_then(|ty| ty.as_str()) != Some("struct") {
+        return Err(format_err!("expected a Spark struct, found {}", spark));
+    }
+    spark
+        .get("fields")
+        .and_then(|fields| fields.as_array())
+        .map(|fields| &fields[..])
+        .ok_or_else(|| format_err!("Spark struct has no fields: {}", spark))
+}
+
+/// Parse a Spark struct field, returning its name, nullability and type.
+fn parse_field(field: &Value) -> Result<(String, bool, DataType)> {
+    let name = field
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| format_err!("Spark field has no name: {}", field))?;
+    let ty = field
+        .get("type")
+        .ok_or_else(|| format_err!("Spark field {:?} has no type", name))?;
+    let is_nullable = field
+        .get("nullable")
+        .and_then(|nullable| nullable.as_bool())
+        .unwrap_or(true);
+    let data_type = parse_type(ty).map_err(|_| ErrorKind::UnsupportedType {
+        column: name.to_owned(),
+        type_name: ty.to_string(),
+    })?;
+    Ok((name.to_owned(), is_nullable, data_type))
+}
+
+/// Parse a Spark type.
+fn parse_type(ty: &Value) -> Result<DataType> {
+    lazy_static! {
+        static ref DECIMAL_RE: Regex = Regex::new(r"^decimal\(\s*\d+\s*,\s*\d+\s*\)$")
+            .expect("invalid `DECIMAL_RE` in source");
+    }
+
+    match ty {
+        Value::String(name) => Ok(match &name[..] {
+            "boolean" => DataType::Bool,
+            "byte" | "short" => DataType::Int16,
+            "date" => DataType::Date,
+            "double" => DataType::Float64,
+            "float" => DataType::Float32,
+            "integer" => DataType::Int32,
+            "long" => DataType::Int64,
+            "string" => DataType::Text,
+            "timestamp" => DataType::TimestampWithTimeZone,
+            "timestamp_ntz" => DataType::TimestampWithoutTimeZone,
+            name if DECIMAL_RE.is_match(name) => DataType::Decimal,
+            _ => return Err(format_err!("unsupported Spark type {}", ty)),
+        }),
+        Value::Object(_) => match ty.get("type").and_then(|t| t.as_str()) {
+            Some("array") => {
+                let elem = ty
+                    .get("elementType")
+                    .ok_or_else(|| format_err!("Spark array has no elementType"))?;
+                Ok(DataType::Array(Box::new(parse_type(elem)?)))
+            }
+            // We don't have a portable map type, so treat maps as JSON objects.
+            Some("map") => Ok(DataType::Json),
+            Some("struct") => Ok(DataType::Struct(
+                struct_fields(ty)?
+                    .iter()
+                    .map(|field| {
+                        let (name, is_nullable, data_type) = parse_field(field)?;
+                        Ok(StructField {
+                            name,
+                            is_nullable,
+                            data_type,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            _ => Err(format_err!("unsupported Spark type {}", ty)),
+        },
+        _ => Err(format_err!("unsupported Spark type {}", ty)),
+    }
+}
+
+/// Build a Spark `struct` schema describing the Parquet files we write for
+/// `schema`.
+///
+/// This needs to match the output of `parquet_shared` exactly, so any column
+/// that we store as text is declared as a `string`.
+pub(crate) fn spark_schema_for(schema: &Schema) -> Result<Value> {
+    lazy_static! {
+        // Characters which Delta forbids in column names unless column mapping
+        // is enabled.
+        static ref INVALID_NAME_RE: Regex =
+            Regex::new(r"[ ,;{}()\n\t=]").expect("invalid `INVALID_NAME_RE` in source");
+    }
+
+    let fields = schema
+        .table
+        .columns
+        .iter()
+        .map(|col| {
+            if INVALID_NAME_RE.is_match(&col.name) {
+                return Err(format_err!(
+                    "cannot use {:?} as a Delta column name (contains one of \" ,;{{}}()\\n\\t=\")",
+                    col.name,
+                ));
+            }
+            let ty = match ParquetType::for_data_type(schema, &col.data_type) {
+                ParquetType::Boolean => "boolean",
+                ParquetType::Int16 => "short",
+                ParquetType::Int32 => "integer",
+                ParquetType::Int64 => "long",
+                ParquetType::Float => "float",
+                ParquetType::Double => "double",
+                ParquetType::Date => "date",
+                ParquetType::TimestampMicros => "timestamp",
+                ParquetType::Utf8 => "string",
+            };
+            let mut metadata = json!({});
+            if let Some(comment) = &col.comment {
+                metadata["comment"] = Value::String(comment.to_owned());
+            }
+            Ok(json!({
+                "name": col.name,
+                "type": ty,
+                "nullable": col.is_nullable,
+                "metadata": metadata,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({ "type": "struct", "fields": fields }))
+}
+
+#[test]
+fn parse_spark_schema() {
+    let spark = json!({
+        "type": "struct",
+        "fields": [
+            { "name": "id", "type": "long", "nullable": false, "metadata": {} },
+            {
+                "name": "name",
+                "type": "string",
+                "nullable": true,
+                "metadata": { "comment": "Customer name" },
+            },
+            { "name": "price", "type": "decimal(10,2)", "nullable": true, "metadata": {} },
+            {
+                "name": "tags",
+                "type": { "type": "array", "elementType": "string", "containsNull": true },
+                "nullable": true,
+                "metadata": {},
+            },
+            {
+                "name": "attrs",
+                "type": {
+                    "type": "map",
+                    "keyType": "string",
+                    "valueType": "string",
+                    "valueContainsNull": true,
+                },
+                "nullable": true,
+                "metadata": {},
+            },
+            {
+                "name": "point",
+                "type": {
+                    "type": "struct",
+                    "fields": [
+                        { "name": "x", "type": "double", "nullable": false, "metadata": {} },
+                    ],
+                },
+                "nullable": true,
+                "metadata": {},
+            },
+            { "name": "created_at", "type": "timestamp", "nullable": true, "metadata": {} },
+        ],
+    });
+    let schema = schema_from_spark("orders", &spark).unwrap();
+    let columns = &schema.table.columns;
+    assert_eq!(schema.table.name, "orders");
+    assert_eq!(columns[0].data_type, DataType::Int64);
+    assert!(!columns[0].is_nullable);
+    assert_eq!(columns[1].comment.as_deref(), Some("Customer name"));
+    assert_eq!(columns[2].data_type, DataType::Decimal);
+    assert_eq!(
+        columns[3].data_type,
+        DataType::Array(Box::new(DataType::Text)),
+    );
+    assert_eq!(columns[4].data_type, DataType::Json);
+    assert_eq!(
+        columns[5].data_type,
+        DataType::Struct(vec![StructField {
+            name: "x".to_owned(),
+            is_nullable: false,
+            data_type: DataType::Float64,
+        }]),
+    );
+    assert_eq!(columns[6].data_type, DataType::TimestampWithTimeZone);
+}
+
+#[test]
+fn spark_schema_matches_parquet_output() {
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            Column {
+                name: "a".to_owned(),
+                is_nullable: true,
+                data_type: DataType::Int16,
+                comment: Some("A".to_owned()),
+            },
+            Column {
+                name: "b".to_owned(),
+                is_nullable: false,
+                data_type: DataType::Decimal,
+                comment: None,
+            },
+        ],
+    })
+    .unwrap();
+    let spark = spark_schema_for(&schema).unwrap();
+    assert_eq!(spark["fields"][0]["type"], "short");
+    assert_eq!(spark["fields"][0]["metadata"]["comment"], "A");
+    assert_eq!(spark["fields"][1]["type"], "string");
+    assert_eq!(spark["fields"][1]["nullable"], false);
+}
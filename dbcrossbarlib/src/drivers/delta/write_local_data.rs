@@ -0,0 +1,145 @@
+//! Writing data to new Delta tables.
+
+use chrono::Utc;
+use serde_json::json;
+use std::{fs::File, path::PathBuf};
+use uuid::Uuid;
+
+use super::{
+    log::LOG_DIR, spark_schema::spark_schema_for, DeltaLocator, DeltaTablePath,
+};
+use crate::common::*;
+use crate::drivers::parquet_shared::{write_csv_as_parquet, ParquetWriteOptions};
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+pub(super) async fn write_local_data_helper(
+    ctx: Context,
+    dest: DeltaLocator,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(DeltaLocator::features())?;
+    let _dest_args = dest_args.verify(DeltaLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let max_streams = shared_args.max_streams();
+    let dir = match &dest.path {
+        DeltaTablePath::Local(dir) => dir.to_owned(),
+        _ => {
+            return Err(format_err!(
+                "can only write Delta tables to local directories, not {}",
+                dest,
+            ))
+        }
+    };
+
+    // Check our schema before we start writing anything.
+    let spark_schema = spark_schema_for(&schema)?;
+
+    // We only create new tables, so refuse to touch an existing log.
+    let log_dir = dir.join(LOG_DIR);
+    if tokio::fs::metadata(&log_dir).await.is_ok() {
+        return Err(format_err!("{} already exists", dest));
+    }
+    tokio::fs::create_dir_all(&log_dir)
+        .await
+        .with_context(|_| format!("cannot create {}", log_dir.display()))?;
+
+    let fut = async move {
+        // Write each stream to its own Parquet file.
+        let add_actions = data
+            .enumerate()
+            .map(|(idx, stream)| stream.map(|stream| (idx, stream)))
+            .map_ok(|(idx, stream)| {
+                write_part(ctx.clone(), dir.clone(), idx, schema.clone(), stream)
+                    .boxed()
+            })
+            .try_buffer_unordered(max_streams)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // Commit version 0 of our table, which makes the data visible.
+        let now = Utc::now().timestamp_millis();
+        let mut actions = vec![
+            json!({ "protocol": { "minReaderVersion": 1, "minWriterVersion": 2 } }),
+            json!({
+                "metaData": {
+                    "id": Uuid::new_v4().to_string(),
+                    "format": { "provider": "parquet", "options": {} },
+                    "schemaString": spark_schema.to_string(),
+                    "partitionColumns": [],
+                    "configuration": {},
+                    "createdTime": now,
+                }
+            }),
+        ];
+        actions.extend(add_actions);
+        let mut commit = String::new();
+        for action in &actions {
+            commit.push_str(&action.to_string());
+            commit.push('\n');
+        }
+        let commit_path = log_dir.join(format!("{:020}.json", 0));
+        debug!(ctx.log(), "committing {}", commit_path.display());
+        let mut f = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&commit_path)
+            .await
+            .with_context(|_| format!("cannot create {}", commit_path.display()))?;
+        f.write_all(commit.as_bytes())
+            .await
+            .with_context(|_| format!("error writing {}", commit_path.display()))?;
+        f.flush().await?;
+        Ok(dest.boxed())
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
+
+/// Write `stream` to a new Parquet file in `dir`, and return an `add` action
+/// for our transaction log.
+async fn write_part(
+    ctx: Context,
+    dir: PathBuf,
+    idx: usize,
+    schema: Schema,
+    stream: CsvStream,
+) -> Result<serde_json::Value> {
+    let file_name = format!("part-{:05}-{}-c000.snappy.parquet", idx, Uuid::new_v4());
+    let path = dir.join(&file_name);
+    let ctx = ctx.child(o!(
+        "stream" => stream.name.clone(),
+        "path" => format!("{}", path.display()),
+    ));
+    let csv_data = stream
+        .data
+        .try_fold(vec![], |mut buf, bytes| async move {
+            buf.extend_from_slice(&bytes);
+            Ok(buf)
+        })
+        .await?;
+
+    // The `parquet` crate is synchronous, so write our file on a helper thread.
+    debug!(ctx.log(), "writing Parquet data");
+    let (row_count, size) = spawn_blocking(move || {
+        let file = File::create(&path)
+            .with_context(|_| format!("cannot create {}", path.display()))?;
+        let options = ParquetWriteOptions::default();
+        let row_count = write_csv_as_parquet(&schema, &csv_data, file, &options)
+            .with_context(|_| format!("error writing {}", path.display()))?;
+        let size = std::fs::metadata(&path)?.len();
+        Ok((row_count, size))
+    })
+    .await?;
+    debug!(ctx.log(), "wrote {} rows ({} bytes)", row_count, size);
+
+    Ok(json!({
+        "add": {
+            "path": file_name,
+            "partitionValues": {},
+            "size": size,
+            "modificationTime": Utc::now().timestamp_millis(),
+            "dataChange": true,
+        }
+    }))
+}
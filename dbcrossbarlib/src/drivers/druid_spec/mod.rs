@@ -0,0 +1,344 @@
+//! Driver for generating Apache Druid native batch ingestion specs.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::schema::DataType;
+
+/// A JSON file containing a Druid ingestion spec, suitable for submitting to
+/// `POST /druid/indexer/v1/task`.
+#[derive(Clone, Debug)]
+pub(crate) struct DruidSpecLocator {
+    path: PathOrStdio,
+}
+
+impl fmt::Display for DruidSpecLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for DruidSpecLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(DruidSpecLocator { path })
+    }
+}
+
+impl Locator for DruidSpecLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+}
+
+impl LocatorStatic for DruidSpecLocator {
+    fn scheme() -> &'static str {
+        "druid-spec:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::WriteSchema.into(),
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DruidSpecDestinationArguments {
+    /// `s3://` or `gs://` prefixes containing the CSV files to ingest.
+    #[serde(default)]
+    input: Vec<String>,
+
+    /// The Druid data source to create. Defaults to the table name.
+    data_source: Option<String>,
+
+    /// The column to use as Druid's `__time`. Defaults to the first timestamp
+    /// or date column.
+    timestamp_column: Option<String>,
+
+    /// The segment granularity to use.
+    #[serde(default = "default_segment_granularity")]
+    segment_granularity: String,
+}
+
+/// Our default segment granularity.
+fn default_segment_granularity() -> String {
+    "day".to_owned()
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: DruidSpecLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args = dest_args.verify_for_write_schema(DruidSpecLocator::features())?;
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<DruidSpecDestinationArguments>()
+        .context("could not parse --to-arg")?;
+    let spec = spec_for(&schema, &to_args)?;
+    let mut f = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
+    buffer_sync_write_and_copy_to_async(&mut f, |buff| {
+        serde_json::to_writer_pretty(&mut *buff, &spec)?;
+        writeln!(buff)?;
+        Ok::<_, Error>(())
+    })
+    .await
+    .with_context(|_| format!("error writing to {}", dest.path))?;
+    f.flush().await?;
+    Ok(())
+}
+
+/// Build an ingestion spec for our table.
+fn spec_for(
+    schema: &Schema,
+    to_args: &DruidSpecDestinationArguments,
+) -> Result<Value> {
+    let columns = &schema.table.columns;
+
+    // Choose our timestamp column.
+    let timestamp_column = match &to_args.timestamp_column {
+        Some(name) => {
+            if !columns.iter().any(|c| &c.name == name) {
+                return Err(format_err!(
+                    "cannot find timestamp_column {:?} in table",
+                    name
+                ));
+            }
+            name.to_owned()
+        }
+        None => columns
+            .iter()
+            .find(|c| is_time(resolve(schema, &c.data_type)))
+            .map(|c| c.name.clone())
+            .ok_or_else(|| {
+                format_err!(
+                    "table has no timestamp columns, please specify --to-arg=timestamp_column=$COLUMN"
+                )
+            })?,
+    };
+
+    // Split our other columns into dimensions and metrics.
+    let mut dimensions = vec![];
+    let mut metrics = vec![];
+    for col in columns {
+        if col.name == timestamp_column {
+            continue;
+        }
+        let data_type = resolve(schema, &col.data_type);
+        if let Some(aggregator) = metric_aggregator(&col.name, data_type) {
+            metrics.push(json!({
+                "type": aggregator,
+                "name": col.name,
+                "fieldName": col.name,
+            }));
+        } else {
+            dimensions.push(json!({
+                "type": dimension_type(data_type),
+                "name": col.name,
+            }));
+        }
+    }
+
+    let data_source = to_args
+        .data_source
+        .clone()
+        .unwrap_or_else(|| schema.table.name.clone());
+    Ok(json!({
+        "type": "index_parallel",
+        "spec": {
+            "dataSchema": {
+                "dataSource": data_source,
+                // Our CSV interchange format uses ISO 8601 dates and times.
+                "timestampSpec": { "column": timestamp_column, "format": "auto" },
+                "dimensionsSpec": { "dimensions": dimensions },
+                "metricsSpec": metrics,
+                "granularitySpec": {
+                    "segmentGranularity": to_args.segment_granularity,
+                    "queryGranularity": "none",
+                    "rollup": false,
+                },
+            },
+            "ioConfig": {
+                "type": "index_parallel",
+                "inputSource": input_source(&to_args.input)?,
+                "inputFormat": { "type": "csv", "findColumnsFromHeader": true },
+            },
+            "tuningConfig": { "type": "index_parallel" },
+        },
+    }))
+}
+
+/// Build a Druid `inputSource` for a list of cloud storage prefixes.
+fn input_source(input: &[String]) -> Result<Value> {
+    let druid_type = if input.is_empty() {
+        return Err(format_err!(
+            "please specify input files using --to-arg=input[]=s3://... or gs://..."
+        ));
+    } else if input.iter().all(|i| i.starts_with("s3://")) {
+        "s3"
+    } else if input.iter().all(|i| i.starts_with("gs://")) {
+        "google"
+    } else {
+        return Err(format_err!(
+            "input[] must contain only s3:// URLs or only gs:// URLs"
+        ));
+    };
+    Ok(json!({ "type": druid_type, "prefixes": input }))
+}
+
+/// Look up the real type of any named types.
+fn resolve<'a>(schema: &'a Schema, data_type: &'a DataType) -> &'a DataType {
+    match data_type {
+        DataType::Named(name) => resolve(schema, schema.data_type_for_name(name)),
+        other => other,
+    }
+}
+
+/// Is this a type we can use as Druid's `__time` column?
+fn is_time(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Date
+            | DataType::TimestampWithoutTimeZone
+            | DataType::TimestampWithTimeZone
+    )
+}
+
+/// Guess whether a column should be a metric, and return the aggregator to use
+/// if so.
+///
+/// Numeric columns are usually things we want to sum, unless their names
+/// suggest that they're identifiers or codes.
+fn metric_aggregator(name: &str, data_type: &DataType) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    let last_word = name.rsplit('_').next().unwrap_or(&name);
+    if ["id", "code", "key", "year", "zip"].contains(&last_word) {
+        return None;
+    }
+    match data_type {
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => Some("longSum"),
+        DataType::Decimal | DataType::Float32 | DataType::Float64 => Some("doubleSum"),
+        _ => None,
+    }
+}
+
+/// The Druid dimension type to use for `data_type`.
+fn dimension_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => "long",
+        DataType::Float32 => "float",
+        DataType::Decimal | DataType::Float64 => "double",
+        // Everything else is ingested as a string, exactly as it appears in
+        // our CSV files.
+        _ => "string",
+    }
+}
+
+#[test]
+fn spec_for_table() {
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "orders",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64" },
+      { "name": "customer_id", "is_nullable": false, "data_type": "int32" },
+      { "name": "country", "is_nullable": true, "data_type": "text" },
+      { "name": "created_at", "is_nullable": false, "data_type": "timestamp_with_time_zone" },
+      { "name": "quantity", "is_nullable": false, "data_type": "int32" },
+      { "name": "total", "is_nullable": false, "data_type": "decimal" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<DruidSpecDestinationArguments>()
+            .unwrap()
+    };
+
+    let spec = spec_for(&schema, &parse(&["input[]=s3://bucket/orders/"])).unwrap();
+    let data_schema = &spec["spec"]["dataSchema"];
+    assert_eq!(data_schema["dataSource"], "orders");
+    assert_eq!(data_schema["timestampSpec"]["column"], "created_at");
+    assert_eq!(
+        data_schema["dimensionsSpec"]["dimensions"],
+        json!([
+            { "type": "long", "name": "id" },
+            { "type": "long", "name": "customer_id" },
+            { "type": "string", "name": "country" },
+        ]),
+    );
+    assert_eq!(
+        data_schema["metricsSpec"],
+        json!([
+            { "type": "longSum", "name": "quantity", "fieldName": "quantity" },
+            { "type": "doubleSum", "name": "total", "fieldName": "total" },
+        ]),
+    );
+    assert_eq!(
+        spec["spec"]["ioConfig"]["inputSource"],
+        json!({ "type": "s3", "prefixes": ["s3://bucket/orders/"] }),
+    );
+
+    let spec = spec_for(
+        &schema,
+        &parse(&[
+            "input[]=gs://bucket/orders/",
+            "data_source=sales",
+            "segment_granularity=month",
+        ]),
+    )
+    .unwrap();
+    assert_eq!(spec["spec"]["dataSchema"]["dataSource"], "sales");
+    assert_eq!(
+        spec["spec"]["dataSchema"]["granularitySpec"]["segmentGranularity"],
+        "month"
+    );
+    assert_eq!(spec["spec"]["ioConfig"]["inputSource"]["type"], "google");
+
+    assert!(spec_for(&schema, &parse(&[])).is_err());
+    assert!(
+        spec_for(&schema, &parse(&["input[]=s3://a/", "input[]=gs://b/"])).is_err()
+    );
+    assert!(spec_for(
+        &schema,
+        &parse(&["input[]=s3://a/", "timestamp_column=missing"])
+    )
+    .is_err());
+}
@@ -0,0 +1,232 @@
+//! Driver for generating Elasticsearch and OpenSearch index mappings.
+
+use serde_json::{json, Map, Value};
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::schema::DataType;
+
+/// The date format we use for `date` columns.
+const DATE_FORMAT: &str = "strict_date";
+
+/// The date format we use for timestamps. Our CSV interchange format uses ISO
+/// 8601, with or without a time zone.
+const TIMESTAMP_FORMAT: &str = "strict_date_optional_time||epoch_millis";
+
+/// A JSON file containing an index mapping, suitable for passing to
+/// `PUT /$INDEX`.
+#[derive(Clone, Debug)]
+pub(crate) struct ElasticsearchMappingLocator {
+    path: PathOrStdio,
+}
+
+impl fmt::Display for ElasticsearchMappingLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for ElasticsearchMappingLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(ElasticsearchMappingLocator { path })
+    }
+}
+
+impl Locator for ElasticsearchMappingLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+}
+
+impl LocatorStatic for ElasticsearchMappingLocator {
+    fn scheme() -> &'static str {
+        "elasticsearch-mapping:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::WriteSchema.into(),
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: ElasticsearchMappingLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args =
+        dest_args.verify_for_write_schema(ElasticsearchMappingLocator::features())?;
+    let mapping = mapping_for(&schema)?;
+    let mut f = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
+    buffer_sync_write_and_copy_to_async(&mut f, |buff| {
+        serde_json::to_writer_pretty(&mut *buff, &mapping)?;
+        writeln!(buff)?;
+        Ok::<_, Error>(())
+    })
+    .await
+    .with_context(|_| format!("error writing to {}", dest.path))?;
+    f.flush().await?;
+    Ok(())
+}
+
+/// Build an index mapping for our table.
+fn mapping_for(schema: &Schema) -> Result<Value> {
+    let mut properties = Map::new();
+    for col in &schema.table.columns {
+        properties.insert(
+            col.name.clone(),
+            field_mapping(schema, &col.name, &col.data_type)?,
+        );
+    }
+    Ok(json!({ "mappings": { "properties": properties } }))
+}
+
+/// Build the mapping for a single field.
+///
+/// Elasticsearch fields can always hold multiple values, so arrays use the
+/// mapping of their elements. The exception is arrays of structs, which we
+/// map as `nested` so that each struct can be queried independently.
+fn field_mapping(schema: &Schema, name: &str, data_type: &DataType) -> Result<Value> {
+    Ok(match data_type {
+        DataType::Array(elem_type) => match elem_type.as_ref() {
+            DataType::Struct(_) => {
+                let mut mapping = field_mapping(schema, name, elem_type)?;
+                mapping["type"] = json!("nested");
+                mapping
+            }
+            elem_type => field_mapping(schema, name, elem_type)?,
+        },
+        DataType::Bool => json!({ "type": "boolean" }),
+        DataType::Date => json!({ "type": "date", "format": DATE_FORMAT }),
+        // Elasticsearch has no arbitrary-precision decimal type.
+        DataType::Decimal => json!({ "type": "double" }),
+        DataType::Float32 => json!({ "type": "float" }),
+        DataType::Float64 => json!({ "type": "double" }),
+        DataType::GeoJson(_) => json!({ "type": "geo_shape" }),
+        DataType::Int16 => json!({ "type": "short" }),
+        DataType::Int32 => json!({ "type": "integer" }),
+        DataType::Int64 => json!({ "type": "long" }),
+        // We don't know anything about the structure of JSON values, so store
+        // them without indexing them.
+        DataType::Json => json!({ "type": "object", "enabled": false }),
+        DataType::Named(type_name) => {
+            field_mapping(schema, name, schema.data_type_for_name(type_name))?
+        }
+        DataType::OneOf(_) | DataType::Uuid => json!({ "type": "keyword" }),
+        DataType::Struct(fields) => {
+            let mut properties = Map::new();
+            for field in fields {
+                properties.insert(
+                    field.name.clone(),
+                    field_mapping(schema, &field.name, &field.data_type)?,
+                );
+            }
+            json!({ "type": "object", "properties": properties })
+        }
+        DataType::Text if looks_like_keyword(name) => json!({ "type": "keyword" }),
+        // This matches what Elasticsearch does for dynamically-mapped strings,
+        // so that text can be searched and also sorted or aggregated.
+        DataType::Text => json!({
+            "type": "text",
+            "fields": { "keyword": { "type": "keyword", "ignore_above": 256 } },
+        }),
+        DataType::TimestampWithoutTimeZone | DataType::TimestampWithTimeZone => {
+            json!({ "type": "date", "format": TIMESTAMP_FORMAT })
+        }
+    })
+}
+
+/// Guess whether a text column named `name` holds identifiers or codes, which
+/// should be matched exactly, instead of prose, which should be analyzed.
+fn looks_like_keyword(name: &str) -> bool {
+    /// Names of columns which usually hold identifiers. We also check whether
+    /// a name ends with `_` followed by one of these.
+    const KEYWORD_NAMES: &[&str] = &[
+        "code", "country", "currency", "email", "hash", "id", "ip", "key", "kind",
+        "locale", "sku", "slug", "state", "status", "tag", "token", "type", "url",
+        "uuid", "zip",
+    ];
+
+    let name = name.to_ascii_lowercase();
+    let last_word = name.rsplit('_').next().unwrap_or(&name);
+    KEYWORD_NAMES.contains(&last_word)
+}
+
+#[test]
+fn keyword_heuristics() {
+    assert!(looks_like_keyword("id"));
+    assert!(looks_like_keyword("customer_id"));
+    assert!(looks_like_keyword("Country_Code"));
+    assert!(looks_like_keyword("status"));
+    assert!(!looks_like_keyword("description"));
+    assert!(!looks_like_keyword("first_name"));
+    assert!(!looks_like_keyword("valid"));
+}
+
+#[test]
+fn mapping_for_table() {
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "text" },
+      { "name": "bio", "is_nullable": true,  "data_type": "text" },
+      { "name": "born", "is_nullable": true,  "data_type": "date" },
+      { "name": "seen_at", "is_nullable": true,  "data_type": "timestamp_with_time_zone" },
+      { "name": "tags", "is_nullable": true,  "data_type": { "array": "text" } },
+      { "name": "addresses", "is_nullable": true,  "data_type": { "array": { "struct": [
+        { "name": "city", "data_type": "text", "is_nullable": true },
+        { "name": "zip", "data_type": "text", "is_nullable": true }
+      ] } } },
+      { "name": "extra", "is_nullable": true,  "data_type": "json" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let mapping = mapping_for(&schema).unwrap();
+    let properties = &mapping["mappings"]["properties"];
+    assert_eq!(properties["id"]["type"], "keyword");
+    assert_eq!(properties["bio"]["type"], "text");
+    assert_eq!(properties["bio"]["fields"]["keyword"]["type"], "keyword");
+    assert_eq!(properties["born"]["format"], DATE_FORMAT);
+    assert_eq!(properties["seen_at"]["format"], TIMESTAMP_FORMAT);
+    assert_eq!(properties["tags"]["type"], "text");
+    assert_eq!(properties["addresses"]["type"], "nested");
+    assert_eq!(
+        properties["addresses"]["properties"]["zip"]["type"],
+        "keyword"
+    );
+    assert_eq!(properties["extra"]["enabled"], false);
+}
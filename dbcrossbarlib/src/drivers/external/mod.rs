@@ -0,0 +1,311 @@
+//! Support for external drivers, which are separate programs named
+//! `dbcrossbar-driver-$NAME` that we find on the `PATH`.
+//!
+//! We talk to an external driver by running it with no arguments, and writing
+//! a single line of JSON describing a request to its standard input. What
+//! happens next depends on the request:
+//!
+//! - `schema`: The driver writes a `dbcrossbar-schema` JSON document (or
+//!   `null`) to standard output.
+//! - `write_schema`: The driver writes the `schema` included in the request.
+//! - `local_data`: The driver writes CSV data (with a header row) to standard
+//!   output.
+//! - `write_local_data`: The driver reads CSV data (with a header row) from
+//!   the rest of standard input.
+//!
+//! The driver should exit with a non-zero status to report an error, and it
+//! should print error messages to standard error.
+
+use dbcrossbarschema::external_schema::ExternalSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::{env, fmt, path::PathBuf, process::Stdio};
+use tokio::{
+    io::BufReader,
+    process::{Child, ChildStdin, Command},
+};
+
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::tokio_glue::{copy_reader_to_stream, copy_stream_to_writer};
+
+/// The prefix we add to a locator scheme to find the external driver's program.
+const PROGRAM_PREFIX: &str = "dbcrossbar-driver-";
+
+/// A locator handled by an external driver program.
+#[derive(Clone, Debug)]
+pub struct ExternalLocator {
+    /// The path to the driver program.
+    program: PathBuf,
+    /// The full locator string, including the scheme.
+    locator: String,
+}
+
+impl ExternalLocator {
+    /// If there's an external driver for `scheme` on our `PATH`, return a
+    /// locator for `locator` which uses it.
+    pub(crate) fn find(scheme: &str, locator: &str) -> Option<ExternalLocator> {
+        let name = scheme.trim_end_matches(':');
+        let program_name =
+            format!("{}{}{}", PROGRAM_PREFIX, name, env::consts::EXE_SUFFIX);
+        let path = env::var_os("PATH")?;
+        env::split_paths(&path)
+            .map(|dir| dir.join(&program_name))
+            .find(|program| program.is_file())
+            .map(|program| ExternalLocator {
+                program,
+                locator: locator.to_owned(),
+            })
+    }
+
+    /// The features we support. External drivers may not actually support all
+    /// of these, but we have no way to know that in advance, so we let the
+    /// driver report an error if it can't handle a request.
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema
+                | LocatorFeatures::WriteSchema
+                | LocatorFeatures::LocalData
+                | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            source_args: SourceArgumentsFeatures::DriverArgs
+                | SourceArgumentsFeatures::WhereClause,
+            dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            dest_if_exists: IfExistsFeatures::Append
+                | IfExistsFeatures::Error
+                | IfExistsFeatures::Overwrite
+                | IfExistsFeatures::Upsert,
+            _placeholder: (),
+        }
+    }
+
+    /// Spawn our driver and send it `request`. The caller is responsible for
+    /// closing `stdin` once it has written any data, and for waiting for the
+    /// child to exit.
+    async fn spawn(
+        &self,
+        request: &Request<'_>,
+        stdout: Stdio,
+    ) -> Result<(Child, ChildStdin)> {
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(stdout)
+            .spawn()
+            .with_context(|_| format!("error running {}", self.program.display()))?;
+        let mut stdin = child.stdin.take().expect("child should have stdin");
+        let mut request_json = serde_json::to_vec(request)?;
+        request_json.push(b'\n');
+        stdin.write_all(&request_json).await.with_context(|_| {
+            format!("error writing to {}", self.program.display())
+        })?;
+        Ok((child, stdin))
+    }
+
+    /// Run our driver with `request`, and return its output.
+    async fn run(&self, request: &Request<'_>) -> Result<Vec<u8>> {
+        let (child, stdin) = self.spawn(request, Stdio::piped()).await?;
+        drop(stdin);
+        let output = child
+            .wait_with_output()
+            .await
+            .with_context(|_| format!("error running {}", self.program.display()))?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(format_err!(
+                "{} failed with {}",
+                self.program.display(),
+                output.status,
+            ))
+        }
+    }
+}
+
+impl fmt::Display for ExternalLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.locator.fmt(f)
+    }
+}
+
+impl Locator for ExternalLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.to_owned(), shared_args, source_args).boxed()
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.to_owned(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+/// A request sent to an external driver.
+#[derive(Debug, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request<'a> {
+    Schema {
+        locator: &'a str,
+    },
+    WriteSchema {
+        locator: &'a str,
+        schema: ExternalSchema,
+        if_exists: String,
+        driver_args: Value,
+    },
+    LocalData {
+        locator: &'a str,
+        schema: ExternalSchema,
+        driver_args: Value,
+        where_clause: Option<&'a str>,
+    },
+    WriteLocalData {
+        locator: &'a str,
+        schema: ExternalSchema,
+        if_exists: String,
+        driver_args: Value,
+    },
+}
+
+/// Implementation of `schema`, but as a real `async` function.
+async fn schema_helper(
+    ctx: Context,
+    source: ExternalLocator,
+) -> Result<Option<Schema>> {
+    debug!(
+        ctx.log(),
+        "fetching schema using {}",
+        source.program.display()
+    );
+    let request = Request::Schema {
+        locator: &source.locator,
+    };
+    let output = source.run(&request).await?;
+    let external_schema: Option<ExternalSchema> = serde_json::from_slice(&output)
+        .with_context(|_| {
+            format!("error parsing schema from {}", source.program.display())
+        })?;
+    external_schema.map(|s| s.into_schema()).transpose()
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: ExternalLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args = dest_args.verify_for_write_schema(ExternalLocator::features())?;
+    debug!(ctx.log(), "writing schema using {}", dest.program.display());
+    let request = Request::WriteSchema {
+        locator: &dest.locator,
+        schema: ExternalSchema::from_schema(schema),
+        if_exists: dest_args.if_exists().to_string(),
+        driver_args: dest_args.driver_args().to_json()?,
+    };
+    dest.run(&request).await?;
+    Ok(())
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+async fn local_data_helper(
+    ctx: Context,
+    source: ExternalLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(ExternalLocator::features())?;
+    let source_args = source_args.verify(ExternalLocator::features())?;
+    debug!(ctx.log(), "reading data using {}", source.program.display());
+
+    let request = Request::LocalData {
+        locator: &source.locator,
+        schema: ExternalSchema::from_schema(shared_args.schema().to_owned()),
+        driver_args: source_args.driver_args().to_json()?,
+        where_clause: source_args.where_clause(),
+    };
+    let (mut child, stdin) = source.spawn(&request, Stdio::piped()).await?;
+    drop(stdin);
+    let child_stdout = child.stdout.take().expect("child should have stdout");
+    let child_stdout = BufReader::with_capacity(BUFFER_SIZE, child_stdout);
+    let data = copy_reader_to_stream(ctx.clone(), child_stdout)?;
+    ctx.spawn_process(source.program.display().to_string(), child);
+
+    let csv_stream = CsvStream {
+        name: "data".to_owned(),
+        data: data.boxed(),
+    };
+    Ok(Some(box_stream_once(Ok(csv_stream))))
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+async fn write_local_data_helper(
+    ctx: Context,
+    dest: ExternalLocator,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(ExternalLocator::features())?;
+    let dest_args = dest_args.verify(ExternalLocator::features())?;
+    debug!(ctx.log(), "writing data using {}", dest.program.display());
+
+    // External drivers only receive a single CSV stream, so combine ours.
+    let csv_stream = concatenate_csv_streams(ctx.clone(), data)?;
+
+    let fut = async move {
+        let request = Request::WriteLocalData {
+            locator: &dest.locator,
+            schema: ExternalSchema::from_schema(shared_args.schema().to_owned()),
+            if_exists: dest_args.if_exists().to_string(),
+            driver_args: dest_args.driver_args().to_json()?,
+        };
+        let (mut child, stdin) = dest.spawn(&request, Stdio::null()).await?;
+        copy_stream_to_writer(ctx.clone(), csv_stream.data, stdin)
+            .await
+            .with_context(|_| {
+                format!("error copying data to {}", dest.program.display())
+            })?;
+        let status = child
+            .wait()
+            .await
+            .with_context(|_| format!("error running {}", dest.program.display()))?;
+        if status.success() {
+            Ok(Box::new(dest.clone()) as BoxLocator)
+        } else {
+            Err(format_err!(
+                "{} failed with {}",
+                dest.program.display(),
+                status,
+            ))
+        }
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
@@ -0,0 +1,319 @@
+//! Fixed-width layout files, and converting between fixed-width text and CSV.
+
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, BufWriter};
+
+use crate::common::*;
+
+/// A layout file, describing where to find each column on a line.
+///
+/// ```json
+/// {
+///   "columns": [
+///     { "name": "id", "offset": 0, "width": 6, "align": "right" },
+///     { "name": "name", "width": 20 }
+///   ]
+/// }
+/// ```
+///
+/// Columns which don't appear in the schema are ignored when reading, and
+/// filled with spaces when writing.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+    /// The columns on each line.
+    columns: Vec<LayoutFileColumn>,
+}
+
+/// A column in a layout file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFileColumn {
+    /// The name of this column.
+    name: String,
+    /// The 0-based character offset of this column. Defaults to the end of the
+    /// previous column.
+    offset: Option<usize>,
+    /// The width of this column, in characters.
+    width: usize,
+    /// How values are padded to fill the column.
+    #[serde(default)]
+    align: Align,
+}
+
+/// How values are aligned within a column.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+enum Align {
+    /// Values are followed by spaces.
+    #[default]
+    Left,
+    /// Values are preceded by spaces.
+    Right,
+}
+
+/// A column in a fixed-width layout, with its offset filled in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct LayoutColumn {
+    name: String,
+    offset: usize,
+    width: usize,
+    align: Align,
+}
+
+/// A fixed-width layout.
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    /// Our columns, in the order they appear on each line.
+    columns: Vec<LayoutColumn>,
+}
+
+impl Layout {
+    /// Parse a layout file.
+    pub(crate) fn from_json(json: &str) -> Result<Layout> {
+        let file = serde_json::from_str::<LayoutFile>(json)?;
+        let mut columns = Vec::with_capacity(file.columns.len());
+        let mut end = 0;
+        for col in file.columns {
+            if col.width == 0 {
+                return Err(format_err!("column {:?} has a width of 0", col.name));
+            }
+            let offset = col.offset.unwrap_or(end);
+            if offset < end {
+                return Err(format_err!(
+                    "column {:?} at offset {} overlaps the previous column",
+                    col.name,
+                    offset,
+                ));
+            }
+            end = offset + col.width;
+            columns.push(LayoutColumn {
+                name: col.name,
+                offset,
+                width: col.width,
+                align: col.align,
+            });
+        }
+        Ok(Layout { columns })
+    }
+
+    /// Find the layout column corresponding to each column in `schema`.
+    fn indices_for(&self, schema: &Schema) -> Result<Vec<usize>> {
+        schema
+            .table
+            .columns
+            .iter()
+            .map(|col| {
+                self.columns
+                    .iter()
+                    .position(|lc| lc.name == col.name)
+                    .ok_or_else(|| {
+                        format_err!("cannot find column {:?} in layout", col.name)
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Read fixed-width text from `rdr`, and write it to `wtr` as CSV.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+pub(crate) fn copy_fixed_width_to_csv(
+    schema: &Schema,
+    layout: &Layout,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let indices = layout.indices_for(schema)?;
+    let rdr = BufReader::with_capacity(BUFFER_SIZE, rdr);
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    wtr.write_record(schema.table.columns.iter().map(|c| &c.name))?;
+    let mut record = Vec::with_capacity(indices.len());
+    for (line_idx, line) in rdr.lines().enumerate() {
+        let line =
+            line.with_context(|_| format!("cannot read line {}", line_idx + 1))?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.is_empty() {
+            continue;
+        }
+        let chars = line.chars().collect::<Vec<_>>();
+        record.clear();
+        for &idx in &indices {
+            let col = &layout.columns[idx];
+            let start = col.offset.min(chars.len());
+            let end = (col.offset + col.width).min(chars.len());
+            let value = chars[start..end].iter().collect::<String>();
+            // Strip the padding.
+            let value = match col.align {
+                Align::Left => value.trim_end(),
+                Align::Right => value.trim_start(),
+            };
+            record.push(value.to_owned());
+        }
+        wtr.write_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Read CSV data from `rdr`, and write it to `wtr` as fixed-width text.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+pub(crate) fn copy_csv_to_fixed_width(
+    schema: &Schema,
+    layout: &Layout,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    // Figure out which CSV column, if any, goes into each layout column.
+    let indices = layout.indices_for(schema)?;
+    let mut sources = vec![None; layout.columns.len()];
+    for (csv_idx, &layout_idx) in indices.iter().enumerate() {
+        sources[layout_idx] = Some(csv_idx);
+    }
+
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = BufWriter::with_capacity(BUFFER_SIZE, wtr);
+    let mut line = String::new();
+    for (row_idx, row) in rdr.records().enumerate() {
+        let row = row?;
+        line.clear();
+        let mut line_width = 0;
+        for (col, source) in layout.columns.iter().zip(&sources) {
+            let value = source.map(|csv_idx| &row[csv_idx]).unwrap_or("");
+            let value_width = value.chars().count();
+            if value_width > col.width || value.contains(&['\n', '\r'][..]) {
+                return Err(format_err!(
+                    "value must fit in {} characters on a single line",
+                    col.width
+                )
+                .context(ErrorKind::InvalidData {
+                    row: row_idx + 1,
+                    column: col.name.clone(),
+                    value: value.to_owned(),
+                })
+                .into());
+            }
+            let padding = col.width - value_width;
+            push_spaces(&mut line, col.offset - line_width);
+            match col.align {
+                Align::Left => {
+                    line.push_str(value);
+                    push_spaces(&mut line, padding);
+                }
+                Align::Right => {
+                    push_spaces(&mut line, padding);
+                    line.push_str(value);
+                }
+            }
+            line_width = col.offset + col.width;
+        }
+        line.push('\n');
+        wtr.write_all(line.as_bytes())?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Append `count` spaces to `s`.
+fn push_spaces(s: &mut String, count: usize) {
+    s.extend((0..count).map(|_| ' '));
+}
+
+#[test]
+fn parses_layouts() {
+    let layout = Layout::from_json(
+        r#"{ "columns": [
+            { "name": "id", "width": 4, "align": "right" },
+            { "name": "name", "offset": 5, "width": 10 },
+            { "name": "code", "width": 2 }
+        ] }"#,
+    )
+    .unwrap();
+    let offsets = layout
+        .columns
+        .iter()
+        .map(|c| (c.offset, c.width, c.align))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        offsets,
+        vec![
+            (0, 4, Align::Right),
+            (5, 10, Align::Left),
+            (15, 2, Align::Left)
+        ],
+    );
+
+    let overlapping = r#"{ "columns": [
+        { "name": "a", "width": 4 },
+        { "name": "b", "offset": 2, "width": 4 }
+    ] }"#;
+    assert!(Layout::from_json(overlapping).is_err());
+    assert!(
+        Layout::from_json(r#"{ "columns": [{ "name": "a", "width": 0 }] }"#).is_err()
+    );
+}
+
+#[test]
+fn fixed_width_round_trip() {
+    use crate::schema::{Column, DataType};
+    use crate::test_util::SharedBuffer;
+    use std::io;
+
+    let layout = Layout::from_json(
+        r#"{ "columns": [
+            { "name": "id", "width": 4, "align": "right" },
+            { "name": "filler", "width": 1 },
+            { "name": "name", "width": 6 }
+        ] }"#,
+    )
+    .unwrap();
+    let column = |name: &str, data_type| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            column("id", DataType::Int32),
+            column("name", DataType::Text),
+        ],
+    })
+    .unwrap();
+
+    let csv = "id,name\n1,Ann\n22,Étienne\n";
+    let out = SharedBuffer::default();
+    let err = copy_csv_to_fixed_width(
+        &schema,
+        &layout,
+        Box::new(csv.as_bytes()),
+        Box::new(out.clone()),
+    );
+    assert!(err.is_err(), "long names should not fit");
+
+    let csv = "id,name\n1,Ann\n22,Émile\n333,\n";
+    let fixed = SharedBuffer::default();
+    copy_csv_to_fixed_width(
+        &schema,
+        &layout,
+        Box::new(csv.as_bytes()),
+        Box::new(fixed.clone()),
+    )
+    .unwrap();
+    let fixed = fixed.contents_str();
+    assert_eq!(fixed, "   1 Ann   \n  22 Émile \n 333       \n");
+
+    let out = SharedBuffer::default();
+    copy_fixed_width_to_csv(
+        &schema,
+        &layout,
+        Box::new(io::Cursor::new(fixed.into_bytes())),
+        Box::new(out.clone()),
+    )
+    .unwrap();
+    let out = out.contents_str();
+    assert_eq!(out, csv);
+}
@@ -0,0 +1,396 @@
+//! Driver for working with fixed-width text files.
+
+use serde::Deserialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::{
+    fs,
+    io::{self, BufReader},
+};
+use walkdir::WalkDir;
+
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::csv_stream::csv_stream_name;
+use crate::tokio_glue::{copy_reader_to_stream, copy_stream_to_writer};
+use crate::transform::spawn_sync_transform;
+
+mod layout;
+
+use self::layout::{copy_csv_to_fixed_width, copy_fixed_width_to_csv, Layout};
+
+/// A fixed-width text file, or a directory containing such files.
+///
+/// The position of each column is described by a layout file, which must be
+/// passed using `--from-arg=layout=...` or `--to-arg=layout=...`.
+#[derive(Clone, Debug)]
+pub(crate) struct FixedWidthLocator {
+    path: PathOrStdio,
+}
+
+impl FixedWidthLocator {
+    /// Construct a `FixedWidthLocator` from a path.
+    fn from_path<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: PathOrStdio::Path(path.into()),
+        }
+    }
+}
+
+impl fmt::Display for FixedWidthLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for FixedWidthLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(FixedWidthLocator { path })
+    }
+}
+
+impl Locator for FixedWidthLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.path.clone(), shared_args, source_args).boxed()
+    }
+
+    fn display_output_locators(&self) -> DisplayOutputLocators {
+        match &self.path {
+            // If we write our data to standard output, we don't also want to
+            // print out "fixed-width:-" to the same standard output.
+            PathOrStdio::Stdio => DisplayOutputLocators::Never,
+            _ => DisplayOutputLocators::IfRequested,
+        }
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.path.clone(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for FixedWidthLocator {
+    fn scheme() -> &'static str {
+        "fixed-width:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::LocalData | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: SourceArgumentsFeatures::DriverArgs.into(),
+            dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            dest_if_exists: IfExistsFeatures::no_append(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Parsed version of `--from-arg` and `--to-arg` values.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixedWidthArguments {
+    /// The path to our layout file.
+    layout: PathBuf,
+}
+
+impl FixedWidthArguments {
+    /// Load the layout file specified by our arguments.
+    async fn load_layout(&self) -> Result<Arc<Layout>> {
+        let json = fs::read_to_string(&self.layout).await.with_context(|_| {
+            format!("cannot read layout file {}", self.layout.display())
+        })?;
+        let layout = Layout::from_json(&json).with_context(|_| {
+            format!("cannot parse layout file {}", self.layout.display())
+        })?;
+        Ok(Arc::new(layout))
+    }
+}
+
+#[test]
+fn layout_argument_is_required() {
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<FixedWidthArguments>()
+    };
+    assert!(parse(&[]).is_err());
+    assert_eq!(
+        parse(&["layout=layout.json"]).unwrap().layout,
+        Path::new("layout.json"),
+    );
+}
+
+/// Convert a stream of fixed-width data into a `CsvStream`.
+fn fixed_width_to_csv_stream(
+    ctx: &Context,
+    schema: &Schema,
+    layout: &Arc<Layout>,
+    name: String,
+    data: BoxStream<BytesMut>,
+) -> Result<CsvStream> {
+    let schema = schema.to_owned();
+    let layout = layout.clone();
+    let data = spawn_sync_transform(
+        ctx.clone(),
+        "copy_fixed_width_to_csv".to_owned(),
+        data,
+        move |_ctx, rdr, wtr| copy_fixed_width_to_csv(&schema, &layout, rdr, wtr),
+    )?;
+    Ok(CsvStream { name, data })
+}
+
+/// Convert a `CsvStream` into a stream of fixed-width data.
+fn csv_stream_to_fixed_width(
+    ctx: &Context,
+    schema: &Schema,
+    layout: &Arc<Layout>,
+    stream: CsvStream,
+) -> Result<BoxStream<BytesMut>> {
+    let schema = schema.to_owned();
+    let layout = layout.clone();
+    spawn_sync_transform(
+        ctx.clone(),
+        "copy_csv_to_fixed_width".to_owned(),
+        stream.data,
+        move |_ctx, rdr, wtr| copy_csv_to_fixed_width(&schema, &layout, rdr, wtr),
+    )
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+async fn local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(FixedWidthLocator::features())?;
+    let source_args = source_args.verify(FixedWidthLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let layout = source_args
+        .driver_args()
+        .deserialize::<FixedWidthArguments>()
+        .context("could not parse --from-arg")?
+        .load_layout()
+        .await?;
+    match path {
+        PathOrStdio::Stdio => {
+            let data = BufReader::with_capacity(BUFFER_SIZE, io::stdin());
+            let stream = copy_reader_to_stream(ctx.clone(), data)?
+                .map_err(move |e| format_err!("cannot read stdin: {}", e))
+                .boxed();
+            let csv_stream = fixed_width_to_csv_stream(
+                &ctx,
+                &schema,
+                &layout,
+                "data".to_owned(),
+                stream,
+            )?;
+            Ok(Some(box_stream_once(Ok(csv_stream))))
+        }
+        PathOrStdio::Path(base_path) => {
+            let paths = fixed_width_paths(&ctx, &base_path)?;
+            let csv_streams = stream::iter(paths).map(Ok).and_then(move |file_path| {
+                let ctx = ctx.clone();
+                let base_path = base_path.clone();
+                let schema = schema.clone();
+                let layout = layout.clone();
+                async move {
+                    // Get the name of our stream.
+                    let name = csv_stream_name(
+                        &base_path.to_string_lossy(),
+                        &file_path.to_string_lossy(),
+                    )?
+                    .to_owned();
+                    let ctx = ctx.child(o!(
+                        "stream" => name.clone(),
+                        "path" => format!("{}", file_path.display())
+                    ));
+
+                    // Open our file and convert it to CSV.
+                    let data = fs::File::open(file_path.clone()).await.with_context(
+                        |_| format!("cannot open {}", file_path.display()),
+                    )?;
+                    let data = BufReader::with_capacity(BUFFER_SIZE, data);
+                    let stream = copy_reader_to_stream(ctx.clone(), data)?
+                        .map_err(move |e| {
+                            format_err!("cannot read {}: {}", file_path.display(), e)
+                        })
+                        .boxed();
+                    fixed_width_to_csv_stream(&ctx, &schema, &layout, name, stream)
+                }
+                .boxed()
+            });
+            Ok(Some(csv_streams.boxed()))
+        }
+    }
+}
+
+/// Recursively look at `base_path`, picking out all the files. If `base_path`
+/// is a file, return it.
+///
+/// Fixed-width files don't have a standard extension, so we don't look at the
+/// file names.
+fn fixed_width_paths(ctx: &Context, base_path: &Path) -> Result<Vec<PathBuf>> {
+    if base_path.is_file() {
+        return Ok(vec![base_path.to_owned()]);
+    }
+
+    let mut paths = vec![];
+    debug!(ctx.log(), "walking {}", base_path.display());
+    let walker = WalkDir::new(base_path)
+        .follow_links(true)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+    for dirent in walker.into_iter() {
+        let dirent = dirent.with_context(|_| {
+            format!("error listing files in {}", base_path.display())
+        })?;
+        let p = dirent.path();
+        trace!(ctx.log(), "found dirent {}", p.display());
+        if dirent.file_type().is_dir() {
+            continue;
+        } else if !dirent.file_type().is_file() {
+            return Err(format_err!("not a file: {}", p.display()));
+        }
+        paths.push(p.to_owned());
+    }
+    Ok(paths)
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+async fn write_local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(FixedWidthLocator::features())?;
+    let dest_args = dest_args.verify(FixedWidthLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+    let layout = dest_args
+        .driver_args()
+        .deserialize::<FixedWidthArguments>()
+        .context("could not parse --to-arg")?
+        .load_layout()
+        .await?;
+    match path {
+        PathOrStdio::Stdio => {
+            if_exists.warn_if_not_default_for_stdout(&ctx);
+            let stream = concatenate_csv_streams(ctx.clone(), data)?;
+            let fixed = csv_stream_to_fixed_width(&ctx, &schema, &layout, stream)?;
+            let fut = async move {
+                copy_stream_to_writer(ctx.clone(), fixed, io::stdout())
+                    .await
+                    .context("error writing to stdout")?;
+                Ok(FixedWidthLocator {
+                    path: PathOrStdio::Stdio,
+                }
+                .boxed())
+            };
+            Ok(box_stream_once(Ok(fut.boxed())))
+        }
+        PathOrStdio::Path(path) => {
+            if path.to_string_lossy().ends_with('/') {
+                // Write streams to our directory as multiple files.
+                let result_stream = data.map_ok(move |stream| {
+                    let path = path.clone();
+                    let ctx = ctx.clone();
+                    let schema = schema.clone();
+                    let layout = layout.clone();
+                    let if_exists = if_exists.clone();
+
+                    async move {
+                        let fixed_path = path.join(format!("{}.txt", stream.name));
+                        let ctx = ctx.child(o!(
+                            "stream" => stream.name.clone(),
+                            "path" => format!("{}", fixed_path.display()),
+                        ));
+                        let fixed =
+                            csv_stream_to_fixed_width(&ctx, &schema, &layout, stream)?;
+                        write_stream_to_file(
+                            ctx,
+                            fixed,
+                            fixed_path.clone(),
+                            if_exists,
+                        )
+                        .await?;
+                        Ok(FixedWidthLocator::from_path(fixed_path).boxed())
+                    }
+                    .boxed()
+                });
+                Ok(result_stream.boxed())
+            } else {
+                // Write all our streams as a single file.
+                let stream = concatenate_csv_streams(ctx.clone(), data)?;
+                let fut = async move {
+                    let ctx = ctx.child(o!(
+                        "stream" => stream.name.clone(),
+                        "path" => format!("{}", path.display()),
+                    ));
+                    let fixed =
+                        csv_stream_to_fixed_width(&ctx, &schema, &layout, stream)?;
+                    write_stream_to_file(ctx, fixed, path.clone(), if_exists).await?;
+                    Ok(FixedWidthLocator::from_path(path).boxed())
+                };
+                Ok(box_stream_once(Ok(fut.boxed())))
+            }
+        }
+    }
+}
+
+/// Write `data` to `dest`, honoring `if_exists`.
+async fn write_stream_to_file(
+    ctx: Context,
+    data: BoxStream<BytesMut>,
+    dest: PathBuf,
+    if_exists: IfExists,
+) -> Result<()> {
+    // Make sure our destination directory exists.
+    let dir = dest
+        .parent()
+        .ok_or_else(|| format_err!("cannot find parent dir for {}", dest.display()))?;
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|_| format!("unable to create directory {}", dir.display()))?;
+
+    debug!(ctx.log(), "writing stream to file {}", dest.display());
+    let wtr = if_exists
+        .to_async_open_options_no_append()?
+        .open(dest.clone())
+        .await
+        .with_context(|_| format!("cannot open {}", dest.display()))?;
+    copy_stream_to_writer(ctx.clone(), data, wtr)
+        .await
+        .with_context(|_| format!("error writing {}", dest.display()))?;
+    Ok(())
+}
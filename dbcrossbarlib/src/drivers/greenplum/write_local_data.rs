@@ -0,0 +1,86 @@
+//! Implementation of `write_local_data` for Greenplum.
+
+use super::{GreenplumDriverArguments, GreenplumLocator};
+use crate::common::*;
+use crate::drivers::{
+    postgres::{copy_csv_to_pg_binary, copy_from_stream, prepare_table},
+    postgres_shared::{connect, CheckCatalog, PgSchema, PgSchemaExt},
+};
+use crate::transform::spawn_sync_transform;
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+///
+/// This works like the PostgreSQL version, except that we add a `DISTRIBUTED
+/// BY` clause when creating tables, and we don't support upserts.
+pub(crate) async fn write_local_data_helper(
+    ctx: Context,
+    dest: GreenplumLocator,
+    mut data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(GreenplumLocator::features())?;
+    let dest_args = dest_args.verify(GreenplumLocator::features())?;
+
+    // Look up our arguments.
+    let schema = shared_args.schema();
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<GreenplumDriverArguments>()
+        .context("could not parse --to-arg")?;
+    let distribution = to_args.distribution()?;
+    let if_exists = dest_args.if_exists().to_owned();
+
+    let table_name = dest.table_name().to_owned();
+    let ctx = ctx.child(o!("table" => table_name.unquoted()));
+    debug!(
+        ctx.log(),
+        "writing data streams to {} table {}",
+        dest.url(),
+        table_name.quoted(),
+    );
+
+    // Connect to Greenplum, and look up any existing table.
+    let mut client = connect(&ctx, dest.url()).await?;
+    let mut dest_schema = PgSchema::from_pg_catalog_or_default(
+        &ctx,
+        CheckCatalog::from(&if_exists),
+        &client,
+        &table_name,
+        schema,
+    )
+    .await?;
+    dest_schema.table_mut()?.distributed_by = distribution;
+
+    // Prepare our destination table.
+    prepare_table(&ctx, &mut client, dest_schema.clone(), &if_exists).await?;
+
+    // Insert data streams one at a time.
+    let fut = async move {
+        while let Some(csv_stream) = data.next().await.transpose()? {
+            let ctx = ctx.child(o!("stream" => csv_stream.name.clone()));
+
+            // Convert our CSV stream into a PostgreSQL `BINARY` stream.
+            let transform_schema = dest_schema.clone();
+            let binary_stream = spawn_sync_transform(
+                ctx.clone(),
+                "copy_csv_to_pg_binary".to_owned(),
+                csv_stream.data,
+                move |_ctx, rdr, wtr| {
+                    copy_csv_to_pg_binary(&transform_schema, rdr, wtr)
+                },
+            )?;
+            copy_from_stream(
+                &ctx,
+                &mut client,
+                dest_schema.table()?,
+                "BINARY",
+                binary_stream,
+            )
+            .await?;
+        }
+        client.release();
+        Ok(dest.boxed())
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
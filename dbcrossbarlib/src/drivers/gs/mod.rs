@@ -2,6 +2,7 @@
 
 use std::{fmt, str::FromStr};
 
+use crate::clouds::gcloud::storage;
 use crate::common::*;
 use crate::drivers::bigquery::BigQueryLocator;
 
@@ -13,7 +14,7 @@ mod write_remote_data;
 use local_data::local_data_helper;
 pub(crate) use prepare_as_destination::prepare_as_destination_helper;
 use write_local_data::write_local_data_helper;
-use write_remote_data::write_remote_data_helper;
+use write_remote_data::{write_dry_run_helper, write_remote_data_helper};
 
 #[derive(Clone, Debug)]
 pub(crate) struct GsLocator {
@@ -103,6 +104,16 @@ impl Locator for GsLocator {
         )
         .boxed()
     }
+
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        source: &dyn Locator,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_dry_run_helper(source, self, shared_args, dest_args).map(Some)
+    }
 }
 
 impl LocatorStatic for GsLocator {
@@ -114,6 +125,7 @@ impl LocatorStatic for GsLocator {
         Features {
             locator: LocatorFeatures::LocalData | LocatorFeatures::WriteLocalData,
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: IfExistsFeatures::Overwrite.into(),
@@ -138,3 +150,13 @@ pub(crate) fn find_gs_temp_dir(
     temp.push('/');
     GsLocator::from_str(&temp)
 }
+
+/// Delete `temp`, which was returned by `find_gs_temp_dir`, if this run is
+/// cancelled.
+pub(crate) fn delete_gs_temp_dir_on_cancel(ctx: &Context, temp: &GsLocator) {
+    let url = temp.as_url().to_owned();
+    ctx.on_cancel(format!("delete {}", url), move |ctx| async move {
+        storage::rmdir(&ctx, &url).await
+    })
+    .keep_until_exit();
+}
@@ -5,7 +5,9 @@ use crate::clouds::gcloud::bigquery;
 use crate::common::*;
 use crate::drivers::{
     bigquery::BigQueryLocator,
-    bigquery_shared::{BqTable, GCloudDriverArguments, Usage},
+    bigquery_shared::{
+        BqTable, BqTableExt, GCloudDriverArguments, TableNameExt, Usage,
+    },
 };
 
 /// Copy `source` to `dest` using `schema`.
@@ -40,13 +42,13 @@ pub(crate) async fn write_remote_data_helper(
     let temporary_storage = shared_args.temporary_storage();
     let if_exists = dest_args.if_exists().to_owned();
 
-    // Get our billing labels.
-    let job_labels = source_args
+    // Get our billing labels and the number of files to extract.
+    let driver_args = source_args
         .driver_args()
         .deserialize::<GCloudDriverArguments>()
-        .context("error parsing --from-args")?
-        .job_labels
-        .to_owned();
+        .context("error parsing --from-args")?;
+    let job_labels = driver_args.job_labels.to_owned();
+    let extract_files = driver_args.extract_files.unwrap_or(1);
 
     // Construct a `BqTable` describing our source table.
     let source_table = BqTable::for_table_name_and_columns(
@@ -59,21 +61,22 @@ pub(crate) async fn write_remote_data_helper(
     // Look up our _actual_ table schema, which we'll need to handle the finer
     // details of exporting RECORDs and other things which aren't visible in the
     // portable schema. We do something similar in PostgreSQL imports.
-    let mut real_source_table =
-        BqTable::read_from_table(&ctx, &source_table_name).await?;
+    let mut real_source_table = bigquery::schema(&ctx, &source_table_name).await?;
     real_source_table = real_source_table.aligned_with(&source_table)?;
 
     // We need to build a temporary export table.
     let temp_table_name = source_table
         .name()
-        .temporary_table_name(&temporary_storage)?;
+        .temporary_table_name(temporary_storage)?;
     let mut export_sql_data = vec![];
     real_source_table.write_export_sql(&source_args, &mut export_sql_data)?;
     let export_sql =
         String::from_utf8(export_sql_data).expect("should always be UTF-8");
     debug!(ctx.log(), "export SQL: {}", export_sql);
 
-    // Run our query.
+    // Run our query, dropping our temporary table if we're cancelled.
+    let _drop_temp_guard =
+        bigquery::drop_table_on_cancel(&ctx, &temp_table_name, &job_labels);
     bigquery::query_to_table(
         &ctx,
         source.project(),
@@ -89,9 +92,41 @@ pub(crate) async fn write_remote_data_helper(
         .await?;
 
     // Build and run a `bq extract` command.
-    bigquery::extract(&ctx, &temp_table_name, dest.as_url(), &job_labels).await?;
+    bigquery::extract(
+        &ctx,
+        &temp_table_name,
+        dest.as_url(),
+        extract_files,
+        &job_labels,
+    )
+    .await?;
 
     // Delete temp table.
     bigquery::drop_table(&ctx, &temp_table_name, &job_labels).await?;
     Ok(vec![dest.boxed()])
 }
+
+/// Describe what we would do to copy `source` to `dest`, without connecting to
+/// anything. This is used to implement `--dry-run`.
+pub(crate) fn write_dry_run_helper(
+    source: &dyn Locator,
+    dest: &GsLocator,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let _shared_args = shared_args.verify(GsLocator::features())?;
+    let _dest_args = dest_args.verify(GsLocator::features())?;
+    let url = dest.as_url();
+
+    let mut out = vec![];
+    writeln!(out, "-- Delete all files under {}", url)?;
+    if let Some(source) = source.as_any().downcast_ref::<BigQueryLocator>() {
+        writeln!(out, "-- Export {} to a temporary BigQuery table", source)?;
+        writeln!(out, "-- Extract the temporary table to {}", url)?;
+        writeln!(out, "-- Drop the temporary table")?;
+    } else {
+        writeln!(out, "-- For each data stream:")?;
+        writeln!(out, "-- Upload CSV data to {}{{stream}}.csv", url)?;
+    }
+    Ok(String::from_utf8(out).expect("generated output should always be UTF-8"))
+}
@@ -0,0 +1,83 @@
+//! Reading data from Google Sheets.
+
+use serde_json::Value;
+
+use super::{cell_to_string, infer_columns, GSheetLocator};
+use crate::clouds::gcloud::sheets;
+use crate::common::*;
+
+/// Implementation of `schema`, but as a real `async` function.
+pub(crate) async fn schema_helper(
+    ctx: Context,
+    source: GSheetLocator,
+) -> Result<Option<Schema>> {
+    let sheet = source.sheet_title(&ctx).await?;
+    let rows = sheets::get_values(&ctx, &source.spreadsheet_id, &sheet).await?;
+    let columns =
+        infer_columns(&rows).with_context(|_| format!("error reading {}", source))?;
+    Ok(Some(Schema::from_table(Table {
+        name: sheet,
+        columns,
+    })?))
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+pub(crate) async fn local_data_helper(
+    ctx: Context,
+    source: GSheetLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(GSheetLocator::features())?;
+    let _source_args = source_args.verify(GSheetLocator::features())?;
+    let column_count = shared_args.schema().table.columns.len();
+
+    // Sheets are small enough that we can just read them into memory.
+    let sheet = source.sheet_title(&ctx).await?;
+    let rows = sheets::get_values(&ctx, &source.spreadsheet_id, &sheet).await?;
+    let csv_data = rows_to_csv(&rows, column_count)
+        .with_context(|_| format!("error converting {} to CSV", source))?;
+    let csv_stream = CsvStream {
+        name: sheet,
+        data: box_stream_once(Ok(BytesMut::from(&csv_data[..]))),
+    };
+    Ok(Some(box_stream_once(Ok(csv_stream))))
+}
+
+/// Convert `rows` into CSV data with `column_count` columns, including the
+/// header row.
+fn rows_to_csv(rows: &[Vec<Value>], column_count: usize) -> Result<Vec<u8>> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    for (idx, row) in rows.iter().enumerate() {
+        if row.len() > column_count {
+            return Err(format_err!(
+                "row {} has {} cells, but the sheet only has {} columns",
+                idx + 1,
+                row.len(),
+                column_count,
+            ));
+        }
+        // Sheets omits trailing empty cells, so pad our rows.
+        let record = (0..column_count)
+            .map(|i| row.get(i).map(cell_to_string).unwrap_or_default());
+        wtr.write_record(record)?;
+    }
+    wtr.into_inner()
+        .map_err(|err| format_err!("error writing CSV: {}", err.error()))
+}
+
+#[test]
+fn rows_to_csv_pads_short_rows() {
+    use serde_json::json;
+
+    let rows = vec![
+        vec![json!("id"), json!("name")],
+        vec![json!(1), json!("Jane, Jr.")],
+        vec![json!(2.5)],
+    ];
+    let csv = rows_to_csv(&rows, 2).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "id,name\n1,\"Jane, Jr.\"\n2.5,\n",
+    );
+}
@@ -0,0 +1,240 @@
+//! Driver for working with Google Sheets.
+
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+mod local_data;
+mod write_local_data;
+
+use self::local_data::{local_data_helper, schema_helper};
+use self::write_local_data::write_local_data_helper;
+
+/// A Google Sheets spreadsheet, and optionally the name of a sheet inside it.
+#[derive(Clone, Debug)]
+pub(crate) struct GSheetLocator {
+    /// The spreadsheet ID, as it appears in the spreadsheet's URL.
+    spreadsheet_id: String,
+    /// The sheet to use. Defaults to the first sheet.
+    sheet: Option<String>,
+}
+
+impl GSheetLocator {
+    /// Look up the name of the sheet we should use.
+    async fn sheet_title(&self, ctx: &Context) -> Result<String> {
+        match &self.sheet {
+            Some(sheet) => Ok(sheet.to_owned()),
+            None => {
+                crate::clouds::gcloud::sheets::first_sheet_title(
+                    ctx,
+                    &self.spreadsheet_id,
+                )
+                .await
+            }
+        }
+    }
+}
+
+impl fmt::Display for GSheetLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Self::scheme(), self.spreadsheet_id)?;
+        if let Some(sheet) = &self.sheet {
+            write!(f, "#{}", sheet)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GSheetLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if !s.starts_with(Self::scheme()) {
+            return Err(format_err!("expected {} to begin with gsheet:", s));
+        }
+        let rest = &s[Self::scheme().len()..];
+        let (spreadsheet_id, sheet) = match rest.find('#') {
+            Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_owned())),
+            None => (rest, None),
+        };
+        if spreadsheet_id.is_empty()
+            || !spreadsheet_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format_err!(
+                "expected a spreadsheet ID in {:?}, like gsheet:ID or gsheet:ID#Sheet1",
+                s,
+            ));
+        }
+        if sheet.as_deref() == Some("") {
+            return Err(format_err!("missing sheet name after '#' in {:?}", s));
+        }
+        Ok(GSheetLocator {
+            spreadsheet_id: spreadsheet_id.to_owned(),
+            sheet,
+        })
+    }
+}
+
+#[test]
+fn from_str_parses_sheet_names() {
+    let loc = "gsheet:1aBc-_9#My Sheet".parse::<GSheetLocator>().unwrap();
+    assert_eq!(loc.spreadsheet_id, "1aBc-_9");
+    assert_eq!(loc.sheet.as_deref(), Some("My Sheet"));
+    let loc = "gsheet:1aBc".parse::<GSheetLocator>().unwrap();
+    assert_eq!(loc.sheet, None);
+    assert!("gsheet:".parse::<GSheetLocator>().is_err());
+    assert!("gsheet:abc#".parse::<GSheetLocator>().is_err());
+    assert!("gsheet:a/b".parse::<GSheetLocator>().is_err());
+}
+
+impl Locator for GSheetLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.to_owned(), shared_args, source_args).boxed()
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.to_owned(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for GSheetLocator {
+    fn scheme() -> &'static str {
+        "gsheet:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema
+                | LocatorFeatures::LocalData
+                | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::Error
+                | IfExistsFeatures::Append
+                | IfExistsFeatures::Overwrite,
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Convert a cell to a string, the way it should appear in a CSV file.
+fn cell_to_string(cell: &Value) -> String {
+    match cell {
+        Value::Null => "".to_owned(),
+        Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    }
+}
+
+/// Build a table schema from the cells in a sheet. The first row contains the
+/// column names, and we guess column types based on the remaining rows.
+fn infer_columns(rows: &[Vec<Value>]) -> Result<Vec<Column>> {
+    let headers = rows
+        .first()
+        .ok_or_else(|| format_err!("sheet is empty, and has no header row"))?;
+    headers
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| {
+            let name = cell_to_string(header);
+            if name.is_empty() {
+                return Err(format_err!("column {} has no header", idx + 1));
+            }
+            let cells = rows[1..]
+                .iter()
+                .filter_map(|row| row.get(idx))
+                .filter(|cell| !cell.is_null() && cell.as_str() != Some(""));
+            Ok(Column {
+                name,
+                is_nullable: true,
+                data_type: infer_data_type(cells),
+                comment: None,
+            })
+        })
+        .collect()
+}
+
+/// Guess the type of column containing `cells`. Empty cells have been
+/// removed.
+fn infer_data_type<'a>(cells: impl Iterator<Item = &'a Value>) -> DataType {
+    let mut inferred: Option<DataType> = None;
+    for cell in cells {
+        let cell_type = match cell {
+            Value::Bool(_) => DataType::Bool,
+            Value::Number(n) if n.is_i64() => DataType::Int64,
+            Value::Number(_) => DataType::Float64,
+            _ => return DataType::Text,
+        };
+        inferred = Some(match (inferred, cell_type) {
+            (None, ty) => ty,
+            (Some(DataType::Int64), DataType::Float64)
+            | (Some(DataType::Float64), DataType::Int64) => DataType::Float64,
+            (Some(prev), ty) if prev == ty => ty,
+            _ => return DataType::Text,
+        });
+    }
+    inferred.unwrap_or(DataType::Text)
+}
+
+#[test]
+fn infers_column_types() {
+    use serde_json::json;
+
+    let rows = vec![
+        vec![
+            json!("id"),
+            json!("score"),
+            json!("ok"),
+            json!("name"),
+            json!("e"),
+        ],
+        vec![json!(1), json!(1), json!(true), json!("a"), json!("")],
+        vec![json!(2), json!(1.5), json!(false), json!(3)],
+    ];
+    let columns = infer_columns(&rows).unwrap();
+    let types = columns
+        .iter()
+        .map(|c| (&c.name[..], c.data_type.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        &[
+            ("id", DataType::Int64),
+            ("score", DataType::Float64),
+            ("ok", DataType::Bool),
+            ("name", DataType::Text),
+            ("e", DataType::Text),
+        ],
+    );
+}
@@ -0,0 +1,186 @@
+//! Writing data to Google Sheets.
+
+use serde_json::Value;
+
+use super::GSheetLocator;
+use crate::clouds::gcloud::sheets;
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::schema::DataType;
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+pub(crate) async fn write_local_data_helper(
+    ctx: Context,
+    dest: GSheetLocator,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(GSheetLocator::features())?;
+    let dest_args = dest_args.verify(GSheetLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+
+    // We need to upload all our data at once, so combine our streams.
+    let stream = concatenate_csv_streams(ctx.clone(), data)?;
+    let fut = async move {
+        let csv_data = stream
+            .data
+            .try_fold(vec![], |mut buf, bytes| async move {
+                buf.extend_from_slice(&bytes);
+                Ok(buf)
+            })
+            .await?;
+        let mut rows = csv_to_rows(&schema, &csv_data)
+            .with_context(|_| format!("error preparing data for {}", dest))?;
+
+        // Decide what to do with any existing data.
+        let sheet = dest.sheet_title(&ctx).await?;
+        let id = &dest.spreadsheet_id;
+        match if_exists {
+            IfExists::Error => {
+                if !sheets::get_values(&ctx, id, &sheet).await?.is_empty() {
+                    return Err(format_err!("{} already contains data", dest));
+                }
+            }
+            IfExists::Overwrite => {
+                sheets::clear_values(&ctx, id, &sheet).await?;
+            }
+            IfExists::Append => {
+                // Only write our headers if the sheet is empty.
+                if !sheets::get_values(&ctx, id, &sheet).await?.is_empty() {
+                    rows.remove(0);
+                }
+            }
+            IfExists::Upsert(_) => {
+                return Err(format_err!("cannot upsert to {}", dest));
+            }
+        }
+        sheets::append_values(&ctx, id, &sheet, rows).await?;
+        Ok(dest.boxed())
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
+
+/// Parse `csv_data`, and convert it into rows of cells. Numeric and boolean
+/// columns are converted to JSON numbers and booleans, so that Sheets will
+/// treat them correctly. The first row contains the column names.
+fn csv_to_rows(schema: &Schema, csv_data: &[u8]) -> Result<Vec<Vec<Value>>> {
+    let columns = &schema.table.columns;
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(csv_data);
+    let mut rows = vec![];
+    for (idx, record) in rdr.records().enumerate() {
+        let record = record?;
+        if record.len() != columns.len() {
+            return Err(format_err!(
+                "expected {} columns, found {}",
+                columns.len(),
+                record.len(),
+            ));
+        }
+        let row = record
+            .iter()
+            .zip(columns)
+            .map(|(cell, col)| {
+                // Leave the header row and empty cells alone.
+                if idx == 0 || cell.is_empty() {
+                    return Ok(Value::String(cell.to_owned()));
+                }
+                // `idx` is also our row number in the data, because the header
+                // is row 0.
+                let value =
+                    cell_to_value(&col.data_type, cell).with_context(|_| {
+                        ErrorKind::InvalidData {
+                            row: idx,
+                            column: col.name.clone(),
+                            value: cell.to_owned(),
+                        }
+                    })?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        rows.push(row);
+    }
+    if rows.is_empty() {
+        return Err(format_err!("CSV data is missing a header row"));
+    }
+    Ok(rows)
+}
+
+/// Convert a non-empty, non-header CSV cell to a JSON value for Sheets.
+fn cell_to_value(data_type: &DataType, cell: &str) -> Result<Value> {
+    match data_type {
+        DataType::Bool => match cell {
+            "t" | "true" => Ok(Value::Bool(true)),
+            "f" | "false" => Ok(Value::Bool(false)),
+            _ => Err(format_err!("cannot parse {:?} as bool", cell)),
+        },
+        DataType::Float32
+        | DataType::Float64
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64 => Ok(serde_json::from_str::<Value>(cell)
+            .ok()
+            .filter(Value::is_number)
+            .ok_or_else(|| format_err!("cannot parse {:?} as a number", cell))?),
+        _ => Ok(Value::String(cell.to_owned())),
+    }
+}
+
+#[test]
+fn csv_to_rows_converts_types() {
+    use crate::schema::{Column, Table};
+    use serde_json::json;
+
+    let column = |name: &str, data_type| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            column("id", DataType::Int64),
+            column("ok", DataType::Bool),
+            column("name", DataType::Text),
+        ],
+    })
+    .unwrap();
+    let rows = csv_to_rows(&schema, b"id,ok,name\n1,t,=A1\n,,\n").unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            vec![json!("id"), json!("ok"), json!("name")],
+            vec![json!(1), json!(true), json!("=A1")],
+            vec![json!(""), json!(""), json!("")],
+        ],
+    );
+}
+
+#[test]
+fn csv_to_rows_reports_row_without_header() {
+    use crate::schema::{Column, Table};
+
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "ok".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Bool,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let err = csv_to_rows(&schema, b"ok\nt\nmaybe\n").unwrap_err();
+    assert_eq!(
+        ErrorKind::of(&err),
+        Some(&ErrorKind::InvalidData {
+            row: 2,
+            column: "ok".to_owned(),
+            value: "maybe".to_owned(),
+        }),
+    );
+}
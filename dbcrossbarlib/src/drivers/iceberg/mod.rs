@@ -0,0 +1,322 @@
+//! Driver for working with Apache Iceberg table schemas.
+
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{env, fmt, path::Path, str::FromStr};
+use uuid::Uuid;
+
+use crate::common::*;
+
+mod rest;
+mod schema;
+
+use self::rest::Client;
+use self::schema::{current_schema, iceberg_schema_for, schema_from_iceberg};
+
+/// Where we store an Iceberg table's metadata.
+#[derive(Clone, Debug)]
+enum IcebergTable {
+    /// A table metadata file, like `metadata/v1.metadata.json`.
+    File(PathOrStdio),
+    /// A table in a REST catalog, like
+    /// `https://catalog.example.com/v1/namespaces/analytics/tables/orders`.
+    Rest(Url),
+}
+
+/// An Iceberg table, like `iceberg:metadata/v1.metadata.json` or
+/// `iceberg:https://catalog.example.com/v1/namespaces/db/tables/orders`.
+#[derive(Clone, Debug)]
+pub(crate) struct IcebergLocator {
+    table: IcebergTable,
+}
+
+impl fmt::Display for IcebergLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            IcebergTable::File(path) => path.fmt_locator_helper(Self::scheme(), f),
+            IcebergTable::Rest(url) => write!(f, "{}{}", Self::scheme(), url),
+        }
+    }
+}
+
+impl FromStr for IcebergLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rest = s.strip_prefix(Self::scheme()).ok_or_else(|| {
+            format_err!("expected {:?} to begin with {}", s, Self::scheme())
+        })?;
+        let table = if rest.starts_with("http://") || rest.starts_with("https://") {
+            let url = rest
+                .parse::<Url>()
+                .context("could not parse Iceberg catalog URL")?;
+            // Make sure the URL names a table.
+            tables_url_and_name(&url)?;
+            IcebergTable::Rest(url)
+        } else {
+            IcebergTable::File(PathOrStdio::from_str_locator_helper(
+                Self::scheme(),
+                s,
+            )?)
+        };
+        Ok(IcebergLocator { table })
+    }
+}
+
+/// Split a REST catalog table URL into the URL of the namespace's table
+/// collection, and the name of the table.
+fn tables_url_and_name(url: &Url) -> Result<(Url, String)> {
+    let segments = url
+        .path_segments()
+        .map(|segments| segments.collect::<Vec<_>>())
+        .unwrap_or_default();
+    match segments.iter().rposition(|&s| s == "namespaces") {
+        Some(idx) if segments.len() == idx + 4 && segments[idx + 2] == "tables" => {
+            let name = percent_encoding::percent_decode_str(segments[idx + 3])
+                .decode_utf8()
+                .with_context(|_| format!("table name is not UTF-8 in {}", url))?
+                .into_owned();
+            let mut tables_url = url.clone();
+            tables_url
+                .path_segments_mut()
+                .map_err(|_| format_err!("cannot modify path of {}", url))?
+                .pop();
+            Ok((tables_url, name))
+        }
+        _ => Err(format_err!(
+            "expected {} to end with /namespaces/$NAMESPACE/tables/$TABLE",
+            url,
+        )),
+    }
+}
+
+#[test]
+fn parses_rest_catalog_urls() {
+    let url = "https://catalog.example.com/v1/warehouse/namespaces/db/tables/orders"
+        .parse::<Url>()
+        .unwrap();
+    let (tables_url, name) = tables_url_and_name(&url).unwrap();
+    assert_eq!(
+        tables_url.as_str(),
+        "https://catalog.example.com/v1/warehouse/namespaces/db/tables",
+    );
+    assert_eq!(name, "orders");
+    assert!("iceberg:https://catalog.example.com/v1/namespaces/db"
+        .parse::<IcebergLocator>()
+        .is_err());
+}
+
+impl Locator for IcebergLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+}
+
+impl LocatorStatic for IcebergLocator {
+    fn scheme() -> &'static str {
+        "iceberg:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema | LocatorFeatures::WriteSchema,
+            write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Guess a table name from the last component of a table's `location`.
+fn table_name_from_metadata(metadata: &Value) -> String {
+    metadata
+        .get("location")
+        .and_then(|location| location.as_str())
+        .and_then(|location| location.trim_end_matches('/').rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("unnamed")
+        .to_owned()
+}
+
+/// Implementation of `schema`, but as a real `async` function.
+async fn schema_helper(
+    ctx: Context,
+    source: IcebergLocator,
+) -> Result<Option<Schema>> {
+    let (name, metadata) = match &source.table {
+        IcebergTable::File(path) => {
+            let input = path.open_async().await?;
+            let data = async_read_to_end(input)
+                .await
+                .with_context(|_| format!("error reading {}", path))?;
+            let metadata = serde_json::from_slice::<Value>(&data)
+                .with_context(|_| format!("error parsing {}", path))?;
+            (table_name_from_metadata(&metadata), metadata)
+        }
+        IcebergTable::Rest(url) => {
+            let (_, name) = tables_url_and_name(url)?;
+            let client = Client::new(&ctx).await;
+            let metadata = client
+                .load_table(&ctx, url)
+                .await?
+                .ok_or_else(|| format_err!("{} does not exist", source))?;
+            (name, metadata)
+        }
+    };
+    let schema = current_schema(&metadata)
+        .and_then(|iceberg| schema_from_iceberg(&name, iceberg))
+        .with_context(|_| format!("error reading schema of {}", source))?;
+    Ok(Some(schema))
+}
+
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IcebergDestinationArguments {
+    /// The base location of the table's data and metadata.
+    location: Option<String>,
+}
+
+/// Implementation of `write_schema`, but as a real `async` function.
+async fn write_schema_helper(
+    ctx: Context,
+    dest: IcebergLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args = dest_args.verify_for_write_schema(IcebergLocator::features())?;
+    let iceberg_dest_args = dest_args
+        .driver_args()
+        .deserialize::<IcebergDestinationArguments>()
+        .context("could not parse --to-arg")?;
+    let if_exists = dest_args.if_exists().to_owned();
+    let (iceberg_schema, last_column_id) = iceberg_schema_for(&schema)?;
+
+    match &dest.table {
+        IcebergTable::File(path) => {
+            let location = match (iceberg_dest_args.location, path) {
+                (Some(location), _) => location,
+                (None, PathOrStdio::Path(path)) => default_location(path)?,
+                (None, PathOrStdio::Stdio) => {
+                    return Err(format_err!(
+                        "need --to-arg=location=$URL to write Iceberg metadata to standard output"
+                    ))
+                }
+            };
+            let metadata = table_metadata(&location, iceberg_schema, last_column_id);
+            let mut f = path.create_async(ctx, if_exists).await?;
+            buffer_sync_write_and_copy_to_async(&mut f, |buff| {
+                serde_json::to_writer_pretty(buff, &metadata)
+            })
+            .await
+            .with_context(|_| format!("error writing to {}", path))?;
+            f.flush().await?;
+        }
+        IcebergTable::Rest(url) => {
+            let (tables_url, name) = tables_url_and_name(url)?;
+            let client = Client::new(&ctx).await;
+            if let IfExists::Overwrite = if_exists {
+                if client.load_table(&ctx, url).await?.is_some() {
+                    debug!(ctx.log(), "dropping existing table {}", dest);
+                    client.drop_table(&ctx, url).await?;
+                }
+            }
+            client
+                .create_table(
+                    &ctx,
+                    &tables_url,
+                    &name,
+                    &iceberg_schema,
+                    iceberg_dest_args.location.as_deref(),
+                )
+                .await?;
+            info!(ctx.log(), "created Iceberg table {}", dest);
+        }
+    }
+    Ok(())
+}
+
+/// Choose a table location for a metadata file at `path`. Iceberg normally
+/// stores metadata in `$LOCATION/metadata/`, so we follow that convention.
+fn default_location(path: &Path) -> Result<String> {
+    let path = env::current_dir()?.join(path);
+    let dir = path
+        .parent()
+        .ok_or_else(|| format_err!("cannot find directory of {}", path.display()))?;
+    let location = if dir.file_name().map(|n| n == "metadata").unwrap_or(false) {
+        dir.parent().unwrap_or(dir)
+    } else {
+        dir
+    };
+    Ok(Url::from_directory_path(location)
+        .map_err(|_| format_err!("cannot build URL for {}", location.display()))?
+        .to_string())
+}
+
+/// Build version 2 table metadata for a new, empty table.
+fn table_metadata(location: &str, schema: Value, last_column_id: u64) -> Value {
+    json!({
+        "format-version": 2,
+        "table-uuid": Uuid::new_v4().to_string(),
+        "location": location,
+        "last-sequence-number": 0,
+        "last-updated-ms": Utc::now().timestamp_millis(),
+        "last-column-id": last_column_id,
+        "current-schema-id": 0,
+        "schemas": [schema],
+        "default-spec-id": 0,
+        "partition-specs": [{ "spec-id": 0, "fields": [] }],
+        "last-partition-id": 999,
+        "default-sort-order-id": 0,
+        "sort-orders": [{ "order-id": 0, "fields": [] }],
+        "properties": {},
+        "current-snapshot-id": -1,
+        "snapshots": [],
+        "snapshot-log": [],
+        "metadata-log": [],
+    })
+}
+
+#[test]
+fn new_table_metadata_has_current_schema() {
+    use crate::schema::{Column, DataType};
+
+    let schema = Schema::from_table(Table {
+        name: "orders".to_owned(),
+        columns: vec![Column {
+            name: "id".to_owned(),
+            is_nullable: false,
+            data_type: DataType::Int64,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let (iceberg, last_id) = iceberg_schema_for(&schema).unwrap();
+    let metadata = table_metadata("s3://bucket/warehouse/orders", iceberg, last_id);
+    assert_eq!(table_name_from_metadata(&metadata), "orders");
+    let parsed =
+        schema_from_iceberg("orders", current_schema(&metadata).unwrap()).unwrap();
+    assert_eq!(parsed.table.columns, schema.table.columns);
+}
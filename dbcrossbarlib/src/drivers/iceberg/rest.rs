@@ -0,0 +1,126 @@
+//! A minimal Iceberg REST catalog client.
+//!
+//! Spec: https://github.com/apache/iceberg/blob/master/open-api/rest-catalog-open-api.yaml
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::common::*;
+use crate::credentials::CredentialsManager;
+
+/// A table returned by the catalog.
+#[derive(Debug, Deserialize)]
+struct LoadTableResult {
+    /// The table's metadata.
+    metadata: Value,
+}
+
+/// A client for an Iceberg REST catalog.
+pub(super) struct Client {
+    /// Our HTTP client.
+    client: reqwest::Client,
+    /// A bearer token, if we have one.
+    token: Option<String>,
+}
+
+impl Client {
+    /// Create a new client, using `ICEBERG_TOKEN` for authentication if it's
+    /// available.
+    pub(super) async fn new(ctx: &Context) -> Client {
+        let token = match CredentialsManager::singleton().get("iceberg").await {
+            Ok(creds) => creds.get_required("token").ok().map(|t| t.to_owned()),
+            Err(err) => {
+                debug!(ctx.log(), "not using Iceberg credentials: {}", err);
+                None
+            }
+        };
+        Client {
+            client: reqwest::Client::new(),
+            token,
+        }
+    }
+
+    /// Fetch the metadata for the table at `table_url`, or `None` if it does
+    /// not exist.
+    pub(super) async fn load_table(
+        &self,
+        ctx: &Context,
+        table_url: &Url,
+    ) -> Result<Option<Value>> {
+        trace!(ctx.log(), "GET {}", table_url);
+        let resp = self
+            .send(self.client.get(table_url.clone()), table_url)
+            .await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let table = check_response(resp, table_url)
+            .await?
+            .json::<LoadTableResult>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", table_url))?;
+        Ok(Some(table.metadata))
+    }
+
+    /// Create a table named `name` in the collection at `tables_url`. If
+    /// `location` is `None`, the catalog will choose one.
+    pub(super) async fn create_table(
+        &self,
+        ctx: &Context,
+        tables_url: &Url,
+        name: &str,
+        schema: &Value,
+        location: Option<&str>,
+    ) -> Result<()> {
+        trace!(ctx.log(), "POST {}", tables_url);
+        let mut body =
+            json!({ "name": name, "schema": schema, "stage-create": false });
+        if let Some(location) = location {
+            body["location"] = Value::String(location.to_owned());
+        }
+        let req = self.client.post(tables_url.clone()).json(&body);
+        let resp = self.send(req, tables_url).await?;
+        if resp.status() == StatusCode::CONFLICT {
+            return Err(format_err!("Iceberg table {} already exists", name));
+        }
+        check_response(resp, tables_url).await?;
+        Ok(())
+    }
+
+    /// Drop the table at `table_url`, without deleting any data files.
+    pub(super) async fn drop_table(
+        &self,
+        ctx: &Context,
+        table_url: &Url,
+    ) -> Result<()> {
+        trace!(ctx.log(), "DELETE {}", table_url);
+        let resp = self
+            .send(self.client.delete(table_url.clone()), table_url)
+            .await?;
+        check_response(resp, table_url).await?;
+        Ok(())
+    }
+
+    /// Add our authentication to `req`, and send it.
+    async fn send(&self, req: RequestBuilder, url: &Url) -> Result<Response> {
+        let mut req = req.header("Accept", "application/json");
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        Ok(req.send().await.context(ErrorKind::Connection {
+            target: url.to_string(),
+        })?)
+    }
+}
+
+/// Return an error if `resp` was not successful.
+async fn check_response(resp: Response, url: &Url) -> Result<Response> {
+    if resp.status().is_success() {
+        Ok(resp)
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(format_err!("error accessing {}: {} {}", url, status, body))
+    }
+}
@@ -0,0 +1,294 @@
+//! Converting between portable schemas and Iceberg schemas.
+//!
+//! Iceberg schemas are JSON documents, so we build and parse them as
+//! `serde_json::Value` instead of defining a full set of types.
+//!
+//! Spec: https://iceberg.apache.org/spec/#schemas-and-data-types
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::common::*;
+use crate::schema::{Column, DataType, StructField};
+
+/// Build an Iceberg schema for our table, and return it along with the highest
+/// field ID that we assigned.
+///
+/// Iceberg tracks columns by ID, not by name, so we number every field
+/// (including nested fields and list elements) starting from 1.
+pub(super) fn iceberg_schema_for(schema: &Schema) -> Result<(Value, u64)> {
+    let mut last_id = 0;
+    let columns = &schema.table.columns;
+
+    // Assign IDs to all top-level columns first, like Iceberg does.
+    let ids = columns
+        .iter()
+        .map(|_| next_id(&mut last_id))
+        .collect::<Vec<_>>();
+    let fields = columns
+        .iter()
+        .zip(ids)
+        .map(|(col, id)| {
+            let mut field = json!({
+                "id": id,
+                "name": col.name,
+                "required": !col.is_nullable,
+                "type": iceberg_type(schema, &col.data_type, &mut last_id)?,
+            });
+            if let Some(comment) = &col.comment {
+                field["doc"] = Value::String(comment.to_owned());
+            }
+            Ok(field)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((
+        json!({ "type": "struct", "schema-id": 0, "fields": fields }),
+        last_id,
+    ))
+}
+
+/// Allocate a new field ID.
+fn next_id(last_id: &mut u64) -> u64 {
+    *last_id += 1;
+    *last_id
+}
+
+/// Build an Iceberg type for `data_type`, allocating field IDs as needed.
+fn iceberg_type(
+    schema: &Schema,
+    data_type: &DataType,
+    last_id: &mut u64,
+) -> Result<Value> {
+    Ok(match data_type {
+        DataType::Array(elem_type) => {
+            let element_id = next_id(last_id);
+            json!({
+                "type": "list",
+                "element-id": element_id,
+                "element": iceberg_type(schema, elem_type, last_id)?,
+                "element-required": false,
+            })
+        }
+        DataType::Bool => json!("boolean"),
+        DataType::Date => json!("date"),
+        // Iceberg decimals need a fixed precision and scale. We use the same
+        // values as BigQuery's `NUMERIC`.
+        DataType::Decimal => json!("decimal(38, 9)"),
+        DataType::Float32 => json!("float"),
+        DataType::Float64 => json!("double"),
+        DataType::GeoJson(_)
+        | DataType::Json
+        | DataType::OneOf(_)
+        | DataType::Text => {
+            json!("string")
+        }
+        DataType::Int16 | DataType::Int32 => json!("int"),
+        DataType::Int64 => json!("long"),
+        DataType::Named(name) => {
+            iceberg_type(schema, schema.data_type_for_name(name), last_id)?
+        }
+        DataType::Struct(fields) => {
+            let ids = fields.iter().map(|_| next_id(last_id)).collect::<Vec<_>>();
+            let fields = fields
+                .iter()
+                .zip(ids)
+                .map(|(field, id)| {
+                    Ok(json!({
+                        "id": id,
+                        "name": field.name,
+                        "required": !field.is_nullable,
+                        "type": iceberg_type(schema, &field.data_type, last_id)?,
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            json!({ "type": "struct", "fields": fields })
+        }
+        DataType::TimestampWithoutTimeZone => json!("timestamp"),
+        DataType::TimestampWithTimeZone => json!("timestamptz"),
+        DataType::Uuid => json!("uuid"),
+    })
+}
+
+/// Find the current schema in Iceberg table metadata.
+pub(super) fn current_schema(metadata: &Value) -> Result<&Value> {
+    // Format version 2 keeps a list of schemas. Format version 1 may only
+    // have a single `schema`.
+    if let (Some(schemas), Some(current_id)) = (
+        metadata.get("schemas").and_then(|s| s.as_array()),
+        metadata.get("current-schema-id").and_then(|id| id.as_i64()),
+    ) {
+        schemas
+            .iter()
+            .find(|s| {
+                s.get("schema-id").and_then(|id| id.as_i64()) == Some(current_id)
+            })
+            .ok_or_else(|| format_err!("cannot find Iceberg schema {}", current_id))
+    } else {
+        metadata
+            .get("schema")
+            .ok_or_else(|| format_err!("Iceberg table metadata has no schema"))
+    }
+}
+
+/// Parse an Iceberg schema into a portable schema named `table_name`.
+pub(super) fn schema_from_iceberg(
+    table_name: &str,
+    iceberg: &Value,
+) -> Result<Schema> {
+    let columns = struct_fields(iceberg)?
+        .iter()
+        .map(|field| {
+            let (name, is_nullable, data_type) = parse_field(field)?;
+            let comment = field.get("doc").and_then(|doc| doc.as_str());
+            Ok(Column {
+                name,
+                is_nullable,
+                data_type,
+                comment: comment.map(|c| c.to_owned()),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Schema::from_table(Table {
+        name: table_name.to_owned(),
+        columns,
+    })
+}
+
+/// Get the fields of an Iceberg `struct`.
+fn struct_fields(iceberg: &Value) -> Result<&[Value]> {
+    if iceberg.get("type").and_then(|ty| ty.as_str()) != Some("struct") {
+        return Err(format_err!("expected an Iceberg struct, found {}", iceberg));
+    }
+    iceberg
+        .get("fields")
+        .and_then(|fields| fields.as_array())
+        .map(|fields| &fields[..])
+        .ok_or_else(|| format_err!("Iceberg struct has no fields: {}", iceberg))
+}
+
+/// Parse an Iceberg struct field, returning its name, nullability and type.
+fn parse_field(field: &Value) -> Result<(String, bool, DataType)> {
+    let name = field
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| format_err!("Iceberg field has no name: {}", field))?;
+    let ty = field
+        .get("type")
+        .ok_or_else(|| format_err!("Iceberg field {:?} has no type", name))?;
+    let is_required = field
+        .get("required")
+        .and_then(|required| required.as_bool())
+        .unwrap_or(false);
+    let data_type = parse_type(ty).map_err(|_| ErrorKind::UnsupportedType {
+        column: name.to_owned(),
+        type_name: ty.to_string(),
+    })?;
+    Ok((name.to_owned(), !is_required, data_type))
+}
+
+/// Parse an Iceberg type.
+fn parse_type(ty: &Value) -> Result<DataType> {
+    lazy_static! {
+        static ref DECIMAL_RE: Regex = Regex::new(r"^decimal\(\s*\d+\s*,\s*\d+\s*\)$")
+            .expect("invalid `DECIMAL_RE` in source");
+    }
+
+    match ty {
+        Value::String(name) => Ok(match &name[..] {
+            "boolean" => DataType::Bool,
+            "date" => DataType::Date,
+            "double" => DataType::Float64,
+            "float" => DataType::Float32,
+            "int" => DataType::Int32,
+            "long" => DataType::Int64,
+            "string" => DataType::Text,
+            "timestamp" | "timestamp_ns" => DataType::TimestampWithoutTimeZone,
+            "timestamptz" | "timestamptz_ns" => DataType::TimestampWithTimeZone,
+            "uuid" => DataType::Uuid,
+            name if DECIMAL_RE.is_match(name) => DataType::Decimal,
+            _ => return Err(format_err!("unsupported Iceberg type {}", ty)),
+        }),
+        Value::Object(_) => match ty.get("type").and_then(|t| t.as_str()) {
+            Some("list") => {
+                let elem = ty
+                    .get("element")
+                    .ok_or_else(|| format_err!("Iceberg list has no element"))?;
+                Ok(DataType::Array(Box::new(parse_type(elem)?)))
+            }
+            // We don't have a portable map type, so treat maps as JSON objects.
+            Some("map") => Ok(DataType::Json),
+            Some("struct") => Ok(DataType::Struct(
+                struct_fields(ty)?
+                    .iter()
+                    .map(|field| {
+                        let (name, is_nullable, data_type) = parse_field(field)?;
+                        Ok(StructField {
+                            name,
+                            is_nullable,
+                            data_type,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            _ => Err(format_err!("unsupported Iceberg type {}", ty)),
+        },
+        _ => Err(format_err!("unsupported Iceberg type {}", ty)),
+    }
+}
+
+#[test]
+fn iceberg_schema_round_trip() {
+    let json = r#"
+{
+  "named_data_types": [{
+    "name": "color",
+    "data_type": { "one_of": ["red", "green", "blue"] }
+  }],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "a", "is_nullable": true,  "data_type": "text", "comment": "A" },
+      { "name": "b", "is_nullable": false, "data_type": "int64" },
+      { "name": "c", "is_nullable": true,  "data_type": "uuid" },
+      { "name": "d", "is_nullable": true,  "data_type": "decimal" },
+      { "name": "e", "is_nullable": true,  "data_type": { "array": "int32" } },
+      { "name": "f", "is_nullable": true,  "data_type": { "struct": [
+        { "name": "x", "data_type": "float64", "is_nullable": false },
+        { "name": "y", "data_type": "float64", "is_nullable": true }
+      ] } },
+      { "name": "g", "is_nullable": true,  "data_type": "timestamp_with_time_zone" },
+      { "name": "h", "is_nullable": true,  "data_type": "timestamp_without_time_zone" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let (iceberg, last_id) = iceberg_schema_for(&schema).unwrap();
+    assert_eq!(iceberg["fields"][0]["id"], 1);
+    assert_eq!(iceberg["fields"][0]["doc"], "A");
+    assert_eq!(iceberg["fields"][1]["required"], true);
+    assert_eq!(iceberg["fields"][4]["type"]["element-id"], 9);
+    assert_eq!(iceberg["fields"][5]["type"]["fields"][1]["id"], 11);
+    assert_eq!(last_id, 11);
+    let parsed = schema_from_iceberg("example", &iceberg).unwrap();
+    assert_eq!(parsed.table, schema.table);
+}
+
+#[test]
+fn finds_current_schema() {
+    let v2 = json!({
+        "format-version": 2,
+        "current-schema-id": 1,
+        "schemas": [
+            { "type": "struct", "schema-id": 0, "fields": [] },
+            { "type": "struct", "schema-id": 1, "fields": [
+                { "id": 1, "name": "id", "required": true, "type": "long" },
+            ] },
+        ],
+    });
+    assert_eq!(current_schema(&v2).unwrap()["schema-id"], 1);
+    let v1 =
+        json!({ "format-version": 1, "schema": { "type": "struct", "fields": [] } });
+    assert!(current_schema(&v1).is_ok());
+}
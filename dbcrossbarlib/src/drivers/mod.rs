@@ -8,20 +8,44 @@ use std::collections::HashMap;
 use crate::common::*;
 use crate::locator::{LocatorDriver, LocatorDriverWrapper};
 
+pub mod arrow;
+pub mod avro;
+pub mod avro_shared;
+pub mod azure;
 pub mod bigml;
 pub mod bigquery;
 pub mod bigquery_schema;
 pub mod bigquery_shared;
+pub mod bigquery_sql;
 pub mod csv;
+pub mod databricks_sql;
 pub mod dbcrossbar_schema;
 pub mod dbcrossbar_ts;
+pub mod delta;
+pub mod druid_spec;
+pub mod elasticsearch_mapping;
+pub mod external;
+pub mod fixed_width;
+pub mod greenplum;
 pub mod gs;
+pub mod gsheet;
+pub mod iceberg;
+pub mod ndjson;
+pub mod parquet;
+pub mod parquet_shared;
 pub mod postgres;
 pub mod postgres_shared;
 pub mod postgres_sql;
 pub mod redshift;
 pub mod s3;
+pub mod salesforce;
+pub mod schema_registry;
 pub mod shopify;
+pub mod singlestore;
+pub mod synapse;
+pub mod timescaledb;
+pub mod vertica;
+pub mod xlsx;
 
 /// A helper which builds a `Box<dyn LocatorDriver>` for a type implementating
 /// `LocatorStatic`.
@@ -32,18 +56,39 @@ fn driver<L: LocatorStatic>() -> Box<dyn LocatorDriver> {
 lazy_static! {
     /// A list of known drivers, computed the first time we use it and cached.
     static ref KNOWN_DRIVERS: Vec<Box<dyn LocatorDriver>> = vec![
+        driver::<arrow::ArrowLocator>(),
+        driver::<avro::AvroLocator>(),
+        driver::<azure::AzureLocator>(),
         driver::<bigml::BigMlLocator>(),
         driver::<bigquery::BigQueryLocator>(),
         driver::<bigquery_schema::BigQuerySchemaLocator>(),
+        driver::<bigquery_sql::BigQuerySqlLocator>(),
         driver::<csv::CsvLocator>(),
+        driver::<databricks_sql::DatabricksSqlLocator>(),
         driver::<dbcrossbar_schema::DbcrossbarSchemaLocator>(),
         driver::<dbcrossbar_ts::DbcrossbarTsLocator>(),
+        driver::<delta::DeltaLocator>(),
+        driver::<druid_spec::DruidSpecLocator>(),
+        driver::<elasticsearch_mapping::ElasticsearchMappingLocator>(),
+        driver::<fixed_width::FixedWidthLocator>(),
+        driver::<greenplum::GreenplumLocator>(),
         driver::<gs::GsLocator>(),
+        driver::<gsheet::GSheetLocator>(),
+        driver::<iceberg::IcebergLocator>(),
+        driver::<ndjson::NdJsonLocator>(),
+        driver::<parquet::ParquetLocator>(),
         driver::<postgres::PostgresLocator>(),
         driver::<postgres_sql::PostgresSqlLocator>(),
         driver::<redshift::RedshiftLocator>(),
         driver::<s3::S3Locator>(),
+        driver::<salesforce::SalesforceLocator>(),
+        driver::<schema_registry::SchemaRegistryLocator>(),
         driver::<shopify::ShopifyLocator>(),
+        driver::<singlestore::SingleStoreLocator>(),
+        driver::<synapse::SynapseLocator>(),
+        driver::<timescaledb::TimescaleDbLocator>(),
+        driver::<vertica::VerticaLocator>(),
+        driver::<xlsx::XlsxLocator>(),
     ];
 
     /// A hash table of all known drivers, indexed by scheme and computed the
@@ -0,0 +1,208 @@
+//! Convert CSV data to newline-delimited JSON.
+
+use serde_json::{Number, Value};
+use std::io::{BufReader, BufWriter};
+
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::{Column, DataType};
+
+/// How should we output `json` and `geojson` columns?
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum JsonColumnStyle {
+    /// Include the JSON value directly in our output.
+    Inline,
+    /// Output the JSON value as a string. This is what BigQuery expects when
+    /// loading `STRING` and `GEOGRAPHY` columns.
+    String,
+}
+
+/// Read CSV data from `rdr`, and write one JSON object per line to `wtr`,
+/// using `schema` to decide how to represent each column.
+///
+/// This is synchronous because it relies on `csv::Reader`, so it should be run
+/// using `spawn_sync_transform`.
+pub(crate) fn copy_csv_to_ndjson(
+    schema: &Schema,
+    json_style: JsonColumnStyle,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = BufWriter::with_capacity(BUFFER_SIZE, wtr);
+
+    // Check to make sure our CSV headers and table column names match.
+    let columns = &schema.table.columns;
+    let headers = rdr.headers()?;
+    if headers.len() != columns.len()
+        || headers.iter().zip(columns).any(|(h, c)| h != c.name)
+    {
+        return Err(format_err!(
+            "CSV headers {:?} do not match schema columns {:?}",
+            headers.iter().collect::<Vec<_>>(),
+            columns.iter().map(|c| &c.name).collect::<Vec<_>>(),
+        ));
+    }
+
+    // Write each row as a JSON object. We write the objects by hand so that
+    // keys appear in the same order as our columns.
+    for (row_idx, row) in rdr.records().enumerate() {
+        let row = row?;
+        write!(wtr, "{{")?;
+        for (col_idx, (col, cell)) in columns.iter().zip(row.iter()).enumerate() {
+            let value =
+                cell_to_json(schema, json_style, col, cell).with_context(|_| {
+                    ErrorKind::InvalidData {
+                        row: row_idx + 1,
+                        column: col.name.clone(),
+                        value: cell.to_owned(),
+                    }
+                })?;
+            if col_idx > 0 {
+                write!(wtr, ",")?;
+            }
+            serde_json::to_writer(&mut wtr, &col.name)?;
+            write!(wtr, ":")?;
+            serde_json::to_writer(&mut wtr, &value)?;
+        }
+        writeln!(wtr, "}}")?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Convert a CSV cell into a JSON value.
+fn cell_to_json(
+    schema: &Schema,
+    json_style: JsonColumnStyle,
+    col: &Column,
+    cell: &str,
+) -> Result<Value> {
+    if cell.is_empty() && col.is_nullable {
+        // Our CSV format can't distinguish between empty strings and `NULL`,
+        // so we treat all empty cells in nullable columns as `NULL`.
+        Ok(Value::Null)
+    } else {
+        value_to_json(schema, json_style, &col.data_type, cell)
+    }
+}
+
+/// Convert a non-`NULL` CSV value into a JSON value.
+fn value_to_json(
+    schema: &Schema,
+    json_style: JsonColumnStyle,
+    data_type: &DataType,
+    cell: &str,
+) -> Result<Value> {
+    match data_type {
+        DataType::Named(name) => {
+            value_to_json(schema, json_style, schema.data_type_for_name(name), cell)
+        }
+        DataType::Json | DataType::GeoJson(_)
+            if json_style == JsonColumnStyle::String =>
+        {
+            // Make sure this is actually valid JSON before passing it on.
+            serde_json::from_str::<Value>(cell)?;
+            Ok(Value::String(cell.to_owned()))
+        }
+        _ if data_type.serializes_as_json_for_csv(schema) => {
+            Ok(serde_json::from_str(cell)?)
+        }
+        DataType::Bool => Ok(Value::Bool(bool::from_csv_cell(cell)?)),
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            Ok(Value::from(i64::from_csv_cell(cell)?))
+        }
+        DataType::Float32 | DataType::Float64 => {
+            let f = f64::from_csv_cell(cell)?;
+            let n = Number::from_f64(f).ok_or_else(|| {
+                format_err!("cannot represent {} as a JSON number", f)
+            })?;
+            Ok(Value::Number(n))
+        }
+        // We keep decimals as strings, because they may not fit in a JSON
+        // number without losing precision. Everything else is naturally a
+        // string.
+        _ => Ok(Value::String(cell.to_owned())),
+    }
+}
+
+#[test]
+fn converts_csv_to_ndjson() {
+    use crate::test_util::SharedBuffer;
+
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64" },
+      { "name": "name", "is_nullable": true, "data_type": "text" },
+      { "name": "active", "is_nullable": true, "data_type": "bool" },
+      { "name": "score", "is_nullable": true, "data_type": "float64" },
+      { "name": "price", "is_nullable": true, "data_type": "decimal" },
+      { "name": "tags", "is_nullable": true, "data_type": { "array": "text" } },
+      { "name": "extra", "is_nullable": true, "data_type": "json" }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let csv = "id,name,active,score,price,tags,extra\n1,Ann,t,1.5,10.25,\"[\"\"a\"\"]\",\"{\"\"k\"\":1}\"\n2,,f,,,,\n";
+    let convert = |json_style| {
+        let out = SharedBuffer::default();
+        copy_csv_to_ndjson(
+            &schema,
+            json_style,
+            Box::new(csv.as_bytes()),
+            Box::new(out.clone()),
+        )
+        .unwrap();
+        let out = out.contents();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        convert(JsonColumnStyle::Inline),
+        r#"{"id":1,"name":"Ann","active":true,"score":1.5,"price":"10.25","tags":["a"],"extra":{"k":1}}
+{"id":2,"name":null,"active":false,"score":null,"price":null,"tags":null,"extra":null}
+"#,
+    );
+    assert_eq!(
+        convert(JsonColumnStyle::String),
+        r#"{"id":1,"name":"Ann","active":true,"score":1.5,"price":"10.25","tags":["a"],"extra":"{\"k\":1}"}
+{"id":2,"name":null,"active":false,"score":null,"price":null,"tags":null,"extra":null}
+"#,
+    );
+}
+
+#[test]
+fn invalid_data_reports_row_without_header() {
+    use crate::schema::{Column, Table};
+
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![Column {
+            name: "id".to_owned(),
+            is_nullable: true,
+            data_type: DataType::Int64,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let csv = "id\nnope\n";
+    let err = copy_csv_to_ndjson(
+        &schema,
+        JsonColumnStyle::Inline,
+        Box::new(csv.as_bytes()),
+        Box::new(vec![]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        ErrorKind::of(&err),
+        Some(&ErrorKind::InvalidData {
+            row: 1,
+            column: "id".to_owned(),
+            value: "nope".to_owned(),
+        }),
+    );
+}
@@ -0,0 +1,348 @@
+//! Driver for working with newline-delimited JSON files.
+
+use std::{
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tokio::{
+    fs,
+    io::{self, BufReader},
+};
+use walkdir::WalkDir;
+
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::csv_stream::csv_stream_name;
+use crate::tokio_glue::{copy_reader_to_stream, copy_stream_to_writer};
+use crate::transform::spawn_sync_transform;
+
+mod csv_to_ndjson;
+mod ndjson_to_csv;
+
+use csv_to_ndjson::copy_csv_to_ndjson;
+pub(crate) use csv_to_ndjson::JsonColumnStyle;
+use ndjson_to_csv::copy_ndjson_to_csv;
+
+/// A newline-delimited JSON file, with one JSON object per line, or a
+/// directory containing such files.
+#[derive(Clone, Debug)]
+pub(crate) struct NdJsonLocator {
+    path: PathOrStdio,
+}
+
+impl NdJsonLocator {
+    /// Construct an `NdJsonLocator` from a path.
+    fn from_path<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: PathOrStdio::Path(path.into()),
+        }
+    }
+}
+
+impl fmt::Display for NdJsonLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for NdJsonLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(NdJsonLocator { path })
+    }
+}
+
+impl Locator for NdJsonLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.path.clone(), shared_args, source_args).boxed()
+    }
+
+    fn display_output_locators(&self) -> DisplayOutputLocators {
+        match &self.path {
+            // If we write our data to standard output, we don't also want to
+            // print out "ndjson:-" to the same standard output.
+            PathOrStdio::Stdio => DisplayOutputLocators::Never,
+            _ => DisplayOutputLocators::IfRequested,
+        }
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.path.clone(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for NdJsonLocator {
+    fn scheme() -> &'static str {
+        "ndjson:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::LocalData | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::no_append(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Convert a stream of NDJSON data into a `CsvStream`.
+fn ndjson_to_csv_stream(
+    ctx: &Context,
+    schema: &Schema,
+    name: String,
+    data: BoxStream<BytesMut>,
+) -> Result<CsvStream> {
+    let schema = schema.to_owned();
+    let data = spawn_sync_transform(
+        ctx.clone(),
+        "copy_ndjson_to_csv".to_owned(),
+        data,
+        move |_ctx, rdr, wtr| copy_ndjson_to_csv(&schema, rdr, wtr),
+    )?;
+    Ok(CsvStream { name, data })
+}
+
+/// Convert a `CsvStream` into a stream of NDJSON data.
+pub(crate) fn csv_stream_to_ndjson(
+    ctx: &Context,
+    schema: &Schema,
+    json_style: JsonColumnStyle,
+    stream: CsvStream,
+) -> Result<BoxStream<BytesMut>> {
+    let schema = schema.to_owned();
+    spawn_sync_transform(
+        ctx.clone(),
+        "copy_csv_to_ndjson".to_owned(),
+        stream.data,
+        move |_ctx, rdr, wtr| copy_csv_to_ndjson(&schema, json_style, rdr, wtr),
+    )
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+async fn local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(NdJsonLocator::features())?;
+    let _source_args = source_args.verify(NdJsonLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    match path {
+        PathOrStdio::Stdio => {
+            let data = BufReader::with_capacity(BUFFER_SIZE, io::stdin());
+            let stream = copy_reader_to_stream(ctx.clone(), data)?
+                .map_err(move |e| format_err!("cannot read stdin: {}", e))
+                .boxed();
+            let csv_stream =
+                ndjson_to_csv_stream(&ctx, &schema, "data".to_owned(), stream)?;
+            Ok(Some(box_stream_once(Ok(csv_stream))))
+        }
+        PathOrStdio::Path(base_path) => {
+            let paths = ndjson_paths(&ctx, &base_path)?;
+            let csv_streams = stream::iter(paths).map(Ok).and_then(move |file_path| {
+                let ctx = ctx.clone();
+                let base_path = base_path.clone();
+                let schema = schema.clone();
+                async move {
+                    // Get the name of our stream.
+                    let name = csv_stream_name(
+                        &base_path.to_string_lossy(),
+                        &file_path.to_string_lossy(),
+                    )?
+                    .to_owned();
+                    let ctx = ctx.child(o!(
+                        "stream" => name.clone(),
+                        "path" => format!("{}", file_path.display())
+                    ));
+
+                    // Open our file and convert it to CSV.
+                    let data = fs::File::open(file_path.clone()).await.with_context(
+                        |_| format!("cannot open {}", file_path.display()),
+                    )?;
+                    let data = BufReader::with_capacity(BUFFER_SIZE, data);
+                    let stream = copy_reader_to_stream(ctx.clone(), data)?
+                        .map_err(move |e| {
+                            format_err!("cannot read {}: {}", file_path.display(), e)
+                        })
+                        .boxed();
+                    ndjson_to_csv_stream(&ctx, &schema, name, stream)
+                }
+                .boxed()
+            });
+            Ok(Some(csv_streams.boxed()))
+        }
+    }
+}
+
+/// Recursively look at `base_path`, picking out the files that look like
+/// NDJSON.
+fn ndjson_paths(ctx: &Context, base_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    debug!(ctx.log(), "walking {}", base_path.display());
+    let walker = WalkDir::new(base_path).follow_links(true);
+    for dirent in walker.into_iter() {
+        let dirent = dirent.with_context(|_| {
+            format!("error listing files in {}", base_path.display())
+        })?;
+        let p = dirent.path();
+        trace!(ctx.log(), "found dirent {}", p.display());
+        if dirent.file_type().is_dir() {
+            continue;
+        } else if !dirent.file_type().is_file() {
+            return Err(format_err!("not a file: {}", p.display()));
+        }
+
+        let ext = p.extension();
+        if ext == Some(OsStr::new("ndjson")) || ext == Some(OsStr::new("jsonl")) {
+            paths.push(p.to_owned());
+        } else {
+            return Err(format_err!(
+                "{} must end in *.ndjson or *.jsonl",
+                p.display()
+            ));
+        }
+    }
+    Ok(paths)
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+async fn write_local_data_helper(
+    ctx: Context,
+    path: PathOrStdio,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(NdJsonLocator::features())?;
+    let dest_args = dest_args.verify(NdJsonLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+    match path {
+        PathOrStdio::Stdio => {
+            if_exists.warn_if_not_default_for_stdout(&ctx);
+            let stream = concatenate_csv_streams(ctx.clone(), data)?;
+            let ndjson =
+                csv_stream_to_ndjson(&ctx, &schema, JsonColumnStyle::Inline, stream)?;
+            let fut = async move {
+                copy_stream_to_writer(ctx.clone(), ndjson, io::stdout())
+                    .await
+                    .context("error writing to stdout")?;
+                Ok(NdJsonLocator {
+                    path: PathOrStdio::Stdio,
+                }
+                .boxed())
+            };
+            Ok(box_stream_once(Ok(fut.boxed())))
+        }
+        PathOrStdio::Path(path) => {
+            if path.to_string_lossy().ends_with('/') {
+                // Write streams to our directory as multiple files.
+                let result_stream = data.map_ok(move |stream| {
+                    let path = path.clone();
+                    let ctx = ctx.clone();
+                    let schema = schema.clone();
+                    let if_exists = if_exists.clone();
+
+                    async move {
+                        let ndjson_path = path.join(format!("{}.ndjson", stream.name));
+                        let ctx = ctx.child(o!(
+                            "stream" => stream.name.clone(),
+                            "path" => format!("{}", ndjson_path.display()),
+                        ));
+                        let ndjson = csv_stream_to_ndjson(
+                            &ctx,
+                            &schema,
+                            JsonColumnStyle::Inline,
+                            stream,
+                        )?;
+                        write_stream_to_file(
+                            ctx,
+                            ndjson,
+                            ndjson_path.clone(),
+                            if_exists,
+                        )
+                        .await?;
+                        Ok(NdJsonLocator::from_path(ndjson_path).boxed())
+                    }
+                    .boxed()
+                });
+                Ok(result_stream.boxed())
+            } else {
+                // Write all our streams as a single file.
+                let stream = concatenate_csv_streams(ctx.clone(), data)?;
+                let fut = async move {
+                    let ctx = ctx.child(o!(
+                        "stream" => stream.name.clone(),
+                        "path" => format!("{}", path.display()),
+                    ));
+                    let ndjson = csv_stream_to_ndjson(
+                        &ctx,
+                        &schema,
+                        JsonColumnStyle::Inline,
+                        stream,
+                    )?;
+                    write_stream_to_file(ctx, ndjson, path.clone(), if_exists).await?;
+                    Ok(NdJsonLocator::from_path(path).boxed())
+                };
+                Ok(box_stream_once(Ok(fut.boxed())))
+            }
+        }
+    }
+}
+
+/// Write `data` to `dest`, honoring `if_exists`.
+async fn write_stream_to_file(
+    ctx: Context,
+    data: BoxStream<BytesMut>,
+    dest: PathBuf,
+    if_exists: IfExists,
+) -> Result<()> {
+    // Make sure our destination directory exists.
+    let dir = dest
+        .parent()
+        .ok_or_else(|| format_err!("cannot find parent dir for {}", dest.display()))?;
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|_| format!("unable to create directory {}", dir.display()))?;
+
+    debug!(ctx.log(), "writing stream to file {}", dest.display());
+    let wtr = if_exists
+        .to_async_open_options_no_append()?
+        .open(dest.clone())
+        .await
+        .with_context(|_| format!("cannot open {}", dest.display()))?;
+    copy_stream_to_writer(ctx.clone(), data, wtr)
+        .await
+        .with_context(|_| format!("error writing {}", dest.display()))?;
+    Ok(())
+}
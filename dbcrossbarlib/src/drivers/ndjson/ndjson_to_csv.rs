@@ -0,0 +1,74 @@
+//! Convert newline-delimited JSON to CSV data.
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader, BufWriter};
+
+use crate::common::*;
+use crate::json_to_csv::write_row;
+
+/// Read one JSON object per line from `rdr`, and write CSV data to `wtr`,
+/// using `schema` to decide which columns to output.
+///
+/// Blank lines are ignored, and keys which aren't in `schema` are ignored.
+pub(crate) fn copy_ndjson_to_csv(
+    schema: &Schema,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let rdr = BufReader::with_capacity(BUFFER_SIZE, rdr);
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    wtr.write_record(schema.table.columns.iter().map(|c| &c.name))?;
+
+    let mut buffer = Vec::with_capacity(2 * 1024);
+    for (line_idx, line) in rdr.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = serde_json::from_str::<Value>(&line)
+            .with_context(|_| format!("could not parse line {}", line_idx + 1))?;
+        write_row(&mut wtr, schema, row, &mut buffer)
+            .with_context(|_| format!("could not convert line {}", line_idx + 1))?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[test]
+fn converts_ndjson_to_csv() {
+    use crate::test_util::SharedBuffer;
+
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64" },
+      { "name": "active", "is_nullable": true, "data_type": "bool" },
+      { "name": "point", "is_nullable": true, "data_type": { "struct": [
+        { "name": "x", "data_type": "float64", "is_nullable": false }
+      ] } }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let ndjson = r#"{"id":1,"active":true,"point":{"x":1.5},"extra":"ignored"}
+
+{"id":2}
+"#;
+    let out = SharedBuffer::default();
+    copy_ndjson_to_csv(&schema, Box::new(ndjson.as_bytes()), Box::new(out.clone()))
+        .unwrap();
+    let out = out.contents_str();
+    assert_eq!(out, "id,active,point\n1,t,\"{\"\"x\"\":1.5}\"\n2,,\n");
+
+    let bad = r#"{"active":true}"#;
+    assert!(copy_ndjson_to_csv(
+        &schema,
+        Box::new(bad.as_bytes()),
+        Box::new(SharedBuffer::default())
+    )
+    .is_err());
+}
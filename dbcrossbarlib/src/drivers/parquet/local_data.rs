@@ -0,0 +1,55 @@
+//! Reading data from Parquet files.
+
+use std::fs::File;
+
+use super::{parquet_paths, ParquetLocator};
+use crate::common::*;
+use crate::csv_stream::csv_stream_name;
+use crate::drivers::parquet_shared::write_parquet_as_csv;
+use crate::tokio_glue::SyncStreamWriter;
+
+/// Implementation of `local_data`, but as a real `async` function.
+pub(super) async fn local_data_helper(
+    ctx: Context,
+    source: ParquetLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(ParquetLocator::features())?;
+    let _source_args = source_args.verify(ParquetLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+
+    let base_path = source.path.clone();
+    let paths = parquet_paths(&ctx, &base_path)?;
+    let csv_streams = stream::iter(paths).map(move |file_path| {
+        // Get the name of our stream.
+        let name = csv_stream_name(
+            &base_path.to_string_lossy(),
+            &file_path.to_string_lossy(),
+        )?
+        .to_owned();
+        let ctx = ctx.child(o!(
+            "stream" => name.clone(),
+            "path" => format!("{}", file_path.display())
+        ));
+
+        // The `parquet` crate is synchronous, so convert our file to CSV on a
+        // helper thread.
+        let (wtr, data) = SyncStreamWriter::pipe(ctx.clone());
+        let schema = schema.clone();
+        let convert_fut = spawn_blocking(move || {
+            let file = File::open(&file_path)
+                .with_context(|_| format!("cannot open {}", file_path.display()))?;
+            write_parquet_as_csv(&schema, file, Box::new(wtr))
+                .with_context(|_| format!("error reading {}", file_path.display()))?;
+            Ok(())
+        });
+        ctx.spawn_worker(convert_fut.boxed());
+
+        Ok(CsvStream {
+            name,
+            data: data.boxed(),
+        })
+    });
+    Ok(Some(csv_streams.boxed()))
+}
@@ -0,0 +1,184 @@
+//! Driver for working with Parquet files.
+
+use std::{
+    ffi::OsStr,
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use walkdir::WalkDir;
+
+use crate::common::*;
+use crate::drivers::parquet_shared::table_from_parquet;
+
+mod local_data;
+mod write_local_data;
+
+use self::local_data::local_data_helper;
+use self::write_local_data::write_local_data_helper;
+
+/// A Parquet file, or a directory containing Parquet files, like
+/// `parquet:orders.parquet` or `parquet:orders/`.
+///
+/// The `parquet` crate needs to be able to seek within files, so we can't read
+/// or write Parquet data using standard input and output.
+#[derive(Clone, Debug)]
+pub(crate) struct ParquetLocator {
+    path: PathBuf,
+}
+
+impl ParquetLocator {
+    /// Does our locator refer to a directory?
+    fn is_directory(&self) -> bool {
+        self.path.to_string_lossy().ends_with('/') || self.path.is_dir()
+    }
+}
+
+impl fmt::Display for ParquetLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Self::scheme(), self.path.display())
+    }
+}
+
+impl FromStr for ParquetLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path_str = s.strip_prefix(Self::scheme()).ok_or_else(|| {
+            format_err!("expected {:?} to begin with {}", s, Self::scheme())
+        })?;
+        if path_str.is_empty() || path_str == "-" {
+            return Err(format_err!(
+                "expected a Parquet file or directory in {:?}",
+                s
+            ));
+        }
+        Ok(ParquetLocator {
+            path: PathBuf::from(path_str),
+        })
+    }
+}
+
+#[test]
+fn rejects_stdio() {
+    assert!("parquet:-".parse::<ParquetLocator>().is_err());
+    assert!("parquet:".parse::<ParquetLocator>().is_err());
+}
+
+impl Locator for ParquetLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.to_owned(), shared_args, source_args).boxed()
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.to_owned(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for ParquetLocator {
+    fn scheme() -> &'static str {
+        "parquet:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema
+                | LocatorFeatures::LocalData
+                | LocatorFeatures::WriteLocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
+            dest_if_exists: IfExistsFeatures::no_append(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// Implementation of `schema`, but as a real `async` function.
+///
+/// If our locator is a directory, we use the schema of the first Parquet file
+/// we find.
+async fn schema_helper(
+    ctx: Context,
+    source: ParquetLocator,
+) -> Result<Option<Schema>> {
+    spawn_blocking(move || {
+        let paths = parquet_paths(&ctx, &source.path)?;
+        let path = paths
+            .first()
+            .ok_or_else(|| format_err!("no Parquet files found in {}", source))?;
+        let table_name = source
+            .path
+            .file_stem()
+            .ok_or_else(|| format_err!("cannot get table name from {}", source))?
+            .to_string_lossy()
+            .into_owned();
+        let file = File::open(path)
+            .with_context(|_| format!("cannot open {}", path.display()))?;
+        let table = table_from_parquet(file, &table_name).with_context(|_| {
+            format!("error reading schema from {}", path.display())
+        })?;
+        Ok(Some(Schema::from_table(table)?))
+    })
+    .await
+}
+
+/// Recursively look at `base_path`, picking out the Parquet files. If
+/// `base_path` is a file, return it.
+fn parquet_paths(ctx: &Context, base_path: &Path) -> Result<Vec<PathBuf>> {
+    if base_path.is_file() {
+        return Ok(vec![base_path.to_owned()]);
+    }
+
+    let mut paths = vec![];
+    debug!(ctx.log(), "walking {}", base_path.display());
+    let walker = WalkDir::new(base_path)
+        .follow_links(true)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+    for dirent in walker.into_iter() {
+        let dirent = dirent.with_context(|_| {
+            format!("error listing files in {}", base_path.display())
+        })?;
+        let p = dirent.path();
+        trace!(ctx.log(), "found dirent {}", p.display());
+        if dirent.file_type().is_dir() {
+            continue;
+        } else if !dirent.file_type().is_file() {
+            return Err(format_err!("not a file: {}", p.display()));
+        } else if p.extension() == Some(OsStr::new("parquet")) {
+            paths.push(p.to_owned());
+        } else {
+            // Directories written by other tools often contain marker files
+            // like `_SUCCESS`, so we skip anything that isn't Parquet.
+            trace!(ctx.log(), "skipping {}", p.display());
+        }
+    }
+    Ok(paths)
+}
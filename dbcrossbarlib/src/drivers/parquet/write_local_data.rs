@@ -0,0 +1,180 @@
+//! Writing data to Parquet files.
+
+use parquet::basic::Compression;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use super::ParquetLocator;
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+use crate::drivers::parquet_shared::{write_csv_as_parquet, ParquetWriteOptions};
+
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParquetDestinationArguments {
+    /// The maximum number of rows to put in each row group.
+    row_group_size: Option<String>,
+
+    /// How to compress our data.
+    compression: Option<ParquetCompression>,
+}
+
+impl ParquetDestinationArguments {
+    /// Convert our arguments into `ParquetWriteOptions`.
+    fn to_write_options(&self) -> Result<ParquetWriteOptions> {
+        let mut options = ParquetWriteOptions::default();
+        if let Some(row_group_size) = &self.row_group_size {
+            options.row_group_size = row_group_size
+                .parse::<usize>()
+                .ok()
+                .filter(|&size| size > 0)
+                .ok_or_else(|| {
+                    format_err!(
+                        "row_group_size must be a positive integer, found {:?}",
+                        row_group_size
+                    )
+                })?;
+        }
+        if let Some(compression) = self.compression {
+            options.compression = compression.into();
+        }
+        Ok(options)
+    }
+}
+
+/// Compression types that we support for Parquet.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ParquetCompression {
+    None,
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP,
+            ParquetCompression::Zstd => Compression::ZSTD,
+        }
+    }
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+pub(super) async fn write_local_data_helper(
+    ctx: Context,
+    dest: ParquetLocator,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(ParquetLocator::features())?;
+    let dest_args = dest_args.verify(ParquetLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+    let options = dest_args
+        .driver_args()
+        .deserialize::<ParquetDestinationArguments>()
+        .context("could not parse --to-arg")?
+        .to_write_options()?;
+
+    if dest.is_directory() {
+        // Write each stream to its own Parquet file.
+        let dir = dest.path.clone();
+        let result_stream = data.map_ok(move |stream| {
+            let path = dir.join(format!("{}.parquet", stream.name));
+            write_stream(
+                ctx.clone(),
+                schema.clone(),
+                stream,
+                path,
+                if_exists.clone(),
+                options.clone(),
+            )
+            .boxed()
+        });
+        Ok(result_stream.boxed())
+    } else {
+        // Write all our streams as a single file.
+        let stream = concatenate_csv_streams(ctx.clone(), data)?;
+        let path = dest.path.clone();
+        let fut = write_stream(ctx, schema, stream, path, if_exists, options);
+        Ok(box_stream_once(Ok(fut.boxed())))
+    }
+}
+
+/// Write `stream` to a new Parquet file at `path`.
+async fn write_stream(
+    ctx: Context,
+    schema: Schema,
+    stream: CsvStream,
+    path: PathBuf,
+    if_exists: IfExists,
+    options: ParquetWriteOptions,
+) -> Result<BoxLocator> {
+    let ctx = ctx.child(o!(
+        "stream" => stream.name.clone(),
+        "path" => format!("{}", path.display()),
+    ));
+
+    // Make sure our destination directory exists.
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.with_context(|_| {
+            format!("unable to create directory {}", dir.display())
+        })?;
+    }
+    let file = if_exists
+        .to_async_open_options_no_append()?
+        .open(&path)
+        .await
+        .with_context(|_| format!("cannot open {}", path.display()))?
+        .into_std()
+        .await;
+
+    // Parquet files are written one row group at a time, and the `parquet`
+    // crate needs to seek, so we buffer our CSV data in memory.
+    let csv_data = stream
+        .data
+        .try_fold(vec![], |mut buf, bytes| async move {
+            buf.extend_from_slice(&bytes);
+            Ok(buf)
+        })
+        .await?;
+
+    debug!(ctx.log(), "writing Parquet data");
+    let locator = ParquetLocator { path: path.clone() };
+    let row_count = spawn_blocking(move || {
+        let row_count = write_csv_as_parquet(&schema, &csv_data, file, &options)
+            .with_context(|_| format!("error writing {}", path.display()))?;
+        Ok(row_count)
+    })
+    .await?;
+    debug!(ctx.log(), "wrote {} rows", row_count);
+    Ok(locator.boxed())
+}
+
+#[test]
+fn parses_write_options() {
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<ParquetDestinationArguments>()
+            .unwrap()
+            .to_write_options()
+    };
+
+    let options = parse(&[]).unwrap();
+    assert_eq!(options.row_group_size, 128 * 1024);
+    assert_eq!(options.compression, Compression::SNAPPY);
+
+    let options = parse(&["row_group_size=1000", "compression=zstd"]).unwrap();
+    assert_eq!(options.row_group_size, 1000);
+    assert_eq!(options.compression, Compression::ZSTD);
+
+    assert!(parse(&["row_group_size=0"]).is_err());
+    assert!(parse(&["row_group_size=lots"]).is_err());
+}
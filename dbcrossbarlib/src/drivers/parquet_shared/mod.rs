@@ -0,0 +1,364 @@
+//! Converting between CSV data and Parquet files.
+//!
+//! We map each portable column onto a single flat Parquet column. Types which
+//! Parquet can represent directly are stored as native values, and everything
+//! else is stored as UTF-8 text in our CSV interchange format.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, Type as PhysicalType},
+    column::writer::ColumnWriter,
+    data_type::ByteArray,
+    file::{
+        properties::WriterProperties,
+        writer::{FileWriter, SerializedFileWriter},
+    },
+    schema::types::Type,
+};
+use std::{fs::File, sync::Arc};
+
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::DataType;
+
+mod read;
+
+pub(crate) use self::read::{table_from_parquet, write_parquet_as_csv};
+
+/// How we store a portable column in Parquet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ParquetType {
+    Boolean,
+    Int16,
+    Int32,
+    Int64,
+    Float,
+    Double,
+    Date,
+    TimestampMicros,
+    Utf8,
+}
+
+impl ParquetType {
+    /// Choose a Parquet type for `data_type`.
+    pub(crate) fn for_data_type(schema: &Schema, data_type: &DataType) -> ParquetType {
+        match data_type {
+            DataType::Bool => ParquetType::Boolean,
+            DataType::Date => ParquetType::Date,
+            DataType::Float32 => ParquetType::Float,
+            DataType::Float64 => ParquetType::Double,
+            DataType::Int16 => ParquetType::Int16,
+            DataType::Int32 => ParquetType::Int32,
+            DataType::Int64 => ParquetType::Int64,
+            DataType::Named(name) => {
+                ParquetType::for_data_type(schema, schema.data_type_for_name(name))
+            }
+            DataType::TimestampWithoutTimeZone | DataType::TimestampWithTimeZone => {
+                ParquetType::TimestampMicros
+            }
+            // We don't know the precision of our decimals, so we store them as
+            // text to avoid rounding.
+            DataType::Array(_)
+            | DataType::Decimal
+            | DataType::GeoJson(_)
+            | DataType::Json
+            | DataType::OneOf(_)
+            | DataType::Struct(_)
+            | DataType::Text
+            | DataType::Uuid => ParquetType::Utf8,
+        }
+    }
+
+    /// Build a Parquet schema field for a column with this type.
+    fn to_parquet_field(self, name: &str) -> Result<Type> {
+        let (physical_type, logical_type) = match self {
+            ParquetType::Boolean => (PhysicalType::BOOLEAN, LogicalType::NONE),
+            ParquetType::Int16 => (PhysicalType::INT32, LogicalType::INT_16),
+            ParquetType::Int32 => (PhysicalType::INT32, LogicalType::NONE),
+            ParquetType::Int64 => (PhysicalType::INT64, LogicalType::NONE),
+            ParquetType::Float => (PhysicalType::FLOAT, LogicalType::NONE),
+            ParquetType::Double => (PhysicalType::DOUBLE, LogicalType::NONE),
+            ParquetType::Date => (PhysicalType::INT32, LogicalType::DATE),
+            ParquetType::TimestampMicros => {
+                (PhysicalType::INT64, LogicalType::TIMESTAMP_MICROS)
+            }
+            ParquetType::Utf8 => (PhysicalType::BYTE_ARRAY, LogicalType::UTF8),
+        };
+        // We make every column optional, because our CSV interchange format
+        // represents `NULL` as an empty cell.
+        Ok(Type::primitive_type_builder(name, physical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical_type)
+            .build()?)
+    }
+}
+
+/// Values for a single column, plus Parquet "definition levels", which are 0
+/// for `NULL` and 1 otherwise.
+enum ColumnBuffer {
+    Boolean(Vec<bool>, Vec<i16>),
+    Int32(Vec<i32>, Vec<i16>),
+    Int64(Vec<i64>, Vec<i16>),
+    Float(Vec<f32>, Vec<i16>),
+    Double(Vec<f64>, Vec<i16>),
+    ByteArray(Vec<ByteArray>, Vec<i16>),
+}
+
+impl ColumnBuffer {
+    /// Create an empty buffer for values of type `ty`.
+    fn new(ty: ParquetType) -> ColumnBuffer {
+        match ty {
+            ParquetType::Boolean => ColumnBuffer::Boolean(vec![], vec![]),
+            ParquetType::Int16 | ParquetType::Int32 | ParquetType::Date => {
+                ColumnBuffer::Int32(vec![], vec![])
+            }
+            ParquetType::Int64 | ParquetType::TimestampMicros => {
+                ColumnBuffer::Int64(vec![], vec![])
+            }
+            ParquetType::Float => ColumnBuffer::Float(vec![], vec![]),
+            ParquetType::Double => ColumnBuffer::Double(vec![], vec![]),
+            ParquetType::Utf8 => ColumnBuffer::ByteArray(vec![], vec![]),
+        }
+    }
+
+    /// Record a `NULL` value.
+    fn push_null(&mut self) {
+        self.def_levels_mut().push(0);
+    }
+
+    /// Get our definition levels.
+    fn def_levels_mut(&mut self) -> &mut Vec<i16> {
+        match self {
+            ColumnBuffer::Boolean(_, levels)
+            | ColumnBuffer::Int32(_, levels)
+            | ColumnBuffer::Int64(_, levels)
+            | ColumnBuffer::Float(_, levels)
+            | ColumnBuffer::Double(_, levels)
+            | ColumnBuffer::ByteArray(_, levels) => levels,
+        }
+    }
+
+    /// Parse `cell` as type `ty` and record it.
+    fn push_cell(&mut self, ty: ParquetType, cell: &str) -> Result<()> {
+        match (self, ty) {
+            (ColumnBuffer::Boolean(values, _), _) => {
+                values.push(bool::from_csv_cell(cell)?)
+            }
+            (ColumnBuffer::Int32(values, _), ParquetType::Int16) => {
+                values.push(i32::from(i16::from_csv_cell(cell)?))
+            }
+            (ColumnBuffer::Int32(values, _), ParquetType::Date) => {
+                let date = NaiveDate::from_csv_cell(cell)?;
+                let days = (date - NaiveDate::from_ymd(1970, 1, 1)).num_days();
+                values.push(days.try_into().map_err(|_| {
+                    format_err!("date {:?} is out of range for Parquet", cell)
+                })?)
+            }
+            (ColumnBuffer::Int32(values, _), _) => {
+                values.push(i32::from_csv_cell(cell)?)
+            }
+            (ColumnBuffer::Int64(values, _), ParquetType::TimestampMicros) => {
+                // Try the zone-less format first, then fall back to UTC.
+                let timestamp = match NaiveDateTime::from_csv_cell(cell) {
+                    Ok(timestamp) => timestamp,
+                    Err(_) => DateTime::<Utc>::from_csv_cell(cell)?.naive_utc(),
+                };
+                values.push(
+                    timestamp.timestamp() * 1_000_000
+                        + i64::from(timestamp.timestamp_subsec_micros()),
+                )
+            }
+            (ColumnBuffer::Int64(values, _), _) => {
+                values.push(i64::from_csv_cell(cell)?)
+            }
+            (ColumnBuffer::Float(values, _), _) => {
+                values.push(f32::from_csv_cell(cell)?)
+            }
+            (ColumnBuffer::Double(values, _), _) => {
+                values.push(f64::from_csv_cell(cell)?)
+            }
+            (ColumnBuffer::ByteArray(values, _), _) => {
+                values.push(ByteArray::from(cell.as_bytes().to_vec()))
+            }
+        }
+        Ok(())
+    }
+
+    /// Write our values to `writer`.
+    fn write_to(&self, writer: &mut ColumnWriter) -> Result<()> {
+        match (self, writer) {
+            (
+                ColumnBuffer::Boolean(values, levels),
+                ColumnWriter::BoolColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            (
+                ColumnBuffer::Int32(values, levels),
+                ColumnWriter::Int32ColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            (
+                ColumnBuffer::Int64(values, levels),
+                ColumnWriter::Int64ColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            (
+                ColumnBuffer::Float(values, levels),
+                ColumnWriter::FloatColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            (
+                ColumnBuffer::Double(values, levels),
+                ColumnWriter::DoubleColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            (
+                ColumnBuffer::ByteArray(values, levels),
+                ColumnWriter::ByteArrayColumnWriter(w),
+            ) => {
+                w.write_batch(values, Some(levels), None)?;
+            }
+            _ => return Err(format_err!("Parquet column writer has the wrong type")),
+        }
+        Ok(())
+    }
+}
+
+/// Options for writing Parquet files.
+#[derive(Clone, Debug)]
+pub(crate) struct ParquetWriteOptions {
+    /// The maximum number of rows to put in a single row group.
+    pub(crate) row_group_size: usize,
+    /// How to compress our data.
+    pub(crate) compression: Compression,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        ParquetWriteOptions {
+            row_group_size: 128 * 1024,
+            compression: Compression::SNAPPY,
+        }
+    }
+}
+
+/// Parse `csv_data`, which must include a header row, and write it to `file`
+/// as Parquet. Returns the number of rows written.
+///
+/// This is synchronous, so call it using `spawn_blocking`.
+pub(crate) fn write_csv_as_parquet(
+    schema: &Schema,
+    csv_data: &[u8],
+    file: File,
+    options: &ParquetWriteOptions,
+) -> Result<usize> {
+    let columns = &schema.table.columns;
+    let types = columns
+        .iter()
+        .map(|col| ParquetType::for_data_type(schema, &col.data_type))
+        .collect::<Vec<_>>();
+
+    // Build our Parquet schema.
+    let mut fields = columns
+        .iter()
+        .zip(&types)
+        .map(|(col, ty)| Ok(Arc::new(ty.to_parquet_field(&col.name)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let parquet_schema = Type::group_type_builder("schema")
+        .with_fields(&mut fields)
+        .build()?;
+    let props = WriterProperties::builder()
+        .set_compression(options.compression)
+        .build();
+    let mut writer =
+        SerializedFileWriter::new(file, Arc::new(parquet_schema), Arc::new(props))?;
+
+    let mut rdr = csv::Reader::from_reader(csv_data);
+    let mut buffers = types
+        .iter()
+        .map(|&ty| ColumnBuffer::new(ty))
+        .collect::<Vec<_>>();
+    let mut rows_in_group = 0;
+    let mut row_count = 0;
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != columns.len() {
+            return Err(format_err!(
+                "expected {} columns, found {}",
+                columns.len(),
+                record.len(),
+            ));
+        }
+        row_count += 1;
+        for (((cell, col), &ty), buffer) in record
+            .iter()
+            .zip(columns)
+            .zip(&types)
+            .zip(buffers.iter_mut())
+        {
+            if cell.is_empty() {
+                buffer.push_null();
+            } else {
+                buffer.push_cell(ty, cell).with_context(|_| {
+                    ErrorKind::InvalidData {
+                        row: row_count,
+                        column: col.name.clone(),
+                        value: cell.to_owned(),
+                    }
+                })?;
+                buffer.def_levels_mut().push(1);
+            }
+        }
+        rows_in_group += 1;
+        if rows_in_group >= options.row_group_size {
+            write_row_group(&mut writer, &buffers)?;
+            buffers = types.iter().map(|&ty| ColumnBuffer::new(ty)).collect();
+            rows_in_group = 0;
+        }
+    }
+    if rows_in_group > 0 {
+        write_row_group(&mut writer, &buffers)?;
+    }
+    writer.close()?;
+    Ok(row_count)
+}
+
+/// Write a single row group containing the values in `buffers`.
+fn write_row_group(
+    writer: &mut SerializedFileWriter<File>,
+    buffers: &[ColumnBuffer],
+) -> Result<()> {
+    let mut row_group_writer = writer.next_row_group()?;
+    let mut buffers = buffers.iter();
+    while let Some(mut column_writer) = row_group_writer.next_column()? {
+        let buffer = buffers
+            .next()
+            .ok_or_else(|| format_err!("Parquet file has too many columns"))?;
+        buffer.write_to(&mut column_writer)?;
+        row_group_writer.close_column(column_writer)?;
+    }
+    writer.close_row_group(row_group_writer)?;
+    Ok(())
+}
+
+#[test]
+fn parquet_types_for_columns() {
+    let schema = Schema::dummy_test_schema();
+    assert_eq!(
+        ParquetType::for_data_type(&schema, &DataType::Int16),
+        ParquetType::Int16,
+    );
+    assert_eq!(
+        ParquetType::for_data_type(&schema, &DataType::TimestampWithTimeZone),
+        ParquetType::TimestampMicros,
+    );
+    assert_eq!(
+        ParquetType::for_data_type(&schema, &DataType::Decimal),
+        ParquetType::Utf8,
+    );
+}
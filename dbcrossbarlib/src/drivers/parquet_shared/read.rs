@@ -0,0 +1,285 @@
+//! Reading Parquet files as CSV data.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    data_type::Decimal,
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+    schema::types::Type,
+};
+use std::{collections::HashMap, convert::TryFrom, fs::File};
+
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+/// Read the schema of a Parquet `file`, and convert it to a portable table
+/// named `table_name`.
+///
+/// We only support flat Parquet files, where each column is a primitive type.
+pub(crate) fn table_from_parquet(file: File, table_name: &str) -> Result<Table> {
+    let reader = SerializedFileReader::new(file)?;
+    let metadata = reader.metadata().file_metadata();
+    let columns = metadata
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| column_from_parquet(field))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Table {
+        name: table_name.to_owned(),
+        columns,
+    })
+}
+
+/// Convert a top-level Parquet field into a portable column.
+fn column_from_parquet(field: &Type) -> Result<Column> {
+    let info = field.get_basic_info();
+    let name = info.name().to_owned();
+    if !field.is_primitive() || info.repetition() == Repetition::REPEATED {
+        return Err(format_err!(
+            "nested Parquet column {:?} is not supported",
+            name
+        ));
+    }
+    let data_type =
+        data_type_from_parquet(field.get_physical_type(), info.logical_type())
+            .with_context(|_| format!("cannot convert Parquet column {:?}", name))?;
+    Ok(Column {
+        name,
+        is_nullable: info.repetition() == Repetition::OPTIONAL,
+        data_type,
+        comment: None,
+    })
+}
+
+/// Convert a Parquet physical and logical type to a portable data type.
+fn data_type_from_parquet(
+    physical_type: PhysicalType,
+    logical_type: LogicalType,
+) -> Result<DataType> {
+    match (physical_type, logical_type) {
+        (PhysicalType::BOOLEAN, _) => Ok(DataType::Bool),
+        (PhysicalType::INT32, LogicalType::INT_8)
+        | (PhysicalType::INT32, LogicalType::INT_16)
+        | (PhysicalType::INT32, LogicalType::UINT_8) => Ok(DataType::Int16),
+        (PhysicalType::INT32, LogicalType::UINT_16) => Ok(DataType::Int32),
+        (PhysicalType::INT32, LogicalType::UINT_32) => Ok(DataType::Int64),
+        (PhysicalType::INT32, LogicalType::DATE) => Ok(DataType::Date),
+        (PhysicalType::INT32, LogicalType::DECIMAL) => Ok(DataType::Decimal),
+        (PhysicalType::INT32, LogicalType::NONE) => Ok(DataType::Int32),
+        // Parquet timestamps are always stored relative to UTC.
+        (PhysicalType::INT64, LogicalType::TIMESTAMP_MICROS)
+        | (PhysicalType::INT64, LogicalType::TIMESTAMP_MILLIS)
+        | (PhysicalType::INT96, _) => Ok(DataType::TimestampWithTimeZone),
+        (PhysicalType::INT64, LogicalType::DECIMAL)
+        | (PhysicalType::INT64, LogicalType::UINT_64) => Ok(DataType::Decimal),
+        (PhysicalType::INT64, LogicalType::NONE) => Ok(DataType::Int64),
+        (PhysicalType::FLOAT, _) => Ok(DataType::Float32),
+        (PhysicalType::DOUBLE, _) => Ok(DataType::Float64),
+        (PhysicalType::BYTE_ARRAY, LogicalType::UTF8)
+        | (PhysicalType::BYTE_ARRAY, LogicalType::ENUM) => Ok(DataType::Text),
+        (PhysicalType::BYTE_ARRAY, LogicalType::JSON) => Ok(DataType::Json),
+        (PhysicalType::BYTE_ARRAY, LogicalType::DECIMAL)
+        | (PhysicalType::FIXED_LEN_BYTE_ARRAY, LogicalType::DECIMAL) => {
+            Ok(DataType::Decimal)
+        }
+        (physical_type, logical_type) => Err(format_err!(
+            "unsupported Parquet type {} ({})",
+            physical_type,
+            logical_type,
+        )),
+    }
+}
+
+/// Read the Parquet data in `file`, and write it to `wtr` as CSV, using the
+/// columns in `schema`.
+///
+/// Columns are matched up by name, so the Parquet file may contain extra
+/// columns, or store its columns in a different order. This is synchronous, so
+/// call it using `spawn_blocking`.
+pub(crate) fn write_parquet_as_csv(
+    schema: &Schema,
+    file: File,
+    wtr: Box<dyn Write + Send>,
+) -> Result<()> {
+    let reader = SerializedFileReader::new(file)?;
+    let columns = &schema.table.columns;
+
+    // Figure out where to find each of our columns.
+    let parquet_columns = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| (field.name().to_owned(), idx))
+        .collect::<HashMap<_, _>>();
+    let indices = columns
+        .iter()
+        .map(|col| {
+            parquet_columns.get(&col.name).copied().ok_or_else(|| {
+                format_err!("cannot find column {:?} in Parquet file", col.name)
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut wtr = csv::Writer::from_writer(wtr);
+    wtr.write_record(columns.iter().map(|c| &c.name))?;
+    let mut record = Vec::with_capacity(columns.len());
+    for (row_idx, row) in reader.get_row_iter(None)?.enumerate() {
+        let fields = row.get_column_iter().map(|(_, f)| f).collect::<Vec<_>>();
+        record.clear();
+        for (col, &idx) in columns.iter().zip(&indices) {
+            let cell = field_to_csv_cell(schema, &col.data_type, fields[idx])
+                .with_context(|_| ErrorKind::InvalidData {
+                    row: row_idx + 1,
+                    column: col.name.clone(),
+                    value: format!("{}", fields[idx]),
+                })?;
+            record.push(cell);
+        }
+        wtr.write_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Convert a Parquet field to a cell in our CSV interchange format.
+///
+/// The `parquet` crate returns timestamps as `u64`, but they're stored as
+/// signed `i64` values, so we cast them back.
+#[allow(clippy::cast_possible_wrap)]
+fn field_to_csv_cell(
+    schema: &Schema,
+    data_type: &DataType,
+    field: &Field,
+) -> Result<String> {
+    if let DataType::Named(name) = data_type {
+        return field_to_csv_cell(schema, schema.data_type_for_name(name), field);
+    }
+    let with_zone = data_type == &DataType::TimestampWithTimeZone;
+    match field {
+        Field::Null => Ok(String::new()),
+        Field::Bool(true) => Ok("t".to_owned()),
+        Field::Bool(false) => Ok("f".to_owned()),
+        Field::Byte(v) => Ok(v.to_string()),
+        Field::Short(v) => Ok(v.to_string()),
+        Field::Int(v) => Ok(v.to_string()),
+        Field::Long(v) => Ok(v.to_string()),
+        Field::UByte(v) => Ok(v.to_string()),
+        Field::UShort(v) => Ok(v.to_string()),
+        Field::UInt(v) => Ok(v.to_string()),
+        Field::ULong(v) => Ok(v.to_string()),
+        Field::Float(v) => Ok(v.to_string()),
+        Field::Double(v) => Ok(v.to_string()),
+        Field::Decimal(d) => decimal_to_string(d),
+        Field::Str(s) => Ok(s.to_owned()),
+        Field::Date(days) => {
+            let date =
+                NaiveDate::from_ymd(1970, 1, 1) + Duration::days(i64::from(*days));
+            Ok(date.format("%Y-%m-%d").to_string())
+        }
+        Field::TimestampMillis(millis) => {
+            Ok(format_timestamp(*millis as i64 * 1_000, with_zone))
+        }
+        Field::TimestampMicros(micros) => {
+            Ok(format_timestamp(*micros as i64, with_zone))
+        }
+        other => Err(format_err!("cannot convert Parquet value {}", other)),
+    }
+}
+
+/// Format a timestamp, measured in microseconds since the Unix epoch.
+fn format_timestamp(micros: i64, with_zone: bool) -> String {
+    let timestamp = NaiveDateTime::from_timestamp(
+        micros.div_euclid(1_000_000),
+        u32::try_from(micros.rem_euclid(1_000_000) * 1_000)
+            .expect("remainder should always fit in u32"),
+    );
+    if with_zone {
+        timestamp.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+    } else {
+        timestamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+    }
+}
+
+/// Convert a Parquet decimal, which is stored as a big-endian two's complement
+/// integer and a scale, into a string.
+fn decimal_to_string(d: &Decimal) -> Result<String> {
+    let bytes = d.data();
+    if bytes.is_empty() || bytes.len() > 16 {
+        return Err(format_err!(
+            "cannot convert {}-byte Parquet decimal",
+            bytes.len()
+        ));
+    }
+    // Sign-extend our bytes to 128 bits.
+    let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    let value = i128::from_be_bytes(buf);
+
+    let scale = usize::try_from(d.scale()).map_err(|_| {
+        format_err!("cannot convert Parquet decimal with negative scale")
+    })?;
+    let digits = value.unsigned_abs().to_string();
+    let digits = if digits.len() <= scale {
+        format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+    } else {
+        digits
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    if scale == 0 {
+        Ok(format!("{}{}", sign, digits))
+    } else {
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        Ok(format!("{}{}.{}", sign, int_part, frac_part))
+    }
+}
+
+#[test]
+fn converts_decimals() {
+    let examples = &[
+        (Decimal::from_i32(12345, 9, 2), "123.45"),
+        (Decimal::from_i32(-5, 9, 2), "-0.05"),
+        (Decimal::from_i64(42, 18, 0), "42"),
+        (Decimal::from_bytes(vec![0xff, 0x85].into(), 9, 1), "-12.3"),
+    ];
+    for (d, expected) in examples {
+        assert_eq!(decimal_to_string(d).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn formats_timestamps() {
+    assert_eq!(format_timestamp(0, true), "1970-01-01T00:00:00Z");
+    assert_eq!(
+        format_timestamp(1_500_000, false),
+        "1970-01-01T00:00:01.500"
+    );
+    assert_eq!(format_timestamp(-1, true), "1969-12-31T23:59:59.999999Z");
+}
+
+#[test]
+fn parquet_types_to_portable_types() {
+    assert_eq!(
+        data_type_from_parquet(PhysicalType::INT32, LogicalType::DATE).unwrap(),
+        DataType::Date,
+    );
+    assert_eq!(
+        data_type_from_parquet(PhysicalType::INT64, LogicalType::TIMESTAMP_MICROS)
+            .unwrap(),
+        DataType::TimestampWithTimeZone,
+    );
+    assert_eq!(
+        data_type_from_parquet(PhysicalType::BYTE_ARRAY, LogicalType::UTF8).unwrap(),
+        DataType::Text,
+    );
+    assert!(
+        data_type_from_parquet(PhysicalType::BYTE_ARRAY, LogicalType::NONE).is_err()
+    );
+}
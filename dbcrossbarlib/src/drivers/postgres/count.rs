@@ -2,7 +2,7 @@
 
 use super::PostgresLocator;
 use crate::common::*;
-use crate::drivers::postgres_shared::{connect, CheckCatalog, PgSchema};
+use crate::drivers::postgres_shared::{connect, CheckCatalog, PgSchema, PgSchemaExt};
 
 /// Implementation of `count`, but as a real `async` function.
 pub(crate) async fn count_helper(
@@ -21,12 +21,15 @@ pub(crate) async fn count_helper(
     // Look up the arguments we'll need.
     let schema = shared_args.schema();
 
+    // Connect to PostgreSQL.
+    let conn = connect(&ctx, &url).await?;
+
     // Convert our schema to a native PostgreSQL schema.
     let pg_schema = PgSchema::from_pg_catalog_or_default(
         &ctx,
         // No need to look at the catalog, since we don't care about columns.
         CheckCatalog::No,
-        &url,
+        &conn,
         &table_name,
         schema,
     )
@@ -39,12 +42,12 @@ pub(crate) async fn count_helper(
     debug!(ctx.log(), "count SQL: {}", sql);
 
     // Run our query.
-    let conn = connect(&ctx, &url).await?;
     let stmt = conn.prepare(&sql).await?;
     let rows = conn
         .query(&stmt, &[])
         .await
         .context("error running count query")?;
+    conn.release();
     if rows.len() != 1 {
         Err(format_err!(
             "expected 1 row of count output, got {}",
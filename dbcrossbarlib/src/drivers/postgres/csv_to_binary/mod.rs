@@ -93,12 +93,11 @@ pub(crate) fn copy_csv_to_pg_binary(
         // checks on the `row` and `columns` arrays.
         for (cell, col) in row.iter().zip(table.columns.iter()) {
             cell_to_binary(&mut wtr, col, cell).with_context(|_| {
-                format!(
-                    "could not convert row {}, column {} ({:?})",
-                    row_idx + 1, // Add 1 for header row.
-                    col.name,
-                    cell,
-                )
+                ErrorKind::InvalidData {
+                    row: row_idx + 1,
+                    column: col.name.clone(),
+                    value: cell.to_owned(),
+                }
             })?;
         }
     }
@@ -338,6 +337,27 @@ fn parse_ewkb_fallback() {
         .unwrap();
 }
 
+#[test]
+fn invalid_data_reports_row_without_header() {
+    let schema = PgSchema::parse(
+        "test.sql".to_owned(),
+        "CREATE TABLE example (id bigint);".to_owned(),
+    )
+    .unwrap();
+    let csv = "id\n1\nnope\n";
+    let err =
+        copy_csv_to_pg_binary(&schema, Box::new(csv.as_bytes()), Box::new(vec![]))
+            .unwrap_err();
+    assert_eq!(
+        ErrorKind::of(&err),
+        Some(&ErrorKind::InvalidData {
+            row: 2,
+            column: "id".to_owned(),
+            value: "nope".to_owned(),
+        }),
+    );
+}
+
 /// Parse a CSV cell and write it out as a PostgreSQL binary value. This works
 /// for any type implementing `FromCsvCell` and `WriteBinary`. More complicated
 /// cases will need to do this manually.
@@ -365,7 +385,7 @@ pub(crate) trait WriteExt {
         F: FnOnce(&mut Vec<u8>) -> Result<()>;
 }
 
-impl<'a, W: Write + 'a> WriteExt for W {
+impl<W: Write> WriteExt for W {
     fn write_len(&mut self, len: usize) -> Result<()> {
         self.write_i32::<NE>(i32::try_from(len)?)?;
         Ok(())
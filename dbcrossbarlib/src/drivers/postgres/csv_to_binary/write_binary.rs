@@ -124,7 +124,7 @@ impl<'a> WriteBinary for RawJsonb<'a> {
     }
 }
 
-impl<'a> WriteBinary for &'a str {
+impl WriteBinary for &str {
     fn write_binary<W: Write>(&self, wtr: &mut W) -> Result<()> {
         wtr.write_len(self.len())?;
         wtr.write_all(self.as_bytes())?;
@@ -133,7 +133,7 @@ impl<'a> WriteBinary for &'a str {
 }
 
 /// Fallback for just writing binary data straight through to PostgreSQL.
-impl<'a> WriteBinary for &'a [u8] {
+impl WriteBinary for &[u8] {
     fn write_binary<W: Write>(&self, wtr: &mut W) -> Result<()> {
         wtr.write_len(self.len())?;
         wtr.write_all(self)?;
@@ -141,7 +141,7 @@ impl<'a> WriteBinary for &'a [u8] {
     }
 }
 
-impl<'a> WriteBinary for NaiveDateTime {
+impl WriteBinary for NaiveDateTime {
     fn write_binary<W: Write>(&self, wtr: &mut W) -> Result<()> {
         let epoch = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
         let duration = *self - epoch;
@@ -154,7 +154,7 @@ impl<'a> WriteBinary for NaiveDateTime {
     }
 }
 
-impl<'a> WriteBinary for DateTime<Utc> {
+impl WriteBinary for DateTime<Utc> {
     fn write_binary<W: Write>(&self, wtr: &mut W) -> Result<()> {
         let epoch = Utc.ymd(2000, 1, 1).and_hms(0, 0, 0);
         let duration = *self - epoch;
@@ -5,7 +5,9 @@ use failure::Fail;
 
 use super::PostgresLocator;
 use crate::common::*;
-use crate::drivers::postgres_shared::{connect, CheckCatalog, PgName, PgSchema};
+use crate::drivers::postgres_shared::{
+    connect, CheckCatalog, PgName, PgSchema, PgSchemaExt,
+};
 
 /// Copy the specified table from the database, returning a `CsvStream`.
 pub(crate) async fn local_data_helper(
@@ -32,11 +34,21 @@ pub(crate) async fn local_data_helper(
         table_name.quoted()
     );
 
-    // Try to look up our table schema in the database.
+    // Connect to PostgreSQL. We use this connection for both introspection
+    // and copying.
+    let conn = connect(&ctx, &url).await?;
+
+    // Try to look up our table schema in the database. If we're reading from
+    // `--from-query`, there's no table to look up.
+    let check_catalog = if source_args.query().is_some() {
+        CheckCatalog::No
+    } else {
+        CheckCatalog::Yes
+    };
     let pg_schema = PgSchema::from_pg_catalog_or_default(
         &ctx,
-        CheckCatalog::Yes,
-        &url,
+        check_catalog,
+        &conn,
         &table_name,
         schema,
     )
@@ -49,7 +61,6 @@ pub(crate) async fn local_data_helper(
     debug!(ctx.log(), "export SQL: {}", sql);
 
     // Copy the data out of PostgreSQL as a CSV stream.
-    let conn = connect(&ctx, &url).await?;
     let stmt = conn.prepare(&sql).await?;
     let rdr = conn
         .copy_out(&stmt)
@@ -60,6 +71,10 @@ pub(crate) async fn local_data_helper(
         })?
         // Convert data representation to match `dbcrossbar` conventions.
         .map_ok(move |bytes: Bytes| -> BytesMut {
+            // Keep `conn` open until we're done reading. We never release it
+            // back into our pool, because we can't tell whether our `COPY`
+            // ran to completion, and so it gets closed when we're dropped.
+            let _conn = &conn;
             trace!(ctx.log(), "read {} bytes", bytes.len());
             bytes.as_ref().into()
         })
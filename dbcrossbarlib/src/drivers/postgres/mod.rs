@@ -3,25 +3,34 @@
 // See https://github.com/diesel-rs/diesel/issues/1785
 #![allow(missing_docs, proc_macro_derive_resolution_fallback)]
 
+use serde::Deserialize;
 use std::{
     fmt,
     str::{self, FromStr},
 };
 
 use crate::common::*;
-use crate::drivers::postgres_shared::{Client, PgName, PgSchema};
+use crate::driver_args::deserialize_bool_arg;
+use crate::drivers::postgres_shared::{
+    connect, fetch_matching_table_names, Client, PgCreateTable, PgName, PgSchema,
+    PgSchemaExt,
+};
 
 mod count;
 mod csv_to_binary;
 mod local_data;
 mod write_local_data;
+mod write_schema;
 
 use self::count::count_helper;
 use self::local_data::local_data_helper;
-use self::write_local_data::write_local_data_helper;
+use self::write_local_data::{write_dry_run_helper, write_local_data_helper};
+use self::write_schema::{write_schema_dry_run_helper, write_schema_helper};
 
+pub(crate) use csv_to_binary::copy_csv_to_pg_binary;
 pub(crate) use write_local_data::{
-    columns_to_update_for_upsert, create_temp_table_for, prepare_table,
+    columns_to_update_for_upsert, copy_from_stream, create_temp_table_for,
+    prepare_table,
 };
 
 /// A Postgres database URL and a table name.
@@ -112,15 +121,96 @@ impl Locator for PostgresLocator {
     fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
         let source = self.to_owned();
         async move {
+            let client = connect(&ctx, &source.url).await?;
+            let schema =
+                PgSchema::from_pg_catalog(&ctx, &client, &source.table_name).await?;
+            client.release();
             let schema =
-                PgSchema::from_pg_catalog(&ctx, &source.url, &source.table_name)
-                    .await?
-                    .ok_or_else(|| format_err!("no such table {}", source))?;
+                schema.ok_or_else(|| format_err!("no such table {}", source))?;
             Ok(Some(schema.to_schema()?))
         }
         .boxed()
     }
 
+    fn existing_schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        let dest = self.to_owned();
+        async move {
+            let client = connect(&ctx, &dest.url).await?;
+            let schema =
+                PgSchema::from_pg_catalog(&ctx, &client, &dest.table_name).await?;
+            client.release();
+            match schema {
+                Some(schema) => Ok(Some(schema.to_schema()?)),
+                None => Ok(None),
+            }
+        }
+        .boxed()
+    }
+
+    fn matching_tables(
+        &self,
+        ctx: Context,
+    ) -> BoxFuture<Option<Vec<(String, BoxLocator)>>> {
+        let source = self.to_owned();
+        async move {
+            if !source.table_name.name().contains('*') {
+                return Ok(None);
+            }
+            let client = connect(&ctx, &source.url).await?;
+            let names =
+                fetch_matching_table_names(&ctx, &client, &source.table_name).await?;
+            client.release();
+            let schema = source.table_name.schema().map(|s| s.to_owned());
+            let tables = names
+                .into_iter()
+                .map(|name| {
+                    let locator = PostgresLocator {
+                        url: source.url.clone(),
+                        table_name: PgName::new(schema.clone(), name.clone()),
+                    };
+                    (name, locator.boxed())
+                })
+                .collect();
+            Ok(Some(tables))
+        }
+        .boxed()
+    }
+
+    fn query_schema(&self, ctx: Context, query: String) -> BoxFuture<Option<Schema>> {
+        let source = self.to_owned();
+        async move {
+            let client = connect(&ctx, &source.url).await?;
+            let schema =
+                PgSchema::from_query(&ctx, &client, &source.table_name, &query)
+                    .await?;
+            client.release();
+            Ok(Some(schema.to_schema()?))
+        }
+        .boxed()
+    }
+
+    fn supports_sampling(&self) -> bool {
+        true
+    }
+
+    fn write_schema(
+        &self,
+        ctx: Context,
+        schema: Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<()> {
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
+    }
+
+    fn write_schema_dry_run(
+        &self,
+        _ctx: &Context,
+        schema: &Schema,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_schema_dry_run_helper(self, schema, dest_args).map(Some)
+    }
+
     fn count(
         &self,
         ctx: Context,
@@ -156,6 +246,16 @@ impl Locator for PostgresLocator {
         write_local_data_helper(ctx, self.clone(), data, shared_args, dest_args)
             .boxed()
     }
+
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        _source: &dyn Locator,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_dry_run_helper(self, shared_args, dest_args).map(Some)
+    }
 }
 
 impl LocatorStatic for PostgresLocator {
@@ -166,12 +266,19 @@ impl LocatorStatic for PostgresLocator {
     fn features() -> Features {
         Features {
             locator: LocatorFeatures::Schema
+                | LocatorFeatures::WriteSchema
                 | LocatorFeatures::LocalData
                 | LocatorFeatures::WriteLocalData
                 | LocatorFeatures::Count,
-            write_schema_if_exists: EnumSet::empty(),
-            source_args: SourceArgumentsFeatures::WhereClause.into(),
-            dest_args: EnumSet::empty(),
+            write_schema_if_exists: IfExistsFeatures::Error
+                | IfExistsFeatures::Append
+                | IfExistsFeatures::Overwrite,
+            write_schema_args: EnumSet::empty(),
+            source_args: SourceArgumentsFeatures::WhereClause
+                | SourceArgumentsFeatures::Query,
+            dest_args: DestinationArgumentsFeatures::DriverArgs
+                | DestinationArgumentsFeatures::EvolveSchema
+                | DestinationArgumentsFeatures::CreateMissing,
             dest_if_exists: IfExistsFeatures::Overwrite
                 | IfExistsFeatures::Append
                 | IfExistsFeatures::Error
@@ -180,3 +287,57 @@ impl LocatorStatic for PostgresLocator {
         }
     }
 }
+
+/// Arguments passed to the Postgres driver.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PostgresDriverArguments {
+    /// The tablespace in which to create new tables.
+    tablespace: Option<String>,
+    /// Create new tables as `UNLOGGED`.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    unlogged: bool,
+}
+
+impl PostgresDriverArguments {
+    /// Apply these options to `table`, which we may be about to create.
+    pub(crate) fn apply_to(&self, table: &mut PgCreateTable) {
+        table.unlogged = self.unlogged;
+        table.tablespace = self.tablespace.clone();
+    }
+}
+
+#[test]
+fn parse_table_option_args() {
+    let table_sql = |args: &[&str]| {
+        let to_args = DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<PostgresDriverArguments>()
+            .unwrap();
+        let mut table = PgCreateTable {
+            name: PgName::new(None, "t"),
+            columns: vec![],
+            if_not_exists: false,
+            temporary: false,
+            unlogged: false,
+            tablespace: None,
+            distributed_by: None,
+            redshift_attributes: None,
+        };
+        to_args.apply_to(&mut table);
+        table.to_string()
+    };
+    assert_eq!(table_sql(&[]), "CREATE TABLE \"t\" (\n);\n");
+    assert_eq!(
+        table_sql(&["unlogged=true", "tablespace=fast"]),
+        "CREATE UNLOGGED TABLE \"t\" (\n) TABLESPACE \"fast\";\n",
+    );
+    assert!(DriverArguments::from_cli_args(&["unlogged=maybe"])
+        .unwrap()
+        .deserialize::<PostgresDriverArguments>()
+        .is_err());
+    assert!(DriverArguments::from_cli_args(&["tablespaces=fast"])
+        .unwrap()
+        .deserialize::<PostgresDriverArguments>()
+        .is_err());
+}
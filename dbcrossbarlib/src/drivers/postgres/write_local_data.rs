@@ -4,13 +4,18 @@ use futures::pin_mut;
 use itertools::Itertools;
 use std::{collections::HashSet, io::prelude::*, str};
 
-use super::{csv_to_binary::copy_csv_to_pg_binary, Client, PostgresLocator};
+use super::{
+    csv_to_binary::copy_csv_to_pg_binary, Client, PostgresDriverArguments,
+    PostgresLocator,
+};
 use crate::drivers::postgres_shared::{
-    connect, CheckCatalog, Ident, PgCreateTable, PgSchema,
+    connect, write_pg_alter_table_sql, CheckCatalog, Ident, PgCreateTable, PgName,
+    PgNameExt, PgSchema, PgSchemaExt,
 };
+use crate::schema_diff::SchemaDiff;
 use crate::tokio_glue::try_forward;
 use crate::transform::spawn_sync_transform;
-use crate::{common::*, drivers::postgres_shared::PgCreateType};
+use crate::{common::*, drivers::postgres_shared::fetch_create_type};
 
 /// If `table_name` exists, `DROP` it.
 async fn drop_table_if_exists(
@@ -31,6 +36,38 @@ async fn drop_table_if_exists(
     Ok(())
 }
 
+/// If `table_name` is in a PostgreSQL schema which doesn't exist yet, create
+/// it.
+///
+/// We check the catalog first, because `CREATE SCHEMA IF NOT EXISTS` requires
+/// `CREATE` permission on the database even when the schema already exists.
+pub(crate) async fn create_schema_if_missing(
+    ctx: &Context,
+    client: &mut Client,
+    table_name: &PgName,
+) -> Result<()> {
+    let schema = match table_name.schema() {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+    let exists_sql = "SELECT 1 FROM pg_catalog.pg_namespace WHERE nspname = $1";
+    let rows = client.query(exists_sql, &[&schema]).await?;
+    if rows.is_empty() {
+        let create_sql = create_schema_sql(schema);
+        debug!(ctx.log(), "creating schema: {}", create_sql);
+        client
+            .execute(&create_sql[..], &[])
+            .await
+            .with_context(|_| format!("error creating schema {}", Ident(schema)))?;
+    }
+    Ok(())
+}
+
+/// Generate SQL to create the PostgreSQL schema `schema`.
+fn create_schema_sql(schema: &str) -> String {
+    format!("CREATE SCHEMA IF NOT EXISTS {}", Ident(schema))
+}
+
 /// Create any types that we will need.
 async fn prepare_types(
     ctx: &Context,
@@ -40,7 +77,7 @@ async fn prepare_types(
     let needed_types = schema.table()?.named_type_names();
     for ty in &schema.types {
         if needed_types.contains(&ty.name) {
-            let existing = PgCreateType::from_database(ctx, client, &ty.name).await?;
+            let existing = fetch_create_type(ctx, client, &ty.name).await?;
             match existing {
                 None => {
                     // The type doesn't exist, so create it.
@@ -98,6 +135,8 @@ pub(crate) async fn create_temp_table_for(
     temp_table.name = temp_name;
     temp_table.if_not_exists = false;
     temp_table.temporary = true;
+    temp_table.unlogged = false;
+    temp_table.tablespace = None;
     let temp_schema = PgSchema {
         tables: vec![temp_table],
         ..schema.to_owned()
@@ -106,6 +145,46 @@ pub(crate) async fn create_temp_table_for(
     Ok(temp_schema.table()?.to_owned())
 }
 
+/// If `table_name` already exists, add any columns from `schema` which it is
+/// missing. We always add new columns as nullable, because the rows already in
+/// the table won't have values for them.
+async fn add_missing_columns(
+    ctx: &Context,
+    client: &mut Client,
+    table_name: &PgName,
+    schema: &Schema,
+) -> Result<()> {
+    // If the table doesn't exist yet, `prepare_table` will create it.
+    let existing = match PgSchema::from_pg_catalog(ctx, client, table_name).await? {
+        Some(existing) => existing,
+        None => return Ok(()),
+    };
+    let diff =
+        SchemaDiff::compare(&existing.to_schema()?, schema).added_columns_only();
+    if diff.is_empty() {
+        return Ok(());
+    }
+
+    // We may need to create types for our new columns.
+    let new_schema = PgSchema::from_schema_and_name(schema, table_name)?;
+    prepare_types(ctx, client, &new_schema).await?;
+
+    let mut alter_sql_buff = vec![];
+    write_pg_alter_table_sql(table_name, &diff, &mut alter_sql_buff)?;
+    let alter_sql = String::from_utf8(alter_sql_buff)
+        .expect("generated SQL should always be UTF-8");
+    info!(
+        ctx.log(),
+        "adding missing columns to {}: {}",
+        table_name.quoted(),
+        alter_sql.trim(),
+    );
+    client.batch_execute(&alter_sql).await.with_context(|_| {
+        format!("error adding missing columns to {}", table_name.quoted())
+    })?;
+    Ok(())
+}
+
 /// Run `DROP TABLE` and/or `CREATE TABLE` as needed to prepare `table` for
 /// copying in data.
 ///
@@ -167,17 +246,17 @@ fn copy_from_sql(table: &PgCreateTable, data_format: &str) -> Result<String> {
     Ok(copy_sql)
 }
 
-/// Given `stream` containing CSV data, plus a the URL and table_name for a
-/// destination, as well as `"COPY FROM"` SQL, copy the data into the specified
-/// destination.
-async fn copy_from_stream<'a>(
+/// Given `stream` containing data in `data_format` (see `copy_from_sql`), copy
+/// the data into `dest`.
+pub(crate) async fn copy_from_stream<'a>(
     ctx: &'a Context,
     client: &'a mut Client,
     dest: &'a PgCreateTable,
+    data_format: &'a str,
     stream: BoxStream<BytesMut>,
 ) -> Result<()> {
     debug!(ctx.log(), "copying data into {:?}", dest.name);
-    let copy_from_sql = copy_from_sql(&dest, "BINARY")?;
+    let copy_from_sql = copy_from_sql(dest, data_format)?;
     let stmt = client.prepare(&copy_from_sql).await?;
     let sink = client
         .copy_in::<_, BytesMut>(&stmt)
@@ -249,7 +328,7 @@ DO UPDATE SET
         key_columns = upsert_keys.iter().map(|k| Ident(k)).join(", "),
         value_updates = value_keys
             .iter()
-            .map(|vk| format!("{name} = EXCLUDED.{name}", name = Ident(&vk)))
+            .map(|vk| format!("{name} = EXCLUDED.{name}", name = Ident(vk)))
             .join(",\n    "),
     ))
 }
@@ -281,6 +360,87 @@ pub(crate) async fn upsert_from(
     Ok(())
 }
 
+/// Describe the SQL that `write_local_data_helper` would run, without
+/// connecting to the database.
+///
+/// Since we don't look at `pg_catalog`, this always uses the portable schema,
+/// even when `--if-exists=append` would normally use an existing table.
+pub(crate) fn write_dry_run_helper(
+    dest: &PostgresLocator,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let shared_args = shared_args.verify(PostgresLocator::features())?;
+    let dest_args = dest_args.verify(PostgresLocator::features())?;
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<PostgresDriverArguments>()
+        .context("could not parse --to-arg")?;
+    let if_exists = dest_args.if_exists();
+    let mut dest_schema =
+        PgSchema::from_schema_and_name(shared_args.schema(), dest.table_name())?;
+
+    let mut out = vec![];
+
+    // Create our PostgreSQL schema, like `create_schema_if_missing`.
+    if dest_args.create_missing() {
+        if let Some(schema) = dest.table_name().schema() {
+            writeln!(out, "-- If this schema does not already exist:")?;
+            writeln!(out, "{};", create_schema_sql(schema))?;
+        }
+    }
+
+    // Prepare our destination table, like `prepare_table`.
+    let table = dest_schema.table_mut()?;
+    to_args.apply_to(table);
+    match if_exists {
+        IfExists::Overwrite => {
+            writeln!(out, "DROP TABLE IF EXISTS {};", table.name.quoted())?;
+            table.if_not_exists = false;
+        }
+        IfExists::Append | IfExists::Upsert(_) => table.if_not_exists = true,
+        IfExists::Error => table.if_not_exists = false,
+    }
+    if dest_args.evolve_schema() {
+        writeln!(
+            out,
+            "-- If {} exists, ALTER TABLE ... ADD COLUMN for any missing columns",
+            table.name.quoted(),
+        )?;
+    }
+    let table = dest_schema.table()?;
+    let needed_types = table.named_type_names();
+    for ty in &dest_schema.types {
+        if needed_types.contains(&ty.name) {
+            writeln!(out, "-- If this type does not already exist:")?;
+            writeln!(out, "{}", ty)?;
+        }
+    }
+    write!(out, "{}", table)?;
+
+    // Copy in our data.
+    writeln!(out, "-- For each data stream:")?;
+    if let IfExists::Upsert(upsert_keys) = if_exists {
+        let mut temp_table = table.to_owned();
+        temp_table.name = table.name.temporary_table_name()?;
+        temp_table.if_not_exists = false;
+        temp_table.temporary = true;
+        temp_table.unlogged = false;
+        temp_table.tablespace = None;
+        write!(out, "{}", temp_table)?;
+        write!(out, "{}", copy_from_sql(&temp_table, "BINARY")?)?;
+        writeln!(
+            out,
+            "{}",
+            upsert_sql(&temp_table, table, upsert_keys)?.trim()
+        )?;
+    } else {
+        write!(out, "{}", copy_from_sql(table, "BINARY")?)?;
+    }
+
+    Ok(String::from_utf8(out).expect("generated SQL should always be UTF-8"))
+}
+
 /// The actual implementation of `write_local_data`, in a separate function so we
 /// can use `async`.
 pub(crate) async fn write_local_data_helper(
@@ -295,6 +455,10 @@ pub(crate) async fn write_local_data_helper(
 
     // Look up our arguments.
     let schema = shared_args.schema();
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<PostgresDriverArguments>()
+        .context("could not parse --to-arg")?;
     let if_exists = dest_args.if_exists().to_owned();
 
     let url = dest.url.clone();
@@ -307,18 +471,33 @@ pub(crate) async fn write_local_data_helper(
         table_name.quoted(),
     );
 
+    // Connect to PostgreSQL. We use this connection for introspection, for
+    // preparing our destination table, and for copying data.
+    let mut client = connect(&ctx, &url).await?;
+
+    // If we were asked to, create the PostgreSQL schema for our table.
+    if dest_args.create_missing() {
+        create_schema_if_missing(&ctx, &mut client, dest.table_name()).await?;
+    }
+
+    // If we were asked to, make sure the destination table has all our
+    // columns before we look it up.
+    if dest_args.evolve_schema() {
+        add_missing_columns(&ctx, &mut client, dest.table_name(), schema).await?;
+    }
+
     // Try to look up our destination table schema in the database.
-    let dest_schema = PgSchema::from_pg_catalog_or_default(
+    let mut dest_schema = PgSchema::from_pg_catalog_or_default(
         &ctx,
         CheckCatalog::from(&if_exists),
-        dest.url(),
+        &client,
         dest.table_name(),
         schema,
     )
     .await?;
+    to_args.apply_to(dest_schema.table_mut()?);
 
-    // Connect to PostgreSQL and prepare our destination table.
-    let mut client = connect(&ctx, &url).await?;
+    // Prepare our destination table.
     prepare_table(&ctx, &mut client, dest_schema.clone(), &if_exists).await?;
 
     // Insert data streams one at a time, because parallel insertion _probably_
@@ -356,6 +535,7 @@ pub(crate) async fn write_local_data_helper(
                             &ctx,
                             &mut client,
                             &temp_table,
+                            "BINARY",
                             binary_stream,
                         )
                         .await?;
@@ -366,7 +546,7 @@ pub(crate) async fn write_local_data_helper(
                             &mut client,
                             &temp_table,
                             dest_schema.table()?,
-                            &cols,
+                            cols,
                         )
                         .await?;
 
@@ -379,6 +559,7 @@ pub(crate) async fn write_local_data_helper(
                             &ctx,
                             &mut client,
                             dest_schema.table()?,
+                            "BINARY",
                             binary_stream,
                         )
                         .await?;
@@ -386,6 +567,7 @@ pub(crate) async fn write_local_data_helper(
                 }
             }
         }
+        client.release();
         Ok(dest.boxed())
     };
     Ok(box_stream_once(Ok(fut.boxed())))
@@ -0,0 +1,56 @@
+//! Support for creating PostgreSQL tables from a schema.
+
+use super::{prepare_table, PostgresLocator};
+use crate::common::*;
+use crate::drivers::postgres_shared::{connect, PgSchema};
+
+/// Implementation of `write_schema`, but as a real `async` function.
+pub(crate) async fn write_schema_helper(
+    ctx: Context,
+    dest: PostgresLocator,
+    schema: Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<()> {
+    let dest_args = dest_args.verify_for_write_schema(PostgresLocator::features())?;
+    let if_exists = dest_args.if_exists().to_owned();
+
+    let ctx = ctx.child(o!("table" => dest.table_name().unquoted()));
+    let pg_schema = PgSchema::from_schema_and_name(&schema, dest.table_name())?;
+    let mut client = connect(&ctx, dest.url()).await?;
+    prepare_table(&ctx, &mut client, pg_schema, &if_exists).await?;
+    client.release();
+    Ok(())
+}
+
+/// Describe the SQL that `write_schema_helper` would run, without connecting
+/// to the database.
+pub(crate) fn write_schema_dry_run_helper(
+    dest: &PostgresLocator,
+    schema: &Schema,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let dest_args = dest_args.verify_for_write_schema(PostgresLocator::features())?;
+    let mut pg_schema = PgSchema::from_schema_and_name(schema, dest.table_name())?;
+
+    // Prepare our destination table, like `prepare_table`.
+    let mut out = vec![];
+    let table = pg_schema.table_mut()?;
+    match dest_args.if_exists() {
+        IfExists::Overwrite => {
+            writeln!(out, "DROP TABLE IF EXISTS {};", table.name.quoted())?;
+            table.if_not_exists = false;
+        }
+        IfExists::Append | IfExists::Upsert(_) => table.if_not_exists = true,
+        IfExists::Error => table.if_not_exists = false,
+    }
+    let table = pg_schema.table()?;
+    let needed_types = table.named_type_names();
+    for ty in &pg_schema.types {
+        if needed_types.contains(&ty.name) {
+            writeln!(out, "-- If this type does not already exist:")?;
+            writeln!(out, "{}", ty)?;
+        }
+    }
+    write!(out, "{}", table)?;
+    Ok(String::from_utf8(out).expect("generated SQL should always be UTF-8"))
+}
@@ -0,0 +1,106 @@
+//! Generating PostgreSQL `ALTER TABLE` statements from a schema diff.
+
+use super::{Ident, PgColumn, PgName};
+use crate::common::*;
+use crate::schema_diff::{ColumnChange, SchemaDiff};
+
+/// Write out the `ALTER TABLE` statements needed to apply `diff` to
+/// `table_name`.
+pub(crate) fn write_pg_alter_table_sql(
+    table_name: &PgName,
+    diff: &SchemaDiff,
+    f: &mut dyn Write,
+) -> Result<()> {
+    let table_name = table_name.quoted();
+    for change in diff.changes() {
+        match change {
+            ColumnChange::Removed(col) => {
+                writeln!(
+                    f,
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    table_name,
+                    Ident(&col.name),
+                )?;
+            }
+            ColumnChange::Added(col) => {
+                let pg_col = PgColumn::from_column(&diff.new, col)?;
+                write!(
+                    f,
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table_name,
+                    Ident(&pg_col.name),
+                    pg_col.data_type,
+                )?;
+                if !pg_col.is_nullable {
+                    write!(f, " NOT NULL")?;
+                }
+                writeln!(f, ";")?;
+            }
+            ColumnChange::Changed { old, new } => {
+                let old_pg_col = PgColumn::from_column(&diff.old, old)?;
+                let pg_col = PgColumn::from_column(&diff.new, new)?;
+                let name = Ident(&pg_col.name);
+                if old_pg_col.data_type != pg_col.data_type {
+                    writeln!(
+                        f,
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};",
+                        table_name, name, pg_col.data_type, name, pg_col.data_type,
+                    )?;
+                }
+                if old.is_nullable && !new.is_nullable {
+                    writeln!(
+                        f,
+                        "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL;",
+                        table_name, name,
+                    )?;
+                } else if !old.is_nullable && new.is_nullable {
+                    writeln!(
+                        f,
+                        "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL;",
+                        table_name, name,
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn generates_alter_table_sql() {
+    use crate::schema::{Column, DataType};
+
+    let col = |name: &str, data_type: DataType, is_nullable: bool| Column {
+        name: name.to_owned(),
+        is_nullable,
+        data_type,
+        comment: None,
+    };
+    let old = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            col("id", DataType::Int32, true),
+            col("legacy", DataType::Bool, true),
+        ],
+    })
+    .unwrap();
+    let new = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            col("id", DataType::Int64, false),
+            col("email", DataType::Text, true),
+        ],
+    })
+    .unwrap();
+    let diff = SchemaDiff::compare(&old, &new);
+    let mut sql = vec![];
+    write_pg_alter_table_sql(&"example".parse().unwrap(), &diff, &mut sql).unwrap();
+    assert_eq!(
+        String::from_utf8(sql).unwrap(),
+        r#"ALTER TABLE "example" DROP COLUMN "legacy";
+ALTER TABLE "example" ALTER COLUMN "id" TYPE bigint USING "id"::bigint;
+ALTER TABLE "example" ALTER COLUMN "id" SET NOT NULL;
+ALTER TABLE "example" ADD COLUMN "email" text;
+"#,
+    );
+}
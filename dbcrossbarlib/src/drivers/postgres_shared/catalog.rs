@@ -7,11 +7,14 @@
 
 use std::collections::HashMap;
 
-use tokio_postgres::Client;
+use tokio_postgres::{
+    types::{Kind, Type},
+    Client,
+};
 
 use super::{
-    connect, PgColumn, PgCreateTable, PgCreateType, PgCreateTypeDefinition,
-    PgDataType, PgName, PgScalarDataType, PgSchema,
+    PgColumn, PgCreateTable, PgCreateType, PgCreateTypeDefinition, PgDataType, PgName,
+    PgScalarDataType, PgSchema,
 };
 use crate::common::*;
 use crate::schema::Srid;
@@ -60,19 +63,24 @@ struct PgColumnSchema {
 impl PgColumnSchema {
     /// Get the data type for a column.
     fn data_type(&self) -> Result<PgDataType> {
-        pg_data_type(&self.data_type, &self.udt_schema, &self.udt_name)
+        pg_data_type(
+            &self.column_name,
+            &self.data_type,
+            &self.udt_schema,
+            &self.udt_name,
+        )
     }
 }
 
-/// Fetch information about a table from the database.
+/// Fetch information about a table from the database, using an existing
+/// connection.
 ///
 /// Returns `None` if no matching table exists.
-pub(crate) async fn fetch_from_url(
+pub(crate) async fn fetch_from_client(
     ctx: &Context,
-    database_url: &UrlWithHiddenPassword,
+    client: &Client,
     table_name: &PgName,
 ) -> Result<Option<PgSchema>> {
-    let client = connect(ctx, database_url).await?;
     let schema = table_name.schema_or_public();
     let table = table_name.name();
 
@@ -84,6 +92,12 @@ WHERE
     table_schema = $1 AND
     table_name = $2
 "#;
+    debug!(
+        ctx.log(),
+        "checking for table {}: {}",
+        table_name.unquoted(),
+        count_matching_tables_sql,
+    );
     let row = client
         .query_one(count_matching_tables_sql, &[&schema, &table])
         .await?;
@@ -101,6 +115,7 @@ WHERE
     table_name = $2
 ORDER BY ordinal_position
 "#;
+    debug!(ctx.log(), "fetching columns: {}", columns_sql);
     let rows = client.query(columns_sql, &[&schema, &table]).await?;
     let pg_columns = rows
         .into_iter()
@@ -184,7 +199,7 @@ WHERE
     for col in &columns {
         if let PgDataType::Scalar(PgScalarDataType::Named(type_name)) = &col.data_type
         {
-            let pg_create_type = fetch_create_type(ctx, &client, type_name)
+            let pg_create_type = fetch_create_type(ctx, client, type_name)
                 .await?
                 .ok_or_else(|| {
                     format_err!(
@@ -201,6 +216,10 @@ WHERE
         columns,
         temporary: false,
         if_not_exists: false,
+        unlogged: false,
+        tablespace: None,
+        distributed_by: None,
+        redshift_attributes: None,
     };
     let pg_schema = PgSchema {
         types,
@@ -209,8 +228,182 @@ WHERE
     Ok(Some(pg_schema))
 }
 
+/// Fetch the names of all tables in the schema of `pattern` whose names match
+/// `pattern`, which may contain `*` wildcards.
+pub(crate) async fn fetch_matching_table_names(
+    ctx: &Context,
+    client: &Client,
+    pattern: &PgName,
+) -> Result<Vec<String>> {
+    let schema = pattern.schema_or_public();
+    let like_pattern = like_pattern_for_glob(pattern.name());
+    let matching_tables_sql = r#"
+SELECT table_name
+FROM information_schema.tables
+WHERE
+    table_schema = $1 AND
+    table_name LIKE $2
+ORDER BY table_name
+"#;
+    debug!(
+        ctx.log(),
+        "listing tables matching {}: {}",
+        pattern.unquoted(),
+        matching_tables_sql,
+    );
+    let rows = client
+        .query(matching_tables_sql, &[&schema, &like_pattern])
+        .await
+        .with_context(|_| {
+            format!("could not list tables matching {}", pattern.unquoted())
+        })?;
+    Ok(rows.into_iter().map(|row| row.get("table_name")).collect())
+}
+
+/// Convert a table name containing `*` wildcards into an SQL `LIKE` pattern.
+fn like_pattern_for_glob(glob: &str) -> String {
+    let mut pattern = String::with_capacity(glob.len());
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push('%'),
+            '%' | '_' | '\\' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            _ => pattern.push(c),
+        }
+    }
+    pattern
+}
+
+#[test]
+fn like_pattern_for_glob_escapes_like_syntax() {
+    assert_eq!(like_pattern_for_glob("events_*"), "events\\_%");
+    assert_eq!(like_pattern_for_glob("*"), "%");
+    assert_eq!(like_pattern_for_glob("a%b\\c*d"), "a\\%b\\\\c%d");
+}
+
+/// Describe the rows returned by `query`, without actually running it, and
+/// return a schema using `table_name`.
+///
+/// PostgreSQL doesn't tell us whether query results may be `NULL`, so we assume
+/// that every column is nullable.
+pub(crate) async fn fetch_from_query(
+    ctx: &Context,
+    client: &Client,
+    table_name: &PgName,
+    query: &str,
+) -> Result<PgSchema> {
+    debug!(ctx.log(), "describing query: {}", query);
+    let stmt = client
+        .prepare(query)
+        .await
+        .context("error describing --from-query")?;
+
+    let mut columns = Vec::with_capacity(stmt.columns().len());
+    let mut types: Vec<PgCreateType> = vec![];
+    for col in stmt.columns() {
+        let data_type = pg_data_type_for_type(col.name(), col.type_())?;
+        if let PgDataType::Scalar(PgScalarDataType::Named(type_name)) = &data_type {
+            if !types.iter().any(|ty| &ty.name == type_name) {
+                let pg_create_type = fetch_create_type(ctx, client, type_name)
+                    .await?
+                    .ok_or_else(|| {
+                        format_err!(
+                            "cannot find definiton of user-defined type {} (perhaps it isn't supported?)",
+                            type_name.unquoted(),)
+                    })?;
+                types.push(pg_create_type);
+            }
+        }
+        columns.push(PgColumn {
+            name: col.name().to_owned(),
+            data_type,
+            is_nullable: true,
+        });
+    }
+
+    let pg_create_table = PgCreateTable {
+        name: table_name.to_owned(),
+        columns,
+        temporary: false,
+        if_not_exists: false,
+        unlogged: false,
+        tablespace: None,
+        distributed_by: None,
+        redshift_attributes: None,
+    };
+    Ok(PgSchema {
+        types,
+        tables: vec![pg_create_table],
+    })
+}
+
+/// Choose an appropriate `DataType` for a column in the results of a query.
+/// This uses PostgreSQL's internal type names, which are slightly different
+/// from the ones we see in `information_schema`.
+fn pg_data_type_for_type(column_name: &str, ty: &Type) -> Result<PgDataType> {
+    match ty.kind() {
+        Kind::Array(_) => pg_data_type(column_name, "ARRAY", ty.schema(), ty.name()),
+        Kind::Enum(_) => Ok(PgDataType::Scalar(PgScalarDataType::Named(PgName::new(
+            ty.schema().to_owned(),
+            ty.name().to_owned(),
+        )))),
+        _ => {
+            let scalar = match ty.name() {
+                "bool" => PgScalarDataType::Boolean,
+                "bpchar" | "citext" | "text" | "varchar" => PgScalarDataType::Text,
+                "date" => PgScalarDataType::Date,
+                "float4" => PgScalarDataType::Real,
+                "float8" => PgScalarDataType::DoublePrecision,
+                "int2" => PgScalarDataType::Smallint,
+                "int4" => PgScalarDataType::Int,
+                "int8" => PgScalarDataType::Bigint,
+                "json" => PgScalarDataType::Json,
+                "jsonb" => PgScalarDataType::Jsonb,
+                "numeric" => PgScalarDataType::Numeric,
+                "timestamp" => PgScalarDataType::TimestampWithoutTimeZone,
+                "timestamptz" => PgScalarDataType::TimestampWithTimeZone,
+                "uuid" => PgScalarDataType::Uuid,
+                other => {
+                    return Err(ErrorKind::UnsupportedType {
+                        column: column_name.to_owned(),
+                        type_name: other.to_owned(),
+                    }
+                    .into())
+                }
+            };
+            Ok(PgDataType::Scalar(scalar))
+        }
+    }
+}
+
+#[test]
+fn parsing_pg_data_type_for_type() {
+    let examples = &[
+        (Type::INT8, PgDataType::Scalar(PgScalarDataType::Bigint)),
+        (Type::VARCHAR, PgDataType::Scalar(PgScalarDataType::Text)),
+        (
+            Type::TIMESTAMPTZ,
+            PgDataType::Scalar(PgScalarDataType::TimestampWithTimeZone),
+        ),
+        (
+            Type::INT4_ARRAY,
+            PgDataType::Array {
+                dimension_count: 1,
+                ty: PgScalarDataType::Int,
+            },
+        ),
+    ];
+    for (ty, expected) in examples {
+        assert_eq!(&pg_data_type_for_type("col", ty).unwrap(), expected);
+    }
+    assert!(pg_data_type_for_type("col", &Type::XML).is_err());
+}
+
 /// Choose an appropriate `DataType`.
 fn pg_data_type(
+    column_name: &str,
     data_type: &str,
     udt_schema: &str,
     udt_name: &str,
@@ -231,7 +424,13 @@ fn pg_data_type(
             "_timestamp" => PgScalarDataType::TimestampWithoutTimeZone,
             "_timestamptz" => PgScalarDataType::TimestampWithTimeZone,
             "_uuid" => PgScalarDataType::Uuid,
-            _ => return Err(format_err!("unknown array element {:?}", udt_name)),
+            _ => {
+                return Err(ErrorKind::UnsupportedType {
+                    column: column_name.to_owned(),
+                    type_name: udt_name.to_owned(),
+                }
+                .into())
+            }
         };
         Ok(PgDataType::Array {
             // TODO: Do we actually check the `dimension_count`?
@@ -272,7 +471,10 @@ fn pg_data_type(
                 Ok(PgScalarDataType::TimestampWithoutTimeZone)
             }
             "uuid" => Ok(PgScalarDataType::Uuid),
-            other => Err(format_err!("unknown data type {:?}", other)),
+            other => Err(ErrorKind::UnsupportedType {
+                column: column_name.to_owned(),
+                type_name: other.to_owned(),
+            }),
         }?;
         Ok(PgDataType::Scalar(ty))
     }
@@ -382,12 +584,26 @@ fn parsing_pg_data_type() {
     ];
     for ((data_type, udt_schema, udt_name), expected) in examples {
         assert_eq!(
-            &pg_data_type(data_type, udt_schema, udt_name).unwrap(),
+            &pg_data_type("example", data_type, udt_schema, udt_name).unwrap(),
             expected,
         );
     }
 }
 
+#[test]
+fn unknown_pg_data_types_are_reported() {
+    for &(data_type, udt_name) in &[("ARRAY", "_xml"), ("xml", "xml")] {
+        let err = pg_data_type("doc", data_type, "pg_catalog", udt_name).unwrap_err();
+        assert_eq!(
+            ErrorKind::of(&err),
+            Some(&ErrorKind::UnsupportedType {
+                column: "doc".to_owned(),
+                type_name: udt_name.to_owned(),
+            }),
+        );
+    }
+}
+
 /// Look up `type_name`.
 ///
 /// - If it is not defined, return `None`.
@@ -433,15 +649,25 @@ WHERE n.nspname = $1 AND t.typname = $2 AND t.typtype = 'e'
         ));
     }
 
-    // We know we have an `enum`, so fetch the values.
-    let enum_values_sql = "\
+    // We know we have an `enum`, so fetch the values. PostgreSQL 9.0 and
+    // earlier (including the older releases of Greenplum) don't have
+    // `enumsortorder`, but enum values there are always sorted by `oid`.
+    let sort_column = if server_version_num(client).await? >= 90100 {
+        "e.enumsortorder"
+    } else {
+        "e.oid"
+    };
+    let enum_values_sql = format!(
+        "\
 SELECT e.enumlabel AS value
 FROM pg_type t
     JOIN pg_enum e ON t.oid = e.enumtypid
     JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace
 WHERE n.nspname = $1 AND t.typname = $2
-ORDER BY e.enumsortorder
-";
+ORDER BY {}
+",
+        sort_column,
+    );
     trace!(
         ctx.log(),
         "fetching enum values {}: {}",
@@ -449,7 +675,7 @@ ORDER BY e.enumsortorder
         enum_values_sql
     );
     let enum_values = client
-        .query(enum_values_sql, &[&schema, &base_name])
+        .query(enum_values_sql.as_str(), &[&schema, &base_name])
         .await?
         .into_iter()
         .map(|r| r.get::<_, String>(0))
@@ -461,3 +687,15 @@ ORDER BY e.enumsortorder
     trace!(ctx.log(), "looked up type definition {:?}", pg_create_type);
     Ok(Some(pg_create_type))
 }
+
+/// Get the numeric server version, like `90100` for PostgreSQL 9.1.
+async fn server_version_num(client: &Client) -> Result<i32> {
+    let row = client
+        .query_one("SELECT current_setting('server_version_num')", &[])
+        .await?;
+    let version = row.get::<_, &str>(0);
+    let version_num = version
+        .parse::<i32>()
+        .with_context(|_| format!("cannot parse server_version_num {:?}", version))?;
+    Ok(version_num)
+}
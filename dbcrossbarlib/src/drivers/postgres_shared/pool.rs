@@ -0,0 +1,152 @@
+//! Reusing PostgreSQL connections.
+//!
+//! A single `dbcrossbar cp` may talk to the same database several times: to
+//! look up a schema, to count rows, to create a table and to copy data. On
+//! databases behind slow authentication proxies, opening a new connection for
+//! each of these can take longer than the actual work. So we keep idle
+//! connections in a `ConnectionPool` shared by a `Context` and its children,
+//! and hand them out again to later operations on the same database.
+//!
+//! A connection is only returned to the pool when its owner calls
+//! `PooledClient::release`, which it should do once it has finished its work
+//! successfully. If a `PooledClient` is simply dropped, we may be in the middle
+//! of a transaction or a `COPY`, so we close the connection instead.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+use tokio_postgres::Client;
+
+/// The maximum number of idle connections we keep for each database.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// Idle PostgreSQL connections, indexed by connection URL.
+#[derive(Default)]
+pub(crate) struct ConnectionPool {
+    /// Idle connections for each database URL, including any password.
+    idle: Mutex<HashMap<String, Vec<Client>>>,
+}
+
+impl ConnectionPool {
+    /// Take an idle connection to `key`, if we have one which is still open.
+    pub(crate) fn take(self: &Arc<Self>, key: &str) -> Option<PooledClient> {
+        let mut idle = self.idle.lock().expect("lock poisoned");
+        let clients = idle.get_mut(key)?;
+        while let Some(client) = clients.pop() {
+            if !client.is_closed() {
+                return Some(self.wrap(key.to_owned(), client));
+            }
+        }
+        None
+    }
+
+    /// Wrap `client` so that it can be returned to this pool using
+    /// `PooledClient::release`.
+    pub(crate) fn wrap(self: &Arc<Self>, key: String, client: Client) -> PooledClient {
+        PooledClient {
+            client,
+            key,
+            pool: self.clone(),
+        }
+    }
+
+    /// Return `client` to the pool, unless it has been closed or we already
+    /// have enough idle connections.
+    fn put(&self, key: String, client: Client) {
+        if client.is_closed() {
+            return;
+        }
+        let mut idle = self.idle.lock().expect("lock poisoned");
+        let clients = idle.entry(key).or_default();
+        if clients.len() < MAX_IDLE_CONNECTIONS {
+            clients.push(client);
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't print our keys, because they may contain passwords.
+        let idle = self.idle.lock().expect("lock poisoned");
+        f.debug_struct("ConnectionPool")
+            .field("idle", &idle.values().map(Vec::len).sum::<usize>())
+            .finish()
+    }
+}
+
+/// A PostgreSQL connection which can be returned to its `ConnectionPool` by
+/// calling `release`. This can be used anywhere a `&Client` or `&mut Client`
+/// is expected.
+pub(crate) struct PooledClient {
+    /// Our client.
+    client: Client,
+    /// The key we use to return our client to `pool`.
+    key: String,
+    /// The pool which owns idle connections.
+    pool: Arc<ConnectionPool>,
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+}
+
+impl PooledClient {
+    /// Return this connection to our pool so that it can be reused. Only call
+    /// this when the connection is idle, with no open transaction or `COPY`.
+    pub(crate) fn release(self) {
+        self.pool.put(self.key, self.client);
+    }
+}
+
+#[test]
+#[ignore]
+fn only_released_clients_are_reused() {
+    use std::env;
+
+    use super::connect;
+    use crate::common::*;
+
+    let (ctx, worker_fut) =
+        Context::create_for_test("only_released_clients_are_reused");
+    let url = env::var("POSTGRES_TEST_URL").unwrap_or_else(|_| {
+        "postgres://postgres:@localhost:5432/dbcrossbar_test".to_owned()
+    });
+    let url = UrlWithHiddenPassword::new(url.parse::<Url>().unwrap());
+
+    let cmd_fut = async move {
+        let pid_query = "SELECT pg_backend_pid() AS pid";
+
+        // Released clients are reused.
+        let client = connect(&ctx, &url).await?;
+        let pid: i32 = client.query_one(pid_query, &[]).await?.get("pid");
+        client.release();
+        let client = connect(&ctx, &url).await?;
+        let reused_pid: i32 = client.query_one(pid_query, &[]).await?.get("pid");
+        assert_eq!(pid, reused_pid);
+
+        // Clients dropped in the middle of a failed transaction are not.
+        client.batch_execute("BEGIN").await?;
+        assert!(client.batch_execute("SELECT 1 / 0").await.is_err());
+        drop(client);
+        let client = connect(&ctx, &url).await?;
+        let new_pid: i32 = client.query_one(pid_query, &[]).await?.get("pid");
+        assert_ne!(pid, new_pid);
+        client.batch_execute("SELECT 1").await?;
+        client.release();
+        Ok(())
+    };
+    run_futures_with_runtime(cmd_fut.boxed(), worker_fut).unwrap();
+}
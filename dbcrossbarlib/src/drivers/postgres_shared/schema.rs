@@ -0,0 +1,165 @@
+//! Looking up PostgreSQL schemas in a database.
+
+use async_trait::async_trait;
+
+use super::{catalog, Client, PgCreateTableExt, PgName, PgSchema};
+use crate::common::*;
+
+/// Should we check the PostgreSQL catalog for a schema, or just use the one we
+/// were given?
+///
+/// This is basically a fancy boolean that exists in order to make the related
+/// logic clear at a glance, and easy to verify.
+pub(crate) enum CheckCatalog {
+    /// Check the PostgreSQL catalog for an existing schema.
+    Yes,
+    /// Always use the schema given by the user.
+    No,
+}
+
+impl From<&IfExists> for CheckCatalog {
+    fn from(if_exists: &IfExists) -> CheckCatalog {
+        match if_exists {
+            IfExists::Error | IfExists::Overwrite => CheckCatalog::No,
+            IfExists::Append | IfExists::Upsert(_) => CheckCatalog::Yes,
+        }
+    }
+}
+
+/// Extensions to `PgSchema` which talk to a database or which depend on our
+/// `--from-arg` options.
+///
+/// We use the `async_trait` macro, which allows async functions to be declared
+/// inside a trait.
+#[async_trait]
+pub(crate) trait PgSchemaExt: Sized {
+    /// Look up `full_table_name` in the database, and return a new
+    /// `PgCreateTable` based on what we find in `pg_catalog`.
+    ///
+    /// This takes an existing `client`, so that callers can reuse a single
+    /// connection for introspection and for their actual work.
+    ///
+    /// Returns `None` if no matching table exists.
+    async fn from_pg_catalog(
+        ctx: &Context,
+        client: &Client,
+        table_name: &PgName,
+    ) -> Result<Option<Self>>;
+
+    /// Describe the rows returned by the SQL `query`, and return a new
+    /// `PgCreateTable` named `table_name` with the same columns.
+    async fn from_query(
+        ctx: &Context,
+        client: &Client,
+        table_name: &PgName,
+        query: &str,
+    ) -> Result<Self>;
+
+    /// Look up `full_table_name` in the database, and return a new
+    /// `PgCreateTable` based on what we find in `pg_catalog`.
+    ///
+    /// If this fails, use `full_table_name` and `default` to construct a new
+    /// table.
+    async fn from_pg_catalog_or_default(
+        ctx: &Context,
+        check_catalog: CheckCatalog,
+        client: &Client,
+        table_name: &PgName,
+        default: &Schema,
+    ) -> Result<Self>;
+
+    /// Write a `COPY (SELECT ...) TO STDOUT ...` statement for this schema's
+    /// table.
+    fn write_export_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
+
+    /// Write a `SELECT ...` statement for this schema's table.
+    fn write_export_select_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
+
+    /// Write a `SELECT COUNT(*) ...` statement for this schema's table.
+    fn write_count_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
+}
+
+#[async_trait]
+impl PgSchemaExt for PgSchema {
+    async fn from_pg_catalog(
+        ctx: &Context,
+        client: &Client,
+        table_name: &PgName,
+    ) -> Result<Option<Self>> {
+        catalog::fetch_from_client(ctx, client, table_name).await
+    }
+
+    async fn from_query(
+        ctx: &Context,
+        client: &Client,
+        table_name: &PgName,
+        query: &str,
+    ) -> Result<Self> {
+        catalog::fetch_from_query(ctx, client, table_name, query).await
+    }
+
+    async fn from_pg_catalog_or_default(
+        ctx: &Context,
+        check_catalog: CheckCatalog,
+        client: &Client,
+        table_name: &PgName,
+        default: &Schema,
+    ) -> Result<Self> {
+        // If we can't find a catalog in the database, use this one.
+        let default_dest_schema = Self::from_schema_and_name(default, table_name)?;
+
+        // Should we check the catalog to see if the table schema exists?
+        match check_catalog {
+            // Nope, we just want to use the default.
+            CheckCatalog::No => Ok(default_dest_schema),
+
+            // See if the table is listed in the catalog.
+            CheckCatalog::Yes => {
+                let opt_dest_schema =
+                    Self::from_pg_catalog(ctx, client, table_name).await?;
+                Ok(match opt_dest_schema {
+                    Some(dest_schema) => {
+                        dest_schema.aligned_with(&default_dest_schema)?
+                    }
+                    None => default_dest_schema,
+                })
+            }
+        }
+    }
+
+    fn write_export_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()> {
+        self.table()?.write_export_sql(f, source_args)
+    }
+
+    fn write_export_select_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()> {
+        self.table()?.write_export_select_sql(f, source_args)
+    }
+
+    fn write_count_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()> {
+        self.table()?.write_count_sql(f, source_args)
+    }
+}
@@ -1,152 +1,36 @@
-//! A PostgreSQL `CREATE TABLE` declaration.
+//! PostgreSQL SQL which depends on command-line arguments.
 
-use itertools::Itertools;
-use std::{
-    collections::{HashMap, HashSet},
-    fmt,
-};
-
-use super::{PgColumn, PgDataType, PgName, PgScalarDataType};
+use super::PgCreateTable;
 use crate::common::*;
-use crate::schema::Column;
 use crate::separator::Separator;
 
-/// Should we check the PostgreSQL catalog for a schema, or just use the one we
-/// were given?
-///
-/// This is basically a fancy boolean that exists in order to make the related
-/// logic clear at a glance, and easy to verify.
-pub(crate) enum CheckCatalog {
-    /// Check the PostgreSQL catalog for an existing schema.
-    Yes,
-    /// Always use the schema given by the user.
-    No,
-}
-
-impl From<&IfExists> for CheckCatalog {
-    fn from(if_exists: &IfExists) -> CheckCatalog {
-        match if_exists {
-            IfExists::Error | IfExists::Overwrite => CheckCatalog::No,
-            IfExists::Append | IfExists::Upsert(_) => CheckCatalog::Yes,
-        }
-    }
-}
-
-/// A PostgreSQL table declaration.
-///
-/// This is marked as `pub` and not `pub(crate)` because of a limitation of the
-/// `peg` crate, which can only declare regular `pub` functions, which aren't
-/// allowed to expose `pub(crate)` types. But we don't actually want to export
-/// this outside of our crate, so we mark it `pub` here but take care to not
-/// export it from a `pub` module anywhere.
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct PgCreateTable {
-    /// The name of the table.
-    pub(crate) name: PgName,
-    /// The columns in the table.
-    pub(crate) columns: Vec<PgColumn>,
-    /// Only create the table if it doesn't already exist.
-    pub(crate) if_not_exists: bool,
-    /// Create a temporary table local to a specific client session.
-    pub(crate) temporary: bool,
-}
-
-impl PgCreateTable {
-    /// Given a table name and a list of portable columns, construct a
-    /// corresponding `PgCreateTable`.
-    ///
-    /// We don't take a portable `Table` as an argument, because the `name`
-    /// contained in the `Table` might be an input table name, something from a
-    /// schema, etc., and it's usually a mistake to use it directly without
-    /// thinking things through first.
-    ///
-    /// We set `if_not_exists` to false, but the caller can change this directly
-    /// once once the `PgCreateTable` has been created.
-    pub(crate) fn from_name_and_columns(
-        schema: &Schema,
-        table_name: PgName,
-        columns: &[Column],
-    ) -> Result<PgCreateTable> {
-        let pg_columns = columns
-            .iter()
-            .map(|c| PgColumn::from_column(schema, c))
-            .collect::<Result<Vec<PgColumn>>>()?;
-        Ok(PgCreateTable {
-            name: table_name,
-            columns: pg_columns,
-            if_not_exists: false,
-            temporary: false,
-        })
-    }
-
-    /// Given a `PgCreateTable`, convert it to a portable `Table`.
-    pub(crate) fn to_table(&self) -> Result<Table> {
-        let columns = self
-            .columns
-            .iter()
-            .map(|c| c.to_column())
-            .collect::<Result<Vec<Column>>>()?;
-        Ok(Table {
-            name: self.name.unquoted(),
-            columns,
-        })
-    }
+/// Extensions to `PgCreateTable` which generate SQL using our `--from-arg`,
+/// `--where` and sampling options.
+pub(crate) trait PgCreateTableExt {
+    /// Write a `COPY (SELECT ...) TO STDOUT ...` statement for this table.
+    fn write_export_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
 
-    /// Create a new table based on this table, but with columns matching the
-    /// the names and order of the columns in `other_table`. This is useful if
-    /// we want to insert from `other_table` into `self`.
-    ///
-    /// Hypothetically, we could also check for compatibility between column
-    /// types in the two tables, but for now, we're happy to let the database
-    /// verify all that for us.
-    pub(crate) fn aligned_with(
+    /// Write a `SELECT ...` statement for this table.
+    fn write_export_select_sql(
         &self,
-        other_table: &PgCreateTable,
-    ) -> Result<PgCreateTable> {
-        let column_map = self
-            .columns
-            .iter()
-            .map(|c| (&c.name[..], c))
-            .collect::<HashMap<_, _>>();
-        Ok(PgCreateTable {
-            name: self.name.clone(),
-            columns: other_table
-                .columns
-                .iter()
-                .map(|c| {
-                    if let Some(&col) = column_map.get(&c.name[..]) {
-                        Ok(col.to_owned())
-                    } else {
-                        Err(format_err!(
-                            "could not find column {} in destination table: {}",
-                            c.name,
-                            column_map.keys().join(", "),
-                        ))
-                    }
-                })
-                .collect::<Result<Vec<_>>>()?,
-            if_not_exists: self.if_not_exists,
-            temporary: self.temporary,
-        })
-    }
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
 
-    /// Return all the unique named types in this `PgTable`.
-    pub(crate) fn named_type_names(&self) -> HashSet<&PgName> {
-        let mut names = HashSet::new();
-        for col in &self.columns {
-            let scalar_ty = match &col.data_type {
-                PgDataType::Array { ty, .. } => ty,
-                PgDataType::Scalar(ty) => ty,
-            };
-            if let PgScalarDataType::Named(name) = scalar_ty {
-                names.insert(name);
-            }
-        }
-        names
-    }
+    /// Write a `SELECT COUNT(*) ...` statement for this table.
+    fn write_count_sql(
+        &self,
+        f: &mut dyn Write,
+        source_args: &SourceArguments<Verified>,
+    ) -> Result<()>;
+}
 
-    /// Write a `COPY (SELECT ...) TO STDOUT ...` statement for this table.
-    pub(crate) fn write_export_sql(
+impl PgCreateTableExt for PgCreateTable {
+    fn write_export_sql(
         &self,
         f: &mut dyn Write,
         source_args: &SourceArguments<Verified>,
@@ -157,8 +41,7 @@ impl PgCreateTable {
         Ok(())
     }
 
-    /// Write a `SELECT ...` statement for this table.
-    pub(crate) fn write_export_select_sql(
+    fn write_export_select_sql(
         &self,
         f: &mut dyn Write,
         source_args: &SourceArguments<Verified>,
@@ -172,21 +55,40 @@ impl PgCreateTable {
             write!(f, "{}", sep.display())?;
             col.write_export_select_expr(f)?;
         }
-        write!(f, " FROM {}", &self.name.quoted())?;
+        write_from_sql(self, f, source_args)?;
+
+        // Sample rows if we were asked to. `TABLESAMPLE` only works on real
+        // tables, so fall back to `random()` for queries. Both of these choose
+        // individual rows, unlike `TABLESAMPLE SYSTEM`, which chooses pages.
+        let sampling = source_args.sampling();
+        let mut conditions = vec![];
         if let Some(where_clause) = source_args.where_clause() {
-            write!(f, " WHERE ({})", where_clause)?;
+            conditions.push(where_clause.to_owned());
+        }
+        if let Some(percent) = sampling.percent() {
+            if source_args.query().is_some() {
+                conditions.push(format!("random() < {} / 100.0", percent));
+            } else {
+                write!(f, " TABLESAMPLE BERNOULLI ({})", percent)?;
+            }
+        }
+        if !conditions.is_empty() {
+            write!(f, " WHERE ({})", conditions.join(") AND ("))?;
+        }
+        if let Some(limit) = sampling.limit() {
+            write!(f, " LIMIT {}", limit)?;
         }
         Ok(())
     }
 
-    /// Write a `SELECT COUNT(*) ...` statement for this table.
-    pub(crate) fn write_count_sql(
+    fn write_count_sql(
         &self,
         f: &mut dyn Write,
         source_args: &SourceArguments<Verified>,
     ) -> Result<()> {
         writeln!(f, "SELECT COUNT(*)")?;
-        writeln!(f, " FROM {}", &self.name.quoted())?;
+        write_from_sql(self, f, source_args)?;
+        writeln!(f)?;
         if let Some(where_clause) = source_args.where_clause() {
             writeln!(f, " WHERE ({})", where_clause)?;
         }
@@ -194,26 +96,53 @@ impl PgCreateTable {
     }
 }
 
-impl fmt::Display for PgCreateTable {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "CREATE")?;
-        if self.temporary {
-            write!(f, " TEMPORARY")?;
-        }
-        write!(f, " TABLE")?;
-        if self.if_not_exists {
-            write!(f, " IF NOT EXISTS")?;
-        }
-        writeln!(f, " {} (", &self.name.quoted())?;
-        for (idx, col) in self.columns.iter().enumerate() {
-            write!(f, "    {}", col)?;
-            if idx + 1 == self.columns.len() {
-                writeln!(f)?;
-            } else {
-                writeln!(f, ",")?;
-            }
-        }
-        writeln!(f, ");")?;
-        Ok(())
+/// Write the `FROM` clause for an export or count. We read from
+/// `--from-query` if we have one, and from this table otherwise.
+fn write_from_sql(
+    table: &PgCreateTable,
+    f: &mut dyn Write,
+    source_args: &SourceArguments<Verified>,
+) -> Result<()> {
+    if let Some(query) = source_args.query() {
+        write!(f, " FROM ({}) AS dbcrossbar_query", query)?;
+    } else {
+        write!(f, " FROM {}", &table.name.quoted())?;
     }
+    Ok(())
+}
+
+#[test]
+fn export_sql_with_sampling() {
+    use super::{PgColumn, PgDataType, PgName, PgScalarDataType};
+    use crate::sampling::Sampling;
+
+    let table = PgCreateTable {
+        name: PgName::new(None, "t"),
+        columns: vec![PgColumn {
+            name: "id".to_owned(),
+            data_type: PgDataType::Scalar(PgScalarDataType::Int),
+            is_nullable: false,
+        }],
+        if_not_exists: false,
+        temporary: false,
+        unlogged: false,
+        tablespace: None,
+        distributed_by: None,
+        redshift_attributes: None,
+    };
+    let mut sampling = Sampling::default();
+    sampling.set_sample("1.5%").unwrap();
+    sampling.set_limit(10);
+    let source_args = SourceArguments::for_temporary()
+        .with_sampling(sampling)
+        .verify(Features::empty())
+        .unwrap();
+    let mut sql = vec![];
+    table
+        .write_export_select_sql(&mut sql, &source_args)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(sql).unwrap(),
+        r#"SELECT "id" FROM "t" TABLESAMPLE BERNOULLI (1.5) LIMIT 10"#,
+    );
 }
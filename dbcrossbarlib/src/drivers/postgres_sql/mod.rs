@@ -2,12 +2,14 @@
 
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::Deserialize;
 use std::{
     fmt,
     str::{self, FromStr},
 };
 
 use crate::common::*;
+use crate::driver_args::deserialize_bool_arg;
 use crate::drivers::postgres_shared::{PgName, PgSchema};
 
 /// An SQL file containing a `CREATE TABLE` statement using Postgres syntax.
@@ -16,6 +18,17 @@ pub struct PostgresSqlLocator {
     path: PathOrStdio,
 }
 
+impl PostgresSqlLocator {
+    /// The PostgreSQL table name we would use when writing `schema` to this
+    /// locator.
+    pub(crate) fn table_name_for_schema(&self, schema: &Schema) -> Result<PgName> {
+        // TODO: We use the existing `table.name` here, but this might produce
+        // odd results if the input table comes from BigQuery or another
+        // database with a very different naming scheme.
+        sanitize_table_name(&schema.table.name)?.parse::<PgName>()
+    }
+}
+
 impl fmt::Display for PostgresSqlLocator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.path.fmt_locator_helper(Self::scheme(), f)
@@ -44,9 +57,9 @@ impl Locator for PostgresSqlLocator {
         &self,
         ctx: Context,
         schema: Schema,
-        if_exists: IfExists,
+        dest_args: DestinationArguments<Unverified>,
     ) -> BoxFuture<()> {
-        write_schema_helper(ctx, self.to_owned(), schema, if_exists).boxed()
+        write_schema_helper(ctx, self.to_owned(), schema, dest_args).boxed()
     }
 }
 
@@ -59,6 +72,7 @@ impl LocatorStatic for PostgresSqlLocator {
         Features {
             locator: LocatorFeatures::Schema | LocatorFeatures::WriteSchema,
             write_schema_if_exists: IfExistsFeatures::no_append(),
+            write_schema_args: DestinationArgumentsFeatures::DriverArgs.into(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
@@ -85,20 +99,64 @@ async fn schema_helper(
     Ok(Some(schema))
 }
 
+/// Parsed version of `--to-arg` values.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PostgresSqlDestinationArguments {
+    /// Output `CREATE TABLE IF NOT EXISTS` instead of `CREATE TABLE`.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    if_not_exists: bool,
+
+    /// Output `DROP TABLE IF EXISTS` (and `DROP TYPE IF EXISTS`, if needed)
+    /// before creating anything.
+    #[serde(default, deserialize_with = "deserialize_bool_arg")]
+    drop_if_exists: bool,
+}
+
 /// Implementation of `write_schema`, but as a real `async` function.
 async fn write_schema_helper(
     ctx: Context,
     dest: PostgresSqlLocator,
     schema: Schema,
-    if_exists: IfExists,
+    dest_args: DestinationArguments<Unverified>,
 ) -> Result<()> {
-    // TODO: We use the existing `table.name` here, but this might produce
-    // odd results if the input table comes from BigQuery or another
-    // database with a very different naming scheme.
-    let table_name = sanitize_table_name(&schema.table.name)?.parse::<PgName>()?;
-    let pg_schema = PgSchema::from_schema_and_name(&ctx, &schema, &table_name)?;
-    let mut out = dest.path.create_async(ctx, if_exists).await?;
+    let dest_args =
+        dest_args.verify_for_write_schema(PostgresSqlLocator::features())?;
+    let pg_dest_args = dest_args
+        .driver_args()
+        .deserialize::<PostgresSqlDestinationArguments>()
+        .context("could not parse --to-arg")?;
+
+    let table_name = dest.table_name_for_schema(&schema)?;
+    let mut pg_schema = PgSchema::from_schema_and_name(&schema, &table_name)?;
+    if pg_dest_args.if_not_exists {
+        // PostgreSQL doesn't have `CREATE TYPE IF NOT EXISTS`, so we can only
+        // do this safely if we're also dropping any existing types.
+        if !pg_schema.types.is_empty() && !pg_dest_args.drop_if_exists {
+            return Err(format_err!(
+                "PostgreSQL has no CREATE TYPE IF NOT EXISTS, so schemas with custom types need drop_if_exists=true as well as if_not_exists=true"
+            ));
+        }
+        for table in &mut pg_schema.tables {
+            table.if_not_exists = true;
+        }
+    }
+
+    let mut out = dest
+        .path
+        .create_async(ctx, dest_args.if_exists().to_owned())
+        .await?;
     buffer_sync_write_and_copy_to_async(&mut out, |buff| {
+        if pg_dest_args.drop_if_exists {
+            // Drop tables first, because they may depend on our types.
+            for table in &pg_schema.tables {
+                writeln!(buff, "DROP TABLE IF EXISTS {};", table.name.quoted())?;
+            }
+            for ty in &pg_schema.types {
+                writeln!(buff, "DROP TYPE IF EXISTS {};", ty.name.quoted())?;
+            }
+            writeln!(buff)?;
+        }
         write!(buff, "{}", pg_schema)
     })
     .await
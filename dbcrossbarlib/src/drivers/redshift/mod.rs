@@ -12,7 +12,7 @@ use std::{
 use crate::common::*;
 use crate::drivers::postgres::PostgresLocator;
 use crate::drivers::{
-    postgres_shared::{pg_quote, PgName},
+    postgres_shared::{pg_quote, PgName, RedshiftDistStyle, RedshiftTableAttributes},
     s3::S3Locator,
 };
 
@@ -22,7 +22,7 @@ mod write_remote_data;
 
 use local_data::local_data_helper;
 use write_local_data::write_local_data_helper;
-use write_remote_data::write_remote_data_helper;
+use write_remote_data::{write_dry_run_helper, write_remote_data_helper};
 
 /// A locator for a Redshift table.
 #[derive(Debug, Clone)]
@@ -126,6 +126,16 @@ impl Locator for RedshiftLocator {
         )
         .boxed()
     }
+
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        source: &dyn Locator,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_dry_run_helper(source, self, shared_args, dest_args).map(Some)
+    }
 }
 
 impl LocatorStatic for RedshiftLocator {
@@ -139,6 +149,7 @@ impl LocatorStatic for RedshiftLocator {
                 | LocatorFeatures::LocalData
                 | LocatorFeatures::WriteLocalData,
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: SourceArgumentsFeatures::DriverArgs
                 | SourceArgumentsFeatures::WhereClause,
             dest_args: DestinationArgumentsFeatures::DriverArgs.into(),
@@ -157,6 +168,14 @@ pub(crate) struct RedshiftDriverArguments {
     // Insert this as a "-- partner: " comment in generated queries. AWS uses
     // this to keep track of which tools generate which queries.
     partner: Option<String>,
+    // Set to `auto`, `even`, `key` or `all` to choose a `DISTSTYLE` for new
+    // tables.
+    diststyle: Option<String>,
+    // The `DISTKEY` column to use for new tables. Implies `diststyle=key`.
+    distkey: Option<String>,
+    // Columns to use for the `SORTKEY` of new tables.
+    #[serde(default)]
+    sortkey: Vec<String>,
     // Everything we don't recognize is treated as a credential, for backwards
     // compatibility.
     #[serde(flatten)]
@@ -181,8 +200,55 @@ impl RedshiftDriverArguments {
         }
     }
 
+    /// The distribution and sort options to use when creating new tables, or
+    /// `None` to use Redshift's defaults.
+    pub(crate) fn table_attributes(&self) -> Result<Option<RedshiftTableAttributes>> {
+        let diststyle = match (self.diststyle.as_deref(), &self.distkey) {
+            (None, None) => None,
+            (None, Some(col)) | (Some("key"), Some(col)) => {
+                Some(RedshiftDistStyle::Key(col.to_owned()))
+            }
+            (Some("key"), None) => {
+                return Err(format_err!("diststyle=key requires distkey=..."));
+            }
+            (Some("auto"), None) => Some(RedshiftDistStyle::Auto),
+            (Some("even"), None) => Some(RedshiftDistStyle::Even),
+            (Some("all"), None) => Some(RedshiftDistStyle::All),
+            (Some(other), None) => {
+                return Err(format_err!(
+                    "expected diststyle=auto, even, key or all, found {:?}",
+                    other,
+                ));
+            }
+            (Some(_), Some(_)) => {
+                return Err(format_err!(
+                    "distkey=... can only be used with diststyle=key"
+                ));
+            }
+        };
+        if diststyle.is_none() && self.sortkey.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(RedshiftTableAttributes {
+                diststyle,
+                sortkey: self.sortkey.clone(),
+            }))
+        }
+    }
+
     /// Given a `DriverArgs` structure, convert it into Redshift credentials SQL.
     pub(crate) fn credentials_sql(&self) -> Result<String> {
+        self.credentials_sql_helper(false)
+    }
+
+    /// Like `credentials_sql`, but with the values of our credentials hidden,
+    /// so that we can show the SQL to the user.
+    pub(crate) fn redacted_credentials_sql(&self) -> Result<String> {
+        self.credentials_sql_helper(true)
+    }
+
+    /// Implementation of `credentials_sql` and `redacted_credentials_sql`.
+    fn credentials_sql_helper(&self, redact: bool) -> Result<String> {
         let mut out = vec![];
         for (k, v) in &self.credentials {
             lazy_static! {
@@ -192,8 +258,45 @@ impl RedshiftDriverArguments {
             if !KEY_RE.is_match(k) {
                 return Err(format_err!("cannot pass {:?} as Redshift credential", k));
             }
+            let v = if redact { "XXXXXX" } else { v };
             writeln!(&mut out, "{} {}", k, pg_quote(v))?;
         }
         Ok(String::from_utf8(out).expect("found non-UTF-8 SQL"))
     }
 }
+
+#[test]
+fn parse_table_attribute_args() {
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<RedshiftDriverArguments>()
+            .unwrap()
+    };
+    assert_eq!(parse(&[]).table_attributes().unwrap(), None);
+    assert_eq!(
+        parse(&["distkey=id", "sortkey[]=a", "sortkey[]=b"])
+            .table_attributes()
+            .unwrap(),
+        Some(RedshiftTableAttributes {
+            diststyle: Some(RedshiftDistStyle::Key("id".to_owned())),
+            sortkey: vec!["a".to_owned(), "b".to_owned()],
+        }),
+    );
+    assert_eq!(
+        parse(&["diststyle=all"]).table_attributes().unwrap(),
+        Some(RedshiftTableAttributes {
+            diststyle: Some(RedshiftDistStyle::All),
+            sortkey: vec![],
+        }),
+    );
+    assert!(parse(&["diststyle=key"]).table_attributes().is_err());
+    assert!(parse(&["diststyle=even", "distkey=id"])
+        .table_attributes()
+        .is_err());
+    assert!(parse(&["diststyle=sideways"]).table_attributes().is_err());
+
+    // Table options are not passed to `COPY` as credentials.
+    let args = parse(&["diststyle=even", "iam_role=role"]);
+    assert_eq!(args.credentials_sql().unwrap(), "iam_role 'role'\n");
+}
@@ -2,7 +2,7 @@
 
 use super::RedshiftLocator;
 use crate::common::*;
-use crate::drivers::s3::find_s3_temp_dir;
+use crate::drivers::s3::{delete_s3_temp_dir_on_cancel, find_s3_temp_dir};
 use crate::tokio_glue::ConsumeWithParallelism;
 
 /// Implementation of `write_local_data`, but as a real `async` function.
@@ -16,6 +16,7 @@ pub(crate) async fn write_local_data_helper(
     // Build a temporary location.
     let shared_args_v = shared_args.clone().verify(RedshiftLocator::features())?;
     let s3_temp = find_s3_temp_dir(shared_args_v.temporary_storage())?;
+    delete_s3_temp_dir_on_cancel(&ctx, &s3_temp);
     let s3_dest_args = DestinationArguments::for_temporary();
     let s3_source_args = SourceArguments::for_temporary();
 
@@ -8,9 +8,9 @@ use crate::drivers::{
     postgres::{columns_to_update_for_upsert, create_temp_table_for, prepare_table},
     postgres_shared::{
         connect, pg_quote, CheckCatalog, Client, Ident, PgCreateTable, PgName,
-        PgSchema,
+        PgNameExt, PgSchema, PgSchemaExt,
     },
-    s3::S3Locator,
+    s3::{find_s3_temp_dir, S3Locator},
 };
 use crate::schema::{Column, DataType};
 
@@ -47,22 +47,24 @@ pub(crate) async fn write_remote_data_helper(
     let to_args = dest_args
         .driver_args()
         .deserialize::<RedshiftDriverArguments>()?;
+    let table_attributes = to_args.table_attributes()?;
     let if_exists = dest_args.if_exists().to_owned();
 
     // Try to look up our table schema in the database.
     schema.verify_redshift_can_import_from_csv()?;
     let table_name = dest.table_name();
-    let pg_schema = PgSchema::from_pg_catalog_or_default(
+    let mut client = connect(&ctx, dest.url()).await?;
+    let mut pg_schema = PgSchema::from_pg_catalog_or_default(
         &ctx,
         CheckCatalog::from(&if_exists),
-        dest.url(),
-        &table_name,
+        &client,
+        table_name,
         schema,
     )
     .await?;
+    pg_schema.table_mut()?.redshift_attributes = table_attributes;
 
-    // Connect to Redshift and prepare our table.
-    let mut client = connect(&ctx, dest.url()).await?;
+    // Prepare our table, reusing the same connection.
     prepare_table(&ctx, &mut client, pg_schema.clone(), &if_exists).await?;
     if let IfExists::Upsert(upsert_keys) = &if_exists {
         // Create a temporary table to hold our imported data.
@@ -81,8 +83,9 @@ pub(crate) async fn write_remote_data_helper(
         )
         .await?;
     } else {
-        copy_in(&ctx, &client, &source_url, &table_name, &to_args).await?;
+        copy_in(&ctx, &client, &source_url, table_name, &to_args).await?;
     }
+    client.release();
 
     Ok(vec![dest.boxed()])
 }
@@ -101,13 +104,12 @@ async fn copy_in(
         dest_table.unquoted(),
         source_s3_url.as_str(),
     );
-    let copy_sql = format!(
-        "{partner}COPY {dest} FROM {source}\n{credentials}FORMAT CSV\nIGNOREHEADER 1\nDATEFORMAT 'auto'\nTIMEFORMAT 'auto'",
-        partner = to_args.partner_sql()?,
-        dest = dest_table.quoted(),
-        source = pg_quote(source_s3_url.as_str()), // `$1` doesn't work here.
-        credentials = to_args.credentials_sql()?,
-    );
+    let copy_sql = copy_sql(
+        source_s3_url,
+        dest_table,
+        to_args,
+        &to_args.credentials_sql()?,
+    )?;
     let copy_stmt = client.prepare(&copy_sql).await?;
     client.execute(&copy_stmt, &[]).await.with_context(|_| {
         format!(
@@ -119,6 +121,96 @@ async fn copy_in(
     Ok(())
 }
 
+/// Generate SQL to copy data from S3 into a Redshift table, using
+/// `credentials_sql` to authenticate.
+fn copy_sql(
+    source_s3_url: &Url,
+    dest_table: &PgName,
+    to_args: &RedshiftDriverArguments,
+    credentials_sql: &str,
+) -> Result<String> {
+    Ok(format!(
+        "{partner}COPY {dest} FROM {source}\n{credentials}FORMAT CSV\nIGNOREHEADER 1\nDATEFORMAT 'auto'\nTIMEFORMAT 'auto'",
+        partner = to_args.partner_sql()?,
+        dest = dest_table.quoted(),
+        source = pg_quote(source_s3_url.as_str()), // `$1` doesn't work here.
+        credentials = credentials_sql,
+    ))
+}
+
+/// Describe the SQL that we would run to copy `source` to `dest`, without
+/// connecting to anything. If `source` isn't an `s3://` locator, we would
+/// upload our data to temporary storage first, like `write_local_data_helper`.
+///
+/// We hide the values of any credentials passed using `--to-arg`.
+pub(crate) fn write_dry_run_helper(
+    source: &dyn Locator,
+    dest: &RedshiftLocator,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let shared_args = shared_args.verify(RedshiftLocator::features())?;
+    let dest_args = dest_args.verify(RedshiftLocator::features())?;
+    let schema = shared_args.schema();
+    let to_args = dest_args
+        .driver_args()
+        .deserialize::<RedshiftDriverArguments>()?;
+    let table_attributes = to_args.table_attributes()?;
+    let if_exists = dest_args.if_exists();
+    schema.verify_redshift_can_import_from_csv()?;
+
+    let mut out = vec![];
+    let source_url = match source.as_any().downcast_ref::<S3Locator>() {
+        Some(s3_source) => s3_source.as_url().to_owned(),
+        None => {
+            let s3_temp = find_s3_temp_dir(shared_args.temporary_storage())?;
+            writeln!(out, "-- Upload data streams to {}", s3_temp)?;
+            s3_temp.as_url().to_owned()
+        }
+    };
+
+    // Prepare our destination table, like `prepare_table`.
+    let mut pg_schema = PgSchema::from_schema_and_name(schema, dest.table_name())?;
+    let table = pg_schema.table_mut()?;
+    table.redshift_attributes = table_attributes;
+    match if_exists {
+        IfExists::Overwrite => {
+            writeln!(out, "DROP TABLE IF EXISTS {};", table.name.quoted())?;
+            table.if_not_exists = false;
+        }
+        IfExists::Append | IfExists::Upsert(_) => table.if_not_exists = true,
+        IfExists::Error => table.if_not_exists = false,
+    }
+    let table = pg_schema.table()?;
+    write!(out, "{}", table)?;
+
+    // Copy in our data.
+    let credentials_sql = to_args.redacted_credentials_sql()?;
+    if let IfExists::Upsert(upsert_keys) = if_exists {
+        let mut temp_table = table.to_owned();
+        temp_table.name = table.name.temporary_table_name()?;
+        temp_table.if_not_exists = false;
+        temp_table.temporary = true;
+        write!(out, "{}", temp_table)?;
+        writeln!(
+            out,
+            "{};",
+            copy_sql(&source_url, &temp_table.name, &to_args, &credentials_sql)?,
+        )?;
+        for sql in upsert_sql(&temp_table, table, upsert_keys)? {
+            writeln!(out, "{};", sql)?;
+        }
+    } else {
+        writeln!(
+            out,
+            "{};",
+            copy_sql(&source_url, &table.name, &to_args, &credentials_sql)?,
+        )?;
+    }
+
+    Ok(String::from_utf8(out).expect("generated SQL should always be UTF-8"))
+}
+
 /// Upsert from `temp_table` into `dest_table`, using the columns `upsert_keys`.
 async fn upsert_from_temp_table(
     ctx: &Context,
@@ -129,7 +221,7 @@ async fn upsert_from_temp_table(
 ) -> Result<()> {
     let transaction = client.transaction().await?;
 
-    let upsert_sql = upsert_sql(&temp_table, &dest_table, upsert_keys)?;
+    let upsert_sql = upsert_sql(temp_table, dest_table, upsert_keys)?;
     for (idx, sql) in upsert_sql.iter().enumerate() {
         debug!(
             ctx.log(),
@@ -168,7 +260,7 @@ fn upsert_sql(
         .map(|k| {
             format!(
                 "{dest_table}.{name} = {temp_table}.{name}",
-                name = Ident(&k),
+                name = Ident(k),
                 dest_table = dest_table_name,
                 temp_table = temp_table_name,
             )
@@ -188,7 +280,7 @@ WHERE {keys_match}",
                 .iter()
                 .map(|k| format!(
                     "{name} = {temp_table}.{name}",
-                    name = Ident(&k),
+                    name = Ident(k),
                     temp_table = temp_table_name,
                 ))
                 .join(",\n    "),
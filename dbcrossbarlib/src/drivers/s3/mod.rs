@@ -2,6 +2,7 @@
 
 use std::{fmt, str::FromStr};
 
+use crate::clouds::aws::s3;
 use crate::common::*;
 use crate::drivers::redshift::RedshiftLocator;
 
@@ -13,7 +14,7 @@ mod write_remote_data;
 use local_data::local_data_helper;
 pub(crate) use prepare_as_destination::prepare_as_destination_helper;
 use write_local_data::write_local_data_helper;
-use write_remote_data::write_remote_data_helper;
+use write_remote_data::{write_dry_run_helper, write_remote_data_helper};
 
 #[derive(Clone, Debug)]
 pub(crate) struct S3Locator {
@@ -104,6 +105,16 @@ impl Locator for S3Locator {
         )
         .boxed()
     }
+
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        source: &dyn Locator,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        write_dry_run_helper(source, self, shared_args, dest_args).map(Some)
+    }
 }
 
 impl LocatorStatic for S3Locator {
@@ -115,8 +126,12 @@ impl LocatorStatic for S3Locator {
         Features {
             locator: LocatorFeatures::LocalData | LocatorFeatures::WriteLocalData,
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
-            dest_args: EnumSet::empty(),
+            // S3 prefixes don't need to be created, so `--create-missing`
+            // has nothing to do.
+            dest_args: DestinationArgumentsFeatures::DriverArgs
+                | DestinationArgumentsFeatures::CreateMissing,
             dest_if_exists: IfExistsFeatures::Overwrite.into(),
             _placeholder: (),
         }
@@ -139,3 +154,13 @@ pub(crate) fn find_s3_temp_dir(
     temp.push('/');
     S3Locator::from_str(&temp)
 }
+
+/// Delete `temp`, which was returned by `find_s3_temp_dir`, if this run is
+/// cancelled.
+pub(crate) fn delete_s3_temp_dir_on_cancel(ctx: &Context, temp: &S3Locator) {
+    let url = temp.as_url().to_owned();
+    ctx.on_cancel(format!("delete {}", url), move |ctx| async move {
+        s3::rmdir(&ctx, &url).await
+    })
+    .keep_until_exit();
+}
@@ -1,8 +1,41 @@
 //! Writing data to AWS S3.
 
+use serde::Deserialize;
+
 use super::{prepare_as_destination_helper, S3Locator};
-use crate::clouds::aws::s3;
+use crate::clouds::aws::s3::{self, UploadOptions};
 use crate::common::*;
+use crate::driver_args::deserialize_optional_usize_arg;
+
+/// Parsed version of `--to-arg` for S3.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct S3DestinationArguments {
+    /// The size of each part of a multipart upload, in MiB.
+    #[serde(default, deserialize_with = "deserialize_optional_usize_arg")]
+    part_size_mib: Option<usize>,
+
+    /// How many parts of each file should we upload at once?
+    #[serde(default, deserialize_with = "deserialize_optional_usize_arg")]
+    upload_concurrency: Option<usize>,
+}
+
+impl S3DestinationArguments {
+    /// Convert these arguments into `UploadOptions`.
+    fn to_upload_options(&self) -> Result<UploadOptions> {
+        let mut options = UploadOptions::default();
+        if let Some(part_size_mib) = self.part_size_mib {
+            options.part_size = part_size_mib
+                .checked_mul(1024 * 1024)
+                .ok_or_else(|| format_err!("part_size_mib is too large"))?;
+        }
+        if let Some(upload_concurrency) = self.upload_concurrency {
+            options.concurrency = upload_concurrency;
+        }
+        options.validate()?;
+        Ok(options)
+    }
+}
 
 /// Implementation of `write_local_data`, but as a real `async` function.
 pub(crate) async fn write_local_data_helper(
@@ -17,6 +50,11 @@ pub(crate) async fn write_local_data_helper(
 
     // Look up our arguments.
     let if_exists = dest_args.if_exists().to_owned();
+    let upload_options = dest_args
+        .driver_args()
+        .deserialize::<S3DestinationArguments>()
+        .context("error parsing --to-args")?
+        .to_upload_options()?;
 
     // Delete the existing output, if it exists.
     prepare_as_destination_helper(ctx.clone(), url.clone(), if_exists).await?;
@@ -25,11 +63,12 @@ pub(crate) async fn write_local_data_helper(
     let written = data.map_ok(move |stream| {
         let url = url.clone();
         let ctx = ctx.clone();
+        let upload_options = upload_options.clone();
         async move {
             let url = url.join(&format!("{}.csv", stream.name))?;
             let ctx = ctx
                 .child(o!("stream" => stream.name.clone(), "url" => url.to_string()));
-            s3::upload_file(&ctx, stream.data, &url).await?;
+            s3::upload_file(&ctx, stream.data, &url, &upload_options).await?;
             Ok(S3Locator { url }.boxed())
         }
         .boxed()
@@ -3,7 +3,7 @@
 use super::{prepare_as_destination_helper, S3Locator};
 use crate::common::*;
 use crate::drivers::{
-    postgres_shared::{connect, pg_quote, CheckCatalog, PgSchema},
+    postgres_shared::{connect, pg_quote, CheckCatalog, PgSchema, PgSchemaExt},
     redshift::{RedshiftDriverArguments, RedshiftLocator},
 };
 
@@ -43,12 +43,13 @@ pub(crate) async fn write_remote_data_helper(
 
     // Convert our schema to a native PostgreSQL schema.
     let table_name = source.table_name();
+    let client = connect(&ctx, source.url()).await?;
     let pg_schema = PgSchema::from_pg_catalog_or_default(
         // Always check the catalog, because `if_exists` is for our S3
         // destination, not for Redshift source.
         &ctx,
         CheckCatalog::Yes,
-        source.url(),
+        &client,
         table_name,
         schema,
     )
@@ -61,7 +62,6 @@ pub(crate) async fn write_remote_data_helper(
     debug!(ctx.log(), "export SQL: {}", select_sql);
 
     // Export as CSV.
-    let client = connect(&ctx, source.url()).await?;
     let unload_sql = format!(
         "{partner}UNLOAD ({source}) TO {dest}\n{credentials}HEADER FORMAT CSV",
         partner = from_args.partner_sql()?,
@@ -73,5 +73,29 @@ pub(crate) async fn write_remote_data_helper(
     client.execute(&unload_stmt, &[]).await.with_context(|_| {
         format!("error copying {} to {}", table_name.quoted(), dest)
     })?;
+    client.release();
     Ok(vec![dest.boxed()])
 }
+
+/// Describe what we would do to copy `source` to `dest`, without connecting to
+/// anything. This is used to implement `--dry-run`.
+pub(crate) fn write_dry_run_helper(
+    source: &dyn Locator,
+    dest: &S3Locator,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<String> {
+    let _shared_args = shared_args.verify(S3Locator::features())?;
+    let _dest_args = dest_args.verify(S3Locator::features())?;
+    let url = dest.as_url();
+
+    let mut out = vec![];
+    writeln!(out, "-- Delete all files under {}", url)?;
+    if let Some(source) = source.as_any().downcast_ref::<RedshiftLocator>() {
+        writeln!(out, "-- UNLOAD data from {} to {}", source, url)?;
+    } else {
+        writeln!(out, "-- For each data stream:")?;
+        writeln!(out, "-- Upload CSV data to {}{{stream}}.csv", url)?;
+    }
+    Ok(String::from_utf8(out).expect("generated output should always be UTF-8"))
+}
@@ -0,0 +1,133 @@
+//! A minimal Salesforce REST client.
+
+use reqwest::{header::HeaderMap, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::common::*;
+use crate::credentials::CredentialsManager;
+
+/// The version of the Salesforce API that we use.
+const API_VERSION: &str = "v51.0";
+
+/// A client for a Salesforce instance.
+pub(crate) struct Client {
+    /// Our HTTP client.
+    client: reqwest::Client,
+    /// The base URL of our Salesforce instance, without a trailing `/`.
+    instance_url: String,
+    /// An OAuth2 access token.
+    access_token: String,
+}
+
+impl Client {
+    /// Create a new client using the `salesforce` credentials.
+    pub(crate) async fn new() -> Result<Client> {
+        let creds = CredentialsManager::singleton().get("salesforce").await?;
+        let instance_url = creds
+            .get_required("instance_url")?
+            .trim_end_matches('/')
+            .to_owned();
+        let access_token = creds.get_required("access_token")?.to_owned();
+        Ok(Client {
+            client: reqwest::Client::new(),
+            instance_url,
+            access_token,
+        })
+    }
+
+    /// The full URL for `path`, which is relative to the versioned API root.
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/services/data/{}/{}",
+            self.instance_url, API_VERSION, path
+        )
+    }
+
+    /// Make a GET request to `path` and deserialize the JSON response.
+    pub(crate) async fn get<Output>(&self, ctx: &Context, path: &str) -> Result<Output>
+    where
+        Output: DeserializeOwned,
+    {
+        let url = self.url(path);
+        trace!(ctx.log(), "GET {}", url);
+        let resp = self.send(self.client.get(&url), "GET", &url).await?;
+        Ok(resp
+            .json::<Output>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", url))?)
+    }
+
+    /// Make a POST request to `path` with a JSON body, and deserialize the
+    /// JSON response.
+    pub(crate) async fn post<Output, Body>(
+        &self,
+        ctx: &Context,
+        path: &str,
+        body: &Body,
+    ) -> Result<Output>
+    where
+        Output: DeserializeOwned,
+        Body: Serialize,
+    {
+        let url = self.url(path);
+        trace!(ctx.log(), "POST {}", url);
+        let resp = self
+            .send(self.client.post(&url).json(body), "POST", &url)
+            .await?;
+        Ok(resp
+            .json::<Output>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", url))?)
+    }
+
+    /// Make a GET request to `path` with `query`, and return the response body
+    /// as raw bytes, along with the response headers.
+    pub(crate) async fn get_bytes<Query>(
+        &self,
+        ctx: &Context,
+        path: &str,
+        query: &Query,
+    ) -> Result<(HeaderMap, BytesMut)>
+    where
+        Query: Serialize,
+    {
+        let url = self.url(path);
+        trace!(ctx.log(), "GET {}", url);
+        let resp = self
+            .send(self.client.get(&url).query(query), "GET", &url)
+            .await?;
+        let headers = resp.headers().to_owned();
+        let body = resp
+            .bytes()
+            .await
+            .with_context(|_| format!("error reading response from {}", url))?;
+        Ok((headers, BytesMut::from(&body[..])))
+    }
+
+    /// Authenticate and send `req`, and return an error if it fails.
+    async fn send(
+        &self,
+        req: RequestBuilder,
+        method: &str,
+        url: &str,
+    ) -> Result<Response> {
+        let resp = req.bearer_auth(&self.access_token).send().await.context(
+            ErrorKind::Connection {
+                target: self.instance_url.clone(),
+            },
+        )?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            Err(format_err!(
+                "could not {} {}: {} {}",
+                method,
+                url,
+                status,
+                body
+            ))
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Reading Salesforce schemas and data.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+use super::{client::Client, ObjectDescription, SalesforceLocator};
+use crate::common::*;
+
+/// How long should we wait between checks on a Bulk API job?
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Implementation of `schema`, but as a real `async` function.
+pub(crate) async fn schema_helper(
+    ctx: Context,
+    source: SalesforceLocator,
+) -> Result<Option<Schema>> {
+    let client = Client::new().await?;
+    let path = format!("sobjects/{}/describe", source.object);
+    let desc = client
+        .get::<ObjectDescription>(&ctx, &path)
+        .await
+        .with_context(|_| format!("error describing {}", source))?;
+    Ok(Some(desc.to_schema()?))
+}
+
+/// A request to create a Bulk API 2.0 query job.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateQueryJob {
+    operation: &'static str,
+    query: String,
+}
+
+/// The status of a Bulk API 2.0 query job.
+///
+/// Docs: https://developer.salesforce.com/docs/atlas.en-us.api_asynch.meta/api_asynch/query_get_one_job.htm
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryJob {
+    id: String,
+    state: String,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+/// Query parameters for fetching query job results.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultsQuery {
+    /// Where to start this page of results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locator: Option<String>,
+}
+
+/// Check that `name` can be used as a field name in SOQL.
+///
+/// SOQL has no way to quote identifiers, so we only allow the characters used
+/// in Salesforce API names, plus `.` for relationship fields.
+fn soql_field_name(name: &str) -> Result<&str> {
+    let valid_part = |part: &str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if name.split('.').all(valid_part) {
+        Ok(name)
+    } else {
+        Err(format_err!(
+            "cannot use {:?} as a Salesforce field name",
+            name
+        ))
+    }
+}
+
+#[test]
+fn soql_field_name_rejects_unquotable_names() {
+    assert_eq!(soql_field_name("Name").unwrap(), "Name");
+    assert_eq!(soql_field_name("Owner.Name").unwrap(), "Owner.Name");
+    assert_eq!(soql_field_name("My_Field__c").unwrap(), "My_Field__c");
+    for &name in &["", "a b", "a,b", "a.", "Name FROM User --"] {
+        assert!(soql_field_name(name).is_err());
+    }
+}
+
+/// Implementation of `local_data`, but as a real `async` function.
+pub(crate) async fn local_data_helper(
+    ctx: Context,
+    source: SalesforceLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+) -> Result<Option<BoxStream<CsvStream>>> {
+    let shared_args = shared_args.verify(SalesforceLocator::features())?;
+    let source_args = source_args.verify(SalesforceLocator::features())?;
+    let schema = shared_args.schema();
+
+    // Build our SOQL query.
+    let columns = schema
+        .table
+        .columns
+        .iter()
+        .map(|c| soql_field_name(&c.name))
+        .collect::<Result<Vec<_>>>()?
+        .join(", ");
+    let mut query = format!("SELECT {} FROM {}", columns, source.object);
+    if let Some(where_clause) = source_args.where_clause() {
+        query.push_str(" WHERE ");
+        query.push_str(where_clause);
+    }
+    debug!(ctx.log(), "exporting from Salesforce: {}", query);
+
+    // Start a query job and wait for it to finish.
+    let client = Arc::new(Client::new().await?);
+    let job = client
+        .post::<QueryJob, _>(
+            &ctx,
+            "jobs/query",
+            &CreateQueryJob {
+                operation: "query",
+                query,
+            },
+        )
+        .await
+        .with_context(|_| format!("error starting export of {}", source))?;
+    let job_id = job.id.clone();
+    info!(ctx.log(), "started Salesforce Bulk API job {}", job_id);
+    wait_for_job(&ctx, &client, job).await?;
+    info!(ctx.log(), "finished Salesforce Bulk API job {}", job_id);
+
+    // Each page of results is a complete CSV file with headers, so we return
+    // each one as a separate stream.
+    let results_path = format!("jobs/query/{}/results", job_id);
+    let name = source.object.clone();
+    let pages = stream::try_unfold(Some((0, None)), move |state| {
+        let ctx = ctx.clone();
+        let client = client.clone();
+        let results_path = results_path.clone();
+        let name = name.clone();
+        async move {
+            let (page_idx, locator) = match state {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let (headers, data) = client
+                .get_bytes(&ctx, &results_path, &ResultsQuery { locator })
+                .await?;
+            let next_locator = headers
+                .get("sforce-locator")
+                .and_then(|value| value.to_str().ok())
+                .filter(|&value| !value.is_empty() && value != "null")
+                .map(|value| value.to_owned());
+            let csv_stream = CsvStream {
+                name: format!("{}_{}", name, page_idx),
+                data: box_stream_once(Ok(data)),
+            };
+            let next_state = next_locator.map(|locator| (page_idx + 1, Some(locator)));
+            Ok::<_, Error>(Some((csv_stream, next_state)))
+        }
+    });
+    Ok(Some(pages.boxed()))
+}
+
+/// Poll `job` until it completes.
+async fn wait_for_job(
+    ctx: &Context,
+    client: &Client,
+    mut job: QueryJob,
+) -> Result<()> {
+    let path = format!("jobs/query/{}", job.id);
+    let mut interval = Duration::from_secs(1);
+    loop {
+        match &job.state[..] {
+            "JobComplete" => return Ok(()),
+            "Aborted" | "Failed" => {
+                return Err(format_err!(
+                    "Salesforce Bulk API job {} {}: {}",
+                    job.id,
+                    job.state.to_lowercase(),
+                    job.error_message.as_deref().unwrap_or("(no error message)"),
+                ));
+            }
+            state => {
+                trace!(ctx.log(), "Salesforce job {} is {}", job.id, state);
+            }
+        }
+        sleep(interval).await;
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        job = client.get::<QueryJob>(ctx, &path).await?;
+    }
+}
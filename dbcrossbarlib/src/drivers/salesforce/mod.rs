@@ -0,0 +1,214 @@
+//! Driver for working with Salesforce objects.
+
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+mod client;
+mod local_data;
+
+use self::local_data::{local_data_helper, schema_helper};
+
+/// A Salesforce object type, like `Account` or `My_Object__c`.
+#[derive(Clone, Debug)]
+pub(crate) struct SalesforceLocator {
+    object: String,
+}
+
+impl fmt::Display for SalesforceLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Self::scheme(), self.object)
+    }
+}
+
+impl FromStr for SalesforceLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let object = s.strip_prefix(Self::scheme()).ok_or_else(|| {
+            format_err!("expected {} to begin with {}", s, Self::scheme())
+        })?;
+        // Object names also appear in SOQL queries, so be strict.
+        if object.is_empty()
+            || !object
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format_err!(
+                "expected an object name in {:?}, like salesforce:Account",
+                s,
+            ));
+        }
+        Ok(SalesforceLocator {
+            object: object.to_owned(),
+        })
+    }
+}
+
+#[test]
+fn from_str_checks_object_names() {
+    let loc = "salesforce:My_Object__c"
+        .parse::<SalesforceLocator>()
+        .unwrap();
+    assert_eq!(loc.object, "My_Object__c");
+    assert!("salesforce:".parse::<SalesforceLocator>().is_err());
+    assert!("salesforce:Account; DROP"
+        .parse::<SalesforceLocator>()
+        .is_err());
+}
+
+impl Locator for SalesforceLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self, ctx: Context) -> BoxFuture<Option<Schema>> {
+        schema_helper(ctx, self.to_owned()).boxed()
+    }
+
+    fn local_data(
+        &self,
+        ctx: Context,
+        shared_args: SharedArguments<Unverified>,
+        source_args: SourceArguments<Unverified>,
+    ) -> BoxFuture<Option<BoxStream<CsvStream>>> {
+        local_data_helper(ctx, self.to_owned(), shared_args, source_args).boxed()
+    }
+}
+
+impl LocatorStatic for SalesforceLocator {
+    fn scheme() -> &'static str {
+        "salesforce:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::Schema | LocatorFeatures::LocalData,
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: SourceArgumentsFeatures::WhereClause.into(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: EnumSet::empty(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
+
+/// The output of the `describe` API for an object.
+///
+/// Docs: https://developer.salesforce.com/docs/atlas.en-us.api_rest.meta/api_rest/resources_sobject_describe.htm
+#[derive(Debug, Deserialize)]
+struct ObjectDescription {
+    /// The API name of this object.
+    name: String,
+    /// The fields of this object.
+    fields: Vec<FieldDescription>,
+}
+
+/// The description of a single field.
+#[derive(Debug, Deserialize)]
+struct FieldDescription {
+    /// The API name of this field.
+    name: String,
+    /// The human-readable label for this field.
+    label: String,
+    /// The Salesforce type of this field.
+    #[serde(rename = "type")]
+    ty: String,
+    /// Can this field be null?
+    nillable: bool,
+}
+
+impl FieldDescription {
+    /// Convert this field to a portable column, or return `None` if the Bulk
+    /// API can't export it.
+    fn to_column(&self) -> Result<Option<Column>> {
+        let data_type = match &self.ty[..] {
+            // Compound fields can't be exported by the Bulk API, but their
+            // sub-fields appear separately in the field list. And the Bulk API
+            // doesn't support binary data.
+            "address" | "location" | "base64" => return Ok(None),
+            "boolean" => DataType::Bool,
+            "currency" | "double" | "percent" => DataType::Decimal,
+            "date" => DataType::Date,
+            "datetime" => DataType::TimestampWithTimeZone,
+            "int" => DataType::Int32,
+            "long" => DataType::Int64,
+            "anyType" | "combobox" | "email" | "encryptedstring" | "id"
+            | "multipicklist" | "phone" | "picklist" | "reference" | "string"
+            | "textarea" | "time" | "url" => DataType::Text,
+            other => {
+                return Err(ErrorKind::UnsupportedType {
+                    column: self.name.clone(),
+                    type_name: other.to_owned(),
+                }
+                .into())
+            }
+        };
+        Ok(Some(Column {
+            name: self.name.clone(),
+            is_nullable: self.nillable,
+            data_type,
+            comment: Some(self.label.clone()),
+        }))
+    }
+}
+
+impl ObjectDescription {
+    /// Convert this description to a portable schema.
+    fn to_schema(&self) -> Result<Schema> {
+        let mut columns = vec![];
+        for field in &self.fields {
+            if let Some(column) = field.to_column()? {
+                columns.push(column);
+            }
+        }
+        Schema::from_table(Table {
+            name: self.name.clone(),
+            columns,
+        })
+    }
+}
+
+#[test]
+fn object_description_to_schema() {
+    use serde_json::json;
+
+    let desc = serde_json::from_value::<ObjectDescription>(json!({
+        "name": "Account",
+        "fields": [
+            { "name": "Id", "label": "Account ID", "type": "id", "nillable": false },
+            { "name": "AnnualRevenue", "label": "Annual Revenue", "type": "currency", "nillable": true },
+            { "name": "BillingAddress", "label": "Billing Address", "type": "address", "nillable": true },
+            { "name": "CreatedDate", "label": "Created Date", "type": "datetime", "nillable": false },
+        ],
+    }))
+    .unwrap();
+    let schema = desc.to_schema().unwrap();
+    assert_eq!(schema.table.name, "Account");
+    let columns = schema
+        .table
+        .columns
+        .iter()
+        .map(|c| (&c.name[..], c.data_type.clone(), c.is_nullable))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        columns,
+        &[
+            ("Id", DataType::Text, false),
+            ("AnnualRevenue", DataType::Decimal, true),
+            ("CreatedDate", DataType::TimestampWithTimeZone, false),
+        ],
+    );
+    assert_eq!(
+        schema.table.columns[0].comment.as_deref(),
+        Some("Account ID")
+    );
+}
@@ -0,0 +1,200 @@
+//! A minimal Confluent Schema Registry REST client.
+//!
+//! Docs: https://docs.confluent.io/platform/current/schema-registry/develop/api.html
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::*;
+
+/// The content type used by the registry API.
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+
+/// A client for a schema registry.
+pub(crate) struct Client {
+    /// Our HTTP client.
+    client: reqwest::Client,
+    /// The base URL of the registry, without credentials.
+    base_url: Url,
+    /// A username and password for HTTP basic authentication, if we have one.
+    basic_auth: Option<(String, Option<String>)>,
+}
+
+/// A schema to register.
+#[derive(Debug, Serialize)]
+struct SchemaRequest {
+    /// The schema itself, serialized as a JSON string.
+    schema: String,
+}
+
+/// A registered schema version.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaVersion {
+    /// The schema itself, serialized as a JSON string.
+    schema: String,
+    /// The type of the schema. Omitted for Avro schemas.
+    #[serde(default)]
+    schema_type: Option<String>,
+}
+
+/// The result of a compatibility check.
+#[derive(Debug, Deserialize)]
+struct CompatibilityResponse {
+    is_compatible: bool,
+    /// Explanations of any problems. Only returned in verbose mode.
+    #[serde(default)]
+    messages: Vec<String>,
+}
+
+/// The result of registering a schema.
+#[derive(Debug, Deserialize)]
+struct RegisterResponse {
+    /// The globally unique ID of our schema.
+    id: u64,
+}
+
+impl Client {
+    /// Create a new client. We take any credentials from `url`.
+    pub(crate) fn new(url: &UrlWithHiddenPassword, base_url: Url) -> Client {
+        let url = url.with_password();
+        let basic_auth = if url.username().is_empty() {
+            None
+        } else {
+            let decode = |s: &str| {
+                percent_encoding::percent_decode_str(s)
+                    .decode_utf8_lossy()
+                    .into_owned()
+            };
+            Some((decode(url.username()), url.password().map(decode)))
+        };
+        Client {
+            client: reqwest::Client::new(),
+            base_url,
+            basic_auth,
+        }
+    }
+
+    /// Build a URL by appending `segments` to our base URL.
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| format_err!("cannot append path to {}", self.base_url))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// Fetch the latest schema registered for `subject`, or `None` if the
+    /// subject does not exist.
+    pub(crate) async fn latest_schema(
+        &self,
+        ctx: &Context,
+        subject: &str,
+    ) -> Result<Option<Value>> {
+        let url = self.url(&["subjects", subject, "versions", "latest"])?;
+        trace!(ctx.log(), "GET {}", url);
+        let resp = self.send(self.client.get(url.clone())).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let version = check_response(resp, &url)
+            .await?
+            .json::<SchemaVersion>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", url))?;
+        if let Some(schema_type) = &version.schema_type {
+            if schema_type != "AVRO" {
+                return Err(format_err!(
+                    "subject {:?} contains a {} schema, but we only support Avro",
+                    subject,
+                    schema_type,
+                ));
+            }
+        }
+        Ok(Some(serde_json::from_str(&version.schema).with_context(
+            |_| format!("error parsing schema for subject {:?}", subject),
+        )?))
+    }
+
+    /// Check whether `schema` is compatible with the latest version of
+    /// `subject`, using the subject's compatibility mode. Returns a list of
+    /// problems, which will be empty if the schema is compatible.
+    pub(crate) async fn check_compatibility(
+        &self,
+        ctx: &Context,
+        subject: &str,
+        schema: &Value,
+    ) -> Result<Vec<String>> {
+        let mut url =
+            self.url(&["compatibility", "subjects", subject, "versions", "latest"])?;
+        url.query_pairs_mut().append_pair("verbose", "true");
+        trace!(ctx.log(), "POST {}", url);
+        let req =
+            self.client
+                .post(url.clone())
+                .body(serde_json::to_vec(&SchemaRequest {
+                    schema: schema.to_string(),
+                })?);
+        let resp = check_response(self.send(req).await?, &url)
+            .await?
+            .json::<CompatibilityResponse>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", url))?;
+        if resp.is_compatible {
+            Ok(vec![])
+        } else if resp.messages.is_empty() {
+            Ok(vec!["(no details given)".to_owned()])
+        } else {
+            Ok(resp.messages)
+        }
+    }
+
+    /// Register `schema` as a new version of `subject`, and return its ID.
+    pub(crate) async fn register(
+        &self,
+        ctx: &Context,
+        subject: &str,
+        schema: &Value,
+    ) -> Result<u64> {
+        let url = self.url(&["subjects", subject, "versions"])?;
+        trace!(ctx.log(), "POST {}", url);
+        let req =
+            self.client
+                .post(url.clone())
+                .body(serde_json::to_vec(&SchemaRequest {
+                    schema: schema.to_string(),
+                })?);
+        let resp = check_response(self.send(req).await?, &url)
+            .await?
+            .json::<RegisterResponse>()
+            .await
+            .with_context(|_| format!("error parsing response from {}", url))?;
+        Ok(resp.id)
+    }
+
+    /// Add our headers and authentication to `req`, and send it.
+    async fn send(&self, req: RequestBuilder) -> Result<Response> {
+        let mut req = req
+            .header("Accept", CONTENT_TYPE)
+            .header("Content-Type", CONTENT_TYPE);
+        if let Some((username, password)) = &self.basic_auth {
+            req = req.basic_auth(username, password.as_ref());
+        }
+        Ok(req.send().await.context(ErrorKind::Connection {
+            target: self.base_url.to_string(),
+        })?)
+    }
+}
+
+/// Return an error if `resp` was not successful.
+async fn check_response(resp: Response, url: &Url) -> Result<Response> {
+    if resp.status().is_success() {
+        Ok(resp)
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(format_err!("error accessing {}: {} {}", url, status, body))
+    }
+}
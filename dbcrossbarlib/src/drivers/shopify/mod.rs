@@ -6,7 +6,6 @@ use std::{fmt, str::FromStr};
 
 use crate::common::*;
 
-mod json_to_csv;
 mod local_data;
 
 use local_data::local_data_helper;
@@ -87,6 +86,7 @@ impl LocatorStatic for ShopifyLocator {
         Features {
             locator: LocatorFeatures::LocalData.into(),
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
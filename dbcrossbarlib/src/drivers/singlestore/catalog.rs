@@ -0,0 +1,130 @@
+//! Reading table schemas from SingleStore's `information_schema`.
+
+use mysql_async::{prelude::*, Conn};
+
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+/// Fetch the schema of `table_name` in `database` from
+/// `information_schema.columns`.
+///
+/// Returns `None` if no matching table exists.
+pub(crate) async fn fetch_from_conn(
+    ctx: &Context,
+    conn: &mut Conn,
+    database: &str,
+    table_name: &str,
+) -> Result<Option<Schema>> {
+    let columns_sql = r#"
+SELECT column_name, data_type, column_type, is_nullable, column_comment
+FROM information_schema.columns
+WHERE
+    table_schema = ? AND
+    table_name = ?
+ORDER BY ordinal_position
+"#;
+    debug!(
+        ctx.log(),
+        "fetching columns for {}: {}", table_name, columns_sql,
+    );
+    let rows = conn
+        .exec::<(String, String, String, String, String), _, _>(
+            columns_sql,
+            (database, table_name),
+        )
+        .await
+        .with_context(|_| format!("error reading schema of {}", table_name))?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let columns = rows
+        .into_iter()
+        .map(|(name, data_type, column_type, is_nullable, comment)| {
+            let data_type = data_type_from_singlestore(&data_type, &column_type)
+                .with_context(|_| format!("cannot convert column {:?}", name))?;
+            Ok(Column {
+                name,
+                is_nullable: is_nullable == "YES",
+                data_type,
+                comment: if comment.is_empty() {
+                    None
+                } else {
+                    Some(comment)
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let table = Table {
+        name: table_name.to_owned(),
+        columns,
+    };
+    Ok(Some(Schema::from_table(table)?))
+}
+
+/// Convert a SingleStore type to a portable data type. Here, `data_type` is a
+/// base type name like `int`, and `column_type` is the full type, like
+/// `int(10) unsigned`.
+fn data_type_from_singlestore(data_type: &str, column_type: &str) -> Result<DataType> {
+    let data_type = data_type.to_ascii_lowercase();
+    let column_type = column_type.to_ascii_lowercase();
+    let unsigned = column_type.contains("unsigned");
+    match data_type.as_str() {
+        // `BOOL` is an alias for `TINYINT(1)`.
+        "tinyint" if column_type.starts_with("tinyint(1)") => Ok(DataType::Bool),
+        "bool" | "boolean" => Ok(DataType::Bool),
+        "tinyint" => Ok(DataType::Int16),
+        "smallint" if !unsigned => Ok(DataType::Int16),
+        "smallint" | "mediumint" => Ok(DataType::Int32),
+        "int" | "integer" if !unsigned => Ok(DataType::Int32),
+        "int" | "integer" => Ok(DataType::Int64),
+        "bigint" if !unsigned => Ok(DataType::Int64),
+        // Unsigned 64-bit values may not fit in an `int64`.
+        "bigint" | "decimal" | "numeric" => Ok(DataType::Decimal),
+        "float" => Ok(DataType::Float32),
+        "double" | "real" => Ok(DataType::Float64),
+        "date" => Ok(DataType::Date),
+        "datetime" | "timestamp" => Ok(DataType::TimestampWithoutTimeZone),
+        "json" => Ok(DataType::Json),
+        "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" => {
+            Ok(DataType::Text)
+        }
+        // We don't have portable types for these, so treat them as text.
+        "enum" | "set" | "time" | "year" => Ok(DataType::Text),
+        _ => Err(format_err!(
+            "SingleStore driver does not support data type {:?}",
+            column_type,
+        )),
+    }
+}
+
+#[test]
+fn parse_singlestore_types() {
+    let examples = &[
+        ("tinyint", "tinyint(1)", DataType::Bool),
+        ("tinyint", "tinyint(4)", DataType::Int16),
+        ("smallint", "smallint(5) unsigned", DataType::Int32),
+        ("int", "int(11)", DataType::Int32),
+        ("int", "int(10) unsigned", DataType::Int64),
+        ("bigint", "bigint(20)", DataType::Int64),
+        ("bigint", "bigint(20) unsigned", DataType::Decimal),
+        ("decimal", "decimal(10,2)", DataType::Decimal),
+        ("double", "double", DataType::Float64),
+        (
+            "datetime",
+            "datetime(6)",
+            DataType::TimestampWithoutTimeZone,
+        ),
+        ("varchar", "varchar(255)", DataType::Text),
+        ("enum", "enum('a','b')", DataType::Text),
+        ("json", "JSON", DataType::Json),
+    ];
+    for (data_type, column_type, expected) in examples {
+        assert_eq!(
+            &data_type_from_singlestore(data_type, column_type).unwrap(),
+            expected,
+        );
+    }
+    assert!(data_type_from_singlestore("blob", "blob").is_err());
+    assert!(data_type_from_singlestore("geography", "geography").is_err());
+}
@@ -0,0 +1,226 @@
+//! Generating SingleStore `CREATE TABLE` statements.
+
+use itertools::Itertools;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::common::*;
+use crate::schema::DataType;
+
+/// A SingleStore identifier, quoted with backticks.
+struct Ident<'a>(&'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.0.replace('`', "``"))
+    }
+}
+
+/// Quote a SingleStore string literal. Like MySQL, SingleStore treats
+/// backslashes in strings as escape characters.
+fn mysql_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[test]
+fn quoting() {
+    assert_eq!(Ident("a`b").to_string(), "`a``b`");
+    assert_eq!(mysql_quote(r"it's a \ test"), r"'it''s a \\ test'");
+}
+
+/// Table options which can be passed using `--to-arg`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SingleStoreTableOptions {
+    /// Columns to use for `SHARD KEY`.
+    #[serde(default)]
+    shard_key: Vec<String>,
+    /// Columns to use for `SORT KEY`.
+    #[serde(default)]
+    sort_key: Vec<String>,
+}
+
+/// A column in a SingleStore table.
+#[derive(Debug)]
+struct SingleStoreColumn {
+    /// The name of the column.
+    name: String,
+    /// The SingleStore type of the column.
+    data_type: &'static str,
+    /// Can this column be `NULL`?
+    is_nullable: bool,
+    /// A comment describing this column.
+    comment: Option<String>,
+}
+
+impl fmt::Display for SingleStoreColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", Ident(&self.name), self.data_type)?;
+        if !self.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, " COMMENT {}", mysql_quote(comment))?;
+        }
+        Ok(())
+    }
+}
+
+/// A SingleStore `CREATE TABLE` statement.
+#[derive(Debug)]
+pub(crate) struct SingleStoreCreateTable {
+    /// The name of the table.
+    name: String,
+    /// The columns of the table.
+    columns: Vec<SingleStoreColumn>,
+    /// Our `SHARD KEY` and `SORT KEY` options.
+    options: SingleStoreTableOptions,
+    /// Should we generate `CREATE TABLE IF NOT EXISTS`?
+    pub(crate) if_not_exists: bool,
+}
+
+impl SingleStoreCreateTable {
+    /// Build a `CREATE TABLE` statement for `schema`.
+    pub(crate) fn from_schema(
+        schema: &Schema,
+        name: String,
+        options: SingleStoreTableOptions,
+    ) -> Result<Self> {
+        let columns = schema
+            .table
+            .columns
+            .iter()
+            .map(|col| SingleStoreColumn {
+                name: col.name.clone(),
+                data_type: singlestore_type(schema, &col.data_type),
+                is_nullable: col.is_nullable,
+                comment: col.comment.clone(),
+            })
+            .collect::<Vec<_>>();
+        for key_col in options.shard_key.iter().chain(&options.sort_key) {
+            if !columns.iter().any(|c| &c.name == key_col) {
+                return Err(format_err!(
+                    "cannot use unknown column {:?} as a key",
+                    key_col,
+                ));
+            }
+        }
+        Ok(SingleStoreCreateTable {
+            name,
+            columns,
+            options,
+            if_not_exists: false,
+        })
+    }
+
+    /// Generate a `DROP TABLE IF EXISTS` statement for this table.
+    pub(crate) fn drop_if_exists_sql(&self) -> String {
+        format!("DROP TABLE IF EXISTS {}", Ident(&self.name))
+    }
+}
+
+/// The SingleStore type we use to store `data_type`.
+fn singlestore_type(schema: &Schema, data_type: &DataType) -> &'static str {
+    match data_type {
+        // SingleStore has no array or struct types, so store them as JSON.
+        DataType::Array(_)
+        | DataType::GeoJson(_)
+        | DataType::Json
+        | DataType::Struct(_) => "JSON",
+        DataType::Bool => "BOOL",
+        DataType::Date => "DATE",
+        DataType::Decimal => "DECIMAL(65, 30)",
+        DataType::Float32 => "FLOAT",
+        DataType::Float64 => "DOUBLE",
+        DataType::Int16 => "SMALLINT",
+        DataType::Int32 => "INT",
+        DataType::Int64 => "BIGINT",
+        DataType::Named(name) => {
+            singlestore_type(schema, schema.data_type_for_name(name))
+        }
+        DataType::OneOf(_) | DataType::Text => "LONGTEXT",
+        // SingleStore has no time zone support, so we store timestamps with
+        // microsecond precision and leave time zones to the application.
+        DataType::TimestampWithoutTimeZone | DataType::TimestampWithTimeZone => {
+            "DATETIME(6)"
+        }
+        DataType::Uuid => "CHAR(36)",
+    }
+}
+
+impl fmt::Display for SingleStoreCreateTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CREATE TABLE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        writeln!(f, "{} (", Ident(&self.name))?;
+        let mut lines = self
+            .columns
+            .iter()
+            .map(|col| format!("    {}", col))
+            .collect::<Vec<_>>();
+        if !self.options.shard_key.is_empty() {
+            lines.push(format!(
+                "    SHARD KEY ({})",
+                self.options.shard_key.iter().map(|c| Ident(c)).join(", "),
+            ));
+        }
+        if !self.options.sort_key.is_empty() {
+            lines.push(format!(
+                "    SORT KEY ({})",
+                self.options.sort_key.iter().map(|c| Ident(c)).join(", "),
+            ));
+        }
+        writeln!(f, "{}", lines.join(",\n"))?;
+        write!(f, ")")
+    }
+}
+
+#[test]
+fn create_table_sql() {
+    let json = r#"
+{
+  "named_data_types": [],
+  "tables": [{
+    "name": "example",
+    "columns": [
+      { "name": "id", "is_nullable": false, "data_type": "int64", "comment": "Primary key" },
+      { "name": "created_at", "is_nullable": true, "data_type": "timestamp_with_time_zone" },
+      { "name": "tags", "is_nullable": true, "data_type": { "array": "text" } }
+    ]
+  }]
+}
+"#;
+    let schema = serde_json::from_str::<Schema>(json).unwrap();
+    let options =
+        DriverArguments::from_cli_args(&["shard_key[]=id", "sort_key[]=created_at"])
+            .unwrap()
+            .deserialize::<SingleStoreTableOptions>()
+            .unwrap();
+    let mut table =
+        SingleStoreCreateTable::from_schema(&schema, "example".to_owned(), options)
+            .unwrap();
+    table.if_not_exists = true;
+    assert_eq!(
+        table.to_string(),
+        "CREATE TABLE IF NOT EXISTS `example` (
+    `id` BIGINT NOT NULL COMMENT 'Primary key',
+    `created_at` DATETIME(6),
+    `tags` JSON,
+    SHARD KEY (`id`),
+    SORT KEY (`created_at`)
+)",
+    );
+
+    let bad_options = DriverArguments::from_cli_args(&["shard_key[]=missing"])
+        .unwrap()
+        .deserialize::<SingleStoreTableOptions>()
+        .unwrap();
+    assert!(SingleStoreCreateTable::from_schema(
+        &schema,
+        "example".to_owned(),
+        bad_options
+    )
+    .is_err());
+}
@@ -0,0 +1,72 @@
+//! Connecting to Synapse and running SQL.
+
+use tiberius::{AuthMethod, Config};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+use crate::common::*;
+
+/// A connection to Synapse.
+pub(crate) type Client = tiberius::Client<Compat<TcpStream>>;
+
+/// The default port for the TDS protocol.
+const DEFAULT_PORT: u16 = 1433;
+
+/// Connect to the Synapse database specified by `url`.
+pub(crate) async fn connect(
+    ctx: &Context,
+    url: &UrlWithHiddenPassword,
+) -> Result<Client> {
+    debug!(ctx.log(), "connecting to {}", url);
+    let full_url = url.with_password();
+    let decode = |s: &str| -> Result<String> {
+        Ok(percent_encoding::percent_decode_str(s)
+            .decode_utf8()
+            .with_context(|_| format!("non-UTF-8 characters in {}", url))?
+            .into_owned())
+    };
+
+    let mut config = Config::new();
+    config.host(
+        full_url
+            .host_str()
+            .ok_or_else(|| format_err!("no host specified in {}", url))?,
+    );
+    config.port(full_url.port().unwrap_or(DEFAULT_PORT));
+    let database = full_url.path().trim_start_matches('/');
+    if database.is_empty() {
+        return Err(format_err!("no database specified in {}", url));
+    }
+    config.database(decode(database)?);
+    config.authentication(AuthMethod::sql_server(
+        decode(full_url.username())?,
+        decode(full_url.password().unwrap_or(""))?,
+    ));
+
+    let tcp = TcpStream::connect(config.get_addr()).await.context(
+        ErrorKind::Connection {
+            target: url.to_string(),
+        },
+    )?;
+    tcp.set_nodelay(true)?;
+    let client = Client::connect(config, tcp.compat_write()).await.context(
+        ErrorKind::Connection {
+            target: url.to_string(),
+        },
+    )?;
+    Ok(client)
+}
+
+/// Execute `sql` using `client`, logging it first.
+pub(crate) async fn execute(
+    ctx: &Context,
+    client: &mut Client,
+    sql: &str,
+) -> Result<()> {
+    debug!(ctx.log(), "executing SQL: {}", sql);
+    client
+        .execute(sql, &[])
+        .await
+        .with_context(|_| format!("error running SQL: {}", sql))?;
+    Ok(())
+}
@@ -0,0 +1,429 @@
+//! Generating Synapse `CREATE TABLE` statements.
+
+use itertools::Itertools;
+use serde::Deserialize;
+use std::fmt;
+
+use super::name::{sql_quote, Ident, SynapseName};
+use crate::common::*;
+use crate::schema::{Column, DataType};
+
+/// How should Synapse distribute table rows between compute nodes?
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+enum Distribution {
+    /// Distribute rows evenly, without regard to their contents.
+    #[default]
+    RoundRobin,
+    /// Keep a full copy of the table on each compute node.
+    Replicate,
+    /// Distribute rows using a hash of `distribution_column`.
+    Hash,
+}
+
+/// What kind of index should we create for a Synapse table?
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+enum Index {
+    /// A clustered columnstore index, which is the Synapse default.
+    #[default]
+    ClusteredColumnstore,
+    /// No index.
+    Heap,
+    /// A clustered rowstore index on `index_columns`.
+    Clustered,
+}
+
+/// Table options which can be passed to the Synapse driver using `--to-arg`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SynapseTableOptions {
+    /// How to distribute our rows.
+    #[serde(default)]
+    distribution: Distribution,
+    /// The column to hash when using `Distribution::Hash`.
+    distribution_column: Option<String>,
+    /// What kind of index to create.
+    #[serde(default)]
+    index: Index,
+    /// The columns to use for `Index::Clustered`.
+    #[serde(default)]
+    index_columns: Vec<String>,
+}
+
+impl SynapseTableOptions {
+    /// Options for a staging table which we only use during loading.
+    fn for_staging() -> Self {
+        SynapseTableOptions {
+            distribution: Distribution::RoundRobin,
+            distribution_column: None,
+            index: Index::Heap,
+            index_columns: vec![],
+        }
+    }
+
+    /// Make sure these options make sense for `table`.
+    fn validate(&self, table: &Table) -> Result<()> {
+        let check_column = |name: &str| -> Result<()> {
+            if table.columns.iter().any(|c| c.name == name) {
+                Ok(())
+            } else {
+                Err(format_err!("no column named {:?} in table", name))
+            }
+        };
+        match (self.distribution, &self.distribution_column) {
+            (Distribution::Hash, Some(col)) => check_column(col)?,
+            (Distribution::Hash, None) => {
+                return Err(format_err!(
+                    "distribution=hash requires distribution_column=..."
+                ));
+            }
+            (_, Some(_)) => {
+                return Err(format_err!(
+                    "distribution_column=... requires distribution=hash"
+                ));
+            }
+            (_, None) => {}
+        }
+        match (self.index, self.index_columns.is_empty()) {
+            (Index::Clustered, true) => {
+                return Err(format_err!(
+                    "index=clustered requires index_columns[]=..."
+                ));
+            }
+            (Index::Clustered, false) => {
+                for col in &self.index_columns {
+                    check_column(col)?;
+                }
+            }
+            (_, false) => {
+                return Err(format_err!(
+                    "index_columns[]=... requires index=clustered"
+                ));
+            }
+            (_, true) => {}
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SynapseTableOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.distribution, &self.distribution_column) {
+            (Distribution::Hash, Some(col)) => {
+                write!(f, "DISTRIBUTION = HASH({})", Ident(col))?
+            }
+            (Distribution::Replicate, _) => write!(f, "DISTRIBUTION = REPLICATE")?,
+            _ => write!(f, "DISTRIBUTION = ROUND_ROBIN")?,
+        }
+        match self.index {
+            Index::ClusteredColumnstore => {
+                write!(f, ",\n    CLUSTERED COLUMNSTORE INDEX")
+            }
+            Index::Heap => write!(f, ",\n    HEAP"),
+            Index::Clustered => write!(
+                f,
+                ",\n    CLUSTERED INDEX ({})",
+                self.index_columns.iter().map(|c| Ident(c)).join(", "),
+            ),
+        }
+    }
+}
+
+/// A Synapse `CREATE TABLE` statement.
+#[derive(Clone, Debug)]
+pub(crate) struct SynapseCreateTable {
+    /// The name of the table.
+    pub(crate) name: SynapseName,
+    /// The columns in the table.
+    columns: Vec<SynapseColumn>,
+    /// Our distribution and index options.
+    options: SynapseTableOptions,
+}
+
+impl SynapseCreateTable {
+    /// Build a `CREATE TABLE` statement for `schema`.
+    pub(crate) fn from_schema(
+        schema: &Schema,
+        name: SynapseName,
+        options: SynapseTableOptions,
+    ) -> Result<Self> {
+        options.validate(&schema.table)?;
+        let columns = schema
+            .table
+            .columns
+            .iter()
+            .map(|col| SynapseColumn::from_column(schema, col))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(SynapseCreateTable {
+            name,
+            columns,
+            options,
+        })
+    }
+
+    /// Build a staging table named `name` with the same columns as `self`,
+    /// but with every column stored as nullable text. We use this to load raw
+    /// CSV data, which we then convert using `insert_from_staging_sql`.
+    pub(crate) fn staging_table(&self, name: SynapseName) -> Self {
+        let columns = self
+            .columns
+            .iter()
+            .map(|col| SynapseColumn {
+                name: col.name.clone(),
+                data_type: DataType::Text,
+                sql_type: TEXT_TYPE.to_owned(),
+                is_nullable: true,
+            })
+            .collect();
+        SynapseCreateTable {
+            name,
+            columns,
+            options: SynapseTableOptions::for_staging(),
+        }
+    }
+
+    /// SQL to create this table if it does not already exist.
+    pub(crate) fn create_if_not_exists_sql(&self) -> String {
+        format!(
+            "IF OBJECT_ID({name}, N'U') IS NULL\n{create}",
+            name = sql_quote(&self.name.quoted()),
+            create = self,
+        )
+    }
+
+    /// SQL to drop this table if it exists.
+    pub(crate) fn drop_if_exists_sql(&self) -> String {
+        format!(
+            "IF OBJECT_ID({name}, N'U') IS NOT NULL\nDROP TABLE {table}",
+            name = sql_quote(&self.name.quoted()),
+            table = self.name.quoted(),
+        )
+    }
+
+    /// SQL to copy the data in `staging` into this table, converting it from
+    /// our CSV interchange format.
+    pub(crate) fn insert_from_staging_sql(
+        &self,
+        staging: &SynapseCreateTable,
+    ) -> String {
+        format!(
+            "INSERT INTO {dest} ({columns})\nSELECT {exprs}\nFROM {src}",
+            dest = self.name.quoted(),
+            columns = self.columns.iter().map(|c| Ident(&c.name)).join(", "),
+            exprs = self
+                .columns
+                .iter()
+                .map(|c| c.convert_from_text_sql())
+                .join(",\n    "),
+            src = staging.name.quoted(),
+        )
+    }
+}
+
+impl fmt::Display for SynapseCreateTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CREATE TABLE {} (", self.name.quoted())?;
+        for (idx, col) in self.columns.iter().enumerate() {
+            write!(f, "    {}", col)?;
+            if idx + 1 < self.columns.len() {
+                writeln!(f, ",")?;
+            } else {
+                writeln!(f)?;
+            }
+        }
+        write!(f, ")\nWITH (\n    {}\n)", self.options)
+    }
+}
+
+/// The Synapse type we use for text, and for types we represent as text.
+const TEXT_TYPE: &str = "NVARCHAR(MAX)";
+
+/// A column in a Synapse table.
+#[derive(Clone, Debug)]
+struct SynapseColumn {
+    /// The name of this column.
+    name: String,
+    /// The portable type of this column, with any named types resolved.
+    data_type: DataType,
+    /// The Synapse type of this column.
+    sql_type: String,
+    /// Can this column be `NULL`?
+    is_nullable: bool,
+}
+
+impl SynapseColumn {
+    /// Build a Synapse column from a portable column.
+    fn from_column(schema: &Schema, col: &Column) -> Result<Self> {
+        let data_type = resolve_named_type(schema, &col.data_type)?;
+        let sql_type = match &data_type {
+            DataType::Bool => "BIT",
+            DataType::Date => "DATE",
+            // This is the same precision and scale as BigQuery's `NUMERIC`.
+            DataType::Decimal => "DECIMAL(38, 9)",
+            DataType::Float32 => "REAL",
+            DataType::Float64 => "FLOAT",
+            DataType::Int16 => "SMALLINT",
+            DataType::Int32 => "INT",
+            DataType::Int64 => "BIGINT",
+            DataType::TimestampWithoutTimeZone => "DATETIME2",
+            DataType::TimestampWithTimeZone => "DATETIMEOFFSET",
+            DataType::Uuid => "UNIQUEIDENTIFIER",
+            // Synapse has no native array, struct or geometry types, so we
+            // store these as JSON or GeoJSON text.
+            DataType::Array(_)
+            | DataType::GeoJson(_)
+            | DataType::Json
+            | DataType::OneOf(_)
+            | DataType::Struct(_)
+            | DataType::Text => TEXT_TYPE,
+            DataType::Named(_) => unreachable!("named types should be resolved"),
+        };
+        Ok(SynapseColumn {
+            name: col.name.clone(),
+            data_type,
+            sql_type: sql_type.to_owned(),
+            is_nullable: col.is_nullable,
+        })
+    }
+
+    /// A SQL expression which converts this column from text to `sql_type`.
+    fn convert_from_text_sql(&self) -> String {
+        let name = Ident(&self.name);
+        match &self.data_type {
+            _ if self.sql_type == TEXT_TYPE => name.to_string(),
+            // Our CSV interchange format uses `t` and `f`.
+            DataType::Bool => format!(
+                "CASE LOWER({name}) WHEN 't' THEN CAST(1 AS BIT) WHEN 'f' THEN CAST(0 AS BIT) ELSE CAST({name} AS BIT) END",
+                name = name,
+            ),
+            _ => format!("CAST({} AS {})", name, self.sql_type),
+        }
+    }
+}
+
+impl fmt::Display for SynapseColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", Ident(&self.name), self.sql_type)?;
+        if !self.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        Ok(())
+    }
+}
+
+/// Replace any named types in `data_type` with their definitions.
+fn resolve_named_type(schema: &Schema, data_type: &DataType) -> Result<DataType> {
+    match data_type {
+        DataType::Named(name) => {
+            resolve_named_type(schema, schema.data_type_for_name(name))
+        }
+        other => Ok(other.to_owned()),
+    }
+}
+
+#[test]
+fn create_table_sql() {
+    use serde_json::json;
+
+    let schema = Schema::from_table(Table {
+        name: "orders".to_owned(),
+        columns: vec![
+            Column {
+                name: "id".to_owned(),
+                is_nullable: false,
+                data_type: DataType::Int64,
+                comment: None,
+            },
+            Column {
+                name: "paid".to_owned(),
+                is_nullable: true,
+                data_type: DataType::Bool,
+                comment: None,
+            },
+            Column {
+                name: "tags".to_owned(),
+                is_nullable: true,
+                data_type: DataType::Array(Box::new(DataType::Text)),
+                comment: None,
+            },
+        ],
+    })
+    .unwrap();
+    let options = serde_json::from_value::<SynapseTableOptions>(json!({
+        "distribution": "hash",
+        "distribution_column": "id",
+    }))
+    .unwrap();
+    let table = SynapseCreateTable::from_schema(
+        &schema,
+        SynapseName::new("dbo", "orders"),
+        options,
+    )
+    .unwrap();
+    assert_eq!(
+        table.to_string(),
+        r#"CREATE TABLE [dbo].[orders] (
+    [id] BIGINT NOT NULL,
+    [paid] BIT,
+    [tags] NVARCHAR(MAX)
+)
+WITH (
+    DISTRIBUTION = HASH([id]),
+    CLUSTERED COLUMNSTORE INDEX
+)"#,
+    );
+
+    let staging = table.staging_table(SynapseName::new("dbo", "orders_temp"));
+    assert!(staging.to_string().contains("[id] NVARCHAR(MAX),"));
+    assert!(staging.to_string().contains("HEAP"));
+    assert_eq!(
+        table.insert_from_staging_sql(&staging),
+        r#"INSERT INTO [dbo].[orders] ([id], [paid], [tags])
+SELECT CAST([id] AS BIGINT),
+    CASE LOWER([paid]) WHEN 't' THEN CAST(1 AS BIT) WHEN 'f' THEN CAST(0 AS BIT) ELSE CAST([paid] AS BIT) END,
+    [tags]
+FROM [dbo].[orders_temp]"#,
+    );
+}
+
+#[test]
+fn table_options_are_validated() {
+    use serde_json::json;
+
+    let table = Table {
+        name: "t".to_owned(),
+        columns: vec![Column {
+            name: "id".to_owned(),
+            is_nullable: false,
+            data_type: DataType::Int64,
+            comment: None,
+        }],
+    };
+    let invalid = &[
+        json!({ "distribution": "hash" }),
+        json!({ "distribution": "hash", "distribution_column": "missing" }),
+        json!({ "distribution_column": "id" }),
+        json!({ "index": "clustered" }),
+        json!({ "index_columns": ["id"] }),
+    ];
+    for args in invalid {
+        let options =
+            serde_json::from_value::<SynapseTableOptions>(args.clone()).unwrap();
+        assert!(options.validate(&table).is_err(), "should reject {}", args);
+    }
+
+    let options = serde_json::from_value::<SynapseTableOptions>(json!({
+        "distribution": "replicate",
+        "index": "clustered",
+        "index_columns": ["id"],
+    }))
+    .unwrap();
+    options.validate(&table).unwrap();
+    assert_eq!(
+        options.to_string(),
+        "DISTRIBUTION = REPLICATE,\n    CLUSTERED INDEX ([id])",
+    );
+}
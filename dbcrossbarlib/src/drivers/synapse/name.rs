@@ -0,0 +1,96 @@
+//! Synapse identifiers and table names.
+
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+
+/// A Synapse identifier. This will be printed in brackets, with any closing
+/// brackets doubled.
+pub(crate) struct Ident<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0.replace(']', "]]"))
+    }
+}
+
+#[test]
+fn ident_escapes_brackets() {
+    assert_eq!(Ident("a").to_string(), "[a]");
+    assert_eq!(Ident("a]b").to_string(), "[a]]b]");
+}
+
+/// Escape and quote a T-SQL string literal.
+pub(crate) fn sql_quote(s: &str) -> String {
+    format!("N'{}'", s.replace('\'', "''"))
+}
+
+/// A Synapse table name, including a database schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SynapseName {
+    /// The database schema. Defaults to `dbo`.
+    schema: String,
+    /// The name of the table.
+    name: String,
+}
+
+impl SynapseName {
+    /// Create a new table name.
+    pub(crate) fn new<S, N>(schema: S, name: N) -> Self
+    where
+        S: Into<String>,
+        N: Into<String>,
+    {
+        Self {
+            schema: schema.into(),
+            name: name.into(),
+        }
+    }
+
+    /// The database schema for this table.
+    pub(crate) fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// The table name, not including the schema.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Format this name as an unquoted string.
+    pub(crate) fn unquoted(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    /// Format this name as a quoted string, suitable for use in SQL.
+    pub(crate) fn quoted(&self) -> String {
+        format!("{}.{}", Ident(&self.schema), Ident(&self.name))
+    }
+}
+
+impl FromStr for SynapseName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let components = s.splitn(2, '.').collect::<Vec<_>>();
+        let (schema, name) = match components.len() {
+            1 => ("dbo", components[0]),
+            2 => (components[0], components[1]),
+            _ => unreachable!("splitn(2) returned too many components"),
+        };
+        if schema.is_empty() || name.is_empty() {
+            return Err(format_err!("cannot parse Synapse table name {:?}", s));
+        }
+        Ok(SynapseName::new(schema, name))
+    }
+}
+
+#[test]
+fn parse_synapse_names() {
+    let name = "orders".parse::<SynapseName>().unwrap();
+    assert_eq!(name, SynapseName::new("dbo", "orders"));
+    assert_eq!(name.quoted(), "[dbo].[orders]");
+    let name = "sales.orders".parse::<SynapseName>().unwrap();
+    assert_eq!(name.unquoted(), "sales.orders");
+    assert!(".orders".parse::<SynapseName>().is_err());
+}
@@ -0,0 +1,147 @@
+//! Implementation of `SynapseLocator::write_remote_data`.
+
+use super::{
+    client::{connect, execute, Client},
+    create_table::{SynapseCreateTable, SynapseTableOptions},
+    name::{sql_quote, SynapseName},
+    SynapseLocator,
+};
+use crate::clouds::azure::blob;
+use crate::common::*;
+use crate::drivers::azure::AzureLocator;
+
+/// Copy `source` to `dest` using `schema`.
+///
+/// We use `COPY INTO` to load the raw CSV data into a staging table, and then
+/// convert it into the destination table using `INSERT INTO ... SELECT`. This
+/// allows us to handle our CSV interchange format, which Synapse can't always
+/// parse directly.
+pub(crate) async fn write_remote_data_helper(
+    ctx: Context,
+    source: BoxLocator,
+    dest: SynapseLocator,
+    shared_args: SharedArguments<Unverified>,
+    source_args: SourceArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<Vec<BoxLocator>> {
+    // Convert the source locator into the underlying `azure://` URL.
+    let source_url = source
+        .as_any()
+        .downcast_ref::<AzureLocator>()
+        .ok_or_else(|| format_err!("not an azure:// locator: {}", source))?
+        .as_url()
+        .to_owned();
+    let ctx = ctx.child(o!("source_url" => source_url.as_str().to_owned()));
+
+    let shared_args = shared_args.verify(SynapseLocator::features())?;
+    let _source_args = source_args.verify(Features::empty())?;
+    let dest_args = dest_args.verify(SynapseLocator::features())?;
+
+    // Look up our arguments.
+    let schema = shared_args.schema();
+    let options = dest_args
+        .driver_args()
+        .deserialize::<SynapseTableOptions>()
+        .context("could not parse --to-arg")?;
+    let if_exists = dest_args.if_exists().to_owned();
+
+    // Build our tables.
+    let dest_table = SynapseCreateTable::from_schema(
+        schema,
+        dest.table_name().to_owned(),
+        options,
+    )?;
+    let staging_name = SynapseName::new(
+        dest.table_name().schema(),
+        format!(
+            "{}_dbcrossbar_{}",
+            dest.table_name().name(),
+            TemporaryStorage::random_tag(),
+        ),
+    );
+    let staging_table = dest_table.staging_table(staging_name);
+
+    // Prepare our destination table.
+    let mut client = connect(&ctx, dest.url()).await?;
+    match &if_exists {
+        IfExists::Error => {
+            execute(&ctx, &mut client, &dest_table.to_string()).await?;
+        }
+        IfExists::Append => {
+            execute(&ctx, &mut client, &dest_table.create_if_not_exists_sql()).await?;
+        }
+        IfExists::Overwrite => {
+            execute(&ctx, &mut client, &dest_table.drop_if_exists_sql()).await?;
+            execute(&ctx, &mut client, &dest_table.to_string()).await?;
+        }
+        IfExists::Upsert(_) => {
+            return Err(format_err!("Synapse driver does not support upsert"));
+        }
+    }
+
+    // Load our data using a staging table, and make sure we clean it up.
+    execute(&ctx, &mut client, &staging_table.to_string()).await?;
+    let result = async {
+        copy_in(&ctx, &mut client, &source_url, &staging_table.name).await?;
+        execute(
+            &ctx,
+            &mut client,
+            &dest_table.insert_from_staging_sql(&staging_table),
+        )
+        .await
+    }
+    .await;
+    execute(&ctx, &mut client, &staging_table.drop_if_exists_sql()).await?;
+    result?;
+
+    Ok(vec![dest.boxed()])
+}
+
+/// Copy CSV files from Azure Blob Storage into a Synapse table.
+async fn copy_in(
+    ctx: &Context,
+    client: &mut Client,
+    source_url: &Url,
+    dest_table: &SynapseName,
+) -> Result<()> {
+    debug!(
+        ctx.log(),
+        "copying into {} from {}",
+        dest_table.unquoted(),
+        source_url,
+    );
+    let (container, path) = blob::parse_azure_url(source_url)?;
+    let azure_client = blob::Client::new().await?;
+    let https_url = azure_client.https_url(&container, &path)?;
+    let copy_sql = |secret: &str| {
+        format!(
+            r#"COPY INTO {dest}
+FROM {source}
+WITH (
+    FILE_TYPE = 'CSV',
+    CREDENTIAL = (IDENTITY = 'Shared Access Signature', SECRET = {secret}),
+    FIELDQUOTE = '"',
+    FIELDTERMINATOR = ',',
+    ROWTERMINATOR = '0x0A',
+    ENCODING = 'UTF8',
+    FIRSTROW = 2
+)"#,
+            dest = dest_table.quoted(),
+            source = sql_quote(https_url.as_str()),
+            secret = secret,
+        )
+    };
+    // Don't log our SAS token.
+    debug!(ctx.log(), "executing SQL: {}", copy_sql("'XXXXXX'"));
+    client
+        .execute(copy_sql(&sql_quote(azure_client.sas_token())), &[])
+        .await
+        .with_context(|_| {
+            format!(
+                "error copying to {} from {}",
+                dest_table.quoted(),
+                source_url
+            )
+        })?;
+    Ok(())
+}
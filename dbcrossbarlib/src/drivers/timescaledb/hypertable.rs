@@ -0,0 +1,150 @@
+//! Detecting and creating TimescaleDB hypertables.
+
+use serde::Deserialize;
+
+use crate::common::*;
+use crate::drivers::postgres_shared::{pg_quote, Client, PgName};
+
+/// Information about an existing hypertable.
+#[derive(Debug)]
+pub(crate) struct Hypertable {
+    /// The column used for time-based partitioning.
+    pub(crate) time_column: String,
+}
+
+/// Is the TimescaleDB extension installed in the database?
+async fn has_timescaledb(client: &Client) -> Result<bool> {
+    let row = client
+        .query_one(
+            "SELECT COUNT(*) AS count FROM pg_extension WHERE extname = 'timescaledb'",
+            &[],
+        )
+        .await?;
+    let count: i64 = row.get("count");
+    Ok(count > 0)
+}
+
+/// Check whether `table_name` is a hypertable, a chunk of a hypertable, or a
+/// regular table.
+///
+/// Returns an error for chunks, because their data is only part of a logical
+/// table, and returns `Some(hypertable)` for hypertables.
+pub(crate) async fn fetch_hypertable(
+    ctx: &Context,
+    client: &Client,
+    table_name: &PgName,
+) -> Result<Option<Hypertable>> {
+    if !has_timescaledb(client).await? {
+        debug!(ctx.log(), "timescaledb extension is not installed");
+        return Ok(None);
+    }
+    let schema = table_name.schema_or_public();
+    let name = table_name.name();
+
+    // These catalog tables are present in both TimescaleDB 1.x and 2.x.
+    let chunk_sql = r#"
+SELECT h.schema_name, h.table_name
+FROM _timescaledb_catalog.chunk c
+    JOIN _timescaledb_catalog.hypertable h ON h.id = c.hypertable_id
+WHERE c.schema_name = $1 AND c.table_name = $2
+"#;
+    let chunk_rows = client.query(chunk_sql, &[&schema, &name]).await?;
+    if let Some(row) = chunk_rows.first() {
+        let hypertable = PgName::new(row.get::<_, String>(0), row.get::<_, String>(1));
+        return Err(format_err!(
+            "{} is a TimescaleDB chunk, use the hypertable {} instead",
+            table_name.unquoted(),
+            hypertable.unquoted(),
+        ));
+    }
+
+    let dimension_sql = r#"
+SELECT d.column_name
+FROM _timescaledb_catalog.hypertable h
+    JOIN _timescaledb_catalog.dimension d ON d.hypertable_id = h.id
+WHERE
+    h.schema_name = $1 AND
+    h.table_name = $2 AND
+    d.interval_length IS NOT NULL
+"#;
+    let dimension_rows = client.query(dimension_sql, &[&schema, &name]).await?;
+    Ok(dimension_rows.first().map(|row| {
+        let hypertable = Hypertable {
+            time_column: row.get(0),
+        };
+        debug!(
+            ctx.log(),
+            "{} is a hypertable partitioned on {:?}",
+            table_name.unquoted(),
+            hypertable.time_column,
+        );
+        hypertable
+    }))
+}
+
+/// Hypertable options which can be passed using `--to-arg`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HypertableOptions {
+    /// If present, make the destination table a hypertable partitioned on this
+    /// column.
+    time_column: Option<String>,
+    /// The chunk interval to use, as a PostgreSQL interval like `"7 days"`.
+    chunk_time_interval: Option<String>,
+}
+
+impl HypertableOptions {
+    /// Generate SQL to convert `table_name` into a hypertable, or `None` if we
+    /// weren't asked to create a hypertable.
+    pub(crate) fn create_hypertable_sql(
+        &self,
+        table_name: &PgName,
+    ) -> Result<Option<String>> {
+        let time_column = match (&self.time_column, &self.chunk_time_interval) {
+            (Some(time_column), _) => time_column,
+            (None, Some(_)) => {
+                return Err(format_err!(
+                    "chunk_time_interval=... requires time_column=..."
+                ));
+            }
+            (None, None) => return Ok(None),
+        };
+        let mut sql = format!(
+            "SELECT create_hypertable({}, {}",
+            pg_quote(&table_name.quoted().to_string()),
+            pg_quote(time_column),
+        );
+        if let Some(interval) = &self.chunk_time_interval {
+            sql.push_str(&format!(
+                ", chunk_time_interval => INTERVAL {}",
+                pg_quote(interval),
+            ));
+        }
+        sql.push_str(", if_not_exists => TRUE)");
+        Ok(Some(sql))
+    }
+}
+
+#[test]
+fn create_hypertable_sql() {
+    let table_name = "metrics.cpu".parse::<PgName>().unwrap();
+    let parse = |args: &[&str]| {
+        DriverArguments::from_cli_args(args)
+            .unwrap()
+            .deserialize::<HypertableOptions>()
+            .unwrap()
+            .create_hypertable_sql(&table_name)
+    };
+    assert_eq!(parse(&[]).unwrap(), None);
+    assert_eq!(
+        parse(&["time_column=ts"]).unwrap().unwrap(),
+        r#"SELECT create_hypertable('"metrics"."cpu"', 'ts', if_not_exists => TRUE)"#,
+    );
+    assert_eq!(
+        parse(&["time_column=ts", "chunk_time_interval=1 day"])
+            .unwrap()
+            .unwrap(),
+        r#"SELECT create_hypertable('"metrics"."cpu"', 'ts', chunk_time_interval => INTERVAL '1 day', if_not_exists => TRUE)"#,
+    );
+    assert!(parse(&["chunk_time_interval=1 day"]).is_err());
+}
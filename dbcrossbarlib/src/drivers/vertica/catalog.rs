@@ -0,0 +1,118 @@
+//! Reading table schemas from Vertica's `v_catalog`.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::common::*;
+use crate::drivers::postgres_shared::{Client, PgName};
+use crate::schema::{Column, DataType};
+
+/// Fetch the schema of `table_name` from `v_catalog.columns`.
+///
+/// Returns `None` if no matching table exists.
+pub(crate) async fn fetch_from_client(
+    ctx: &Context,
+    client: &Client,
+    table_name: &PgName,
+) -> Result<Option<Schema>> {
+    let columns_sql = r#"
+SELECT column_name, data_type, is_nullable
+FROM v_catalog.columns
+WHERE
+    table_schema = $1 AND
+    table_name = $2
+ORDER BY ordinal_position
+"#;
+    debug!(
+        ctx.log(),
+        "fetching columns for {}: {}",
+        table_name.unquoted(),
+        columns_sql,
+    );
+    let rows = client
+        .query(
+            columns_sql,
+            &[&table_name.schema_or_public(), &table_name.name()],
+        )
+        .await
+        .with_context(|_| {
+            format!("error reading schema of {}", table_name.quoted())
+        })?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let columns = rows
+        .into_iter()
+        .map(|row| {
+            let name: String = row.get("column_name");
+            let vertica_type: String = row.get("data_type");
+            let data_type = data_type_from_vertica(&vertica_type)
+                .with_context(|_| format!("cannot convert column {:?}", name))?;
+            Ok(Column {
+                name,
+                is_nullable: row.get("is_nullable"),
+                data_type,
+                comment: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let table = Table {
+        name: table_name.name().to_owned(),
+        columns,
+    };
+    Ok(Some(Schema::from_table(table)?))
+}
+
+/// Convert a Vertica type name, as found in `v_catalog.columns.data_type`, to a
+/// portable data type.
+fn data_type_from_vertica(vertica_type: &str) -> Result<DataType> {
+    lazy_static! {
+        static ref BASE_TYPE_RE: Regex = Regex::new(r"^([a-z ]+?)\s*(?:\(.*\))?$")
+            .expect("invalid regex in source code");
+    }
+    let lowercase = vertica_type.to_ascii_lowercase();
+    let base_type = BASE_TYPE_RE
+        .captures(&lowercase)
+        .map(|cap| cap[1].to_owned())
+        .ok_or_else(|| format_err!("cannot parse Vertica type {:?}", vertica_type))?;
+    match base_type.as_str() {
+        "boolean" => Ok(DataType::Bool),
+        "date" => Ok(DataType::Date),
+        // Vertica stores all integer types as 64-bit values.
+        "int" | "integer" | "bigint" | "smallint" | "tinyint" | "int8" => {
+            Ok(DataType::Int64)
+        }
+        "float" | "float8" | "double precision" | "real" => Ok(DataType::Float64),
+        "numeric" | "decimal" | "number" | "money" => Ok(DataType::Decimal),
+        "char" | "varchar" | "long varchar" => Ok(DataType::Text),
+        "timestamp" => Ok(DataType::TimestampWithoutTimeZone),
+        "timestamptz" => Ok(DataType::TimestampWithTimeZone),
+        "uuid" => Ok(DataType::Uuid),
+        // We don't have portable types for these, so treat them as text.
+        "time" | "timetz" => Ok(DataType::Text),
+        _ if base_type.starts_with("interval") => Ok(DataType::Text),
+        _ => Err(format_err!(
+            "Vertica driver does not support data type {:?}",
+            vertica_type
+        )),
+    }
+}
+
+#[test]
+fn parse_vertica_types() {
+    let examples = &[
+        ("boolean", DataType::Bool),
+        ("int", DataType::Int64),
+        ("numeric(37,15)", DataType::Decimal),
+        ("varchar(80)", DataType::Text),
+        ("long varchar(1048576)", DataType::Text),
+        ("timestamptz", DataType::TimestampWithTimeZone),
+        ("interval day to second", DataType::Text),
+        ("uuid", DataType::Uuid),
+    ];
+    for (vertica_type, expected) in examples {
+        assert_eq!(&data_type_from_vertica(vertica_type).unwrap(), expected);
+    }
+    assert!(data_type_from_vertica("varbinary(80)").is_err());
+}
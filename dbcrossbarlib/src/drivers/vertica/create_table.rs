@@ -0,0 +1,153 @@
+//! Generating Vertica `CREATE TABLE` statements.
+
+use std::fmt;
+
+use crate::common::*;
+use crate::drivers::postgres_shared::{Ident, PgName};
+use crate::schema::{Column, DataType};
+
+/// A Vertica `CREATE TABLE` statement.
+///
+/// We don't generate any projections, so Vertica will create a default
+/// superprojection when we first load data.
+#[derive(Clone, Debug)]
+pub(crate) struct VerticaCreateTable {
+    /// The name of the table.
+    pub(crate) name: PgName,
+    /// The columns in the table.
+    pub(crate) columns: Vec<VerticaColumn>,
+    /// Should we add `IF NOT EXISTS`?
+    pub(crate) if_not_exists: bool,
+}
+
+impl VerticaCreateTable {
+    /// Build a `CREATE TABLE` statement for `schema`.
+    pub(crate) fn from_schema(schema: &Schema, name: PgName) -> Self {
+        let columns = schema
+            .table
+            .columns
+            .iter()
+            .map(|col| VerticaColumn::from_column(schema, col))
+            .collect();
+        VerticaCreateTable {
+            name,
+            columns,
+            if_not_exists: false,
+        }
+    }
+}
+
+impl fmt::Display for VerticaCreateTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CREATE TABLE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        writeln!(f, "{} (", self.name.quoted())?;
+        for (idx, col) in self.columns.iter().enumerate() {
+            write!(f, "    {}", col)?;
+            if idx + 1 < self.columns.len() {
+                writeln!(f, ",")?;
+            } else {
+                writeln!(f)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// A column in a Vertica table.
+#[derive(Clone, Debug)]
+pub(crate) struct VerticaColumn {
+    /// The name of this column.
+    pub(crate) name: String,
+    /// The Vertica type of this column.
+    sql_type: &'static str,
+    /// Can this column be `NULL`?
+    is_nullable: bool,
+}
+
+impl VerticaColumn {
+    /// Build a Vertica column from a portable column.
+    fn from_column(schema: &Schema, col: &Column) -> Self {
+        VerticaColumn {
+            name: col.name.clone(),
+            sql_type: vertica_type_for(schema, &col.data_type),
+            is_nullable: col.is_nullable,
+        }
+    }
+}
+
+impl fmt::Display for VerticaColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", Ident(&self.name), self.sql_type)?;
+        if !self.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        Ok(())
+    }
+}
+
+/// Choose a Vertica type for `data_type`.
+fn vertica_type_for(schema: &Schema, data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "BOOLEAN",
+        DataType::Date => "DATE",
+        // This is the same precision and scale as BigQuery's `NUMERIC`.
+        DataType::Decimal => "NUMERIC(38, 9)",
+        DataType::Float32 | DataType::Float64 => "FLOAT",
+        // Vertica stores all integers as 64-bit values.
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => "INTEGER",
+        DataType::Named(name) => {
+            vertica_type_for(schema, schema.data_type_for_name(name))
+        }
+        DataType::OneOf(_) | DataType::Text => "VARCHAR(65000)",
+        DataType::TimestampWithoutTimeZone => "TIMESTAMP",
+        DataType::TimestampWithTimeZone => "TIMESTAMPTZ",
+        DataType::Uuid => "UUID",
+        // We store these as JSON or GeoJSON text, which may be long.
+        DataType::Array(_)
+        | DataType::GeoJson(_)
+        | DataType::Json
+        | DataType::Struct(_) => "LONG VARCHAR(32000000)",
+    }
+}
+
+#[test]
+fn create_table_sql() {
+    let schema = Schema::from_table(Table {
+        name: "orders".to_owned(),
+        columns: vec![
+            Column {
+                name: "id".to_owned(),
+                is_nullable: false,
+                data_type: DataType::Int32,
+                comment: None,
+            },
+            Column {
+                name: "note".to_owned(),
+                is_nullable: true,
+                data_type: DataType::Text,
+                comment: None,
+            },
+            Column {
+                name: "extra".to_owned(),
+                is_nullable: true,
+                data_type: DataType::Json,
+                comment: None,
+            },
+        ],
+    })
+    .unwrap();
+    let mut table =
+        VerticaCreateTable::from_schema(&schema, "sales.orders".parse().unwrap());
+    table.if_not_exists = true;
+    assert_eq!(
+        table.to_string(),
+        r#"CREATE TABLE IF NOT EXISTS "sales"."orders" (
+    "id" INTEGER NOT NULL,
+    "note" VARCHAR(65000),
+    "extra" LONG VARCHAR(32000000)
+)"#,
+    );
+}
@@ -0,0 +1,108 @@
+//! Implementation of `write_local_data` for Vertica.
+
+use futures::pin_mut;
+use itertools::Itertools;
+
+use super::{connect, create_table::VerticaCreateTable, VerticaLocator};
+use crate::common::*;
+use crate::drivers::postgres_shared::{Client, Ident};
+use crate::tokio_glue::try_forward;
+
+/// Run `sql` using `client`.
+async fn execute(ctx: &Context, client: &Client, sql: &str) -> Result<()> {
+    debug!(ctx.log(), "executing SQL: {}", sql);
+    client
+        .batch_execute(sql)
+        .await
+        .with_context(|_| format!("error running SQL: {}", sql))?;
+    Ok(())
+}
+
+/// Generate the `COPY ... FROM STDIN` SQL we use to load CSV data into `table`.
+fn copy_from_sql(table: &VerticaCreateTable) -> String {
+    format!(
+        "COPY {} ({}) FROM STDIN PARSER fcsvparser(type='rfc4180', header='true') ABORT ON ERROR",
+        table.name.quoted(),
+        table.columns.iter().map(|c| Ident(&c.name)).join(", "),
+    )
+}
+
+#[test]
+fn copy_from_sql_lists_columns() {
+    use crate::schema::{Column, DataType};
+
+    let schema = Schema::from_table(Table {
+        name: "t".to_owned(),
+        columns: vec![Column {
+            name: "id".to_owned(),
+            is_nullable: false,
+            data_type: DataType::Int64,
+            comment: None,
+        }],
+    })
+    .unwrap();
+    let table = VerticaCreateTable::from_schema(&schema, "t".parse().unwrap());
+    assert_eq!(
+        copy_from_sql(&table),
+        r#"COPY "t" ("id") FROM STDIN PARSER fcsvparser(type='rfc4180', header='true') ABORT ON ERROR"#,
+    );
+}
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+pub(crate) async fn write_local_data_helper(
+    ctx: Context,
+    dest: VerticaLocator,
+    mut data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(VerticaLocator::features())?;
+    let dest_args = dest_args.verify(VerticaLocator::features())?;
+
+    // Look up our arguments.
+    let schema = shared_args.schema();
+    let if_exists = dest_args.if_exists().to_owned();
+    let ctx = ctx.child(o!("table" => dest.table_name().unquoted()));
+    let mut table =
+        VerticaCreateTable::from_schema(schema, dest.table_name().to_owned());
+
+    // Prepare our destination table.
+    let client = connect(&ctx, dest.url()).await?;
+    match &if_exists {
+        IfExists::Error => {}
+        IfExists::Append => table.if_not_exists = true,
+        IfExists::Overwrite => {
+            // `CASCADE` also drops any projections which were created for
+            // this table.
+            let drop_sql =
+                format!("DROP TABLE IF EXISTS {} CASCADE", table.name.quoted());
+            execute(&ctx, &client, &drop_sql).await?;
+        }
+        IfExists::Upsert(_) => {
+            return Err(format_err!("Vertica driver does not support upsert"));
+        }
+    }
+    execute(&ctx, &client, &table.to_string()).await?;
+
+    // Load our data streams one at a time, using a single connection.
+    let fut = async move {
+        let copy_sql = copy_from_sql(&table);
+        while let Some(csv_stream) = data.next().await.transpose()? {
+            let ctx = ctx.child(o!("stream" => csv_stream.name.clone()));
+            debug!(ctx.log(), "copying data: {}", copy_sql);
+            let stmt = client.prepare(&copy_sql).await?;
+            let sink =
+                client
+                    .copy_in::<_, BytesMut>(&stmt)
+                    .await
+                    .with_context(|_| {
+                        format!("error copying data into {}", table.name.quoted())
+                    })?;
+            pin_mut!(sink);
+            try_forward(&ctx, csv_stream.data, sink).await?;
+        }
+        client.release();
+        Ok(dest.boxed())
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
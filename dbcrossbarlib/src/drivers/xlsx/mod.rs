@@ -0,0 +1,79 @@
+//! Driver for writing Excel `.xlsx` workbooks.
+
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+
+mod workbook;
+mod write_local_data;
+
+use self::write_local_data::write_local_data_helper;
+
+/// An Excel workbook, or standard output.
+#[derive(Clone, Debug)]
+pub(crate) struct XlsxLocator {
+    path: PathOrStdio,
+}
+
+impl fmt::Display for XlsxLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt_locator_helper(Self::scheme(), f)
+    }
+}
+
+impl FromStr for XlsxLocator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = PathOrStdio::from_str_locator_helper(Self::scheme(), s)?;
+        Ok(XlsxLocator { path })
+    }
+}
+
+impl Locator for XlsxLocator {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn display_output_locators(&self) -> DisplayOutputLocators {
+        match &self.path {
+            // Don't print "xlsx:-" after our workbook on standard output.
+            PathOrStdio::Stdio => DisplayOutputLocators::Never,
+            _ => DisplayOutputLocators::IfRequested,
+        }
+    }
+
+    fn write_local_data(
+        &self,
+        ctx: Context,
+        data: BoxStream<CsvStream>,
+        shared_args: SharedArguments<Unverified>,
+        dest_args: DestinationArguments<Unverified>,
+    ) -> BoxFuture<BoxStream<BoxFuture<BoxLocator>>> {
+        write_local_data_helper(ctx, self.to_owned(), data, shared_args, dest_args)
+            .boxed()
+    }
+}
+
+impl LocatorStatic for XlsxLocator {
+    fn scheme() -> &'static str {
+        "xlsx:"
+    }
+
+    fn features() -> Features {
+        Features {
+            locator: LocatorFeatures::WriteLocalData.into(),
+            write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
+            source_args: EnumSet::empty(),
+            dest_args: EnumSet::empty(),
+            dest_if_exists: IfExistsFeatures::no_append(),
+            _placeholder: (),
+        }
+    }
+
+    /// This locator type is currently unstable.
+    fn is_unstable() -> bool {
+        true
+    }
+}
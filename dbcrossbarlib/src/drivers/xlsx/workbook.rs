@@ -0,0 +1,355 @@
+//! A minimal `.xlsx` writer.
+//!
+//! An `.xlsx` file is a zip archive containing a handful of XML files. We only
+//! need to write a single sheet with a header row and some typed cells, so we
+//! generate the XML ourselves instead of pulling in a full spreadsheet library.
+//!
+//! Spec: ECMA-376, "Office Open XML File Formats", Part 1.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::io::Seek;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::common::*;
+use crate::from_csv_cell::FromCsvCell;
+use crate::schema::DataType;
+
+/// The maximum length of a sheet name.
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// The largest integer we can store in a cell without losing precision. Excel
+/// stores all numbers as `f64`.
+const MAX_EXACT_INTEGER: i64 = 1 << 53;
+
+/// Style index for bold header cells. See `STYLES_XML`.
+const HEADER_STYLE: usize = 1;
+
+/// Style index for date cells. See `STYLES_XML`.
+const DATE_STYLE: usize = 2;
+
+/// Style index for timestamp cells. See `STYLES_XML`.
+const TIMESTAMP_STYLE: usize = 3;
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>
+"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>
+"#;
+
+const WORKBOOK_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>
+"#;
+
+/// Our cell styles. The entries in `cellXfs` are referred to by index, so keep
+/// them in sync with `HEADER_STYLE`, `DATE_STYLE` and `TIMESTAMP_STYLE`.
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><numFmts count="2"><numFmt numFmtId="164" formatCode="yyyy-mm-dd"/><numFmt numFmtId="165" formatCode="yyyy-mm-dd hh:mm:ss"/></numFmts><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="4"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/><xf numFmtId="164" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/><xf numFmtId="165" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>
+"#;
+
+/// Write `csv_data` to `wtr` as an `.xlsx` workbook with a single sheet named
+/// after our table. The first row of `csv_data` contains the column names.
+pub(crate) fn write_workbook<W: Write + Seek>(
+    wtr: W,
+    schema: &Schema,
+    csv_data: &[u8],
+) -> Result<W> {
+    let options =
+        FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(wtr);
+
+    zip.start_file("[Content_Types].xml", options)?;
+    zip.write_all(CONTENT_TYPES_XML.as_bytes())?;
+    zip.start_file("_rels/.rels", options)?;
+    zip.write_all(ROOT_RELS_XML.as_bytes())?;
+    zip.start_file("xl/_rels/workbook.xml.rels", options)?;
+    zip.write_all(WORKBOOK_RELS_XML.as_bytes())?;
+    zip.start_file("xl/styles.xml", options)?;
+    zip.write_all(STYLES_XML.as_bytes())?;
+
+    zip.start_file("xl/workbook.xml", options)?;
+    write!(
+        zip,
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>
+"#,
+        xml_escape(&sheet_name(&schema.table.name)),
+    )?;
+
+    zip.start_file("xl/worksheets/sheet1.xml", options)?;
+    write_sheet(&mut zip, schema, csv_data)?;
+
+    Ok(zip.finish()?)
+}
+
+/// Write the XML for a worksheet containing `csv_data`.
+fn write_sheet(wtr: &mut dyn Write, schema: &Schema, csv_data: &[u8]) -> Result<()> {
+    let columns = &schema.table.columns;
+    write!(
+        wtr,
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    )?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(csv_data);
+    for (idx, record) in rdr.records().enumerate() {
+        let record = record?;
+        if record.len() != columns.len() {
+            return Err(format_err!(
+                "expected {} columns, found {}",
+                columns.len(),
+                record.len(),
+            ));
+        }
+        let row_number = idx + 1;
+        write!(wtr, r#"<row r="{}">"#, row_number)?;
+        for (col_idx, (cell, col)) in record.iter().zip(columns).enumerate() {
+            let cell_ref = format!("{}{}", column_letters(col_idx), row_number);
+            if idx == 0 {
+                write_string_cell(wtr, &cell_ref, Some(HEADER_STYLE), cell)?;
+            } else if !cell.is_empty() {
+                // `idx` is also our row number in the data, because the
+                // header is row 0.
+                write_typed_cell(wtr, &cell_ref, &col.data_type, cell).with_context(
+                    |_| ErrorKind::InvalidData {
+                        row: idx,
+                        column: col.name.clone(),
+                        value: cell.to_owned(),
+                    },
+                )?;
+            }
+        }
+        write!(wtr, "</row>")?;
+    }
+    writeln!(wtr, "</sheetData></worksheet>")?;
+    Ok(())
+}
+
+/// Write a non-empty CSV cell, converting it to a number, boolean or date if
+/// its column has an appropriate type.
+fn write_typed_cell(
+    wtr: &mut dyn Write,
+    cell_ref: &str,
+    data_type: &DataType,
+    cell: &str,
+) -> Result<()> {
+    match data_type {
+        DataType::Bool => {
+            let value = if bool::from_csv_cell(cell)? { 1 } else { 0 };
+            write!(wtr, r#"<c r="{}" t="b"><v>{}</v></c>"#, cell_ref, value)?;
+        }
+        DataType::Date => {
+            let date = NaiveDate::from_csv_cell(cell)?;
+            write_number_cell(wtr, cell_ref, Some(DATE_STYLE), date_serial(date))?;
+        }
+        DataType::Decimal | DataType::Float32 | DataType::Float64 => {
+            let value = f64::from_csv_cell(cell)?;
+            if value.is_finite() {
+                write_number_cell(wtr, cell_ref, None, value)?;
+            } else {
+                // Excel has no way to represent these as numbers.
+                write_string_cell(wtr, cell_ref, None, cell)?;
+            }
+        }
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            let value = i64::from_csv_cell(cell)?;
+            if (-MAX_EXACT_INTEGER..=MAX_EXACT_INTEGER).contains(&value) {
+                // We've checked that this conversion is exact.
+                #[allow(clippy::cast_precision_loss)]
+                let number = value as f64;
+                write_number_cell(wtr, cell_ref, None, number)?;
+            } else {
+                // Don't silently round large IDs.
+                write_string_cell(wtr, cell_ref, None, cell)?;
+            }
+        }
+        DataType::TimestampWithoutTimeZone => {
+            let timestamp = NaiveDateTime::from_csv_cell(cell)?;
+            let serial = timestamp_serial(timestamp);
+            write_number_cell(wtr, cell_ref, Some(TIMESTAMP_STYLE), serial)?;
+        }
+        DataType::TimestampWithTimeZone => {
+            // Excel has no time zones, so we write these in UTC.
+            let timestamp = DateTime::<Utc>::from_csv_cell(cell)?;
+            let serial = timestamp_serial(timestamp.naive_utc());
+            write_number_cell(wtr, cell_ref, Some(TIMESTAMP_STYLE), serial)?;
+        }
+        _ => write_string_cell(wtr, cell_ref, None, cell)?,
+    }
+    Ok(())
+}
+
+/// Write a numeric cell.
+fn write_number_cell(
+    wtr: &mut dyn Write,
+    cell_ref: &str,
+    style: Option<usize>,
+    value: f64,
+) -> Result<()> {
+    write!(
+        wtr,
+        r#"<c r="{}"{}><v>{}</v></c>"#,
+        cell_ref,
+        style_attr(style),
+        value
+    )?;
+    Ok(())
+}
+
+/// Write a string cell. We use inline strings, so that we don't need to build
+/// a shared string table.
+fn write_string_cell(
+    wtr: &mut dyn Write,
+    cell_ref: &str,
+    style: Option<usize>,
+    value: &str,
+) -> Result<()> {
+    write!(
+        wtr,
+        r#"<c r="{}"{} t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+        cell_ref,
+        style_attr(style),
+        xml_escape(value),
+    )?;
+    Ok(())
+}
+
+/// Format an optional style index as an XML attribute.
+fn style_attr(style: Option<usize>) -> String {
+    match style {
+        Some(style) => format!(r#" s="{}""#, style),
+        None => "".to_owned(),
+    }
+}
+
+/// Convert a zero-based column index to Excel-style letters: `A`, `B`, ...,
+/// `Z`, `AA`, `AB`, etc.
+fn column_letters(mut idx: usize) -> String {
+    let mut letters = vec![];
+    loop {
+        letters.push(b'A' + u8::try_from(idx % 26).expect("always less than 26"));
+        if idx < 26 {
+            break;
+        }
+        idx = idx / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).expect("column letters should be ASCII")
+}
+
+#[test]
+fn column_letters_are_base_26() {
+    assert_eq!(column_letters(0), "A");
+    assert_eq!(column_letters(25), "Z");
+    assert_eq!(column_letters(26), "AA");
+    assert_eq!(column_letters(27), "AB");
+    assert_eq!(column_letters(701), "ZZ");
+    assert_eq!(column_letters(702), "AAA");
+}
+
+/// Convert a date to an Excel date serial number. Excel counts days from
+/// 1899-12-30, thanks to a historical bug involving 1900 leap years.
+#[allow(clippy::cast_precision_loss)]
+fn date_serial(date: NaiveDate) -> f64 {
+    (date - NaiveDate::from_ymd(1899, 12, 30)).num_days() as f64
+}
+
+/// Convert a timestamp to an Excel date serial number, where the fractional
+/// part represents the time of day.
+#[allow(clippy::cast_precision_loss)]
+fn timestamp_serial(timestamp: NaiveDateTime) -> f64 {
+    let epoch = NaiveDate::from_ymd(1899, 12, 30).and_hms(0, 0, 0);
+    let duration = timestamp - epoch;
+    let millis = duration.num_milliseconds() as f64;
+    millis / (24.0 * 60.0 * 60.0 * 1000.0)
+}
+
+#[test]
+fn serials_match_excel() {
+    assert_eq!(date_serial(NaiveDate::from_ymd(1900, 3, 1)), 61.0);
+    assert_eq!(date_serial(NaiveDate::from_ymd(2021, 3, 4)), 44259.0);
+    let timestamp = NaiveDate::from_ymd(2021, 3, 4).and_hms(18, 0, 0);
+    assert_eq!(timestamp_serial(timestamp), 44259.75);
+}
+
+/// Build a valid sheet name from a table name. Sheet names may not contain
+/// certain punctuation, and they may only be 31 characters long.
+fn sheet_name(table_name: &str) -> String {
+    let name = table_name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .take(MAX_SHEET_NAME_LEN)
+        .collect::<String>();
+    if name.is_empty() {
+        "Sheet1".to_owned()
+    } else {
+        name
+    }
+}
+
+#[test]
+fn sheet_names_are_valid() {
+    assert_eq!(sheet_name("public.users"), "public.users");
+    assert_eq!(sheet_name("a/b:c"), "a_b_c");
+    assert_eq!(sheet_name(""), "Sheet1");
+    assert_eq!(sheet_name(&"x".repeat(40)).len(), MAX_SHEET_NAME_LEN);
+}
+
+/// Escape a string for use in XML text or attributes. XML 1.0 doesn't allow
+/// most control characters, even escaped, so we drop them.
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn write_sheet_uses_typed_cells() {
+    use crate::schema::{Column, Table};
+
+    let column = |name: &str, data_type| Column {
+        name: name.to_owned(),
+        is_nullable: true,
+        data_type,
+        comment: None,
+    };
+    let schema = Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: vec![
+            column("id", DataType::Int64),
+            column("ok", DataType::Bool),
+            column("day", DataType::Date),
+            column("name", DataType::Text),
+        ],
+    })
+    .unwrap();
+    let mut xml = vec![];
+    write_sheet(
+        &mut xml,
+        &schema,
+        b"id,ok,day,name\n1,t,2021-03-04,<Jane & co>\n,,,\n",
+    )
+    .unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains(r#"<c r="A2"><v>1</v></c>"#));
+    assert!(xml.contains(r#"<c r="B2" t="b"><v>1</v></c>"#));
+    assert!(xml.contains(r#"<c r="C2" s="2"><v>44259</v></c>"#));
+    assert!(xml.contains("&lt;Jane &amp; co&gt;"));
+    assert!(xml.contains(r#"<row r="3"></row>"#));
+}
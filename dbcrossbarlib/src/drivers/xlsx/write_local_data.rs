@@ -0,0 +1,54 @@
+//! Writing data to Excel workbooks.
+
+use std::io::Cursor;
+
+use super::{workbook::write_workbook, XlsxLocator};
+use crate::common::*;
+use crate::concat::concatenate_csv_streams;
+
+/// Implementation of `write_local_data`, but as a real `async` function.
+pub(crate) async fn write_local_data_helper(
+    ctx: Context,
+    dest: XlsxLocator,
+    data: BoxStream<CsvStream>,
+    shared_args: SharedArguments<Unverified>,
+    dest_args: DestinationArguments<Unverified>,
+) -> Result<BoxStream<BoxFuture<BoxLocator>>> {
+    let shared_args = shared_args.verify(XlsxLocator::features())?;
+    let dest_args = dest_args.verify(XlsxLocator::features())?;
+    let schema = shared_args.schema().to_owned();
+    let if_exists = dest_args.if_exists().to_owned();
+    if dest.path == PathOrStdio::Stdio {
+        if_exists.warn_if_not_default_for_stdout(&ctx);
+    }
+
+    // A workbook is a zip file, which we can't easily build incrementally, so
+    // gather all our data into a single stream.
+    let stream = concatenate_csv_streams(ctx.clone(), data)?;
+    let fut = async move {
+        let csv_data = stream
+            .data
+            .try_fold(vec![], |mut buf, bytes| async move {
+                buf.extend_from_slice(&bytes);
+                Ok(buf)
+            })
+            .await?;
+
+        // `zip` is synchronous, so build our workbook on a helper thread.
+        let workbook = spawn_blocking(move || {
+            let cursor = write_workbook(Cursor::new(vec![]), &schema, &csv_data)?;
+            Ok(cursor.into_inner())
+        })
+        .await
+        .with_context(|_| format!("error preparing data for {}", dest))?;
+
+        debug!(ctx.log(), "writing {} bytes to {}", workbook.len(), dest);
+        let mut f = dest.path.create_async(ctx.clone(), if_exists).await?;
+        f.write_all(&workbook)
+            .await
+            .with_context(|_| format!("error writing to {}", dest.path))?;
+        f.flush().await?;
+        Ok(dest.boxed())
+    };
+    Ok(box_stream_once(Ok(fut.boxed())))
+}
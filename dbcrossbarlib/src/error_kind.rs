@@ -0,0 +1,112 @@
+//! Specific kinds of errors which callers may want to detect.
+//!
+//! Most of our errors are plain `failure::Error` values with a human-readable
+//! context chain. But in a few important cases, we attach an `ErrorKind` to the
+//! chain, so that programs using this library can find out _what_ went wrong
+//! without parsing error messages.
+
+// `#[derive(Fail)]` generates its impls inside a `const` block.
+#![allow(non_local_definitions)]
+
+use failure::Fail;
+
+use crate::common::*;
+
+/// A specific kind of error. Use [`ErrorKind::of`] to find the `ErrorKind`
+/// associated with an [`Error`], if there is one.
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum ErrorKind {
+    /// We could not connect to a database or other service.
+    #[fail(display = "could not connect to {}", target)]
+    Connection {
+        /// The database or service we tried to connect to, with any passwords
+        /// removed.
+        target: String,
+    },
+
+    /// We found a column type that we don't know how to handle.
+    #[fail(display = "unsupported data type {} in column {:?}", type_name, column)]
+    UnsupportedType {
+        /// The name of the column.
+        column: String,
+        /// The name of the unsupported type, as reported by the database.
+        type_name: String,
+    },
+
+    /// The driver arguments passed using `--from-arg` or `--to-arg` were not
+    /// valid.
+    #[fail(display = "invalid driver arguments: {}", message)]
+    DriverArguments {
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// A value in our input data could not be converted.
+    #[fail(
+        display = "could not convert row {}, column {} ({:?})",
+        row, column, value
+    )]
+    InvalidData {
+        /// The 1-based row number, not counting any header row. So the first
+        /// row of data is row 1, even if it's on line 2 of a CSV file.
+        row: usize,
+        /// The name of the column.
+        column: String,
+        /// The value we could not convert.
+        value: String,
+    },
+
+    /// We were interrupted by a signal, such as `SIGINT` or `SIGTERM`.
+    #[fail(display = "cancelled by {}", signal)]
+    Cancelled {
+        /// The name of the signal, such as `"SIGINT"`.
+        signal: String,
+    },
+}
+
+impl ErrorKind {
+    /// Search the cause chain of `err` for an `ErrorKind`. This will find an
+    /// `ErrorKind` no matter whether it was returned directly or attached
+    /// using `.context(...)`.
+    pub fn of(err: &Error) -> Option<&ErrorKind> {
+        err.iter_chain().find_map(|cause| {
+            cause.downcast_ref::<ErrorKind>().or_else(|| {
+                cause
+                    .downcast_ref::<failure::Context<ErrorKind>>()
+                    .map(|ctx| ctx.get_context())
+            })
+        })
+    }
+}
+
+#[test]
+fn error_kind_is_found_in_cause_chain() {
+    let direct: Error = ErrorKind::DriverArguments {
+        message: "bad".to_owned(),
+    }
+    .into();
+    assert_eq!(
+        ErrorKind::of(&direct),
+        Some(&ErrorKind::DriverArguments {
+            message: "bad".to_owned()
+        }),
+    );
+
+    let io_err = std::io::Error::other("refused");
+    let wrapped: Error = Err::<(), _>(io_err)
+        .context(ErrorKind::Connection {
+            target: "postgres://localhost/db".to_owned(),
+        })
+        .context("could not read schema")
+        .unwrap_err()
+        .into();
+    assert_eq!(
+        ErrorKind::of(&wrapped),
+        Some(&ErrorKind::Connection {
+            target: "postgres://localhost/db".to_owned(),
+        }),
+    );
+
+    let plain = format_err!("something else");
+    assert_eq!(ErrorKind::of(&plain), None);
+}
@@ -46,9 +46,10 @@ impl fmt::Display for DisplayEnumSet<IfExistsFeatures> {
 }
 
 /// What to do if the destination already exists.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub enum IfExists {
     /// If the destination exists, return an error.
+    #[default]
     Error,
     /// If the destination exists, try to append the new data.
     Append,
@@ -121,12 +122,6 @@ impl IfExists {
     }
 }
 
-impl Default for IfExists {
-    fn default() -> Self {
-        IfExists::Error
-    }
-}
-
 /// The prefix used for the serialized version of `IfExists::Upsert`.
 const UPSERT_PREFIX: &str = "upsert-on:";
 
@@ -28,7 +28,7 @@ pub(crate) fn write_rows<W: Write>(
 }
 
 /// Write a JSON row to a CSV document.
-fn write_row<W: Write>(
+pub(crate) fn write_row<W: Write>(
     wtr: &mut csv::Writer<W>,
     schema: &Schema,
     row: Value,
@@ -44,7 +44,7 @@ fn write_row<W: Write>(
     for col in &schema.table.columns {
         let value = obj.get(&col.name).unwrap_or(&Value::Null);
         buffer.clear();
-        write_json_value(buffer, schema, &col.data_type, &value)?;
+        write_json_value(buffer, schema, &col.data_type, value)?;
         if !col.is_nullable && buffer.is_empty() {
             return Err(format_err!(
                 "unexpected NULL value in column {:?}",
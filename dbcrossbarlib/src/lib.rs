@@ -31,28 +31,45 @@
 use std::result;
 
 pub(crate) mod args;
+pub(crate) mod cancellation;
 pub(crate) mod clouds;
+pub(crate) mod column_mapping;
 pub(crate) mod concat;
 pub mod config;
 pub(crate) mod context;
+pub(crate) mod copy_operation;
 pub(crate) mod credentials;
 pub(crate) mod csv_stream;
+pub(crate) mod date_formats;
+pub(crate) mod dedup;
 mod driver_args;
 pub mod drivers;
+pub(crate) mod error_kind;
 pub(crate) mod from_csv_cell;
 pub(crate) mod from_json_value;
 pub(crate) mod if_exists;
+pub(crate) mod json_to_csv;
 pub(crate) mod locator;
-pub(crate) mod parse_error;
+pub(crate) mod masking;
+pub mod metrics;
 pub(crate) mod path_or_stdio;
 pub mod rechunk;
-pub mod schema;
-pub(crate) mod separator;
+pub(crate) mod sampling;
+pub mod schema_diff;
+pub mod schema_lint;
+pub mod summary;
+pub mod telemetry;
 mod temporary_storage;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub(crate) mod throttle;
 pub mod tokio_glue;
 pub(crate) mod transform;
 mod url_with_hidden_password;
 
+pub use dbcrossbarschema::schema;
+pub(crate) use dbcrossbarschema::{parse_error, separator};
+
 /// Standard error type for this library.
 pub use failure::Error;
 
@@ -66,11 +83,18 @@ pub use args::{
     ArgumentState, DestinationArguments, SharedArguments, SourceArguments, Unverified,
     Verified,
 };
+pub use column_mapping::ColumnMapping;
 pub use context::Context;
+pub use copy_operation::CopyOperation;
 pub use csv_stream::CsvStream;
+pub use date_formats::DateFormats;
+pub use dedup::Deduplication;
 pub use driver_args::DriverArguments;
+pub use error_kind::ErrorKind;
 pub use if_exists::IfExists;
 pub use locator::{BoxLocator, DisplayOutputLocators, Locator, UnparsedLocator};
+pub use masking::Masking;
+pub use sampling::Sampling;
 pub use temporary_storage::TemporaryStorage;
 pub use tokio_glue::{run_futures_with_runtime, ConsumeWithParallelism};
 
@@ -116,6 +140,7 @@ pub(crate) mod common {
         context::Context,
         csv_stream::CsvStream,
         driver_args::DriverArguments,
+        error_kind::ErrorKind,
         if_exists::{IfExists, IfExistsFeatures},
         locator::{
             BoxLocator, DisplayOutputLocators, Features, Locator, LocatorFeatures,
@@ -6,7 +6,7 @@ use std::{fmt, marker::PhantomData, str::FromStr};
 
 use crate::args::EnumSetExt;
 use crate::common::*;
-use crate::drivers::find_driver;
+use crate::drivers::{external::ExternalLocator, find_driver};
 
 /// When called from the CLI, should we display a list of individual locators
 /// for each data stream?
@@ -53,18 +53,65 @@ pub trait Locator: fmt::Debug + fmt::Display + Send + Sync + 'static {
         async { Ok(None) }.boxed()
     }
 
+    /// Return the schema of the rows returned by an SQL `query`, if this
+    /// driver supports `--from-query`.
+    fn query_schema(
+        &self,
+        _ctx: Context,
+        _query: String,
+    ) -> BoxFuture<Option<Schema>> {
+        async { Ok(None) }.boxed()
+    }
+
+    /// If this locator is a destination table which already exists, return
+    /// its current schema. Returns `None` if the table doesn't exist, or if this
+    /// driver doesn't know how to check.
+    ///
+    /// This is used to check that we can load data into a table before we
+    /// start copying it.
+    fn existing_schema(&self, _ctx: Context) -> BoxFuture<Option<Schema>> {
+        async { Ok(None) }.boxed()
+    }
+
+    /// If this locator contains a wildcard pattern, return the name and
+    /// locator of each matching table, in a stable order. Returns `None` if
+    /// this locator doesn't contain a wildcard, or if this driver doesn't
+    /// support them.
+    ///
+    /// This is used to copy several tables at once.
+    fn matching_tables(
+        &self,
+        _ctx: Context,
+    ) -> BoxFuture<Option<Vec<(String, BoxLocator)>>> {
+        async { Ok(None) }.boxed()
+    }
+
     /// Write a table schema to this locator, if that's the sort of thing that
     /// we can do.
     fn write_schema(
         &self,
         _ctx: Context,
         _schema: Schema,
-        _if_exists: IfExists,
+        _dest_args: DestinationArguments<Unverified>,
     ) -> BoxFuture<()> {
         let err = format_err!("cannot write schema to {}", self);
         async move { Err(err) }.boxed()
     }
 
+    /// Describe the SQL statements or jobs that `write_schema` would run,
+    /// without running them or connecting to anything. This is used to
+    /// implement `schema apply --dry-run`.
+    ///
+    /// Returns `None` if this driver can't describe what it would do.
+    fn write_schema_dry_run(
+        &self,
+        _ctx: &Context,
+        _schema: &Schema,
+        _dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+
     /// Count the records specified by this locator.
     fn count(
         &self,
@@ -138,6 +185,12 @@ pub trait Locator: fmt::Debug + fmt::Display + Send + Sync + 'static {
         async move { Err(err) }.boxed()
     }
 
+    /// Can this locator sample rows itself when reading data, using
+    /// `SourceArguments::sampling`? If not, we'll sample the rows locally.
+    fn supports_sampling(&self) -> bool {
+        false
+    }
+
     /// Can we access the data at `source` directly using `write_remote_data`?
     fn supports_write_remote_data(&self, _source: &dyn Locator) -> bool {
         false
@@ -159,6 +212,21 @@ pub trait Locator: fmt::Debug + fmt::Display + Send + Sync + 'static {
         let err = format_err!("cannot write_remote_data from source {}", source);
         async move { Err(err) }.boxed()
     }
+
+    /// Describe the SQL statements or jobs that we would run to copy data from
+    /// `source` to this locator, without running them or connecting to
+    /// anything. This is used to implement `--dry-run`.
+    ///
+    /// Returns `None` if this driver can't describe what it would do.
+    fn write_dry_run(
+        &self,
+        _ctx: &Context,
+        _source: &dyn Locator,
+        _shared_args: SharedArguments<Unverified>,
+        _dest_args: DestinationArguments<Unverified>,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
 /// A value of an unknown type implementing `Locator`.
@@ -175,14 +243,29 @@ fn parse_locator(s: &str, enable_unstable: bool) -> Result<BoxLocator> {
         .ok_or_else(|| format_err!("cannot parse locator: {:?}", s))?;
     let scheme = &cap[0];
 
-    // Select an appropriate locator type.
-    let driver = find_driver(scheme, enable_unstable)?;
-    driver.parse(s)
+    // Select an appropriate locator type. If we don't have a built-in driver,
+    // look for an external one.
+    match find_driver(scheme, enable_unstable) {
+        Ok(driver) => driver.parse(s),
+        Err(err) => {
+            if enable_unstable {
+                if let Some(locator) = ExternalLocator::find(scheme, s) {
+                    return Ok(Box::new(locator));
+                }
+            }
+            Err(err)
+        }
+    }
 }
 
 #[test]
 fn locator_from_str_to_string_roundtrip() {
     let locators = vec![
+        "arrow:file.arrows",
+        "arrow:dir/",
+        "avro:file.avro",
+        "avro:dir/",
+        "azure://container/dir/",
         "bigquery:my_project:my_dataset.my_table",
         "bigquery-schema:dir/my_table.json",
         "bigml:dataset",
@@ -192,13 +275,35 @@ fn locator_from_str_to_string_roundtrip() {
         "bigml:sources",
         "csv:file.csv",
         "csv:dir/",
+        "databricks-sql:dir/my_table.sql",
         "dbcrossbar-schema:file.json",
         "dbcrossbar-ts:file %231 20%25.ts#Type",
+        "delta:dir/orders/",
+        "delta:s3://example/orders/",
+        "druid-spec:spec.json",
+        "elasticsearch-mapping:mapping.json",
+        "fixed-width:file.txt",
+        "fixed-width:dir/",
+        "greenplum://localhost:5432/db#my_table",
         "gs://example-bucket/tmp/",
+        "gsheet:1aBcD#Sheet1",
+        "iceberg:metadata/v1.metadata.json",
+        "iceberg:https://catalog.example.com/v1/namespaces/db/tables/orders",
+        "ndjson:file.ndjson",
+        "ndjson:dir/",
+        "parquet:file.parquet",
+        "parquet:dir/",
         "postgres://localhost:5432/db#my_table",
         "postgres-sql:dir/my_table.sql",
         "s3://example/my-dir/",
+        "salesforce:Account",
+        "schema-registry:https://registry.example.com/subjects/orders-value",
         "shopify://example.myshopify.com/admin/api/2020-04/orders.json",
+        "singlestore://localhost:3306/db#orders",
+        "synapse://example.sql.azuresynapse.net:1433/pool#dbo.orders",
+        "timescaledb://localhost:5432/db#metrics",
+        "vertica://localhost:5433/db#public.orders",
+        "xlsx:report.xlsx",
     ];
     for locator in locators.into_iter() {
         let parsed: BoxLocator = parse_locator(locator, true).unwrap();
@@ -210,10 +315,15 @@ fn locator_from_str_to_string_roundtrip() {
 ///
 /// This is separate from `BoxLocator` because `BoxLocator` can only be parsed
 /// once we have the `enable_unstable` flag.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct UnparsedLocator(String);
 
 impl UnparsedLocator {
+    /// The original locator string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
     /// Try to parse this locator.
     pub fn parse(&self, enable_unstable: bool) -> Result<BoxLocator> {
         parse_locator(&self.0, enable_unstable)
@@ -245,6 +355,7 @@ pub enum LocatorFeatures {
 pub struct Features {
     pub locator: EnumSet<LocatorFeatures>,
     pub write_schema_if_exists: EnumSet<IfExistsFeatures>,
+    pub write_schema_args: EnumSet<DestinationArgumentsFeatures>,
     pub source_args: EnumSet<SourceArgumentsFeatures>,
     pub dest_args: EnumSet<DestinationArgumentsFeatures>,
     pub dest_if_exists: EnumSet<IfExistsFeatures>,
@@ -257,6 +368,7 @@ impl Features {
         Features {
             locator: EnumSet::empty(),
             write_schema_if_exists: EnumSet::empty(),
+            write_schema_args: EnumSet::empty(),
             source_args: EnumSet::empty(),
             dest_args: EnumSet::empty(),
             dest_if_exists: EnumSet::empty(),
@@ -272,6 +384,9 @@ impl fmt::Display for Features {
         }
         if self.locator.contains(LocatorFeatures::WriteSchema) {
             writeln!(f, "- conv TO:")?;
+            if !self.write_schema_args.is_empty() {
+                writeln!(f, "  {}", self.write_schema_args.display())?;
+            }
             writeln!(f, "  {}", self.write_schema_if_exists.display())?;
         }
         if self.locator.contains(LocatorFeatures::Count) {
@@ -0,0 +1,302 @@
+//! Masking sensitive columns as we copy them.
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufReader, BufWriter},
+    str,
+};
+
+use crate::common::*;
+use crate::date_formats::resolve_data_type;
+use crate::schema::DataType;
+use crate::transform::spawn_sync_transform;
+
+/// First names used by `{"fake": "first_name"}`.
+const FIRST_NAMES: &[&str] = &[
+    "Alex", "Blake", "Casey", "Dana", "Emery", "Finley", "Gray", "Harper", "Jamie",
+    "Jordan", "Kendall", "Logan", "Morgan", "Parker", "Quinn", "Riley", "Rowan",
+    "Sage", "Taylor", "Avery",
+];
+
+/// Last names used by `{"fake": "last_name"}`.
+const LAST_NAMES: &[&str] = &[
+    "Adams", "Baker", "Clark", "Davis", "Evans", "Foster", "Garcia", "Hughes", "Ito",
+    "Jones", "Kim", "Lopez", "Miller", "Nguyen", "Okafor", "Patel", "Rossi", "Smith",
+    "Turner", "Walsh",
+];
+
+/// How to mask sensitive columns in our data.
+///
+/// This is normally loaded from a JSON file:
+///
+/// ```json
+/// {
+///   "salt": "a secret value",
+///   "columns": {
+///     "email": { "fake": "email" },
+///     "ssn": "hash",
+///     "zip_code": { "truncate": 3 },
+///     "notes": { "replace": "REDACTED" },
+///     "phone": "null"
+///   }
+/// }
+/// ```
+///
+/// Columns are named using their destination names, so masks are applied after
+/// any renames or casts.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Masking {
+    /// A secret value to include when hashing, which makes it harder to guess
+    /// the original values.
+    #[serde(default)]
+    salt: String,
+    /// How to mask each column.
+    #[serde(default)]
+    columns: BTreeMap<String, Mask>,
+}
+
+impl Masking {
+    /// Parse a masking specification from JSON.
+    pub fn from_json(json: &str) -> Result<Masking> {
+        Ok(serde_json::from_str(json).context("could not parse masking spec")?)
+    }
+
+    /// Are we masking any columns?
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Make sure that we can apply our masks to `schema`.
+    pub(crate) fn validate(&self, schema: &Schema) -> Result<()> {
+        for (name, mask) in &self.columns {
+            let column = schema
+                .table
+                .columns
+                .iter()
+                .find(|c| &c.name == name)
+                .ok_or_else(|| {
+                    format_err!(
+                        "cannot mask {:?}, because it is not being copied",
+                        name
+                    )
+                })?;
+            let data_type = resolve_data_type(schema, &column.data_type);
+            match mask {
+                Mask::Hash | Mask::Truncate(_) | Mask::Fake(_)
+                    if data_type != &DataType::Text =>
+                {
+                    return Err(format_err!(
+                        "cannot mask {:?} because it is not a text column (try --cast={}:text)",
+                        name,
+                        name,
+                    ));
+                }
+                Mask::Null if !column.is_nullable => {
+                    return Err(format_err!(
+                        "cannot replace {:?} with NULL because it is NOT NULL",
+                        name,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How to mask a single column. Empty values are always left alone.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum Mask {
+    /// Replace the value with a hex-encoded SHA-256 hash.
+    Hash,
+    /// Keep only the first N characters.
+    Truncate(usize),
+    /// Replace every value with a constant.
+    Replace(String),
+    /// Replace every value with NULL.
+    Null,
+    /// Replace each value with a realistic-looking fake value.
+    Fake(FakeKind),
+}
+
+/// Types of fake data that we can generate.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum FakeKind {
+    FirstName,
+    LastName,
+    FullName,
+    Email,
+    Phone,
+}
+
+impl Mask {
+    /// Mask a single non-empty value. We always return the same output for
+    /// the same input, so that masked columns can still be joined.
+    fn apply(&self, salt: &str, value: &str) -> String {
+        match self {
+            Mask::Hash => {
+                let mut out = String::with_capacity(64);
+                for b in digest(salt, value) {
+                    write!(&mut out, "{:02x}", b).expect("write should never fail");
+                }
+                out
+            }
+            Mask::Truncate(len) => value.chars().take(*len).collect(),
+            Mask::Replace(replacement) => replacement.to_owned(),
+            Mask::Null => String::new(),
+            Mask::Fake(kind) => {
+                let digest = digest(salt, value);
+                let first = pick(FIRST_NAMES, digest[0]);
+                let last = pick(LAST_NAMES, digest[1]);
+                match kind {
+                    FakeKind::FirstName => first.to_owned(),
+                    FakeKind::LastName => last.to_owned(),
+                    FakeKind::FullName => format!("{} {}", first, last),
+                    FakeKind::Email => format!(
+                        "{}.{}{}@example.com",
+                        first.to_ascii_lowercase(),
+                        last.to_ascii_lowercase(),
+                        u16::from_be_bytes([digest[2], digest[3]]),
+                    ),
+                    // The 555-0100 to 555-0199 range is reserved for fictional
+                    // numbers in North America.
+                    FakeKind::Phone => format!("555-01{:02}", digest[2] % 100),
+                }
+            }
+        }
+    }
+}
+
+/// Hash `value` using `salt`.
+fn digest(salt: &str, value: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(b"\0");
+    hasher.update(value);
+    hasher.finalize().to_vec()
+}
+
+/// Choose an item from `items` using `byte`.
+fn pick(items: &[&'static str], byte: u8) -> &'static str {
+    items[usize::from(byte) % items.len()]
+}
+
+/// Mask the columns in `streams`, which use the column names in `schema`.
+pub(crate) fn mask_columns(
+    ctx: Context,
+    schema: &Schema,
+    masking: &Masking,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    masking.validate(schema)?;
+    let masking = masking.to_owned();
+    let masked = streams.and_then(move |stream| {
+        let ctx = ctx.clone();
+        let masking = masking.clone();
+        async move {
+            let data = spawn_sync_transform(
+                ctx,
+                "mask_columns".to_owned(),
+                stream.data,
+                move |_ctx, rdr, wtr| copy_masking_columns(&masking, rdr, wtr),
+            )?;
+            Ok(CsvStream {
+                name: stream.name,
+                data,
+            })
+        }
+        .boxed()
+    });
+    Ok(masked.boxed())
+}
+
+/// Copy CSV data from `rdr` to `wtr`, masking columns as we go.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+fn copy_masking_columns(
+    masking: &Masking,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+
+    // Look up the mask for each column.
+    let headers = rdr.byte_headers()?.to_owned();
+    let masks = headers
+        .iter()
+        .map(|name| {
+            str::from_utf8(name)
+                .ok()
+                .and_then(|name| masking.columns.get(name))
+        })
+        .collect::<Vec<_>>();
+    wtr.write_byte_record(&headers)?;
+
+    let mut record = csv::ByteRecord::new();
+    let mut row_idx = 0;
+    while rdr.read_byte_record(&mut record)? {
+        row_idx += 1;
+        for (idx, (cell, mask)) in record.iter().zip(&masks).enumerate() {
+            match mask {
+                Some(mask) if !cell.is_empty() => {
+                    let value = str::from_utf8(cell).with_context(|_| {
+                        ErrorKind::InvalidData {
+                            row: row_idx,
+                            column: String::from_utf8_lossy(&headers[idx])
+                                .into_owned(),
+                            value: String::from_utf8_lossy(cell).into_owned(),
+                        }
+                    })?;
+                    wtr.write_field(mask.apply(&masking.salt, value))?;
+                }
+                _ => wtr.write_field(cell)?,
+            }
+        }
+        wtr.write_record(None::<&[u8]>)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[test]
+fn masks_values() {
+    let masking = Masking::from_json(
+        r#"{
+            "salt": "pepper",
+            "columns": {
+                "a": "hash",
+                "b": { "truncate": 2 },
+                "c": { "replace": "X" },
+                "d": "null",
+                "e": { "fake": "email" }
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(masking.columns["b"], Mask::Truncate(2));
+    assert_eq!(masking.columns["e"], Mask::Fake(FakeKind::Email));
+    assert!(Masking::from_json(r#"{"columns": {"a": "scramble"}}"#).is_err());
+
+    let hash = Mask::Hash.apply("pepper", "secret");
+    assert_eq!(hash.len(), 64);
+    assert_eq!(hash, Mask::Hash.apply("pepper", "secret"));
+    assert_ne!(hash, Mask::Hash.apply("salt", "secret"));
+    assert_eq!(Mask::Truncate(2).apply("", "héllo"), "hé");
+    assert_eq!(Mask::Replace("X".to_owned()).apply("", "secret"), "X");
+    let email = Mask::Fake(FakeKind::Email).apply("pepper", "me@example.org");
+    assert!(email.ends_with("@example.com"));
+    assert_eq!(
+        email,
+        Mask::Fake(FakeKind::Email).apply("pepper", "me@example.org")
+    );
+    assert!(Mask::Fake(FakeKind::Phone)
+        .apply("", "(617) 555-1234")
+        .starts_with("555-01"));
+}
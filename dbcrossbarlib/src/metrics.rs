@@ -0,0 +1,378 @@
+//! Prometheus-compatible metrics.
+//!
+//! We keep a small set of process-wide counters and gauges, which can be
+//! served over HTTP while a command runs, or pushed to a Prometheus
+//! Pushgateway when it finishes.
+
+use std::{
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::common::*;
+
+/// A single metric.
+pub(crate) struct Metric {
+    /// The Prometheus name of this metric.
+    name: &'static str,
+    /// Either `"counter"` or `"gauge"`.
+    metric_type: &'static str,
+    /// A short description.
+    help: &'static str,
+    /// The current value.
+    value: AtomicU64,
+}
+
+impl Metric {
+    /// Create a new counter.
+    const fn counter(name: &'static str, help: &'static str) -> Metric {
+        Metric {
+            name,
+            metric_type: "counter",
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a new gauge.
+    const fn gauge(name: &'static str, help: &'static str) -> Metric {
+        Metric {
+            name,
+            metric_type: "gauge",
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    /// Add `n` to this metric.
+    pub(crate) fn add(&self, n: usize) {
+        let n = u64::try_from(n).unwrap_or(u64::MAX);
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Subtract `n` from this metric. Only useful for gauges.
+    pub(crate) fn sub(&self, n: usize) {
+        let n = u64::try_from(n).unwrap_or(u64::MAX);
+        self.value.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Get the current value of this metric.
+    pub(crate) fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// All the metrics we record.
+pub(crate) struct Metrics {
+    /// CSV rows read from sources.
+    pub(crate) rows_read: Metric,
+    /// CSV rows passed to destinations.
+    pub(crate) rows_written: Metric,
+    /// CSV bytes read from sources.
+    pub(crate) bytes_read: Metric,
+    /// CSV bytes passed to destinations.
+    pub(crate) bytes_written: Metric,
+    /// Streams which are currently being written.
+    pub(crate) active_streams: Metric,
+    /// Streams which have finished writing successfully.
+    pub(crate) streams_completed: Metric,
+    /// Cloud API calls which we retried.
+    pub(crate) retries: Metric,
+}
+
+impl Metrics {
+    /// Iterate over all our metrics.
+    fn all(&self) -> [&Metric; 7] {
+        [
+            &self.rows_read,
+            &self.rows_written,
+            &self.bytes_read,
+            &self.bytes_written,
+            &self.active_streams,
+            &self.streams_completed,
+            &self.retries,
+        ]
+    }
+
+    /// Render our metrics using the Prometheus text exposition format.
+    pub(crate) fn render(&self) -> String {
+        let mut out = String::new();
+        for metric in self.all().iter() {
+            writeln!(&mut out, "# HELP {} {}", metric.name, metric.help)
+                .expect("write to String failed");
+            writeln!(&mut out, "# TYPE {} {}", metric.name, metric.metric_type)
+                .expect("write to String failed");
+            writeln!(&mut out, "{} {}", metric.name, metric.get())
+                .expect("write to String failed");
+        }
+        out
+    }
+}
+
+/// Our process-wide metrics.
+pub(crate) static METRICS: Metrics = Metrics {
+    rows_read: Metric::counter(
+        "dbcrossbar_rows_read_total",
+        "CSV rows read from sources.",
+    ),
+    rows_written: Metric::counter(
+        "dbcrossbar_rows_written_total",
+        "CSV rows passed to destinations.",
+    ),
+    bytes_read: Metric::counter(
+        "dbcrossbar_bytes_read_total",
+        "CSV bytes read from sources.",
+    ),
+    bytes_written: Metric::counter(
+        "dbcrossbar_bytes_written_total",
+        "CSV bytes passed to destinations.",
+    ),
+    active_streams: Metric::gauge(
+        "dbcrossbar_active_streams",
+        "Streams which are currently being written.",
+    ),
+    streams_completed: Metric::counter(
+        "dbcrossbar_streams_completed_total",
+        "Streams which have finished writing successfully.",
+    ),
+    retries: Metric::counter(
+        "dbcrossbar_retries_total",
+        "Cloud API calls which failed temporarily and were retried.",
+    ),
+};
+
+/// Render all our metrics using the Prometheus text exposition format.
+pub fn render() -> String {
+    METRICS.render()
+}
+
+/// Which direction is data flowing?
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Direction {
+    /// Data read from a source.
+    Read,
+    /// Data passed to a destination.
+    Written,
+}
+
+/// Count the rows and bytes in each of `streams`. When writing, we also record
+/// per-stream totals in our run summary, if we have one.
+pub(crate) fn count_csvs(
+    ctx: &Context,
+    direction: Direction,
+    streams: BoxStream<CsvStream>,
+) -> BoxStream<CsvStream> {
+    let (rows, bytes) = match direction {
+        Direction::Read => (&METRICS.rows_read, &METRICS.bytes_read),
+        Direction::Written => (&METRICS.rows_written, &METRICS.bytes_written),
+    };
+    let summary = match direction {
+        Direction::Read => None,
+        Direction::Written => ctx.summary().cloned(),
+    };
+    streams
+        .map_ok(move |stream| {
+            let mut counter = RowCounter::default();
+            let totals = summary
+                .as_ref()
+                .map(|summary| summary.start_stream(&stream.name));
+            let data = stream
+                .data
+                .map_ok(move |chunk| {
+                    let chunk_rows = counter.count(&chunk);
+                    rows.add(chunk_rows);
+                    bytes.add(chunk.len());
+                    if let Some(totals) = &totals {
+                        totals.add(chunk_rows, chunk.len());
+                    }
+                    chunk
+                })
+                .boxed();
+            CsvStream {
+                name: stream.name,
+                data,
+            }
+        })
+        .boxed()
+}
+
+/// Counts rows in a CSV stream, one chunk at a time, without fully parsing
+/// it.
+///
+/// We count newlines that appear outside of quotes. This works for our
+/// standard CSV format because a quote inside a quoted field is written as
+/// `""`, which toggles our state twice. The header row is not counted.
+#[derive(Debug, Default)]
+struct RowCounter {
+    /// Are we inside a quoted field?
+    in_quotes: bool,
+    /// Have we seen the end of the header row yet?
+    seen_header: bool,
+}
+
+impl RowCounter {
+    /// Count the data rows which end in `chunk`.
+    fn count(&mut self, chunk: &[u8]) -> usize {
+        let mut rows = 0;
+        for &b in chunk {
+            match b {
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => {
+                    if self.seen_header {
+                        rows += 1;
+                    } else {
+                        self.seen_header = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        rows
+    }
+}
+
+/// Tracks a stream that is currently being written, and updates our metrics
+/// when it finishes.
+pub(crate) struct ActiveStream {
+    /// Did this stream finish successfully?
+    succeeded: bool,
+}
+
+impl ActiveStream {
+    /// Start tracking a stream.
+    pub(crate) fn start() -> ActiveStream {
+        METRICS.active_streams.add(1);
+        ActiveStream { succeeded: false }
+    }
+
+    /// Record that this stream finished successfully.
+    pub(crate) fn succeeded(&mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for ActiveStream {
+    fn drop(&mut self) {
+        METRICS.active_streams.sub(1);
+        if self.succeeded {
+            METRICS.streams_completed.add(1);
+        }
+    }
+}
+
+/// Serve our metrics over HTTP at `addr` until the process exits.
+///
+/// This is a deliberately minimal HTTP server, which only answers
+/// `GET /metrics`.
+pub async fn serve(ctx: &Context, addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|_| format!("cannot listen for metrics requests on {}", addr))?;
+    info!(ctx.log(), "serving metrics at http://{}/metrics", addr);
+    // Only keep a logger, not a `Context`, because holding a `Context` would
+    // prevent our background workers from ever finishing.
+    let log = ctx.log().clone();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((socket, peer)) => {
+                    let log = log.new(o!("metrics_client" => peer.to_string()));
+                    tokio::spawn(async move {
+                        if let Err(err) = answer_request(socket).await {
+                            debug!(log, "error serving metrics: {}", err);
+                        }
+                    });
+                }
+                Err(err) => {
+                    warn!(log, "error accepting metrics connection: {}", err);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Read an HTTP request from `socket` and reply with our metrics.
+async fn answer_request(socket: TcpStream) -> Result<()> {
+    let mut socket = io::BufReader::new(socket);
+
+    // Read the request line and headers, which end with a blank line. We
+    // don't care what they say.
+    let mut request_line = String::new();
+    socket.read_line(&mut request_line).await?;
+    loop {
+        let mut header = String::new();
+        let len = socket.read_line(&mut header).await?;
+        if len == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", "not found\n".to_owned())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        status,
+        body.len(),
+        body,
+    );
+    let mut socket = socket.into_inner();
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+/// Push our metrics to the Prometheus Pushgateway at `url`.
+pub async fn push(ctx: &Context, url: &Url) -> Result<()> {
+    let push_url = format!(
+        "{}/metrics/job/dbcrossbar",
+        url.as_str().trim_end_matches('/'),
+    );
+    debug!(ctx.log(), "pushing metrics to {}", push_url);
+    let resp = reqwest::Client::new()
+        .put(&push_url)
+        .header("content-type", "text/plain; version=0.0.4")
+        .body(render())
+        .send()
+        .await
+        .context(ErrorKind::Connection {
+            target: push_url.clone(),
+        })?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format_err!(
+            "error pushing metrics to {}: {} {}",
+            push_url,
+            status,
+            body,
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn counts_csv_rows() {
+    let mut counter = RowCounter::default();
+    assert_eq!(counter.count(b"a,b\n1,"), 0);
+    assert_eq!(counter.count(b"\"x\ny\"\n2,\"\"\"\"\n"), 2);
+    assert_eq!(counter.count(b"3,4\n"), 1);
+}
+
+#[test]
+fn renders_prometheus_text_format() {
+    let text = render();
+    assert!(text.contains("# TYPE dbcrossbar_rows_read_total counter\n"));
+    assert!(text.contains("# TYPE dbcrossbar_active_streams gauge\n"));
+    assert!(text.contains("\ndbcrossbar_retries_total "));
+}
@@ -0,0 +1,183 @@
+//! Copying a random or bounded subset of rows.
+
+use rand::{thread_rng, Rng};
+use std::{
+    io::{BufReader, BufWriter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::common::*;
+use crate::transform::spawn_sync_transform;
+
+/// Which rows to copy, if we don't want all of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sampling {
+    /// The percentage of rows to copy, chosen at random.
+    percent: Option<f64>,
+    /// The maximum number of rows to copy.
+    limit: Option<u64>,
+}
+
+impl Sampling {
+    /// Are we copying all the rows?
+    pub fn is_empty(&self) -> bool {
+        self.percent.is_none() && self.limit.is_none()
+    }
+
+    /// Copy a random sample of rows, using a percentage like `1%` or `0.5%`.
+    pub fn set_sample(&mut self, spec: &str) -> Result<()> {
+        let percent = spec
+            .strip_suffix('%')
+            .and_then(|p| p.trim().parse::<f64>().ok())
+            .ok_or_else(|| {
+                format_err!("expected a percentage like \"1%\", found {:?}", spec)
+            })?;
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err(format_err!(
+                "sample percentage must be between 0% and 100%, found {:?}",
+                spec,
+            ));
+        }
+        self.percent = Some(percent);
+        Ok(())
+    }
+
+    /// Copy at most `limit` rows.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = Some(limit);
+    }
+
+    /// The percentage of rows to copy, if we're sampling.
+    pub fn percent(&self) -> Option<f64> {
+        self.percent
+    }
+
+    /// The maximum number of rows to copy, if any.
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+}
+
+/// Sample the rows in `streams`, for sources which can't do it themselves.
+///
+/// `limit` applies to all of our streams together, so when we're copying
+/// several streams in parallel, which rows we get is a matter of timing. Once
+/// we've hit our limit, we still need to read the rest of the data, but we
+/// discard it.
+pub(crate) fn sample_rows(
+    ctx: Context,
+    sampling: &Sampling,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    let sampling = sampling.to_owned();
+    let rows_copied = Arc::new(AtomicU64::new(0));
+    let sampled = streams.and_then(move |stream| {
+        let ctx = ctx.clone();
+        let sampling = sampling.clone();
+        let rows_copied = rows_copied.clone();
+        async move {
+            let data = spawn_sync_transform(
+                ctx,
+                "sample_rows".to_owned(),
+                stream.data,
+                move |_ctx, rdr, wtr| {
+                    copy_sampling_rows(&sampling, &rows_copied, rdr, wtr)
+                },
+            )?;
+            Ok(CsvStream {
+                name: stream.name,
+                data,
+            })
+        }
+        .boxed()
+    });
+    Ok(sampled.boxed())
+}
+
+/// Copy a sample of the CSV data in `rdr` to `wtr`, incrementing `rows_copied`
+/// for each row we copy.
+///
+/// This is synchronous, so it should be run using `spawn_sync_transform`.
+fn copy_sampling_rows(
+    sampling: &Sampling,
+    rows_copied: &AtomicU64,
+    rdr: Box<dyn Read>,
+    wtr: Box<dyn Write>,
+) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(BufReader::with_capacity(BUFFER_SIZE, rdr));
+    let mut wtr = csv::Writer::from_writer(BufWriter::with_capacity(BUFFER_SIZE, wtr));
+    wtr.write_byte_record(rdr.byte_headers()?)?;
+
+    let mut rng = thread_rng();
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        if let Some(percent) = sampling.percent {
+            if rng.gen_range(0.0..100.0) >= percent {
+                continue;
+            }
+        }
+        if let Some(limit) = sampling.limit {
+            if rows_copied.fetch_add(1, Ordering::SeqCst) >= limit {
+                continue;
+            }
+        }
+        wtr.write_byte_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[test]
+fn parses_sampling() {
+    let mut sampling = Sampling::default();
+    assert!(sampling.is_empty());
+    sampling.set_sample("0.5%").unwrap();
+    assert_eq!(sampling.percent(), Some(0.5));
+    sampling.set_limit(10);
+    assert_eq!(sampling.limit(), Some(10));
+    assert!(!sampling.is_empty());
+    for &bad in &["1", "abc%", "0%", "101%", "-1%"] {
+        assert!(sampling.set_sample(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn samples_rows() {
+    use crate::test_util::SharedBuffer;
+
+    let input = "id\n1\n2\n3\n4\n";
+    let mut sampling = Sampling::default();
+    sampling.set_limit(3);
+    let rows_copied = AtomicU64::new(0);
+
+    // Our limit applies across multiple streams.
+    let mut outputs = vec![];
+    for _ in 0..2 {
+        let output = SharedBuffer::default();
+        copy_sampling_rows(
+            &sampling,
+            &rows_copied,
+            Box::new(input.as_bytes()),
+            Box::new(output.clone()),
+        )
+        .unwrap();
+        outputs.push(output.contents_str());
+    }
+    assert_eq!(outputs, vec!["id\n1\n2\n3\n", "id\n"]);
+
+    // A 100% sample copies everything.
+    let mut sampling = Sampling::default();
+    sampling.set_sample("100%").unwrap();
+    let output = SharedBuffer::default();
+    copy_sampling_rows(
+        &sampling,
+        &AtomicU64::new(0),
+        Box::new(input.as_bytes()),
+        Box::new(output.clone()),
+    )
+    .unwrap();
+    assert_eq!(output.contents().as_slice(), input.as_bytes());
+}
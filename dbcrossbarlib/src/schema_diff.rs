@@ -0,0 +1,517 @@
+//! Comparing two portable schemas.
+//!
+//! This is used to detect "drift" between two copies of a table, for example a
+//! staging table in PostgreSQL and a production table in BigQuery. We compare
+//! the portable versions of each schema, which means that differences which
+//! can't be represented by our portable types will not be reported.
+
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+use crate::common::*;
+use crate::drivers::{
+    bigquery::BigQueryLocator, bigquery_shared::write_bq_alter_table_sql,
+    postgres::PostgresLocator, postgres_shared::write_pg_alter_table_sql,
+    postgres_sql::PostgresSqlLocator,
+};
+use crate::schema::{Column, DataType};
+
+/// A difference between the columns of two tables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ColumnChange {
+    /// A column which only appears in the new table.
+    Added(Column),
+    /// A column which only appears in the old table.
+    Removed(Column),
+    /// A column which appears in both tables, but with a different data type
+    /// or nullability.
+    Changed {
+        /// The column in the old table.
+        old: Column,
+        /// The column in the new table.
+        new: Column,
+    },
+}
+
+impl ColumnChange {
+    /// The name of the column affected by this change.
+    pub fn column_name(&self) -> &str {
+        match self {
+            ColumnChange::Added(col) | ColumnChange::Removed(col) => &col.name,
+            ColumnChange::Changed { new, .. } => &new.name,
+        }
+    }
+}
+
+/// The differences between two schemas.
+#[derive(Clone, Debug)]
+pub struct SchemaDiff {
+    /// The schema we're comparing against.
+    pub(crate) old: Schema,
+    /// The schema we're comparing.
+    pub(crate) new: Schema,
+    /// The changes required to turn the table in `old` into the table in
+    /// `new`.
+    changes: Vec<ColumnChange>,
+}
+
+impl SchemaDiff {
+    /// Compare two schemas, and report the changes to their columns which would
+    /// be needed to turn `old` into `new`.
+    ///
+    /// Table names are ignored, because different databases have very
+    /// different ideas about how to name tables.
+    pub fn compare(old: &Schema, new: &Schema) -> SchemaDiff {
+        let old_columns = old
+            .table
+            .columns
+            .iter()
+            .map(|c| (&c.name[..], c))
+            .collect::<HashMap<_, _>>();
+        let new_columns = new
+            .table
+            .columns
+            .iter()
+            .map(|c| (&c.name[..], c))
+            .collect::<HashMap<_, _>>();
+
+        // Report removed columns in their original order, followed by added
+        // and changed columns in the order they appear in `new`.
+        let mut changes = vec![];
+        for old_col in &old.table.columns {
+            if !new_columns.contains_key(&old_col.name[..]) {
+                changes.push(ColumnChange::Removed(old_col.to_owned()));
+            }
+        }
+        for new_col in &new.table.columns {
+            match old_columns.get(&new_col.name[..]) {
+                None => changes.push(ColumnChange::Added(new_col.to_owned())),
+                Some(&old_col) => {
+                    if old_col.is_nullable != new_col.is_nullable
+                        || !data_types_match(
+                            old,
+                            &old_col.data_type,
+                            new,
+                            &new_col.data_type,
+                        )
+                    {
+                        changes.push(ColumnChange::Changed {
+                            old: old_col.to_owned(),
+                            new: new_col.to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+
+        SchemaDiff {
+            old: old.to_owned(),
+            new: new.to_owned(),
+            changes,
+        }
+    }
+
+    /// Are the two schemas equivalent?
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The individual changes to each column.
+    pub fn changes(&self) -> &[ColumnChange] {
+        &self.changes
+    }
+
+    /// Keep only the columns which were added, and make them nullable. This
+    /// is the safe subset of changes we can apply to a table which already
+    /// contains data that we're appending to.
+    pub(crate) fn added_columns_only(&self) -> SchemaDiff {
+        let changes = self
+            .changes
+            .iter()
+            .filter_map(|change| match change {
+                ColumnChange::Added(col) => Some(ColumnChange::Added(Column {
+                    is_nullable: true,
+                    ..col.to_owned()
+                })),
+                _ => None,
+            })
+            .collect();
+        SchemaDiff {
+            old: self.old.clone(),
+            new: self.new.clone(),
+            changes,
+        }
+    }
+
+    /// Describe the problems which would prevent us from loading data with
+    /// the `new` schema into an existing table with the `old` schema. If
+    /// `allow_added_columns` is true, we assume that any missing columns will
+    /// be added to the table before loading (see `--evolve-schema`).
+    pub(crate) fn load_incompatibilities(
+        &self,
+        allow_added_columns: bool,
+    ) -> Vec<String> {
+        let mut problems = vec![];
+        for change in &self.changes {
+            match change {
+                ColumnChange::Added(col) => {
+                    if !allow_added_columns {
+                        problems.push(format!(
+                            "column {:?} is missing from the destination table",
+                            col.name,
+                        ));
+                    }
+                }
+                ColumnChange::Removed(col) => {
+                    if !col.is_nullable {
+                        problems.push(format!(
+                            "destination column {:?} is NOT NULL, but the source has no such column",
+                            col.name,
+                        ));
+                    }
+                }
+                ColumnChange::Changed { old, new } => {
+                    if !can_store(&self.old, &old.data_type, &self.new, &new.data_type)
+                    {
+                        problems.push(format!(
+                            "column {:?} has type {}, which won't fit in destination type {}",
+                            new.name,
+                            DisplayDataType(&new.data_type),
+                            DisplayDataType(&old.data_type),
+                        ));
+                    }
+                    if !old.is_nullable && new.is_nullable {
+                        problems.push(format!(
+                            "column {:?} may contain NULL, but the destination column is NOT NULL",
+                            new.name,
+                        ));
+                    }
+                }
+            }
+        }
+        problems
+    }
+
+    /// Generate SQL which will apply this diff to `target`, by running the
+    /// appropriate `ALTER TABLE` statements. `target` should contain the table
+    /// we were passed as the "old" schema.
+    ///
+    /// This is only supported for certain kinds of locators.
+    pub fn alter_table_sql(&self, target: &dyn Locator) -> Result<String> {
+        let mut sql = vec![];
+        let target_any = target.as_any();
+        if let Some(pg) = target_any.downcast_ref::<PostgresLocator>() {
+            write_pg_alter_table_sql(pg.table_name(), self, &mut sql)?;
+        } else if let Some(pg_sql) = target_any.downcast_ref::<PostgresSqlLocator>() {
+            let table_name = pg_sql.table_name_for_schema(&self.old)?;
+            write_pg_alter_table_sql(&table_name, self, &mut sql)?;
+        } else if let Some(bq) = target_any.downcast_ref::<BigQueryLocator>() {
+            write_bq_alter_table_sql(bq.as_table_name(), self, &mut sql)?;
+        } else {
+            return Err(format_err!(
+                "don't know how to generate ALTER TABLE for {}",
+                target,
+            ));
+        }
+        Ok(String::from_utf8(sql).expect("generated SQL should be UTF-8"))
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                ColumnChange::Added(col) => {
+                    writeln!(f, "+ {:?} {}", col.name, DisplayColumnType(col))?;
+                }
+                ColumnChange::Removed(col) => {
+                    writeln!(f, "- {:?} {}", col.name, DisplayColumnType(col))?;
+                }
+                ColumnChange::Changed { old, new } => {
+                    writeln!(
+                        f,
+                        "~ {:?} {} -> {}",
+                        new.name,
+                        DisplayColumnType(old),
+                        DisplayColumnType(new),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Display the type and nullability of a column in a compact form.
+struct DisplayColumnType<'a>(&'a Column);
+
+impl fmt::Display for DisplayColumnType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", DisplayDataType(&self.0.data_type))?;
+        if !self.0.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        Ok(())
+    }
+}
+
+/// Display a data type in a compact form.
+struct DisplayDataType<'a>(&'a DataType);
+
+impl fmt::Display for DisplayDataType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Use our serialized JSON format for types, because that's what users
+        // will see in `dbcrossbar-schema` files. But strip the quotes from
+        // simple types, because they make the output harder to read.
+        match serde_json::to_value(self.0) {
+            Ok(Value::String(s)) => write!(f, "{}", s),
+            Ok(value) => write!(f, "{}", value),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// Do two data types represent the same type? We resolve named types in each
+/// schema before comparing, so that a named enum will match an identical
+/// anonymous enum.
+fn data_types_match(
+    old_schema: &Schema,
+    old: &DataType,
+    new_schema: &Schema,
+    new: &DataType,
+) -> bool {
+    let old = resolve_named(old_schema, old);
+    let new = resolve_named(new_schema, new);
+    match (old, new) {
+        (DataType::Array(old_elem), DataType::Array(new_elem)) => {
+            data_types_match(old_schema, old_elem, new_schema, new_elem)
+        }
+        (DataType::Struct(old_fields), DataType::Struct(new_fields)) => {
+            old_fields.len() == new_fields.len()
+                && old_fields.iter().zip(new_fields).all(|(o, n)| {
+                    o.name == n.name
+                        && o.is_nullable == n.is_nullable
+                        && data_types_match(
+                            old_schema,
+                            &o.data_type,
+                            new_schema,
+                            &n.data_type,
+                        )
+                })
+        }
+        (old, new) => old == new,
+    }
+}
+
+/// Can a column of type `dest` in `dest_schema` hold every value of type `src`
+/// in `src_schema`?
+///
+/// Text is handled specially. Sources like CSV files report all their columns
+/// as text, and we leave it to the destination to parse them, so we can't tell
+/// in advance whether text values will fit.
+fn can_store(
+    dest_schema: &Schema,
+    dest: &DataType,
+    src_schema: &Schema,
+    src: &DataType,
+) -> bool {
+    if data_types_match(dest_schema, dest, src_schema, src) {
+        return true;
+    }
+    let dest = resolve_named(dest_schema, dest);
+    let src = resolve_named(src_schema, src);
+    match (src, dest) {
+        (DataType::Text, _) | (_, DataType::Text) => true,
+        (DataType::Array(src_elem), DataType::Array(dest_elem)) => {
+            can_store(dest_schema, dest_elem, src_schema, src_elem)
+        }
+        (DataType::OneOf(src_values), DataType::OneOf(dest_values)) => {
+            src_values.iter().all(|v| dest_values.contains(v))
+        }
+        (DataType::Struct(_), DataType::Json) => true,
+        // Widening numeric and time conversions.
+        (DataType::Int16, DataType::Int32)
+        | (DataType::Int16, DataType::Int64)
+        | (DataType::Int16, DataType::Float32)
+        | (DataType::Int16, DataType::Float64)
+        | (DataType::Int16, DataType::Decimal)
+        | (DataType::Int32, DataType::Int64)
+        | (DataType::Int32, DataType::Float64)
+        | (DataType::Int32, DataType::Decimal)
+        | (DataType::Int64, DataType::Decimal)
+        | (DataType::Float32, DataType::Float64)
+        | (DataType::Date, DataType::TimestampWithoutTimeZone)
+        | (DataType::Date, DataType::TimestampWithTimeZone)
+        | (DataType::TimestampWithoutTimeZone, DataType::TimestampWithTimeZone) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// If `data_type` is a `DataType::Named`, look up the underlying type.
+fn resolve_named<'a>(schema: &'a Schema, data_type: &'a DataType) -> &'a DataType {
+    match data_type {
+        DataType::Named(name) => {
+            resolve_named(schema, schema.data_type_for_name(name))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+fn test_schema(columns: Vec<Column>) -> Schema {
+    Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns,
+    })
+    .unwrap()
+}
+
+#[cfg(test)]
+fn test_column(name: &str, data_type: DataType, is_nullable: bool) -> Column {
+    Column {
+        name: name.to_owned(),
+        is_nullable,
+        data_type,
+        comment: None,
+    }
+}
+
+#[test]
+fn identical_schemas_have_no_changes() {
+    let schema = test_schema(vec![
+        test_column("id", DataType::Int64, false),
+        test_column("name", DataType::Text, true),
+    ]);
+    let diff = SchemaDiff::compare(&schema, &schema);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn detects_added_removed_and_changed_columns() {
+    let old = test_schema(vec![
+        test_column("id", DataType::Int32, false),
+        test_column("name", DataType::Text, true),
+        test_column("legacy", DataType::Bool, true),
+    ]);
+    let new = test_schema(vec![
+        test_column("id", DataType::Int64, false),
+        test_column("name", DataType::Text, false),
+        test_column("tags", DataType::Array(Box::new(DataType::Text)), true),
+    ]);
+    let diff = SchemaDiff::compare(&old, &new);
+    assert_eq!(
+        diff.changes(),
+        &[
+            ColumnChange::Removed(test_column("legacy", DataType::Bool, true)),
+            ColumnChange::Changed {
+                old: test_column("id", DataType::Int32, false),
+                new: test_column("id", DataType::Int64, false),
+            },
+            ColumnChange::Changed {
+                old: test_column("name", DataType::Text, true),
+                new: test_column("name", DataType::Text, false),
+            },
+            ColumnChange::Added(test_column(
+                "tags",
+                DataType::Array(Box::new(DataType::Text)),
+                true
+            )),
+        ],
+    );
+    assert_eq!(
+        diff.to_string(),
+        r#"- "legacy" bool
+~ "id" int32 NOT NULL -> int64 NOT NULL
+~ "name" text -> text NOT NULL
++ "tags" {"array":"text"}
+"#,
+    );
+}
+
+#[test]
+fn keeps_only_added_columns() {
+    let old = test_schema(vec![
+        test_column("id", DataType::Int32, false),
+        test_column("legacy", DataType::Bool, true),
+    ]);
+    let new = test_schema(vec![
+        test_column("id", DataType::Int64, false),
+        test_column("email", DataType::Text, false),
+    ]);
+    let diff = SchemaDiff::compare(&old, &new).added_columns_only();
+    assert_eq!(
+        diff.changes(),
+        &[ColumnChange::Added(test_column(
+            "email",
+            DataType::Text,
+            true
+        ))],
+    );
+}
+
+#[test]
+fn reports_load_incompatibilities() {
+    let dest = test_schema(vec![
+        test_column("id", DataType::Int32, false),
+        test_column("score", DataType::Int64, true),
+        test_column("name", DataType::Text, false),
+        test_column("created", DataType::Date, false),
+        test_column("notes", DataType::Text, true),
+    ]);
+    let src = test_schema(vec![
+        test_column("id", DataType::Int64, false),
+        test_column("score", DataType::Int16, false),
+        test_column("name", DataType::Text, true),
+        test_column("email", DataType::Text, true),
+    ]);
+    let diff = SchemaDiff::compare(&dest, &src);
+    assert_eq!(
+        diff.load_incompatibilities(false),
+        vec![
+            r#"destination column "created" is NOT NULL, but the source has no such column"#,
+            r#"column "id" has type int64, which won't fit in destination type int32"#,
+            r#"column "name" may contain NULL, but the destination column is NOT NULL"#,
+            r#"column "email" is missing from the destination table"#,
+        ],
+    );
+    assert_eq!(diff.load_incompatibilities(true).len(), 3);
+
+    // Text columns from sources like CSV files will be parsed when loaded.
+    let csv_src = test_schema(vec![
+        test_column("id", DataType::Text, false),
+        test_column("score", DataType::Text, true),
+        test_column("name", DataType::Text, false),
+        test_column("created", DataType::Text, false),
+        test_column("notes", DataType::Text, true),
+    ]);
+    assert!(SchemaDiff::compare(&dest, &csv_src)
+        .load_incompatibilities(false)
+        .is_empty());
+}
+
+#[test]
+fn resolves_named_types_before_comparing() {
+    use crate::schema::NamedDataType;
+
+    let colors = DataType::OneOf(vec!["red".to_owned(), "green".to_owned()]);
+    let old = Schema::from_types_and_table(
+        vec![NamedDataType {
+            name: "color".to_owned(),
+            data_type: colors.clone(),
+        }],
+        Table {
+            name: "example".to_owned(),
+            columns: vec![test_column(
+                "color",
+                DataType::Named("color".to_owned()),
+                true,
+            )],
+        },
+    )
+    .unwrap();
+    let new = test_schema(vec![test_column("color", colors, true)]);
+    assert!(SchemaDiff::compare(&old, &new).is_empty());
+}
@@ -0,0 +1,499 @@
+//! Checking a portable schema for problems with a specific database.
+//!
+//! Many schema problems won't be noticed until we're halfway through a copy,
+//! for example when a load job fails because of an invalid column name. Here,
+//! we try to find as many of those problems as we can up front, and report all
+//! of them at once.
+
+use std::{collections::HashMap, fmt};
+
+use crate::common::*;
+use crate::drivers::{
+    bigquery::BigQueryLocator,
+    bigquery_schema::BigQuerySchemaLocator,
+    bigquery_shared::{BqColumn, ColumnName, Usage},
+    postgres::PostgresLocator,
+    postgres_shared::PgColumn,
+    postgres_sql::PostgresSqlLocator,
+    redshift::RedshiftLocator,
+};
+use crate::schema::Column;
+
+/// How serious is a problem?
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// This will probably work, but it may cause trouble for people writing
+    /// SQL by hand.
+    Warning,
+    /// This will not work.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem with a schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintProblem {
+    /// How serious is this problem?
+    pub severity: Severity,
+    /// The column with the problem.
+    pub column_name: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for LintProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: column {:?}: {}",
+            self.severity, self.column_name, self.message,
+        )
+    }
+}
+
+/// The databases we know how to check schemas against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    BigQuery,
+    Postgres,
+    Redshift,
+}
+
+impl Target {
+    /// Look up the target for a driver scheme, such as `"postgres:"`.
+    fn for_scheme(scheme: &str) -> Result<Target> {
+        if scheme == BigQueryLocator::scheme()
+            || scheme == BigQuerySchemaLocator::scheme()
+        {
+            Ok(Target::BigQuery)
+        } else if scheme == PostgresLocator::scheme()
+            || scheme == PostgresSqlLocator::scheme()
+        {
+            Ok(Target::Postgres)
+        } else if scheme == RedshiftLocator::scheme() {
+            Ok(Target::Redshift)
+        } else {
+            Err(format_err!("don't know how to lint schemas for {}", scheme))
+        }
+    }
+
+    /// The maximum length of a column name, in bytes.
+    fn max_identifier_len(self) -> usize {
+        match self {
+            Target::BigQuery => 300,
+            // This is `NAMEDATALEN - 1`. Longer names are silently truncated.
+            Target::Postgres => 63,
+            Target::Redshift => 127,
+        }
+    }
+
+    /// Does this database ignore case when comparing column names?
+    fn has_case_insensitive_columns(self) -> bool {
+        match self {
+            Target::BigQuery => true,
+            // By default, RedShift lowercases all identifiers, even quoted
+            // ones.
+            Target::Redshift => true,
+            Target::Postgres => false,
+        }
+    }
+
+    /// Is `name` a reserved word? We always quote column names, so these are
+    /// only warnings.
+    fn is_reserved_word(self, name: &str) -> bool {
+        let name = name.to_ascii_uppercase();
+        match self {
+            Target::BigQuery => BIGQUERY_RESERVED_WORDS.contains(&&name[..]),
+            Target::Postgres | Target::Redshift => {
+                POSTGRES_RESERVED_WORDS.contains(&&name[..])
+            }
+        }
+    }
+
+    /// Check to see whether we can convert the type of `col`.
+    fn check_column_type(self, schema: &Schema, col: &Column) -> Result<()> {
+        match self {
+            Target::BigQuery => {
+                let name = ColumnName::try_from(&col.name)?;
+                BqColumn::for_column(schema, name, col, Usage::FinalTable)?;
+            }
+            Target::Postgres | Target::Redshift => {
+                PgColumn::from_column(schema, col)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reserved words in PostgreSQL. See
+/// https://www.postgresql.org/docs/current/sql-keywords-appendix.html.
+const POSTGRES_RESERVED_WORDS: &[&str] = &[
+    "ALL",
+    "ANALYSE",
+    "ANALYZE",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "ASYMMETRIC",
+    "BOTH",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "CONSTRAINT",
+    "CREATE",
+    "CURRENT_CATALOG",
+    "CURRENT_DATE",
+    "CURRENT_ROLE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INITIALLY",
+    "INTERSECT",
+    "INTO",
+    "LATERAL",
+    "LEADING",
+    "LIMIT",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "PLACING",
+    "PRIMARY",
+    "REFERENCES",
+    "RETURNING",
+    "SELECT",
+    "SESSION_USER",
+    "SOME",
+    "SYMMETRIC",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRAILING",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "USER",
+    "USING",
+    "VARIADIC",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+
+/// Reserved words in BigQuery. See
+/// https://cloud.google.com/bigquery/docs/reference/standard-sql/lexical#reserved_keywords.
+const BIGQUERY_RESERVED_WORDS: &[&str] = &[
+    "ALL",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "ASSERT_ROWS_MODIFIED",
+    "AT",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CAST",
+    "COLLATE",
+    "CONTAINS",
+    "CREATE",
+    "CROSS",
+    "CUBE",
+    "CURRENT",
+    "DEFAULT",
+    "DEFINE",
+    "DESC",
+    "DISTINCT",
+    "ELSE",
+    "END",
+    "ENUM",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXISTS",
+    "EXTRACT",
+    "FALSE",
+    "FETCH",
+    "FOLLOWING",
+    "FOR",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "GROUPING",
+    "GROUPS",
+    "HASH",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "IN",
+    "INNER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOOKUP",
+    "MERGE",
+    "NATURAL",
+    "NEW",
+    "NO",
+    "NOT",
+    "NULL",
+    "NULLS",
+    "OF",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PRECEDING",
+    "PROTO",
+    "RANGE",
+    "RECURSIVE",
+    "RESPECT",
+    "RIGHT",
+    "ROLLUP",
+    "ROWS",
+    "SELECT",
+    "SET",
+    "SOME",
+    "STRUCT",
+    "TABLESAMPLE",
+    "THEN",
+    "TO",
+    "TREAT",
+    "TRUE",
+    "UNBOUNDED",
+    "UNION",
+    "UNNEST",
+    "USING",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHIN",
+];
+
+/// The result of checking a schema against a specific database.
+#[derive(Clone, Debug)]
+pub struct SchemaLint {
+    /// The problems we found, in column order.
+    problems: Vec<LintProblem>,
+}
+
+impl SchemaLint {
+    /// Check `schema` for problems that would occur when creating a table with
+    /// the driver for `scheme`, such as `"postgres:"` or `"bigquery:"`.
+    pub fn check(schema: &Schema, scheme: &str) -> Result<SchemaLint> {
+        let target = Target::for_scheme(scheme)?;
+        let mut problems = vec![];
+        let mut problem = |severity, col: &Column, message: String| {
+            problems.push(LintProblem {
+                severity,
+                column_name: col.name.clone(),
+                message,
+            })
+        };
+
+        let mut seen = HashMap::<String, &Column>::new();
+        for col in &schema.table.columns {
+            if col.name.len() > target.max_identifier_len() {
+                problem(
+                    Severity::Error,
+                    col,
+                    format!(
+                        "name is longer than {} bytes",
+                        target.max_identifier_len(),
+                    ),
+                );
+            }
+
+            if target.is_reserved_word(&col.name) {
+                problem(
+                    Severity::Warning,
+                    col,
+                    "name is a reserved word, and must be quoted in SQL".to_owned(),
+                );
+            }
+
+            let key = col.name.to_lowercase();
+            if let Some(prev) = seen.get(&key) {
+                if prev.name == col.name {
+                    problem(Severity::Error, col, "duplicate column name".to_owned());
+                } else if target.has_case_insensitive_columns() {
+                    problem(
+                        Severity::Error,
+                        col,
+                        format!("name conflicts with column {:?}", prev.name),
+                    );
+                } else {
+                    problem(
+                        Severity::Warning,
+                        col,
+                        format!("name only differs in case from {:?}", prev.name),
+                    );
+                }
+            } else {
+                seen.insert(key, col);
+            }
+
+            if let Err(err) = target.check_column_type(schema, col) {
+                problem(Severity::Error, col, err.to_string());
+            }
+        }
+        Ok(SchemaLint { problems })
+    }
+
+    /// The problems we found.
+    pub fn problems(&self) -> &[LintProblem] {
+        &self.problems
+    }
+
+    /// Did we find any problems which would prevent us from creating a table?
+    pub fn has_errors(&self) -> bool {
+        self.problems.iter().any(|p| p.severity == Severity::Error)
+    }
+}
+
+impl fmt::Display for SchemaLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_schema(names_and_types: &[(&str, crate::schema::DataType)]) -> Schema {
+    Schema::from_table(Table {
+        name: "example".to_owned(),
+        columns: names_and_types
+            .iter()
+            .map(|(name, data_type)| Column {
+                name: (*name).to_owned(),
+                is_nullable: true,
+                data_type: data_type.clone(),
+                comment: None,
+            })
+            .collect(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn valid_schemas_have_no_problems() {
+    use crate::schema::DataType;
+
+    let schema = test_schema(&[("id", DataType::Int64), ("name", DataType::Text)]);
+    for &scheme in &["postgres:", "bigquery:", "redshift:"] {
+        let lint = SchemaLint::check(&schema, scheme).unwrap();
+        assert!(lint.problems().is_empty());
+        assert!(!lint.has_errors());
+    }
+}
+
+#[test]
+fn reports_postgres_problems() {
+    use crate::schema::DataType;
+
+    let long_name = "x".repeat(64);
+    let schema = test_schema(&[
+        ("id", DataType::Int64),
+        ("ID", DataType::Int64),
+        ("select", DataType::Text),
+        (&long_name, DataType::Text),
+        ("color", DataType::OneOf(vec!["red".to_owned()])),
+    ]);
+    let lint = SchemaLint::check(&schema, "postgres:").unwrap();
+    let problems = lint
+        .problems()
+        .iter()
+        .map(|p| (p.severity, &p.column_name[..]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        &[
+            (Severity::Warning, "ID"),
+            (Severity::Warning, "select"),
+            (Severity::Error, &long_name[..]),
+            (Severity::Error, "color"),
+        ],
+    );
+    assert!(lint.has_errors());
+}
+
+#[test]
+fn reports_bigquery_problems() {
+    use crate::schema::DataType;
+
+    let schema = test_schema(&[
+        ("id", DataType::Int64),
+        ("ID", DataType::Int64),
+        ("bad-name", DataType::Text),
+        ("data", DataType::Array(Box::new(DataType::Json))),
+    ]);
+    let lint = SchemaLint::check(&schema, "bigquery:").unwrap();
+    let problems = lint
+        .problems()
+        .iter()
+        .map(|p| (p.severity, &p.column_name[..]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        &[
+            (Severity::Error, "ID"),
+            (Severity::Error, "bad-name"),
+            (Severity::Error, "data"),
+        ],
+    );
+}
+
+#[test]
+fn unknown_targets_are_rejected() {
+    use crate::schema::DataType;
+
+    let schema = test_schema(&[("id", DataType::Int64)]);
+    assert!(SchemaLint::check(&schema, "csv:").is_err());
+}
@@ -0,0 +1,227 @@
+//! Machine-readable summaries of a run.
+//!
+//! Tools which run `dbcrossbar` often need to know what a copy actually did.
+//! A `SummaryCollector` records the streams we wrote, the jobs we ran and the
+//! warnings we logged, so that we can report them as JSON when we finish.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use slog::{Drain, Never, OwnedKVList, Record};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::common::*;
+
+/// A summary of a single run, suitable for serializing as JSON.
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    /// Our source locator.
+    pub from_locator: String,
+    /// Our destination locator.
+    pub to_locator: String,
+    /// Did the run succeed?
+    pub succeeded: bool,
+    /// The error which stopped the run, if any.
+    pub error: Option<String>,
+    /// When did we start, as an RFC 3339 timestamp?
+    pub started_at: String,
+    /// How long did we run for, in seconds?
+    pub duration_seconds: f64,
+    /// The streams we wrote via the local machine.
+    pub streams: Vec<StreamSummary>,
+    /// Where we wrote our data.
+    pub dest_locators: Vec<String>,
+    /// The IDs of any load or extract jobs we ran, such as BigQuery jobs.
+    pub job_ids: Vec<String>,
+    /// Any warnings we logged.
+    pub warnings: Vec<String>,
+}
+
+/// A summary of a single stream.
+#[derive(Debug, Serialize)]
+pub struct StreamSummary {
+    /// The name of the stream.
+    pub name: String,
+    /// The number of CSV rows written, not counting headers.
+    pub rows: u64,
+    /// The number of CSV bytes written.
+    pub bytes: u64,
+}
+
+/// Row and byte counts for a stream, which we update as data passes through.
+#[derive(Debug)]
+pub(crate) struct StreamTotals {
+    /// The name of the stream.
+    name: String,
+    /// Rows seen so far.
+    rows: AtomicU64,
+    /// Bytes seen so far.
+    bytes: AtomicU64,
+}
+
+impl StreamTotals {
+    /// Add `rows` and `bytes` to our totals.
+    pub(crate) fn add(&self, rows: usize, bytes: usize) {
+        let rows = u64::try_from(rows).unwrap_or(u64::MAX);
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+        self.rows.fetch_add(rows, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Everything we've recorded so far.
+#[derive(Debug, Default)]
+struct SummaryState {
+    streams: Vec<Arc<StreamTotals>>,
+    dest_locators: Vec<String>,
+    job_ids: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Collects information about a run, so that we can summarize it at the end.
+#[derive(Debug)]
+pub struct SummaryCollector {
+    /// When we started.
+    started_at: DateTime<Utc>,
+    /// When we started, for measuring our duration.
+    start_instant: Instant,
+    /// What we've recorded so far.
+    state: Mutex<SummaryState>,
+}
+
+impl SummaryCollector {
+    /// Create a new collector, starting now.
+    pub fn new() -> Arc<SummaryCollector> {
+        Arc::new(SummaryCollector {
+            started_at: Utc::now(),
+            start_instant: Instant::now(),
+            state: Mutex::new(SummaryState::default()),
+        })
+    }
+
+    /// Lock our state.
+    fn state(&self) -> std::sync::MutexGuard<'_, SummaryState> {
+        self.state.lock().expect("summary collector lock poisoned")
+    }
+
+    /// Start tracking a stream named `name`.
+    pub(crate) fn start_stream(&self, name: &str) -> Arc<StreamTotals> {
+        let totals = Arc::new(StreamTotals {
+            name: name.to_owned(),
+            rows: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        });
+        self.state().streams.push(totals.clone());
+        totals
+    }
+
+    /// Record that we wrote data to `dest`.
+    pub(crate) fn record_dest_locator(&self, dest: &str) {
+        self.state().dest_locators.push(dest.to_owned());
+    }
+
+    /// Record that we ran the job `job_id`.
+    pub(crate) fn record_job_id(&self, job_id: &str) {
+        self.state().job_ids.push(job_id.to_owned());
+    }
+
+    /// Record a warning.
+    fn record_warning(&self, warning: String) {
+        self.state().warnings.push(warning);
+    }
+
+    /// Summarize a run from `from_locator` to `to_locator` which finished with
+    /// `result`.
+    pub fn summarize(
+        &self,
+        from_locator: &str,
+        to_locator: &str,
+        result: &Result<()>,
+    ) -> RunSummary {
+        let state = self.state();
+        RunSummary {
+            from_locator: from_locator.to_owned(),
+            to_locator: to_locator.to_owned(),
+            succeeded: result.is_ok(),
+            error: result.as_ref().err().map(|err| {
+                // Include the causes, which usually say what went wrong.
+                err.iter_chain()
+                    .map(|cause| cause.to_string())
+                    .collect::<Vec<_>>()
+                    .join(": ")
+            }),
+            started_at: self.started_at.to_rfc3339(),
+            duration_seconds: self.start_instant.elapsed().as_secs_f64(),
+            streams: state
+                .streams
+                .iter()
+                .map(|totals| StreamSummary {
+                    name: totals.name.clone(),
+                    rows: totals.rows.load(Ordering::Relaxed),
+                    bytes: totals.bytes.load(Ordering::Relaxed),
+                })
+                .collect(),
+            dest_locators: state.dest_locators.clone(),
+            job_ids: state.job_ids.clone(),
+            warnings: state.warnings.clone(),
+        }
+    }
+}
+
+/// A `slog` drain which records warnings and errors in a `SummaryCollector`.
+pub(crate) struct WarningRecorder {
+    /// Where to record warnings.
+    collector: Arc<SummaryCollector>,
+}
+
+impl WarningRecorder {
+    /// Create a new `WarningRecorder`.
+    pub(crate) fn new(collector: Arc<SummaryCollector>) -> WarningRecorder {
+        WarningRecorder { collector }
+    }
+}
+
+impl Drain for WarningRecorder {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(
+        &self,
+        record: &Record<'_>,
+        _values: &OwnedKVList,
+    ) -> std::result::Result<(), Never> {
+        if record.level().is_at_least(slog::Level::Warning) {
+            self.collector.record_warning(record.msg().to_string());
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn summarizes_runs() {
+    let collector = SummaryCollector::new();
+    let totals = collector.start_stream("data");
+    totals.add(2, 10);
+    totals.add(1, 5);
+    collector.record_dest_locator("csv:/tmp/out.csv");
+    collector.record_job_id("job_1");
+    collector.record_warning("careful".to_owned());
+
+    let summary = collector.summarize("csv:in.csv", "csv:/tmp/out.csv", &Ok(()));
+    assert!(summary.succeeded);
+    assert_eq!(summary.streams.len(), 1);
+    assert_eq!(summary.streams[0].rows, 3);
+    assert_eq!(summary.streams[0].bytes, 15);
+    assert_eq!(summary.dest_locators, vec!["csv:/tmp/out.csv".to_owned()]);
+    assert_eq!(summary.job_ids, vec!["job_1".to_owned()]);
+    assert_eq!(summary.warnings, vec!["careful".to_owned()]);
+
+    let failed = collector.summarize("a", "b", &Err(format_err!("oops")));
+    assert!(!failed.succeeded);
+    assert_eq!(failed.error.as_deref(), Some("oops"));
+}
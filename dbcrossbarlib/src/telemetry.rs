@@ -0,0 +1,397 @@
+//! Tracing spans, with optional export to an OpenTelemetry collector.
+//!
+//! We record a span for each major stage of a copy, such as reading the
+//! schema, writing each stream, uploading files or running a BigQuery job.
+//! Each span's duration is logged at the debug level. If
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` is
+//! set, we also send all our spans to that endpoint using OTLP/HTTP with JSON
+//! encoding when the command finishes. If `TRACEPARENT` is set, our spans will
+//! be part of that trace, which allows runs started by workflow tools to show
+//! up in existing distributed traces.
+
+use rand::random;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::common::*;
+
+/// A 16-byte trace ID.
+type TraceId = [u8; 16];
+
+/// An 8-byte span ID.
+type SpanId = [u8; 8];
+
+/// Information about the span which contains the current context.
+#[derive(Clone, Debug)]
+pub(crate) struct TraceContext {
+    /// Where we should record finished spans.
+    collector: Arc<SpanCollector>,
+    /// The trace we belong to.
+    trace_id: TraceId,
+    /// The span which contains our context, if any.
+    parent_span_id: Option<SpanId>,
+}
+
+impl TraceContext {
+    /// Create a trace context for the top level of a program.
+    pub(crate) fn root(collector: Arc<SpanCollector>) -> Self {
+        let (trace_id, parent_span_id) = match collector.traceparent {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (random_nonzero_id(), None),
+        };
+        TraceContext {
+            collector,
+            trace_id,
+            parent_span_id,
+        }
+    }
+
+    /// Create a trace context for code running inside `span_id`.
+    fn child(&self, span_id: SpanId) -> Self {
+        TraceContext {
+            collector: self.collector.clone(),
+            trace_id: self.trace_id,
+            parent_span_id: Some(span_id),
+        }
+    }
+}
+
+/// Collects finished spans, and exports them to an OpenTelemetry collector.
+#[derive(Debug)]
+pub struct SpanCollector {
+    /// The URL to which we should `POST` our spans.
+    endpoint: Url,
+    /// Extra HTTP headers to send with our spans.
+    headers: Vec<(String, String)>,
+    /// The value of `service.name` for our spans.
+    service_name: String,
+    /// The trace ID and span ID from `TRACEPARENT`, if any.
+    traceparent: Option<(TraceId, SpanId)>,
+    /// Spans which have finished.
+    finished: Mutex<Vec<SpanData>>,
+}
+
+impl SpanCollector {
+    /// Create a span collector using the standard OpenTelemetry environment
+    /// variables, or return `None` if no OTLP endpoint has been configured.
+    pub fn from_env() -> Result<Option<Arc<SpanCollector>>> {
+        let endpoint = match (
+            env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"),
+            env::var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+        ) {
+            (Ok(endpoint), _) => endpoint,
+            (Err(_), Ok(endpoint)) => {
+                format!("{}/v1/traces", endpoint.trim_end_matches('/'))
+            }
+            (Err(_), Err(_)) => return Ok(None),
+        };
+        let endpoint = endpoint.parse::<Url>().with_context(|_| {
+            format!("could not parse OTLP endpoint {:?}", endpoint)
+        })?;
+        let headers = match env::var("OTEL_EXPORTER_OTLP_HEADERS") {
+            Ok(headers) => parse_otlp_headers(&headers)?,
+            Err(_) => vec![],
+        };
+        let service_name =
+            env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "dbcrossbar".to_owned());
+        let traceparent = match env::var("TRACEPARENT") {
+            Ok(traceparent) => Some(parse_traceparent(&traceparent)?),
+            Err(_) => None,
+        };
+        Ok(Some(Arc::new(SpanCollector {
+            endpoint,
+            headers,
+            service_name,
+            traceparent,
+            finished: Mutex::new(vec![]),
+        })))
+    }
+
+    /// Record a finished span.
+    fn record(&self, span: SpanData) {
+        self.finished
+            .lock()
+            .expect("span collector lock poisoned")
+            .push(span);
+    }
+
+    /// Send all our finished spans to our OTLP endpoint.
+    pub async fn export(&self, ctx: &Context) -> Result<()> {
+        let spans = std::mem::take(
+            &mut *self.finished.lock().expect("span collector lock poisoned"),
+        );
+        if spans.is_empty() {
+            return Ok(());
+        }
+        debug!(
+            ctx.log(),
+            "exporting {} spans to {}",
+            spans.len(),
+            self.endpoint
+        );
+        let body = self.otlp_json(&spans);
+        let mut req = reqwest::Client::new()
+            .post(self.endpoint.clone())
+            .json(&body);
+        for (name, value) in &self.headers {
+            req = req.header(&name[..], &value[..]);
+        }
+        let resp = req.send().await.context(ErrorKind::Connection {
+            target: self.endpoint.to_string(),
+        })?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format_err!(
+                "error exporting spans to {}: {} {}",
+                self.endpoint,
+                status,
+                body,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build an OTLP/HTTP JSON request containing `spans`.
+    fn otlp_json(&self, spans: &[SpanData]) -> Value {
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        string_attribute("service.name", &self.service_name),
+                    ],
+                },
+                "scopeSpans": [{
+                    "scope": {
+                        "name": "dbcrossbar",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+}
+
+/// A finished span, in OTLP JSON format.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpanData {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    /// Always `SPAN_KIND_INTERNAL`.
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<Value>,
+}
+
+/// A span in progress. The span will be logged and recorded when this is
+/// dropped.
+pub struct Span {
+    /// Our logger.
+    log: Logger,
+    /// The name of this span.
+    name: String,
+    /// When this span started, for measuring its duration.
+    started: Instant,
+    /// When this span started, for reporting.
+    start_time: SystemTime,
+    /// Our trace context, and our own span ID, if we're recording spans.
+    trace: Option<(TraceContext, SpanId)>,
+    /// Extra attributes describing this span.
+    attributes: Vec<(String, String)>,
+    /// The error which ended this span, if any.
+    error: Option<String>,
+}
+
+impl Span {
+    /// Start a new span. Returns the span, and the trace context to use for
+    /// any code running inside it.
+    pub(crate) fn start(
+        log: Logger,
+        trace: Option<&TraceContext>,
+        name: &str,
+    ) -> (Span, Option<TraceContext>) {
+        let trace = trace.map(|trace| (trace.clone(), random_nonzero_id()));
+        let child_trace = trace.as_ref().map(|(trace, span_id)| trace.child(*span_id));
+        let span = Span {
+            log,
+            name: name.to_owned(),
+            started: Instant::now(),
+            start_time: SystemTime::now(),
+            trace,
+            attributes: vec![],
+            error: None,
+        };
+        (span, child_trace)
+    }
+
+    /// Add an attribute describing this span.
+    pub(crate) fn set_attribute<V: ToString>(&mut self, key: &str, value: V) {
+        self.attributes.push((key.to_owned(), value.to_string()));
+    }
+
+    /// Mark this span as having failed with `err`.
+    pub fn record_error(&mut self, err: &Error) {
+        self.error = Some(err.to_string());
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        debug!(self.log, "finished {} in {:.3?}", self.name, elapsed);
+        if let Some((trace, span_id)) = self.trace.take() {
+            let end_time = self.start_time + elapsed;
+            trace.collector.record(SpanData {
+                trace_id: hex::encode(trace.trace_id),
+                span_id: hex::encode(span_id),
+                parent_span_id: trace.parent_span_id.map(hex::encode),
+                name: self.name.clone(),
+                kind: 1,
+                start_time_unix_nano: unix_nanos(self.start_time),
+                end_time_unix_nano: unix_nanos(end_time),
+                attributes: self
+                    .attributes
+                    .iter()
+                    .map(|(k, v)| string_attribute(k, v))
+                    .collect(),
+                status: self.error.as_ref().map(|message| {
+                    // `STATUS_CODE_ERROR`.
+                    json!({ "code": 2, "message": message })
+                }),
+            });
+        }
+    }
+}
+
+/// Build an OTLP string attribute.
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Convert `time` to a string containing nanoseconds since the Unix epoch.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+/// Generate a random ID. All-zero IDs are invalid.
+fn random_nonzero_id<T>() -> T
+where
+    T: Default + PartialEq,
+    rand::distributions::Standard: rand::distributions::Distribution<T>,
+{
+    loop {
+        let id = random::<T>();
+        if id != T::default() {
+            return id;
+        }
+    }
+}
+
+/// Parse a W3C `traceparent` header, like
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+fn parse_traceparent(traceparent: &str) -> Result<(TraceId, SpanId)> {
+    let parse = || -> Result<(TraceId, SpanId)> {
+        let parts = traceparent.trim().split('-').collect::<Vec<_>>();
+        if parts.len() != 4 || parts[0] != "00" {
+            return Err(format_err!("expected 4 parts and version 00"));
+        }
+        let mut trace_id = TraceId::default();
+        hex::decode_to_slice(parts[1], &mut trace_id)?;
+        let mut span_id = SpanId::default();
+        hex::decode_to_slice(parts[2], &mut span_id)?;
+        Ok((trace_id, span_id))
+    };
+    Ok(parse()
+        .with_context(|_| format!("could not parse TRACEPARENT {:?}", traceparent))?)
+}
+
+/// Parse `OTEL_EXPORTER_OTLP_HEADERS`, which looks like `key1=value1,key2=value2`.
+fn parse_otlp_headers(headers: &str) -> Result<Vec<(String, String)>> {
+    headers
+        .split(',')
+        .filter(|h| !h.trim().is_empty())
+        .map(|header| {
+            let mut parts = header.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => {
+                    let value = percent_encoding::percent_decode_str(value.trim())
+                        .decode_utf8()?
+                        .into_owned();
+                    Ok((name.trim().to_owned(), value))
+                }
+                _ => Err(format_err!(
+                    "expected KEY=VALUE in OTEL_EXPORTER_OTLP_HEADERS, found {:?}",
+                    header,
+                )),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn parses_traceparent() {
+    let (trace_id, span_id) =
+        parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+            .unwrap();
+    assert_eq!(hex::encode(trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(hex::encode(span_id), "00f067aa0ba902b7");
+    assert!(parse_traceparent("01-abc-def-00").is_err());
+}
+
+#[test]
+fn parses_otlp_headers() {
+    let headers = parse_otlp_headers("api-key=secret, x-team=data%20eng").unwrap();
+    assert_eq!(
+        headers,
+        vec![
+            ("api-key".to_owned(), "secret".to_owned()),
+            ("x-team".to_owned(), "data eng".to_owned()),
+        ],
+    );
+    assert!(parse_otlp_headers("nope").is_err());
+}
+
+#[test]
+fn records_nested_spans() {
+    let collector = Arc::new(SpanCollector {
+        endpoint: "http://localhost:4318/v1/traces".parse().unwrap(),
+        headers: vec![],
+        service_name: "dbcrossbar".to_owned(),
+        traceparent: None,
+        finished: Mutex::new(vec![]),
+    });
+    let log = Logger::root(slog::Discard, o!());
+    let root = TraceContext::root(collector.clone());
+    let (mut outer, outer_trace) = Span::start(log.clone(), Some(&root), "outer");
+    outer.set_attribute("stream", "data");
+    let (inner, _) = Span::start(log, outer_trace.as_ref(), "inner");
+    drop(inner);
+    drop(outer);
+
+    let finished = collector.finished.lock().unwrap();
+    assert_eq!(finished.len(), 2);
+    assert_eq!(finished[0].name, "inner");
+    assert_eq!(finished[1].name, "outer");
+    assert_eq!(finished[0].trace_id, finished[1].trace_id);
+    assert_eq!(
+        finished[0].parent_span_id.as_ref(),
+        Some(&finished[1].span_id),
+    );
+    assert!(finished[1].parent_span_id.is_none());
+}
@@ -32,8 +32,13 @@ impl TemporaryStorage {
         Ok(TemporaryStorage { locations })
     }
 
+    /// Add a location after any existing locations.
+    pub(crate) fn push(&mut self, location: String) {
+        self.locations.push(location);
+    }
+
     /// Find a location with the specified scheme.
-    pub fn find_scheme<'a, 'b>(&'a self, scheme: &'b str) -> Option<&'a str> {
+    pub fn find_scheme<'a>(&'a self, scheme: &str) -> Option<&'a str> {
         assert!(scheme.ends_with(':'));
         self.locations
             .iter()
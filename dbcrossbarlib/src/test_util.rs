@@ -0,0 +1,33 @@
+//! Helpers shared by our unit tests.
+
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+/// A `Write` implementation which we can inspect after handing it to code
+/// which takes ownership of its output.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Return a copy of everything written so far.
+    pub(crate) fn contents(&self) -> Vec<u8> {
+        self.0.lock().expect("lock poisoned").clone()
+    }
+
+    /// Return a copy of everything written so far, as a UTF-8 string.
+    pub(crate) fn contents_str(&self) -> String {
+        String::from_utf8(self.contents()).expect("output should be UTF-8")
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("lock poisoned").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Limit how quickly we copy data.
+
+use std::{
+    cmp::max,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::{sleep_until, Instant};
+
+use crate::common::*;
+
+/// Limit all the streams in `streams` to a combined total of about
+/// `max_bytes_per_second`.
+///
+/// Since the destination can only accept data as fast as we pass it along,
+/// this also slows down how quickly we read from the source.
+pub(crate) fn throttle_csvs(
+    ctx: Context,
+    max_bytes_per_second: usize,
+    streams: BoxStream<CsvStream>,
+) -> Result<BoxStream<CsvStream>> {
+    let limiter = Arc::new(RateLimiter::new(max_bytes_per_second)?);
+    let ctx = ctx.child(o!("streams_transform" => "throttle_csvs"));
+    debug!(
+        ctx.log(),
+        "limiting copy to {} bytes/second", max_bytes_per_second
+    );
+    let throttled = streams.map_ok(move |stream| {
+        let limiter = limiter.clone();
+        let data = stream
+            .data
+            .and_then(move |chunk| {
+                let limiter = limiter.clone();
+                async move {
+                    limiter.wait_for(chunk.len()).await;
+                    Ok(chunk)
+                }
+            })
+            .boxed();
+        CsvStream {
+            name: stream.name,
+            data,
+        }
+    });
+    Ok(throttled.boxed())
+}
+
+/// A rate limiter shared between several streams.
+struct RateLimiter {
+    /// How many bytes may we copy each second?
+    bytes_per_second: u64,
+    /// When may the next chunk of data be sent? All the chunks we've already
+    /// allowed through are paid for by this time.
+    next_available: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter.
+    fn new(bytes_per_second: usize) -> Result<RateLimiter> {
+        if bytes_per_second == 0 {
+            return Err(format_err!("--max-bytes-per-second must be at least 1"));
+        }
+        Ok(RateLimiter {
+            bytes_per_second: u64::try_from(bytes_per_second)?,
+            next_available: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// How long does it take to send `bytes` at our rate?
+    fn cost(&self, bytes: usize) -> Duration {
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+        let nanos =
+            u128::from(bytes) * 1_000_000_000 / u128::from(self.bytes_per_second);
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// Wait until we're allowed to send `bytes` more data.
+    async fn wait_for(&self, bytes: usize) {
+        let start = {
+            let mut next_available = self
+                .next_available
+                .lock()
+                .expect("rate limiter lock poisoned");
+            // Don't let unused time pile up, or we'd allow big bursts after an
+            // idle period.
+            let start = max(*next_available, Instant::now());
+            *next_available = start + self.cost(bytes);
+            start
+        };
+        sleep_until(start).await;
+    }
+}
+
+#[test]
+fn rate_limiter_costs() {
+    let limiter = RateLimiter::new(1000).unwrap();
+    assert_eq!(limiter.cost(1000), Duration::from_secs(1));
+    assert_eq!(limiter.cost(1), Duration::from_millis(1));
+    assert_eq!(limiter.cost(0), Duration::from_secs(0));
+    assert!(RateLimiter::new(0).is_err());
+}
@@ -313,10 +313,7 @@ impl Read for SyncStreamReader {
                 Some(Err(err)) => {
                     error!(self.ctx.log(), "error reading from stream: {}", err);
                     self.seen_error = true;
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        Box::new(err.compat()),
-                    ));
+                    return Err(io::Error::other(Box::new(err.compat())));
                 }
             }
         }
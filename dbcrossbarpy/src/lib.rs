@@ -0,0 +1,258 @@
+//! Python bindings for `dbcrossbar`.
+//!
+//! This exposes schema introspection, schema conversion and copying as a
+//! native `dbcrossbar` Python module, so that Python programs don't need to
+//! shell out to the CLI and parse its error messages.
+
+#![warn(missing_docs, unused_extern_crates, clippy::all)]
+
+use dbcrossbarffi::{error_message, run_with_context, run_with_logger};
+use dbcrossbarlib::{
+    BoxLocator, CopyOperation, DriverArguments, Error, ErrorKind, IfExists, Result,
+    UnparsedLocator,
+};
+use futures::TryStreamExt;
+use pyo3::{prelude::*, types::PyDict, wrap_pyfunction};
+use slog::{o, Drain, Level, Logger, Never, OwnedKVList, Record, KV};
+use std::{fmt, panic::AssertUnwindSafe};
+
+use self::exceptions::*;
+
+/// Our Python exception types. These are declared in their own module, because
+/// `create_exception!` generates public items that we can't document.
+mod exceptions {
+    use pyo3::{create_exception, exceptions::PyException};
+
+    create_exception!(dbcrossbar, DbcrossbarError, PyException);
+    create_exception!(dbcrossbar, ConnectionError, DbcrossbarError);
+    create_exception!(dbcrossbar, UnsupportedTypeError, DbcrossbarError);
+    create_exception!(dbcrossbar, DriverArgumentsError, DbcrossbarError);
+    create_exception!(dbcrossbar, InvalidDataError, DbcrossbarError);
+    create_exception!(dbcrossbar, CancelledError, DbcrossbarError);
+}
+
+/// Convert a Rust error into a Python exception. If `err` has an `ErrorKind`,
+/// we raise the matching subclass of `DbcrossbarError`, and attach the
+/// details as attributes.
+fn to_py_err(py: Python, err: Error) -> PyErr {
+    let msg = error_message(&err);
+    let (py_err, attrs): (_, Vec<(&str, PyObject)>) = match ErrorKind::of(&err) {
+        Some(ErrorKind::Connection { target }) => (
+            ConnectionError::new_err(msg),
+            vec![("target", target.to_object(py))],
+        ),
+        Some(ErrorKind::UnsupportedType { column, type_name }) => (
+            UnsupportedTypeError::new_err(msg),
+            vec![
+                ("column", column.to_object(py)),
+                ("type_name", type_name.to_object(py)),
+            ],
+        ),
+        Some(ErrorKind::DriverArguments { .. }) => {
+            (DriverArgumentsError::new_err(msg), vec![])
+        }
+        Some(ErrorKind::InvalidData { row, column, value }) => (
+            InvalidDataError::new_err(msg),
+            vec![
+                ("row", row.to_object(py)),
+                ("column", column.to_object(py)),
+                ("value", value.to_object(py)),
+            ],
+        ),
+        Some(ErrorKind::Cancelled { signal }) => (
+            CancelledError::new_err(msg),
+            vec![("signal", signal.to_object(py))],
+        ),
+        None => (DbcrossbarError::new_err(msg), vec![]),
+    };
+    for (name, value) in attrs {
+        if let Err(err) = py_err.instance(py).setattr(name, value) {
+            return err;
+        }
+    }
+    py_err
+}
+
+/// Look up the schema of `locator`, and return it in `dbcrossbar-schema`
+/// JSON format.
+#[pyfunction]
+#[text_signature = "(locator)"]
+fn schema_json(py: Python, locator: String) -> PyResult<String> {
+    py.allow_threads(|| dbcrossbarffi::schema_json_string(&locator))
+        .map_err(|err| to_py_err(py, err))
+}
+
+/// Convert `schema_json` into the format used by the schema driver
+/// `dest_scheme`, such as "postgres-sql:" or "bigquery-schema:".
+#[pyfunction]
+#[text_signature = "(schema_json, dest_scheme)"]
+fn schema_conv(
+    py: Python,
+    schema_json: String,
+    dest_scheme: String,
+) -> PyResult<String> {
+    py.allow_threads(|| dbcrossbarffi::schema_conv_string(&schema_json, &dest_scheme))
+        .map_err(|err| to_py_err(py, err))
+}
+
+/// Copy data from `from_locator` to `to_locator`, returning a list of
+/// locators describing where the data was written. The keyword arguments
+/// work like the options to `dbcrossbar cp`.
+///
+/// If `progress` is specified, it will be called as `progress(level,
+/// message, fields)` for each step of the copy. These are the same messages
+/// that `dbcrossbar -v cp` would log.
+#[pyfunction(
+    if_exists = "\"error\".to_owned()",
+    schema = "None",
+    temporaries = "vec![]",
+    from_args = "vec![]",
+    to_args = "vec![]",
+    where_clause = "None",
+    max_streams = "4",
+    progress = "None"
+)]
+#[text_signature = "(from_locator, to_locator, *, if_exists='error', schema=None, \
+                    temporaries=[], from_args=[], to_args=[], where_clause=None, \
+                    max_streams=4, progress=None)"]
+#[allow(clippy::too_many_arguments)]
+fn copy(
+    py: Python,
+    from_locator: String,
+    to_locator: String,
+    if_exists: String,
+    schema: Option<String>,
+    temporaries: Vec<String>,
+    from_args: Vec<String>,
+    to_args: Vec<String>,
+    where_clause: Option<String>,
+    max_streams: usize,
+    progress: Option<PyObject>,
+) -> PyResult<Vec<String>> {
+    py.allow_threads(|| -> Result<Vec<String>> {
+        let mut copy = CopyOperation::new(
+            parse_locator(&from_locator)?,
+            parse_locator(&to_locator)?,
+        )
+        .if_exists(if_exists.parse::<IfExists>()?)
+        .from_args(DriverArguments::from_cli_args(&from_args)?)
+        .to_args(DriverArguments::from_cli_args(&to_args)?)
+        .max_streams(max_streams);
+        if let Some(schema) = &schema {
+            copy = copy.schema(parse_locator(schema)?);
+        }
+        for temporary in temporaries {
+            copy = copy.temporary(temporary);
+        }
+        if let Some(where_clause) = where_clause {
+            copy = copy.where_clause(where_clause);
+        }
+        let run = move |ctx| async move {
+            copy.run(ctx)
+                .await?
+                .map_ok(|dest| dest.to_string())
+                .try_collect::<Vec<_>>()
+                .await
+        };
+        match progress {
+            Some(callback) => {
+                let log = Logger::root(
+                    ProgressDrain {
+                        callback: AssertUnwindSafe(callback),
+                    }
+                    .fuse(),
+                    o!(),
+                );
+                run_with_logger(log, run)
+            }
+            None => run_with_context(run),
+        }
+    })
+    .map_err(|err| to_py_err(py, err))
+}
+
+/// Parse a locator, without enabling unstable drivers.
+fn parse_locator(locator: &str) -> Result<BoxLocator> {
+    locator.parse::<UnparsedLocator>()?.parse(false)
+}
+
+/// A `slog` drain which passes log messages at `Level::Info` and above to a
+/// Python callback.
+struct ProgressDrain {
+    /// The Python function to call. `slog` requires drains to be unwind-safe,
+    /// and a panic can't leave a Python object half-updated from Rust's point
+    /// of view.
+    callback: AssertUnwindSafe<PyObject>,
+}
+
+impl Drain for ProgressDrain {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<(), Never> {
+        if !record.level().is_at_least(Level::Info) {
+            return Ok(());
+        }
+
+        // Collect our key-value pairs. Serializing to a `Vec` can't fail.
+        let mut fields = FieldCollector::default();
+        let _ = record.kv().serialize(record, &mut fields);
+        let _ = values.serialize(record, &mut fields);
+
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            let result = fields
+                .0
+                .iter()
+                .try_for_each(|(key, value)| dict.set_item(key, value))
+                .and_then(|()| {
+                    self.callback.call1(
+                        py,
+                        (record.level().as_str(), record.msg().to_string(), dict),
+                    )
+                });
+            // We can't raise an exception from inside a copy, so just report
+            // it the way Python reports exceptions in other callbacks.
+            if let Err(err) = result {
+                err.print(py);
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Collects the key-value pairs attached to a log message as strings.
+#[derive(Default)]
+struct FieldCollector(Vec<(String, String)>);
+
+impl slog::Serializer for FieldCollector {
+    fn emit_arguments(
+        &mut self,
+        key: slog::Key,
+        val: &fmt::Arguments<'_>,
+    ) -> slog::Result {
+        self.0.push((key.to_string(), val.to_string()));
+        Ok(())
+    }
+}
+
+/// The `dbcrossbar` Python module.
+#[pymodule]
+fn dbcrossbar(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("DbcrossbarError", py.get_type::<DbcrossbarError>())?;
+    m.add("ConnectionError", py.get_type::<ConnectionError>())?;
+    m.add(
+        "UnsupportedTypeError",
+        py.get_type::<UnsupportedTypeError>(),
+    )?;
+    m.add(
+        "DriverArgumentsError",
+        py.get_type::<DriverArgumentsError>(),
+    )?;
+    m.add("InvalidDataError", py.get_type::<InvalidDataError>())?;
+    m.add("CancelledError", py.get_type::<CancelledError>())?;
+    m.add_function(wrap_pyfunction!(schema_json, m)?)?;
+    m.add_function(wrap_pyfunction!(schema_conv, m)?)?;
+    m.add_function(wrap_pyfunction!(copy, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+"""Smoke tests for the `dbcrossbar` Python module.
+
+Build and install the module with `maturin develop`, then run `pytest`.
+"""
+
+import json
+
+import pytest
+
+import dbcrossbar
+
+POSTS_SQL = """
+CREATE TABLE posts (
+    id bigint NOT NULL,
+    title text
+);
+"""
+
+
+def test_schema_json(tmp_path):
+    sql_path = tmp_path / "posts.sql"
+    sql_path.write_text(POSTS_SQL)
+    schema = json.loads(dbcrossbar.schema_json(f"postgres-sql:{sql_path}"))
+    columns = schema["tables"][0]["columns"]
+    assert [c["name"] for c in columns] == ["id", "title"]
+
+
+def test_schema_conv(tmp_path):
+    sql_path = tmp_path / "posts.sql"
+    sql_path.write_text(POSTS_SQL)
+    schema = dbcrossbar.schema_json(f"postgres-sql:{sql_path}")
+    bq_schema = json.loads(dbcrossbar.schema_conv(schema, "bigquery-schema:"))
+    assert [c["name"] for c in bq_schema] == ["id", "title"]
+
+
+def test_copy(tmp_path):
+    (tmp_path / "in.csv").write_text("id,title\n1,Hello\n")
+    messages = []
+    dests = dbcrossbar.copy(
+        f"csv:{tmp_path / 'in.csv'}",
+        f"csv:{tmp_path / 'out.csv'}",
+        progress=lambda level, msg, fields: messages.append((level, msg, fields)),
+    )
+    assert dests == [f"csv:{tmp_path / 'out.csv'}"]
+    assert (tmp_path / "out.csv").read_text() == "id,title\n1,Hello\n"
+    assert messages, "expected at least one progress message"
+    level, msg, fields = messages[0]
+    assert isinstance(level, str)
+    assert isinstance(msg, str)
+    assert isinstance(fields, dict)
+
+
+def test_errors_have_types(tmp_path):
+    with pytest.raises(dbcrossbar.DbcrossbarError):
+        dbcrossbar.schema_json("nope:example")
+
+    (tmp_path / "in.csv").write_text("id,title\n1,Hello\n")
+    with pytest.raises(dbcrossbar.ConnectionError) as excinfo:
+        dbcrossbar.copy(
+            f"csv:{tmp_path / 'in.csv'}",
+            "postgres://postgres@localhost:1/nonexistent#posts",
+            schema=f"csv:{tmp_path / 'in.csv'}",
+        )
+    assert isinstance(excinfo.value, dbcrossbar.DbcrossbarError)
+    assert "localhost" in excinfo.value.target
@@ -1,6 +1,6 @@
 //! BigQuery columns.
 
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use super::{
     export_udf::{generate_export_udf, needs_custom_json_export},
@@ -13,7 +13,7 @@ use crate::schema::Column;
 
 /// Extensions to `Column` (the portable version) to handle BigQuery-query
 /// specific stuff.
-pub(crate) trait ColumnBigQueryExt {
+pub trait ColumnBigQueryExt {
     /// Can BigQuery import this column from a CSV file without special
     /// processing?
     fn bigquery_can_import_from_csv(&self, schema: &Schema) -> Result<bool>;
@@ -27,7 +27,7 @@ impl ColumnBigQueryExt for Column {
 
 /// A BigQuery column declaration.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub(crate) struct BqColumn {
+pub struct BqColumn {
     /// An optional description of the BigQuery column.
     #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<String>,
@@ -60,7 +60,7 @@ impl BqColumn {
     /// `BqColumn`.
     ///
     /// Note that dashes and spaces are replaced with underscores to satisfy BigQuery naming rules.
-    pub(crate) fn for_column(
+    pub fn for_column(
         schema: &Schema,
         name: ColumnName,
         col: &Column,
@@ -84,7 +84,7 @@ impl BqColumn {
     }
 
     /// Given a `BqColumn`, construct a portable `Column`.
-    pub(crate) fn to_column(&self) -> Result<Column> {
+    pub fn to_column(&self) -> Result<Column> {
         Ok(Column {
             name: self.name.to_portable_name(),
             data_type: self.bq_data_type()?.to_data_type()?,
@@ -99,7 +99,7 @@ impl BqColumn {
     }
 
     /// Can we MERGE on this column? True is this column is `NOT NULL`.
-    pub(crate) fn can_be_merged_on(&self) -> bool {
+    pub fn can_be_merged_on(&self) -> bool {
         match self.mode {
             Mode::Required => true,
             Mode::Repeated | Mode::Nullable => false,
@@ -108,7 +108,7 @@ impl BqColumn {
 
     /// Get the BigQuery data type for this column, taking into account
     /// shenanigans like `RECORD` and `REPEATED`.
-    pub(crate) fn bq_data_type(&self) -> Result<BqDataType> {
+    pub fn bq_data_type(&self) -> Result<BqDataType> {
         let ty = match &self.ty {
             BqRecordOrNonArrayDataType::Record => {
                 let fields = self
@@ -127,7 +127,7 @@ impl BqColumn {
     }
 
     /// Should this column be declared as `NOT NULL` when generating a `CREATE TABLE`?
-    pub(crate) fn is_not_null(&self) -> bool {
+    pub fn is_not_null(&self) -> bool {
         match &self.mode {
             Mode::Required => true,
             Mode::Repeated | Mode::Nullable => false,
@@ -142,7 +142,7 @@ impl BqColumn {
     /// information available in `other` than we have in `self`. We can't just
     /// use `other` directly, because it may be less _accurate_ than what we
     /// have in `self`, and we need accurate types to export correctly.
-    pub(crate) fn aligned_with(&self, other: &BqColumn) -> Result<BqColumn> {
+    pub fn aligned_with(&self, other: &BqColumn) -> Result<BqColumn> {
         // Check to make sure that our columns have the same name. (Should be
         // guaranteed by our caller.)
         if self.name != other.name {
@@ -188,7 +188,7 @@ impl BqColumn {
 
     /// Convert this column into a struct field. We use this to implement
     /// `RECORD` column parsing.
-    pub(crate) fn to_struct_field(&self) -> Result<BqStructField> {
+    pub fn to_struct_field(&self) -> Result<BqStructField> {
         Ok(BqStructField {
             name: Some(self.name.clone()),
             ty: self.bq_data_type()?,
@@ -197,11 +197,7 @@ impl BqColumn {
 
     /// Output JavaScript UDF for importing a column (if necessary). This can be
     /// used to patch up types that can't be loaded directly from a CSV.
-    pub(crate) fn write_import_udf(
-        &self,
-        f: &mut dyn Write,
-        idx: usize,
-    ) -> Result<()> {
+    pub fn write_import_udf(&self, f: &mut dyn Write, idx: usize) -> Result<()> {
         match self.bq_data_type()? {
             // JavaScript UDFs can't return `DATETIME` yet, so we need a fairly
             // elaborate workaround.
@@ -249,7 +245,7 @@ AS ((
     /// declare it `'static` as a hack to more or less enforce this.
     ///
     /// This should never fail when writing output to a `Vec<u8>`.
-    pub(crate) fn write_import_expr(
+    pub fn write_import_expr(
         &self,
         f: &mut dyn Write,
         idx: usize,
@@ -282,7 +278,7 @@ AS ((
 
     /// Output the SQL expression used in the `SELECT` clause of our table
     /// import statement.
-    pub(crate) fn write_import_select_expr(
+    pub fn write_import_select_expr(
         &self,
         f: &mut dyn Write,
         idx: usize,
@@ -293,11 +289,7 @@ AS ((
     }
 
     /// Write an an export UDF function if we need one.
-    pub(crate) fn write_export_udf(
-        &self,
-        f: &mut dyn Write,
-        idx: usize,
-    ) -> Result<()> {
+    pub fn write_export_udf(&self, f: &mut dyn Write, idx: usize) -> Result<()> {
         if needs_custom_json_export(&self.bq_data_type()?)?.in_sql_code() {
             generate_export_udf(self, idx, f)?;
         }
@@ -306,14 +298,14 @@ AS ((
 
     /// Output the SQL expression used in the `SELECT` clause of our table
     /// export statement.
-    pub(crate) fn write_export_select_expr(
+    pub fn write_export_select_expr(
         &self,
         f: &mut dyn Write,
         idx: usize,
     ) -> Result<()> {
         match &self.bq_data_type()? {
             // Some types need a custom export function.
-            ty if needs_custom_json_export(&ty)?.in_sql_code() => {
+            ty if needs_custom_json_export(ty)?.in_sql_code() => {
                 write!(
                     f,
                     "ExportJson_{idx}({name}) AS {name}",
@@ -392,7 +384,7 @@ AS ((
     }
 
     /// Output a `SELECT`-clause expression for a non-`ARRAY<...>` column.
-    pub(crate) fn write_export_select_expr_for_non_array(
+    pub fn write_export_select_expr_for_non_array(
         &self,
         data_type: &BqNonArrayDataType,
         f: &mut dyn Write,
@@ -480,7 +472,7 @@ fn column_without_mode() {
 /// A column mode.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub(crate) enum Mode {
+pub enum Mode {
     /// This column is `NOT NULL`.
     Required,
 
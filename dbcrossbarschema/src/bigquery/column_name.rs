@@ -9,6 +9,7 @@ use std::{
     str::FromStr,
 };
 
+use super::Ident;
 use crate::common::*;
 
 /// A BigQuery column name.
@@ -33,7 +34,7 @@ use crate::common::*;
 ///
 /// [docs]: https://cloud.google.com/bigquery/docs/schemas#column_names
 #[derive(Clone)]
-pub(crate) struct ColumnName {
+pub struct ColumnName {
     /// The original, mixed-case string, followed by an all-lowercase copy.
     ///
     /// Since we know that ASCII strings always have one character per byte, and
@@ -44,7 +45,7 @@ pub(crate) struct ColumnName {
 
 impl ColumnName {
     /// The original string, including case information.
-    pub(crate) fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &str {
         // We store the original string in the first half.
         &self.data[..self.data.len() / 2]
     }
@@ -56,17 +57,17 @@ impl ColumnName {
     }
 
     /// Convert this to a portable name.
-    pub(crate) fn to_portable_name(&self) -> String {
+    pub fn to_portable_name(&self) -> String {
         self.as_str().to_owned()
     }
 
     /// Quote this for use in SQL.
-    pub(crate) fn quoted(&self) -> ColumnNameQuoted<'_> {
+    pub fn quoted(&self) -> ColumnNameQuoted<'_> {
         ColumnNameQuoted(self)
     }
 
     /// Quote this for use in JavaScript.
-    pub(crate) fn javascript_quoted(&self) -> ColumnNameJavaScriptQuoted<'_> {
+    pub fn javascript_quoted(&self) -> ColumnNameJavaScriptQuoted<'_> {
         ColumnNameJavaScriptQuoted(self)
     }
 }
@@ -163,7 +164,7 @@ impl<'de> Deserialize<'de> for ColumnName {
         D: Deserializer<'de>,
     {
         let s: &str = Deserialize::deserialize(deserializer)?;
-        Ok(ColumnName::try_from(s).map_err(de::Error::custom)?)
+        ColumnName::try_from(s).map_err(de::Error::custom)
     }
 }
 
@@ -171,12 +172,12 @@ impl<'de> Deserialize<'de> for ColumnName {
 ///
 /// We avoid defining `Display` directly on `ColumnName`, so that there's no way
 /// to display it without making a decision.
-pub(crate) struct ColumnNameQuoted<'a>(&'a ColumnName);
+pub struct ColumnNameQuoted<'a>(&'a ColumnName);
 
 impl<'a> fmt::Display for ColumnNameQuoted<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Always quote, just in case the column name is a keyword.
-        write!(f, "`{}`", self.0.as_str())
+        write!(f, "{}", Ident(self.0.as_str()))
     }
 }
 
@@ -185,7 +186,7 @@ impl<'a> fmt::Display for ColumnNameQuoted<'a> {
 ///
 /// TODO: Do we need to anything special with case-handling here? BigQuery
 /// ignores case, but JavaScript treats it as significant.
-pub(crate) struct ColumnNameJavaScriptQuoted<'a>(&'a ColumnName);
+pub struct ColumnNameJavaScriptQuoted<'a>(&'a ColumnName);
 
 impl<'a> fmt::Display for ColumnNameJavaScriptQuoted<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -12,7 +12,7 @@ mod grammar;
 
 /// Extensions to `DataType` (the portable version) to handle BigQuery-query
 /// specific stuff.
-pub(crate) trait DataTypeBigQueryExt {
+pub trait DataTypeBigQueryExt {
     /// Can BigQuery import this type from a CSV file?
     fn bigquery_can_import_from_csv(&self, schema: &Schema) -> Result<bool>;
 }
@@ -27,7 +27,7 @@ impl DataTypeBigQueryExt for DataType {
 
 /// How do we intend to use a BigQuery type?
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum Usage {
+pub enum Usage {
     /// We intend to use this type for loading from a CSV, which means we can't
     /// that certain data types will need to be treated as `STRING`.
     CsvLoad,
@@ -37,9 +37,6 @@ pub(crate) enum Usage {
 }
 
 /// A BigQuery data type.
-///
-/// This is marked `pub` instead of `pub(crate)` because of limitations in
-/// `rust-peg`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum BqDataType {
     /// An array type. May not contain another directly nested array inside
@@ -54,7 +51,7 @@ impl BqDataType {
     /// BigQuery, map it to a corresponding `BqDataType`.
     ///
     /// See https://cloud.google.com/bigquery/docs/reference/standard-sql/data-types.
-    pub(crate) fn for_data_type(
+    pub fn for_data_type(
         schema: &Schema,
         data_type: &DataType,
         usage: Usage,
@@ -84,7 +81,7 @@ impl BqDataType {
     }
 
     /// Convert this `BqDataType` to `DataType`.
-    pub(crate) fn to_data_type(&self) -> Result<DataType> {
+    pub fn to_data_type(&self) -> Result<DataType> {
         match self {
             BqDataType::Array(ty) => Ok(DataType::Array(Box::new(ty.to_data_type()?))),
             BqDataType::NonArray(ty) => ty.to_data_type(),
@@ -92,12 +89,12 @@ impl BqDataType {
     }
 
     /// Can BigQuery import this type from a CSV file?
-    pub(crate) fn bigquery_can_import_from_csv(&self) -> bool {
+    pub fn bigquery_can_import_from_csv(&self) -> bool {
         matches!(self, BqDataType::Array(_))
     }
 
     /// Can this type be safely represented as a JSON value?
-    pub(crate) fn is_json_safe(&self) -> bool {
+    pub fn is_json_safe(&self) -> bool {
         match self {
             BqDataType::Array(ty) => ty.is_json_safe(),
             BqDataType::NonArray(ty) => ty.is_json_safe(),
@@ -110,7 +107,7 @@ impl BqDataType {
     /// This is used to replace `BqNonArrayDataType::String` with
     /// `BqNonArrayDataType::Stringified(_)` when we have more specific type
     /// information available.
-    pub(crate) fn aligned_with(&self, other: &BqDataType) -> Result<BqDataType> {
+    pub fn aligned_with(&self, other: &BqDataType) -> Result<BqDataType> {
         match (self, other) {
             (BqDataType::Array(self_nested), BqDataType::Array(other_nested)) => {
                 Ok(BqDataType::Array(self_nested.aligned_with(other_nested)?))
@@ -164,9 +161,6 @@ impl Serialize for BqDataType {
 /// Either a regular BigQuery non-array data type or `"RECORD"`, which appears
 /// as a placeholder in BigQuery schema files, but it really a placeholder
 /// telling us to construct a `STRUCT` type using the column's `"fields"`.
-///
-/// This is marked `pub` instead of `pub(crate)` because of limitations in
-/// `rust-peg`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum BqRecordOrNonArrayDataType {
     Record,
@@ -208,8 +202,6 @@ impl Serialize for BqRecordOrNonArrayDataType {
     }
 }
 /// Any type except `ARRAY` (which cannot be nested in another `ARRAY`).
-///
-/// This should really be `pub(crate)`, see [BqDataType].
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(dead_code)]
 pub enum BqNonArrayDataType {
@@ -305,7 +297,7 @@ impl BqNonArrayDataType {
     }
 
     /// Convert this `BqNonArrayDataType` to a portable `DataType`.
-    pub(crate) fn to_data_type(&self) -> Result<DataType> {
+    pub fn to_data_type(&self) -> Result<DataType> {
         match self {
             BqNonArrayDataType::Bool => Ok(DataType::Bool),
             BqNonArrayDataType::Date => Ok(DataType::Date),
@@ -356,7 +348,7 @@ impl BqNonArrayDataType {
     }
 
     /// Can this type be safely represented as a JSON value?
-    pub(crate) fn is_json_safe(&self) -> bool {
+    pub fn is_json_safe(&self) -> bool {
         match self {
             BqNonArrayDataType::Struct(fields) => {
                 for field in fields {
@@ -386,7 +378,7 @@ impl BqNonArrayDataType {
     /// This is used to replace `BqNonArrayDataType::String` with
     /// `BqNonArrayDataType::Stringified(_)` when we have more specific type
     /// information available.
-    pub(crate) fn aligned_with(
+    pub fn aligned_with(
         &self,
         other: &BqNonArrayDataType,
     ) -> Result<BqNonArrayDataType> {
@@ -489,8 +481,6 @@ impl Serialize for BqNonArrayDataType {
 }
 
 /// A field of a `STRUCT`.
-///
-/// This should really be `pub(crate)`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BqStructField {
     /// An optional field name. BigQuery `STRUCT`s are basically tuples, but
@@ -499,9 +489,9 @@ pub struct BqStructField {
     /// We assume, with no particular documentation that we've seen, that these
     /// follow the rules from columns names and not generic BigQuery
     /// identifiers. However, they do _not_ need to be unique within a struct.
-    pub(crate) name: Option<ColumnName>,
+    pub name: Option<ColumnName>,
     /// The field type.
-    pub(crate) ty: BqDataType,
+    pub ty: BqDataType,
 }
 
 impl BqStructField {
@@ -14,7 +14,7 @@ use crate::schema::DataType;
 /// `OnlyInsideUdf > Never`. We use this with `max` to combine
 /// `NeedsCustomJsonExport` values.
 #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
-pub(crate) enum NeedsCustomJsonExport {
+pub enum NeedsCustomJsonExport {
     /// This type will always be correctly serialized without any custom
     /// processing, from either SQL or JavaScript.
     Never,
@@ -29,7 +29,7 @@ pub(crate) enum NeedsCustomJsonExport {
 
 impl NeedsCustomJsonExport {
     /// Does this type require custom export in an SQL context?
-    pub(crate) fn in_sql_code(self) -> bool {
+    pub fn in_sql_code(self) -> bool {
         match self {
             NeedsCustomJsonExport::Never | NeedsCustomJsonExport::OnlyInsideUdf => {
                 false
@@ -51,9 +51,7 @@ impl NeedsCustomJsonExport {
 
 /// If `ty` appears inside a JSON value, will it need a JSON export that's
 /// fancier than what's provided by `TO_JSON_STRING` on the entire value?
-pub(crate) fn needs_custom_json_export(
-    ty: &BqDataType,
-) -> Result<NeedsCustomJsonExport> {
+pub fn needs_custom_json_export(ty: &BqDataType) -> Result<NeedsCustomJsonExport> {
     match ty {
         BqDataType::Array(nested) => non_array_needs_custom_json_export(nested),
         BqDataType::NonArray(nested) => non_array_needs_custom_json_export(nested),
@@ -131,7 +129,7 @@ fn non_array_needs_custom_json_export(
 ///
 /// Note that we prefer to avoid calling this, because many common types can
 /// exported using SQL, which is faster.
-pub(crate) fn generate_export_udf(
+pub fn generate_export_udf(
     column: &BqColumn,
     idx: usize,
     f: &mut dyn Write,
@@ -10,7 +10,7 @@ use crate::schema::DataType;
 
 /// Given `column` and its index `idx`, generate a UDF function to deserialize
 /// JSON strings and convert them to values of the appropriate type.
-pub(crate) fn generate_import_udf(
+pub fn generate_import_udf(
     column: &BqColumn,
     idx: usize,
     f: &mut dyn Write,
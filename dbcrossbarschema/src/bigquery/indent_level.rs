@@ -2,16 +2,16 @@ use std::fmt;
 
 /// How many levels should we indent generated code?
 #[derive(Clone, Copy)]
-pub(crate) struct IndentLevel(u8);
+pub struct IndentLevel(u8);
 
 impl IndentLevel {
     /// No idententation.
-    pub(crate) fn none() -> Self {
+    pub fn none() -> Self {
         IndentLevel(0)
     }
 
     /// Indent by one more level, up a maximum level.
-    pub(crate) fn incr(self) -> Self {
+    pub fn incr(self) -> Self {
         IndentLevel(self.0.saturating_add(1))
     }
 }
@@ -0,0 +1,28 @@
+//! BigQuery table schemas.
+//!
+//! Much of this code falls into a few major categories:
+//!
+//! - Extension traits which extend "portable" types with BigQuery-specific
+//!   APIs. These wrappers include [`TableBigQueryExt`], [`ColumnBigQueryExt`]
+//!   and [`DataTypeBigQueryExt`].
+//! - Native BigQuery equivalents of our portable types, including [`BqTable`],
+//!   [`BqColumn`] and [`BqDataType`].
+//!
+//! The best starting points are probably [`TableBigQueryExt`] and [`BqTable`].
+
+mod column;
+mod column_name;
+mod data_type;
+mod export_udf;
+mod import_udf;
+mod indent_level;
+mod schema;
+mod table;
+mod table_name;
+
+pub use self::column::*;
+pub use self::column_name::*;
+pub use self::data_type::*;
+pub use self::schema::*;
+pub use self::table::*;
+pub use self::table_name::*;
@@ -5,7 +5,7 @@ use crate::common::*;
 
 /// Extensions to `Column` (the portable version) to handle BigQuery-query
 /// specific stuff.
-pub(crate) trait SchemaBigQueryExt {
+pub trait SchemaBigQueryExt {
     /// Can we import data into this table directly from a CSV file?
     fn bigquery_can_import_from_csv(&self) -> Result<bool>;
 }
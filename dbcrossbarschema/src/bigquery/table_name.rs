@@ -0,0 +1,144 @@
+//! BigQuery table names.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+
+/// A BigQuery table name of the form `"project:dataset.table"`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TableName {
+    /// The name of the Google Cloud project.
+    project: String,
+    /// The BigQuery dataset.
+    dataset: String,
+    /// The table.
+    table: String,
+}
+
+impl TableName {
+    /// Create a new table name from its parts. The parts must not contain `:`,
+    /// `.` or backticks.
+    pub fn new<P, D, T>(project: P, dataset: D, table: T) -> TableName
+    where
+        P: Into<String>,
+        D: Into<String>,
+        T: Into<String>,
+    {
+        TableName {
+            project: project.into(),
+            dataset: dataset.into(),
+            table: table.into(),
+        }
+    }
+
+    /// Return the name of the table's project.
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// Return the name of the table's dataset.
+    pub fn dataset(&self) -> &str {
+        &self.dataset
+    }
+
+    /// Return the bare table name itself, without project or dataset.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Return a value which will be formatted as
+    /// `"\`project\`.\`dataset\`.\`table\`"`, with "backtick" quoting.
+    ///
+    /// This form of the name is used in BigQuery "standard SQL".
+    pub fn dotted_and_quoted(&self) -> DottedTableName<'_> {
+        DottedTableName(self)
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}.{}", self.project, self.dataset, self.table)
+    }
+}
+
+impl FromStr for TableName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new("^([^:.`]+):([^:.`]+).([^:.`]+)$")
+                .expect("could not parse built-in regex");
+        }
+        let cap = RE.captures(s).ok_or_else(|| {
+            format_err!("could not parse BigQuery table name: {:?}", s)
+        })?;
+        let (project, dataset, table) = (&cap[1], &cap[2], &cap[3]);
+        Ok(TableName {
+            project: project.to_string(),
+            dataset: dataset.to_string(),
+            table: table.to_string(),
+        })
+    }
+}
+
+/// A short-lived wrapped type which displays a BigQuery table name as
+/// `"\`project\`.\`dataset\`.\`table\`"`, with "backtick" quoting.
+///
+/// This form of the name is used in BigQuery "standard SQL".
+pub struct DottedTableName<'a>(&'a TableName);
+
+impl<'a> fmt::Display for DottedTableName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}",
+            Ident(&self.0.project),
+            Ident(&self.0.dataset),
+            Ident(&self.0.table),
+        )
+    }
+}
+
+/// A BigQuery identifier, for formatting purposes.
+///
+/// We always quote identifiers with backticks, so that they can't clash with
+/// reserved words. Quoted identifiers use the same escape sequences as string
+/// literals, so we escape backticks, backslashes and control characters.
+pub struct Ident<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Ident<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`")?;
+        for c in self.0.chars() {
+            match c {
+                '`' => write!(f, "\\`")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "`")
+    }
+}
+
+#[test]
+fn ident_escapes_special_characters() {
+    let examples = &[
+        ("a", "`a`"),
+        ("select", "`select`"),
+        ("MixedCase", "`MixedCase`"),
+        ("caf\u{e9}", "`caf\u{e9}`"),
+        ("a`b", r"`a\`b`"),
+        (r"a\b", r"`a\\b`"),
+        ("a\nb", r"`a\nb`"),
+        ("a\u{1}b", r"`a\u0001b`"),
+    ];
+    for &(input, expected) in examples {
+        assert_eq!(Ident(input).to_string(), expected);
+    }
+}
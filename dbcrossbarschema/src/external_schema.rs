@@ -7,16 +7,16 @@ use crate::{common::*, schema::NamedDataType};
 
 /// Our external schema format, version 2.
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub(crate) struct ExternalSchemaV2 {
-    pub(crate) named_data_types: Vec<NamedDataType>,
-    pub(crate) tables: Vec<Table>,
+pub struct ExternalSchemaV2 {
+    pub named_data_types: Vec<NamedDataType>,
+    pub tables: Vec<Table>,
 }
 
 /// Our external schema format. This exists so that `serde` can magically figure
 /// out which version of our external schema format we're using.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
-pub(crate) enum ExternalSchema {
+pub enum ExternalSchema {
     /// A full schema, including a single table and a list of types.
     V2(ExternalSchemaV2),
     /// Just a bare top-level table, from before we added support for tables and
@@ -26,20 +26,16 @@ pub(crate) enum ExternalSchema {
 
 impl ExternalSchema {
     /// Turn a portable schema into an external schema (we always use v2).
-    pub(crate) fn from_schema(schema: Schema) -> Self {
+    pub fn from_schema(schema: Schema) -> Self {
         let v2 = ExternalSchemaV2 {
-            named_data_types: schema
-                .named_data_types
-                .into_iter()
-                .map(|(_, v)| v)
-                .collect(),
+            named_data_types: schema.named_data_types.into_values().collect(),
             tables: vec![schema.table],
         };
         ExternalSchema::V2(v2)
     }
 
     /// Convert an external schema to our internal format.
-    pub(crate) fn into_schema(self) -> Result<Schema> {
+    pub fn into_schema(self) -> Result<Schema> {
         match self {
             ExternalSchema::V2(mut v2) => {
                 if v2.tables.len() != 1 {
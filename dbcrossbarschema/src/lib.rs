@@ -0,0 +1,82 @@
+//! Portable table schemas, and conversions to and from database-specific
+//! schemas.
+//!
+//! This crate contains the schema-conversion logic used by `dbcrossbar`, but
+//! none of the code that talks to databases or cloud storage. It has no async
+//! runtime or native dependencies, so it can be compiled for
+//! `wasm32-unknown-unknown` and used in a browser.
+//!
+//! The [`schema`] module defines our portable schema. The [`postgres`] and
+//! [`bigquery`] modules convert portable schemas to and from PostgreSQL
+//! `CREATE TABLE` statements and BigQuery JSON schemas and DDL.
+//!
+//! ```
+//! use dbcrossbarschema::{
+//!     bigquery::{BqTable, TableName, Usage},
+//!     postgres::PgSchema,
+//! };
+//!
+//! let sql = "CREATE TABLE example (id int NOT NULL, name text);";
+//! let pg_schema = PgSchema::parse("example.sql".to_owned(), sql.to_owned())
+//!     .expect("could not parse SQL");
+//! let schema = pg_schema.to_schema().unwrap();
+//!
+//! let name = "project:dataset.example".parse::<TableName>().unwrap();
+//! let table = BqTable::for_table_name_and_columns(
+//!     &schema,
+//!     name,
+//!     &schema.table.columns,
+//!     Usage::FinalTable,
+//! )
+//! .unwrap();
+//! let mut json = vec![];
+//! table.write_json_schema(&mut json).unwrap();
+//! ```
+
+#![forbid(unsafe_code)]
+#![warn(
+    unused_extern_crates,
+    clippy::all,
+    clippy::cargo,
+    clippy::cast_lossless,
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::inefficient_to_string
+)]
+// We handle this using `cargo deny` instead.
+#![allow(clippy::multiple_crate_versions)]
+// Allow functions that can't fail to return `Result`. These could be simplified
+// if we wanted.
+#![allow(clippy::unnecessary_wraps)]
+
+use std::result;
+
+pub mod bigquery;
+pub mod external_schema;
+pub mod parse_error;
+pub mod postgres;
+pub mod schema;
+pub mod separator;
+
+/// Standard error type for this library.
+pub use failure::Error;
+
+/// Standard result type for this library.
+pub type Result<T, E = Error> = result::Result<T, E>;
+
+/// Definitions included by all the files in this crate.
+#[allow(unused_imports)]
+pub(crate) mod common {
+    pub(crate) use failure::{format_err, ResultExt};
+    pub(crate) use std::{
+        convert::{TryFrom, TryInto},
+        io::Write,
+    };
+
+    pub(crate) use crate::{
+        schema::{Schema, Table},
+        Error, Result,
+    };
+}
@@ -8,18 +8,18 @@ use crate::schema::Column;
 
 /// A column in a PostgreSQL table.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub(crate) struct PgColumn {
+pub struct PgColumn {
     /// The name of this column.
-    pub(crate) name: String,
+    pub name: String,
     /// The type of data stored in this column.
-    pub(crate) data_type: PgDataType,
+    pub data_type: PgDataType,
     /// Can this column be `NULL`?
-    pub(crate) is_nullable: bool,
+    pub is_nullable: bool,
 }
 
 impl PgColumn {
     /// Given a portable `Column`, construct a `PgColumn`.
-    pub(crate) fn from_column(schema: &Schema, col: &Column) -> Result<PgColumn> {
+    pub fn from_column(schema: &Schema, col: &Column) -> Result<PgColumn> {
         let data_type = PgDataType::from_data_type(schema, &col.data_type)?;
         Ok(PgColumn {
             name: col.name.clone(),
@@ -29,7 +29,7 @@ impl PgColumn {
     }
 
     /// Given a `PgColumn`, construct a portable `Column`.
-    pub(crate) fn to_column(&self) -> Result<Column> {
+    pub fn to_column(&self) -> Result<Column> {
         Ok(Column {
             name: self.name.clone(),
             data_type: self.data_type.to_data_type()?,
@@ -39,7 +39,7 @@ impl PgColumn {
     }
 
     /// Write a `SELECT` expression for this column.
-    pub(crate) fn write_export_select_expr(&self, f: &mut dyn Write) -> Result<()> {
+    pub fn write_export_select_expr(&self, f: &mut dyn Write) -> Result<()> {
         let name = Ident(&self.name);
         let check_dimension = |dimension_count: i32| -> Result<()> {
             if dimension_count == 1 {
@@ -2,24 +2,23 @@
 
 use std::fmt;
 
-use tokio_postgres::Client;
-
 use crate::schema::NamedDataType;
 use crate::{common::*, schema::DataType};
 
-use super::{catalog, PgName};
+use super::PgName;
+
 /// A PostgreSQL `CREATE TYPE` declaration.
 #[derive(Clone, Debug)]
-pub(crate) struct PgCreateType {
+pub struct PgCreateType {
     /// The name of the custom type.
-    pub(crate) name: PgName,
+    pub name: PgName,
     /// The definition of the custom type.
-    pub(crate) definition: PgCreateTypeDefinition,
+    pub definition: PgCreateTypeDefinition,
 }
 
 impl PgCreateType {
     /// Convert a Postgres `PgCreateType` to a portable `NamedDataType`.
-    pub(crate) fn to_named_data_type(&self) -> Result<NamedDataType> {
+    pub fn to_named_data_type(&self) -> Result<NamedDataType> {
         Ok(NamedDataType {
             name: self.name.to_portable_name()?,
             data_type: self.definition.to_data_type()?,
@@ -27,22 +26,12 @@ impl PgCreateType {
     }
 
     /// Convert a portable `NamedDataType` to a Postgres `PgCreateType`.
-    pub(crate) fn from_named_data_type(ty: &NamedDataType) -> Result<Self> {
+    pub fn from_named_data_type(ty: &NamedDataType) -> Result<Self> {
         Ok(Self {
             name: PgName::from_portable_type_name(&ty.name)?,
             definition: PgCreateTypeDefinition::from_data_type(&ty.data_type)?,
         })
     }
-
-    /// Look up a `PgCreateType` by name using the specified database
-    /// connection.
-    pub(crate) async fn from_database(
-        ctx: &Context,
-        client: &Client,
-        type_name: &PgName,
-    ) -> Result<Option<Self>> {
-        catalog::fetch_create_type(ctx, client, type_name).await
-    }
 }
 
 impl fmt::Display for PgCreateType {
@@ -60,14 +49,14 @@ impl fmt::Display for PgCreateType {
 
 /// Definition of a PostgreSQL custom type.
 #[derive(Clone, Debug)]
-pub(crate) enum PgCreateTypeDefinition {
+pub enum PgCreateTypeDefinition {
     /// The body of a `CREATE TYPE name AS ENUM(...)` definition.
     Enum(Vec<String>),
 }
 
 impl PgCreateTypeDefinition {
     /// Convert a PostgreSQL `CREATE TYPE` definition into a portable data type.
-    pub(crate) fn to_data_type(&self) -> Result<DataType> {
+    pub fn to_data_type(&self) -> Result<DataType> {
         match self {
             PgCreateTypeDefinition::Enum(values) => {
                 Ok(DataType::OneOf(values.to_owned()))
@@ -77,7 +66,7 @@ impl PgCreateTypeDefinition {
 
     /// Construct a PostgreSQL `CREATE TYPE` definition from a portable data
     /// type.
-    pub(crate) fn from_data_type(ty: &DataType) -> Result<Self> {
+    pub fn from_data_type(ty: &DataType) -> Result<Self> {
         match ty {
             DataType::OneOf(values) => Ok(Self::Enum(values.to_owned())),
             _ => Err(format_err!(
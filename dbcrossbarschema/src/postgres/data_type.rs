@@ -12,7 +12,7 @@ use super::PgName;
 /// This is obviously simplified, but feel free to "unsimplify" it by adding
 /// any other useful types or details of types.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub(crate) enum PgDataType {
+pub enum PgDataType {
     /// An array type.
     Array {
         /// The number of dimensions of this array.
@@ -26,10 +26,7 @@ pub(crate) enum PgDataType {
 
 impl PgDataType {
     /// Given a `DataType`, try to find a corresponding `PgDataType`.
-    pub(crate) fn from_data_type(
-        schema: &Schema,
-        ty: &DataType,
-    ) -> Result<PgDataType> {
+    pub fn from_data_type(schema: &Schema, ty: &DataType) -> Result<PgDataType> {
         match ty {
             DataType::Array(nested) => {
                 // Iterate over our nested child array types, figuring out how
@@ -52,7 +49,7 @@ impl PgDataType {
     }
 
     /// Convert this `PgDataType` to a portable `DataType`.
-    pub(crate) fn to_data_type(&self) -> Result<DataType> {
+    pub fn to_data_type(&self) -> Result<DataType> {
         match self {
             PgDataType::Array {
                 dimension_count,
@@ -119,7 +116,7 @@ impl fmt::Display for PgDataType {
 /// As with `PgDataType`, feel free to add any details you need here.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(missing_docs)]
-pub(crate) enum PgScalarDataType {
+pub enum PgScalarDataType {
     Boolean,
     Date,
     Numeric,
@@ -194,7 +191,7 @@ impl PgScalarDataType {
     }
 
     /// Convert this `PgDataType` to a portable `DataType`.
-    pub(crate) fn to_data_type(&self) -> Result<DataType> {
+    pub fn to_data_type(&self) -> Result<DataType> {
         match self {
             PgScalarDataType::Boolean => Ok(DataType::Bool),
             PgScalarDataType::Date => Ok(DataType::Date),
@@ -223,7 +220,7 @@ impl PgScalarDataType {
     /// See [this list of types and OIDs][types].
     ///
     /// [types]: https://github.com/postgres/postgres/blob/master/src/include/catalog/pg_type.dat
-    pub(crate) fn oid(&self) -> Result<i32> {
+    pub fn oid(&self) -> Result<i32> {
         match self {
             PgScalarDataType::Boolean => Ok(16),
             PgScalarDataType::Date => Ok(1082),
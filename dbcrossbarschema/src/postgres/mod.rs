@@ -0,0 +1,195 @@
+//! PostgreSQL table schemas, including `CREATE TABLE` and `CREATE TYPE`.
+
+use std::{fmt, str::FromStr};
+
+use crate::common::*;
+
+mod column;
+mod create_type;
+mod data_type;
+mod schema;
+mod table;
+
+pub use self::column::PgColumn;
+pub use self::create_type::{PgCreateType, PgCreateTypeDefinition};
+pub use self::data_type::{PgDataType, PgScalarDataType};
+pub use self::schema::PgSchema;
+pub use self::table::{
+    PgCreateTable, PgDistribution, RedshiftDistStyle, RedshiftTableAttributes,
+};
+
+/// A PostgreSQL identifier. This is always printed with double quotes, which
+/// prevents clashes with keywords and preserves case. Double quotes inside the
+/// identifier are doubled.
+pub struct Ident<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Ident<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        write!(f, "{}", self.0.replace('"', "\"\""))?;
+        write!(f, "\"")?;
+        Ok(())
+    }
+}
+
+/// A PostgreSQL table or type name, including a possible PostgreSQL schema (in
+/// the PostgreSQL sense of a namespace, not what `dbcrossbar` calls a
+/// "schema").
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PgName {
+    /// A PostgreSQL namespace (not what `dbcrossbar` normally means by
+    /// "schema")!
+    schema: Option<String>,
+    /// Our underlying name.
+    name: String,
+}
+
+impl PgName {
+    /// Create a new `TableName`.
+    pub fn new<S, T>(schema: S, name: T) -> Self
+    where
+        S: Into<Option<String>>,
+        T: Into<String>,
+    {
+        Self {
+            schema: schema.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Given the name of `NamedDataType`, construct a PostgreSQL `TableName`.
+    pub fn from_portable_type_name<T>(type_name: T) -> Result<Self>
+    where
+        T: Into<String>,
+    {
+        let type_name = type_name.into();
+        if type_name.contains('.') {
+            // We don't yet have a design for mapping portable enums to enums in
+            // PostgreSQL schemas other than `"public"`. Getting this right will
+            // require some thought, so just error out for now.
+            Err(format_err!(
+                "portable type names containing \".\" are not yet supported: {:?}",
+                type_name
+            ))
+        } else {
+            Ok(Self::new(None, type_name))
+        }
+    }
+
+    /// The schema (namespace) portion of the table name, or `None` if none was provided.
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_ref().map(|s| &s[..])
+    }
+
+    /// The schema (namespace) portion of the table name, or `"public""` if none was provided.
+    pub fn schema_or_public(&self) -> &str {
+        self.schema.as_ref().map_or_else(|| "public", |s| &s[..])
+    }
+
+    /// The base portion of the name, not including the schema.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Format this name as an unquoted string.
+    pub fn unquoted(&self) -> String {
+        if let Some(schema) = &self.schema {
+            format!("{}.{}", schema, self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Properly quote a name for use in SQL. Returns a value that implements
+    /// `Display`.
+    pub fn quoted(&self) -> TableNameQuoted<'_> {
+        TableNameQuoted(self)
+    }
+
+    /// Convert this name to a portable name, if we know how. For now, we err on
+    /// the side of refusing.
+    pub fn to_portable_name(&self) -> Result<String> {
+        match &self.schema {
+            None => Ok(self.name.clone()),
+            // If we're in the "public" PostgreSQL schema (which is a namespace,
+            // not what dbcrossbar calls a "schema"), we can just drop it to
+            // produce a cleaner portable name.
+            Some(schema) if schema == "public" => Ok(self.name.clone()),
+            Some(_) => Err(format_err!(
+                "don't know how to convert {} to portable name yet, because it has a schema",
+                self.quoted()
+            ))
+        }
+    }
+}
+
+impl FromStr for PgName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let components = s.splitn(2, '.').collect::<Vec<_>>();
+        match components.len() {
+            1 => Ok(Self {
+                schema: None,
+                name: components[0].to_owned(),
+            }),
+            2 => Ok(Self {
+                schema: Some(components[0].to_owned()),
+                name: components[1].to_owned(),
+            }),
+            _ => Err(format_err!("cannot parse PostgreSQL name {:?}", s)),
+        }
+    }
+}
+
+/// A wrapper for `TableName` that implemented `Display`.
+pub struct TableNameQuoted<'a>(&'a PgName);
+
+impl fmt::Display for TableNameQuoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(schema) = self.0.schema() {
+            write!(f, "{}.{}", Ident(schema), Ident(&self.0.name))?
+        } else {
+            write!(f, "{}", Ident(&self.0.name))?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn ident_quotes_keywords_case_and_special_characters() {
+    let examples = &[
+        ("a", r#""a""#),
+        ("select", r#""select""#),
+        ("MixedCase", r#""MixedCase""#),
+        ("caf\u{e9}", "\"caf\u{e9}\""),
+        (r#"a"b"#, r#""a""b""#),
+        (r"a\b", r#""a\b""#),
+    ];
+    for &(input, expected) in examples {
+        assert_eq!(Ident(input).to_string(), expected);
+    }
+}
+
+#[test]
+fn postgres_name_is_quoted_correctly() {
+    assert_eq!(
+        format!("{}", PgName::from_str("example").unwrap().quoted()),
+        "\"example\""
+    );
+    assert_eq!(
+        format!("{}", PgName::from_str("schema.example").unwrap().quoted()),
+        "\"schema\".\"example\""
+    );
+
+    // Don't parse this one, because we haven't decided how to parse weird names
+    // like this yet.
+    let with_quote = PgName {
+        schema: Some("testme1".to_owned()),
+        name: "lat-\"lon".to_owned(),
+    };
+    assert_eq!(
+        format!("{}", with_quote.quoted()),
+        "\"testme1\".\"lat-\"\"lon\""
+    );
+}
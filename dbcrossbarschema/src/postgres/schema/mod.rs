@@ -2,7 +2,7 @@
 
 use std::{fmt, sync::Arc};
 
-use super::{catalog, CheckCatalog, PgCreateTable, PgCreateType, PgName};
+use super::{PgCreateTable, PgCreateType, PgName};
 use crate::common::*;
 use crate::parse_error::{Annotation, FileInfo, ParseError};
 
@@ -14,15 +14,15 @@ mod schema_sql;
 /// PostgreSQL "schema" is a namespace. This is what `dbcrossbar` calls a
 /// schema, which is a collection of named tables and types.
 #[derive(Clone, Debug)]
-pub(crate) struct PgSchema {
-    pub(crate) types: Vec<PgCreateType>,
-    pub(crate) tables: Vec<PgCreateTable>,
+pub struct PgSchema {
+    pub types: Vec<PgCreateType>,
+    pub tables: Vec<PgCreateTable>,
 }
 
 impl PgSchema {
     /// Parse a source file containing PostgreSQL `CREATE TABLE` and `CREATE
     /// TYPE` statements.
-    pub(crate) fn parse(
+    pub fn parse(
         file_name: String,
         file_contents: String,
     ) -> Result<Self, ParseError> {
@@ -39,64 +39,13 @@ impl PgSchema {
         })
     }
 
-    /// Look up `full_table_name` in the database, and return a new
-    /// `PgCreateTable` based on what we find in `pg_catalog`.
-    ///
-    /// Returns `None` if no matching table exists.
-    pub(crate) async fn from_pg_catalog(
-        ctx: &Context,
-        database_url: &UrlWithHiddenPassword,
-        table_name: &PgName,
-    ) -> Result<Option<Self>> {
-        catalog::fetch_from_url(ctx, database_url, table_name).await
-    }
-
-    /// Look up `full_table_name` in the database, and return a new
-    /// `PgCreateTable` based on what we find in `pg_catalog`.
-    ///
-    /// If this fails, use `full_table_name` and `default` to construct a new
-    /// table.
-    pub(crate) async fn from_pg_catalog_or_default(
-        ctx: &Context,
-        check_catalog: CheckCatalog,
-        database_url: &UrlWithHiddenPassword,
-        table_name: &PgName,
-        default: &Schema,
-    ) -> Result<Self> {
-        // If we can't find a catalog in the database, use this one.
-        let default_dest_schema =
-            Self::from_schema_and_name(ctx, default, table_name)?;
-
-        // Should we check the catalog to see if the table schema exists?
-        match check_catalog {
-            // Nope, we just want to use the default.
-            CheckCatalog::No => Ok(default_dest_schema),
-
-            // See if the table is listed in the catalog.
-            CheckCatalog::Yes => {
-                let opt_dest_schema =
-                    Self::from_pg_catalog(ctx, database_url, table_name).await?;
-                Ok(match opt_dest_schema {
-                    Some(dest_schema) => {
-                        dest_schema.aligned_with(&default_dest_schema)?
-                    }
-                    None => default_dest_schema,
-                })
-            }
-        }
-    }
-
     /// Construct a PostgreSQL schema from a portable schema and a table name
     /// (which will be used instead of the name in the schema).
-    pub(crate) fn from_schema_and_name(
-        _ctx: &Context,
-        schema: &Schema,
-        name: &PgName,
-    ) -> Result<Self> {
+    pub fn from_schema_and_name(schema: &Schema, name: &PgName) -> Result<Self> {
         let types = schema
             .named_data_types
             .values()
-            .map(|ty| PgCreateType::from_named_data_type(ty))
+            .map(PgCreateType::from_named_data_type)
             .collect::<Result<Vec<_>>>()?;
         let tables = vec![PgCreateTable::from_name_and_columns(
             schema,
@@ -107,7 +56,7 @@ impl PgSchema {
     }
 
     /// Convert to a portable schema.
-    pub(crate) fn to_schema(&self) -> Result<Schema> {
+    pub fn to_schema(&self) -> Result<Schema> {
         if self.tables.len() != 1 {
             Err(format_err!("Postgres schema must contain exactly 1 table"))
         } else {
@@ -122,7 +71,7 @@ impl PgSchema {
     }
 
     /// Return either the sole table associated with this schema, or an error.
-    pub(crate) fn table(&self) -> Result<&PgCreateTable> {
+    pub fn table(&self) -> Result<&PgCreateTable> {
         if self.tables.len() != 1 {
             Err(format_err!(
                 "expected PostgreSQL schema to contain only one table"
@@ -133,7 +82,7 @@ impl PgSchema {
     }
 
     /// Return either the sole table associated with this schema, or an error.
-    pub(crate) fn table_mut(&mut self) -> Result<&mut PgCreateTable> {
+    pub fn table_mut(&mut self) -> Result<&mut PgCreateTable> {
         if self.tables.len() != 1 {
             Err(format_err!(
                 "expected PostgreSQL schema to contain only one table"
@@ -146,7 +95,7 @@ impl PgSchema {
     /// Create a new scehma based on this schema, but with columns matching the
     /// the names and order of the columns in `other_table`. This is useful if
     /// we want to insert from `other_schema`'s table into `self`'s table.
-    pub(crate) fn aligned_with(&self, other_schema: &PgSchema) -> Result<PgSchema> {
+    pub fn aligned_with(&self, other_schema: &PgSchema) -> Result<PgSchema> {
         // Get the table from each schema, erroring out if we have more than one
         // at this point, and align them.
         let self_table = self.table()?;
@@ -157,34 +106,6 @@ impl PgSchema {
             types: self.types.clone(),
         })
     }
-
-    /// Write a `COPY (SELECT ...) TO STDOUT ...` statement for this schema's
-    /// table.
-    pub(crate) fn write_export_sql(
-        &self,
-        f: &mut dyn Write,
-        source_args: &SourceArguments<Verified>,
-    ) -> Result<()> {
-        self.table()?.write_export_sql(f, source_args)
-    }
-
-    /// Write a `SELECT ...` statement for this schema's table.
-    pub(crate) fn write_export_select_sql(
-        &self,
-        f: &mut dyn Write,
-        source_args: &SourceArguments<Verified>,
-    ) -> Result<()> {
-        self.table()?.write_export_select_sql(f, source_args)
-    }
-
-    /// Write a `SELECT COUNT(*) ...` statement for this schema's table.
-    pub(crate) fn write_count_sql(
-        &self,
-        f: &mut dyn Write,
-        source_args: &SourceArguments<Verified>,
-    ) -> Result<()> {
-        self.table()?.write_count_sql(f, source_args)
-    }
 }
 
 impl fmt::Display for PgSchema {
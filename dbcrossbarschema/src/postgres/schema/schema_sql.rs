@@ -16,11 +16,11 @@ use super::super::{
 };
 use crate::schema::Srid;
 
-pub(crate) use schema_grammar::schema as parse;
+pub use schema_grammar::schema as parse;
 
 /// A top-level definition in the SQL. We'll separate these into multiple lists
 /// before returning.
-pub(self) enum Definition {
+enum Definition {
     /// `CREATE TYPE`.
     Type(PgCreateType),
     /// `CREATE TABLE`.
@@ -28,7 +28,7 @@ pub(self) enum Definition {
 }
 
 /// Group `CREATE` definitions by type.
-pub(self) fn group_definitions(
+fn group_definitions(
     defs: Vec<Definition>,
 ) -> (Vec<PgCreateType>, Vec<PgCreateTable>) {
     let mut types = vec![];
@@ -45,7 +45,7 @@ pub(self) fn group_definitions(
 peg::parser! {
     grammar schema_grammar() for str {
         /// A mix of tables and data types.
-        pub(crate) rule schema() -> PgSchema
+        pub rule schema() -> PgSchema
             = ws()? defs:definition() ** (ws()? ";" ws()?) (";" ws()?)?
             {
                 let (types, tables) = group_definitions(defs);
@@ -89,6 +89,10 @@ peg::parser! {
                     // We don't worry about trying to parse this, which we only use
                     // internally at the moment.
                     temporary: false,
+                    unlogged: false,
+                    tablespace: None,
+                    distributed_by: None,
+                    redshift_attributes: None,
                 }
             }
 
@@ -0,0 +1,293 @@
+//! A PostgreSQL `CREATE TABLE` declaration.
+
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::{Ident, PgColumn, PgDataType, PgName, PgScalarDataType};
+use crate::common::*;
+use crate::schema::Column;
+
+/// A PostgreSQL table declaration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PgCreateTable {
+    /// The name of the table.
+    pub name: PgName,
+    /// The columns in the table.
+    pub columns: Vec<PgColumn>,
+    /// Only create the table if it doesn't already exist.
+    pub if_not_exists: bool,
+    /// Create a temporary table local to a specific client session.
+    pub temporary: bool,
+    /// Create an `UNLOGGED` table, which is faster to write but is not
+    /// crash-safe.
+    pub unlogged: bool,
+    /// The tablespace in which to create the table.
+    pub tablespace: Option<String>,
+    /// How to distribute rows between segments, for Greenplum.
+    pub distributed_by: Option<PgDistribution>,
+    /// How to distribute and sort rows, for Redshift.
+    pub redshift_attributes: Option<RedshiftTableAttributes>,
+}
+
+impl PgCreateTable {
+    /// Given a table name and a list of portable columns, construct a
+    /// corresponding `PgCreateTable`.
+    ///
+    /// We don't take a portable `Table` as an argument, because the `name`
+    /// contained in the `Table` might be an input table name, something from a
+    /// schema, etc., and it's usually a mistake to use it directly without
+    /// thinking things through first.
+    ///
+    /// We set `if_not_exists` to false, but the caller can change this directly
+    /// once once the `PgCreateTable` has been created.
+    pub fn from_name_and_columns(
+        schema: &Schema,
+        table_name: PgName,
+        columns: &[Column],
+    ) -> Result<PgCreateTable> {
+        let pg_columns = columns
+            .iter()
+            .map(|c| PgColumn::from_column(schema, c))
+            .collect::<Result<Vec<PgColumn>>>()?;
+        Ok(PgCreateTable {
+            name: table_name,
+            columns: pg_columns,
+            if_not_exists: false,
+            temporary: false,
+            unlogged: false,
+            tablespace: None,
+            distributed_by: None,
+            redshift_attributes: None,
+        })
+    }
+
+    /// Given a `PgCreateTable`, convert it to a portable `Table`.
+    pub fn to_table(&self) -> Result<Table> {
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| c.to_column())
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(Table {
+            name: self.name.unquoted(),
+            columns,
+        })
+    }
+
+    /// Create a new table based on this table, but with columns matching the
+    /// the names and order of the columns in `other_table`. This is useful if
+    /// we want to insert from `other_table` into `self`.
+    ///
+    /// Hypothetically, we could also check for compatibility between column
+    /// types in the two tables, but for now, we're happy to let the database
+    /// verify all that for us.
+    pub fn aligned_with(&self, other_table: &PgCreateTable) -> Result<PgCreateTable> {
+        let column_map = self
+            .columns
+            .iter()
+            .map(|c| (&c.name[..], c))
+            .collect::<HashMap<_, _>>();
+        Ok(PgCreateTable {
+            name: self.name.clone(),
+            columns: other_table
+                .columns
+                .iter()
+                .map(|c| {
+                    if let Some(&col) = column_map.get(&c.name[..]) {
+                        Ok(col.to_owned())
+                    } else {
+                        Err(format_err!(
+                            "could not find column {} in destination table: {}",
+                            c.name,
+                            column_map.keys().join(", "),
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?,
+            if_not_exists: self.if_not_exists,
+            temporary: self.temporary,
+            unlogged: self.unlogged,
+            tablespace: self.tablespace.clone(),
+            distributed_by: self.distributed_by.clone(),
+            redshift_attributes: self.redshift_attributes.clone(),
+        })
+    }
+
+    /// Return all the unique named types in this `PgTable`.
+    pub fn named_type_names(&self) -> HashSet<&PgName> {
+        let mut names = HashSet::new();
+        for col in &self.columns {
+            let scalar_ty = match &col.data_type {
+                PgDataType::Array { ty, .. } => ty,
+                PgDataType::Scalar(ty) => ty,
+            };
+            if let PgScalarDataType::Named(name) = scalar_ty {
+                names.insert(name);
+            }
+        }
+        names
+    }
+}
+
+impl fmt::Display for PgCreateTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE")?;
+        if self.temporary {
+            write!(f, " TEMPORARY")?;
+        } else if self.unlogged {
+            write!(f, " UNLOGGED")?;
+        }
+        write!(f, " TABLE")?;
+        if self.if_not_exists {
+            write!(f, " IF NOT EXISTS")?;
+        }
+        writeln!(f, " {} (", &self.name.quoted())?;
+        for (idx, col) in self.columns.iter().enumerate() {
+            write!(f, "    {}", col)?;
+            if idx + 1 == self.columns.len() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, ",")?;
+            }
+        }
+        write!(f, ")")?;
+        if let Some(tablespace) = &self.tablespace {
+            write!(f, " TABLESPACE {}", Ident(tablespace))?;
+        }
+        if let Some(distributed_by) = &self.distributed_by {
+            write!(f, " {}", distributed_by)?;
+        }
+        if let Some(redshift_attributes) = &self.redshift_attributes {
+            write!(f, "{}", redshift_attributes)?;
+        }
+        writeln!(f, ";")?;
+        Ok(())
+    }
+}
+
+/// How Greenplum should distribute the rows of a table between segments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PgDistribution {
+    /// Distribute rows using a hash of the specified columns.
+    Columns(Vec<String>),
+    /// Distribute rows randomly.
+    Randomly,
+    /// Keep a copy of every row on every segment.
+    Replicated,
+}
+
+impl fmt::Display for PgDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgDistribution::Columns(cols) => write!(
+                f,
+                "DISTRIBUTED BY ({})",
+                cols.iter().map(|c| Ident(c)).join(", "),
+            ),
+            PgDistribution::Randomly => write!(f, "DISTRIBUTED RANDOMLY"),
+            PgDistribution::Replicated => write!(f, "DISTRIBUTED REPLICATED"),
+        }
+    }
+}
+
+/// How Redshift should distribute rows between nodes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RedshiftDistStyle {
+    /// Let Redshift choose.
+    Auto,
+    /// Distribute rows round-robin.
+    Even,
+    /// Distribute rows using the value of the specified column.
+    Key(String),
+    /// Keep a copy of every row on every node.
+    All,
+}
+
+/// Redshift-specific table attributes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RedshiftTableAttributes {
+    /// How to distribute rows between nodes.
+    pub diststyle: Option<RedshiftDistStyle>,
+    /// Columns to use for `SORTKEY`.
+    pub sortkey: Vec<String>,
+}
+
+impl fmt::Display for RedshiftTableAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.diststyle {
+            None => {}
+            Some(RedshiftDistStyle::Auto) => write!(f, " DISTSTYLE AUTO")?,
+            Some(RedshiftDistStyle::Even) => write!(f, " DISTSTYLE EVEN")?,
+            Some(RedshiftDistStyle::Key(col)) => {
+                write!(f, " DISTSTYLE KEY DISTKEY ({})", Ident(col))?
+            }
+            Some(RedshiftDistStyle::All) => write!(f, " DISTSTYLE ALL")?,
+        }
+        if !self.sortkey.is_empty() {
+            write!(
+                f,
+                " SORTKEY ({})",
+                self.sortkey.iter().map(|c| Ident(c)).join(", "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn create_table_with_distribution() {
+    let mut table = PgCreateTable {
+        name: PgName::new(None, "t"),
+        columns: vec![PgColumn {
+            name: "id".to_owned(),
+            data_type: PgDataType::Scalar(PgScalarDataType::Bigint),
+            is_nullable: false,
+        }],
+        if_not_exists: false,
+        temporary: false,
+        unlogged: false,
+        tablespace: None,
+        distributed_by: Some(PgDistribution::Columns(vec!["id".to_owned()])),
+        redshift_attributes: None,
+    };
+    assert_eq!(
+        table.to_string(),
+        "CREATE TABLE \"t\" (\n    \"id\" bigint NOT NULL\n) DISTRIBUTED BY (\"id\");\n",
+    );
+    table.distributed_by = Some(PgDistribution::Randomly);
+    assert!(table.to_string().ends_with(") DISTRIBUTED RANDOMLY;\n"));
+}
+
+#[test]
+fn create_table_with_storage_options() {
+    let mut table = PgCreateTable {
+        name: PgName::new(None, "t"),
+        columns: vec![PgColumn {
+            name: "id".to_owned(),
+            data_type: PgDataType::Scalar(PgScalarDataType::Bigint),
+            is_nullable: false,
+        }],
+        if_not_exists: false,
+        temporary: false,
+        unlogged: true,
+        tablespace: Some("fast_disk".to_owned()),
+        distributed_by: None,
+        redshift_attributes: None,
+    };
+    assert_eq!(
+        table.to_string(),
+        "CREATE UNLOGGED TABLE \"t\" (\n    \"id\" bigint NOT NULL\n) TABLESPACE \"fast_disk\";\n",
+    );
+    table.unlogged = false;
+    table.tablespace = None;
+    table.redshift_attributes = Some(RedshiftTableAttributes {
+        diststyle: Some(RedshiftDistStyle::Key("id".to_owned())),
+        sortkey: vec!["id".to_owned()],
+    });
+    assert!(table
+        .to_string()
+        .ends_with(") DISTSTYLE KEY DISTKEY (\"id\") SORTKEY (\"id\");\n"));
+}
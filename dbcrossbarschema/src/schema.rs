@@ -20,7 +20,7 @@
 //! deserialized using [`serde`](https://serde.rs/).
 //!
 //! ```
-//! use dbcrossbarlib::schema::Schema;
+//! use dbcrossbarschema::schema::Schema;
 //! use serde_json;
 //!
 //! let json = r#"
@@ -60,7 +60,7 @@ use std::{
     fmt,
 };
 
-use crate::{common::*, drivers::dbcrossbar_schema::external_schema::ExternalSchema};
+use crate::{common::*, external_schema::ExternalSchema};
 
 /// Information about about a table and any supporting types. This is the "top
 /// level" of our JSON schema format.
@@ -68,10 +68,10 @@ use crate::{common::*, drivers::dbcrossbar_schema::external_schema::ExternalSche
 #[non_exhaustive]
 pub struct Schema {
     /// Named type aliases. This is serialized as a list.
-    pub(crate) named_data_types: HashMap<String, NamedDataType>,
+    pub named_data_types: HashMap<String, NamedDataType>,
 
     /// Tables. This is serialized as a list.
-    pub(crate) table: Table,
+    pub table: Table,
 }
 
 impl Schema {
@@ -88,7 +88,7 @@ impl Schema {
     }
 
     /// Construct a `Schema` from a list of `NamedDataType` and a `Table`.
-    pub(crate) fn from_types_and_table(
+    pub fn from_types_and_table(
         types: Vec<NamedDataType>,
         table: Table,
     ) -> Result<Schema> {
@@ -106,7 +106,7 @@ impl Schema {
 
     /// Given a standalone table, create a new `` object containing just
     /// that table. Returns an error if the resulting `Schema` would be invalid.
-    pub(crate) fn from_table(table: Table) -> Result<Schema> {
+    pub fn from_table(table: Table) -> Result<Schema> {
         let schema = Schema {
             named_data_types: HashMap::new(),
             table,
@@ -117,7 +117,7 @@ impl Schema {
 
     /// Look up the `DataType` associated with a name. We assume that `validate`
     /// has already been called on this schema.
-    pub(crate) fn data_type_for_name(&self, name: &str) -> &DataType {
+    pub fn data_type_for_name(&self, name: &str) -> &DataType {
         if let Some(named_data_type) = self.named_data_types.get(name) {
             &named_data_type.data_type
         } else {
@@ -129,9 +129,9 @@ impl Schema {
     }
 
     /// Create a dummy schema with a placeholder table and no named data types
-    /// for test purposes.
-    #[cfg(test)]
-    pub(crate) fn dummy_test_schema() -> Schema {
+    /// for test purposes. This is also used by `dbcrossbarlib`'s tests.
+    #[doc(hidden)]
+    pub fn dummy_test_schema() -> Schema {
         Schema {
             named_data_types: HashMap::new(),
             table: Table {
@@ -284,8 +284,8 @@ fn round_trip_serialization() {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct NamedDataType {
-    pub(crate) name: String,
-    pub(crate) data_type: DataType,
+    pub name: String,
+    pub data_type: DataType,
 }
 
 /// Information about a table.
@@ -461,7 +461,7 @@ impl DataType {
     }
 
     /// Should we serialize values of this type as JSON in a CSV file?
-    pub(crate) fn serializes_as_json_for_csv(&self, schema: &Schema) -> bool {
+    pub fn serializes_as_json_for_csv(&self, schema: &Schema) -> bool {
         match self {
             DataType::Array(_)
             | DataType::GeoJson(_)
@@ -4,14 +4,14 @@ use std::fmt;
 /// A separator string that will not print the _first_ time it used,
 /// but will print every time thereafter. Used to print commas or spaces
 /// in between items, but not before the first item.
-pub(crate) struct Separator<'a> {
+pub struct Separator<'a> {
     text: &'a str,
     first_time: bool,
 }
 
 impl<'a> Separator<'a> {
     /// Create a new separator which displays the specified string.
-    pub(crate) fn new(text: &str) -> Separator {
+    pub fn new(text: &str) -> Separator<'_> {
         Separator {
             text,
             first_time: true,
@@ -21,7 +21,7 @@ impl<'a> Separator<'a> {
     /// Return a displayable version of this separator. The first time this
     /// is called, the resulting `SeparatorDisplay` will not print anything.
     /// The next time, it will print the separator text.
-    pub(crate) fn display(&mut self) -> SeparatorDisplay {
+    pub fn display(&mut self) -> SeparatorDisplay<'_> {
         if self.first_time {
             self.first_time = false;
             SeparatorDisplay(None)
@@ -32,7 +32,7 @@ impl<'a> Separator<'a> {
 }
 
 /// Displays either nothing or a separator string.
-pub(crate) struct SeparatorDisplay<'a>(Option<&'a str>);
+pub struct SeparatorDisplay<'a>(Option<&'a str>);
 
 impl<'a> fmt::Display for SeparatorDisplay<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -10,12 +10,12 @@ function dbxb {
     ../../../target/debug/dbcrossbar --enable-unstable "$@" 2>&1
 }
 
-for c in cp count "schema conv"; do
+for c in cp count "schema conv" "schema diff" "schema lint" "schema dump" "schema apply"; do
     dbxb $c --help | tail -n +2 > "$(echo "$c" | sed 's/ /_/g')"_help.txt
 done
 
 dbxb features > features.txt
 
-for d in bigml bigquery csv gs postgres redshift s3 shopify; do
+for d in arrow avro azure bigml bigquery csv delta fixed-width greenplum gs gsheet ndjson parquet postgres redshift s3 salesforce shopify singlestore synapse timescaledb vertica xlsx; do
     dbxb features $d > features_$d.txt
 done
\ No newline at end of file